@@ -0,0 +1,72 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_json_rpc::errors::{InvalidRequestCode, JsonRpcError};
+use serde::Serialize;
+use warp::{
+    http::StatusCode,
+    reply::{self, Reply, Response},
+};
+
+/// An error answered with its HTTP status
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    /// The HTTP status code
+    pub code: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: String) -> Self {
+        Self {
+            code: status.as_u16(),
+            message,
+        }
+    }
+
+    pub fn bad_request(message: String) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: String) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+
+    pub fn internal(message: String) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+
+    fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+/// The invalid requests of JSON-RPC are bad requests, every other error is the node's
+impl From<JsonRpcError> for ApiError {
+    fn from(error: JsonRpcError) -> Self {
+        let invalid_request = [
+            InvalidRequestCode::InvalidRequest as i16,
+            InvalidRequestCode::InvalidParams as i16,
+            InvalidRequestCode::InvalidFormat as i16,
+        ]
+        .contains(&error.code);
+        if invalid_request {
+            Self::bad_request(error.message)
+        } else {
+            Self::internal(error.message)
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::internal(error.to_string())
+    }
+}
+
+impl Reply for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        reply::with_status(reply::json(&self), status).into_response()
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! REST API, next to the JSON-RPC endpoint
+//!
+//! Serves the accounts, transactions, events and ledger info of a node as plain JSON, for web
+//! wallets and explorers, see `ApiConfig`. The bodies are the views of JSON-RPC, read with the
+//! same `diem_json_rpc::data` functions.
+//!
+//! Module organization:
+//! ├── error.rs          # errors as HTTP statuses
+//! ├── runtime.rs        # the routes and their handlers
+//! ├── tests.rs          # tests
+//!
+//! The OpenAPI spec of the API is `doc/openapi.yaml`, served at `/openapi.yaml`.
+
+mod error;
+mod runtime;
+
+pub use runtime::{bootstrap, bootstrap_from_config};
+
+#[cfg(test)]
+mod tests;
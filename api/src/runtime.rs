@@ -0,0 +1,269 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::error::ApiError;
+use diem_config::config::{NodeConfig, TlsConfig};
+use diem_json_rpc::{
+    data,
+    response::{X_DIEM_CHAIN_ID, X_DIEM_TIMESTAMP_USEC_ID, X_DIEM_VERSION_ID},
+};
+use diem_logger::prelude::*;
+use diem_types::{
+    account_address::AccountAddress, chain_id::ChainId, event::EventKey, ledger_info::LedgerInfo,
+};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc};
+use storage_interface::DbReader;
+use tokio::runtime::{Builder, Runtime};
+use warp::{
+    http::header::{HeaderValue, CONTENT_TYPE},
+    reply::{self, Reply, Response},
+    Filter,
+};
+
+/// The size of a page without `limit`
+const DEFAULT_LIMIT: u16 = 25;
+
+const OPENAPI_SPEC: &str = include_str!("../doc/openapi.yaml");
+
+struct Context {
+    db: Arc<dyn DbReader>,
+    chain_id: ChainId,
+    page_size_limit: u16,
+}
+
+/// The query of the routes listing transactions or events
+#[derive(Debug, Deserialize)]
+struct Page {
+    /// The first version or sequence number
+    #[serde(default)]
+    start: u64,
+    limit: Option<u16>,
+    #[serde(default)]
+    include_events: bool,
+}
+
+impl Page {
+    fn limit(&self, context: &Context) -> Result<u64, ApiError> {
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        if limit > context.page_size_limit {
+            return Err(ApiError::bad_request(format!(
+                "limit = {}, exceed limit {}",
+                limit, context.page_size_limit
+            )));
+        }
+        Ok(limit as u64)
+    }
+}
+
+impl Context {
+    /// Answers a request from the latest ledger info, which every read of the request shares
+    fn answer<T: Serialize>(
+        &self,
+        read: impl FnOnce(&LedgerInfo) -> Result<T, ApiError>,
+    ) -> Response {
+        let ledger_info = match self.db.get_latest_ledger_info() {
+            Ok(ledger_info) => ledger_info,
+            Err(e) => return ApiError::from(e).into_response(),
+        };
+        let ledger_info = ledger_info.ledger_info();
+        let mut response = match read(ledger_info) {
+            Ok(body) => reply::json(&body).into_response(),
+            Err(e) => e.into_response(),
+        };
+
+        let headers = response.headers_mut();
+        headers.insert(
+            X_DIEM_CHAIN_ID,
+            HeaderValue::from(self.chain_id.id() as u16),
+        );
+        headers.insert(X_DIEM_VERSION_ID, HeaderValue::from(ledger_info.version()));
+        headers.insert(
+            X_DIEM_TIMESTAMP_USEC_ID,
+            HeaderValue::from(ledger_info.timestamp_usecs()),
+        );
+        response
+    }
+
+    fn ledger_info(&self) -> Response {
+        self.answer(|ledger_info| {
+            let version = ledger_info.version();
+            Ok(data::get_metadata(
+                self.db.as_ref(),
+                version,
+                self.chain_id,
+                version,
+            )?)
+        })
+    }
+
+    fn account(&self, address: String) -> Response {
+        self.answer(|ledger_info| {
+            let address = parse_address(&address)?;
+            data::get_account(self.db.as_ref(), address, ledger_info.version())?
+                .ok_or_else(|| ApiError::not_found(format!("could not find account {}", address)))
+        })
+    }
+
+    fn account_transactions(&self, address: String, page: Page) -> Response {
+        self.answer(|ledger_info| {
+            let address = parse_address(&address)?;
+            Ok(data::get_account_transactions(
+                self.db.as_ref(),
+                ledger_info.version(),
+                address,
+                page.start,
+                page.limit(self)?,
+                page.include_events,
+            )?)
+        })
+    }
+
+    fn transactions(&self, page: Page) -> Response {
+        self.answer(|ledger_info| {
+            Ok(data::get_transactions(
+                self.db.as_ref(),
+                ledger_info.version(),
+                page.start,
+                page.limit(self)?,
+                page.include_events,
+            )?
+            .0)
+        })
+    }
+
+    fn transaction(&self, version: u64) -> Response {
+        self.answer(|ledger_info| {
+            data::get_transactions(self.db.as_ref(), ledger_info.version(), version, 1, true)?
+                .0
+                .pop()
+                .ok_or_else(|| {
+                    ApiError::not_found(format!("could not find transaction {}", version))
+                })
+        })
+    }
+
+    fn events(&self, key: String, page: Page) -> Response {
+        self.answer(|ledger_info| {
+            let key: EventKey = key
+                .parse()
+                .map_err(|_| ApiError::bad_request(format!("invalid event key {}", key)))?;
+            Ok(data::get_events(
+                self.db.as_ref(),
+                ledger_info.version(),
+                key,
+                page.start,
+                page.limit(self)?,
+            )?)
+        })
+    }
+}
+
+fn parse_address(address: &str) -> Result<AccountAddress, ApiError> {
+    AccountAddress::from_hex_literal(address)
+        .or_else(|_| AccountAddress::from_hex(address))
+        .map_err(|_| ApiError::bad_request(format!("invalid account address {}", address)))
+}
+
+/// Creates the HTTP server (warp-based) serving the REST API
+pub fn bootstrap(
+    address: SocketAddr,
+    page_size_limit: u16,
+    diem_db: Arc<dyn DbReader>,
+    chain_id: ChainId,
+    tls: Option<&TlsConfig>,
+) -> Runtime {
+    let runtime = Builder::new_multi_thread()
+        .thread_name("api")
+        .enable_all()
+        .build()
+        .expect("[api] failed to create runtime");
+
+    let context = Arc::new(Context {
+        db: diem_db,
+        chain_id,
+        page_size_limit,
+    });
+    let context = warp::any().map(move || context.clone());
+
+    // GET /
+    let ledger_info = warp::path::end()
+        .and(context.clone())
+        .map(|context: Arc<Context>| context.ledger_info());
+    // GET /accounts/{address}
+    let account = warp::path!("accounts" / String)
+        .and(context.clone())
+        .map(|address, context: Arc<Context>| context.account(address));
+    // GET /accounts/{address}/transactions
+    let account_transactions = warp::path!("accounts" / String / "transactions")
+        .and(warp::query::<Page>())
+        .and(context.clone())
+        .map(|address, page, context: Arc<Context>| context.account_transactions(address, page));
+    // GET /transactions
+    let transactions = warp::path!("transactions")
+        .and(warp::query::<Page>())
+        .and(context.clone())
+        .map(|page, context: Arc<Context>| context.transactions(page));
+    // GET /transactions/{version}
+    let transaction = warp::path!("transactions" / u64)
+        .and(context.clone())
+        .map(|version, context: Arc<Context>| context.transaction(version));
+    // GET /events/{key}
+    let events = warp::path!("events" / String)
+        .and(warp::query::<Page>())
+        .and(context)
+        .map(|key, page, context: Arc<Context>| context.events(key, page));
+    // GET /openapi.yaml
+    let spec = warp::path!("openapi.yaml")
+        .map(|| reply::with_header(OPENAPI_SPEC, CONTENT_TYPE, "application/x-yaml"));
+
+    let routes = warp::get()
+        .and(
+            ledger_info
+                .or(account)
+                .or(account_transactions)
+                .or(transactions)
+                .or(transaction)
+                .or(events)
+                .or(spec),
+        )
+        // Web wallets and explorers read the API from their own origin
+        .with(warp::cors().allow_any_origin().allow_methods(vec!["GET"]));
+
+    match tls {
+        None => {
+            runtime
+                .handle()
+                .spawn(async move { warp::serve(routes).bind(address).await });
+        }
+        Some(tls) => {
+            let config =
+                diem_tls::server_config(&tls.cert_path, &tls.key_path, tls.reload_interval())
+                    .expect("[api] invalid TLS config");
+            runtime.handle().spawn(async move {
+                let listener = diem_tls::bind(address).expect("[api] failed to bind");
+                diem_tls::serve_filter(listener, config, routes).await
+            });
+        }
+    }
+    info!("REST API listening on {}", address);
+    runtime
+}
+
+/// Creates the REST API by the given node config, if enabled
+pub fn bootstrap_from_config(
+    config: &NodeConfig,
+    chain_id: ChainId,
+    diem_db: Arc<dyn DbReader>,
+) -> Option<Runtime> {
+    if !config.api.enabled {
+        return None;
+    }
+    Some(bootstrap(
+        config.api.address,
+        config.api.page_size_limit,
+        diem_db,
+        chain_id,
+        config.api.tls.as_ref(),
+    ))
+}
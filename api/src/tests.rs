@@ -0,0 +1,104 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::bootstrap;
+use diem_config::{config::DEFAULT_API_PAGE_SIZE_LIMIT, utils};
+use diem_json_rpc::{mock_db, response::X_DIEM_VERSION_ID, MockDiemDB};
+use diem_types::{account_address::AccountAddress, chain_id::ChainId};
+use reqwest::{blocking::Client, StatusCode};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+fn create_db_and_runtime() -> (MockDiemDB, Runtime, String) {
+    let mock_db = mock_db();
+    let port = utils::get_available_port();
+    let runtime = bootstrap(
+        format!("127.0.0.1:{}", port).parse().unwrap(),
+        DEFAULT_API_PAGE_SIZE_LIMIT,
+        Arc::new(mock_db.clone()),
+        ChainId::test(),
+        None,
+    );
+    (mock_db, runtime, format!("http://127.0.0.1:{}", port))
+}
+
+fn get(url: &str) -> (StatusCode, Value) {
+    let response = Client::new().get(url).send().unwrap();
+    (response.status(), response.json().unwrap())
+}
+
+#[test]
+fn test_get_ledger_info() {
+    let (mock_db, _runtime, url) = create_db_and_runtime();
+
+    let response = Client::new().get(&url).send().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[X_DIEM_VERSION_ID],
+        mock_db.version.to_string()
+    );
+    let body: Value = response.json().unwrap();
+    assert_eq!(body["version"], mock_db.version);
+    assert_eq!(body["chain_id"], ChainId::test().id());
+}
+
+#[test]
+fn test_get_account() {
+    let (mock_db, _runtime, url) = create_db_and_runtime();
+    let address = mock_db.all_accounts.keys().next().unwrap();
+
+    let (status, body) = get(&format!("{}/accounts/{}", url, address));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["address"], serde_json::to_value(address).unwrap());
+
+    let (status, _) = get(&format!("{}/accounts/0x{}", url, address));
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = get(&format!("{}/accounts/not-hex", url));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], 400);
+
+    let unknown = AccountAddress::random();
+    let (status, _) = get(&format!("{}/accounts/{}", url, unknown));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn test_get_transactions() {
+    let (mock_db, _runtime, url) = create_db_and_runtime();
+
+    let (status, body) = get(&format!("{}/transactions?limit=2", url));
+    assert_eq!(status, StatusCode::OK);
+    let transactions = body.as_array().unwrap();
+    assert_eq!(transactions.len(), 2.min(mock_db.version as usize + 1));
+    for (version, transaction) in transactions.iter().enumerate() {
+        assert_eq!(transaction["version"], version);
+    }
+
+    let (status, body) = get(&format!("{}/transactions/{}", url, mock_db.version));
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["version"], mock_db.version);
+
+    let (status, _) = get(&format!("{}/transactions/{}", url, mock_db.version + 1));
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get(&format!(
+        "{}/transactions?limit={}",
+        url,
+        DEFAULT_API_PAGE_SIZE_LIMIT + 1
+    ));
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn test_get_openapi_spec() {
+    let (_mock_db, _runtime, url) = create_db_and_runtime();
+
+    let response = Client::new()
+        .get(&format!("{}/openapi.yaml", url))
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().unwrap().starts_with("openapi: 3"));
+}
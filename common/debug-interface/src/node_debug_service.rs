@@ -4,7 +4,7 @@
 //! Debug interface to access information in a specific node.
 
 use diem_logger::{info, json_log, Filter, Logger};
-use std::{net::SocketAddr, sync::Arc};
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
 use warp::Filter as _;
 
@@ -28,6 +28,11 @@ impl NodeDebugService {
         // GET /events
         let events = warp::path("events").map(|| warp::reply::json(&json_log::pop_last_entries()));
 
+        //////// 0L ////////
+        // GET /handshake_failures
+        let handshake_failures = warp::path("handshake_failures")
+            .map(|| warp::reply::json(&handshake_failures_summary()));
+
         // Post /log/filter
         let local_filter = {
             let logger = logger.clone();
@@ -65,7 +70,7 @@ impl NodeDebugService {
             .and(warp::path("log"))
             .and(local_filter.or(remote_filter));
 
-        let routes = log.or(warp::get().and(metrics.or(events)));
+        let routes = log.or(warp::get().and(metrics.or(events).or(handshake_failures)));
 
         runtime
             .handle()
@@ -78,3 +83,28 @@ impl NodeDebugService {
         &self.runtime
     }
 }
+
+//////// 0L ////////
+/// Failure counts by network, direction and reason
+pub type HandshakeFailures = BTreeMap<String, BTreeMap<String, BTreeMap<String, u64>>>;
+
+/// The connection upgrades failed since the start of the node, by network, direction and reason,
+/// from the `diem_network_handshake_failures` counter of the network crate
+pub fn handshake_failures_summary() -> HandshakeFailures {
+    let mut summary = HandshakeFailures::new();
+    let totals = diem_metrics::counter_totals(
+        "diem_network_handshake_failures",
+        &["network_id", "direction", "reason"],
+    );
+    for (labels, total) in totals {
+        if let [network_id, direction, reason] = labels.as_slice() {
+            summary
+                .entry(network_id.clone())
+                .or_default()
+                .entry(direction.clone())
+                .or_default()
+                .insert(reason.clone(), total);
+        }
+    }
+    summary
+}
@@ -547,6 +547,53 @@ fn default_format(entry: &LogEntry) -> Result<String, fmt::Error> {
     Ok(w)
 }
 
+//////// 0L ////////
+/// The data key `NetworkSchema` logs the `NetworkContext` under
+const NETWORK_CONTEXT_KEY: &str = "network_context";
+
+//////// 0L ////////
+/// Converts a record into a line of JSON, for log aggregation pipelines. The fields of a logged
+/// `NetworkContext` (role, network_id, peer_id, ...) are lifted to the top level of the entry and
+/// the bracketed display of the context leading the message is dropped, so entries can be
+/// filtered by network without parsing messages.
+pub fn json_format(entry: &LogEntry) -> Result<String, fmt::Error> {
+    let mut value = serde_json::to_value(entry).map_err(|_| fmt::Error)?;
+    split_network_context(&mut value);
+    serde_json::to_string(&value).map_err(|_| fmt::Error)
+}
+
+fn split_network_context(entry: &mut serde_json::Value) {
+    let entry = match entry.as_object_mut() {
+        Some(entry) => entry,
+        None => return,
+    };
+    let data = match entry
+        .get_mut("data")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        Some(data) => data,
+        None => return,
+    };
+    let context = match data.remove(NETWORK_CONTEXT_KEY) {
+        Some(serde_json::Value::Object(context)) => context,
+        _ => return,
+    };
+    if data.is_empty() {
+        entry.remove("data");
+    }
+
+    if let Some(serde_json::Value::String(message)) = entry.get_mut("message") {
+        if message.starts_with('[') {
+            if let Some(end) = message.find("] ") {
+                *message = message[end + 2..].to_string();
+            }
+        }
+    }
+    for (key, value) in context {
+        entry.entry(key).or_insert(value);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::LogEntry;
@@ -555,7 +602,7 @@ mod tests {
         Schema, Value, Visitor,
     };
     use chrono::{DateTime, Utc};
-    use serde_json::Value as JsonValue;
+    use serde_json::{json, Value as JsonValue};
     use std::{
         sync::{
             mpsc::{self, Receiver, SyncSender},
@@ -728,4 +775,39 @@ mod tests {
             write!(f, "DisplayStruct!")
         }
     }
+
+    #[test]
+    fn test_split_network_context() {
+        let mut entry = json!({
+            "level": "INFO",
+            "data": {
+                "network_context": {
+                    "role": "validator",
+                    "network_id": "Validator",
+                    "peer_id": "0000000000000000000000000000000a",
+                    "chain_id": 1,
+                },
+                "remote_peer": "b",
+            },
+            "message": "[validator,Validator,0000000a,1] Connected to b",
+        });
+        super::split_network_context(&mut entry);
+        assert_eq!(
+            entry,
+            json!({
+                "level": "INFO",
+                "role": "validator",
+                "network_id": "Validator",
+                "peer_id": "0000000000000000000000000000000a",
+                "chain_id": 1,
+                "data": { "remote_peer": "b" },
+                "message": "Connected to b",
+            })
+        );
+
+        // Entries without a context are left as is
+        let mut entry = json!({ "message": "[not a context] message" });
+        super::split_network_context(&mut entry);
+        assert_eq!(entry, json!({ "message": "[not a context] message" }));
+    }
 }
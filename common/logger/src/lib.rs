@@ -158,7 +158,7 @@ mod security;
 mod struct_log;
 
 pub use crate::diem_logger::{
-    DiemLogger, DiemLogger as Logger, DiemLoggerBuilder, Writer, CHANNEL_SIZE,
+    json_format, DiemLogger, DiemLogger as Logger, DiemLoggerBuilder, Writer, CHANNEL_SIZE,
 };
 pub use event::Event;
 pub use filter::{Filter, LevelFilter};
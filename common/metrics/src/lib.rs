@@ -51,6 +51,7 @@ mod json_encoder;
 mod json_metrics;
 pub mod metric_server;
 mod public_metrics;
+pub mod push_gateway; //////// 0L ////////
 
 mod op_counters;
 pub use op_counters::{DurationHistogram, OpMetrics};
@@ -70,7 +71,7 @@ use diem_logger::prelude::*;
 use once_cell::sync::Lazy;
 use prometheus::{proto::MetricType, Encoder, TextEncoder};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::{create_dir_all, File, OpenOptions},
     io::Write,
     path::Path,
@@ -183,6 +184,54 @@ pub fn get_all_metrics() -> HashMap<String, String> {
     all_metrics
 }
 
+//////// 0L ////////
+/// The values of the counter `name`, summed by the values of its `labels`, e.g. the failures of
+/// every peer by network and reason
+pub fn counter_totals(name: &str, labels: &[&str]) -> BTreeMap<Vec<String>, u64> {
+    let mut totals = BTreeMap::new();
+    for metric_family in gather_metrics()
+        .iter()
+        .filter(|metric_family| metric_family.get_name() == name)
+    {
+        for metric in metric_family.get_metric() {
+            let key = labels
+                .iter()
+                .map(|label| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .find(|pair| pair.get_name() == *label)
+                        .map(|pair| pair.get_value().to_string())
+                        .unwrap_or_default()
+                })
+                .collect();
+            *totals.entry(key).or_insert(0) += metric.get_counter().get_value() as u64;
+        }
+    }
+    totals
+}
+
+//////// 0L ////////
+/// The value of the counter or gauge `name` with the given label values, if it was ever set
+pub fn metric_value(name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+    let metric_family = gather_metrics()
+        .into_iter()
+        .find(|metric_family| metric_family.get_name() == name)?;
+    let metric = metric_family.get_metric().iter().find(|metric| {
+        labels.iter().all(|(name, value)| {
+            metric
+                .get_label()
+                .iter()
+                .any(|pair| pair.get_name() == *name && pair.get_value() == *value)
+        })
+    })?;
+    match metric_family.get_field_type() {
+        MetricType::COUNTER => Some(metric.get_counter().get_value()),
+        MetricType::GAUGE => Some(metric.get_gauge().get_value()),
+        _ => None,
+    }
+}
+
 // Launches a background thread which will periodically collect metrics
 // every interval and write them to the provided file
 pub fn dump_all_metrics_to_file_periodically<P: AsRef<Path>>(
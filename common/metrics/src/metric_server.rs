@@ -5,8 +5,10 @@ use crate::{
     gather_metrics, json_encoder::JsonEncoder, json_metrics::get_json_metrics,
     public_metrics::PUBLIC_METRICS, NUM_METRICS,
 };
+use diem_tls::ServerConfig;
 use futures::future;
 use hyper::{
+    header::{self, HeaderValue},
     service::{make_service_fn, service_fn},
     Body, Method, Request, Response, Server, StatusCode,
 };
@@ -14,11 +16,13 @@ use prometheus::{proto::MetricFamily, Encoder, TextEncoder};
 use std::{
     collections::HashMap,
     net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
     thread,
 };
+use subtle::ConstantTimeEq;
 use tokio::runtime;
 
-fn encode_metrics(encoder: impl Encoder, whitelist: &'static [&'static str]) -> Vec<u8> {
+pub(crate) fn encode_metrics(encoder: impl Encoder, whitelist: &'static [&'static str]) -> Vec<u8> {
     let mut metric_families = gather_metrics();
     if !whitelist.is_empty() {
         metric_families = whitelist_metrics(metric_families, whitelist);
@@ -63,13 +67,25 @@ fn whitelist_json_metrics(
     whitelist_metrics
 }
 
-async fn serve_metrics(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
+async fn serve_metrics(
+    req: Request<Body>,
+    metrics_path: Arc<str>,
+    authorization: Option<Arc<str>>,
+) -> Result<Response<Body>, hyper::Error> {
     let mut resp = Response::new(Body::empty());
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/-/healthy") => {
             *resp.body_mut() = Body::from("diem-node:ok");
         }
-        (&Method::GET, "/metrics") => {
+        //////// 0L ////////
+        _ if !is_authorized(&req, authorization.as_deref()) => {
+            *resp.status_mut() = StatusCode::UNAUTHORIZED;
+            resp.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Basic realm=\"metrics\""),
+            );
+        }
+        (&Method::GET, path) if path == &*metrics_path => {
             //Prometheus server expects metrics to be on host:port/metrics
             let encoder = TextEncoder::new();
             let buffer = encode_metrics(encoder, &[]);
@@ -127,35 +143,87 @@ pub fn start_server(host: String, port: u16, public_metric: bool) {
         .unwrap();
 
     if public_metric {
-        thread::spawn(move || {
-            let make_service = make_service_fn(|_| {
-                future::ok::<_, hyper::Error>(service_fn(serve_public_metrics))
-            });
-
-            let rt = runtime::Builder::new_current_thread()
-                .enable_io()
-                .build()
-                .unwrap();
-            rt.block_on(async {
-                let server = Server::bind(&addr).serve(make_service);
-                server.await
-            })
-            .unwrap();
-        });
+        start_public_server(addr);
     } else {
-        thread::spawn(move || {
-            let make_service =
-                make_service_fn(|_| future::ok::<_, hyper::Error>(service_fn(serve_metrics)));
-
-            let rt = runtime::Builder::new_current_thread()
-                .enable_io()
-                .build()
-                .unwrap();
-            rt.block_on(async {
-                let server = Server::bind(&addr).serve(make_service);
-                server.await
-            })
+        start_server_with_path(addr, "/metrics", None, None);
+    }
+}
+
+//////// 0L ////////
+/// The `Authorization` header value of basic auth credentials
+pub fn basic_authorization(username: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        base64::encode(format!("{}:{}", username, password))
+    )
+}
+
+/// Whether the request carries the expected `Authorization` header, if any is required
+fn is_authorized(req: &Request<Body>, authorization: Option<&str>) -> bool {
+    match authorization {
+        Some(expected) => req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .map_or(false, |value| {
+                value.as_bytes().ct_eq(expected.as_bytes()).into()
+            }),
+        None => true,
+    }
+}
+
+//////// 0L ////////
+/// Starts the full metrics server, serving the Prometheus metrics on `metrics_path`. With
+/// `basic_auth` credentials, every endpoint but the health check requires them. With a `tls`
+/// config, the server serves HTTPS.
+pub fn start_server_with_path(
+    addr: SocketAddr,
+    metrics_path: &str,
+    basic_auth: Option<(&str, &str)>,
+    tls: Option<Arc<ServerConfig>>,
+) {
+    let metrics_path: Arc<str> = Arc::from(metrics_path);
+    let authorization: Option<Arc<str>> = basic_auth
+        .map(|(username, password)| Arc::from(basic_authorization(username, password).as_str()));
+    thread::spawn(move || {
+        let service = move || {
+            let metrics_path = metrics_path.clone();
+            let authorization = authorization.clone();
+            service_fn(move |req| serve_metrics(req, metrics_path.clone(), authorization.clone()))
+        };
+
+        let rt = runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
             .unwrap();
+        rt.block_on(async {
+            match tls {
+                None => {
+                    let make_service =
+                        make_service_fn(move |_| future::ok::<_, hyper::Error>(service()));
+                    Server::bind(&addr).serve(make_service).await.unwrap();
+                }
+                Some(tls) => {
+                    let listener = diem_tls::bind(addr).unwrap();
+                    diem_tls::serve(listener, tls, move |_| service()).await;
+                }
+            }
         });
-    }
+    });
+}
+
+fn start_public_server(addr: SocketAddr) {
+    thread::spawn(move || {
+        let make_service =
+            make_service_fn(|_| future::ok::<_, hyper::Error>(service_fn(serve_public_metrics)));
+
+        let rt = runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let server = Server::bind(&addr).serve(make_service);
+            server.await
+        })
+        .unwrap();
+    });
 }
@@ -0,0 +1,97 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Periodically pushes all metrics to a Prometheus push-gateway, for nodes which cannot be
+//! scraped directly.
+
+use crate::metric_server::encode_metrics;
+use diem_logger::prelude::*;
+use hyper::{Body, Client, Method, Request};
+use prometheus::TextEncoder;
+use std::{collections::BTreeMap, thread, time::Duration};
+use tokio::runtime;
+
+/// Builds the push URL for a group, see
+/// https://github.com/prometheus/pushgateway#url for the format.
+pub fn push_url(base_url: &str, job: &str, labels: &BTreeMap<String, String>) -> String {
+    let mut url = format!(
+        "{}/metrics/job/{}",
+        base_url.trim_end_matches('/'),
+        escape(job)
+    );
+    for (name, value) in labels {
+        url.push_str(&format!("/{}/{}", escape(name), escape(value)));
+    }
+    url
+}
+
+/// Percent-encodes everything but unreserved characters
+fn escape(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Starts a thread pushing the metrics every `interval`. Failed pushes are logged and retried on
+/// the next interval.
+pub fn start_pusher(
+    base_url: String,
+    job: String,
+    labels: BTreeMap<String, String>,
+    interval: Duration,
+) {
+    let url = push_url(&base_url, &job, &labels);
+    thread::spawn(move || {
+        let rt = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let client = Client::new();
+        rt.block_on(async {
+            loop {
+                let body = encode_metrics(TextEncoder::new(), &[]);
+                let request = Request::builder()
+                    .method(Method::PUT)
+                    .uri(&url)
+                    .body(Body::from(body));
+                match request {
+                    Ok(request) => match client.request(request).await {
+                        Ok(response) if !response.status().is_success() => {
+                            warn!("Push-gateway {} responded with {}", url, response.status())
+                        }
+                        Ok(_) => (),
+                        Err(e) => warn!("Unable to push metrics to {}: {}", url, e),
+                    },
+                    Err(e) => {
+                        error!("Invalid push-gateway url {}: {}", url, e);
+                        return;
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_url() {
+        let mut labels = BTreeMap::new();
+        labels.insert("chain_id".to_string(), "TESTING".to_string());
+        labels.insert("peer_id".to_string(), "a b/c".to_string());
+        assert_eq!(
+            push_url("http://localhost:9091/", "diem-node", &labels),
+            "http://localhost:9091/metrics/job/diem-node/chain_id/TESTING/peer_id/a%20b%2Fc"
+        );
+    }
+}
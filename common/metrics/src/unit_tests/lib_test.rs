@@ -24,6 +24,41 @@ fn gather_metrics_test() {
     assert!(log.contains(&iterations.to_string()));
     assert!(log.contains(INT_COUNTER.collect()[0].get_name()));
 }
+
+#[test]
+fn counter_totals_test() {
+    let counter = register_int_counter_vec!(
+        "diem_test_counter_totals",
+        "A counter summed by label",
+        &["network", "peer", "reason"]
+    )
+    .unwrap();
+    counter.with_label_values(&["public", "a", "unknown_key"]).inc();
+    counter.with_label_values(&["public", "b", "unknown_key"]).inc_by(2);
+    counter.with_label_values(&["vfn", "a", "replay"]).inc();
+
+    let totals = counter_totals("diem_test_counter_totals", &["network", "reason"]);
+    let key = |network: &str, reason: &str| vec![network.to_string(), reason.to_string()];
+    assert_eq!(totals.get(&key("public", "unknown_key")), Some(&3));
+    assert_eq!(totals.get(&key("vfn", "replay")), Some(&1));
+    assert_eq!(totals.len(), 2);
+}
+
+#[test]
+fn metric_value_test() {
+    let gauge = register_int_gauge_vec!("diem_test_metric_value", "A gauge", &["type"]).unwrap();
+    gauge.with_label_values(&["committed"]).set(7);
+
+    assert_eq!(
+        metric_value("diem_test_metric_value", &[("type", "committed")]),
+        Some(7.0)
+    );
+    assert_eq!(
+        metric_value("diem_test_metric_value", &[("type", "highest")]),
+        None
+    );
+    assert_eq!(metric_value("diem_test_metric_missing", &[]), None);
+}
 }
 
 // To test if the placeholder static metrics registered in Registry, a counter type metric,
@@ -66,3 +101,11 @@ fn get_all_metrics_test() {
     }
 }
 }
+
+#[test]
+fn basic_authorization_test() {
+    assert_eq!(
+        metric_server::basic_authorization("user", "pass"),
+        "Basic dXNlcjpwYXNz"
+    );
+}
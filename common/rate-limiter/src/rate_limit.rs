@@ -171,6 +171,16 @@ impl<Key: Eq + Hash + Clone + Debug> TokenBucketRateLimiter<Key> {
         }
         remove
     }
+
+    //////// 0L ////////
+    /// Garbage collects the buckets no one holds that refilled, as a new one would be no fuller,
+    /// and returns how many
+    pub fn garbage_collect_full_buckets(&self) -> usize {
+        let mut buckets = self.buckets.write();
+        let before = buckets.len();
+        buckets.retain(|_, bucket| Arc::strong_count(bucket) > 1 || !bucket.lock().is_full());
+        before - buckets.len()
+    }
 }
 
 /// A token bucket object that keeps track of everything related to a key
@@ -375,6 +385,13 @@ impl Bucket {
         self.allowed_in_period = self.allowed_in_period.saturating_sub(new_tokens);
         self.add_tokens(new_tokens);
     }
+
+    //////// 0L ////////
+    /// Whether the bucket holds as many tokens as it can, once refilled
+    pub fn is_full(&mut self) -> bool {
+        self.refill();
+        self.tokens >= self.size
+    }
 }
 
 #[cfg(test)]
@@ -546,4 +563,23 @@ mod tests {
         assert!(!rate_limiter.try_garbage_collect_key(&key_to_keep));
         assert_num_keys(&rate_limiter, 1);
     }
+
+    //////// 0L ////////
+    #[test]
+    fn test_garbage_collect_full_buckets() {
+        let rate_limiter = TokenBucketRateLimiter::test(2, 1);
+        let _bucket_arc = rate_limiter.bucket("held");
+        rate_limiter.bucket("full");
+        rate_limiter
+            .bucket("drained")
+            .lock()
+            .acquire_all_tokens(1)
+            .unwrap();
+        assert_num_keys(&rate_limiter, 3);
+
+        // Only the full bucket no one holds is collected
+        assert_eq!(rate_limiter.garbage_collect_full_buckets(), 1);
+        assert_num_keys(&rate_limiter, 2);
+        assert!(rate_limiter.buckets.read().contains_key("drained"));
+    }
 }
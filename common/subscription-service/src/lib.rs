@@ -10,11 +10,12 @@ use channel::{
     diem_channel::{self, Receiver, Sender},
     message_queues::QueueStyle,
 };
+use diem_infallible::Mutex;
 use diem_types::{
     event::EventKey,
     on_chain_config::{ConfigID, OnChainConfigPayload},
 };
-use std::collections::HashSet;
+use std::{collections::HashSet, mem, sync::Arc};
 
 pub struct SubscriptionService<T, U> {
     pub name: String,
@@ -79,3 +80,143 @@ impl ReconfigSubscription {
         Self::subscribe(name, bundle)
     }
 }
+
+//////// 0L ////////
+/// The reconfiguration notifications of state sync, shared by the subsystems that follow the
+/// validator set and the on-chain configs. A subsystem subscribes when it starts, including after
+/// state sync: it is sent the latest on-chain configs at once, then notified in the block of each
+/// change of its configs or events.
+#[derive(Clone)]
+pub struct ReconfigBus {
+    inner: Arc<Mutex<BusState>>,
+}
+
+impl Default for ReconfigBus {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(BusState::default())),
+        }
+    }
+}
+
+#[derive(Default)]
+struct BusState {
+    subscriptions: Vec<ReconfigSubscription>,
+    latest: Option<OnChainConfigPayload>,
+}
+
+impl ReconfigBus {
+    pub fn new(subscriptions: Vec<ReconfigSubscription>) -> Self {
+        let bus = Self::default();
+        for subscription in subscriptions {
+            bus.add(subscription);
+        }
+        bus
+    }
+
+    /// Subscribes to changes of `configs` and emissions of `events`, see `ReconfigSubscription`
+    pub fn subscribe(
+        &self,
+        name: &str,
+        configs: Vec<ConfigID>,
+        events: Vec<EventKey>,
+    ) -> Receiver<(), OnChainConfigPayload> {
+        let (subscription, receiver) = ReconfigSubscription::subscribe_all(name, configs, events);
+        self.add(subscription);
+        receiver
+    }
+
+    /// Adds a subscription made beforehand, e.g., by a network builder
+    pub fn add(&self, mut subscription: ReconfigSubscription) {
+        let mut state = self.inner.lock();
+        if let Some(latest) = &state.latest {
+            // A dropped receiver is reported, and the subscription removed, on the next change
+            let _ = subscription.publish(latest.clone());
+        }
+        state.subscriptions.push(subscription);
+    }
+
+    /// Sends `payload` to every subscription, e.g., the configs state sync starts with
+    pub fn publish_all(&self, payload: OnChainConfigPayload) -> Result<()> {
+        let mut state = self.inner.lock();
+        for subscription in state.subscriptions.iter_mut() {
+            subscription.publish(payload.clone())?;
+        }
+        state.latest = Some(payload);
+        Ok(())
+    }
+
+    /// Sends `payload` to the subscriptions of the `changed_configs` or the emitted `event_keys`,
+    /// and returns the result by subscription name. The subscriptions whose receiver is dropped
+    /// are removed.
+    pub fn publish(
+        &self,
+        payload: OnChainConfigPayload,
+        changed_configs: &HashSet<ConfigID>,
+        event_keys: &HashSet<EventKey>,
+    ) -> Vec<(String, Result<()>)> {
+        let mut state = self.inner.lock();
+        let mut results = vec![];
+        for mut subscription in mem::take(&mut state.subscriptions) {
+            let subscribed_items = subscription.subscribed_items();
+            if !changed_configs.is_disjoint(&subscribed_items.configs)
+                || !event_keys.is_disjoint(&subscribed_items.events)
+            {
+                let result = subscription.publish(payload.clone());
+                let delivered = result.is_ok();
+                results.push((subscription.name.clone(), result));
+                if !delivered {
+                    continue;
+                }
+            }
+            state.subscriptions.push(subscription);
+        }
+        state.latest = Some(payload);
+        results
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_types::on_chain_config::{DiemVersion, OnChainConfig, ValidatorSet};
+    use futures::{FutureExt, StreamExt};
+    use std::collections::HashMap;
+
+    fn payload(epoch: u64) -> OnChainConfigPayload {
+        OnChainConfigPayload::new(epoch, Arc::new(HashMap::new()))
+    }
+
+    fn next_epoch(receiver: &mut Receiver<(), OnChainConfigPayload>) -> Option<u64> {
+        receiver
+            .select_next_some()
+            .now_or_never()
+            .map(|payload| payload.epoch())
+    }
+
+    #[test]
+    fn test_reconfig_bus() {
+        let bus = ReconfigBus::default();
+        let mut early = bus.subscribe("early", vec![ValidatorSet::CONFIG_ID], vec![]);
+        bus.publish_all(payload(1)).unwrap();
+        assert_eq!(next_epoch(&mut early), Some(1));
+
+        // A late subscriber starts from the latest configs
+        let mut late = bus.subscribe("late", vec![DiemVersion::CONFIG_ID], vec![]);
+        assert_eq!(next_epoch(&mut late), Some(1));
+
+        let changed = vec![ValidatorSet::CONFIG_ID].into_iter().collect();
+        let results = bus.publish(payload(2), &changed, &HashSet::new());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "early");
+        assert_eq!(next_epoch(&mut early), Some(2));
+        assert_eq!(next_epoch(&mut late), None);
+
+        // A dropped receiver is reported once, then its subscription removed
+        drop(early);
+        let results = bus.publish(payload(3), &changed, &HashSet::new());
+        assert!(results[0].1.is_err());
+        let results = bus.publish(payload(4), &changed, &HashSet::new());
+        assert!(results.is_empty());
+    }
+}
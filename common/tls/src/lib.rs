@@ -0,0 +1,313 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+#![forbid(unsafe_code)]
+
+//! HTTPS for the servers of a node (JSON-RPC, REST API, metrics), so operators don't need a
+//! reverse proxy to terminate TLS.
+//!
+//! The certificate and key are read from PEM files. With a reload interval, the files are checked
+//! that often and a renewed certificate is served to new connections without a restart: point the
+//! paths at the output of an ACME client (e.g. certbot's `live` directory) to renew automatically.
+
+use anyhow::{ensure, format_err, Result};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use hyper::{
+    server::conn::Http,
+    service::{service_fn, Service},
+    Body, Request, Response,
+};
+use std::{
+    convert::Infallible,
+    error::Error,
+    fs, io,
+    net::{self, SocketAddr},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        internal::pemfile,
+        sign::{self, CertifiedKey},
+        ClientHello, NoClientAuth, PrivateKey, ResolvesServerCert,
+    },
+    TlsAcceptor,
+};
+use warp::{Filter, Rejection, Reply};
+
+pub use tokio_rustls::rustls::ServerConfig;
+
+/// The TLS config of a server serving the certificate and key at the paths, see the module doc
+pub fn server_config(
+    cert_path: &Path,
+    key_path: &Path,
+    reload_interval: Option<Duration>,
+) -> Result<Arc<ServerConfig>> {
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config.cert_resolver = Arc::new(CertResolver::new(cert_path, key_path, reload_interval)?);
+    config.set_protocols(&[b"h2".to_vec(), b"http/1.1".to_vec()]);
+    Ok(Arc::new(config))
+}
+
+/// Binds the listener of a server, in the context of its runtime
+pub fn bind(address: SocketAddr) -> io::Result<TcpListener> {
+    let listener = net::TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+    TcpListener::from_std(listener)
+}
+
+/// Serves HTTPS on the listener, building the service of each connection from its peer address
+pub async fn serve<F, S>(listener: TcpListener, config: Arc<ServerConfig>, make_service: F)
+where
+    F: Fn(SocketAddr) -> S,
+    S: Service<Request<Body>, Response = Response<Body>> + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    let acceptor = TlsAcceptor::from(config);
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let service = make_service(peer);
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    debug!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            if let Err(e) = Http::new().serve_connection(stream, service).await {
+                debug!("HTTPS connection with {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// The peer address of a connection served by `serve_filter`
+#[derive(Clone, Copy, Debug)]
+struct RemoteAddr(SocketAddr);
+
+/// Serves a warp filter over HTTPS, where `remote_addr` replaces `warp::addr::remote`
+pub async fn serve_filter<F>(listener: TcpListener, config: Arc<ServerConfig>, filter: F)
+where
+    F: Filter<Error = Rejection> + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    let service = warp::service(filter);
+    serve(listener, config, move |peer| {
+        let mut service = service.clone();
+        service_fn(move |mut request: Request<Body>| {
+            request.extensions_mut().insert(RemoteAddr(peer));
+            service.call(request)
+        })
+    })
+    .await
+}
+
+/// The peer address of a request, served over HTTP by warp or over HTTPS by `serve_filter`
+pub fn remote_addr() -> impl Filter<Extract = (Option<SocketAddr>,), Error = Infallible> + Clone {
+    warp::ext::get::<RemoteAddr>()
+        .map(|RemoteAddr(addr)| Some(addr))
+        .or(warp::addr::remote())
+        .unify()
+}
+
+/// A certificate and key, with the modification times of their files
+struct Loaded {
+    modified: (SystemTime, SystemTime),
+    key: CertifiedKey,
+}
+
+impl Loaded {
+    fn read(cert_path: &Path, key_path: &Path) -> Result<Self> {
+        let modified = modified(cert_path, key_path)?;
+
+        let pem = fs::read(cert_path)?;
+        let certs = pemfile::certs(&mut pem.as_slice())
+            .map_err(|_| format_err!("invalid certificate {}", cert_path.display()))?;
+        ensure!(
+            !certs.is_empty(),
+            "no certificate in {}",
+            cert_path.display()
+        );
+
+        let key = read_private_key(key_path)?;
+        let key = sign::any_supported_type(&key)
+            .map_err(|_| format_err!("unsupported private key {}", key_path.display()))?;
+        Ok(Self {
+            modified,
+            key: CertifiedKey::new(certs, Arc::new(key)),
+        })
+    }
+}
+
+fn modified(cert_path: &Path, key_path: &Path) -> io::Result<(SystemTime, SystemTime)> {
+    Ok((
+        fs::metadata(cert_path)?.modified()?,
+        fs::metadata(key_path)?.modified()?,
+    ))
+}
+
+/// Reads a PKCS8 or RSA private key
+fn read_private_key(path: &Path) -> Result<PrivateKey> {
+    let pem = fs::read(path)?;
+    let invalid = |_| format_err!("invalid private key {}", path.display());
+    let mut keys = pemfile::pkcs8_private_keys(&mut pem.as_slice()).map_err(invalid)?;
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut pem.as_slice()).map_err(invalid)?;
+    }
+    keys.into_iter()
+        .next()
+        .ok_or_else(|| format_err!("no private key in {}", path.display()))
+}
+
+struct CertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    reload_interval: Option<Duration>,
+    /// The served certificate, and when its files were last checked
+    current: Mutex<(Loaded, Instant)>,
+}
+
+impl CertResolver {
+    fn new(cert_path: &Path, key_path: &Path, reload_interval: Option<Duration>) -> Result<Self> {
+        Ok(Self {
+            cert_path: cert_path.to_path_buf(),
+            key_path: key_path.to_path_buf(),
+            reload_interval,
+            current: Mutex::new((Loaded::read(cert_path, key_path)?, Instant::now())),
+        })
+    }
+
+    /// The certificate to serve, reloaded first if it's time to check the files and they changed
+    fn current(&self) -> CertifiedKey {
+        let mut current = self.current.lock();
+        let (loaded, checked) = &mut *current;
+        if let Some(interval) = self.reload_interval {
+            if checked.elapsed() >= interval {
+                *checked = Instant::now();
+                self.reload(loaded);
+            }
+        }
+        loaded.key.clone()
+    }
+
+    fn reload(&self, loaded: &mut Loaded) {
+        match modified(&self.cert_path, &self.key_path) {
+            Ok(modified) if modified == loaded.modified => (),
+            // A renewal may be half written, the next check retries
+            _ => match Loaded::read(&self.cert_path, &self.key_path) {
+                Ok(renewed) => {
+                    info!("Reloaded TLS certificate {}", self.cert_path.display());
+                    *loaded = renewed;
+                }
+                Err(e) => warn!(
+                    "Failed to reload TLS certificate {}, still serving the previous one: {}",
+                    self.cert_path.display(),
+                    e
+                ),
+            },
+        }
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<CertifiedKey> {
+        Some(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_temppath::TempPath;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+    use tokio_rustls::{rustls::ClientConfig, webpki::DNSNameRef, TlsConnector};
+
+    fn test_data(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/test_data")
+            .join(name)
+    }
+
+    fn certs(path: &Path) -> Vec<tokio_rustls::rustls::Certificate> {
+        pemfile::certs(&mut fs::read(path).unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_server_config() {
+        assert!(server_config(&test_data("cert_a.pem"), &test_data("key_a.pem"), None).is_ok());
+        // The key is not a certificate
+        assert!(server_config(&test_data("key_a.pem"), &test_data("key_a.pem"), None).is_err());
+        assert!(server_config(&test_data("cert_a.pem"), &test_data("missing.pem"), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_serve_filter() {
+        let config =
+            server_config(&test_data("cert_a.pem"), &test_data("key_a.pem"), None).unwrap();
+        let listener = bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let address = listener.local_addr().unwrap();
+        let route = warp::path::end()
+            .and(remote_addr())
+            .map(|addr: Option<SocketAddr>| addr.unwrap().ip().to_string());
+        tokio::spawn(serve_filter(listener, config, route));
+
+        let mut client = ClientConfig::new();
+        client
+            .root_store
+            .add_pem_file(&mut fs::read(test_data("cert_a.pem")).unwrap().as_slice())
+            .unwrap();
+        let domain = DNSNameRef::try_from_ascii_str("localhost").unwrap();
+        let stream = TcpStream::connect(address).await.unwrap();
+        let mut stream = TlsConnector::from(Arc::new(client))
+            .connect(domain, stream)
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        // The server may close without a close_notify
+        let _ = stream.read_to_string(&mut response).await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("127.0.0.1"));
+    }
+
+    #[test]
+    fn test_reload_renewed_certificate() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        fs::copy(test_data("cert_a.pem"), &cert_path).unwrap();
+        fs::copy(test_data("key_a.pem"), &key_path).unwrap();
+
+        let resolver =
+            CertResolver::new(&cert_path, &key_path, Some(Duration::from_secs(0))).unwrap();
+        assert!(resolver.current().cert == certs(&test_data("cert_a.pem")));
+
+        // A broken renewal keeps the previous certificate
+        fs::write(&cert_path, "not a certificate").unwrap();
+        assert!(resolver.current().cert == certs(&test_data("cert_a.pem")));
+
+        fs::copy(test_data("cert_b.pem"), &cert_path).unwrap();
+        fs::copy(test_data("key_b.pem"), &key_path).unwrap();
+        assert!(resolver.current().cert == certs(&test_data("cert_b.pem")));
+    }
+}
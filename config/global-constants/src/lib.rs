@@ -33,9 +33,16 @@ pub const NODE_HOME: &str = ".0L/";
 pub const PROOF_OF_WORK_PREIMAGE: &str = "pow_preimage";
 pub const PROOF_OF_WORK_PROOF: &str = "pow_proof";
 pub const ACCOUNT_PROFILE: &str = "account_profile";
+/// The signature by the owner key of the registrations of an owner and of its operator
+pub const REGISTRATION_SIGNATURE: &str = "registration_signature";
 pub const SALT_0L: &str = "0L";
 pub const SOURCE_DIR: &str = "libra/";
 pub const GENESIS_VDF_SECURITY_PARAM: u64 = 512;
+/// The network key of the VFN of a validator on their private network, and the peer id it derives
+pub const VFN_NETWORK_KEY: &str = "vfn_network";
+pub const VFN_NETWORK_PEER_ID: &str = "vfn_network_peer_id";
+/// The lease of the active instance of a validator failover pair
+pub const FAILOVER_LEASE: &str = "failover_lease";
 
 /// Filename for 0L configs
 pub const CONFIG_FILE: &str = "0L.toml";
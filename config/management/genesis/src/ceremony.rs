@@ -0,0 +1,570 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Offline genesis ceremonies, for air-gapped participants. Instead of uploading to a GitHub
+//! genesis repo, each participant exports the registrations of its shared namespaces to a bundle
+//! signed with its owner key. A coordinator verifies the bundles of a directory and aggregates
+//! them in a local shared storage, from which genesis is built as from the genesis repo.
+//!
+//! Online ceremonies can sign their registrations the same way: the signature is written next
+//! to the registrations of the owner, and genesis checks it when built with
+//! `--require-signed-registrations`, so that a registry cannot be tampered with unnoticed.
+
+use crate::{layout::Layout, waypoint::extract_waypoint_from_file};
+use diem_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    PrivateKey, Signature, SigningKey,
+};
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use diem_global_constants::{
+    ACCOUNT_PROFILE, OPERATOR_KEY, OWNER_KEY, PROOF_OF_WORK_PREIMAGE, PROOF_OF_WORK_PROOF,
+    REGISTRATION_SIGNATURE,
+};
+use diem_management::{
+    config::{Config, ConfigPath},
+    constants,
+    error::Error,
+    progress,
+    secure_backend::{SharedBackend, ValidatorBackend, DISK},
+    storage::StorageWrapper,
+};
+use diem_types::{chain_id::ChainId, transaction::authenticator::AuthenticationKey};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+/// The values an owner registers in its shared namespace
+const OWNER_ENTRIES: &[&str] = &[
+    OWNER_KEY,
+    constants::VALIDATOR_OPERATOR,
+    ACCOUNT_PROFILE,
+    PROOF_OF_WORK_PREIMAGE,
+    PROOF_OF_WORK_PROOF,
+];
+/// The values an operator registers in its shared namespace
+const OPERATOR_ENTRIES: &[&str] = &[OPERATOR_KEY, constants::VALIDATOR_CONFIG];
+/// The values genesis does without
+const OPTIONAL_ENTRIES: &[&str] = &[ACCOUNT_PROFILE];
+
+/// The local shared storage the registrations are aggregated in, in the data path of `files`
+pub const REGISTRATIONS_STORE: &str = "registrations.json";
+
+/// The namespace an owner registers under: the account its owner key derives
+pub fn owner_namespace(owner_key: &Ed25519PublicKey) -> String {
+    AuthenticationKey::ed25519(owner_key)
+        .derived_address()
+        .to_hex()
+}
+
+/// The namespace the operator of `owner` registers under
+pub fn operator_namespace(owner: &str) -> String {
+    format!("{}-oper", owner)
+}
+
+/// The registrations of an owner and of its operator, as read from the shared storage. Values
+/// are kept as the json stored by the shared storage, keyed by their name.
+#[derive(BCSCryptoHash, Clone, CryptoHasher, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Registration {
+    pub owner: String,
+    pub operator: String,
+    pub owner_entries: BTreeMap<String, String>,
+    pub operator_entries: BTreeMap<String, String>,
+}
+
+/// A registration signed by the owner key, as exchanged in an offline ceremony
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RegistrationBundle {
+    pub registration: Registration,
+    pub owner_key: Ed25519PublicKey,
+    pub signature: Ed25519Signature,
+}
+
+impl Registration {
+    /// Reads the registrations of `owner` and of the operator it set
+    pub fn read(config: &Config, owner: &str) -> Result<Self, Error> {
+        let owner_storage = config.shared_backend_with_namespace(owner.into());
+        let operator = owner_storage.string(constants::VALIDATOR_OPERATOR)?;
+        let operator_storage = config.shared_backend_with_namespace(operator.clone());
+        Ok(Registration {
+            owner: owner.into(),
+            owner_entries: read_entries(&owner_storage, OWNER_ENTRIES)?,
+            operator_entries: read_entries(&operator_storage, OPERATOR_ENTRIES)?,
+            operator,
+        })
+    }
+
+    /// Writes the registrations to the namespaces of the owner and operator
+    pub fn write(&self, config: &Config) -> Result<(), Error> {
+        write_entries(
+            &mut config.shared_backend_with_namespace(self.owner.clone()),
+            &self.owner_entries,
+            OWNER_ENTRIES,
+        )?;
+        write_entries(
+            &mut config.shared_backend_with_namespace(self.operator.clone()),
+            &self.operator_entries,
+            OPERATOR_ENTRIES,
+        )
+    }
+}
+
+impl RegistrationBundle {
+    /// Signs the registration with the owner key of the validator storage
+    pub fn sign(registration: Registration, validator: &StorageWrapper) -> Result<Self, Error> {
+        let key = validator.ed25519_private(OWNER_KEY)?;
+        Ok(RegistrationBundle {
+            signature: key.sign(&registration),
+            owner_key: key.public_key(),
+            registration,
+        })
+    }
+
+    /// Checks the signature, that it is by the owner key the registration submits, and that the
+    /// key is the one of the account the namespace names. Without the last check, anyone able to
+    /// write to the registry could replace the owner key and sign again.
+    pub fn verify(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Error::ConfigInvalid {
+            path: self.registration.owner.clone(),
+            reason,
+        };
+        self.signature
+            .verify(&self.registration, &self.owner_key)
+            .map_err(|e| invalid(format!("invalid registration signature: {}", e)))?;
+        let registered_key: Ed25519PublicKey = self
+            .registration
+            .owner_entries
+            .get(OWNER_KEY)
+            .and_then(|value| serde_json::from_str(value).ok())
+            .ok_or_else(|| invalid("the registration has no owner key".into()))?;
+        if registered_key != self.owner_key {
+            return Err(invalid(
+                "the registration is not signed by its owner key".into(),
+            ));
+        }
+        let namespace = owner_namespace(&self.owner_key);
+        if namespace != self.registration.owner {
+            return Err(invalid(format!(
+                "the owner key is the one of account {}, not of the namespace",
+                namespace
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn read_entries(
+    storage: &StorageWrapper,
+    names: &[&'static str],
+) -> Result<BTreeMap<String, String>, Error> {
+    let mut entries = BTreeMap::new();
+    for name in names {
+        let value: serde_json::Value = match storage.value(*name) {
+            Ok(value) => value,
+            Err(_) if OPTIONAL_ENTRIES.contains(name) => continue,
+            Err(e) => return Err(e),
+        };
+        entries.insert(name.to_string(), value.to_string());
+    }
+    Ok(entries)
+}
+
+fn write_entries(
+    storage: &mut StorageWrapper,
+    entries: &BTreeMap<String, String>,
+    names: &[&'static str],
+) -> Result<(), Error> {
+    for (key, value) in entries {
+        let name = *names
+            .iter()
+            .find(|name| **name == key.as_str())
+            .ok_or_else(|| Error::UnexpectedError(format!("Unexpected registration {}", key)))?;
+        let value: serde_json::Value =
+            serde_json::from_str(value).map_err(|e| Error::UnableToParse(name, e.to_string()))?;
+        storage.set(name, value)?;
+    }
+    Ok(())
+}
+
+/// Signs the registrations of `owner` and of its operator with the owner key of the validator
+/// storage, and writes the signature next to the registrations of the owner
+pub fn sign_registration(config: &Config, owner: &str) -> Result<Ed25519Signature, Error> {
+    let registration = Registration::read(config, owner)?;
+    let bundle = RegistrationBundle::sign(registration, &config.validator_backend())?;
+    bundle.verify()?;
+    let mut owner_storage = config.shared_backend_with_namespace(owner.into());
+    owner_storage.set(REGISTRATION_SIGNATURE, bundle.signature.clone())?;
+    Ok(bundle.signature)
+}
+
+/// Checks that the registrations of `owner` and of its operator are those the owner signed
+pub fn verify_registration(config: &Config, owner: &str) -> Result<(), Error> {
+    let registration = Registration::read(config, owner)?;
+    let owner_storage = config.shared_backend_with_namespace(owner.into());
+    let signature = owner_storage
+        .value::<Ed25519Signature>(REGISTRATION_SIGNATURE)
+        .map_err(|_| Error::ConfigInvalid {
+            path: owner.into(),
+            reason: "the registration is not signed, see sign-registration".into(),
+        })?;
+    let owner_key = owner_storage.ed25519_key(OWNER_KEY)?;
+    RegistrationBundle {
+        registration,
+        owner_key,
+        signature,
+    }
+    .verify()
+}
+
+/// Reads the bundles of `dir`, its `.json` files, ordered by owner
+pub fn read_bundles(dir: &Path) -> Result<Vec<RegistrationBundle>, Error> {
+    let entries = fs::read_dir(dir).map_err(|e| Error::IO(dir.display().to_string(), e))?;
+    let mut bundles = vec![];
+    for entry in entries {
+        let path = entry
+            .map_err(|e| Error::IO(dir.display().to_string(), e))?
+            .path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+        let contents =
+            fs::read_to_string(&path).map_err(|e| Error::IO(path.display().to_string(), e))?;
+        let bundle: RegistrationBundle = serde_json::from_str(&contents)
+            .map_err(|e| Error::UnableToParseFile(path.display().to_string(), e.to_string()))?;
+        bundles.push(bundle);
+    }
+    bundles.sort_by(|a, b| a.registration.owner.cmp(&b.registration.owner));
+    Ok(bundles)
+}
+
+/// Verifies the bundles of `dir` and writes their registrations, and the layout listing them, to
+/// the shared storage of `config`
+pub fn aggregate_registrations(dir: &Path, config: &Config) -> Result<Layout, Error> {
+    let bundles = read_bundles(dir)?;
+    let mut steps = progress::Progress::new("aggregate registrations", bundles.len());
+    let mut layout = Layout::default();
+    for bundle in &bundles {
+        let registration = &bundle.registration;
+        steps.step(&registration.owner);
+        bundle.verify()?;
+        // A bundle only writes to the namespaces of its own owner, the one its key was just
+        // checked against and the operator namespace of that owner
+        let operator = operator_namespace(&registration.owner);
+        if registration.operator != operator {
+            return Err(Error::ConfigInvalid {
+                path: registration.owner.clone(),
+                reason: format!("the operator must register under {}", operator),
+            });
+        }
+        if layout.owners.contains(&registration.owner) {
+            return Err(Error::CommandArgumentError(format!(
+                "Several registrations of {} in {:?}",
+                registration.owner, dir
+            )));
+        }
+        registration.write(config)?;
+        layout.owners.push(registration.owner.clone());
+        layout.operators.push(registration.operator.clone());
+    }
+    steps.done();
+
+    let mut common = config.shared_backend_with_namespace(constants::COMMON_NS.into());
+    common.set(constants::LAYOUT, layout.to_toml()?)?;
+    Ok(layout)
+}
+
+/// Builds the genesis of the bundles of `dir` in `output_dir`, without network access. The
+/// registrations are aggregated in its `registrations.json`, and the genesis waypoint is written
+/// next to the blob, as for a prebuilt genesis.
+pub fn offline_genesis(
+    dir: &Path,
+    output_dir: &Path,
+    chain_id: ChainId,
+    layout_path: &Option<PathBuf>,
+) -> Result<PathBuf, Error> {
+    let store = output_dir.join(REGISTRATIONS_STORE);
+    let backend: SharedBackend = format!("backend={};path={}", DISK, store.display()).parse()?;
+    let config = Config::default()
+        .override_chain_id(Some(chain_id))
+        .override_shared_backend(&backend.shared_backend)?;
+    aggregate_registrations(dir, &config)?;
+
+    let genesis_path = output_dir.join("genesis.blob");
+    crate::genesis::Genesis {
+        config: ConfigPath::default(),
+        chain_id: Some(chain_id),
+        backend,
+        path: Some(genesis_path.clone()),
+        layout_path: layout_path.clone(),
+        // The bundles were verified as they were aggregated
+        require_signed_registrations: false,
+    }
+    .execute()?;
+    let waypoint = extract_waypoint_from_file(&genesis_path)?;
+    let waypoint_path = output_dir.join("genesis_waypoint.txt");
+    fs::write(&waypoint_path, waypoint.to_string())
+        .map_err(|e| Error::IO(waypoint_path.display().to_string(), e))?;
+    progress::detail(&format!("offline genesis waypoint: {}", waypoint));
+    Ok(genesis_path)
+}
+
+/// Exports the registrations of an owner to a signed bundle
+#[derive(Debug, StructOpt)]
+pub struct ExportRegistration {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// The shared storage the owner and operator registered to, e.g. a local disk storage
+    #[structopt(flatten)]
+    shared_backend: SharedBackend,
+    /// The storage holding the owner key
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+    /// The namespace of the owner
+    #[structopt(long)]
+    owner_name: String,
+    /// The bundle to write
+    #[structopt(long, parse(from_os_str))]
+    output: PathBuf,
+}
+
+impl ExportRegistration {
+    pub fn execute(self) -> Result<String, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_shared_backend(&self.shared_backend.shared_backend)?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        let registration = Registration::read(&config, &self.owner_name)?;
+        let bundle = RegistrationBundle::sign(registration, &config.validator_backend())?;
+        let contents = serde_json::to_string_pretty(&bundle)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to serialize bundle: {}", e)))?;
+        fs::write(&self.output, contents)
+            .map_err(|e| Error::IO(self.output.display().to_string(), e))?;
+        Ok(format!(
+            "Registration of {} written to {}",
+            self.owner_name,
+            self.output.display()
+        ))
+    }
+}
+
+/// Signs the registrations of an owner in the shared storage, for genesis to verify them
+#[derive(Debug, StructOpt)]
+pub struct SignRegistration {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// The shared storage the owner and operator registered to
+    #[structopt(flatten)]
+    shared_backend: SharedBackend,
+    /// The storage holding the owner key
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+    /// The namespace of the owner
+    #[structopt(long)]
+    owner_name: String,
+}
+
+impl SignRegistration {
+    pub fn execute(self) -> Result<String, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_shared_backend(&self.shared_backend.shared_backend)?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        sign_registration(&config, &self.owner_name)?;
+        Ok(format!("Registration of {} signed", self.owner_name))
+    }
+}
+
+/// Aggregates the registration bundles of a directory in a shared storage
+#[derive(Debug, StructOpt)]
+pub struct AggregateRegistrations {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// The directory of the bundles, one `.json` file per owner
+    #[structopt(long, parse(from_os_str))]
+    registrations_dir: PathBuf,
+    /// The shared storage to build genesis from, e.g. backend=disk;path=registrations.json
+    #[structopt(flatten)]
+    shared_backend: SharedBackend,
+}
+
+impl AggregateRegistrations {
+    pub fn execute(self) -> Result<String, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_shared_backend(&self.shared_backend.shared_backend)?;
+        let layout = aggregate_registrations(&self.registrations_dir, &config)?;
+        Ok(format!(
+            "{} registrations aggregated, layout:\n{}",
+            layout.owners.len(),
+            layout
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config_builder::{operator_shared_namespace, owner_shared_namespace, ValidatorBuilder},
+        swarm_config::BuildSwarm,
+    };
+    use diem_config::config::NodeConfig;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+    use diem_management::secure_backend::SecureBackend;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_offline_ceremony() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let builder = ValidatorBuilder::new(2, NodeConfig::default_for_validator(), dir.path());
+        let (validators, _) = builder.build_swarm().unwrap();
+        let swarm_genesis =
+            bcs::to_bytes(validators[0].execution.genesis.as_ref().unwrap()).unwrap();
+
+        // Each participant exports the registrations it made to the swarm storage
+        let bundles = dir.path().join("bundles");
+        fs::create_dir(&bundles).unwrap();
+        let store = format!("backend=disk;path={}", builder.storage_path().display());
+        let owner_config = |index: usize| {
+            let validator: SecureBackend = format!("{};namespace={}_owner", store, index)
+                .parse()
+                .unwrap();
+            Config::default()
+                .override_shared_backend(&Some(store.parse().unwrap()))
+                .unwrap()
+                .override_validator_backend(&Some(validator))
+                .unwrap()
+        };
+        for index in 0..2 {
+            let config = owner_config(index);
+            let registration = Registration::read(&config, &owner_shared_namespace(index)).unwrap();
+            let bundle =
+                RegistrationBundle::sign(registration, &config.validator_backend()).unwrap();
+            bundle.verify().unwrap();
+            fs::write(
+                bundles.join(format!("{}.json", index)),
+                serde_json::to_string(&bundle).unwrap(),
+            )
+            .unwrap();
+        }
+
+        // The coordinator builds the same genesis from the bundles alone, the validators in the
+        // order of the swarm
+        let output = dir.path().join("coordinator");
+        fs::create_dir(&output).unwrap();
+        let layout_path = dir.path().join("layout.toml");
+        let swarm_config = Config::default()
+            .override_shared_backend(&Some(store.parse().unwrap()))
+            .unwrap();
+        let layout = swarm_config
+            .shared_backend_with_namespace(constants::COMMON_NS.into())
+            .string(constants::LAYOUT)
+            .unwrap();
+        fs::write(&layout_path, layout).unwrap();
+        let genesis_path =
+            offline_genesis(&bundles, &output, ChainId::test(), &Some(layout_path)).unwrap();
+        assert_eq!(fs::read(&genesis_path).unwrap(), swarm_genesis);
+        assert_eq!(
+            fs::read_to_string(output.join("genesis_waypoint.txt")).unwrap(),
+            validators[0].base.waypoint.waypoint().to_string()
+        );
+
+        // A tampered bundle is rejected
+        let path = bundles.join("0.json");
+        let mut bundle: RegistrationBundle =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        bundle.registration.operator = operator_shared_namespace(1);
+        assert!(bundle.verify().is_err());
+        fs::write(&path, serde_json::to_string(&bundle).unwrap()).unwrap();
+        assert!(offline_genesis(&bundles, &output, ChainId::test(), &None).is_err());
+
+        // So is a bundle its owner signed, writing to the operator namespace of another owner
+        let bundle =
+            RegistrationBundle::sign(bundle.registration, &owner_config(0).validator_backend())
+                .unwrap();
+        bundle.verify().unwrap();
+        fs::write(&path, serde_json::to_string(&bundle).unwrap()).unwrap();
+        assert!(offline_genesis(&bundles, &output, ChainId::test(), &None).is_err());
+    }
+
+    #[test]
+    fn test_signed_registrations() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let builder = ValidatorBuilder::new(2, NodeConfig::default_for_validator(), dir.path());
+        builder.build_swarm().unwrap();
+        let store = format!("backend=disk;path={}", builder.storage_path().display());
+        let config = Config::default()
+            .override_shared_backend(&Some(store.parse().unwrap()))
+            .unwrap();
+        let genesis = || crate::genesis::Genesis {
+            config: ConfigPath::default(),
+            chain_id: Some(ChainId::test()),
+            backend: SharedBackend {
+                shared_backend: Some(store.parse().unwrap()),
+            },
+            path: None,
+            layout_path: None,
+            require_signed_registrations: true,
+        };
+
+        // The swarm signs the registrations it makes
+        for index in 0..2 {
+            verify_registration(&config, &owner_shared_namespace(index)).unwrap();
+        }
+        genesis().execute().unwrap();
+
+        // Pointing an owner to another operator breaks its signature
+        let owner = owner_shared_namespace(0);
+        let mut owner_storage = config.shared_backend_with_namespace(owner.clone());
+        owner_storage
+            .set(constants::VALIDATOR_OPERATOR, operator_shared_namespace(1))
+            .unwrap();
+        assert!(verify_registration(&config, &owner).is_err());
+        assert!(genesis().execute().is_err());
+    }
+
+    #[test]
+    fn test_forged_registration() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let builder = ValidatorBuilder::new(1, NodeConfig::default_for_validator(), dir.path());
+        builder.build_swarm().unwrap();
+        let store = format!("backend=disk;path={}", builder.storage_path().display());
+        let config = Config::default()
+            .override_shared_backend(&Some(store.parse().unwrap()))
+            .unwrap();
+        let owner = owner_shared_namespace(0);
+        verify_registration(&config, &owner).unwrap();
+
+        // Whoever can write to the registry replaces the owner key, and signs again with theirs
+        let forger = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+        let mut owner_storage = config.shared_backend_with_namespace(owner.clone());
+        owner_storage.set(OWNER_KEY, forger.public_key()).unwrap();
+        let registration = Registration::read(&config, &owner).unwrap();
+        let signature = forger.sign(&registration);
+        owner_storage
+            .set(REGISTRATION_SIGNATURE, signature.clone())
+            .unwrap();
+        assert!(verify_registration(&config, &owner).is_err());
+
+        // The same forgery, as an offline bundle
+        let bundle = RegistrationBundle {
+            registration,
+            owner_key: forger.public_key(),
+            signature,
+        };
+        assert!(bundle.verify().is_err());
+    }
+}
@@ -42,6 +42,38 @@ pub enum Command {
     CreateRepo(crate::ol_create_repo::CreateGenesisRepo),
     #[structopt(about = "Shows seed peers in a genesis blob")]
     GenesisSeedPeers(crate::seeds::Seeds),  
+    #[structopt(about = "Inspects and checks node configs")]
+    NodeConfig(crate::node_config::NodeConfigCommand),
+    #[structopt(about = "Exports the peer graph of a network")]
+    Topology(crate::topology::TopologyCommand),
+    #[structopt(about = "Manages the seed peers of node configs")]
+    Peers(crate::peers::PeersCommand),
+    #[structopt(about = "Rebuilds genesis from the shared storage and compares it to a blob")]
+    VerifyGenesis(crate::verify_genesis::VerifyGenesis),
+    #[structopt(about = "Exports the registrations of an owner to a signed bundle")]
+    ExportRegistration(crate::ceremony::ExportRegistration),
+    #[structopt(about = "Aggregates registration bundles for an offline genesis")]
+    AggregateRegistrations(crate::ceremony::AggregateRegistrations),
+    #[structopt(about = "Initializes the validator keys of the key store, step 1 of files")]
+    InitKeys(crate::ol_node_files::InitKeys),
+    #[structopt(about = "Builds or copies genesis into the data path, step 2 of files")]
+    FetchGenesis(crate::ol_node_files::FetchGenesis),
+    #[structopt(about = "Writes the node configs from the data path, step 3 of files")]
+    RenderConfig(crate::ol_node_files::RenderConfig),
+    #[structopt(about = "Encrypts, decrypts or changes the passphrase of a key_store.json")]
+    KeyStore(crate::key_store::KeyStore),
+    #[structopt(about = "Restores the db of a node")]
+    Db(crate::db::DbCommand),
+    #[structopt(about = "Lists and checks the namespaces of the genesis registry")]
+    Registry(crate::registry::RegistryCommand),
+    #[structopt(about = "Signs the registrations of an owner in the shared storage")]
+    SignRegistration(crate::ceremony::SignRegistration),
+    #[structopt(about = "Sets up a node")]
+    Node(crate::node::NodeCommand),
+    #[structopt(about = "Backs up and restores the identity keys of a key_store.json")]
+    Keys(crate::keys::KeysCommand),
+    #[structopt(about = "Checks the namespace to register under against the keys and registry")]
+    Namespace(crate::namespace::NamespaceCommand),
 }
 
 #[derive(Debug, PartialEq)]
@@ -63,7 +95,22 @@ pub enum CommandName {
     Mining,
     CreateRepo, 
     GenesisSeedPeers,
-
+    NodeConfig,
+    Topology,
+    Peers,
+    VerifyGenesis,
+    ExportRegistration,
+    AggregateRegistrations,
+    InitKeys,
+    FetchGenesis,
+    RenderConfig,
+    KeyStore,
+    Db,
+    Registry,
+    SignRegistration,
+    Node,
+    Keys,
+    Namespace,
 }
 
 impl From<&Command> for CommandName {
@@ -86,6 +133,22 @@ impl From<&Command> for CommandName {
             Command::Mining(_) => CommandName::Mining,  
             Command::CreateRepo(_) => CommandName::CreateRepo,  
             Command::GenesisSeedPeers(_) => CommandName::GenesisSeedPeers,  
+            Command::NodeConfig(_) => CommandName::NodeConfig,
+            Command::Topology(_) => CommandName::Topology,
+            Command::Peers(_) => CommandName::Peers,
+            Command::VerifyGenesis(_) => CommandName::VerifyGenesis,
+            Command::ExportRegistration(_) => CommandName::ExportRegistration,
+            Command::AggregateRegistrations(_) => CommandName::AggregateRegistrations,
+            Command::InitKeys(_) => CommandName::InitKeys,
+            Command::FetchGenesis(_) => CommandName::FetchGenesis,
+            Command::RenderConfig(_) => CommandName::RenderConfig,
+            Command::KeyStore(_) => CommandName::KeyStore,
+            Command::Db(_) => CommandName::Db,
+            Command::Registry(_) => CommandName::Registry,
+            Command::SignRegistration(_) => CommandName::SignRegistration,
+            Command::Node(_) => CommandName::Node,
+            Command::Keys(_) => CommandName::Keys,
+            Command::Namespace(_) => CommandName::Namespace,
             
         }
     }
@@ -111,6 +174,22 @@ impl std::fmt::Display for CommandName {
             CommandName::Mining => "mining",
             CommandName::CreateRepo => "create-repo",
             CommandName::GenesisSeedPeers => "genesis-seed-peers",
+            CommandName::NodeConfig => "node-config",
+            CommandName::Topology => "topology",
+            CommandName::Peers => "peers",
+            CommandName::VerifyGenesis => "verify-genesis",
+            CommandName::ExportRegistration => "export-registration",
+            CommandName::AggregateRegistrations => "aggregate-registrations",
+            CommandName::InitKeys => "init-keys",
+            CommandName::FetchGenesis => "fetch-genesis",
+            CommandName::RenderConfig => "render-config",
+            CommandName::KeyStore => "key-store",
+            CommandName::Db => "db",
+            CommandName::Registry => "registry",
+            CommandName::SignRegistration => "sign-registration",
+            CommandName::Node => "node",
+            CommandName::Keys => "keys",
+            CommandName::Namespace => "namespace",
         };
         write!(f, "{}", name)
     }
@@ -140,6 +219,22 @@ impl Command {
             Command::Mining(_) => self.mining(),
             Command::CreateRepo(_) => self.create_repo(),
             Command::GenesisSeedPeers(_) => self.genesis_seed_peers(),
+            Command::NodeConfig(_) => self.node_config(),
+            Command::Topology(_) => self.topology(),
+            Command::Peers(_) => self.peers(),
+            Command::VerifyGenesis(_) => self.verify_genesis(),
+            Command::ExportRegistration(_) => self.export_registration(),
+            Command::AggregateRegistrations(_) => self.aggregate_registrations(),
+            Command::InitKeys(_) => self.init_keys(),
+            Command::FetchGenesis(_) => self.fetch_genesis(),
+            Command::RenderConfig(_) => self.render_config().map(|_| "Success!".to_string()),
+            Command::KeyStore(_) => self.key_store(),
+            Command::Db(_) => self.db(),
+            Command::Registry(_) => self.registry(),
+            Command::SignRegistration(_) => self.sign_registration(),
+            Command::Node(_) => self.node(),
+            Command::Keys(_) => self.keys(),
+            Command::Namespace(_) => self.namespace(),
 
         }
     }
@@ -208,6 +303,58 @@ impl Command {
     pub fn genesis_seed_peers(self) -> Result<String, Error> {
         execute_command!(self, Command::GenesisSeedPeers, CommandName::GenesisSeedPeers)
     }
+    pub fn node_config(self) -> Result<String, Error> {
+        execute_command!(self, Command::NodeConfig, CommandName::NodeConfig)
+    }
+    pub fn topology(self) -> Result<String, Error> {
+        execute_command!(self, Command::Topology, CommandName::Topology)
+    }
+    pub fn peers(self) -> Result<String, Error> {
+        execute_command!(self, Command::Peers, CommandName::Peers)
+    }
+    pub fn verify_genesis(self) -> Result<String, Error> {
+        execute_command!(self, Command::VerifyGenesis, CommandName::VerifyGenesis)
+    }
+    pub fn export_registration(self) -> Result<String, Error> {
+        execute_command!(self, Command::ExportRegistration, CommandName::ExportRegistration)
+    }
+    pub fn aggregate_registrations(self) -> Result<String, Error> {
+        execute_command!(
+            self,
+            Command::AggregateRegistrations,
+            CommandName::AggregateRegistrations
+        )
+    }
+    pub fn init_keys(self) -> Result<String, Error> {
+        execute_command!(self, Command::InitKeys, CommandName::InitKeys)
+    }
+    pub fn fetch_genesis(self) -> Result<String, Error> {
+        execute_command!(self, Command::FetchGenesis, CommandName::FetchGenesis)
+    }
+    pub fn render_config(self) -> Result<NodeConfig, Error> {
+        execute_command!(self, Command::RenderConfig, CommandName::RenderConfig)
+    }
+    pub fn key_store(self) -> Result<String, Error> {
+        execute_command!(self, Command::KeyStore, CommandName::KeyStore)
+    }
+    pub fn db(self) -> Result<String, Error> {
+        execute_command!(self, Command::Db, CommandName::Db)
+    }
+    pub fn registry(self) -> Result<String, Error> {
+        execute_command!(self, Command::Registry, CommandName::Registry)
+    }
+    pub fn sign_registration(self) -> Result<String, Error> {
+        execute_command!(self, Command::SignRegistration, CommandName::SignRegistration)
+    }
+    pub fn node(self) -> Result<String, Error> {
+        execute_command!(self, Command::Node, CommandName::Node)
+    }
+    pub fn keys(self) -> Result<String, Error> {
+        execute_command!(self, Command::Keys, CommandName::Keys)
+    }
+    pub fn namespace(self) -> Result<String, Error> {
+        execute_command!(self, Command::Namespace, CommandName::Namespace)
+    }
 }
 
 /// These tests depends on running Vault, which can be done by using the provided docker run script
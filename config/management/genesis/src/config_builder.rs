@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{layout::Layout, storage_helper::StorageHelper, swarm_config::BuildSwarm};
+use crate::{
+    ceremony, layout::Layout, storage_helper::StorageHelper, swarm_config::BuildSwarm,
+    testing::test_identity,
+};
 use diem_config::{
     config::{
         Identity, NodeConfig, OnDiskStorageConfig, PeerRole, SafetyRulesService, SecureBackend,
@@ -20,9 +23,26 @@ use std::path::{Path, PathBuf};
 const DIEM_ROOT_NS: &str = "diem_root";
 const DIEM_ROOT_SHARED_NS: &str = "diem_root_shared";
 const OPERATOR_NS: &str = "_operator";
-const OPERATOR_SHARED_NS: &str = "_operator_shared";
 const OWNER_NS: &str = "_owner";
-const OWNER_SHARED_NS: &str = "_owner_shared";
+
+//////// 0L ////////
+/// The local namespace of the operator of validator `index`
+pub(crate) fn operator_namespace(index: usize) -> String {
+    index.to_string() + OPERATOR_NS
+}
+
+//////// 0L ////////
+/// The shared namespace of the owner of validator `index`, its account, as genesis requires
+pub(crate) fn owner_shared_namespace(index: usize) -> String {
+    let (_, _, account) = test_identity(index);
+    account.to_hex()
+}
+
+//////// 0L ////////
+/// The shared namespace of the operator of validator `index`, named after its owner
+pub(crate) fn operator_shared_namespace(index: usize) -> String {
+    ceremony::operator_namespace(&owner_shared_namespace(index))
+}
 
 pub struct ValidatorBuilder<T: AsRef<Path>> {
     storage_helper: StorageHelper,
@@ -30,6 +50,7 @@ pub struct ValidatorBuilder<T: AsRef<Path>> {
     randomize_first_validator_ports: bool,
     swarm_path: T,
     template: NodeConfig,
+    chain_id: ChainId, //////// 0L ////////
 }
 
 impl<T: AsRef<Path>> ValidatorBuilder<T> {
@@ -40,6 +61,7 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
             randomize_first_validator_ports: true,
             swarm_path,
             template,
+            chain_id: ChainId::test(),
         }
     }
 
@@ -48,8 +70,20 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
         self
     }
 
+    //////// 0L ////////
+    pub fn chain_id(mut self, chain_id: ChainId) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    //////// 0L ////////
+    /// The store holding the local and shared namespaces of all participants
+    pub(crate) fn storage_path(&self) -> &Path {
+        self.storage_helper.path().expect("swarm keys are on disk")
+    }
+
     fn secure_backend(&self, ns: &str, usage: &str) -> SecureBackend {
-        let original = self.storage_helper.path();
+        let original = self.storage_path();
         let dst_base = self.swarm_path.as_ref();
         let mut dst = dst_base.to_path_buf();
         dst.push(format!("{}_{}", usage, ns));
@@ -66,12 +100,12 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
     fn create_layout(&self) {
         let layout = Layout {
             owners: (0..self.num_validators)
-                .map(|i| (i.to_string() + OWNER_SHARED_NS))
+                .map(owner_shared_namespace)
                 .collect(),
             operators: (0..self.num_validators)
-                .map(|i| (i.to_string() + OPERATOR_SHARED_NS))
+                .map(operator_shared_namespace)
                 .collect(),
-            //////// 0L ////////                
+            //////// 0L ////////
             // diem_root: DIEM_ROOT_SHARED_NS.into(),
             // treasury_compliance: DIEM_ROOT_SHARED_NS.into(),
         };
@@ -96,15 +130,16 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
     /// Generate owner key locally and upload to shared storage.
     fn initialize_validator_owner(&self, index: usize) {
         let local_ns = index.to_string() + OWNER_NS;
-        let remote_ns = index.to_string() + OWNER_SHARED_NS;
+        let remote_ns = owner_shared_namespace(index);
 
-        self.storage_helper
-            .initialize_by_idx(local_ns.clone(), 1 + index);
-        
         //////// 0L /////////
+        let (keys, _, _) = test_identity(index);
         self.storage_helper
-            .swarm_pow_helper(remote_ns.clone());
-                        
+            .initialize_with_keys(local_ns.clone(), keys);
+
+        //////// 0L /////////
+        self.storage_helper.swarm_pow_helper(remote_ns.clone());
+
         let _ = self
             .storage_helper
             .owner_key(&local_ns, &remote_ns)
@@ -114,10 +149,13 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
     /// Generate operator key locally and upload to shared storage.
     fn initialize_validator_operator(&self, index: usize) {
         let local_ns = index.to_string() + OPERATOR_NS;
-        let remote_ns = index.to_string() + OPERATOR_SHARED_NS;
+        let remote_ns = operator_shared_namespace(index);
 
+        //////// 0L /////////
+        // Owners and operators share their keys, as with a 0L mnemonic
+        let (keys, _, _) = test_identity(index);
         self.storage_helper
-            .initialize_by_idx(local_ns.clone(), self.num_validators + 1 + index);
+            .initialize_with_keys(local_ns.clone(), keys);
         let _ = self
             .storage_helper
             .operator_key(&local_ns, &remote_ns)
@@ -127,16 +165,27 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
     /// Sets the operator for the owner by uploading a set-operator transaction to shared storage.
     /// Note, we assume that owner i chooses operator i to operate the validator.
     fn set_validator_operator(&self, index: usize) {
-        let remote_ns = index.to_string() + OWNER_SHARED_NS;
+        let remote_ns = owner_shared_namespace(index);
 
-        let operator_name = index.to_string() + OPERATOR_SHARED_NS;
+        let operator_name = operator_shared_namespace(index);
         let _ = self.storage_helper.set_operator(&operator_name, &remote_ns);
     }
 
+    //////// 0L ////////
+    /// Owners sign their registrations, once the operator uploaded its validator_config.
+    fn sign_registration(&self, index: usize) {
+        let local_ns = index.to_string() + OWNER_NS;
+        let remote_ns = owner_shared_namespace(index);
+
+        self.storage_helper
+            .sign_registration(&local_ns, &remote_ns)
+            .unwrap();
+    }
+
     /// Operators upload their validator_config to shared storage.
     fn initialize_validator_config(&self, index: usize) -> NodeConfig {
         let local_ns = index.to_string() + OPERATOR_NS;
-        let remote_ns = index.to_string() + OPERATOR_SHARED_NS;
+        let remote_ns = operator_shared_namespace(index);
 
         let mut config = self.template.clone();
         if index > 0 || self.randomize_first_validator_ports {
@@ -150,10 +199,10 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
 
         self.storage_helper
             .validator_config(
-                &(index.to_string() + OWNER_SHARED_NS),
+                &owner_shared_namespace(index),
                 validator_network_address,
                 fullnode_network_address,
-                ChainId::test(),
+                self.chain_id,
                 &local_ns,
                 &remote_ns,
             )
@@ -187,7 +236,7 @@ impl<T: AsRef<Path>> ValidatorBuilder<T> {
         genesis_path.create_as_file().unwrap();
         let genesis = self
             .storage_helper
-            .genesis(ChainId::test(), genesis_path.path())
+            .genesis(self.chain_id, genesis_path.path())
             .unwrap();
 
         self.storage_helper
@@ -232,13 +281,11 @@ impl<T: AsRef<Path>> BuildSwarm for ValidatorBuilder<T> {
         for index in 0..self.num_validators {
             let _ = self.set_validator_operator(index);
             let config = self.initialize_validator_config(index);
+            self.sign_registration(index);
             configs.push(config);
         }
 
-        let waypoint = self
-            .storage_helper
-            .create_waypoint(ChainId::test())
-            .unwrap();
+        let waypoint = self.storage_helper.create_waypoint(self.chain_id).unwrap();
         // Create genesis and waypoint
         for (i, config) in configs.iter_mut().enumerate() {
             self.finish_validator_config(i, config, waypoint);
@@ -276,7 +323,10 @@ impl FullnodeBuilder {
         }
     }
 
-    fn attach_validator_full_node(&self, validator_config: &mut NodeConfig) -> NodeConfig {
+    pub(crate) fn attach_validator_full_node(
+        &self,
+        validator_config: &mut NodeConfig,
+    ) -> NodeConfig {
         // Create two vfns, we'll pass one to the validator later
         let mut full_node_config = self.template.clone();
         full_node_config.randomize_ports();
@@ -309,7 +359,7 @@ impl FullnodeBuilder {
         full_node_config
     }
 
-    fn insert_waypoint_and_genesis(config: &mut NodeConfig, upstream: &NodeConfig) {
+    pub(crate) fn insert_waypoint_and_genesis(config: &mut NodeConfig, upstream: &NodeConfig) {
         config.base.waypoint = upstream.base.waypoint.clone();
         config.execution.genesis = upstream.execution.genesis.clone();
         config.execution.genesis_file_location = PathBuf::from("");
@@ -0,0 +1,163 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! `db` subcommands, for the database of a node
+
+use anyhow::{anyhow, ensure};
+use backup_cli::{
+    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    metadata::cache::MetadataCacheOpt,
+    storage::init_storage_for_target,
+    utils::{
+        ConcurrentDownloadsOpt, GlobalRestoreOpt, GlobalRestoreOptions, RocksdbOpt,
+        TrustedWaypointOpt,
+    },
+};
+use diem_config::config::{BackupTarget, NodeConfig, PersistableConfig, WaypointConfig};
+use diem_management::{error::Error, progress};
+use diem_types::waypoint::Waypoint;
+use diemdb::DiemDB;
+use std::{
+    convert::TryInto,
+    path::{Path, PathBuf},
+};
+use storage_interface::DbReader;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum DbCommand {
+    #[structopt(about = "Rebuilds the db of a node from backups and sets its waypoint")]
+    Restore(Restore),
+}
+
+impl DbCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            DbCommand::Restore(restore) => restore.execute(),
+        }
+    }
+}
+
+/// Restores the db of the node config from the backups of a directory or of an S3 bucket, up to
+/// the version of the waypoint, checks that the restored ledger matches the waypoint, and writes
+/// the waypoint into the node config
+#[derive(Debug, StructOpt)]
+pub struct Restore {
+    /// Path to the node config, e.g. fullnode.node.yaml. The db is restored in its data dir.
+    #[structopt(long, parse(from_os_str))]
+    config: PathBuf,
+    /// Waypoint of an epoch change to restore to, e.g. from `ol query --epoch`
+    #[structopt(long)]
+    waypoint: Waypoint,
+    /// Directory of the backups, holding their metadata folder
+    #[structopt(long, parse(from_os_str), required_unless = "bucket")]
+    backup_dir: Option<PathBuf>,
+    /// S3 bucket of the backups, through the aws cli
+    #[structopt(long, conflicts_with = "backup-dir")]
+    bucket: Option<String>,
+    /// Directory of the backups in the bucket
+    #[structopt(long, default_value = "", requires = "bucket")]
+    sub_dir: String,
+    /// Endpoint of an S3 compatible store holding the bucket
+    #[structopt(long, requires = "bucket")]
+    endpoint_url: Option<String>,
+    /// Other waypoints to trust, e.g. those of epochs the validator set was overwritten in
+    #[structopt(long)]
+    trust_waypoint: Vec<Waypoint>,
+}
+
+impl Restore {
+    pub fn execute(self) -> Result<String, Error> {
+        let invalid = |reason: String| Error::ConfigInvalid {
+            path: self.config.display().to_string(),
+            reason,
+        };
+        let mut config = NodeConfig::load(&self.config).map_err(|e| invalid(e.to_string()))?;
+        let db_dir = config.storage.dir();
+        let target = match (&self.backup_dir, &self.bucket) {
+            (Some(path), _) => BackupTarget::LocalFs { path: path.clone() },
+            (None, Some(bucket)) => BackupTarget::S3 {
+                bucket: bucket.clone(),
+                sub_dir: self.sub_dir.clone(),
+                endpoint_url: self.endpoint_url.clone(),
+            },
+            (None, None) => {
+                return Err(Error::CommandArgumentError(
+                    "pass --backup-dir or --bucket".into(),
+                ))
+            }
+        };
+
+        let mut steps = progress::Progress::new("db restore", 2);
+        steps.step("restoring from backups");
+        let mut trust_waypoint = self.trust_waypoint.clone();
+        trust_waypoint.push(self.waypoint);
+        let global_opt: GlobalRestoreOptions = GlobalRestoreOpt {
+            dry_run: false,
+            db_dir: Some(db_dir.clone()),
+            target_version: Some(self.waypoint.version()),
+            trusted_waypoints: TrustedWaypointOpt { trust_waypoint },
+            rocksdb_opt: RocksdbOpt::default(),
+            concurernt_downloads: ConcurrentDownloadsOpt::default(),
+        }
+        .try_into()
+        .map_err(|e: anyhow::Error| Error::UnexpectedError(format!("Can't open the db: {}", e)))?;
+        tokio::runtime::Runtime::new()
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?
+            .block_on(async {
+                let storage = init_storage_for_target(&target).await?;
+                RestoreCoordinator::new(
+                    RestoreCoordinatorOpt {
+                        metadata_cache_opt: MetadataCacheOpt::new(None),
+                        replay_all: false,
+                    },
+                    global_opt,
+                    storage,
+                )
+                .run()
+                .await
+            })
+            .map_err(|e| Error::UnexpectedError(format!("Restore failed: {}", e)))?;
+
+        steps.step("verifying the restored db");
+        verify_waypoint(&db_dir, self.waypoint)
+            .map_err(|e| Error::UnexpectedError(format!("Restored db is invalid: {}", e)))?;
+        config.base.waypoint = WaypointConfig::FromConfig(self.waypoint);
+        config
+            .save_config(&self.config)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+        Ok(format!(
+            "Restored {} to waypoint {}",
+            db_dir.display(),
+            self.waypoint
+        ))
+    }
+}
+
+/// Checks that the ledger of the db at `db_dir` reaches the version of `waypoint`, with the epoch
+/// change and the transaction accumulator of the waypoint
+fn verify_waypoint(db_dir: &Path, waypoint: Waypoint) -> anyhow::Result<()> {
+    let db = DiemDB::open(
+        db_dir,
+        true, /* readonly */
+        None, /* pruner */
+        Default::default(),
+    )?;
+    let (latest_version, _) = db
+        .get_latest_transaction_info_option()?
+        .ok_or_else(|| anyhow!("No transaction was restored."))?;
+    ensure!(
+        latest_version >= waypoint.version(),
+        "The backups end at version {}, before the waypoint.",
+        latest_version,
+    );
+    let ledger_info = db.get_epoch_ending_ledger_info(waypoint.version())?;
+    waypoint.verify(ledger_info.ledger_info())?;
+    ensure!(
+        db.get_accumulator_root_hash(waypoint.version())?
+            == ledger_info.ledger_info().transaction_accumulator_hash(),
+        "The restored transactions don't match the waypoint.",
+    );
+    Ok(())
+}
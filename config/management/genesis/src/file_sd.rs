@@ -0,0 +1,134 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Prometheus file-based service discovery of the validator set, see
+//! https://prometheus.io/docs/guide/file-sd/
+//!
+//! Every validator and VFN of the set is a target on its metrics port. Validator addresses are
+//! decrypted with the shared network address key that 0L uses for all validators.
+
+use crate::validator_set::{Validator, ValidatorSetSource};
+use diem_config::{config::DebugInterfaceConfig, network_id::NetworkId};
+use diem_management::error::Error;
+use diem_network_address_encryption::Encryptor;
+use diem_types::network_address::{parse_tcp, NetworkAddress};
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Writes the Prometheus `file_sd` targets of the validator set
+#[derive(Debug, StructOpt)]
+pub struct FileSd {
+    #[structopt(flatten)]
+    source: ValidatorSetSource,
+    /// Metrics port of the nodes, defaults to the port of the default config
+    #[structopt(long)]
+    metrics_port: Option<u16>,
+    /// Write the targets to this file instead of printing them
+    #[structopt(long)]
+    output: Option<PathBuf>,
+}
+
+/// A group of targets sharing labels, as read by `file_sd_configs`
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TargetGroup {
+    pub targets: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl FileSd {
+    pub fn execute(self) -> Result<String, Error> {
+        let metrics_port = self
+            .metrics_port
+            .unwrap_or_else(|| DebugInterfaceConfig::default().metrics_server_port);
+        let groups = target_groups(
+            &self.source.validators()?,
+            &Encryptor::for_testing(),
+            metrics_port,
+        );
+        let json = serde_json::to_string_pretty(&groups)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        match self.output {
+            Some(output) => {
+                fs::write(&output, json).map_err(|e| Error::IO(output.display().to_string(), e))?;
+                Ok(format!(
+                    "{} targets written to {}",
+                    groups.len(),
+                    output.display()
+                ))
+            }
+            None => Ok(json),
+        }
+    }
+}
+
+/// One group per node, labeled with the network its address is advertised on, its role and the
+/// namespace of its operator. Nodes without a TCP address are skipped.
+pub fn target_groups(
+    validators: &[Validator],
+    encryptor: &Encryptor,
+    metrics_port: u16,
+) -> Vec<TargetGroup> {
+    let mut groups = vec![];
+    for validator in validators {
+        let config = validator.info.config();
+        let account = *validator.info.account_address();
+        let validator_addresses = encryptor
+            .decrypt(&config.validator_network_addresses, account)
+            .unwrap_or_default();
+        let fullnode_addresses = config.fullnode_network_addresses().unwrap_or_default();
+
+        let nodes = [
+            (validator_addresses, NetworkId::Validator, "validator"),
+            (fullnode_addresses, NetworkId::Public, "vfn"),
+        ];
+        for (addresses, network, role) in nodes.iter() {
+            if let Some(host) = host(addresses) {
+                let mut labels = BTreeMap::new();
+                labels.insert("network".to_string(), network.to_string());
+                labels.insert("role".to_string(), role.to_string());
+                labels.insert("namespace".to_string(), validator.name.clone());
+                labels.insert("account".to_string(), account.to_string());
+                groups.push(TargetGroup {
+                    targets: vec![format!("{}:{}", host, metrics_port)],
+                    labels,
+                });
+            }
+        }
+    }
+    groups
+}
+
+fn host(addresses: &[NetworkAddress]) -> Option<String> {
+    addresses
+        .iter()
+        .find_map(|address| parse_tcp(address.as_slice()).map(|((host, _), _)| host))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::genesis_fixture;
+    use diem_types::chain_id::ChainId;
+
+    #[test]
+    fn test_target_groups() {
+        let fixture = genesis_fixture(2, ChainId::test());
+        let validators = ValidatorSetSource::from_genesis(fixture.genesis_path())
+            .validators()
+            .unwrap();
+        let groups = target_groups(&validators, &Encryptor::for_testing(), 9101);
+
+        let validator_groups: Vec<_> = groups
+            .iter()
+            .filter(|group| group.labels["role"] == "validator")
+            .collect();
+        assert_eq!(validator_groups.len(), 2);
+        for group in groups {
+            assert_eq!(group.targets.len(), 1);
+            assert!(group.targets[0].ends_with(":9101"));
+            assert!(!group.labels["namespace"].is_empty());
+        }
+    }
+}
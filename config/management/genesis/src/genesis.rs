@@ -3,7 +3,9 @@
 
 use crate::layout::Layout;
 use diem_global_constants::{OPERATOR_KEY, OWNER_KEY};
-use diem_management::{config::ConfigPath, constants, error::Error, secure_backend::SharedBackend};
+use diem_management::{
+    config::ConfigPath, constants, error::Error, progress, secure_backend::SharedBackend,
+};
 use diem_transaction_builder::stdlib as transaction_builder;
 use diem_types::{
     account_address,
@@ -31,6 +33,10 @@ pub struct Genesis {
     pub path: Option<PathBuf>,
     #[structopt(long)]
     pub layout_path: Option<PathBuf>,
+    //////// 0L ////////
+    /// Refuse registrations that their owner did not sign, see sign-registration
+    #[structopt(long)]
+    pub require_signed_registrations: bool,
 }
 
 impl Genesis {
@@ -45,12 +51,16 @@ impl Genesis {
         ///////// 0L ////////
         // for a decentralized genesis allow the participants to set their own layout file (will not have a central repo providing one).
         // for dev and testnets layouts can be found on genesis repo
+        let mut steps = progress::Progress::new("genesis build", 4);
+        steps.step("reading layout");
         let layout: Layout = match &self.layout_path {
           Some(p) => {
-            println!("Getting genesis validator set from file: {:?}\n", &self.layout_path);
-            let mut file = File::open(p).expect("could not open layout file");
+            progress::detail(&format!("reading genesis validator set from file: {:?}", p));
+            let mut file = File::open(p)
+                .map_err(|e| Error::UnableToReadFile(format!("{:?}", p), e.to_string()))?;
             let mut layout = String::new();
-            file.read_to_string(&mut layout).expect("could not read");
+            file.read_to_string(&mut layout)
+                .map_err(|e| Error::UnableToReadFile(format!("{:?}", p), e.to_string()))?;
             Layout::parse(&layout)
             .map_err(|e| Error::UnableToParse(constants::LAYOUT, e.to_string()))?
           },
@@ -62,6 +72,14 @@ impl Genesis {
         //////// 0L ////////        
         // let diem_root_key = self.diem_root_key(&layout)?;
         // let treasury_compliance_key = self.treasury_compliance_key(&layout)?;
+        steps.step("collecting owner and operator registrations");
+        if self.require_signed_registrations {
+            let config = self.config()?;
+            for owner in layout.owners.iter() {
+                progress::detail(&format!("verifying the signed registration of {}", owner));
+                crate::ceremony::verify_registration(&config, owner)?;
+            }
+        }
         let operator_assignments = self.operator_assignments(&layout)?;
         let operator_registrations = self.operator_registrations(&layout)?;
 
@@ -69,6 +87,7 @@ impl Genesis {
         // Only have an allowlist of stdlib scripts
         let script_policy = None;
 
+        steps.step("encoding genesis transaction");
         let genesis = vm_genesis::encode_genesis_transaction(
             //////// 0L ////////
             // diem_root_key,
@@ -82,7 +101,9 @@ impl Genesis {
             chain_id,
         );
 
+        steps.step("writing genesis blob");
         if let Some(path) = self.path {
+            progress::detail(&format!("writing genesis to {:?}", path));
             let mut file = File::create(path).map_err(|e| {
                 Error::UnexpectedError(format!("Unable to create genesis file: {}", e.to_string()))
             })?;
@@ -93,6 +114,7 @@ impl Genesis {
                 Error::UnexpectedError(format!("Unable to write genesis file: {}", e.to_string()))
             })?;
         }
+        steps.done();
 
         Ok(genesis)
     }
@@ -121,6 +143,7 @@ impl Genesis {
         let mut operator_assignments = Vec::new();
 
         for owner in layout.owners.iter() {
+            progress::detail(&format!("reading owner registration: {}", owner));
             let owner_storage = config.shared_backend_with_namespace(owner.into());
             let owner_key = owner_storage.ed25519_key(OWNER_KEY).ok();
 
@@ -147,8 +170,8 @@ impl Genesis {
             };
 
             let pow = GenesisMiningProof {
-                preimage: owner_storage.string(diem_global_constants::PROOF_OF_WORK_PREIMAGE).unwrap(),
-                proof: owner_storage.string(diem_global_constants::PROOF_OF_WORK_PROOF).unwrap(),
+                preimage: owner_storage.string(diem_global_constants::PROOF_OF_WORK_PREIMAGE)?,
+                proof: owner_storage.string(diem_global_constants::PROOF_OF_WORK_PROOF)?,
                 profile,
             };
 
@@ -178,6 +201,7 @@ impl Genesis {
 
         //////// 0L ////////
         for operator_name in layout.operators.iter() {
+            progress::detail(&format!("reading operator registration: {}", operator_name));
             let operator_storage = config.shared_backend_with_namespace(operator_name.into());
             let operator_key = operator_storage.ed25519_key(OPERATOR_KEY)?;
             let validator_config_tx = operator_storage.transaction(constants::VALIDATOR_CONFIG)?;
@@ -0,0 +1,174 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Where the `StorageHelper` keeps the key material of a genesis: a file on disk, as used by the
+//! ceremonies so far, the memory of the process, for CI, or a Vault.
+
+use diem_config::config::{SecureBackend, Token, VaultConfig};
+use diem_management::{
+    error::Error,
+    secure_backend::{DISK, VAULT},
+};
+use diem_secure_storage::{InMemoryStorage, Namespaced, OnDiskStorage, Storage};
+use diem_temppath::TempPath;
+use std::path::Path;
+
+/// The key store of the participants of a genesis, split in namespaces
+pub trait GenesisStorage {
+    /// The storage of `namespace`
+    fn storage(&self, namespace: String) -> Storage;
+
+    /// The store as a `--*-backend` argument of the genesis tool commands, without a namespace.
+    /// Fails if commands can't open it.
+    fn backend(&self) -> Result<String, Error>;
+
+    /// The file of the store, if on disk
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// A key store file, e.g., `key_store.json`
+pub struct OnDiskGenesisStorage {
+    temppath: TempPath,
+}
+
+impl OnDiskGenesisStorage {
+    pub fn new(temppath: TempPath) -> Self {
+        Self { temppath }
+    }
+}
+
+impl GenesisStorage for OnDiskGenesisStorage {
+    fn storage(&self, namespace: String) -> Storage {
+        let storage = OnDiskStorage::new(self.temppath.path().to_path_buf());
+        Storage::from(Namespaced::new(namespace, Box::new(Storage::from(storage))))
+    }
+
+    fn backend(&self) -> Result<String, Error> {
+        let path = self.temppath.path().to_str().ok_or_else(|| {
+            Error::UnexpectedError(format!("Invalid key store path {:?}", self.temppath.path()))
+        })?;
+        Ok(format!("backend={};path={}", DISK, path))
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(self.temppath.path())
+    }
+}
+
+/// A key store lost with the process, for tests and CI. The commands of the genesis tool run in
+/// their own storage, so only the direct storage operations of the helper are available.
+#[derive(Default)]
+pub struct InMemoryGenesisStorage {
+    storage: InMemoryStorage,
+}
+
+impl InMemoryGenesisStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GenesisStorage for InMemoryGenesisStorage {
+    fn storage(&self, namespace: String) -> Storage {
+        let storage = Storage::from(self.storage.clone());
+        Storage::from(Namespaced::new(namespace, Box::new(storage)))
+    }
+
+    fn backend(&self) -> Result<String, Error> {
+        Err(Error::CommandArgumentError(
+            "an in memory key store can't be opened by the genesis tool commands".into(),
+        ))
+    }
+}
+
+/// A key store in Vault, the namespaces of the genesis replace the one of `config`
+pub struct VaultGenesisStorage {
+    config: VaultConfig,
+}
+
+impl VaultGenesisStorage {
+    pub fn new(config: VaultConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl GenesisStorage for VaultGenesisStorage {
+    fn storage(&self, namespace: String) -> Storage {
+        let mut config = self.config.clone();
+        config.namespace = Some(namespace);
+        Storage::from(&SecureBackend::Vault(config))
+    }
+
+    fn backend(&self) -> Result<String, Error> {
+        // Commands read the token from a file
+        let token = match &self.config.token {
+            Token::FromDisk(path) => path.to_str(),
+            Token::FromConfig(_) => None,
+        }
+        .ok_or_else(|| {
+            Error::CommandArgumentError(
+                "the genesis tool commands need the vault token in a file".into(),
+            )
+        })?;
+        let mut backend = format!(
+            "backend={};server={};token={}",
+            VAULT, self.config.server, token
+        );
+        if let Some(certificate) = &self.config.ca_certificate {
+            let certificate = certificate.to_str().ok_or_else(|| {
+                Error::UnexpectedError(format!("Invalid certificate path {:?}", certificate))
+            })?;
+            backend.push_str(&format!(";ca_certificate={}", certificate));
+        }
+        Ok(backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_secure_storage::KVStorage;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_in_memory_namespaces() {
+        let store = InMemoryGenesisStorage::new();
+        store.storage("alice".into()).set("key", 1u64).unwrap();
+        store.storage("bob".into()).set("key", 2u64).unwrap();
+
+        let value = |namespace: &str| store.storage(namespace.into()).get::<u64>("key");
+        assert_eq!(value("alice").unwrap().value, 1);
+        assert_eq!(value("bob").unwrap().value, 2);
+        assert!(value("carol").is_err());
+        assert!(store.backend().is_err());
+    }
+
+    #[test]
+    fn test_vault_backend() {
+        let config = VaultConfig {
+            namespace: None,
+            server: "http://127.0.0.1:8200".into(),
+            ca_certificate: Some(PathBuf::from("/opt/vault/ca.pem")),
+            token: Token::FromDisk(PathBuf::from("/opt/vault/token")),
+            renew_ttl_secs: None,
+            disable_cas: None,
+            connection_timeout_ms: None,
+            response_timeout_ms: None,
+        };
+        let store = VaultGenesisStorage::new(config.clone());
+        assert_eq!(
+            store.backend().unwrap(),
+            "backend=vault;server=http://127.0.0.1:8200;token=/opt/vault/token;\
+             ca_certificate=/opt/vault/ca.pem"
+        );
+
+        let store = VaultGenesisStorage::new(VaultConfig {
+            token: Token::FromConfig("root".into()),
+            ..config
+        });
+        assert!(store.backend().is_err());
+    }
+}
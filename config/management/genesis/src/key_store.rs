@@ -0,0 +1,114 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Migrates a `key_store.json` between plaintext and encryption at rest, or to a new passphrase.
+//! The node then reads it with the same passphrase set in the `passphrase` of its
+//! `on_disk_storage` backends.
+
+use diem_config::config::Passphrase;
+use diem_global_constants::NODE_HOME;
+use diem_management::error::{from_storage_error, Error};
+use diem_secure_storage::OnDiskStorage;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct KeyStore {
+    /// Path to the key store, defaults to the key_store.json of the node home
+    #[structopt(long)]
+    pub path: Option<PathBuf>,
+    /// Where to read the passphrase to encrypt with: env:NAME, file:PATH or cmd:COMMAND, e.g.
+    /// "cmd:secret-tool lookup service diem" for the OS keyring
+    #[structopt(long, required_unless = "decrypt")]
+    pub passphrase: Option<Passphrase>,
+    /// Where to read the passphrase the key store is encrypted with, if it is
+    #[structopt(long)]
+    pub current_passphrase: Option<Passphrase>,
+    /// Writes the key store back in plaintext
+    #[structopt(long, conflicts_with = "passphrase")]
+    pub decrypt: bool,
+}
+
+impl KeyStore {
+    pub fn execute(self) -> Result<String, Error> {
+        let path = self.path.unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap()
+                .join(NODE_HOME)
+                .join("key_store.json")
+        });
+        if !path.exists() {
+            return Err(Error::CommandArgumentError(format!(
+                "No key store at {}",
+                path.display()
+            )));
+        }
+        let read = |passphrase: Option<Passphrase>| {
+            passphrase
+                .map(|passphrase| passphrase.read_passphrase())
+                .transpose()
+                .map_err(|e| Error::ConfigError(e.to_string()))
+        };
+        let passphrase = if self.decrypt {
+            None
+        } else {
+            read(self.passphrase)?
+        };
+
+        migrate(path.clone(), read(self.current_passphrase)?, passphrase)?;
+        Ok(format!("Key store migrated: {}", path.display()))
+    }
+}
+
+/// Rewrites the key store at `path`, encrypted with `current` if any, with `passphrase` or in
+/// plaintext without one
+pub fn migrate(
+    path: PathBuf,
+    current: Option<String>,
+    passphrase: Option<String>,
+) -> Result<(), Error> {
+    let mut storage = match current {
+        Some(current) => OnDiskStorage::new_encrypted(path, current),
+        None => OnDiskStorage::new(path),
+    };
+    storage.migrate(passphrase).map_err(|e| {
+        from_storage_error("validator", e, |e| {
+            Error::StorageWriteError("validator", "key_store", e)
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_secure_storage::KVStorage;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_migrate() {
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+        let path = path.path().to_path_buf();
+        OnDiskStorage::new(path.clone())
+            .set("owner", "secret".to_string())
+            .unwrap();
+
+        migrate(path.clone(), None, Some("passphrase".into())).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("secret"));
+        let get = |storage: OnDiskStorage| storage.get::<String>("owner").map(|r| r.value);
+        assert_eq!(
+            get(OnDiskStorage::new_encrypted(
+                path.clone(),
+                "passphrase".into()
+            ))
+            .unwrap(),
+            "secret"
+        );
+
+        // A wrong current passphrase leaves the key store as it was
+        assert!(migrate(path.clone(), Some("wrong".into()), None).is_err());
+        migrate(path.clone(), Some("passphrase".into()), None).unwrap();
+        assert_eq!(get(OnDiskStorage::new(path)).unwrap(), "secret");
+    }
+}
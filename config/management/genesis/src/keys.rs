@@ -0,0 +1,435 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Backs up the identity keys of a node held in its `key_store.json` as recovery phrases, one
+//! BIP-39 phrase of 24 words per key, and restores them to a new key store after a disk loss. The
+//! keys of `init` derive from the account mnemonic, but the ones rotated since no longer do, and
+//! only a backup keeps the node the identity it registered with.
+
+use crate::storage_helper::import_vfn_network_key;
+use diem_config::config::Passphrase;
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    PrivateKey,
+};
+use diem_global_constants::{
+    CONSENSUS_KEY, EXECUTION_KEY, FULLNODE_NETWORK_KEY, NODE_HOME, OPERATOR_KEY, OWNER_KEY,
+    VALIDATOR_NETWORK_KEY, VFN_NETWORK_KEY,
+};
+use diem_management::{
+    error::{from_storage_error, Error},
+    progress,
+};
+use diem_secure_storage::{CryptoStorage, Namespaced, OnDiskStorage, Storage};
+use diem_wallet::Mnemonic;
+use std::{
+    convert::TryFrom,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+/// The keys of an identity, with the suffix of the namespace `init` keeps them in
+const IDENTITY_KEYS: [(&str, &str); 7] = [
+    (OWNER_KEY, ""),
+    (OPERATOR_KEY, "-oper"),
+    (VALIDATOR_NETWORK_KEY, "-oper"),
+    (FULLNODE_NETWORK_KEY, "-oper"),
+    (VFN_NETWORK_KEY, "-oper"),
+    (CONSENSUS_KEY, "-oper"),
+    (EXECUTION_KEY, "-oper"),
+];
+
+#[derive(Debug, StructOpt)]
+pub enum KeysCommand {
+    #[structopt(about = "Writes the identity keys of the key store as recovery phrases")]
+    Backup(Backup),
+    #[structopt(about = "Imports the identity keys of recovery phrases in the key store")]
+    Restore(Restore),
+}
+
+impl KeysCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            KeysCommand::Backup(backup) => backup.execute(),
+            KeysCommand::Restore(restore) => restore.execute(),
+        }
+    }
+}
+
+/// The key store and namespace of the identity
+#[derive(Debug, StructOpt)]
+pub struct KeyStoreArgs {
+    /// The namespace of the identity, as given to init
+    #[structopt(long, short)]
+    namespace: String,
+    /// Path to the key store, defaults to the key_store.json of the node home
+    #[structopt(long, parse(from_os_str))]
+    path: Option<PathBuf>,
+    /// Where to read the passphrase the key store is encrypted with, if it is
+    #[structopt(long)]
+    passphrase: Option<Passphrase>,
+}
+
+impl KeyStoreArgs {
+    fn path(&self) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap()
+                .join(NODE_HOME)
+                .join("key_store.json")
+        })
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn open(&self) -> Result<IdentityStore, Error> {
+        let passphrase = self
+            .passphrase
+            .as_ref()
+            .map(|passphrase| passphrase.read_passphrase())
+            .transpose()
+            .map_err(|e| Error::ConfigError(e.to_string()))?;
+        Ok(IdentityStore {
+            path: self.path(),
+            passphrase,
+            namespace: self.namespace.clone(),
+        })
+    }
+}
+
+/// Writes the recovery phrases of the identity keys found in the key store. The phrases hold the
+/// keys in the clear, even those of an encrypted key store.
+#[derive(Debug, StructOpt)]
+pub struct Backup {
+    #[structopt(flatten)]
+    args: KeyStoreArgs,
+    /// The file to write the phrases to, only readable by its owner. They are printed without one
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Prints the phrases to a terminal without --output, where they stay in its scrollback
+    #[structopt(long)]
+    force: bool,
+}
+
+impl Backup {
+    pub fn execute(self) -> Result<String, Error> {
+        let path = self.args.path();
+        if !path.exists() {
+            return Err(Error::CommandArgumentError(format!(
+                "No key store at {}",
+                path.display()
+            )));
+        }
+        if self.output.is_none() && !self.force && atty::is(atty::Stream::Stdout) {
+            return Err(Error::CommandArgumentError(
+                "Refusing to print the recovery phrases to a terminal, write them to a file with \
+                 --output or use --force"
+                    .into(),
+            ));
+        }
+        let store = self.args.open()?;
+        let phrases = store.backup()?;
+        if store.passphrase.is_some() {
+            progress::warn(
+                "the key store is encrypted, but its recovery phrases are not: keep the backup \
+                 offline",
+            );
+        }
+        match self.output {
+            Some(output) => {
+                write_private(&output, &phrases)
+                    .map_err(|e| Error::IO(format!("Unable to write {}", output.display()), e))?;
+                Ok(format!("Recovery phrases written to {}", output.display()))
+            }
+            None => Ok(phrases),
+        }
+    }
+}
+
+/// Writes `contents` to `path`, only readable by its owner
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to a new file
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
+
+/// Imports the keys of a backup in the key store, which is created if lost
+#[derive(Debug, StructOpt)]
+pub struct Restore {
+    #[structopt(flatten)]
+    args: KeyStoreArgs,
+    /// The file of the phrases, as written by keys backup
+    #[structopt(long, parse(from_os_str))]
+    input: PathBuf,
+    /// Replaces the keys of the key store that differ from the backup
+    #[structopt(long)]
+    force: bool,
+}
+
+impl Restore {
+    pub fn execute(self) -> Result<String, Error> {
+        let phrases = fs::read_to_string(&self.input)
+            .map_err(|e| Error::IO(format!("Unable to read {}", self.input.display()), e))?;
+        let restored = self.args.open()?.restore(&phrases, self.force)?;
+        Ok(format!(
+            "Restored {} in {}",
+            restored.join(", "),
+            self.args.path().display()
+        ))
+    }
+}
+
+/// The namespaces of an identity in an on disk key store
+pub struct IdentityStore {
+    path: PathBuf,
+    passphrase: Option<String>,
+    namespace: String,
+}
+
+impl IdentityStore {
+    pub fn new(path: PathBuf, passphrase: Option<String>, namespace: String) -> Self {
+        Self {
+            path,
+            passphrase,
+            namespace,
+        }
+    }
+
+    fn storage(&self, suffix: &str) -> Storage {
+        let storage = match &self.passphrase {
+            Some(passphrase) => OnDiskStorage::new_encrypted(self.path.clone(), passphrase.clone()),
+            None => OnDiskStorage::new(self.path.clone()),
+        };
+        Storage::from(Namespaced::new(
+            format!("{}{}", self.namespace, suffix),
+            Box::new(Storage::from(storage)),
+        ))
+    }
+
+    /// The public key of the identity key `name`, None if the key store doesn't hold it
+    pub fn public_key(&self, name: &'static str) -> Result<Option<Ed25519PublicKey>, Error> {
+        let suffix = IDENTITY_KEYS
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, suffix)| *suffix)
+            .ok_or_else(|| Error::UnexpectedError(format!("{} is not an identity key", name)))?;
+        match self.storage(suffix).get_public_key(name) {
+            Ok(response) => Ok(Some(response.public_key)),
+            Err(diem_secure_storage::Error::KeyNotSet(_)) => Ok(None),
+            Err(e) => Err(from_storage_error("validator", e, |e| {
+                Error::StorageReadError("validator", name, e)
+            })),
+        }
+    }
+
+    /// The phrases of the identity keys in the key store, a `key: phrase` line each. Keys the
+    /// key store doesn't hold, e.g., the owner key of a node initialized out of genesis, are
+    /// left out.
+    pub fn backup(&self) -> Result<String, Error> {
+        let mut lines = Vec::new();
+        for (name, suffix) in IDENTITY_KEYS.iter() {
+            let key = match self.storage(suffix).export_private_key(name) {
+                Ok(key) => key,
+                Err(diem_secure_storage::Error::KeyNotSet(_)) => continue,
+                Err(e) => {
+                    return Err(from_storage_error("validator", e, |e| {
+                        Error::StorageReadError("validator", *name, e)
+                    }))
+                }
+            };
+            let mnemonic = Mnemonic::mnemonic(&key.to_bytes())
+                .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+            lines.push(format!("{}: {}\n", name, mnemonic.to_string()));
+        }
+        if lines.is_empty() {
+            return Err(Error::CommandArgumentError(format!(
+                "The key store has no keys in namespace {}",
+                self.namespace
+            )));
+        }
+        Ok(lines.concat())
+    }
+
+    /// Imports the keys of `phrases` and returns their names. Fails before writing anything if a
+    /// phrase is invalid, or if a key of the key store differs from the backup, unless `force`.
+    pub fn restore(&self, phrases: &str, force: bool) -> Result<Vec<&'static str>, Error> {
+        let mut keys = Vec::new();
+        for line in phrases.lines().filter(|line| !line.trim().is_empty()) {
+            let (name, phrase) = line.split_once(':').ok_or_else(|| {
+                Error::CommandArgumentError(format!("Expected key: phrase, got {}", line))
+            })?;
+            let (name, suffix) = IDENTITY_KEYS
+                .iter()
+                .find(|(key, _)| *key == name.trim())
+                .ok_or_else(|| {
+                    Error::CommandArgumentError(format!("Unknown key {}", name.trim()))
+                })?;
+            let words: Vec<_> = phrase.split_whitespace().collect();
+            let key = Mnemonic::from(&words.join(" "))
+                .map_err(|e| e.to_string())
+                .and_then(|mnemonic| {
+                    Ed25519PrivateKey::try_from(mnemonic.entropy().as_slice())
+                        .map_err(|e| e.to_string())
+                })
+                .map_err(|e| Error::CommandArgumentError(format!("{}: {}", name, e)))?;
+
+            if let Ok(current) = self.storage(suffix).get_public_key(name) {
+                if current.public_key != key.public_key() && !force {
+                    return Err(Error::CommandArgumentError(format!(
+                        "The key store holds another {} key, use --force to replace it",
+                        name
+                    )));
+                }
+            }
+            keys.push((*name, *suffix, key));
+        }
+
+        let mut restored = Vec::new();
+        for (name, suffix, key) in keys {
+            let mut storage = self.storage(suffix);
+            if name == VFN_NETWORK_KEY {
+                // Its peer id goes with it
+                import_vfn_network_key(&mut storage, key)?;
+            } else {
+                storage.import_private_key(name, key).map_err(|e| {
+                    from_storage_error("validator", e, |e| {
+                        Error::StorageWriteError("validator", name, e)
+                    })
+                })?;
+            }
+            restored.push(name);
+        }
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_crypto::Uniform;
+    use diem_temppath::TempPath;
+
+    fn store(path: &TempPath, passphrase: Option<&str>) -> IdentityStore {
+        IdentityStore::new(
+            path.path().to_path_buf(),
+            passphrase.map(String::from),
+            "alice".into(),
+        )
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+        let original = store(&path, Some("passphrase"));
+        let owner = Ed25519PrivateKey::generate_for_testing();
+        let consensus = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+        original
+            .storage("")
+            .import_private_key(OWNER_KEY, owner.clone())
+            .unwrap();
+        original
+            .storage("-oper")
+            .import_private_key(CONSENSUS_KEY, consensus.clone())
+            .unwrap();
+        let phrases = original.backup().unwrap();
+        assert_eq!(phrases.lines().count(), 2);
+        assert!(phrases.lines().all(|line| line.split(' ').count() == 25));
+
+        // The disk is lost
+        let path = TempPath::new();
+        let restored = store(&path, None);
+        assert_eq!(
+            restored.restore(&phrases, false).unwrap(),
+            vec![OWNER_KEY, CONSENSUS_KEY]
+        );
+        assert_eq!(
+            restored.storage("").export_private_key(OWNER_KEY).unwrap(),
+            owner
+        );
+        assert_eq!(
+            restored
+                .storage("-oper")
+                .export_private_key(CONSENSUS_KEY)
+                .unwrap(),
+            consensus
+        );
+        // Restoring the same keys again is harmless
+        restored.restore(&phrases, false).unwrap();
+    }
+
+    #[test]
+    fn test_restore_checks() {
+        let path = TempPath::new();
+        let restored = store(&path, None);
+        let key = Ed25519PrivateKey::generate_for_testing();
+        let phrase = Mnemonic::mnemonic(&key.to_bytes()).unwrap().to_string();
+
+        assert!(restored
+            .restore(&format!("root: {}", phrase), false)
+            .is_err());
+        let words: Vec<_> = phrase.split(' ').skip(1).collect();
+        assert!(restored
+            .restore(&format!("owner: notaword {}", words.join(" ")), false)
+            .is_err());
+
+        let other = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+        restored
+            .storage("")
+            .import_private_key(OWNER_KEY, other)
+            .unwrap();
+        let backup = format!("owner: {}", phrase);
+        assert!(restored.restore(&backup, false).is_err());
+        restored.restore(&backup, true).unwrap();
+        assert_eq!(
+            restored.storage("").export_private_key(OWNER_KEY).unwrap(),
+            key
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_backup_file_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+        store(&path, None)
+            .storage("")
+            .import_private_key(OWNER_KEY, Ed25519PrivateKey::generate_for_testing())
+            .unwrap();
+        let output = TempPath::new();
+        // A backup replacing a world readable file still hides it
+        fs::write(output.path(), "").unwrap();
+        fs::set_permissions(output.path(), fs::Permissions::from_mode(0o644)).unwrap();
+
+        Backup {
+            args: KeyStoreArgs {
+                namespace: "alice".into(),
+                path: Some(path.path().to_path_buf()),
+                passphrase: None,
+            },
+            output: Some(output.path().to_path_buf()),
+            force: false,
+        }
+        .execute()
+        .unwrap();
+        let metadata = fs::metadata(output.path()).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert_eq!(
+            fs::read_to_string(output.path()).unwrap().lines().count(),
+            1
+        );
+    }
+}
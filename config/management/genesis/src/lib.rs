@@ -13,11 +13,27 @@ mod validator_operator;
 mod verify;
 
 //////// 0L ////////
+pub mod ceremony;
+pub mod db;
 pub mod init;
+pub mod key_store;
+pub mod keys;
+pub mod namespace;
+pub mod file_sd;
+pub mod genesis_storage;
+pub mod ol_export;
 pub mod ol_node_files;
+pub mod node;
+pub mod node_config;
+pub mod peers;
 mod ol_mining;
 pub mod seeds;
 pub mod ol_create_repo;
+pub mod registry;
+pub mod rerun;
+pub mod topology;
+pub mod validator_set;
+pub mod verify_genesis;
 
 #[cfg(any(test, feature = "testing"))]
 pub mod config_builder;
@@ -27,6 +43,9 @@ pub mod config_builder;
 pub mod storage_helper;
 #[cfg(any(test, feature = "testing"))]
 pub mod swarm_config;
+//////// 0L ////////
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[cfg(any(test, feature = "testing"))]
 pub use crate::config_builder::test_config;
@@ -3,14 +3,29 @@
 
 #![forbid(unsafe_code)]
 use diem_genesis_tool::command::Command;
+use diem_management::progress::VerbosityArgs;
 use structopt::StructOpt;
 
+//////// 0L ////////
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Tool used for genesis")]
+struct Args {
+    #[structopt(flatten)]
+    verbosity: VerbosityArgs,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
 fn main() {
-    match Command::from_args().execute() {
+    let args = Args::from_args();
+    args.verbosity.init();
+    #[cfg(feature = "tracing")]
+    diem_management::init_tracing();
+    match args.command.execute() {
         Ok(output) => println!("{}", output),
         Err(err) => {
-            println!("Operation unsuccessful: {}", err);
-            std::process::exit(1);
+            println!("Operation unsuccessful [{}]: {}", err.code(), err);
+            std::process::exit(err.exit_code());
         }
     }
 }
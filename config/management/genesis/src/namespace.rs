@@ -0,0 +1,238 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Checks the namespace an operator is about to register under. Owners register under their
+//! account address, and operators under the address followed by `-oper`, so genesis finds the
+//! account of each validator from its namespace. A vanity namespace, or one another participant
+//! already registered to, only fails later, when genesis is built.
+
+use crate::{
+    keys::KeyStoreArgs,
+    registry::{OpenedRegistry, RegistryArgs},
+};
+use diem_crypto::ed25519::Ed25519PublicKey;
+use diem_global_constants::{OPERATOR_KEY, OWNER_KEY};
+use diem_management::error::Error;
+use diem_types::{account_address::AccountAddress, transaction::authenticator::AuthenticationKey};
+use ol_keys::{scheme::KeyScheme, wallet::get_account_from_prompt};
+use structopt::StructOpt;
+
+/// The suffix of the namespace of the operator of an owner
+const OPERATOR_SUFFIX: &str = "-oper";
+
+#[derive(Debug, StructOpt)]
+pub enum NamespaceCommand {
+    #[structopt(about = "Checks a namespace is the owner account, and is free in the registry")]
+    Check(CheckNamespace),
+}
+
+impl NamespaceCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            NamespaceCommand::Check(check) => check.execute(),
+        }
+    }
+}
+
+/// Derives the owner account from the keys of the key store, or from the mnemonic, and checks
+/// the namespace against it and against the registrations of the registry
+#[derive(Debug, StructOpt)]
+pub struct CheckNamespace {
+    #[structopt(flatten)]
+    key_store: KeyStoreArgs,
+    #[structopt(flatten)]
+    registry: RegistryArgs,
+    /// Derives the keys from the mnemonic, prompted for, instead of reading the key store
+    #[structopt(long)]
+    mnemonic: bool,
+}
+
+impl CheckNamespace {
+    pub fn execute(self) -> Result<String, Error> {
+        let registrant = if self.mnemonic {
+            let (_, _, wallet) = get_account_from_prompt();
+            Registrant::from_keys(&KeyScheme::new(&wallet))
+        } else {
+            let store = self.key_store.open()?;
+            let owner_key = store.public_key(OWNER_KEY)?.ok_or_else(|| {
+                Error::CommandArgumentError(format!(
+                    "The key store has no owner key in namespace {}, check the mnemonic instead",
+                    self.key_store.namespace()
+                ))
+            })?;
+            Registrant {
+                owner_key,
+                operator_key: store.public_key(OPERATOR_KEY)?,
+            }
+        };
+
+        let namespace = self.key_store.namespace();
+        let problems = registrant.check(namespace, &self.registry.open()?);
+        if !problems.is_empty() {
+            return Err(Error::CommandArgumentError(problems.join("\n")));
+        }
+        Ok(format!(
+            "Namespace {} is the owner account, and free in the registry",
+            namespace
+        ))
+    }
+}
+
+/// The public keys a participant registers with
+#[derive(Debug)]
+pub struct Registrant {
+    pub owner_key: Ed25519PublicKey,
+    /// None if the key store doesn't hold it, the operator namespace is then left unchecked
+    pub operator_key: Option<Ed25519PublicKey>,
+}
+
+impl Registrant {
+    pub fn from_keys(keys: &KeyScheme) -> Self {
+        Self {
+            owner_key: keys.child_0_owner.get_public(),
+            operator_key: Some(keys.child_1_operator.get_public()),
+        }
+    }
+
+    /// The account of the owner, as genesis derives it
+    pub fn account(&self) -> AccountAddress {
+        AuthenticationKey::ed25519(&self.owner_key).derived_address()
+    }
+
+    /// What keeps `namespace` from being registered to, an empty list if nothing does. Namespaces
+    /// of the registry holding the same keys are collisions, unless they are the ones of
+    /// `namespace`, which is then registered again.
+    pub fn check(&self, namespace: &str, registry: &OpenedRegistry) -> Vec<String> {
+        let account = self.account();
+        let mut problems = vec![];
+        if namespace != account.to_hex() {
+            problems.push(format!(
+                "{} is not the owner account, register under {}",
+                namespace,
+                account.to_hex()
+            ));
+        }
+
+        let operator_namespace = format!("{}{}", namespace, OPERATOR_SUFFIX);
+        for registered in registry.namespaces() {
+            let owner_key = registry.registered_key(registered, OWNER_KEY);
+            if registered == namespace {
+                if owner_key.map_or(false, |key| key != self.owner_key) {
+                    problems.push(format!(
+                        "{} is registered with another owner key",
+                        namespace
+                    ));
+                }
+            } else if registered == &operator_namespace {
+                let operator_key = registry.registered_key(registered, OPERATOR_KEY);
+                match (operator_key, &self.operator_key) {
+                    (Some(registered_key), Some(key)) if &registered_key != key => problems.push(
+                        format!("{} is registered with another operator key", registered),
+                    ),
+                    _ => (),
+                }
+            } else if namespace_account(registered) == Some(account) {
+                problems.push(format!("{} names the owner account too", registered));
+            } else if owner_key.as_ref() == Some(&self.owner_key) {
+                problems.push(format!(
+                    "{} is registered with the owner key too",
+                    registered
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// The account a namespace names, with or without `0x` and the operator suffix
+fn namespace_account(namespace: &str) -> Option<AccountAddress> {
+    let address = namespace.strip_suffix(OPERATOR_SUFFIX).unwrap_or(namespace);
+    if address.starts_with("0x") {
+        AccountAddress::from_hex_literal(address).ok()
+    } else {
+        AccountAddress::from_hex(address).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_management::secure_backend::DISK;
+    use diem_secure_storage::{KVStorage, Namespaced, OnDiskStorage, Storage};
+    use diem_temppath::TempPath;
+    use ol_types::fixtures;
+
+    fn registrant(persona: &str) -> Registrant {
+        Registrant::from_keys(&KeyScheme::new_from_mnemonic(fixtures::get_persona_mnem(
+            persona,
+        )))
+    }
+
+    /// A registry of the owner keys of `registrations`, by namespace
+    fn registry(path: &TempPath, registrations: &[(&str, &Ed25519PublicKey)]) -> OpenedRegistry {
+        for (namespace, key) in registrations {
+            let storage = Storage::from(OnDiskStorage::new(path.path().to_path_buf()));
+            Storage::from(Namespaced::new(*namespace, Box::new(storage)))
+                .set(OWNER_KEY, (*key).clone())
+                .unwrap();
+        }
+        OpenedRegistry::new(
+            format!("backend={};path={}", DISK, path.path().display()),
+            registrations
+                .iter()
+                .map(|(namespace, _)| namespace.to_string())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_check_namespace() {
+        let alice = registrant("alice");
+        let bob = registrant("bob");
+        let namespace = alice.account().to_hex();
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+
+        // Free, then registered again by its owner
+        assert!(alice.check(&namespace, &registry(&path, &[])).is_empty());
+        let registry = registry(&path, &[(namespace.as_str(), &alice.owner_key)]);
+        assert!(alice.check(&namespace, &registry).is_empty());
+        assert_eq!(bob.check(&namespace, &registry).len(), 2);
+
+        assert_eq!(
+            alice.check("alice", &registry),
+            vec![
+                format!(
+                    "alice is not the owner account, register under {}",
+                    namespace
+                ),
+                format!("{} names the owner account too", namespace),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_owner_key_collision() {
+        let alice = registrant("alice");
+        let path = TempPath::new();
+        path.create_as_file().unwrap();
+        let registry = registry(&path, &[("carol", &alice.owner_key)]);
+        assert_eq!(
+            alice.check(&alice.account().to_hex(), &registry),
+            vec!["carol is registered with the owner key too".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_namespace_account() {
+        let account = registrant("alice").account();
+        let upper = account.to_hex().to_uppercase();
+        assert_eq!(namespace_account(&upper), Some(account));
+        assert_eq!(
+            namespace_account(&format!("0x{}-oper", account.to_hex())),
+            Some(account)
+        );
+        assert_eq!(namespace_account("alice"), None);
+    }
+}
@@ -0,0 +1,435 @@
+//////// 0L ////////
+//! `node` subcommands, for setting up a node. `node init` asks for what its flags do not give,
+//! and writes the node files with the `files` command, printing its flags to run it again.
+
+use crate::{
+    command::Command,
+    ol_node_files::{init_keys, NodeBackend, NodeType},
+    storage_helper::StorageHelper,
+};
+use diem_global_constants::{NODE_HOME, OWNER_KEY};
+use diem_management::{error::Error, progress};
+use diem_secure_storage::CryptoStorage;
+use diem_types::chain_id::ChainId;
+use ol_keys::{scheme::KeyScheme, wallet::get_account_from_prompt};
+use rustyline::Editor;
+use std::{fmt::Display, fs, net::IpAddr, path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+
+/// The registry the genesis of the public chains is built from
+const DEFAULT_GITHUB_ORG: &str = "0LNetworkCommunity";
+const DEFAULT_REPO: &str = "genesis-registration";
+
+#[derive(Debug, StructOpt)]
+pub enum NodeCommand {
+    #[structopt(about = "Sets up the keys, genesis and configs of a node, asking for the details")]
+    Init(NodeInit),
+}
+
+impl NodeCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            NodeCommand::Init(init) => init.execute(),
+        }
+    }
+}
+
+/// Asks for the role, chain, data path, backend and registry of the node, unless given as flags,
+/// then initializes its keys if needed and writes its files like `files` does
+#[derive(Debug, StructOpt)]
+pub struct NodeInit {
+    /// Fail on a missing answer that has no default instead of asking for it, e.g. in scripts
+    #[structopt(long)]
+    non_interactive: bool,
+    /// The node to set up: validator, vfn or fullnode
+    #[structopt(long)]
+    role: Option<NodeType>,
+    /// The chain to join: mainnet, rc, devnet, testing, ... or the numeric id of a custom chain
+    #[structopt(long)]
+    chain_id: Option<ChainId>,
+    /// Defaults to ~/.0L
+    #[structopt(long, parse(from_os_str))]
+    data_path: Option<PathBuf>,
+    /// Namespace of the keys in the key store of the data path
+    #[structopt(long)]
+    namespace: Option<String>,
+    /// Secure storage of a validator: disk, vault or in-memory
+    #[structopt(long)]
+    backend: Option<NodeBackend>,
+    /// URL of the Vault server, with --backend vault
+    #[structopt(long)]
+    vault_server: Option<String>,
+    /// File holding the Vault token, with --backend vault
+    #[structopt(long, parse(from_os_str))]
+    vault_token_path: Option<PathBuf>,
+    /// Owner of the GitHub registry genesis is built from
+    #[structopt(long)]
+    github_org: Option<String>,
+    /// The GitHub registry genesis is built from
+    #[structopt(long)]
+    repo: Option<String>,
+    /// Public ip address of the node, of its validator for a VFN
+    #[structopt(long)]
+    ip_address: Option<IpAddr>,
+    /// File holding the mnemonic of the keys, prompted for if the key store does not have them
+    #[structopt(long, parse(from_os_str))]
+    mnemonic_path: Option<PathBuf>,
+    /// Replace the files of a previous run that differ from the generated ones
+    #[structopt(long)]
+    force: bool,
+    /// Leave the data path as it is and print the changes a run would make
+    #[structopt(long)]
+    dry_run: bool,
+}
+
+/// The details of the node, either given or answered
+#[derive(Debug, PartialEq)]
+struct Answers {
+    role: NodeType,
+    chain_id: ChainId,
+    data_path: PathBuf,
+    namespace: String,
+    backend: NodeBackend,
+    vault: Option<(String, PathBuf)>,
+    registry: Option<(String, String)>,
+    ip_address: IpAddr,
+}
+
+impl NodeInit {
+    pub fn execute(self) -> Result<String, Error> {
+        let mut prompt = Prompt::new(self.non_interactive);
+        let answers = self.answers(&mut prompt)?;
+        self.init_keys_if_missing(&answers)?;
+
+        let args = answers.files_args(self.force, self.dry_run);
+        progress::detail(&format!(
+            "running: diem-genesis-tool {}",
+            args.iter()
+                .map(|arg| quote(arg))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+        let command = Command::from_iter_safe(args)
+            .map_err(|e| Error::CommandArgumentError(e.to_string()))?;
+        command.files()?;
+        Ok(format!(
+            "{} files written to {:?}",
+            answers.role.template_section(),
+            answers.data_path
+        ))
+    }
+
+    fn answers(&self, prompt: &mut Prompt) -> Result<Answers, Error> {
+        let role: NodeType = prompt.ask(
+            "role",
+            "Role of the node: validator, vfn or fullnode",
+            self.role,
+            Some("validator"),
+        )?;
+        let chain_id = prompt.ask(
+            "chain-id",
+            "Chain to join: mainnet, rc, devnet, testing, ... or a numeric id",
+            self.chain_id,
+            Some("mainnet"),
+        )?;
+        let default_data_path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(NODE_HOME)
+            .display()
+            .to_string();
+        let data_path = prompt.ask(
+            "data-path",
+            "Data path of the node",
+            self.data_path.clone(),
+            Some(&default_data_path),
+        )?;
+        let namespace = prompt.ask(
+            "namespace",
+            "Namespace of the keys, e.g. the owner account",
+            self.namespace.clone(),
+            None,
+        )?;
+
+        let backend = match role {
+            NodeType::Validator => prompt.ask(
+                "backend",
+                "Secure storage of the validator: disk, vault or in-memory",
+                self.backend,
+                Some("disk"),
+            )?,
+            _ => NodeBackend::Disk,
+        };
+        let vault = match backend {
+            NodeBackend::Vault => Some((
+                prompt.ask(
+                    "vault-server",
+                    "URL of the Vault server",
+                    self.vault_server.clone(),
+                    None,
+                )?,
+                prompt.ask(
+                    "vault-token-path",
+                    "File holding the Vault token",
+                    self.vault_token_path.clone(),
+                    None,
+                )?,
+            )),
+            _ => None,
+        };
+        // A VFN takes the genesis its validator already wrote into the data path
+        let registry = match role {
+            NodeType::ValidatorFullNode => None,
+            _ => Some((
+                prompt.ask(
+                    "github-org",
+                    "Owner of the GitHub genesis registry",
+                    self.github_org.clone(),
+                    Some(DEFAULT_GITHUB_ORG),
+                )?,
+                prompt.ask(
+                    "repo",
+                    "GitHub genesis registry",
+                    self.repo.clone(),
+                    Some(DEFAULT_REPO),
+                )?,
+            )),
+        };
+        let ip_address = prompt.ask(
+            "ip-address",
+            match role {
+                NodeType::ValidatorFullNode => "Ip address the validator is reached at",
+                _ => "Public ip address of the node",
+            },
+            self.ip_address,
+            None,
+        )?;
+
+        Ok(Answers {
+            role,
+            chain_id,
+            data_path,
+            namespace,
+            backend,
+            vault,
+            registry,
+            ip_address,
+        })
+    }
+
+    /// Initializes the keys of the namespace from the mnemonic, unless the key store has them
+    fn init_keys_if_missing(&self, answers: &Answers) -> Result<(), Error> {
+        let initialized = StorageHelper::get_with_path(answers.data_path.clone())
+            .storage(answers.namespace.clone())
+            .get_public_key(OWNER_KEY)
+            .is_ok();
+        if initialized {
+            return Ok(());
+        }
+        if answers.role == NodeType::ValidatorFullNode {
+            return Err(Error::CommandArgumentError(format!(
+                "a VFN uses the keys of its validator, copy its key_store.json and genesis.blob \
+                 into {:?}",
+                answers.data_path
+            )));
+        }
+        if self.dry_run {
+            return Err(Error::CommandArgumentError(format!(
+                "--dry-run needs the keys of {} in the key store, run init-keys first",
+                answers.namespace
+            )));
+        }
+        let keys = match &self.mnemonic_path {
+            Some(path) => {
+                let mnemonic = fs::read_to_string(path)
+                    .map_err(|e| Error::IO(path.display().to_string(), e))?;
+                KeyScheme::new_from_mnemonic(mnemonic.trim().to_string())
+            }
+            None if self.non_interactive => {
+                return Err(Error::CommandArgumentError(format!(
+                    "the key store has no keys for {}, --mnemonic-path is missing",
+                    answers.namespace
+                )))
+            }
+            None => KeyScheme::new(&get_account_from_prompt().2),
+        };
+        init_keys(&answers.data_path, &answers.namespace, keys, false)
+    }
+}
+
+impl Answers {
+    /// The arguments of the `files` command writing the files of the node
+    fn files_args(&self, force: bool, dry_run: bool) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "diem-genesis-tool".into(),
+            "files".into(),
+            "--validator-backend".into(),
+            format!(
+                "backend=disk;path={};namespace={}",
+                self.data_path.join("key_store.json").display(),
+                self.namespace
+            ),
+            "--namespace".into(),
+            self.namespace.clone(),
+            "--chain-id".into(),
+            self.chain_id.to_string(),
+            "--data-path".into(),
+            self.data_path.display().to_string(),
+            "--val-ip-address".into(),
+            self.ip_address.to_string(),
+        ];
+        if self.role == NodeType::ValidatorFullNode {
+            args.push("--vfn".into());
+        }
+        if let Some((org, repo)) = &self.registry {
+            args.extend(vec!["--github-org".into(), org.clone()]);
+            args.extend(vec!["--repo".into(), repo.clone()]);
+        }
+        if self.backend != NodeBackend::Disk {
+            args.extend(vec!["--backend".into(), self.backend.to_string()]);
+        }
+        if let Some((server, token_path)) = &self.vault {
+            args.extend(vec!["--vault-server".into(), server.clone()]);
+            args.extend(vec![
+                "--vault-token-path".into(),
+                token_path.display().to_string(),
+            ]);
+        }
+        if force {
+            args.push("--force".into());
+        }
+        if dry_run {
+            args.push("--dry-run".into());
+        }
+        args
+    }
+}
+
+/// Asks for the answers missing from the flags, on the terminal unless non interactive
+struct Prompt {
+    editor: Option<Editor<()>>,
+}
+
+impl Prompt {
+    fn new(non_interactive: bool) -> Self {
+        Self {
+            editor: if non_interactive {
+                None
+            } else {
+                Some(Editor::<()>::new())
+            },
+        }
+    }
+
+    /// The given value, or the answer to `question`. An empty answer takes the default, non
+    /// interactively the default is taken and `--flag` is missing without one.
+    fn ask<T>(
+        &mut self,
+        flag: &str,
+        question: &str,
+        value: Option<T>,
+        default: Option<&str>,
+    ) -> Result<T, Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        if let Some(value) = value {
+            return Ok(value);
+        }
+        let parse = |answer: &str| {
+            answer.parse::<T>().map_err(|e| {
+                Error::CommandArgumentError(format!("invalid --{} {}: {}", flag, answer, e))
+            })
+        };
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            None => {
+                return match default {
+                    Some(default) => parse(default),
+                    None => Err(Error::CommandArgumentError(format!(
+                        "--{} is missing",
+                        flag
+                    ))),
+                }
+            }
+        };
+        let line = match default {
+            Some(default) => format!("{} [{}]: ", question, default),
+            None => format!("{}: ", question),
+        };
+        loop {
+            let answer = editor
+                .readline(&line)
+                .map_err(|e| Error::UnexpectedError(format!("Unable to read the answer: {}", e)))?;
+            let answer = match (answer.trim(), default) {
+                ("", Some(default)) => default,
+                ("", None) => continue,
+                (answer, _) => answer,
+            };
+            match parse(answer) {
+                Ok(value) => return Ok(value),
+                Err(e) => progress::warn(&e.to_string()),
+            }
+        }
+    }
+}
+
+/// Quotes an argument of the printed command line for the shell
+fn quote(arg: &str) -> String {
+    if arg.contains(|c: char| c == ';' || c.is_whitespace()) {
+        format!("'{}'", arg)
+    } else {
+        arg.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn non_interactive(args: &str) -> Result<Answers, Error> {
+        let init =
+            NodeInit::from_iter_safe(format!("init --non-interactive {}", args).split_whitespace())
+                .unwrap();
+        init.answers(&mut Prompt::new(true))
+    }
+
+    #[test]
+    fn test_non_interactive_answers() {
+        let answers =
+            non_interactive("--namespace alice --data-path /tmp/node --ip-address 1.2.3.4")
+                .unwrap();
+        assert_eq!(answers.role, NodeType::Validator);
+        assert_eq!(answers.chain_id, ChainId::from_str("mainnet").unwrap());
+        assert_eq!(answers.backend, NodeBackend::Disk);
+        assert_eq!(
+            answers.registry,
+            Some((DEFAULT_GITHUB_ORG.to_string(), DEFAULT_REPO.to_string()))
+        );
+
+        // Without a default the flag is needed
+        assert!(non_interactive("--data-path /tmp/node --ip-address 1.2.3.4").is_err());
+        assert!(non_interactive(
+            "--namespace alice --ip-address 1.2.3.4 --backend vault --vault-server http://vault"
+        )
+        .is_err());
+
+        let answers = non_interactive("--namespace alice --role vfn --ip-address 1.2.3.4").unwrap();
+        assert_eq!(answers.registry, None);
+    }
+
+    #[test]
+    fn test_files_args() {
+        let answers = non_interactive(
+            "--namespace alice --data-path /tmp/node --ip-address 1.2.3.4 --backend vault \
+             --vault-server http://vault --vault-token-path /tmp/token",
+        )
+        .unwrap();
+        let args = answers.files_args(false, true);
+        assert!(args.contains(&"--dry-run".to_string()));
+        match Command::from_iter_safe(args).unwrap() {
+            Command::Files(_) => (),
+            command => panic!("unexpected command {:?}", command),
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! `node-config` subcommands, for inspecting generated node configs
+
+use crate::verify::compute_genesis;
+use diem_config::{
+    compat,
+    config::{NodeConfig, PersistableConfig, RootPath, WaypointConfig},
+    convert::convert_from_diem,
+    diff::diff_configs,
+    doctor::{self, DoctorReport},
+    explain::{explain, value_at},
+};
+use diem_global_constants::GENESIS_WAYPOINT;
+use diem_management::{error::Error, progress};
+use diem_secure_storage::{KVStorage, Storage};
+use diem_temppath::TempPath;
+use diem_types::waypoint::Waypoint;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum NodeConfigCommand {
+    #[structopt(about = "Checks that a node can start with the given config on this host")]
+    Doctor(Doctor),
+    #[structopt(about = "Stores the config serialization goldens of this release")]
+    GenerateGoldens(GenerateGoldens),
+    #[structopt(about = "Converts a node config written for upstream Diem")]
+    Convert(Convert),
+    #[structopt(about = "Shows the fields that differ between two node configs")]
+    Diff(Diff),
+    #[structopt(about = "Prints the documentation of a node config field")]
+    Explain(Explain),
+}
+
+impl NodeConfigCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            NodeConfigCommand::Doctor(doctor) => doctor.execute(),
+            NodeConfigCommand::GenerateGoldens(goldens) => goldens.execute(),
+            NodeConfigCommand::Convert(convert) => convert.execute(),
+            NodeConfigCommand::Diff(diff) => diff.execute(),
+            NodeConfigCommand::Explain(explain) => explain.execute(),
+        }
+    }
+}
+
+/// Runs the config validations and environment checks, and prints a pass/warn/fail report
+#[derive(Debug, StructOpt)]
+pub struct Doctor {
+    /// Path to the node config, e.g. validator.node.yaml
+    #[structopt(parse(from_os_str))]
+    config: PathBuf,
+    /// Warn if the data directory has less free space than this, in GiB
+    #[structopt(long, default_value = "10")]
+    min_free_space_gb: u64,
+}
+
+impl Doctor {
+    pub fn execute(self) -> Result<String, Error> {
+        let report = self.report();
+        if report.has_failures() {
+            Err(Error::ConfigInvalid {
+                path: self.config.display().to_string(),
+                reason: format!("self-check failed\n{}", report),
+            })
+        } else {
+            Ok(report.to_string())
+        }
+    }
+
+    pub fn report(&self) -> DoctorReport {
+        let mut steps = progress::Progress::new("node-config doctor", 3);
+
+        steps.step("validating config");
+        let mut report = DoctorReport::default();
+        let config = match NodeConfig::load(&self.config) {
+            Ok(config) => {
+                report.pass("config", format!("{} is valid", self.config.display()));
+                config
+            }
+            Err(e) => {
+                report.fail(
+                    "config",
+                    e.to_string(),
+                    "fix the reported field, or regenerate the file with the `files` command",
+                );
+                return report;
+            }
+        };
+
+        steps.step("checking environment");
+        let environment =
+            doctor::check_environment(&config, self.min_free_space_gb * 1024 * 1024 * 1024);
+        let waypoint_readable = environment
+            .checks
+            .iter()
+            .all(|c| c.name != "waypoint" || c.status != doctor::CheckStatus::Fail);
+        report.checks.extend(environment.checks);
+
+        steps.step("checking genesis and waypoint");
+        if waypoint_readable {
+            self.check_genesis_waypoint(&mut report, &config);
+        }
+        steps.done();
+        report
+    }
+
+    /// Recomputes the waypoint of the genesis blob and compares it with the configured one
+    fn check_genesis_waypoint(&self, report: &mut DoctorReport, config: &NodeConfig) {
+        const NAME: &str = "genesis waypoint";
+        let location = &config.execution.genesis_file_location;
+        if location.as_os_str().is_empty() {
+            report.warn(
+                NAME,
+                "no genesis file configured",
+                "set execution.genesis_file_location to the genesis.blob of the chain",
+            );
+            return;
+        }
+
+        let configured = match configured_genesis_waypoint(&config.base.waypoint) {
+            Ok(Some(waypoint)) => waypoint,
+            Ok(None) => {
+                report.warn(NAME, "no waypoint configured", "set base.waypoint");
+                return;
+            }
+            Err(e) => {
+                report.fail(NAME, e, "fix base.waypoint");
+                return;
+            }
+        };
+
+        let genesis_path = RootPath::new(&self.config).full_path(location);
+        let db_path = TempPath::new();
+        let computed = match compute_genesis(&genesis_path, db_path.path()) {
+            Ok((_, waypoint)) => waypoint,
+            Err(e) => {
+                report.fail(
+                    NAME,
+                    format!(
+                        "unable to compute the waypoint of {}: {}",
+                        genesis_path.display(),
+                        e
+                    ),
+                    "re-download the genesis blob of the chain",
+                );
+                return;
+            }
+        };
+
+        if computed == configured {
+            report.pass(NAME, format!("{} matches the genesis blob", computed));
+        } else if configured.version() > 0 {
+            report.warn(
+                NAME,
+                format!("configured waypoint {} is past genesis", configured),
+                "nothing to do if the node was restored from a later waypoint",
+            );
+        } else {
+            let mismatch = Error::WaypointMismatch {
+                expected: computed,
+                actual: configured,
+            };
+            report.fail(
+                NAME,
+                mismatch.to_string(),
+                "the genesis blob and key store belong to different chains, re-run `files`",
+            );
+        }
+    }
+}
+
+/// Writes the serialization goldens used by the diem-config compat test, run at release time
+#[derive(Debug, StructOpt)]
+pub struct GenerateGoldens {
+    /// The compat directory of diem-config
+    #[structopt(long, parse(from_os_str), default_value = "config/tests/compat")]
+    compat_dir: PathBuf,
+}
+
+impl GenerateGoldens {
+    pub fn execute(self) -> Result<String, Error> {
+        let dir = compat::write_goldens(&self.compat_dir)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write goldens: {}", e)))?;
+        Ok(format!("Goldens written to {}", dir.display()))
+    }
+}
+
+/// Maps an upstream Diem node config into this format, and reports the fields that could not be
+/// mapped. The converted config is written to `output`; review the report before using it.
+#[derive(Debug, StructOpt)]
+pub struct Convert {
+    /// The upstream Diem node config
+    #[structopt(long, parse(from_os_str))]
+    from_diem: PathBuf,
+    /// Where to write the converted config
+    #[structopt(long, parse(from_os_str))]
+    output: PathBuf,
+}
+
+impl Convert {
+    pub fn execute(self) -> Result<String, Error> {
+        let path = self.from_diem.display().to_string();
+        let yaml = fs::read_to_string(&self.from_diem).map_err(|e| Error::IO(path.clone(), e))?;
+        let conversion = convert_from_diem(&yaml).map_err(|e| Error::ConfigInvalid {
+            path,
+            reason: e.to_string(),
+        })?;
+        conversion
+            .config
+            .save_config(&self.output)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+
+        let mut report = format!("Converted config written to {}", self.output.display());
+        for change in &conversion.changes {
+            report.push_str(&format!("\n  converted: {}", change));
+        }
+        for field in &conversion.unmapped {
+            report.push_str(&format!("\n  dropped: {}", field));
+        }
+        Ok(report)
+    }
+}
+
+/// Compares two node configs field by field, e.g. the configs written by two releases. The order
+/// of the fields does not matter, and a field left out is the same as its default.
+#[derive(Debug, StructOpt)]
+pub struct Diff {
+    #[structopt(parse(from_os_str))]
+    before: PathBuf,
+    #[structopt(parse(from_os_str))]
+    after: PathBuf,
+}
+
+impl Diff {
+    pub fn execute(self) -> Result<String, Error> {
+        let changes = diff_configs(&load(&self.before)?, &load(&self.after)?)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to compare the configs: {}", e)))?;
+        if changes.is_empty() {
+            return Ok("The configs are the same".into());
+        }
+        let mut report = format!(
+            "{} fields differ from {} to {}",
+            changes.len(),
+            self.before.display(),
+            self.after.display()
+        );
+        for change in &changes {
+            report.push_str(&format!("\n  {}", change));
+        }
+        Ok(report)
+    }
+}
+
+/// Prints the type, documentation and default of a field, e.g. `storage.prune_window`, and the
+/// fields of its type. An index selects an element of a list, e.g. `full_node_networks[0]`.
+#[derive(Debug, StructOpt)]
+pub struct Explain {
+    /// Path of the field, the whole config if empty
+    #[structopt(default_value = "")]
+    field: String,
+    /// Also print the value of the field in this node config
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+}
+
+impl Explain {
+    pub fn execute(self) -> Result<String, Error> {
+        let explanation =
+            explain(&self.field).map_err(|e| Error::CommandArgumentError(e.to_string()))?;
+        let mut report = explanation.to_string();
+        if let Some(path) = &self.config {
+            let value = value_at(&load(path)?, &self.field)
+                .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+            report.push_str(&format!(
+                "  in {}: {}\n",
+                path.display(),
+                value.as_deref().unwrap_or("(not set)")
+            ));
+        }
+        Ok(report.trim_end().to_string())
+    }
+}
+
+/// Reads a node config as written, without the files it refers to
+fn load(path: &Path) -> Result<NodeConfig, Error> {
+    NodeConfig::load_config(path).map_err(|e| Error::ConfigInvalid {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })
+}
+
+/// Reads the genesis waypoint without panicking, unlike `WaypointConfig::genesis_waypoint`
+fn configured_genesis_waypoint(waypoint: &WaypointConfig) -> Result<Option<Waypoint>, String> {
+    match waypoint {
+        WaypointConfig::FromConfig(waypoint) => Ok(Some(*waypoint)),
+        WaypointConfig::FromFile(path) => {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+            Waypoint::from_str(content.trim())
+                .map(Some)
+                .map_err(|e| format!("unable to parse {}: {}", path.display(), e))
+        }
+        WaypointConfig::FromStorage(backend) => {
+            let storage: Storage = backend.into();
+            storage
+                .get::<Waypoint>(GENESIS_WAYPOINT)
+                .map(|response| Some(response.value))
+                .map_err(|e| format!("unable to read {}: {}", GENESIS_WAYPOINT, e))
+        }
+        WaypointConfig::FromRpc {
+            genesis_waypoint, ..
+        } => Ok(Some(*genesis_waypoint)),
+        WaypointConfig::None => Ok(None),
+    }
+}
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_github_client::Client;
-use diem_management::{config::ConfigPath, error::Error, secure_backend::SharedBackend};
+use diem_management::{
+    config::ConfigPath, error::Error, secure_backend::SharedBackend, trace_span,
+};
 use std::{process::exit, path::PathBuf, fs::File};
 use structopt::StructOpt;
 use std::io::prelude::*;
@@ -63,12 +65,14 @@ impl CreateGenesisRepo {
 
                   let repo_file_path = format!("genesis/{}", p.file_name().unwrap().to_str().unwrap());
 
+                  trace_span!("github.put", path = %repo_file_path);
                   github.put(&repo_file_path, &base64_encoded).expect("could not put file in github repo");
 
                   return Ok(format!("published file to genesis repo at {:?}", &repo_file_path));
                 }
                 // Make a pull request of the the forked repo, back to the genesis coordination repository.
                 if let Some(user) = self.pull_request_user {
+                    trace_span!("github.pull_request", user = %user);
                     match github.make_genesis_pull_request(&config.repository_owner, &config.repository, &user) {
                         Ok(_) => Ok("created pull request to genesis repo".to_string()),
                         Err(e) => Err(Error::StorageWriteError(
@@ -78,6 +82,7 @@ impl CreateGenesisRepo {
                         )),
                     }
                 } else if let Some(user) = self.delete_repo_user{
+                    trace_span!("github.delete_repo", user = %user);
                     match github.delete_own_repo(&user, &config.repository) {
                         Ok(_) => Ok("created pull request to genesis repo".to_string()),
                         Err(e) => Err(Error::StorageWriteError(
@@ -88,6 +93,7 @@ impl CreateGenesisRepo {
                     }
                 } else {
                 // Fork the genesis coordination repo into a personal repo
+                    trace_span!("github.fork_repo");
                     match github.fork_genesis_repo(&self.repo_owner.unwrap(), &self.repo_name.as_ref().unwrap()) {
                         Ok(_) => Ok(format!("Created new repo {}", &self.repo_name.unwrap())),
                         Err(e) => Err(Error::StorageWriteError(
@@ -0,0 +1,779 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Exports of the files written by `ol_node_files`, for deployments that do not read them from
+//! the host.
+//!
+//! The node configs refer to the key store and genesis by their absolute path in the data
+//! directory, so the exported files have to be mounted at the same path in the container.
+
+use crate::ol_node_files::{NodeType, NODE_TYPES};
+use anyhow::{bail, Result};
+use diem_config::{
+    config::{
+        Identity, NodeConfig, PeerRole, PersistableConfig, SecureBackend, WaypointConfig,
+        METRICS_PASSWORD_FILE,
+    },
+    doctor::listen_addresses,
+    redact::contains_secrets,
+};
+use diem_management::progress;
+use diem_types::{
+    network_address::{parse_tcp, NetworkAddress},
+    PeerId,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const KEY_STORE_FILE: &str = "key_store.json";
+pub const K8S_FILE: &str = "k8s.yaml";
+pub const DOCKER_COMPOSE_FILE: &str = "docker-compose.yaml";
+/// Read by docker-compose for the variables of the compose file
+pub const DOCKER_ENV_FILE: &str = ".env";
+pub const ANSIBLE_FILE: &str = "inventory.yaml";
+pub const TERRAFORM_FILE: &str = "endpoints.tfvars.json";
+
+const NODE_BINARY: &str = "/opt/diem/bin/diem-node";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Export {
+    /// A ConfigMap with the node configs and a Secret with the key material
+    K8s,
+    /// A docker-compose file running the nodes, with its env file
+    Docker,
+    /// An Ansible inventory of the nodes and their seed peers
+    Ansible,
+    /// The endpoints and seed peers of the nodes as Terraform variables
+    Terraform,
+    /// A systemd unit per node
+    Systemd,
+}
+
+impl FromStr for Export {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "k8s" => Ok(Export::K8s),
+            "docker" => Ok(Export::Docker),
+            "ansible" => Ok(Export::Ansible),
+            "terraform" => Ok(Export::Terraform),
+            "systemd" => Ok(Export::Systemd),
+            _ => bail!(
+                "Unknown export {}, expected k8s, docker, ansible, terraform or systemd",
+                s
+            ),
+        }
+    }
+}
+
+/// Writes the export of the node files in `output_dir` next to them
+pub fn export(output_dir: &Path, namespace: &str, export: Export) -> Result<()> {
+    match export {
+        Export::K8s => write(
+            &output_dir.join(K8S_FILE),
+            k8s_manifests(output_dir, namespace)?,
+        ),
+        Export::Docker => {
+            write(
+                &output_dir.join(DOCKER_COMPOSE_FILE),
+                docker_compose(output_dir)?,
+            )?;
+            write(&output_dir.join(DOCKER_ENV_FILE), docker_env())
+        }
+        Export::Ansible => write(
+            &output_dir.join(ANSIBLE_FILE),
+            ansible_inventory(output_dir, namespace)?,
+        ),
+        Export::Terraform => write(
+            &output_dir.join(TERRAFORM_FILE),
+            terraform_variables(output_dir)?,
+        ),
+        Export::Systemd => {
+            for (node_type, path, config) in node_configs(output_dir)? {
+                write(
+                    &output_dir.join(systemd_unit_file(node_type)),
+                    systemd_unit(node_type, &config, &path),
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// The node configs written in `output_dir`, with their path
+fn node_configs(output_dir: &Path) -> Result<Vec<(NodeType, PathBuf, NodeConfig)>> {
+    let mut configs = vec![];
+    for node_type in NODE_TYPES {
+        let path = output_dir.join(node_type.file_name());
+        if path.exists() {
+            let config = NodeConfig::load_config(&path)?;
+            configs.push((*node_type, path, config));
+        }
+    }
+    Ok(configs)
+}
+
+fn write(path: &Path, contents: String) -> Result<()> {
+    fs::write(path, contents)?;
+    progress::report(
+        progress::Verbosity::Normal,
+        &format!("export saved to: {:?}", path),
+    );
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: Metadata,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    secret_type: Option<&'static str>,
+    data: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    name: String,
+    labels: BTreeMap<&'static str, String>,
+}
+
+impl Manifest {
+    fn new(kind: &'static str, name: String, namespace: &str) -> Self {
+        let mut labels = BTreeMap::new();
+        labels.insert("app.kubernetes.io/name", "0l-node".to_string());
+        labels.insert("app.kubernetes.io/instance", resource_name(namespace));
+        Self {
+            api_version: "v1",
+            kind,
+            metadata: Metadata { name, labels },
+            secret_type: if kind == "Secret" {
+                Some("Opaque")
+            } else {
+                None
+            },
+            data: BTreeMap::new(),
+        }
+    }
+}
+
+/// A ConfigMap with the node configs and a Secret with the key store and metrics password, as one
+/// multi-document yaml.
+/// Node configs that embed key material (e.g., the public network identity of the VFN) go to the
+/// Secret as well, so the ConfigMap never holds secrets. Secret data is only base64 encoded; it
+/// is up to the cluster to encrypt Secrets at rest.
+///
+/// The genesis blob is not embedded, as it may exceed the size limit of both resources. The
+/// configs reference it at its path in the data directory, and the key store holds the genesis
+/// waypoint.
+pub fn k8s_manifests(output_dir: &Path, namespace: &str) -> Result<String> {
+    let (config_map, secret) = k8s_resources(output_dir, namespace)?;
+    Ok(format!(
+        "{}{}",
+        serde_yaml::to_string(&config_map)?,
+        serde_yaml::to_string(&secret)?
+    ))
+}
+
+fn k8s_resources(output_dir: &Path, namespace: &str) -> Result<(Manifest, Manifest)> {
+    let name = resource_name(namespace);
+    let mut config_map = Manifest::new("ConfigMap", format!("{}-node-config", name), namespace);
+    let mut secret = Manifest::new("Secret", format!("{}-keys", name), namespace);
+
+    for node_type in NODE_TYPES {
+        let file = node_type.file_name();
+        let path = output_dir.join(file);
+        if !path.exists() {
+            continue;
+        }
+        let contents = fs::read_to_string(&path)?;
+        if contains_secrets(&serde_yaml::from_str(&contents)?) {
+            secret
+                .data
+                .insert(file.to_string(), base64::encode(&contents));
+        } else {
+            config_map.data.insert(file.to_string(), contents);
+        }
+    }
+    let key_store = fs::read(output_dir.join(KEY_STORE_FILE))?;
+    secret
+        .data
+        .insert(KEY_STORE_FILE.to_string(), base64::encode(key_store));
+    let metrics_password = output_dir.join(METRICS_PASSWORD_FILE);
+    if metrics_password.exists() {
+        secret.data.insert(
+            METRICS_PASSWORD_FILE.to_string(),
+            base64::encode(fs::read(metrics_password)?),
+        );
+    }
+    Ok((config_map, secret))
+}
+
+#[derive(Serialize)]
+struct Compose {
+    version: &'static str,
+    services: BTreeMap<&'static str, Service>,
+}
+
+#[derive(Serialize)]
+struct Service {
+    image: &'static str,
+    command: Vec<String>,
+    volumes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ports: Vec<String>,
+}
+
+/// A docker-compose file with a service per node config. Everything the container needs is
+/// derived from the configs, so the wiring cannot drift from them:
+/// * the data directory and any key store outside of it are bind mounted at the path the configs
+///   refer to them by, as the node resolves them inside the container
+/// * the ports of all listen addresses that are reachable from outside of the container are
+///   published on the host, shifted by the `host_port_offset` of the node type, as the nodes
+///   listen on the same ports by default and share the host
+///
+/// The image tag is left to the env file.
+pub fn docker_compose(output_dir: &Path) -> Result<String> {
+    let mut services = BTreeMap::new();
+    let mut host_ports = BTreeMap::new();
+    for (node_type, path, config) in node_configs(output_dir)? {
+        let name = service_name(node_type);
+        let ports = published_ports(node_type, &config)?;
+        for host_port in ports.keys() {
+            if let Some(other) = host_ports.insert(*host_port, name) {
+                bail!(
+                    "Services {} and {} both publish host port {}, give their listeners \
+                     distinct ports",
+                    other,
+                    name,
+                    host_port
+                );
+            }
+        }
+        services.insert(name, docker_service(&config, &path, &ports));
+    }
+    Ok(serde_yaml::to_string(&Compose {
+        version: "3.8",
+        services,
+    })?)
+}
+
+fn service_name(node_type: NodeType) -> &'static str {
+    match node_type {
+        NodeType::Validator => "validator",
+        NodeType::ValidatorFullNode => "vfn",
+        NodeType::PublicFullNode => "fullnode",
+    }
+}
+
+/// What the ports of a node type are shifted by on the host
+fn host_port_offset(node_type: NodeType) -> u16 {
+    match node_type {
+        NodeType::Validator => 0,
+        NodeType::ValidatorFullNode => 10_000,
+        NodeType::PublicFullNode => 20_000,
+    }
+}
+
+/// The host ports the exposed ports of the config are published on, with the container port
+fn published_ports(node_type: NodeType, config: &NodeConfig) -> Result<BTreeMap<u16, u16>> {
+    let offset = host_port_offset(node_type);
+    let mut ports = BTreeMap::new();
+    for port in exposed_ports(config).keys() {
+        match port.checked_add(offset) {
+            Some(host_port) => ports.insert(host_port, *port),
+            None => bail!(
+                "Port {} of the {} service is out of range on the host once shifted by {}",
+                port,
+                service_name(node_type),
+                offset
+            ),
+        };
+    }
+    Ok(ports)
+}
+
+fn docker_service(config: &NodeConfig, config_path: &Path, ports: &BTreeMap<u16, u16>) -> Service {
+    let data_dir = config.data_dir();
+    let mut mounts = vec![data_dir.to_path_buf()];
+    mounts.extend(
+        key_store_paths(config)
+            .into_iter()
+            .filter(|path| !path.starts_with(data_dir)),
+    );
+    if !config_path.starts_with(data_dir) {
+        mounts.push(config_path.to_path_buf());
+    }
+
+    Service {
+        image: "diem/validator:${IMAGE_TAG:?set IMAGE_TAG in .env}",
+        command: vec![
+            NODE_BINARY.to_string(),
+            "--config".to_string(),
+            config_path.display().to_string(),
+        ],
+        volumes: mounts
+            .iter()
+            .map(|path| format!("{0}:{0}", path.display()))
+            .collect(),
+        ports: ports
+            .iter()
+            .map(|(host_port, port)| format!("{}:{}", host_port, port))
+            .collect(),
+    }
+}
+
+/// The ports of the listen addresses reachable from other hosts, with the config fields listening
+/// on them
+fn exposed_ports(config: &NodeConfig) -> BTreeMap<u16, Vec<String>> {
+    let mut ports = BTreeMap::new();
+    for (name, address) in listen_addresses(config) {
+        if !address.ip().is_loopback() {
+            ports
+                .entry(address.port())
+                .or_insert_with(Vec::new)
+                .push(name);
+        }
+    }
+    ports
+}
+
+/// The on disk key stores the config reads from
+fn key_store_paths(config: &NodeConfig) -> BTreeSet<PathBuf> {
+    let mut backends = vec![
+        &config.execution.backend,
+        &config.consensus.safety_rules.backend,
+    ];
+    if let WaypointConfig::FromStorage(backend) = &config.base.waypoint {
+        backends.push(backend);
+    }
+    for network in config
+        .validator_network
+        .iter()
+        .chain(config.full_node_networks.iter())
+    {
+        if let Identity::FromStorage(identity) = &network.identity {
+            backends.push(&identity.backend);
+        }
+        backends.extend(network.network_address_key_backend.as_ref());
+    }
+    backends
+        .into_iter()
+        .filter_map(|backend| match backend {
+            SecureBackend::OnDiskStorage(storage) => Some(storage.path()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn docker_env() -> String {
+    "# The tag of the diem/validator image to run, e.g., the release the configs were generated \
+     with\nIMAGE_TAG=\n"
+        .to_string()
+}
+
+/// Used when RocksDB may open any number of files
+const FD_LIMIT_UNBOUNDED: u64 = 1_048_576;
+/// Descriptors for everything besides RocksDB files and connections, e.g., logs and listeners
+const FD_LIMIT_HEADROOM: u64 = 4096;
+
+pub fn systemd_unit_file(node_type: NodeType) -> String {
+    format!("0l-{}.service", service_name(node_type))
+}
+
+/// A unit running the node with its config. The node may only write to its data directory and the
+/// key stores outside of it, and may hold as many descriptors as `fd_limit` of the config. The
+/// ports to open in the firewall of the host head the unit.
+pub fn systemd_unit(node_type: NodeType, config: &NodeConfig, config_path: &Path) -> String {
+    let data_dir = config.data_dir();
+    let mut writable = vec![data_dir.to_path_buf()];
+    writable.extend(
+        key_store_paths(config)
+            .into_iter()
+            .filter(|path| !path.starts_with(data_dir)),
+    );
+    let writable: Vec<String> = writable
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+
+    let ports: Vec<String> = exposed_ports(config)
+        .into_iter()
+        .map(|(port, names)| format!("# {}/tcp: {}\n", port, names.join(", ")))
+        .collect();
+
+    format!(
+        "# Ports reachable from other hosts\n\
+         {ports}\
+         [Unit]\n\
+         Description=0L {name} node\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         StartLimitIntervalSec=300\n\
+         StartLimitBurst=5\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={binary} --config {config}\n\
+         WorkingDirectory={data_dir}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         LimitNOFILE={fd_limit}\n\
+         NoNewPrivileges=true\n\
+         PrivateTmp=true\n\
+         PrivateDevices=true\n\
+         ProtectSystem=strict\n\
+         ProtectHome=read-only\n\
+         ReadWritePaths={writable}\n\
+         ProtectKernelTunables=true\n\
+         ProtectKernelModules=true\n\
+         ProtectControlGroups=true\n\
+         RestrictSUIDSGID=true\n\
+         RestrictNamespaces=true\n\
+         LockPersonality=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        ports = ports.concat(),
+        name = service_name(node_type),
+        binary = NODE_BINARY,
+        config = config_path.display(),
+        data_dir = data_dir.display(),
+        fd_limit = fd_limit(config),
+        writable = writable.join(" "),
+    )
+}
+
+/// The descriptors a node may hold open: the files RocksDB keeps open, a socket per connection
+/// the networks accept or dial, and some headroom
+fn fd_limit(config: &NodeConfig) -> u64 {
+    let max_open_files = config.storage.rocksdb_config.max_open_files;
+    if max_open_files < 0 {
+        return FD_LIMIT_UNBOUNDED;
+    }
+    let connections: usize = config
+        .validator_network
+        .iter()
+        .chain(config.full_node_networks.iter())
+        .map(|network| network.max_inbound_connections + network.max_outbound_connections)
+        .sum();
+    (max_open_files as u64 + connections as u64 + FD_LIMIT_HEADROOM).min(FD_LIMIT_UNBOUNDED)
+}
+
+#[derive(Debug, Serialize)]
+struct Endpoints {
+    /// Listen addresses by component, e.g., `validator_network` or `json_rpc`
+    listen_addresses: BTreeMap<String, String>,
+    seeds: Vec<SeedPeer>,
+}
+
+#[derive(Debug, Serialize)]
+struct SeedPeer {
+    network: String,
+    peer_id: String,
+    role: String,
+    /// IP or DNS name
+    host: String,
+    port: u16,
+    address: String,
+    /// Whether the keys of the peer are pinned in the config, rather than learned on connection
+    pinned: bool,
+}
+
+/// The endpoints of a node config: where it listens and the seed peers it dials. Peers in `seeds`
+/// are pinned with their keys, peers in `seed_addrs` only by address.
+fn endpoints(config: &NodeConfig) -> Endpoints {
+    let listen_addresses = listen_addresses(config)
+        .into_iter()
+        .map(|(name, address)| (name, address.to_string()))
+        .collect();
+
+    let mut seeds = vec![];
+    for network in config
+        .validator_network
+        .iter()
+        .chain(config.full_node_networks.iter())
+    {
+        let mut seed = |peer_id: &PeerId, role: PeerRole, address: &NetworkAddress, pinned| {
+            if let Some(((host, port), _)) = parse_tcp(address.as_slice()) {
+                seeds.push(SeedPeer {
+                    network: network.network_id.to_string(),
+                    peer_id: peer_id.to_string(),
+                    role: format!("{:?}", role),
+                    host,
+                    port,
+                    address: address.to_string(),
+                    pinned,
+                });
+            }
+        };
+        for (peer_id, peer) in &network.seeds {
+            for address in &peer.addresses {
+                seed(peer_id, peer.role, address, !peer.keys.is_empty());
+            }
+        }
+        for (peer_id, addresses) in &network.seed_addrs {
+            for address in addresses {
+                seed(peer_id, PeerRole::Upstream, address, false);
+            }
+        }
+    }
+    // Seeds are kept in hash maps, sort them for a stable output
+    seeds.sort_by(|a, b| {
+        (&a.network, &a.peer_id, &a.address).cmp(&(&b.network, &b.peer_id, &b.address))
+    });
+
+    Endpoints {
+        listen_addresses,
+        seeds,
+    }
+}
+
+/// An Ansible inventory with a group per node type and a `seeds` group of the peers the nodes
+/// dial. Node hosts are named `<namespace>-<node type>`, their endpoints are host variables.
+pub fn ansible_inventory(output_dir: &Path, namespace: &str) -> Result<String> {
+    let mut groups = serde_yaml::Mapping::new();
+    let mut seed_hosts = serde_yaml::Mapping::new();
+    for (node_type, _, config) in node_configs(output_dir)? {
+        let endpoints = endpoints(&config);
+        for seed in &endpoints.seeds {
+            seed_hosts.insert(
+                seed.host.clone().into(),
+                serde_yaml::Value::Mapping(serde_yaml::Mapping::new()),
+            );
+        }
+        let mut hosts = serde_yaml::Mapping::new();
+        hosts.insert(
+            format!("{}-{}", resource_name(namespace), service_name(node_type)).into(),
+            serde_yaml::to_value(&endpoints)?,
+        );
+        groups.insert(service_name(node_type).into(), group(hosts));
+    }
+    groups.insert("seeds".into(), group(seed_hosts));
+
+    let mut all = serde_yaml::Mapping::new();
+    all.insert("children".into(), serde_yaml::Value::Mapping(groups));
+    let mut inventory = serde_yaml::Mapping::new();
+    inventory.insert("all".into(), serde_yaml::Value::Mapping(all));
+    Ok(serde_yaml::to_string(&inventory)?)
+}
+
+fn group(hosts: serde_yaml::Mapping) -> serde_yaml::Value {
+    let mut group = serde_yaml::Mapping::new();
+    group.insert("hosts".into(), serde_yaml::Value::Mapping(hosts));
+    serde_yaml::Value::Mapping(group)
+}
+
+/// A `.tfvars.json` file setting the `nodes` variable to the endpoints of each node type, e.g.,
+/// to derive firewall rules from the listen ports
+pub fn terraform_variables(output_dir: &Path) -> Result<String> {
+    let nodes: BTreeMap<_, _> = node_configs(output_dir)?
+        .into_iter()
+        .map(|(node_type, _, config)| (service_name(node_type), endpoints(&config)))
+        .collect();
+    let mut variables = BTreeMap::new();
+    variables.insert("nodes", nodes);
+    Ok(serde_json::to_string_pretty(&variables)?)
+}
+
+/// Resource names are lowercase DNS labels
+fn resource_name(namespace: &str) -> String {
+    namespace
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{x25519, Uniform};
+    use diem_temppath::TempPath;
+    use diem_types::PeerId;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_docker_compose() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let mut validator = NodeConfig::default_for_validator();
+        validator.set_data_dir(dir.path().to_path_buf());
+        let validator_path = dir.path().join(NodeType::Validator.file_name());
+        validator.save(&validator_path).unwrap();
+
+        let compose: serde_yaml::Value =
+            serde_yaml::from_str(&docker_compose(dir.path()).unwrap()).unwrap();
+        let service = &compose["services"]["validator"];
+        assert!(compose["services"]["vfn"].is_null());
+        assert_eq!(
+            service["command"][2],
+            validator_path.display().to_string().into()
+        );
+        assert_eq!(
+            service["volumes"][0],
+            format!("{0}:{0}", dir.path().display()).into()
+        );
+
+        // Exactly the ports reachable from outside of the container are published
+        let ports: Vec<String> = serde_yaml::from_value(service["ports"].clone()).unwrap();
+        assert_eq!(
+            ports,
+            [
+                "6180:6180",
+                "6191:6191",
+                "7180:7180",
+                "9101:9101",
+                "9102:9102"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_docker_compose_distinct_host_ports() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let configs = vec![
+            (NodeType::Validator, NodeConfig::default_for_validator()),
+            (
+                NodeType::ValidatorFullNode,
+                NodeConfig::default_for_validator_full_node(),
+            ),
+            (
+                NodeType::PublicFullNode,
+                NodeConfig::default_for_public_full_node(),
+            ),
+        ];
+        for (node_type, mut config) in configs {
+            config.set_data_dir(dir.path().to_path_buf());
+            config.save(dir.path().join(node_type.file_name())).unwrap();
+        }
+
+        let compose: serde_yaml::Value =
+            serde_yaml::from_str(&docker_compose(dir.path()).unwrap()).unwrap();
+        let ports = |service: &str| -> Vec<String> {
+            serde_yaml::from_value(compose["services"][service]["ports"].clone()).unwrap()
+        };
+        assert_eq!(
+            ports("validator"),
+            [
+                "6180:6180",
+                "6191:6191",
+                "7180:7180",
+                "9101:9101",
+                "9102:9102"
+            ]
+        );
+        assert_eq!(
+            ports("vfn"),
+            [
+                "16180:6180",
+                "16191:6191",
+                "17180:7180",
+                "19101:9101",
+                "19102:9102"
+            ]
+        );
+        assert_eq!(
+            ports("fullnode"),
+            ["26191:6191", "29101:9101", "29102:9102"]
+        );
+
+        // A listener the offset pushes past the last port is refused
+        let mut fullnode = NodeConfig::default_for_public_full_node();
+        fullnode.set_data_dir(dir.path().to_path_buf());
+        fullnode.debug_interface.public_metrics_server_port = 50_000;
+        fullnode
+            .save(dir.path().join(NodeType::PublicFullNode.file_name()))
+            .unwrap();
+        assert!(docker_compose(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let dir = TempPath::new();
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.set_data_dir(dir.path().to_path_buf());
+        let config_path = dir.path().join(NodeType::PublicFullNode.file_name());
+
+        let unit = systemd_unit(NodeType::PublicFullNode, &config, &config_path);
+        assert!(unit.contains(&format!(
+            "ExecStart={} --config {}\n",
+            NODE_BINARY,
+            config_path.display()
+        )));
+        assert!(unit.contains(&format!("WorkingDirectory={}\n", dir.path().display())));
+        let ports = exposed_ports(&config);
+        assert!(!ports.is_empty());
+        for port in ports.keys() {
+            assert!(unit.contains(&format!("\n# {}/tcp: ", port)));
+        }
+        assert!(unit.contains(&format!("LimitNOFILE={}\n", fd_limit(&config))));
+        assert!(fd_limit(&config) > config.storage.rocksdb_config.max_open_files as u64);
+
+        config.storage.rocksdb_config.max_open_files = -1;
+        assert_eq!(fd_limit(&config), FD_LIMIT_UNBOUNDED);
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let mut config = NodeConfig::default_for_public_full_node();
+        let peer_id = PeerId::random();
+        let address: NetworkAddress = "/dns4/seed.example.com/tcp/6182".parse().unwrap();
+        config.full_node_networks[0]
+            .seed_addrs
+            .insert(peer_id, vec![address.clone()]);
+
+        let endpoints = endpoints(&config);
+        assert!(endpoints.listen_addresses.contains_key("json_rpc"));
+        assert_eq!(endpoints.seeds.len(), 1);
+        let seed = &endpoints.seeds[0];
+        assert_eq!(seed.host, "seed.example.com");
+        assert_eq!(seed.port, 6182);
+        assert_eq!(seed.peer_id, peer_id.to_string());
+        assert!(!seed.pinned);
+    }
+
+    #[test]
+    fn test_k8s_secrets_separated() {
+        let (validator_file, vfn_file) = (
+            NodeType::Validator.file_name(),
+            NodeType::ValidatorFullNode.file_name(),
+        );
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let mut validator = NodeConfig::default_for_validator();
+        validator.save(dir.path().join(validator_file)).unwrap();
+        let mut vfn = NodeConfig::default_for_validator_full_node();
+        let key = x25519::PrivateKey::generate(&mut StdRng::from_seed([0u8; 32]));
+        vfn.full_node_networks[0].identity = Identity::from_config(key, PeerId::random());
+        vfn.save(dir.path().join(vfn_file)).unwrap();
+        fs::write(dir.path().join(KEY_STORE_FILE), "{}").unwrap();
+
+        let (config_map, secret) = k8s_resources(dir.path(), "Alice_Oper").unwrap();
+        let config_map = serde_yaml::to_value(&config_map).unwrap();
+        let secret = serde_yaml::to_value(&secret).unwrap();
+
+        assert_eq!(
+            config_map["metadata"]["name"],
+            "alice-oper-node-config".into()
+        );
+        assert!(config_map["data"][validator_file].is_string());
+        assert!(config_map["data"][vfn_file].is_null());
+        assert!(secret["data"][vfn_file].is_string());
+        assert!(secret["data"][KEY_STORE_FILE].is_string());
+        let validator: serde_yaml::Value =
+            serde_yaml::from_str(config_map["data"][validator_file].as_str().unwrap()).unwrap();
+        assert!(!contains_secrets(&validator));
+    }
+}
@@ -1,25 +1,41 @@
-use std::{fmt::Debug, fs, net::Ipv4Addr, path::PathBuf, process::exit};
+use std::{
+    fmt::{self, Debug},
+    fs,
+    io::Write,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use crate::{storage_helper::StorageHelper, seeds::{SeedAddresses, Seeds}};
+use crate::{ceremony, ol_export::{self, Export}, registry::{RemoteRegistry, SharedBackend}, rerun::{RerunMode, Snapshot}, storage_helper::{vfn_network_identity, StorageHelper}, seeds::{self, SeedAddresses, Seeds}};
 use diem_config::{
     config::OnDiskStorageConfig,
-    config::SafetyRulesService,
+    config::{MutualTlsConfig, PrunePreset, RemoteService, SafetyRulesService},
     config::{
-        DiscoveryMethod, NetworkConfig, NodeConfig, Peer, PeerRole, PeerSet, RoleType,
-        SecureBackend,
+        BasicAuthConfig, DiscoveryMethod, MetricsScrapeConfig, NetworkConfig, NodeConfig, Peer,
+        PeerRole, PeerSet, RoleType, SecureBackend, Token, VaultConfig, DEFAULT_METRICS_USER,
     },
-    config::{Identity, WaypointConfig},
+    config::{Identity, PersistableConfig, WaypointConfig, HANDSHAKE_VERSION},
     network_id::NetworkId,
+    template,
 };
 use diem_crypto::x25519::PublicKey;
 use diem_global_constants::{
-    DEFAULT_PUB_PORT, DEFAULT_VFN_PORT, OWNER_ACCOUNT,
-    VALIDATOR_NETWORK_KEY, FULLNODE_NETWORK_KEY,
+    DEFAULT_PUB_PORT, DEFAULT_VAL_PORT, DEFAULT_VFN_PORT, GENESIS_WAYPOINT, OWNER_ACCOUNT,
+    OWNER_KEY,
+    VALIDATOR_NETWORK_KEY, FULLNODE_NETWORK_KEY, VFN_NETWORK_KEY, VFN_NETWORK_PEER_ID,
+};
+use diem_management::{
+    config::ConfigPath, error::Error, progress, secure_backend::ValidatorBackend,
+    trace_span,
+};
+use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
+use diem_types::{
+    account_address::AccountAddress, chain_id::ChainId, network_address::NetworkAddress,
+    waypoint::Waypoint,
 };
-use diem_management::{config::ConfigPath, error::Error, secure_backend::ValidatorBackend};
-use diem_secure_storage::{CryptoStorage, KVStorage};
-use diem_types::{account_address::AccountAddress, chain_id::{ChainId, NamedChain}, waypoint::Waypoint};
-use ol_types::account::ValConfigs;
+use ol_keys::{scheme::KeyScheme, wallet::get_account_from_prompt};
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 use diem_crypto::x25519::PrivateKey;
@@ -31,6 +47,211 @@ pub enum NodeType {
     PublicFullNode,
 }
 
+impl NodeType {
+    /// The config file of the node type in the output directory
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            NodeType::Validator => "validator.node.yaml",
+            NodeType::ValidatorFullNode => "vfn.node.yaml",
+            NodeType::PublicFullNode => "fullnode.node.yaml",
+        }
+    }
+
+    /// The section of a node template only applied to the node type
+    pub fn template_section(&self) -> &'static str {
+        match self {
+            NodeType::Validator => "validator",
+            NodeType::ValidatorFullNode => "vfn",
+            NodeType::PublicFullNode => "fullnode",
+        }
+    }
+}
+
+//////// 0L ////////
+impl FromStr for NodeType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NODE_TYPES
+            .iter()
+            .find(|node_type| node_type.template_section() == s)
+            .copied()
+            .ok_or_else(|| {
+                Error::CommandArgumentError(format!(
+                    "Unknown node type {}, expected validator, vfn or fullnode",
+                    s
+                ))
+            })
+    }
+}
+
+/// The node types a config file is written for
+pub const NODE_TYPES: &[NodeType] = &[
+    NodeType::Validator,
+    NodeType::ValidatorFullNode,
+    NodeType::PublicFullNode,
+];
+
+//////// 0L ////////
+/// Where a validator reads its keys, waypoint and safety data from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NodeBackend {
+    /// The key_store.json of the data path
+    Disk,
+    Vault,
+    /// For tests, the keys do not outlive the node
+    InMemory,
+}
+
+impl FromStr for NodeBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disk" => Ok(NodeBackend::Disk),
+            "vault" => Ok(NodeBackend::Vault),
+            "in-memory" => Ok(NodeBackend::InMemory),
+            _ => Err(Error::CommandArgumentError(format!(
+                "Unknown backend {}, expected vault, disk or in-memory",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for NodeBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            NodeBackend::Disk => "disk",
+            NodeBackend::Vault => "vault",
+            NodeBackend::InMemory => "in-memory",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+//////// 0L ////////
+/// The addresses the networks of the generated configs listen on
+#[derive(Clone, Debug, PartialEq, StructOpt)]
+pub struct ListenAddresses {
+    /// Listen address of the validator network, on the validator
+    #[structopt(long, default_value = "/ip4/0.0.0.0/tcp/6180")]
+    pub validator_listen: NetworkAddress,
+    /// Listen address of the private network of the validator and its VFN, on both. The VFN
+    /// dials the validator on its port at val-ip-address.
+    #[structopt(long, default_value = "/ip4/0.0.0.0/tcp/6179")]
+    pub vfn_listen: NetworkAddress,
+    /// Listen address of the public network, on the VFN and public fullnodes. The validator
+    /// serves it on its port, on localhost only.
+    #[structopt(long, default_value = "/ip4/0.0.0.0/tcp/6178")]
+    pub fullnode_listen: NetworkAddress,
+    /// Also listen on the other ip version, on the same ports: each network of an /ip4 listen
+    /// address on 0.0.0.0 or 127.0.0.1 also listens on :: or ::1, and the other way around
+    #[structopt(long)]
+    pub dual_stack: bool,
+}
+
+impl Default for ListenAddresses {
+    fn default() -> Self {
+        let any = |port| format!("/ip4/0.0.0.0/tcp/{}", port).parse().unwrap();
+        ListenAddresses {
+            validator_listen: any(DEFAULT_VAL_PORT),
+            vfn_listen: any(DEFAULT_VFN_PORT),
+            fullnode_listen: any(DEFAULT_PUB_PORT),
+            dual_stack: false,
+        }
+    }
+}
+
+impl ListenAddresses {
+    fn port(address: &NetworkAddress) -> Result<u64, anyhow::Error> {
+        address
+            .find_port()
+            .map(u64::from)
+            .ok_or_else(|| anyhow::anyhow!("the listen address {} has no port", address))
+    }
+
+    /// The `dual_stack_address` of a network listening on `address`: the same port on the
+    /// unspecified or loopback ip of the other version
+    fn dual_stack_address(
+        &self,
+        address: &NetworkAddress,
+    ) -> Result<Option<NetworkAddress>, anyhow::Error> {
+        if !self.dual_stack {
+            return Ok(None);
+        }
+        let ip: IpAddr = match address.find_ip_addr() {
+            Some(ip) if ip.is_unspecified() && ip.is_ipv4() => Ipv6Addr::UNSPECIFIED.into(),
+            Some(ip) if ip.is_unspecified() => Ipv4Addr::UNSPECIFIED.into(),
+            Some(ip) if ip.is_loopback() && ip.is_ipv4() => Ipv6Addr::LOCALHOST.into(),
+            Some(ip) if ip.is_loopback() => Ipv4Addr::LOCALHOST.into(),
+            _ => anyhow::bail!(
+                "--dual-stack needs the listen address {} on an unspecified or loopback ip, \
+                 which has a counterpart of the other ip version",
+                address
+            ),
+        };
+        let port = Self::port(address)? as u16;
+        Ok(Some(SocketAddr::new(ip, port).into()))
+    }
+}
+
+/// The Vault holding the validator keys, for `--backend vault`
+#[derive(Debug, Default, StructOpt)]
+pub struct VaultOptions {
+    /// URL of the Vault server, e.g. http://127.0.0.1:8200
+    #[structopt(long)]
+    vault_server: Option<String>,
+    /// File holding the Vault token, read by the node
+    #[structopt(long, parse(from_os_str))]
+    vault_token_path: Option<PathBuf>,
+    /// Namespace of the keys in Vault, defaults to --namespace
+    #[structopt(long)]
+    vault_namespace: Option<String>,
+    /// CA certificate of the Vault server
+    #[structopt(long, parse(from_os_str))]
+    vault_ca_cert: Option<PathBuf>,
+}
+
+//////// 0L ////////
+/// Runs the safety rules of the validator in a separate process, e.g. on a hardened host, which
+/// signs with the secure backend of the validator config. Its config is written to
+/// `SAFETY_RULES_FILE`.
+#[derive(Debug, Default, StructOpt)]
+pub struct RemoteSafetyRules {
+    /// Address the validator reaches its safety rules process at
+    #[structopt(long)]
+    safety_rules_address: Option<NetworkAddress>,
+    /// Directory of the files securing the connection with mutually authenticated TLS: ca.pem,
+    /// validator.pem and validator_key.pem valid for the DNS name validator, safety_rules.pem and
+    /// safety_rules_key.pem valid for the DNS name safety-rules
+    #[structopt(long, parse(from_os_str))]
+    safety_rules_tls_dir: Option<PathBuf>,
+    /// Idle time after which the validator checks the connection before a request
+    #[structopt(long)]
+    safety_rules_health_check_ms: Option<u64>,
+}
+
+impl RemoteSafetyRules {
+    fn apply(&self, output_dir: &Path, config: NodeConfig) -> Result<NodeConfig, Error> {
+        match &self.safety_rules_address {
+            Some(address) => apply_remote_safety_rules(
+                output_dir,
+                address,
+                self.safety_rules_tls_dir.as_deref(),
+                self.safety_rules_health_check_ms,
+            ),
+            None if self.safety_rules_tls_dir.is_some() => Err(Error::CommandArgumentError(
+                "--safety-rules-tls-dir needs the --safety-rules-address".into(),
+            )),
+            None => Ok(config),
+        }
+    }
+}
+
+/// The config of the safety rules process, written with `--safety-rules-address`
+pub const SAFETY_RULES_FILE: &str = "safety_rules.yaml";
+
 /// Prints the public information within a store
 #[derive(Debug, StructOpt)]
 pub struct Files {
@@ -44,8 +265,13 @@ pub struct Files {
     github_org: Option<String>,
     #[structopt(long)]
     repo: Option<String>,
+    /// The registry of the ceremony, instead of --github-org and --repo: github:ORG/REPO,
+    /// gitlab:HOST/GROUP/REPO with a token in gitlab_token.txt, or git:URL over ssh
     #[structopt(long)]
-    chain_id: NamedChain,
+    registry: Option<SharedBackend>,
+    /// The chain to join: mainnet, rc, devnet, testing, ... or the numeric id of a custom chain
+    #[structopt(long)]
+    chain_id: ChainId,
     /// If specified, compares the internal state to that of a
     /// provided genesis. Note, that a waypont might diverge from
     /// the provided genesis after execution has begun.
@@ -60,83 +286,860 @@ pub struct Files {
     #[structopt(long, verbatim_doc_comment)]
     layout_path: Option<PathBuf>,
     #[structopt(long, verbatim_doc_comment)]
-    val_ip_address: Option<Ipv4Addr>,
+    val_ip_address: Option<IpAddr>,
     #[structopt(long, verbatim_doc_comment)]
     seed_peers_path: Option<PathBuf>,
+    /// Also export the files for a deployment: k8s, docker, ansible, terraform or systemd
+    #[structopt(long)]
+    export: Option<Export>,
+    /// Also write a systemd unit per node config, as `--export systemd`
+    #[structopt(long)]
+    emit_systemd: bool,
+    /// Also write a docker-compose file running the node configs, as `--export docker`
+    #[structopt(long)]
+    emit_compose: bool,
+    /// Only write vfn.node.yaml, for the host of the validator's fullnode, from the key store
+    /// and genesis already in the data path. The VFN reaches the validator at val-ip-address.
+    #[structopt(long)]
+    vfn: bool,
+    /// YAML values merged over the written configs. The `validator`, `vfn` and `fullnode`
+    /// sections only apply to the config of that node type, other values apply to all of them.
+    #[structopt(long, parse(from_os_str))]
+    template: Option<PathBuf>,
+    /// Secure storage of the validator config: disk, vault or in-memory. With vault, the keys of
+    /// the key store must be imported in Vault before the validator starts.
+    #[structopt(long = "backend", default_value = "disk")]
+    node_backend: NodeBackend,
+    #[structopt(flatten)]
+    vault: VaultOptions,
+    /// Builds genesis offline from the registration bundles of this directory, instead of from
+    /// the genesis repo
+    #[structopt(long, parse(from_os_str))]
+    registrations_dir: Option<PathBuf>,
+    /// Registers the public address of the fullnode, at val-ip-address, in the validator config
+    /// of the GitHub registry before genesis is built from it. The public networks then discover
+    /// the fullnodes of the validator set on chain, instead of from the seeds of the genesis.
+    #[structopt(long)]
+    onchain_seeds: bool,
+    /// Dial the seeds of the public networks and drop those that do not complete a handshake
+    #[structopt(long)]
+    probe_seeds: bool,
+    /// Warn when fewer seeds than this answer the --probe-seeds
+    #[structopt(long, default_value = "2")]
+    min_live_seeds: usize,
+    /// Replace the files of a previous run that differ from the generated ones, e.g. edited since
+    #[structopt(long)]
+    force: bool,
+    /// Leave the data path as it is and print the changes a run would make
+    #[structopt(long)]
+    dry_run: bool,
+    /// Prune windows of all the node configs: archive keeps the whole history, minimal only
+    /// the recent one
+    #[structopt(long)]
+    prune_preset: Option<PrunePreset>,
+    #[structopt(flatten)]
+    listen: ListenAddresses,
+    #[structopt(flatten)]
+    safety_rules: RemoteSafetyRules,
 }
 
 impl Files {
     pub fn execute(self) -> Result<NodeConfig, Error> {
-        onboard_helper_all_files(
-            self.data_path,
+        //////// 0L ////////
+        let snapshot = Snapshot::take(&self.data_path)?;
+        let result = if self.vfn {
+            self.write_vfn()
+        } else {
+            self.write_files()
+        };
+        match result {
+            Ok(config) => {
+                snapshot.reconcile(RerunMode {
+                    force: self.force,
+                    dry_run: self.dry_run,
+                })?;
+                Ok(config)
+            }
+            Err(e) => {
+                snapshot.restore()?;
+                Err(e)
+            }
+        }
+    }
+
+    fn write_files(&self) -> Result<NodeConfig, Error> {
+        let data_path = self.data_path.clone();
+        let prebuilt_genesis = match &self.registrations_dir {
+            Some(dir) => Some(ceremony::offline_genesis(
+                dir,
+                &data_path,
+                self.chain_id,
+                &self.layout_path,
+            )?),
+            None => None,
+        };
+        let registry = self.registry.clone().or_else(|| {
+            SharedBackend::from_github(self.github_org.clone(), self.repo.clone())
+        });
+        if self.onchain_seeds && self.dry_run {
+            progress::detail("dry run, the fullnode address is not registered");
+        } else if self.onchain_seeds {
+            self.register_fullnode_address(registry.as_ref(), &prebuilt_genesis)?;
+        }
+        let mut config = onboard_helper_all_files(
+            self.data_path.clone(),
             self.chain_id,
-            self.github_org,
-            self.repo,
+            registry,
             &self.namespace,
-            &None,
+            &prebuilt_genesis,
             &self.fullnode_only,
-            self.seed_peers_path,
+            self.seed_peers_path.clone(),
             &self.layout_path,
             self.val_ip_address,
+            &self.listen,
         )
         .map_err(|e| {
             Error::ConfigError(format!(
                 "Could not write node config files, message: {}",
                 e.to_string()
             ))
-        })
+        })?;
+        //////// 0L ////////
+        if self.node_backend != NodeBackend::Disk {
+            config = self.apply_node_backend(&data_path)?;
+        }
+        if self.onchain_seeds {
+            config = apply_onchain_seeds(&data_path)?;
+        }
+        if self.probe_seeds {
+            config = apply_seed_probe(&data_path, self.chain_id, self.min_live_seeds)?;
+        }
+        if let Some(preset) = self.prune_preset {
+            config = apply_prune_preset(&data_path, preset)?;
+        }
+        config = self.safety_rules.apply(&data_path, config)?;
+        if let Some(template) = &self.template {
+            for node_type in NODE_TYPES {
+                let merged = apply_template(&data_path, template, *node_type)?;
+                if *node_type == NodeType::Validator {
+                    config = merged;
+                }
+            }
+        }
+        for export in self.exports().into_iter().filter(|_| !self.dry_run) {
+            ol_export::export(&data_path, &self.namespace, export).map_err(|e| {
+                Error::ConfigError(format!("Could not export node files, message: {}", e))
+            })?;
+        }
+        Ok(config)
+    }
+
+    //////// 0L ////////
+    /// The exports of `--export` and the `--emit-*` flags
+    fn exports(&self) -> Vec<Export> {
+        let mut exports: Vec<Export> = self.export.into_iter().collect();
+        let emitted = [
+            (self.emit_systemd, Export::Systemd),
+            (self.emit_compose, Export::Docker),
+        ];
+        for (emit, export) in emitted.iter() {
+            if *emit && !exports.contains(export) {
+                exports.push(*export);
+            }
+        }
+        exports
+    }
+
+    //////// 0L ////////
+    fn secure_backend(&self) -> Result<SecureBackend, Error> {
+        match self.node_backend {
+            NodeBackend::Disk => Ok(disk_backend(&self.data_path, &self.namespace)),
+            NodeBackend::InMemory => Ok(SecureBackend::InMemoryStorage),
+            NodeBackend::Vault => {
+                let missing = |flag: &str| {
+                    Error::CommandArgumentError(format!("--backend vault needs --{}", flag))
+                };
+                let vault = &self.vault;
+                Ok(SecureBackend::Vault(VaultConfig {
+                    ca_certificate: vault.vault_ca_cert.clone(),
+                    namespace: Some(
+                        vault
+                            .vault_namespace
+                            .clone()
+                            .unwrap_or_else(|| self.namespace.clone()),
+                    ),
+                    renew_ttl_secs: None,
+                    server: vault
+                        .vault_server
+                        .clone()
+                        .ok_or_else(|| missing("vault-server"))?,
+                    token: Token::FromDisk(
+                        vault
+                            .vault_token_path
+                            .clone()
+                            .ok_or_else(|| missing("vault-token-path"))?,
+                    ),
+                    disable_cas: None,
+                    connection_timeout_ms: None,
+                    response_timeout_ms: None,
+                }))
+            }
+        }
+    }
+
+    /// Switches the written validator config to the secure storage of `--backend`
+    fn apply_node_backend(&self, data_path: &Path) -> Result<NodeConfig, Error> {
+        let backend = self.secure_backend()?;
+        let path = data_path.join(NodeType::Validator.file_name());
+        let mut config = NodeConfig::load_config(&path).map_err(|e| Error::ConfigInvalid {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        set_secure_backend(&mut config, &backend);
+        if backend == SecureBackend::InMemoryStorage {
+            // An empty storage has no waypoint, take it from the key store
+            let waypoint = get_default_keystore_helper(data_path.to_path_buf())
+                .storage(self.namespace.clone())
+                .get::<Waypoint>(GENESIS_WAYPOINT)
+                .map_err(|e| Error::StorageReadError("validator", GENESIS_WAYPOINT, e.to_string()))?
+                .value;
+            config.base.waypoint = WaypointConfig::FromConfig(waypoint);
+        }
+        config
+            .save_config(&path)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+        progress::detail(&format!(
+            "{:?} backend set in {:?}",
+            self.node_backend, path
+        ));
+        Ok(config)
+    }
+
+    /// Writes the public address of the fullnode in the validator config the operator registered
+    fn register_fullnode_address(
+        &self,
+        registry: Option<&SharedBackend>,
+        prebuilt_genesis: &Option<PathBuf>,
+    ) -> Result<(), Error> {
+        let unsupported =
+            |reason: &str| Error::CommandArgumentError(format!("--onchain-seeds {}", reason));
+        let ip_address = self.val_ip_address.ok_or_else(|| {
+            unsupported("needs the --val-ip-address the fullnode of the validator is reached at")
+        })?;
+        // A git or gitlab registry is a read only checkout, cloned again to build genesis
+        let registry = match registry {
+            Some(registry @ SharedBackend::GitHub { .. }) if prebuilt_genesis.is_none() => registry,
+            _ => return Err(unsupported(
+                "writes the fullnode address to a GitHub registry, genesis must be built from it",
+            )),
+        };
+
+        // NOTE: This must match ol/types/src/config.rs format_oper_namespace
+        let operator_namespace = format!("{}-oper", self.namespace);
+        let remote = registry.shared_backend(&self.data_path, &operator_namespace)?;
+        let mut shared = Storage::from(&diem_management::secure_backend::storage(&remote)?);
+        let validator =
+            get_default_keystore_helper(self.data_path.clone()).storage(self.namespace.clone());
+        let address = seeds::fullnode_network_address(&validator, &ip_address)?;
+        seeds::register_fullnode_addresses(&validator, &mut shared, &[address.clone()])?;
+        progress::detail(&format!(
+            "fullnode address {} registered in the validator config of {}",
+            address, operator_namespace
+        ));
+        Ok(())
+    }
+
+    fn write_vfn(&self) -> Result<NodeConfig, Error> {
+        let val_ip_address = self.val_ip_address.ok_or_else(|| {
+            Error::CommandArgumentError("--vfn needs the --val-ip-address of the validator".into())
+        })?;
+        let waypoint = match self.waypoint {
+            Some(waypoint) => waypoint,
+            None => {
+                get_default_keystore_helper(self.data_path.clone())
+                    .storage(self.namespace.clone())
+                    .get::<Waypoint>(GENESIS_WAYPOINT)
+                    .map_err(|e| {
+                        Error::StorageReadError("validator", GENESIS_WAYPOINT, e.to_string())
+                    })?
+                    .value
+            }
+        };
+        let config = make_vfn_file(
+            self.data_path.clone(),
+            val_ip_address,
+            waypoint,
+            &self.namespace,
+            &self.listen,
+        )
+        .map_err(|e| {
+            Error::ConfigError(format!(
+                "Could not write the VFN config file, message: {}",
+                e.to_string()
+            ))
+        })?;
+        match &self.template {
+            Some(template) => {
+                apply_template(&self.data_path, template, NodeType::ValidatorFullNode)
+            }
+            None => Ok(config),
+        }
     }
 }
 
-pub fn onboard_helper_all_files(
-    output_dir: PathBuf,
-    chain_name: NamedChain,
+//////// 0L ////////
+/// Initializes the keys of a validator in the key store of the data path, from a mnemonic. Keys
+/// already in the key store are kept unless --overwrite is given.
+#[derive(Debug, StructOpt)]
+pub struct InitKeys {
+    #[structopt(long)]
+    namespace: String,
+    #[structopt(long, parse(from_os_str))]
+    data_path: PathBuf,
+    /// File holding the mnemonic, prompted for otherwise
+    #[structopt(long, parse(from_os_str))]
+    mnemonic_path: Option<PathBuf>,
+    #[structopt(long)]
+    overwrite: bool,
+}
+
+impl InitKeys {
+    pub fn execute(self) -> Result<String, Error> {
+        let keys = match &self.mnemonic_path {
+            Some(path) => {
+                let mnemonic = fs::read_to_string(path)
+                    .map_err(|e| Error::IO(path.display().to_string(), e))?;
+                KeyScheme::new_from_mnemonic(mnemonic.trim().to_string())
+            }
+            None => KeyScheme::new(&get_account_from_prompt().2),
+        };
+        init_keys(&self.data_path, &self.namespace, keys, self.overwrite)?;
+        Ok(format!(
+            "Keys of {} initialized in {:?}",
+            self.namespace,
+            self.data_path.join("key_store.json")
+        ))
+    }
+}
+
+/// Builds or copies the genesis into the data path, and inserts its waypoint in the key store.
+/// The keys must be initialized, the node configs are left as they are.
+#[derive(Debug, StructOpt)]
+pub struct FetchGenesis {
+    #[structopt(long)]
+    namespace: String,
+    #[structopt(long, parse(from_os_str))]
+    data_path: PathBuf,
+    /// The chain to join: mainnet, rc, devnet, testing, ... or the numeric id of a custom chain
+    #[structopt(long)]
+    chain_id: ChainId,
+    #[structopt(long)]
     github_org: Option<String>,
+    #[structopt(long)]
     repo: Option<String>,
+    /// The registry of the ceremony, instead of --github-org and --repo
+    #[structopt(long)]
+    registry: Option<SharedBackend>,
+    /// Use this genesis, next to its genesis_waypoint.txt, instead of building it
+    #[structopt(long, parse(from_os_str))]
+    genesis_path: Option<PathBuf>,
+    #[structopt(long, parse(from_os_str))]
+    layout_path: Option<PathBuf>,
+}
+
+impl FetchGenesis {
+    pub fn execute(self) -> Result<String, Error> {
+        let registry = self
+            .registry
+            .clone()
+            .or_else(|| SharedBackend::from_github(self.github_org.clone(), self.repo.clone()));
+        let (genesis_path, waypoint) = fetch_genesis(
+            &self.data_path,
+            self.chain_id,
+            &registry,
+            &self.namespace,
+            &self.genesis_path,
+            &self.layout_path,
+        )
+        .map_err(|e| Error::ConfigError(format!("Could not fetch genesis, message: {}", e)))?;
+        Ok(format!("Genesis {:?}, waypoint {}", genesis_path, waypoint))
+    }
+}
+
+/// Writes the config files of the node types from the key store and genesis of the data path,
+/// without touching either
+#[derive(Debug, StructOpt)]
+pub struct RenderConfig {
+    #[structopt(long)]
+    namespace: String,
+    #[structopt(long, parse(from_os_str))]
+    data_path: PathBuf,
+    #[structopt(long)]
+    val_ip_address: IpAddr,
+    /// Seed peers of the fullnodes, extracted from the genesis otherwise
+    #[structopt(long, parse(from_os_str))]
+    seed_peers_path: Option<PathBuf>,
+    /// The genesis waypoint, read from the key store otherwise
+    #[structopt(long)]
+    waypoint: Option<Waypoint>,
+    /// YAML values merged over the written configs, see `files --template`
+    #[structopt(long, parse(from_os_str))]
+    template: Option<PathBuf>,
+    /// Replace the configs that differ from the generated ones, see `files --force`
+    #[structopt(long)]
+    force: bool,
+    /// Leave the configs as they are and print the changes a run would make
+    #[structopt(long)]
+    dry_run: bool,
+    /// Prune windows of all the node configs, see `files --prune-preset`
+    #[structopt(long)]
+    prune_preset: Option<PrunePreset>,
+    #[structopt(flatten)]
+    listen: ListenAddresses,
+    #[structopt(flatten)]
+    safety_rules: RemoteSafetyRules,
+}
+
+impl RenderConfig {
+    pub fn execute(self) -> Result<NodeConfig, Error> {
+        let snapshot = Snapshot::take(&self.data_path)?;
+        match self.write_configs() {
+            Ok(config) => {
+                snapshot.reconcile(RerunMode {
+                    force: self.force,
+                    dry_run: self.dry_run,
+                })?;
+                Ok(config)
+            }
+            Err(e) => {
+                snapshot.restore()?;
+                Err(e)
+            }
+        }
+    }
+
+    fn write_configs(&self) -> Result<NodeConfig, Error> {
+        let mut config = render_config(
+            &self.data_path,
+            &self.namespace,
+            self.waypoint,
+            self.seed_peers_path.clone(),
+            self.val_ip_address,
+            &self.listen,
+        )
+        .map_err(|e| {
+            Error::ConfigError(format!("Could not write node config files, message: {}", e))
+        })?;
+        if let Some(preset) = self.prune_preset {
+            config = apply_prune_preset(&self.data_path, preset)?;
+        }
+        config = self.safety_rules.apply(&self.data_path, config)?;
+        if let Some(template) = &self.template {
+            for node_type in NODE_TYPES {
+                let merged = apply_template(&self.data_path, template, *node_type)?;
+                if *node_type == NodeType::Validator {
+                    config = merged;
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+//////// 0L ////////
+/// Merges the template at `template_path` over the config of `node_type` in `output_dir`,
+/// reporting every generated value it replaces
+pub fn apply_template(
+    output_dir: &Path,
+    template_path: &Path,
+    node_type: NodeType,
+) -> Result<NodeConfig, Error> {
+    trace_span!("node_files.template", role = ?node_type);
+    let invalid = |path: &Path, reason: String| Error::ConfigInvalid {
+        path: path.display().to_string(),
+        reason,
+    };
+    let contents = fs::read_to_string(template_path)
+        .map_err(|e| Error::IO(template_path.display().to_string(), e))?;
+    let mut common: serde_yaml::Value =
+        serde_yaml::from_str(&contents).map_err(|e| invalid(template_path, e.to_string()))?;
+    let sections = match common.as_mapping_mut() {
+        Some(mapping) => NODE_TYPES
+            .iter()
+            .map(|section| {
+                let key = serde_yaml::Value::from(section.template_section());
+                (*section, mapping.remove(&key))
+            })
+            .collect::<Vec<_>>(),
+        None if common.is_null() => vec![],
+        None => {
+            return Err(invalid(
+                template_path,
+                "a template must be a mapping".to_string(),
+            ))
+        }
+    };
+    let section = sections
+        .into_iter()
+        .find(|(section, _)| *section == node_type)
+        .and_then(|(_, value)| value);
+
+    let config_path = output_dir.join(node_type.file_name());
+    let mut config =
+        NodeConfig::load_config(&config_path).map_err(|e| invalid(&config_path, e.to_string()))?;
+    for template in std::iter::once(common).chain(section) {
+        let merge = template::merge_template(&config, &template)
+            .map_err(|e| invalid(template_path, format!("{}: {}", node_type.file_name(), e)))?;
+        for replaced in &merge.overrides {
+            progress::detail(&format!(
+                "{} template override {}",
+                node_type.file_name(),
+                replaced
+            ));
+        }
+        config = merge.config;
+    }
+    config
+        .save_config(&config_path)
+        .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+    progress::report(
+        progress::Verbosity::Normal,
+        &format!("template {:?} applied to {:?}", template_path, config_path),
+    );
+    Ok(config)
+}
+
+//////// 0L ////////
+/// Has the public networks of the validator and public fullnode configs in `output_dir` discover
+/// peers on chain, from the fullnode addresses of the validator set, rather than from the static
+/// seeds of the genesis. Returns the validator config.
+pub fn apply_onchain_seeds(output_dir: &Path) -> Result<NodeConfig, Error> {
+    let mut validator = None;
+    for node_type in &[NodeType::Validator, NodeType::PublicFullNode] {
+        let path = output_dir.join(node_type.file_name());
+        let mut config = NodeConfig::load_config(&path).map_err(|e| Error::ConfigInvalid {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        for network in config
+            .full_node_networks
+            .iter_mut()
+            .filter(|network| network.network_id == NetworkId::Public)
+        {
+            network.discovery_method = DiscoveryMethod::Onchain;
+            network.seed_addrs.clear();
+        }
+        config
+            .save_config(&path)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+        if *node_type == NodeType::Validator {
+            validator = Some(config);
+        }
+    }
+    progress::detail("public networks discover their peers on chain");
+    Ok(validator.expect("The validator config is written"))
+}
+
+//////// 0L ////////
+/// Dials the seeds of the public networks of the validator and public fullnode configs in
+/// `output_dir`, and drops those that do not complete a handshake, see `seeds::probe_seeds`.
+/// Warns when fewer than `min_live` answer. When none answers, e.g. from a host without outbound
+/// access, the seeds are kept. Returns the validator config.
+pub fn apply_seed_probe(
+    output_dir: &Path,
+    chain_id: ChainId,
+    min_live: usize,
+) -> Result<NodeConfig, Error> {
+    let node_types = [NodeType::Validator, NodeType::PublicFullNode];
+    let mut configs = vec![];
+    let mut seeds = SeedAddresses::new();
+    for node_type in &node_types {
+        let path = output_dir.join(node_type.file_name());
+        let config = NodeConfig::load_config(&path).map_err(|e| Error::ConfigInvalid {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        for network in config
+            .full_node_networks
+            .iter()
+            .filter(|network| network.network_id == NetworkId::Public)
+        {
+            seeds.extend(network.seed_addrs.clone());
+        }
+        configs.push((path, config));
+    }
+
+    let probes = seeds::probe_seeds(chain_id, &seeds, seeds::SEED_PROBE_TIMEOUT)?;
+    let mut live = vec![];
+    for (peer_id, probe) in &probes {
+        match probe {
+            Ok(address) => {
+                progress::detail(&format!("seed {} answered on {}", peer_id, address));
+                live.push(*peer_id);
+            }
+            Err(reason) => progress::warn(&format!("seed {} is unreachable: {}", peer_id, reason)),
+        }
+    }
+    if live.len() < min_live {
+        progress::warn(&format!(
+            "{} of the {} seeds answered, fewer than {}: the nodes may not find peers",
+            live.len(),
+            probes.len(),
+            min_live
+        ));
+    }
+    if live.is_empty() {
+        return Ok(configs.swap_remove(0).1);
+    }
+
+    for (path, config) in configs.iter_mut() {
+        for network in config
+            .full_node_networks
+            .iter_mut()
+            .filter(|network| network.network_id == NetworkId::Public)
+        {
+            network
+                .seed_addrs
+                .retain(|peer_id, _| live.contains(peer_id));
+        }
+        config
+            .save_config(path.as_path())
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+    }
+    progress::detail(&format!(
+        "{} unreachable seeds dropped",
+        probes.len() - live.len()
+    ));
+    Ok(configs.swap_remove(0).1)
+}
+
+//////// 0L ////////
+/// Sets the prune windows of every node config in `output_dir` to those of `preset`. Returns the
+/// validator config.
+pub fn apply_prune_preset(output_dir: &Path, preset: PrunePreset) -> Result<NodeConfig, Error> {
+    let mut validator = None;
+    for node_type in NODE_TYPES {
+        let path = output_dir.join(node_type.file_name());
+        let mut config = NodeConfig::load_config(&path).map_err(|e| Error::ConfigInvalid {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        config.storage.apply_prune_preset(preset);
+        config
+            .save_config(&path)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+        if *node_type == NodeType::Validator {
+            validator = Some(config);
+        }
+    }
+    progress::detail(&format!("prune windows of the {:?} preset", preset));
+    Ok(validator.expect("The validator config is written"))
+}
+
+//////// 0L ////////
+/// Has the validator config in `output_dir` reach its safety rules at `address`, and writes the
+/// config of the safety rules process serving it on the port of `address`, from the secure
+/// backend of the validator config. Returns the validator config.
+pub fn apply_remote_safety_rules(
+    output_dir: &Path,
+    address: &NetworkAddress,
+    tls_dir: Option<&Path>,
+    health_check_interval_ms: Option<u64>,
+) -> Result<NodeConfig, Error> {
+    let path = output_dir.join(NodeType::Validator.file_name());
+    let mut config = NodeConfig::load_config(&path).map_err(|e| Error::ConfigInvalid {
+        path: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    let port = ListenAddresses::port(address)
+        .map_err(|e| Error::CommandArgumentError(format!("--safety-rules-address: {}", e)))?;
+    let tls = |name: &str, allowed_clients: Vec<String>| {
+        tls_dir.map(|dir| MutualTlsConfig {
+            cert_path: dir.join(format!("{}.pem", name)),
+            key_path: dir.join(format!("{}_key.pem", name)),
+            ca_cert_path: dir.join("ca.pem"),
+            server_name: "safety-rules".into(),
+            allowed_clients,
+        })
+    };
+
+    let mut process = config.consensus.safety_rules.clone();
+    process.service = SafetyRulesService::Process(RemoteService {
+        server_address: format!("/ip4/0.0.0.0/tcp/{}", port)
+            .parse()
+            .expect("A valid listen address"),
+        tls: tls("safety_rules", vec!["validator".into()]),
+        health_check_interval_ms: None,
+    });
+    config.consensus.safety_rules.service = SafetyRulesService::Process(RemoteService {
+        server_address: address.clone(),
+        tls: tls("validator", vec![]),
+        health_check_interval_ms,
+    });
+
+    let process_path = output_dir.join(SAFETY_RULES_FILE);
+    process
+        .save_config(&process_path)
+        .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+    config
+        .save_config(&path)
+        .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+    progress::detail(&format!(
+        "safety rules reached at {}, the process config is {:?}",
+        address, process_path
+    ));
+    Ok(config)
+}
+
+pub fn onboard_helper_all_files(
+    output_dir: PathBuf,
+    chain_id: ChainId,
+    registry: Option<SharedBackend>,
     namespace: &str,
     prebuilt_genesis: &Option<PathBuf>,
     _fullnode_only: &bool,
     seed_peers_path: Option<PathBuf>,
     layout_path: &Option<PathBuf>,
-    val_ip_address: Option<Ipv4Addr>,
+    val_ip_address: Option<IpAddr>,
+    listen: &ListenAddresses,
 ) -> Result<NodeConfig, anyhow::Error> {
+        trace_span!("node_files.write_all", output_dir = ?output_dir, chain = %chain_id, namespace);
 
-  let chain_id = ChainId::new(chain_name.id()) ;
+    //////// 0L ////////
+    let mut steps = progress::Progress::new("node files", 2);
+    steps.step("fetching genesis");
+    let (_, genesis_waypoint) = fetch_genesis(
+        &output_dir,
+        chain_id,
+        &registry,
+        namespace,
+        prebuilt_genesis,
+        layout_path,
+    )?;
 
-    let storage_helper = StorageHelper::get_with_path(output_dir.clone());
+    steps.step("rendering node configs");
+    let config = render_config(
+        &output_dir,
+        namespace,
+        Some(genesis_waypoint),
+        seed_peers_path,
+        val_ip_address.ok_or_else(|| anyhow::anyhow!("missing an ip address for validator"))?,
+        listen,
+    )?;
+    steps.done();
+    Ok(config)
+}
+
+//////// 0L ////////
+/// Initializes the keys of `namespace` in the key store of `output_dir`. An initialized namespace
+/// is only overwritten with `overwrite`, as its keys may already be registered.
+pub fn init_keys(
+    output_dir: &Path,
+    namespace: &str,
+    keys: KeyScheme,
+    overwrite: bool,
+) -> Result<(), Error> {
+    trace_span!("node_files.init_keys", namespace);
+    let storage_helper = StorageHelper::get_with_path(output_dir.to_path_buf());
+    let initialized = storage_helper
+        .storage(namespace.to_string())
+        .get_public_key(OWNER_KEY)
+        .is_ok();
+    if initialized && !overwrite {
+        return Err(Error::CommandArgumentError(format!(
+            "the keys of {} are already in {:?}, pass --overwrite to replace them",
+            namespace,
+            output_dir.join("key_store.json")
+        )));
+    }
+    fs::create_dir_all(output_dir).map_err(|e| Error::IO(output_dir.display().to_string(), e))?;
+    storage_helper.initialize_with_mnemonic(namespace.to_string(), keys, true);
+    progress::detail(&format!("keys of {} initialized", namespace));
+    Ok(())
+}
 
-    let (genesis_path, genesis_waypoint) = make_genesis_file(
+/// Builds genesis from the registry, or takes the prebuilt one, into `output_dir`, and inserts its
+/// waypoint in the key store. Returns the genesis path and waypoint.
+pub fn fetch_genesis(
+    output_dir: &Path,
+    chain_id: ChainId,
+    registry: &Option<SharedBackend>,
+    namespace: &str,
+    prebuilt_genesis: &Option<PathBuf>,
+    layout_path: &Option<PathBuf>,
+) -> Result<(PathBuf, Waypoint), anyhow::Error> {
+    let output_dir = output_dir.to_path_buf();
+    let storage_helper = StorageHelper::get_with_path(output_dir.clone());
+    let (mut genesis_path, genesis_waypoint) = make_genesis_file(
         &output_dir,
         prebuilt_genesis,
-        &repo,
-        &github_org,
+        registry,
         layout_path,
         storage_helper,
         chain_id,
         namespace,
     )?;
+    // The configs read the genesis of the data path
+    let data_genesis_path = output_dir.join("genesis.blob");
+    if fs::canonicalize(&genesis_path).ok() != fs::canonicalize(&data_genesis_path).ok() {
+        fs::copy(&genesis_path, &data_genesis_path)?;
+        genesis_path = data_genesis_path;
+    }
 
+    progress::detail(&format!("genesis waypoint: {}", genesis_waypoint));
+    update_genesis_waypoint_in_key_store(&output_dir, namespace, genesis_waypoint)?;
+    Ok((genesis_path, genesis_waypoint))
+}
 
-    update_genesis_waypoint_in_key_store(&output_dir, namespace, genesis_waypoint.clone());
+/// Writes the configs of every node type into `output_dir`, from its key store and genesis. The
+/// waypoint defaults to the one of the key store, the seed peers to those of the genesis.
+pub fn render_config(
+    output_dir: &Path,
+    namespace: &str,
+    waypoint: Option<Waypoint>,
+    seed_peers_path: Option<PathBuf>,
+    val_ip_address: IpAddr,
+    listen: &ListenAddresses,
+) -> Result<NodeConfig, anyhow::Error> {
+    trace_span!("node_files.render", namespace);
+    let output_dir = output_dir.to_path_buf();
+    let genesis_waypoint = match waypoint {
+        Some(waypoint) => waypoint,
+        None => {
+            get_default_keystore_helper(output_dir.clone())
+                .storage(namespace.to_string())
+                .get::<Waypoint>(GENESIS_WAYPOINT)
+                .map_err(|e| Error::StorageReadError("validator", GENESIS_WAYPOINT, e.to_string()))?
+                .value
+        }
+    };
+    let genesis_path = output_dir.join("genesis.blob");
 
     // fullnodes need seed peers, try to extract from the genesis file as a starting place.
     let seeds: Option<SeedAddresses> = if let Some(p) = seed_peers_path {
-      let file_string = fs::read_to_string(&p)?;
-      let yaml: SeedAddresses = serde_yaml::from_str(&file_string)?;
-      Some(yaml)
+        progress::detail(&format!("reading seed peers from {:?}", p));
+        let file_string = fs::read_to_string(&p)?;
+        let yaml: SeedAddresses = serde_yaml::from_str(&file_string)?;
+        Some(yaml)
     } else {
-      Seeds::new(genesis_path).get_network_peers_info().ok()
+        progress::detail("extracting seed peers from the genesis blob");
+        match Seeds::new(genesis_path).get_network_peers_info() {
+            Ok(seeds) => Some(seeds),
+            Err(e) => {
+                progress::warn(&format!("could not extract seed peers from genesis: {}", e));
+                None
+            }
+        }
     };
 
-    let vfn_ip_address = val_ip_address.clone();
-    // This next step depends on genesis waypoint existing in key_store.
     make_all_profiles_yaml(
-      output_dir,
-      val_ip_address.expect("missing an ip address for validator"),
-      vfn_ip_address, 
-      seeds,
-      namespace, 
-      genesis_waypoint
+        output_dir,
+        val_ip_address,
+        Some(val_ip_address),
+        seeds,
+        namespace,
+        genesis_waypoint,
+        listen,
     )
 }
 
@@ -148,29 +1151,35 @@ fn update_genesis_waypoint_in_key_store(
     output_dir: &PathBuf,
     namespace: &str,
     genesis_waypoint: Waypoint,
-) {
+) -> Result<(), Error> {
+        trace_span!("node_files.insert_waypoint", namespace);
     let storage_helper = StorageHelper::get_with_path(output_dir.clone());
     // for genesis cases, need to insert the waypoint in the key_store.json
-    storage_helper
-        .insert_waypoint(namespace, genesis_waypoint)
-        .unwrap();
+    storage_helper.insert_waypoint(namespace, genesis_waypoint)
 }
 
 /// Make all the node configurations needed
 pub fn make_all_profiles_yaml(
     output_dir: PathBuf,
-    val_ip_address: Ipv4Addr,
-    vfn_ip_address: Option<Ipv4Addr>,
+    val_ip_address: IpAddr,
+    vfn_ip_address: Option<IpAddr>,
     seed_addr: Option<SeedAddresses>,
     namespace: &str,
     genesis_waypoint: Waypoint,
+    listen: &ListenAddresses,
     // _fullnode_only: bool,
 ) -> Result<NodeConfig, anyhow::Error> {
     // fullnodes need seed peers, try to extract from the genesis file as a starting place.
 
-    let config = make_val_file(output_dir.clone(), seed_addr.clone(), vfn_ip_address, namespace)?;
-    make_vfn_file(output_dir.clone(), val_ip_address, genesis_waypoint, namespace)?;
-    make_fullnode_file(output_dir.clone(), seed_addr, genesis_waypoint)?;
+    let config = make_val_file(
+        output_dir.clone(),
+        seed_addr.clone(),
+        vfn_ip_address,
+        namespace,
+        listen,
+    )?;
+    make_vfn_file(output_dir.clone(), val_ip_address, genesis_waypoint, namespace, listen)?;
+    make_fullnode_file(output_dir.clone(), seed_addr, genesis_waypoint, listen)?;
 
     Ok(config)
     
@@ -180,24 +1189,27 @@ pub fn make_all_profiles_yaml(
 pub fn make_val_file(
     output_dir: PathBuf,
     seed_addr: Option<SeedAddresses>,
-    _vfn_ip_address: Option<Ipv4Addr>,
+    _vfn_ip_address: Option<IpAddr>,
     namespace: &str,
+    listen: &ListenAddresses,
 ) -> Result<NodeConfig, anyhow::Error> {
   // TODO: The validator's connection to VFN should be restricted to the vfn_ip_address
-    let mut val = make_validator_cfg(output_dir.clone(), namespace, seed_addr)?;
+    let mut val = make_validator_cfg(output_dir.clone(), namespace, seed_addr, listen)?;
     write_yaml(output_dir.clone(), &mut val, NodeType::Validator)?;
     Ok(val)
 }
 
-// helper to write a new validator.node.yaml file.
+// helper to write a new vfn.node.yaml file.
 pub fn make_vfn_file(
     output_dir: PathBuf,
-    val_ip_address: Ipv4Addr,
+    val_ip_address: IpAddr,
     gen_wp: Waypoint,
     namespace: &str,
-) -> Result<(), anyhow::Error> {
-    let mut vfn = make_vfn_cfg(output_dir.clone(), gen_wp, val_ip_address, namespace)?;
-    write_yaml(output_dir.clone(), &mut vfn, NodeType::ValidatorFullNode)
+    listen: &ListenAddresses,
+) -> Result<NodeConfig, anyhow::Error> {
+    let mut vfn = make_vfn_cfg(output_dir.clone(), gen_wp, val_ip_address, namespace, listen)?;
+    write_yaml(output_dir.clone(), &mut vfn, NodeType::ValidatorFullNode)?;
+    Ok(vfn)
 }
 
 // helper to write a new validator.node.yaml file.
@@ -205,8 +1217,9 @@ pub fn make_fullnode_file(
     output_dir: PathBuf,
     seed_addr: Option<SeedAddresses>,
     gen_wp: Waypoint,
+    listen: &ListenAddresses,
 ) -> Result<(), anyhow::Error> {
-    let mut n = make_fullnode_cfg(output_dir.clone(), seed_addr, gen_wp)?;
+    let mut n = make_fullnode_cfg(output_dir.clone(), seed_addr, gen_wp, listen)?;
     write_yaml(output_dir.clone(), &mut n, NodeType::PublicFullNode)
 }
 
@@ -216,37 +1229,71 @@ fn write_yaml(
     config: &mut NodeConfig,
     role: NodeType,
 ) -> Result<(), anyhow::Error> {
-    let filename = match role {
-        NodeType::Validator => "validator.node.yaml",
-        NodeType::ValidatorFullNode => "vfn.node.yaml",
-        NodeType::PublicFullNode => "fullnode.node.yaml",
-    };
+        trace_span!("node_files.write_yaml", role = ?role);
+    let filename = role.file_name();
 
     let yaml_path = output_dir.join(filename);
+    //////// 0L ////////
+    // The node refuses to load an invalid config, e.g. with two networks on one port
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("{} would be invalid: {}", filename, e))?;
     fs::create_dir_all(&output_dir)?;
+    //////// 0L ////////
+    // The metrics password of the node is generated once and kept across regenerated configs
+    if let Some(BasicAuthConfig {
+        password: Token::FromDisk(path),
+        ..
+    }) = &config.metrics.scrape.basic_auth
+    {
+        if !path.exists() {
+            write_metrics_password(path)?;
+        }
+    }
     config.save(&yaml_path)?;
 
-    println!(
-        "validator configurations initialized, file saved to: {:?}",
-        &yaml_path
+    progress::report(
+        progress::Verbosity::Normal,
+        &format!("{} configuration saved to: {:?}", filename, &yaml_path),
     );
     Ok(())
 }
 
+//////// 0L ////////
+/// Writes a random password, only readable by the owner, for Prometheus to scrape the metrics with
+fn write_metrics_password(path: &Path) -> Result<(), anyhow::Error> {
+    let password: String = OsRng
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(password.as_bytes())?;
+    progress::detail(&format!(
+        "metrics password of user {} written to {:?}",
+        DEFAULT_METRICS_USER, path
+    ));
+    Ok(())
+}
+
 // we need to both write the genesis file and return the waypoint so we can set it in the key_store.json
 fn make_genesis_file(
     output_dir: &PathBuf,
     prebuilt_genesis: &Option<PathBuf>,
-    repo: &Option<String>,
-    github_org: &Option<String>,
+    registry: &Option<SharedBackend>,
     layout_path: &Option<PathBuf>,
     storage_helper: StorageHelper,
     chain_id: ChainId,
     namespace: &str,
 ) -> Result<(PathBuf, Waypoint), anyhow::Error> {
+        trace_span!("node_files.genesis", prebuilt = prebuilt_genesis.is_some());
     let genesis_path = output_dir.join("genesis.blob");
     match prebuilt_genesis {
         Some(path) => {
+            progress::detail(&format!("using prebuilt genesis: {:?}", path));
             // TODO: insert waypoint
             let gen_wp_path = path.parent().unwrap().join("genesis_waypoint.txt");
             let wp_string = fs::read_to_string(&gen_wp_path)?;
@@ -255,28 +1302,25 @@ fn make_genesis_file(
             Ok((path.to_owned(), wp))
         }
         None => {
-            if repo.is_some() && github_org.is_some() {
-                let remote = format!(
-                    "backend=github;repository_owner={github_org};repository={repo};token={path};namespace={ns}",
-                    repo = repo.as_ref().unwrap(),
-                    github_org = github_org.as_ref().unwrap(),
-                    path = output_dir.join("github_token.txt").to_str().unwrap(),
-                    ns = &namespace
-                );
-                // building a genesis file requires a set_layout path. The default is for genesis to use a local set_layout file. Once a genesis occurs, the canonical chain can store the genesis information to github repo for future verification and creating a genesis blob.
-                let genesis_waypoint = match layout_path {
-                    Some(layout_path) => storage_helper
-                        .build_genesis_with_layout(chain_id, &remote, &genesis_path, &layout_path)?,
-                    None => {
-                        println!("attempting to get a set_layout file from the genesis repo");
-                        storage_helper
-                            .build_genesis_from_github(chain_id, &remote, &genesis_path)?
+            //////// 0L ////////
+            // building a genesis file requires a set_layout path. The default is for genesis to use a local set_layout file. Once a genesis occurs, the canonical chain can store the genesis information to the registry for future verification and creating a genesis blob.
+            match registry {
+                Some(registry) => {
+                    progress::detail(&format!("building genesis from the registry {}", registry));
+                    if layout_path.is_none() {
+                        progress::detail("attempting to get a set_layout file from the genesis repo");
                     }
-                };
-                Ok((genesis_path, genesis_waypoint))
-            } else {
-                println!("Expected either a prebuilt genesis file, or github repo and org to build a new genesis, exiting.");
-                exit(1);
+                    let genesis_waypoint = storage_helper.build_genesis_from_registry(
+                        chain_id,
+                        registry,
+                        output_dir,
+                        namespace,
+                        &genesis_path,
+                        layout_path,
+                    )?;
+                    Ok((genesis_path, genesis_waypoint))
+                }
+                None => Err(anyhow::anyhow!("Expected either a prebuilt genesis file, or a registry to build a new genesis")),
             }
         }
     }
@@ -287,6 +1331,7 @@ pub fn make_fullnode_cfg(
     output_dir: PathBuf,
     seed_addr: Option<SeedAddresses>,
     waypoint: Waypoint,
+    listen: &ListenAddresses,
 ) -> Result<NodeConfig, anyhow::Error> {
     let mut c = NodeConfig::default();
 
@@ -297,84 +1342,90 @@ pub fn make_fullnode_cfg(
     // c.execution.genesis_file_location = output_dir.clone().join("genesis.blob");
     // prune window exists to prevent state snapshots from taking up too much space.
     c.storage.prune_window = Some(100_000);
+    //////// 0L ////////
+    c.metrics.scrape = MetricsScrapeConfig::node_default(&output_dir);
     
     // Public fullnodes have JSON RPC enabled to the public (0.0.0.0), so that the validator does not need to do so.
     c.json_rpc.address = "0.0.0.0:8080".parse()?;
 
     // Public fullnodes only connect to one network. Public fullnodes network.
     let mut pub_network = NetworkConfig::network_with_id(NetworkId::Public);
-    pub_network.listen_address = format!("/ip4/0.0.0.0/tcp/{}", DEFAULT_PUB_PORT).parse()?;
+    pub_network.listen_address = listen.fullnode_listen.clone();
+    pub_network.dual_stack_address = listen.dual_stack_address(&pub_network.listen_address)?;
 
     if let Some(seeds) = seed_addr {
       pub_network.seed_addrs  = seeds;
     }
+    //////// 0L ////////
+    // Along with the seeds of the genesis, find the fullnodes the validators registered on chain
+    pub_network.discovery_method = DiscoveryMethod::Onchain;
 
     c.full_node_networks = vec![pub_network];
 
     Ok(c)
 }
 
-fn make_validator_cfg(output_dir: PathBuf, namespace: &str, seed_addresses: Option<SeedAddresses>) -> Result<NodeConfig, anyhow::Error> {
+fn make_validator_cfg(
+    output_dir: PathBuf,
+    namespace: &str,
+    seed_addresses: Option<SeedAddresses>,
+    listen: &ListenAddresses,
+) -> Result<NodeConfig, anyhow::Error> {
     // TODO: make the validator node have mutual authentication with VFN.
-    // for that it will need to get the Peer object of the VFN after the identity has been created
-    // by default the VFN identity is random.
-    let mut disk_storage = OnDiskStorageConfig::default();
-    disk_storage.set_data_dir(output_dir.clone());
-    disk_storage.path = output_dir.clone().join("key_store.json");
-    disk_storage.namespace = Some(namespace.to_owned());
-
     let mut c = NodeConfig::default();
 
     c.set_data_dir(output_dir.clone());
-    // Note skip setting namepace for later.
-    c.base.waypoint =
-        WaypointConfig::FromStorage(SecureBackend::OnDiskStorage(disk_storage.clone()));
     c.base.role = RoleType::Validator;
     // If validator configs set val network configs
     let mut network = NetworkConfig::network_with_id(NetworkId::Validator);
 
-    let network_id = Identity::from_storage(
-        VALIDATOR_NETWORK_KEY.to_string(),
-        OWNER_ACCOUNT.to_string(),
-        SecureBackend::OnDiskStorage(disk_storage.clone()),
-    );
     // NOTE: Using configs as described in cluster tests:
     // testsuite/cluster-test/src/cluster_swarm/configs/validator.yaml
     network.discovery_method = DiscoveryMethod::Onchain;
     network.mutual_authentication = true;
-    network.identity = network_id.clone(); // will also use for VFN.
-
-    network.network_address_key_backend = Some(SecureBackend::OnDiskStorage(disk_storage.clone()));
-
-    c.validator_network = Some(network.clone());
+    network.listen_address = listen.validator_listen.clone();
+    network.dual_stack_address = listen.dual_stack_address(&network.listen_address)?;
 
-    // Consensus
-    c.base.waypoint =
-        WaypointConfig::FromStorage(SecureBackend::OnDiskStorage(disk_storage.clone()));
+    c.validator_network = Some(network);
 
-    c.execution.backend = SecureBackend::OnDiskStorage(disk_storage.clone());
     c.execution.genesis_file_location = output_dir.clone().join("genesis.blob");
 
     c.consensus.safety_rules.service = SafetyRulesService::Thread;
-    c.consensus.safety_rules.backend = SecureBackend::OnDiskStorage(disk_storage.clone());
 
     c.storage.prune_window = Some(100_000);
+    //////// 0L ////////
+    c.metrics.scrape = MetricsScrapeConfig::node_default(&output_dir);
 
     //////////////// CREATE CONFIGS FOR CONNECTING TO VFN PRIVATE NETWORK ////////////////
     // this is the only fullnode network a validator should connect to, so to be isolated from public.
 
     // TODO: The validator's connection to VFN should be restricted to the vfn_ip_address.
     let mut vfn_net = NetworkConfig::network_with_id(NetworkId::Private("vfn".to_string()));
-    vfn_net.listen_address = format!("/ip4/0.0.0.0/tcp/{}", DEFAULT_VFN_PORT).parse()?;
+    vfn_net.listen_address = listen.vfn_listen.clone();
+    vfn_net.dual_stack_address = listen.dual_stack_address(&vfn_net.listen_address)?;
+    //////// 0L ////////
+    // The VFN is known by its own network key, see `VFN_NETWORK_KEY`
+    let mut storage = get_default_keystore_helper(output_dir.clone()).storage(namespace.to_string());
+    let (vfn_key, vfn_peer_id) = vfn_network_identity(&mut storage)?;
+    vfn_net.seeds.insert(
+        vfn_peer_id,
+        Peer::new(vec![], std::iter::once(vfn_key).collect(), PeerRole::ValidatorFullNode),
+    );
 
     let mut pub_net = NetworkConfig::network_with_id(NetworkId::Public);
     
-    pub_net.listen_address = format!("/ip4/127.0.0.1/tcp/{}", DEFAULT_PUB_PORT).parse()?; // Don't fullnode sync requests
+    // Don't fullnode sync requests
+    pub_net.listen_address = format!(
+        "/ip4/127.0.0.1/tcp/{}",
+        ListenAddresses::port(&listen.fullnode_listen)?
+    )
+    .parse()?;
+    pub_net.dual_stack_address = listen.dual_stack_address(&pub_net.listen_address)?;
     
     // This ID is how the Validator node identifies themselves on their private VFN network.
     // same ID as being used in the validator network.
     // Note that the the public network has no setting, so that it is randomly generated. 
-    vfn_net.identity = network_id;
+    // The identity is set with the other keys in set_secure_backend.
 
     if let Some(s) = seed_addresses {
       pub_net.seed_addrs = s;
@@ -391,15 +1442,55 @@ fn make_validator_cfg(output_dir: PathBuf, namespace: &str, seed_addresses: Opti
     // NOTE: Validator does not have public JSON RPC enabled. Only for localhost queries
     // this is set with the NodeConfig defaults.
 
+    //////// 0L ////////
+    set_secure_backend(&mut c, &disk_backend(&output_dir, namespace));
+
     Ok(c)
 }
 
+//////// 0L ////////
+/// The key_store.json of the data path
+fn disk_backend(output_dir: &Path, namespace: &str) -> SecureBackend {
+    let mut disk_storage = OnDiskStorageConfig::default();
+    disk_storage.set_data_dir(output_dir.to_path_buf());
+    disk_storage.path = output_dir.join("key_store.json");
+    disk_storage.namespace = Some(namespace.to_owned());
+    SecureBackend::OnDiskStorage(disk_storage)
+}
+
+/// Points the waypoint, execution, safety rules, network identities and network address keys
+/// of a validator config to `backend`
+pub fn set_secure_backend(config: &mut NodeConfig, backend: &SecureBackend) {
+    config.base.waypoint = WaypointConfig::FromStorage(backend.clone());
+    config.execution.backend = backend.clone();
+    config.consensus.safety_rules.backend = backend.clone();
+
+    let identity = Identity::from_storage(
+        VALIDATOR_NETWORK_KEY.to_string(),
+        OWNER_ACCOUNT.to_string(),
+        backend.clone(),
+    );
+    if let Some(network) = config.validator_network.as_mut() {
+        network.identity = identity.clone();
+        network.network_address_key_backend = Some(backend.clone());
+    }
+    // The validator uses its validator network identity on the private network with its VFN
+    for network in config
+        .full_node_networks
+        .iter_mut()
+        .filter(|network| network.network_id.is_vfn_network())
+    {
+        network.identity = identity.clone();
+    }
+}
+
 /// make the fullnode NodeConfig
 pub fn make_vfn_cfg(
     output_dir: PathBuf,
     waypoint: Waypoint,
-    val_ip_address: Ipv4Addr,
+    val_ip_address: IpAddr,
     namespace: &str,
+    listen: &ListenAddresses,
 ) -> Result<NodeConfig, anyhow::Error> {
     let mut c = NodeConfig::default();
 
@@ -408,10 +1499,15 @@ pub fn make_vfn_cfg(
     c.set_data_dir(output_dir.clone());
     c.base.waypoint = WaypointConfig::FromConfig(waypoint);
     c.base.role = RoleType::FullNode;
-    // c.execution.genesis_file_location = output_dir.clone().join("genesis.blob");
+    // Like any fullnode, the VFN bootstraps its db from genesis
+    c.execution.genesis_file_location = output_dir.clone().join("genesis.blob");
 
-    let storage = storage_helper.storage(namespace.to_string());
-    c.storage.prune_window = Some(100_000);
+    let mut storage = storage_helper.storage(namespace.to_string());
+    //////// 0L ////////
+    // The VFN serves the recent history only, see `PrunePreset::Minimal`
+    c.storage.apply_prune_preset(PrunePreset::Minimal);
+    //////// 0L ////////
+    c.metrics.scrape = MetricsScrapeConfig::node_default(&output_dir);
 
     
 
@@ -423,14 +1519,20 @@ pub fn make_vfn_cfg(
     // the VFN announces itself as the owner address, but uses FULLNODE private key to authenticate.
     // make the fullnode discoverable by the account address of the validator owner.
     let owner_address_as_fn_id = storage.get(OWNER_ACCOUNT)?.value;
-    
-    // TODO: determine if we want deterministic identity for the PRIVATE NETWORK. 
 
     // A VFN has two fullnode networks it participates in.
     // 1. A private network with the Validator.
     // 2. the fullnode network. The fullnode network cannot exist unless the VFN briges the validators to the public.
 
-    // vfn_network.identity = // KEYS ARE RANDOMLY SET IF UNDEFINED
+    //////// 0L ////////
+    // The VFN authenticates to the validator with a network key of its own, the validator knows
+    // it from its seeds.
+    vfn_network_identity(&mut storage)?;
+    vfn_network.identity = Identity::from_storage(
+        VFN_NETWORK_KEY.to_string(),
+        VFN_NETWORK_PEER_ID.to_string(),
+        disk_backend(&output_dir, namespace),
+    );
 
     // set the Validator as the Seed peer for the VFN network
     // need to get their ID and IP address
@@ -442,14 +1544,18 @@ pub fn make_vfn_cfg(
     let seeds = encode_validator_seed_for_vfn_discovery(
       owner_address_as_fn_id, 
       p.public_key(), 
-      val_ip_address
+      val_ip_address,
+      ListenAddresses::port(&listen.vfn_listen)?,
     )?;
 
     // The seed for the VFN is the validator's ID on the private network.
     vfn_network.seeds = seeds;
+    // The validator is the only peer of the VFN on the private network.
+    vfn_network.max_outbound_connections = 1;
 
     // TODO: This should be restricted to receiving connections from a known peer.
-    vfn_network.listen_address = format!("/ip4/0.0.0.0/tcp/{}", DEFAULT_VFN_PORT).parse()?;
+    vfn_network.listen_address = listen.vfn_listen.clone();
+    vfn_network.dual_stack_address = listen.dual_stack_address(&vfn_network.listen_address)?;
 
     //////////////// CREATE CONFIGS FOR CONNECTING TO PUBLIC FULLNODES ////////////////
 
@@ -469,14 +1575,16 @@ pub fn make_vfn_cfg(
     pub_network.identity = id_of_vfn_node;
 
     // this port accepts connections from unknown peers.
-    pub_network.listen_address = format!("/ip4/0.0.0.0/tcp/{}", DEFAULT_PUB_PORT).parse()?;
+    pub_network.listen_address = listen.fullnode_listen.clone();
+    pub_network.dual_stack_address = listen.dual_stack_address(&pub_network.listen_address)?;
 
     // NOTE: VFNs do not serve JSON RPC Requests.
 
     c.full_node_networks = vec![vfn_network, pub_network];
 
-    // pick the order of the networks to connect to if the VFN network is not reachable.
-    c.upstream.networks = vec![NetworkId::Public];
+    // The validator, through the private network, is the upstream of the VFN. Fall back to the
+    // public network if it is not reachable.
+    c.upstream.networks = vec![NetworkId::vfn_network(), NetworkId::Public];
 
     Ok(c)
 }
@@ -486,16 +1594,339 @@ pub fn make_vfn_cfg(
 fn encode_validator_seed_for_vfn_discovery(
     validator_account: AccountAddress,
     val_net_pubkey: PublicKey,
-    ip_address: Ipv4Addr,
+    ip_address: IpAddr,
+    vfn_port: u64,
 ) -> Result<PeerSet, Error> {
     // construct seed peer info, using the validator's ID it uses on the private network VALIDATOR_NETWORK_KEY
 
     let role = PeerRole::Validator;
-    let val_addr = ValConfigs::make_unencrypted_addr(&ip_address, val_net_pubkey, NetworkId::Private("vfn".to_owned()));
+    // The validator listens to its VFN on the port of --vfn-listen
+    // An ip6 val-ip-address makes an /ip6 seed
+    let val_addr = NetworkAddress::from(SocketAddr::new(ip_address, vfn_port as u16))
+        .append_prod_protos(val_net_pubkey, HANDSHAKE_VERSION);
     let val_peer_data = Peer::from_addrs(role, vec![val_addr]);
 
     // The seed address for the VFN can only be the Validator's address.
     let mut seeds = PeerSet::default();
     seeds.insert(validator_account, val_peer_data);
     Ok(seeds)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::simulate_ceremony;
+    use diem_config::config::SafetyRulesConfig;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_vfn_file() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let ceremony = simulate_ceremony(1, ChainId::test(), dir.path()).unwrap();
+        let participant = &ceremony.participants[0];
+        let vfn = NodeConfig::load_config(
+            participant
+                .dir
+                .join(NodeType::ValidatorFullNode.file_name()),
+        )
+        .unwrap();
+
+        assert_eq!(vfn.base.role, RoleType::FullNode);
+        assert_eq!(
+            vfn.upstream.networks,
+            vec![NetworkId::vfn_network(), NetworkId::Public]
+        );
+
+        // The validator is the single seed of the private network, on the port it listens to
+        let vfn_network = vfn
+            .full_node_networks
+            .iter()
+            .find(|network| network.network_id.is_vfn_network())
+            .unwrap();
+        let validator_id = participant
+            .validator
+            .validator_network
+            .as_ref()
+            .unwrap()
+            .peer_id();
+        assert_eq!(vfn_network.seeds.len(), 1);
+        let seed = &vfn_network.seeds[&validator_id];
+        assert_eq!(seed.role, PeerRole::Validator);
+        assert!(seed.addresses[0]
+            .to_string()
+            .contains(&format!("/tcp/{}/", DEFAULT_VFN_PORT)));
+
+        // The VFN authenticates with a network key of its own, which the validator knows
+        assert_eq!(vfn_network.identity_from_storage().key_name, VFN_NETWORK_KEY);
+        let vfn_id = vfn_network.peer_id();
+        assert_ne!(vfn_id, validator_id);
+        let validator_vfn_network = participant
+            .validator
+            .full_node_networks
+            .iter()
+            .find(|network| network.network_id.is_vfn_network())
+            .unwrap();
+        assert_eq!(
+            validator_vfn_network.seeds[&vfn_id].role,
+            PeerRole::ValidatorFullNode
+        );
+    }
+
+    #[test]
+    fn test_listen_ports() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let mut listen = ListenAddresses::default();
+        listen.validator_listen = "/ip4/0.0.0.0/tcp/7180".parse().unwrap();
+        let config =
+            make_validator_cfg(dir.path().to_path_buf(), "alice", None, &listen).unwrap();
+        config.validate().unwrap();
+        assert_eq!(
+            config.validator_network.as_ref().unwrap().listen_address,
+            listen.validator_listen
+        );
+
+        // The validator serves the public network on localhost, on the port of the fullnodes
+        listen.fullnode_listen = "/ip4/0.0.0.0/tcp/7180".parse().unwrap();
+        let config =
+            make_validator_cfg(dir.path().to_path_buf(), "alice", None, &listen).unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_dual_stack() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let mut listen = ListenAddresses::default();
+        listen.dual_stack = true;
+        let config =
+            make_validator_cfg(dir.path().to_path_buf(), "alice", None, &listen).unwrap();
+        config.validate().unwrap();
+        assert_eq!(
+            config.validator_network.as_ref().unwrap().dual_stack_address,
+            Some("/ip6/::/tcp/6180".parse().unwrap())
+        );
+        // The public network of the validator stays on localhost, of both versions
+        assert_eq!(
+            config.full_node_networks[1].dual_stack_address,
+            Some("/ip6/::1/tcp/6178".parse().unwrap())
+        );
+
+        // An ip6 listen address also listens on ip4
+        listen.validator_listen = "/ip6/::/tcp/7180".parse().unwrap();
+        let config =
+            make_validator_cfg(dir.path().to_path_buf(), "alice", None, &listen).unwrap();
+        config.validate().unwrap();
+        assert_eq!(
+            config.validator_network.as_ref().unwrap().dual_stack_address,
+            Some("/ip4/0.0.0.0/tcp/7180".parse().unwrap())
+        );
+
+        // A specific ip has no counterpart of the other version
+        listen.validator_listen = "/ip4/10.0.0.1/tcp/6180".parse().unwrap();
+        assert!(make_validator_cfg(dir.path().to_path_buf(), "alice", None, &listen).is_err());
+    }
+
+    #[test]
+    fn test_set_secure_backend() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let mut config = make_validator_cfg(
+            dir.path().to_path_buf(),
+            "alice",
+            None,
+            &ListenAddresses::default(),
+        )
+        .unwrap();
+        assert!(serde_yaml::to_string(&config)
+            .unwrap()
+            .contains("key_store.json"));
+
+        let vault = SecureBackend::Vault(VaultConfig {
+            ca_certificate: Some(PathBuf::from("/opt/vault/ca.pem")),
+            namespace: Some("alice".to_string()),
+            renew_ttl_secs: None,
+            server: "https://127.0.0.1:8200".to_string(),
+            token: Token::FromDisk(PathBuf::from("/opt/vault/token")),
+            disable_cas: None,
+            connection_timeout_ms: None,
+            response_timeout_ms: None,
+        });
+        set_secure_backend(&mut config, &vault);
+        assert_eq!(config.execution.backend, vault);
+        assert_eq!(config.consensus.safety_rules.backend, vault);
+        let validator_network = config.validator_network.as_ref().unwrap();
+        assert_eq!(
+            validator_network.network_address_key_backend,
+            Some(vault.clone())
+        );
+        // No key is left on disk
+        assert!(!serde_yaml::to_string(&config)
+            .unwrap()
+            .contains("key_store.json"));
+
+        assert_eq!(
+            "in-memory".parse::<NodeBackend>().unwrap(),
+            NodeBackend::InMemory
+        );
+        assert!("s3".parse::<NodeBackend>().is_err());
+    }
+
+    #[test]
+    fn test_apply_template() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let ceremony = simulate_ceremony(1, ChainId::test(), dir.path()).unwrap();
+        let output_dir = &ceremony.participants[0].dir;
+        let template_path = output_dir.join("template.yaml");
+        fs::write(
+            &template_path,
+            "storage:\n  prune_window: 50000\nvfn:\n  mempool:\n    capacity: 1000\n",
+        )
+        .unwrap();
+
+        let validator = apply_template(output_dir, &template_path, NodeType::Validator).unwrap();
+        assert_eq!(validator.storage.prune_window, Some(50_000));
+        assert_ne!(validator.mempool.capacity, 1000);
+        let vfn = apply_template(output_dir, &template_path, NodeType::ValidatorFullNode).unwrap();
+        assert_eq!(vfn.mempool.capacity, 1000);
+        let saved =
+            NodeConfig::load_config(output_dir.join(NodeType::ValidatorFullNode.file_name()))
+                .unwrap();
+        assert_eq!(saved, vfn);
+
+        fs::write(&template_path, "json_rpc: 8080\n").unwrap();
+        assert!(matches!(
+            apply_template(output_dir, &template_path, NodeType::PublicFullNode),
+            Err(Error::ConfigInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_apply_onchain_seeds() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let ceremony = simulate_ceremony(1, ChainId::test(), dir.path()).unwrap();
+        let output_dir = &ceremony.participants[0].dir;
+
+        let validator = apply_onchain_seeds(output_dir).unwrap();
+        let fullnode =
+            NodeConfig::load_config(output_dir.join(NodeType::PublicFullNode.file_name())).unwrap();
+        for config in &[validator, fullnode] {
+            let public = config
+                .full_node_networks
+                .iter()
+                .find(|network| network.network_id == NetworkId::Public)
+                .unwrap();
+            assert_eq!(public.discovery_method, DiscoveryMethod::Onchain);
+            assert!(public.seed_addrs.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_apply_prune_preset() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let ceremony = simulate_ceremony(1, ChainId::test(), dir.path()).unwrap();
+        let output_dir = &ceremony.participants[0].dir;
+
+        let vfn = NodeConfig::load_config(output_dir.join(NodeType::ValidatorFullNode.file_name()))
+            .unwrap();
+        assert_eq!(vfn.storage.prune_windows(), PrunePreset::Minimal.windows());
+
+        let validator = apply_prune_preset(output_dir, PrunePreset::Archive).unwrap();
+        assert!(!validator.storage.prune_windows().is_pruning());
+        for node_type in NODE_TYPES {
+            let config = NodeConfig::load_config(output_dir.join(node_type.file_name())).unwrap();
+            assert!(!config.storage.prune_windows().is_pruning());
+        }
+    }
+
+    #[test]
+    fn test_apply_remote_safety_rules() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let ceremony = simulate_ceremony(1, ChainId::test(), dir.path()).unwrap();
+        let output_dir = &ceremony.participants[0].dir;
+        let address: NetworkAddress = "/ip4/10.0.0.2/tcp/6191".parse().unwrap();
+
+        let validator = apply_remote_safety_rules(
+            output_dir,
+            &address,
+            Some(Path::new("/opt/diem/tls")),
+            Some(5_000),
+        )
+        .unwrap();
+        match &validator.consensus.safety_rules.service {
+            SafetyRulesService::Process(service) => {
+                assert_eq!(service.server_address, address);
+                assert_eq!(service.health_check_interval_ms, Some(5_000));
+                let tls = service.tls.as_ref().unwrap();
+                assert_eq!(tls.cert_path, Path::new("/opt/diem/tls/validator.pem"));
+                assert_eq!(tls.server_name, "safety-rules");
+            }
+            service => panic!("Unexpected service {:?}", service),
+        }
+
+        // The process serves the validator from the same backend
+        let process = SafetyRulesConfig::load_config(output_dir.join(SAFETY_RULES_FILE)).unwrap();
+        assert_eq!(process.backend, validator.consensus.safety_rules.backend);
+        match &process.service {
+            SafetyRulesService::Process(service) => {
+                assert_eq!(
+                    service.server_address(),
+                    "0.0.0.0:6191".parse::<SocketAddr>().unwrap()
+                );
+                let tls = service.tls.as_ref().unwrap();
+                assert_eq!(
+                    tls.key_path,
+                    Path::new("/opt/diem/tls/safety_rules_key.pem")
+                );
+                assert_eq!(tls.allowed_clients, vec!["validator".to_string()]);
+            }
+            service => panic!("Unexpected service {:?}", service),
+        }
+    }
+
+    #[test]
+    fn test_render_config() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let ceremony = simulate_ceremony(1, ChainId::test(), dir.path()).unwrap();
+        let participant = &ceremony.participants[0];
+        let output_dir = &participant.dir;
+        for node_type in NODE_TYPES {
+            fs::remove_file(output_dir.join(node_type.file_name())).unwrap();
+        }
+        let key_store = fs::read(output_dir.join("key_store.json")).unwrap();
+
+        // The waypoint is read from the key store, and neither keys nor genesis are touched
+        render_config(
+            output_dir,
+            &participant.namespace,
+            None,
+            None,
+            Ipv4Addr::LOCALHOST.into(),
+            &ListenAddresses::default(),
+        )
+        .unwrap();
+        let fullnode =
+            NodeConfig::load_config(output_dir.join(NodeType::PublicFullNode.file_name())).unwrap();
+        assert_eq!(fullnode.base.waypoint.waypoint(), ceremony.waypoint);
+        assert!(!fullnode.full_node_networks[0].seed_addrs.is_empty());
+        assert!(output_dir.join(NodeType::Validator.file_name()).exists());
+        assert_eq!(
+            fs::read(output_dir.join("key_store.json")).unwrap(),
+            key_store
+        );
+
+        // Keys are only replaced on request
+        let keys = || KeyScheme::new_from_mnemonic(ol_types::fixtures::get_persona_mnem("alice"));
+        init_keys(output_dir, "alice", keys(), false).unwrap();
+        assert!(matches!(
+            init_keys(output_dir, "alice", keys(), false),
+            Err(Error::CommandArgumentError(_))
+        ));
+        init_keys(output_dir, "alice", keys(), true).unwrap();
+    }
+}
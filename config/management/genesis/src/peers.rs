@@ -0,0 +1,251 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! `peers` subcommands, for maintaining the seed peers of file-based setups and the reputation
+//! of the peers of a node.
+
+use crate::{seeds::SeedAddresses, validator_set::ValidatorSetSource};
+use diem_config::{
+    config::{NodeConfig, PersistableConfig},
+    network_id::NetworkId,
+};
+use diem_management::error::Error;
+use diem_time_service::TimeService;
+use diem_types::{on_chain_config::ValidatorSet, PeerId};
+use network::peer_reputation::PeerReputationStore;
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum PeersCommand {
+    #[structopt(about = "Imports the VFNs of the on-chain validator set as seed peers")]
+    ImportOnchain(ImportOnchain),
+    #[structopt(about = "Lists the peers that misbehaved, and their bans")]
+    Reputations(Reputations),
+    #[structopt(about = "Clears the reputation of a peer, or of every peer, lifting their bans")]
+    ClearReputations(ClearReputations),
+}
+
+impl PeersCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            PeersCommand::ImportOnchain(import) => import.execute(),
+            PeersCommand::Reputations(reputations) => reputations.execute(),
+            PeersCommand::ClearReputations(clear) => clear.execute(),
+        }
+    }
+}
+
+/// Fetches the validator set from a node and writes the VFN addresses in the seed peers format
+#[derive(Debug, StructOpt)]
+pub struct ImportOnchain {
+    /// JSON-RPC endpoint of a node, e.g. http://localhost:8080
+    #[structopt(long)]
+    json_rpc: String,
+    /// Write the seed peers to this file instead of printing them
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Node config to merge the seed peers into, on its public networks
+    #[structopt(long, parse(from_os_str))]
+    merge_into: Option<PathBuf>,
+}
+
+impl ImportOnchain {
+    pub fn execute(self) -> Result<String, Error> {
+        let validator_set = ValidatorSetSource::from_json_rpc(self.json_rpc).validator_set()?;
+        let seeds = seeds_from_validator_set(&validator_set);
+        let yaml = serde_yaml::to_string(&seeds)
+            .map_err(|e| Error::UnexpectedError(format!("Unable to serialize seeds: {}", e)))?;
+
+        let mut report = match &self.output {
+            Some(output) => {
+                fs::write(output, &yaml).map_err(|e| Error::IO(output.display().to_string(), e))?;
+                format!("{} seed peers written to {}", seeds.len(), output.display())
+            }
+            None => yaml,
+        };
+
+        if let Some(path) = &self.merge_into {
+            let mut config = NodeConfig::load_config(path).map_err(|e| Error::ConfigInvalid {
+                path: path.display().to_string(),
+                reason: e.to_string(),
+            })?;
+            let networks = merge_seeds(&mut config, &seeds);
+            if networks == 0 {
+                return Err(Error::ConfigInvalid {
+                    path: path.display().to_string(),
+                    reason: "the config has no public network to merge the seed peers into"
+                        .to_string(),
+                });
+            }
+            config
+                .save_config(path)
+                .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+            report.push_str(&format!(
+                "\nMerged {} seed peers into {} network(s) of {}",
+                seeds.len(),
+                networks,
+                path.display()
+            ));
+        }
+        Ok(report)
+    }
+}
+
+/// Opens the peer reputation store of a node config. The node must be stopped, as it keeps its
+/// own copy of the store and overwrites it on the next misbehavior.
+fn open_reputation_store(config: &PathBuf) -> Result<PeerReputationStore, Error> {
+    let node_config = NodeConfig::load(config).map_err(|e| Error::ConfigInvalid {
+        path: config.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok(PeerReputationStore::new(
+        &node_config.peer_reputation,
+        TimeService::real(),
+    ))
+}
+
+/// Prints the reputation of every peer that misbehaved, as kept by a stopped node
+#[derive(Debug, StructOpt)]
+pub struct Reputations {
+    /// Path to the node config, e.g. fullnode.node.yaml
+    #[structopt(long, parse(from_os_str))]
+    config: PathBuf,
+}
+
+impl Reputations {
+    pub fn execute(self) -> Result<String, Error> {
+        let store = open_reputation_store(&self.config)?;
+        let mut reputations: Vec<_> = store.reputations().into_iter().collect();
+        reputations.sort_by_key(|(peer, _)| (peer.raw_network_id().to_string(), peer.peer_id()));
+        let lines: Vec<_> = reputations
+            .iter()
+            .map(|(peer, reputation)| {
+                format!(
+                    "{} {}: {} violation(s), last {:?}, {} ban(s){}",
+                    peer.raw_network_id(),
+                    peer.peer_id(),
+                    reputation.violations,
+                    reputation.last_misbehavior,
+                    reputation.bans,
+                    if store.is_banned(peer) {
+                        format!(", banned until {}", reputation.banned_until_secs)
+                    } else {
+                        String::new()
+                    }
+                )
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Forgets the reputation of a peer on every network, or of every peer, while the node is stopped
+#[derive(Debug, StructOpt)]
+pub struct ClearReputations {
+    /// Path to the node config, e.g. fullnode.node.yaml
+    #[structopt(long, parse(from_os_str))]
+    config: PathBuf,
+    /// The peer to clear, every peer if omitted
+    #[structopt(long)]
+    peer_id: Option<PeerId>,
+}
+
+impl ClearReputations {
+    pub fn execute(self) -> Result<String, Error> {
+        let store = open_reputation_store(&self.config)?;
+        let peer_id = match self.peer_id {
+            Some(peer_id) => peer_id,
+            None => {
+                store.clear(None);
+                return Ok("Cleared the reputation of every peer".to_string());
+            }
+        };
+        let peers: Vec<_> = store
+            .reputations()
+            .into_iter()
+            .map(|(peer, _)| peer)
+            .filter(|peer| peer.peer_id() == peer_id)
+            .collect();
+        for peer in &peers {
+            store.clear(Some(peer));
+        }
+        Ok(format!(
+            "Cleared the reputation of {} on {} network(s)",
+            peer_id,
+            peers.len()
+        ))
+    }
+}
+
+/// The fullnode addresses of the validators, keyed by validator account as the seed peers
+/// refreshed by the `ol` cli
+pub fn seeds_from_validator_set(validator_set: &ValidatorSet) -> SeedAddresses {
+    validator_set
+        .payload()
+        .iter()
+        .filter_map(|info| {
+            let addresses = info.config().fullnode_network_addresses().ok()?;
+            if addresses.is_empty() {
+                None
+            } else {
+                Some((*info.account_address(), addresses))
+            }
+        })
+        .collect()
+}
+
+/// Adds the seeds to the `seed_addrs` of the public networks, replacing the addresses of peers
+/// already present. Returns the number of networks updated.
+pub fn merge_seeds(config: &mut NodeConfig, seeds: &SeedAddresses) -> usize {
+    let mut networks = 0;
+    for network in config
+        .full_node_networks
+        .iter_mut()
+        .filter(|network| network.network_id == NetworkId::Public)
+    {
+        network
+            .seed_addrs
+            .extend(seeds.iter().map(|(peer, addrs)| (*peer, addrs.clone())));
+        networks += 1;
+    }
+    networks
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::genesis_fixture;
+    use diem_config::config::NetworkConfig;
+    use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
+
+    #[test]
+    fn test_merge_seeds() {
+        let fixture = genesis_fixture(2, ChainId::test());
+        let validator_set = ValidatorSetSource::from_genesis(fixture.genesis_path())
+            .validator_set()
+            .unwrap();
+        let seeds = seeds_from_validator_set(&validator_set);
+        assert_eq!(seeds.len(), 2);
+
+        let existing = PeerId::random();
+        let mut config = NodeConfig::default();
+        let mut public = NetworkConfig::network_with_id(NetworkId::Public);
+        let address: NetworkAddress = "/ip4/10.0.0.1/tcp/6179".parse().unwrap();
+        public.seed_addrs.insert(existing, vec![address]);
+        config.full_node_networks = vec![
+            NetworkConfig::network_with_id(NetworkId::vfn_network()),
+            public,
+        ];
+
+        assert_eq!(merge_seeds(&mut config, &seeds), 1);
+        assert!(config.full_node_networks[0].seed_addrs.is_empty());
+        let merged = &config.full_node_networks[1].seed_addrs;
+        assert_eq!(merged.len(), 3);
+        assert!(merged.contains_key(&existing));
+        for (peer, addresses) in &seeds {
+            assert_eq!(&merged[peer], addresses);
+        }
+    }
+}
@@ -0,0 +1,765 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The remote registries a genesis ceremony shares its registrations through. GitHub is read
+//! through its API, as any shared backend. GitLab and plain Git remotes are cloned, and their
+//! checkout read as a local storage, so a ceremony only depends on git and on the credentials of
+//! its remote. The `registry` subcommands list the namespaces of a registry and check their
+//! registrations, so that a coordinator finds the incomplete ones before building genesis.
+
+use crate::{ceremony, layout::Layout};
+use diem_crypto::ed25519::Ed25519PublicKey;
+use diem_github_client::Client;
+use diem_global_constants::{
+    ACCOUNT_PROFILE, OPERATOR_KEY, OWNER_KEY, PROOF_OF_WORK_PREIMAGE, PROOF_OF_WORK_PROOF,
+    REGISTRATION_SIGNATURE,
+};
+use diem_management::{
+    config::Config,
+    constants,
+    error::Error,
+    progress,
+    secure_backend::{SecureBackend as ManagementBackend, DISK},
+    storage::StorageWrapper,
+    trace_span,
+};
+use diem_types::{
+    account_address,
+    network_address::NetworkAddress,
+    transaction::{Transaction, TransactionPayload},
+};
+use ol_types::account::ValConfigs;
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+use structopt::StructOpt;
+
+/// The GitHub token, in the data path of `files`
+pub const GITHUB_TOKEN_FILE: &str = "github_token.txt";
+/// The GitLab token, in the data path of `files`
+pub const GITLAB_TOKEN_FILE: &str = "gitlab_token.txt";
+/// The checkout of a Git registry, and the local storage converted from it
+const CHECKOUT_DIR: &str = "genesis_registry";
+const CHECKOUT_STORE: &str = "genesis_registry.json";
+
+/// A registry the registrations of the ceremony are read from
+pub trait RemoteRegistry {
+    /// The `--shared-backend` of the registrations of `namespace`. Credentials and checkouts are
+    /// in `work_dir`.
+    fn shared_backend(&self, work_dir: &Path, namespace: &str) -> Result<String, Error>;
+}
+
+/// The remote shared backend of a genesis ceremony
+#[derive(Clone, Debug, PartialEq)]
+pub enum SharedBackend {
+    /// github:ORG/REPO, with a token in `github_token.txt`
+    GitHub { org: String, repo: String },
+    /// gitlab:HOST/GROUP/REPO, with a token in `gitlab_token.txt`
+    GitLab {
+        host: String,
+        group: String,
+        repo: String,
+    },
+    /// git:URL, e.g. git:git@example.com:org/genesis.git, with the credentials of ssh
+    Git { url: String },
+}
+
+impl SharedBackend {
+    /// The GitHub repo given by the `--github-org` and `--repo` flags, if both are
+    pub fn from_github(org: Option<String>, repo: Option<String>) -> Option<Self> {
+        Some(SharedBackend::GitHub {
+            org: org?,
+            repo: repo?,
+        })
+    }
+}
+
+impl FromStr for SharedBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::CommandArgumentError(format!(
+                "Unknown registry {}, expected github:ORG/REPO, gitlab:HOST/GROUP/REPO or git:URL",
+                s
+            ))
+        };
+        let (kind, location) = s.split_once(':').ok_or_else(invalid)?;
+        match kind {
+            "github" => match location.split_once('/') {
+                Some((org, repo)) if !org.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+                    Ok(SharedBackend::GitHub {
+                        org: org.into(),
+                        repo: repo.into(),
+                    })
+                }
+                _ => Err(invalid()),
+            },
+            "gitlab" => {
+                // Groups may have subgroups, the host is first and the repo last
+                let (host, path) = location.split_once('/').ok_or_else(invalid)?;
+                let (group, repo) = path.rsplit_once('/').ok_or_else(invalid)?;
+                if host.is_empty() || group.is_empty() || repo.is_empty() {
+                    return Err(invalid());
+                }
+                Ok(SharedBackend::GitLab {
+                    host: host.into(),
+                    group: group.into(),
+                    repo: repo.into(),
+                })
+            }
+            "git" if !location.is_empty() => Ok(SharedBackend::Git {
+                url: location.into(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for SharedBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SharedBackend::GitHub { org, repo } => write!(f, "github:{}/{}", org, repo),
+            SharedBackend::GitLab { host, group, repo } => {
+                write!(f, "gitlab:{}/{}/{}", host, group, repo)
+            }
+            SharedBackend::Git { url } => write!(f, "git:{}", url),
+        }
+    }
+}
+
+impl RemoteRegistry for SharedBackend {
+    fn shared_backend(&self, work_dir: &Path, namespace: &str) -> Result<String, Error> {
+        Ok(format!(
+            "{};namespace={}",
+            self.backend(work_dir)?,
+            namespace
+        ))
+    }
+}
+
+impl SharedBackend {
+    /// The `--shared-backend` of the registry, without a namespace
+    fn backend(&self, work_dir: &Path) -> Result<String, Error> {
+        match self {
+            SharedBackend::GitHub { org, repo } => Ok(format!(
+                "backend=github;repository_owner={};repository={};token={}",
+                org,
+                repo,
+                work_dir.join(GITHUB_TOKEN_FILE).display(),
+            )),
+            SharedBackend::GitLab { host, group, repo } => {
+                let token_path = work_dir.join(GITLAB_TOKEN_FILE);
+                let token = fs::read_to_string(&token_path)
+                    .map_err(|e| Error::IO(token_path.display().to_string(), e))?;
+                // The token goes in a header rather than in the url, which git would persist
+                let header = format!(
+                    "http.extraHeader=Authorization: Basic {}",
+                    base64::encode(format!("oauth2:{}", token.trim()))
+                );
+                let url = format!("https://{}/{}/{}.git", host, group, repo);
+                clone_registry(work_dir, &url, &["-c", &header])
+            }
+            SharedBackend::Git { url } => clone_registry(work_dir, url, &[]),
+        }
+    }
+
+    /// Opens the registry for inspection, listing the namespaces registrations were made to
+    pub fn open(&self, work_dir: &Path) -> Result<OpenedRegistry, Error> {
+        trace_span!("registry.open", registry = %self);
+        let backend = self.backend(work_dir)?;
+        let namespaces = match self {
+            SharedBackend::GitHub { org, repo } => {
+                let token_path = work_dir.join(GITHUB_TOKEN_FILE);
+                let token = fs::read_to_string(&token_path)
+                    .map_err(|e| Error::IO(token_path.display().to_string(), e))?;
+                let github = Client::new(
+                    org.clone(),
+                    repo.clone(),
+                    "master".into(),
+                    token.trim().into(),
+                );
+                trace_span!("github.get_directory");
+                github
+                    .get_directory("")
+                    .map_err(|e| Error::StorageReadError("github", "namespaces", e.to_string()))?
+                    .into_iter()
+                    .filter_map(|entry| entry.strip_suffix('/').map(String::from))
+                    .filter(|namespace| !namespace.starts_with('.'))
+                    .collect()
+            }
+            _ => store_namespaces(&work_dir.join(CHECKOUT_STORE))?,
+        };
+        Ok(OpenedRegistry::new(backend, namespaces))
+    }
+}
+
+/// Clones the registry in `work_dir` and converts its checkout to a local storage
+fn clone_registry(work_dir: &Path, url: &str, git_options: &[&str]) -> Result<String, Error> {
+    let checkout = work_dir.join(CHECKOUT_DIR);
+    if checkout.exists() {
+        fs::remove_dir_all(&checkout).map_err(|e| Error::IO(checkout.display().to_string(), e))?;
+    }
+    trace_span!("registry.clone", url);
+    progress::detail(&format!("cloning the genesis registry {}", url));
+    let status = Command::new("git")
+        .args(git_options)
+        .args(&["clone", "--quiet", "--depth", "1", url])
+        .arg(&checkout)
+        .status()
+        .map_err(|e| Error::IO("git".into(), e))?;
+    if !status.success() {
+        return Err(Error::UnexpectedError(format!(
+            "Unable to clone the genesis registry {}: git {}",
+            url, status
+        )));
+    }
+
+    let store = work_dir.join(CHECKOUT_STORE);
+    checkout_to_storage(&checkout, &store)?;
+    Ok(format!("backend={};path={}", DISK, store.display()))
+}
+
+/// Converts the checkout of a registry to an on disk storage. As written by the GitHub storage,
+/// each `namespace/key` file holds the base64 of the json stored value.
+pub fn checkout_to_storage(checkout: &Path, store: &Path) -> Result<(), Error> {
+    let mut data = HashMap::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        let dir = checkout.join(&relative);
+        let entries = fs::read_dir(&dir).map_err(|e| Error::IO(dir.display().to_string(), e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::IO(dir.display().to_string(), e))?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(relative.join(&name));
+                continue;
+            }
+            // Other files of the repo, e.g. a readme, are not stored values
+            let value = fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| base64::decode(contents.trim()).ok())
+                .and_then(|decoded| serde_json::from_slice::<Value>(&decoded).ok());
+            let value = match value {
+                Some(value) => value,
+                None => {
+                    progress::warn(&format!("skipping {:?}, not a stored value", path));
+                    continue;
+                }
+            };
+            let key = if relative.as_os_str().is_empty() {
+                name
+            } else {
+                format!("{}/{}", relative.display(), name)
+            };
+            data.insert(key, value);
+        }
+    }
+    let contents = serde_json::to_vec(&data)
+        .map_err(|e| Error::UnexpectedError(format!("Unable to serialize registry: {}", e)))?;
+    fs::write(store, contents).map_err(|e| Error::IO(store.display().to_string(), e))
+}
+
+/// The namespaces of an on disk storage, the prefixes of its `namespace/key` entries
+pub fn store_namespaces(store: &Path) -> Result<Vec<String>, Error> {
+    let contents = fs::read(store).map_err(|e| Error::IO(store.display().to_string(), e))?;
+    let data: HashMap<String, Value> = serde_json::from_slice(&contents)
+        .map_err(|e| Error::UnableToParseFile(store.display().to_string(), e.to_string()))?;
+    let namespaces: BTreeSet<_> = data
+        .keys()
+        .filter_map(|key| {
+            key.split_once('/')
+                .map(|(namespace, _)| namespace.to_string())
+        })
+        .collect();
+    Ok(namespaces.into_iter().collect())
+}
+
+/// A registry opened for inspection: the namespaces it holds, and the backend they are read from
+pub struct OpenedRegistry {
+    backend: String,
+    namespaces: Vec<String>,
+}
+
+/// The role a namespace registers for, as told by the values it holds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NamespaceRole {
+    Owner,
+    Operator,
+    Common,
+    Unknown,
+}
+
+impl fmt::Display for NamespaceRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let role = match self {
+            NamespaceRole::Owner => "owner",
+            NamespaceRole::Operator => "operator",
+            NamespaceRole::Common => "common",
+            NamespaceRole::Unknown => "unknown",
+        };
+        write!(f, "{}", role)
+    }
+}
+
+/// The registrations of a namespace, and what is wrong with them
+#[derive(Debug)]
+pub struct NamespaceReport {
+    pub namespace: String,
+    pub role: NamespaceRole,
+    /// The values found, and a summary of each
+    pub entries: Vec<(&'static str, String)>,
+    pub problems: Vec<String>,
+}
+
+impl NamespaceReport {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl fmt::Display for NamespaceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} ({})", self.namespace, self.role)?;
+        for (name, summary) in &self.entries {
+            writeln!(f, "  {}: {}", name, summary)?;
+        }
+        if self.is_valid() {
+            write!(f, "  ok")
+        } else {
+            let problems: Vec<_> = self
+                .problems
+                .iter()
+                .map(|problem| format!("  INVALID: {}", problem))
+                .collect();
+            write!(f, "{}", problems.join("\n"))
+        }
+    }
+}
+
+impl OpenedRegistry {
+    /// A registry read through `backend`, a `--shared-backend` without a namespace
+    pub fn new(backend: String, namespaces: Vec<String>) -> Self {
+        OpenedRegistry {
+            backend,
+            namespaces,
+        }
+    }
+
+    pub fn namespaces(&self) -> &[String] {
+        &self.namespaces
+    }
+
+    fn config(&self) -> Result<Config, Error> {
+        let backend: ManagementBackend = self.backend.parse()?;
+        Config::default().override_shared_backend(&Some(backend))
+    }
+
+    fn storage(&self, namespace: &str) -> Result<StorageWrapper, Error> {
+        Ok(self
+            .config()?
+            .shared_backend_with_namespace(namespace.into()))
+    }
+
+    /// Checks the registrations of every namespace, and that the namespaces the layout names
+    /// were registered to
+    pub fn inspect_all(&self) -> Result<Vec<NamespaceReport>, Error> {
+        let mut reports = vec![];
+        for namespace in &self.namespaces {
+            reports.push(self.inspect(namespace)?);
+        }
+        if let Some(layout) = reports
+            .iter()
+            .find(|report| report.role == NamespaceRole::Common)
+            .and_then(|_| self.layout())
+        {
+            for namespace in layout.owners.iter().chain(layout.operators.iter()) {
+                if !self.namespaces.contains(namespace) {
+                    reports.push(NamespaceReport {
+                        namespace: namespace.clone(),
+                        role: NamespaceRole::Unknown,
+                        entries: vec![],
+                        problems: vec!["named in the layout, but nothing was registered".into()],
+                    });
+                }
+            }
+        }
+        Ok(reports)
+    }
+
+    /// The public key `name` registered in `namespace`, if any
+    pub fn registered_key(&self, namespace: &str, name: &'static str) -> Option<Ed25519PublicKey> {
+        self.storage(namespace).ok()?.ed25519_key(name).ok()
+    }
+
+    fn layout(&self) -> Option<Layout> {
+        let storage = self.storage(constants::COMMON_NS).ok()?;
+        Layout::parse(&storage.string(constants::LAYOUT).ok()?).ok()
+    }
+
+    /// Checks the registrations of `namespace`, as genesis would read them
+    pub fn inspect(&self, namespace: &str) -> Result<NamespaceReport, Error> {
+        let storage = self.storage(namespace)?;
+        let mut report = NamespaceReport {
+            namespace: namespace.into(),
+            role: NamespaceRole::Unknown,
+            entries: vec![],
+            problems: vec![],
+        };
+        let has = |name: &'static str| storage.value::<Value>(name).is_ok();
+        if namespace == constants::COMMON_NS {
+            report.role = NamespaceRole::Common;
+            match storage.string(constants::LAYOUT) {
+                Ok(layout) => match Layout::parse(&layout) {
+                    Ok(layout) => report.entries.push((
+                        constants::LAYOUT,
+                        format!(
+                            "{} owner(s), {} operator(s)",
+                            layout.owners.len(),
+                            layout.operators.len()
+                        ),
+                    )),
+                    Err(e) => report.problems.push(format!("malformed layout: {}", e)),
+                },
+                Err(_) => report.problems.push("missing layout".into()),
+            }
+        } else if has(OWNER_KEY) || has(constants::VALIDATOR_OPERATOR) {
+            report.role = NamespaceRole::Owner;
+            self.inspect_owner(&storage, &mut report);
+        } else if has(OPERATOR_KEY) || has(constants::VALIDATOR_CONFIG) {
+            report.role = NamespaceRole::Operator;
+            inspect_operator(&storage, &mut report);
+        } else {
+            report
+                .problems
+                .push("neither an owner nor an operator registration".into());
+        }
+        Ok(report)
+    }
+
+    fn inspect_owner(&self, storage: &StorageWrapper, report: &mut NamespaceReport) {
+        check(storage, report, OWNER_KEY, |value| {
+            serde_json::from_value::<Ed25519PublicKey>(value)
+                .map(|key| key.to_string())
+                .map_err(|e| e.to_string())
+        });
+        check(storage, report, constants::VALIDATOR_OPERATOR, |value| {
+            let operator: String = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            if self.namespaces.contains(&operator) {
+                Ok(operator)
+            } else {
+                Err(format!("the operator {} registered nothing", operator))
+            }
+        });
+        for name in &[PROOF_OF_WORK_PREIMAGE, PROOF_OF_WORK_PROOF] {
+            check(storage, report, *name, |value| {
+                let hex_value: String = serde_json::from_value(value).map_err(|e| e.to_string())?;
+                let bytes = hex::decode(&hex_value).map_err(|e| e.to_string())?;
+                if bytes.is_empty() {
+                    Err("empty".into())
+                } else {
+                    Ok(format!("{} bytes", bytes.len()))
+                }
+            });
+        }
+        // Genesis does without a profile, but not with a malformed one
+        if storage.value::<Value>(ACCOUNT_PROFILE).is_ok() {
+            check(storage, report, ACCOUNT_PROFILE, |value| {
+                let profile: String = serde_json::from_value(value).map_err(|e| e.to_string())?;
+                serde_json::from_str::<ValConfigs>(&profile)
+                    .map(|_| "set".into())
+                    .map_err(|e| e.to_string())
+            });
+        }
+        // Signatures are only required by genesis with --require-signed-registrations
+        if storage.value::<Value>(REGISTRATION_SIGNATURE).is_ok() {
+            let verified = self
+                .config()
+                .and_then(|config| ceremony::verify_registration(&config, &report.namespace));
+            match verified {
+                Ok(()) => report
+                    .entries
+                    .push((REGISTRATION_SIGNATURE, "valid".into())),
+                Err(e) => report
+                    .problems
+                    .push(format!("invalid {}: {}", REGISTRATION_SIGNATURE, e)),
+            }
+        }
+    }
+}
+
+fn inspect_operator(storage: &StorageWrapper, report: &mut NamespaceReport) {
+    let operator_key = check(storage, report, OPERATOR_KEY, |value| {
+        serde_json::from_value::<Ed25519PublicKey>(value)
+            .map(|key| key.to_string())
+            .map_err(|e| e.to_string())
+    })
+    .and_then(|_| storage.ed25519_key(OPERATOR_KEY).ok());
+    check(storage, report, constants::VALIDATOR_CONFIG, |value| {
+        let transaction: Transaction = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        let signed = transaction
+            .as_signed_user_txn()
+            .map_err(|_| "not a signed user transaction".to_string())?;
+        let script = match signed.payload() {
+            TransactionPayload::ScriptFunction(script) if script.args().len() == 4 => script,
+            _ => return Err("not a validator config script".into()),
+        };
+        signed
+            .clone()
+            .check_signature()
+            .map_err(|e| format!("invalid signature: {}", e))?;
+        if let Some(operator_key) = &operator_key {
+            if signed.sender() != account_address::from_public_key(operator_key) {
+                return Err("not sent by the account of the operator key".into());
+            }
+        }
+        let addresses = fullnode_addresses(&script.args()[3])
+            .ok_or_else(|| "malformed fullnode network addresses".to_string())?;
+        if addresses.is_empty() {
+            return Err("no fullnode network address".into());
+        }
+        let addresses: Vec<_> = addresses.iter().map(|a| a.to_string()).collect();
+        Ok(format!("fullnode {}", addresses.join(", ")))
+    });
+}
+
+/// The fullnode addresses of a validator config script, as encoded by the transaction builder
+/// or by `--onchain-seeds`
+fn fullnode_addresses(arg: &[u8]) -> Option<Vec<NetworkAddress>> {
+    bcs::from_bytes::<Vec<u8>>(arg)
+        .ok()
+        .and_then(|bytes| bcs::from_bytes(&bytes).ok())
+        .or_else(|| bcs::from_bytes(arg).ok())
+}
+
+/// Reads `name` and checks it with `parse`, which summarizes it. Missing and malformed values
+/// are reported as problems.
+fn check<F>(
+    storage: &StorageWrapper,
+    report: &mut NamespaceReport,
+    name: &'static str,
+    parse: F,
+) -> Option<String>
+where
+    F: FnOnce(Value) -> Result<String, String>,
+{
+    let value = match storage.value::<Value>(name) {
+        Ok(value) => value,
+        Err(_) => {
+            report.problems.push(format!("missing {}", name));
+            return None;
+        }
+    };
+    match parse(value) {
+        Ok(summary) => {
+            report.entries.push((name, summary.clone()));
+            Some(summary)
+        }
+        Err(e) => {
+            report.problems.push(format!("malformed {}: {}", name, e));
+            None
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub enum RegistryCommand {
+    #[structopt(about = "Lists the namespaces of the registry and flags invalid registrations")]
+    List(ListRegistry),
+    #[structopt(about = "Shows and checks the registrations of a namespace")]
+    Show(ShowRegistry),
+}
+
+impl RegistryCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            RegistryCommand::List(list) => list.execute(),
+            RegistryCommand::Show(show) => show.execute(),
+        }
+    }
+}
+
+/// The registry to inspect, and where its credentials and checkout are
+#[derive(Debug, StructOpt)]
+pub struct RegistryArgs {
+    /// The registry of the ceremony: github:ORG/REPO, gitlab:HOST/GROUP/REPO or git:URL
+    #[structopt(long)]
+    registry: SharedBackend,
+    /// The directory of github_token.txt or gitlab_token.txt, the checkout is cloned in it
+    #[structopt(long, parse(from_os_str))]
+    data_path: PathBuf,
+}
+
+impl RegistryArgs {
+    pub fn open(&self) -> Result<OpenedRegistry, Error> {
+        self.registry.open(&self.data_path)
+    }
+}
+
+/// Lists the namespaces of a registry with their role, flagging the malformed or incomplete ones
+#[derive(Debug, StructOpt)]
+pub struct ListRegistry {
+    #[structopt(flatten)]
+    args: RegistryArgs,
+}
+
+impl ListRegistry {
+    pub fn execute(self) -> Result<String, Error> {
+        let registry = self.args.open()?;
+        let reports = registry.inspect_all()?;
+        let mut lines: Vec<_> = reports
+            .iter()
+            .map(|report| {
+                if report.is_valid() {
+                    format!("{} ({}): ok", report.namespace, report.role)
+                } else {
+                    format!(
+                        "{} ({}): INVALID, {}",
+                        report.namespace,
+                        report.role,
+                        report.problems.join(", ")
+                    )
+                }
+            })
+            .collect();
+        let invalid = reports.iter().filter(|report| !report.is_valid()).count();
+        lines.push(format!(
+            "{} namespace(s), {} invalid",
+            reports.len(),
+            invalid
+        ));
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Shows the registrations of a namespace of a registry, and what is wrong with them
+#[derive(Debug, StructOpt)]
+pub struct ShowRegistry {
+    #[structopt(flatten)]
+    args: RegistryArgs,
+    /// The namespace of an owner or operator
+    namespace: String,
+}
+
+impl ShowRegistry {
+    pub fn execute(self) -> Result<String, Error> {
+        let registry = self.args.open()?;
+        if !registry.namespaces().contains(&self.namespace) {
+            return Err(Error::CommandArgumentError(format!(
+                "No namespace {} in {}",
+                self.namespace, self.args.registry
+            )));
+        }
+        Ok(registry.inspect(&self.namespace)?.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config_builder::{operator_shared_namespace, owner_shared_namespace, ValidatorBuilder},
+        swarm_config::BuildSwarm,
+    };
+    use diem_config::config::NodeConfig;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_parse_registry() {
+        for registry in &[
+            "github:OLSF/experimental-genesis",
+            "gitlab:gitlab.com/0l/ceremonies/genesis",
+            "git:git@example.com:0l/genesis.git",
+        ] {
+            let parsed: SharedBackend = registry.parse().unwrap();
+            assert_eq!(&parsed.to_string(), registry);
+        }
+        assert_eq!(
+            "gitlab:gitlab.com/0l/ceremonies/genesis"
+                .parse::<SharedBackend>()
+                .unwrap(),
+            SharedBackend::GitLab {
+                host: "gitlab.com".into(),
+                group: "0l/ceremonies".into(),
+                repo: "genesis".into(),
+            }
+        );
+        assert!("github:OLSF".parse::<SharedBackend>().is_err());
+        assert!("gitlab:gitlab.com/genesis"
+            .parse::<SharedBackend>()
+            .is_err());
+        assert!("svn:genesis".parse::<SharedBackend>().is_err());
+        assert_eq!(SharedBackend::from_github(Some("OLSF".into()), None), None);
+    }
+
+    #[test]
+    fn test_checkout_to_storage() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let checkout = dir.path().join("checkout");
+        fs::create_dir_all(checkout.join("alice")).unwrap();
+        fs::create_dir_all(checkout.join(".git")).unwrap();
+        fs::write(checkout.join("README.md"), "# Genesis registrations").unwrap();
+        let stored = serde_json::json!({"last_update": 0, "value": "alice-oper"});
+        fs::write(
+            checkout.join("alice").join("validator_operator"),
+            base64::encode(stored.to_string()),
+        )
+        .unwrap();
+
+        let store = dir.path().join("store.json");
+        checkout_to_storage(&checkout, &store).unwrap();
+        let backend = format!("backend={};path={}", DISK, store.display());
+        let config = Config::default()
+            .override_shared_backend(&Some(backend.parse().unwrap()))
+            .unwrap();
+        let storage = config.shared_backend_with_namespace("alice".into());
+        assert_eq!(storage.string("validator_operator").unwrap(), "alice-oper");
+        assert_eq!(store_namespaces(&store).unwrap(), vec!["alice".to_string()]);
+    }
+
+    #[test]
+    fn test_inspect_registrations() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let builder = ValidatorBuilder::new(1, NodeConfig::default_for_validator(), dir.path());
+        builder.build_swarm().unwrap();
+        let backend = format!("backend={};path={}", DISK, builder.storage_path().display());
+
+        let owner_ns = owner_shared_namespace(0);
+        let registry = OpenedRegistry::new(
+            backend.clone(),
+            vec![owner_ns.clone(), operator_shared_namespace(0)],
+        );
+        let owner = registry.inspect(&owner_ns).unwrap();
+        assert_eq!(owner.role, NamespaceRole::Owner);
+        assert!(owner.is_valid(), "{}", owner);
+        let operator = registry.inspect(&operator_shared_namespace(0)).unwrap();
+        assert_eq!(operator.role, NamespaceRole::Operator);
+        assert!(operator.is_valid(), "{}", operator);
+
+        // An owner whose operator registered nothing, and a namespace with no registration
+        let registry = OpenedRegistry::new(backend, vec![owner_ns.clone()]);
+        let owner = registry.inspect(&owner_ns).unwrap();
+        assert_eq!(
+            owner.problems,
+            vec![format!(
+                "malformed validator_operator: the operator {} registered nothing",
+                operator_shared_namespace(0)
+            )]
+        );
+        let unknown = registry.inspect("1_owner_shared").unwrap();
+        assert_eq!(unknown.role, NamespaceRole::Unknown);
+        assert!(!unknown.is_valid());
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Re-runs of `files` over a data path holding the files of a previous run. The files are read
+//! before the run and compared with what it wrote after: a file generated with the same content is
+//! left byte for byte as it was, and a file generated with different content, e.g. edited by the
+//! operator since, is only replaced with `--force`. With `--dry-run` every file is left as it was
+//! and the changes are printed instead.
+
+use crate::ol_node_files::NODE_TYPES;
+use diem_config::config::NodeConfig;
+use diem_management::{error::Error, progress};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+const KEY_STORE_FILE: &str = "key_store.json";
+const GENESIS_FILE: &str = "genesis.blob";
+
+/// What a run did to a file of the data path
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// No file before the run
+    Created,
+    /// Generated with the content of the file before the run, which is kept as is
+    Unchanged,
+    /// Generated with different content, replacing the file before the run
+    Replaced,
+    /// Generated with different content, the file before the run is kept
+    Kept,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileChange {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// How a run treats the files found in the data path
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RerunMode {
+    /// Replace the files generated with different content
+    pub force: bool,
+    /// Leave every file as it was, printing the changes of the run
+    pub dry_run: bool,
+}
+
+/// The files of the data path written by `files`, as they were before a run
+pub struct Snapshot {
+    dir: PathBuf,
+    files: Vec<(String, Option<Vec<u8>>)>,
+}
+
+impl Snapshot {
+    pub fn take(dir: &Path) -> Result<Self, Error> {
+        let names = [KEY_STORE_FILE, GENESIS_FILE]
+            .iter()
+            .copied()
+            .chain(NODE_TYPES.iter().map(|node_type| node_type.file_name()));
+        let mut files = vec![];
+        for name in names {
+            let path = dir.join(name);
+            let contents = if path.exists() {
+                Some(fs::read(&path).map_err(|e| Error::IO(path.display().to_string(), e))?)
+            } else {
+                None
+            };
+            files.push((name.to_string(), contents));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            files,
+        })
+    }
+
+    /// Compares the files written by the run with the snapshot, restoring those that are to be
+    /// left as they were
+    pub fn reconcile(self, mode: RerunMode) -> Result<Vec<FileChange>, Error> {
+        let mut changes = vec![];
+        let mut kept = vec![];
+        for (name, before) in &self.files {
+            let path = self.dir.join(name);
+            let after = if path.exists() {
+                Some(fs::read(&path).map_err(|e| Error::IO(path.display().to_string(), e))?)
+            } else {
+                None
+            };
+            let (before, after) = match (before, after) {
+                (before, None) => {
+                    // Not written by the run
+                    self.restore_file(name, before.as_deref())?;
+                    continue;
+                }
+                (None, Some(_)) => {
+                    if mode.dry_run {
+                        progress::report(
+                            progress::Verbosity::Normal,
+                            &format!("would create {}", name),
+                        );
+                        self.restore_file(name, None)?;
+                    }
+                    changes.push(FileChange {
+                        name: name.clone(),
+                        outcome: Outcome::Created,
+                    });
+                    continue;
+                }
+                (Some(before), Some(after)) => (before, after),
+            };
+
+            let outcome = if same_content(name, before, &after) {
+                Outcome::Unchanged
+            } else if mode.force && !mode.dry_run {
+                progress::detail(&format!("{} replaced", name));
+                Outcome::Replaced
+            } else {
+                let verb = if mode.dry_run { "would change" } else { "kept" };
+                progress::report(
+                    progress::Verbosity::Normal,
+                    &format!("{} {}:\n{}", verb, name, content_diff(name, before, &after)),
+                );
+                if !mode.dry_run {
+                    kept.push(name.as_str());
+                }
+                if mode.force {
+                    Outcome::Replaced
+                } else {
+                    Outcome::Kept
+                }
+            };
+            if outcome != Outcome::Replaced || mode.dry_run {
+                self.restore_file(name, Some(before))?;
+            }
+            changes.push(FileChange {
+                name: name.clone(),
+                outcome,
+            });
+        }
+        if !kept.is_empty() {
+            progress::warn(&format!(
+                "{} differ from the generated files and were kept, rerun with --force to replace \
+                 them",
+                kept.join(", ")
+            ));
+        }
+        Ok(changes)
+    }
+
+    /// Puts every file back as it was before the run, e.g. after the run failed
+    pub fn restore(self) -> Result<(), Error> {
+        for (name, before) in &self.files {
+            self.restore_file(name, before.as_deref())?;
+        }
+        Ok(())
+    }
+
+    fn restore_file(&self, name: &str, contents: Option<&[u8]>) -> Result<(), Error> {
+        let path = self.dir.join(name);
+        let result = match contents {
+            Some(contents) => fs::write(&path, contents),
+            None if path.exists() => fs::remove_file(&path),
+            None => Ok(()),
+        };
+        result.map_err(|e| Error::IO(path.display().to_string(), e))
+    }
+}
+
+/// Whether two versions of a file hold the same values. Node configs serialize their maps in no
+/// fixed order, and the key store records the time of each write.
+fn same_content(name: &str, before: &[u8], after: &[u8]) -> bool {
+    if before == after {
+        return true;
+    }
+    if name == KEY_STORE_FILE {
+        let before = key_store_values(before);
+        return before.is_some() && before == key_store_values(after);
+    }
+    if name.ends_with(".yaml") {
+        let config = |contents: &[u8]| serde_yaml::from_slice::<NodeConfig>(contents).ok();
+        let before = config(before);
+        return before.is_some() && before == config(after);
+    }
+    false
+}
+
+/// The values of a key store, without their time of write
+fn key_store_values(contents: &[u8]) -> Option<BTreeMap<String, serde_json::Value>> {
+    let mut entries: BTreeMap<String, serde_json::Value> = serde_json::from_slice(contents).ok()?;
+    for entry in entries.values_mut() {
+        if let Some(entry) = entry.as_object_mut() {
+            entry.remove("last_update");
+        }
+    }
+    Some(entries)
+}
+
+/// The changes of a file, without the private keys of a key store
+fn content_diff(name: &str, before: &[u8], after: &[u8]) -> String {
+    if name == KEY_STORE_FILE {
+        let before = key_store_values(before).unwrap_or_default();
+        let after = key_store_values(after).unwrap_or_default();
+        return before
+            .keys()
+            .chain(after.keys())
+            .filter(|key| before.get(*key) != after.get(*key))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|key| format!("~{}\n", key))
+            .collect();
+    }
+    match (std::str::from_utf8(before), std::str::from_utf8(after)) {
+        (Ok(before), Ok(after)) => line_diff(before, after),
+        _ => "binary contents differ\n".to_string(),
+    }
+}
+
+/// The lines of `expected` and `actual`, prefixed by `-` if only in `expected`, `+` if only in
+/// `actual` and a space if in both, following their longest common subsequence
+pub(crate) fn line_diff(expected: &str, actual: &str) -> String {
+    let (expected, actual): (Vec<_>, Vec<_>) =
+        (expected.lines().collect(), actual.lines().collect());
+    // common[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let mut push = |prefix: char, line: &str| {
+        diff.push(prefix);
+        diff.push_str(line);
+        diff.push('\n');
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            push(' ', expected[i]);
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            push('-', expected[i]);
+            i += 1;
+        } else {
+            push('+', actual[j]);
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_temppath::TempPath;
+
+    #[test]
+    fn test_reconcile() {
+        let dir = TempPath::new();
+        dir.create_as_dir().unwrap();
+        let dir = dir.path();
+        let write = |name: &str, contents: &str| fs::write(dir.join(name), contents).unwrap();
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        let key_store = |time: u64, waypoint: &str| {
+            format!(
+                r#"{{"ns/waypoint":{{"last_update":{},"value":"{}"}}}}"#,
+                time, waypoint
+            )
+        };
+        let validator = NODE_TYPES[0].file_name();
+        write(KEY_STORE_FILE, &key_store(1, "0:aa"));
+        write(validator, "edited by the operator");
+
+        // A rerun rewrites the same waypoint at a later time, and a validator config
+        let run = |mode| {
+            let snapshot = Snapshot::take(dir).unwrap();
+            write(KEY_STORE_FILE, &key_store(2, "0:aa"));
+            write(validator, "generated");
+            write(GENESIS_FILE, "genesis");
+            snapshot.reconcile(mode).unwrap()
+        };
+        let outcome = |changes: &[FileChange], name: &str| {
+            changes
+                .iter()
+                .find(|change| change.name == name)
+                .map(|change| change.outcome)
+        };
+
+        let changes = run(RerunMode {
+            force: false,
+            dry_run: true,
+        });
+        assert_eq!(outcome(&changes, GENESIS_FILE), Some(Outcome::Created));
+        assert!(!dir.join(GENESIS_FILE).exists());
+
+        let changes = run(RerunMode::default());
+        assert_eq!(outcome(&changes, KEY_STORE_FILE), Some(Outcome::Unchanged));
+        assert_eq!(read(KEY_STORE_FILE), key_store(1, "0:aa"));
+        assert_eq!(outcome(&changes, validator), Some(Outcome::Kept));
+        assert_eq!(read(validator), "edited by the operator");
+        assert_eq!(read(GENESIS_FILE), "genesis");
+
+        let changes = run(RerunMode {
+            force: true,
+            dry_run: false,
+        });
+        assert_eq!(outcome(&changes, validator), Some(Outcome::Replaced));
+        assert_eq!(read(validator), "generated");
+        assert_eq!(outcome(&changes, GENESIS_FILE), Some(Outcome::Unchanged));
+    }
+
+    #[test]
+    fn test_key_store_diff_hides_values() {
+        let before = br#"{"ns/owner":{"last_update":1,"value":"secret"}}"#;
+        let after = br#"{"ns/owner":{"last_update":2,"value":"other secret"}}"#;
+        let diff = content_diff(KEY_STORE_FILE, before, after);
+        assert_eq!(diff, "~ns/owner\n");
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff("a\nb\nc\n", "a\nb\nc\n"), " a\n b\n c\n");
+        assert_eq!(
+            line_diff("a\nb\nc\nd\n", "a\nc\nx\nd\ne\n"),
+            " a\n-b\n c\n+x\n d\n+e\n"
+        );
+        assert_eq!(line_diff("", "a\n"), "+a\n");
+        assert_eq!(line_diff("a\n", ""), "-a\n");
+    }
+}
@@ -1,20 +1,49 @@
-use diem_crypto::x25519::PublicKey;
-use diem_management::error::Error;
-use diem_temppath::TempPath;
+use diem_config::{
+    config::{
+        HandshakeEnforcement, Peer, PeerAccess, PeerRole, PeerSet, RoleType, HANDSHAKE_VERSION,
+    },
+    network_id::{NetworkContext, NetworkId},
+};
+use diem_crypto::{
+    x25519::{self, PublicKey},
+    Uniform,
+};
+use diem_global_constants::{FULLNODE_NETWORK_KEY, OPERATOR_KEY};
+use diem_management::{constants, error::Error, trace_span, transaction::build_raw_transaction};
+use diem_network_address_encryption::Encryptor;
+use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
 use diem_types::{
-    account_config, account_state::AccountState, PeerId, 
-    on_chain_config::ValidatorSet, network_address::NetworkAddress,
+    account_address,
+    chain_id::ChainId,
+    network_address::NetworkAddress,
+    transaction::{ScriptFunction, SignedTransaction, Transaction, TransactionPayload},
+    PeerId,
 };
-use structopt::StructOpt;
+use netcore::transport::tcp::{resolve_and_connect, TcpSocket};
+use network::{
+    noise::{HandshakeAuthMode, NoiseUpgrader},
+    protocols::wire::handshake::v1::SupportedProtocols,
+    transport::{upgrade_outbound, UpgradeContext, SUPPORTED_MESSAGING_PROTOCOL},
+    ProtocolId,
+};
+use ol_types::account::ValConfigs;
+use rand::rngs::OsRng;
+use serde::Serialize;
 use std::{
-    convert::TryFrom,
-    collections::{HashMap},
-    path::{PathBuf}, fs,
+    collections::{BTreeMap, HashMap},
+    fs,
+    net::IpAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
+use structopt::StructOpt;
+use tokio::runtime::Runtime;
 
-use crate::verify::compute_genesis;
+use crate::validator_set::ValidatorSetSource; //////// 0L ////////
 
-pub type SeedAddresses = HashMap<PeerId, Vec<NetworkAddress>>; 
+pub type SeedAddresses = HashMap<PeerId, Vec<NetworkAddress>>;
 
 // NOTE: Deprecated for use on validator config. Kept here for reference.
 
@@ -24,70 +53,457 @@ pub struct Seeds {
     /// Path to genesis file to extract seed nodes
     #[structopt(long, verbatim_doc_comment)]
     pub genesis_path: PathBuf,
+    //////// 0L ////////
+    /// yaml or json for the `seeds` of a network config, with the role of each peer, or
+    /// seed-addrs for a `seed_addrs` fragment
+    #[structopt(long, default_value = "yaml")]
+    pub format: SeedFormat,
+    /// Only export the peers of these roles, e.g. validator_full_node
+    #[structopt(long)]
+    pub role: Vec<PeerRole>,
+    /// Write the seed peers to this file instead of printing them
+    #[structopt(long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+//////// 0L ////////
+/// The formats the seed peers of a genesis are exported in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeedFormat {
+    Yaml,
+    Json,
+    SeedAddrs,
+}
+
+impl FromStr for SeedFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yaml" => Ok(SeedFormat::Yaml),
+            "json" => Ok(SeedFormat::Json),
+            "seed-addrs" => Ok(SeedFormat::SeedAddrs),
+            _ => Err(Error::CommandArgumentError(format!(
+                "Unknown seed format {}, expected yaml, json or seed-addrs",
+                s
+            ))),
+        }
+    }
+}
+
+/// A `seed_addrs` fragment, sorted to keep diffs of exports small
+#[derive(Serialize)]
+struct SeedAddrsFragment {
+    seed_addrs: BTreeMap<PeerId, Vec<NetworkAddress>>,
 }
 
 impl Seeds {
     pub fn execute(self) -> Result<String, Error> {
-
-     let peers = &self.get_network_peers_info()?;
-     dbg!(peers);
-     Ok("ok".to_string())
+        //////// 0L ////////
+        let peers = self.get_network_peers()?;
+        let peers = filter_roles(peers, &self.role);
+        let exported = export(&peers, self.format)?;
+        match &self.output {
+            Some(output) => {
+                fs::write(output, &exported)
+                    .map_err(|e| Error::IO(output.display().to_string(), e))?;
+                Ok(format!(
+                    "{} seed peers written to {}",
+                    peers.len(),
+                    output.display()
+                ))
+            }
+            None => Ok(exported),
+        }
     }
 
     pub fn new(genesis_path: PathBuf) -> Self {
-      Self {
-        genesis_path,
-      }
+        Self {
+            genesis_path,
+            format: SeedFormat::Yaml,
+            role: vec![],
+            output: None,
+        }
     }
 
-    pub fn read_from_file(seed_peers_path: PathBuf) -> Result<SeedAddresses, Error>  {
+    pub fn read_from_file(seed_peers_path: PathBuf) -> Result<SeedAddresses, Error> {
+        trace_span!("seeds.read_from_file", path = ?seed_peers_path);
 
-        let file_string = fs::read_to_string(seed_peers_path)
-        .map_err(|e| Error::ConfigError(e.to_string()))?;
-        let yaml: SeedAddresses = serde_yaml::from_str(&file_string)
-        .map_err(|e| Error::ConfigError(e.to_string()))?;
+        let file_string =
+            fs::read_to_string(seed_peers_path).map_err(|e| Error::ConfigError(e.to_string()))?;
+        let yaml: SeedAddresses =
+            serde_yaml::from_str(&file_string).map_err(|e| Error::ConfigError(e.to_string()))?;
         Ok(yaml)
     }
 
+    pub fn get_network_peers_info(&self) -> Result<SeedAddresses, Error> {
+        //////// 0L ////////
+        let peers = filter_roles(self.get_network_peers()?, &[PeerRole::ValidatorFullNode]);
+        Ok(peers
+            .into_iter()
+            .map(|(peer_id, peer)| (peer_id, peer.addresses))
+            .collect())
+    }
 
+    //////// 0L ////////
+    /// The peers of the genesis validator set: each validator on its validator network addresses,
+    /// keyed by account, and its fullnode on the fullnode network addresses. Validator addresses
+    /// are decrypted with the shared network address key that 0L uses for all validators.
+    pub fn get_network_peers(&self) -> Result<PeerSet, Error> {
+        trace_span!("seeds.extract", genesis_path = ?self.genesis_path);
+        let validator_set =
+            ValidatorSetSource::from_genesis(self.genesis_path.clone()).validator_set()?;
+        let encryptor = Encryptor::for_testing();
 
-    pub fn get_network_peers_info(&self)->Result<SeedAddresses, Error> {
-        let db_path = TempPath::new();
-
-        let (db_rw, _expected_waypoint) = compute_genesis(&self.genesis_path, db_path.path())?;
-
-        let blob = db_rw
-            .reader
-            .get_latest_account_state(account_config::validator_set_address())
-            .map_err(|e| {
-                Error::UnexpectedError(format!("ValidatorSet Account issue {}", e.to_string()))
-            })
-            .unwrap()
-            .unwrap();
-
-        let account_state = AccountState::try_from(&blob)
-            .map_err(|e| Error::UnexpectedError(format!("Failed to parse blob: {}", e)))
-            .unwrap();
-
-        let validator_set: ValidatorSet = account_state
-            .get_validator_set()
-            .map_err(|e| Error::UnexpectedError(format!("ValidatorSet issue {}", e.to_string())))?
-            .ok_or_else(|| Error::UnexpectedError("ValidatorSet does not exist".into()))?;
-
-        let mut seed_addr = SeedAddresses::default();
-        let info = validator_set.payload();
-        for info in info.iter() {
+        let mut peers = PeerSet::default();
+        for info in validator_set.payload().iter() {
             let seed_pubkey = info.config().consensus_public_key.clone();
             //NOTE: This usually expects a x25519 key
-            let x25519 = PublicKey::from_ed25519_public_bytes(&seed_pubkey.to_bytes()).expect("Seed peers could not generate x25519 identitykey from ed25519 key provided");
+            let x25519 =
+                PublicKey::from_ed25519_public_bytes(&seed_pubkey.to_bytes()).map_err(|e| {
+                    Error::UnexpectedError(format!(
+                        "Seed peers could not generate x25519 identity key: {}",
+                        e
+                    ))
+                })?;
             let peer_id = PeerId::from_identity_public_key(x25519);
 
             // use validator address, not the operator consensus key.
             // let peer_id = info.account_address().to_owned();
-            let addr_vec = info.config().fullnode_network_addresses().expect("Get the Fullnode network address");
-            seed_addr.insert(peer_id, addr_vec);
+            let addr_vec = info.config().fullnode_network_addresses().map_err(|e| {
+                Error::UnexpectedError(format!(
+                    "Unable to read the fullnode network addresses of {}: {}",
+                    info.account_address(),
+                    e
+                ))
+            })?;
+            peers.insert(
+                peer_id,
+                Peer::from_addrs(PeerRole::ValidatorFullNode, addr_vec),
+            );
+
+            let account = *info.account_address();
+            match encryptor.decrypt(&info.config().validator_network_addresses, account) {
+                Ok(addresses) if !addresses.is_empty() => {
+                    peers.insert(account, Peer::from_addrs(PeerRole::Validator, addresses));
+                }
+                _ => {}
+            }
         }
 
-        Ok(seed_addr)
+        Ok(peers)
     }
-  }
\ No newline at end of file
+}
+
+//////// 0L ////////
+/// Keeps the peers of the given roles, or all of them if no role is given
+pub fn filter_roles(peers: PeerSet, roles: &[PeerRole]) -> PeerSet {
+    peers
+        .into_iter()
+        .filter(|(_, peer)| roles.is_empty() || roles.contains(&peer.role))
+        .collect()
+}
+
+/// The address the fullnode of a validator serves the public network on, at `ip_address` and
+/// authenticated with the fullnode network key of `validator`
+pub fn fullnode_network_address(
+    validator: &Storage,
+    ip_address: &IpAddr,
+) -> Result<NetworkAddress, Error> {
+    let key = validator
+        .export_private_key(FULLNODE_NETWORK_KEY)
+        .map_err(|e| Error::StorageReadError("validator", FULLNODE_NETWORK_KEY, e.to_string()))?;
+    let key = x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes())
+        .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    Ok(ValConfigs::make_unencrypted_addr(
+        ip_address,
+        key.public_key(),
+        NetworkId::Public,
+    ))
+}
+
+/// Writes `addresses` as the fullnode network addresses of the validator config registered in
+/// `shared`, signed again with the operator key of `validator`. Genesis puts them in the on chain
+/// validator config, where public networks with `DiscoveryMethod::Onchain` find the fullnode of
+/// each validator without static `seed_addrs`.
+pub fn register_fullnode_addresses(
+    validator: &Storage,
+    shared: &mut Storage,
+    addresses: &[NetworkAddress],
+) -> Result<Transaction, Error> {
+    trace_span!("seeds.register_fullnode_addresses");
+    let registration = shared
+        .get::<Transaction>(constants::VALIDATOR_CONFIG)
+        .map_err(|e| Error::StorageReadError("shared", constants::VALIDATOR_CONFIG, e.to_string()))?
+        .value;
+    let invalid = || Error::UnexpectedError("Found invalid registration".into());
+    let signed = registration.as_signed_user_txn().map_err(|_| invalid())?;
+    let script = match signed.payload() {
+        TransactionPayload::ScriptFunction(script) if script.args().len() == 4 => script,
+        _ => return Err(invalid()),
+    };
+
+    let public_key = validator
+        .get_public_key(OPERATOR_KEY)
+        .map_err(|e| Error::StorageReadError("validator", OPERATOR_KEY, e.to_string()))?
+        .public_key;
+    if account_address::from_public_key(&public_key) != signed.sender() {
+        return Err(Error::UnexpectedError(format!(
+            "The registration was sent by {}, not by the operator of this key store",
+            signed.sender()
+        )));
+    }
+
+    // The fullnode network addresses are the last argument of register_validator_config
+    let mut args = script.args().to_vec();
+    args[3] =
+        bcs::to_bytes(addresses).map_err(|e| Error::BCS("fullnode network addresses".into(), e))?;
+    let script = ScriptFunction::new(
+        script.module().clone(),
+        script.function().to_owned(),
+        script.ty_args().to_vec(),
+        args,
+    );
+    let raw_txn = build_raw_transaction(
+        signed.chain_id(),
+        signed.sender(),
+        signed.sequence_number(),
+        script,
+    );
+    let signature = validator.sign(OPERATOR_KEY, &raw_txn).map_err(|e| {
+        Error::StorageSigningError("validator", "validator-config", OPERATOR_KEY, e.to_string())
+    })?;
+    let txn = Transaction::UserTransaction(SignedTransaction::new(raw_txn, public_key, signature));
+    shared
+        .set(constants::VALIDATOR_CONFIG, txn.clone())
+        .map_err(|e| {
+            Error::StorageWriteError("shared", constants::VALIDATOR_CONFIG, e.to_string())
+        })?;
+    Ok(txn)
+}
+
+/// Serializes the peers in the given format
+pub fn export(peers: &PeerSet, format: SeedFormat) -> Result<String, Error> {
+    let serialize_error = |e: &dyn std::fmt::Display| {
+        Error::UnexpectedError(format!("Unable to serialize seeds: {}", e))
+    };
+    let sorted: BTreeMap<_, _> = peers.iter().collect();
+    match format {
+        SeedFormat::Yaml => serde_yaml::to_string(&sorted).map_err(|e| serialize_error(&e)),
+        SeedFormat::Json => serde_json::to_string_pretty(&sorted).map_err(|e| serialize_error(&e)),
+        SeedFormat::SeedAddrs => {
+            let fragment = SeedAddrsFragment {
+                seed_addrs: peers
+                    .iter()
+                    .map(|(peer_id, peer)| (*peer_id, peer.addresses.clone()))
+                    .collect(),
+            };
+            serde_yaml::to_string(&fragment).map_err(|e| serialize_error(&e))
+        }
+    }
+}
+
+//////// 0L ////////
+/// How long a seed is given to complete the handshake on one of its addresses
+pub const SEED_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Dials each seed on the public network of `chain_id`, address by address until one completes
+/// the noise and network handshakes. Returns the address each seed answered on, or why it did not
+/// answer on any. The seeds are dialed at once, so probing takes one `timeout` per address at most.
+pub fn probe_seeds(
+    chain_id: ChainId,
+    seeds: &SeedAddresses,
+    timeout: Duration,
+) -> Result<BTreeMap<PeerId, Result<NetworkAddress, String>>, Error> {
+    trace_span!("seeds.probe", count = seeds.len());
+    let runtime = Runtime::new().map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    // The seeds only check that the dialer holds the key it presents, any key will do
+    let private_key = x25519::PrivateKey::generate(&mut OsRng);
+    let network_context = Arc::new(NetworkContext::new(
+        RoleType::FullNode,
+        NetworkId::Public,
+        PeerId::from_identity_public_key(private_key.public_key()),
+        chain_id,
+    ));
+    let mut supported_protocols = BTreeMap::new();
+    supported_protocols.insert(
+        SUPPORTED_MESSAGING_PROTOCOL,
+        SupportedProtocols::from(ProtocolId::all().iter()),
+    );
+    let context = Arc::new(UpgradeContext::new(
+        NoiseUpgrader::new(
+            network_context,
+            private_key,
+            HandshakeAuthMode::server_only(),
+        ),
+        HANDSHAKE_VERSION,
+        supported_protocols,
+        chain_id,
+        NetworkId::Public,
+        PeerAccess::default(),
+        HandshakeEnforcement::Strict,
+    ));
+
+    let probes: Vec<_> = seeds
+        .iter()
+        .map(|(peer_id, addresses)| {
+            let probe = probe_seed(context.clone(), *peer_id, addresses.clone(), timeout);
+            (*peer_id, runtime.spawn(probe))
+        })
+        .collect();
+    Ok(probes
+        .into_iter()
+        .map(|(peer_id, probe)| {
+            let result = runtime
+                .block_on(probe)
+                .unwrap_or_else(|e| Err(e.to_string()));
+            (peer_id, result)
+        })
+        .collect())
+}
+
+async fn probe_seed(
+    context: Arc<UpgradeContext>,
+    peer_id: PeerId,
+    addresses: Vec<NetworkAddress>,
+    timeout: Duration,
+) -> Result<NetworkAddress, String> {
+    let mut failures = vec![];
+    for address in addresses {
+        let pubkey = match address.find_noise_proto() {
+            Some(pubkey) if address.is_diemnet_addr() => pubkey,
+            _ => {
+                failures.push(format!("{} has no noise key or handshake", address));
+                continue;
+            }
+        };
+        let socket = resolve_and_connect(address.clone());
+        let socket = async { socket.await.map(TcpSocket::new) };
+        let connection =
+            upgrade_outbound(context.clone(), socket, address.clone(), peer_id, pubkey);
+        match tokio::time::timeout(timeout, connection).await {
+            Ok(Ok(_)) => return Ok(address),
+            Ok(Err(e)) => failures.push(format!("{}: {}", address, e)),
+            Err(_) => failures.push(format!("{}: no answer in {:?}", address, timeout)),
+        }
+    }
+    if failures.is_empty() {
+        failures.push("no address".into());
+    }
+    Err(failures.join(", "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{storage_helper::StorageHelper, testing::genesis_fixture};
+    use diem_config::config::NetworkConfig;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use diem_global_constants::{DEFAULT_PUB_PORT, OWNER_KEY};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_export_seeds() {
+        let fixture = genesis_fixture(2, ChainId::test());
+        let seeds = Seeds::new(fixture.genesis_path());
+        let peers = seeds.get_network_peers().unwrap();
+        assert_eq!(peers.len(), 4);
+        let vfns = filter_roles(peers.clone(), &[PeerRole::ValidatorFullNode]);
+        assert_eq!(vfns.len(), 2);
+        assert_eq!(seeds.get_network_peers_info().unwrap().len(), 2);
+
+        // The yaml and json exports are the `seeds` of a network config
+        let yaml: PeerSet =
+            serde_yaml::from_str(&export(&vfns, SeedFormat::Yaml).unwrap()).unwrap();
+        assert_eq!(yaml, vfns);
+        let json: PeerSet =
+            serde_json::from_str(&export(&peers, SeedFormat::Json).unwrap()).unwrap();
+        assert_eq!(json, peers);
+
+        // And the fragment can be pasted in a network config
+        let fragment = export(&vfns, SeedFormat::SeedAddrs).unwrap();
+        let mut network: serde_yaml::Value =
+            serde_yaml::to_value(NetworkConfig::default()).unwrap();
+        let fragment: serde_yaml::Value = serde_yaml::from_str(&fragment).unwrap();
+        network["seed_addrs"] = fragment["seed_addrs"].clone();
+        let network: NetworkConfig = serde_yaml::from_value(network).unwrap();
+        assert_eq!(network.seed_addrs.len(), 2);
+
+        assert_eq!(
+            "validator_full_node".parse::<PeerRole>().unwrap(),
+            PeerRole::ValidatorFullNode
+        );
+        assert!("seed-addrs".parse::<SeedFormat>().is_ok());
+    }
+
+    #[test]
+    fn test_register_fullnode_addresses() {
+        let helper = StorageHelper::new();
+        helper.initialize_by_idx("local".into(), 0);
+        helper.operator_key("local", "operator").unwrap();
+        let owner_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        helper
+            .storage("owner".into())
+            .set(OWNER_KEY, owner_key)
+            .unwrap();
+        let address: NetworkAddress = "/ip4/0.0.0.0/tcp/6180".parse().unwrap();
+        helper
+            .validator_config(
+                "owner",
+                address.clone(),
+                address,
+                ChainId::test(),
+                "local",
+                "operator",
+            )
+            .unwrap();
+
+        let validator = helper.storage("local".into());
+        let mut shared = helper.storage("operator".into());
+        let address = fullnode_network_address(&validator, &Ipv4Addr::LOCALHOST.into()).unwrap();
+        assert!(address
+            .to_string()
+            .starts_with(&format!("/ip4/127.0.0.1/tcp/{}/", DEFAULT_PUB_PORT)));
+
+        let txn = register_fullnode_addresses(&validator, &mut shared, &[address.clone()]).unwrap();
+        let stored = shared
+            .get::<Transaction>(constants::VALIDATOR_CONFIG)
+            .unwrap()
+            .value;
+        assert_eq!(stored, txn);
+        let signed = txn.as_signed_user_txn().unwrap().clone();
+        match signed.payload() {
+            TransactionPayload::ScriptFunction(script) => {
+                let addresses: Vec<NetworkAddress> = bcs::from_bytes(&script.args()[3]).unwrap();
+                assert_eq!(addresses, vec![address]);
+            }
+            _ => panic!("Invalid validator-config transaction payload found!"),
+        }
+        signed.check_signature().unwrap();
+    }
+
+    #[test]
+    fn test_probe_seeds() {
+        // Nothing listens on the port of a dropped listener
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let key = x25519::PrivateKey::generate(&mut OsRng).public_key();
+        let closed = NetworkAddress::from_str(&format!("/ip4/127.0.0.1/tcp/{}", port))
+            .unwrap()
+            .append_prod_protos(key, HANDSHAKE_VERSION);
+        let bare = NetworkAddress::from_str("/ip4/127.0.0.1/tcp/6180").unwrap();
+        let seeds: SeedAddresses = vec![(PeerId::random(), vec![bare, closed])]
+            .into_iter()
+            .collect();
+
+        let probes = probe_seeds(ChainId::test(), &seeds, Duration::from_secs(1)).unwrap();
+        assert_eq!(probes.len(), 1);
+        // Every address is tried
+        let reason = probes.values().next().unwrap().as_ref().unwrap_err();
+        assert!(reason.contains("has no noise key"));
+        assert!(reason.contains(&format!("/tcp/{}/", port)));
+    }
+}
@@ -3,24 +3,36 @@
 
 // FIXME: (gnazario) storage helper doesn't belong in the genesis tool, but it's attached to it right now
 
-use crate::command::Command;
+use crate::{
+    command::Command,
+    genesis_storage::{
+        GenesisStorage, InMemoryGenesisStorage, OnDiskGenesisStorage, VaultGenesisStorage,
+    },
+    registry::RemoteRegistry,
+};
 use consensus_types::safety_data::SafetyData;
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
-    Uniform,
+    x25519, Uniform,
     ValidCryptoMaterialStringExt
 };
 use diem_global_constants::{
     CONSENSUS_KEY, DIEM_ROOT_KEY, EXECUTION_KEY, FULLNODE_NETWORK_KEY, OPERATOR_KEY, OWNER_KEY,
-    SAFETY_DATA, TREASURY_COMPLIANCE_KEY, VALIDATOR_NETWORK_KEY, WAYPOINT,
+    SAFETY_DATA, TREASURY_COMPLIANCE_KEY, VALIDATOR_NETWORK_KEY, VFN_NETWORK_KEY,
+    VFN_NETWORK_PEER_ID, WAYPOINT,
 };
-use diem_management::{error::Error, secure_backend::DISK};
-use diem_secure_storage::{CryptoStorage, KVStorage, Namespaced, OnDiskStorage, Storage};
+use diem_config::config::VaultConfig;
+use diem_management::{error::Error, trace_span};
+#[cfg(any(test, feature = "testing"))]
+use diem_secure_storage::{FaultyStorage, Faults};
+use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
 use diem_types::{
+    account_address,
     chain_id::ChainId,
     network_address::{self, NetworkAddress},
     transaction::Transaction,
     waypoint::Waypoint,
+    PeerId,
 };
 use std::{
     fs::File,
@@ -31,7 +43,9 @@ use ol_keys::scheme::KeyScheme;
 use ol_types::{ fixtures, genesis_proof::GenesisMiningProof};
 
 pub struct StorageHelper {
-    temppath: diem_temppath::TempPath,
+    storage: Box<dyn GenesisStorage>, //////// 0L ////////
+    #[cfg(any(test, feature = "testing"))]
+    faults: Option<Faults>, //////// 0L ////////
 }
 
 impl StorageHelper {
@@ -39,7 +53,7 @@ impl StorageHelper {
         let temppath = diem_temppath::TempPath::new();
         temppath.create_as_file().unwrap();
         File::create(temppath.path()).unwrap();
-        Self { temppath }
+        Self::with_storage(OnDiskGenesisStorage::new(temppath))
     }
 
     //////// 0L ////////
@@ -48,7 +62,7 @@ impl StorageHelper {
         let path = diem_temppath::TempPath::new_with_dir(path);
         path.create_as_file().expect("Failed on create_as_file");
         File::create(path.path()).expect("Could not create file");
-        Self { temppath: path }
+        Self::with_storage(OnDiskGenesisStorage::new(path))
     }
 
     ///////// 0L /////////
@@ -56,12 +70,39 @@ impl StorageHelper {
         let path = diem_temppath::TempPath::new_with_dir(path);
         // path.create_as_file().expect("Failed on create_as_file");
         // File::create(path.path()).expect("Could not create file");
-        Self { temppath: path }
+        Self::with_storage(OnDiskGenesisStorage::new(path))
+    }
+
+    //////// 0L ////////
+    /// Keeps the key material in memory, e.g., in CI. Commands executed by the helper can't open
+    /// this storage and fail.
+    pub fn new_in_memory() -> Self {
+        Self::with_storage(InMemoryGenesisStorage::new())
+    }
+
+    //////// 0L ////////
+    /// Keeps the key material in the Vault of `config`, each namespace in a namespace of its own
+    pub fn new_with_vault(config: VaultConfig) -> Self {
+        Self::with_storage(VaultGenesisStorage::new(config))
+    }
+
+    //////// 0L ////////
+    pub fn with_storage(storage: impl GenesisStorage + 'static) -> Self {
+        Self {
+            storage: Box::new(storage),
+            #[cfg(any(test, feature = "testing"))]
+            faults: None,
+        }
     }
 
     ///////// 0L /////////
     pub fn initialize_with_mnemonic_swarm(&self, namespace: String, mnemonic: String) {
-        let keys = KeyScheme::new_from_mnemonic(mnemonic);
+        self.initialize_with_keys(namespace, KeyScheme::new_from_mnemonic(mnemonic));
+    }
+
+    ///////// 0L /////////
+    /// Initializes a namespace with all keys of a validator, as used by swarm
+    pub fn initialize_with_keys(&self, namespace: String, keys: KeyScheme) {
         let mut storage = self.storage(namespace.clone());
         // let mut rng: rand::rngs::StdRng = rand::SeedableRng::from_seed([5; 32]);
         let dummy_root = Ed25519PrivateKey::from_encoded_string(
@@ -99,6 +140,7 @@ impl StorageHelper {
         storage
             .import_private_key(EXECUTION_KEY, keys.child_5_executor.get_private_key())
             .unwrap();
+        import_vfn_network_key(&mut storage, keys.child_6_vfn_network.get_private_key()).unwrap();
         storage
             .set(SAFETY_DATA, SafetyData::new(0, 0, 0, None))
             .unwrap();
@@ -118,6 +160,7 @@ impl StorageHelper {
     pub fn initialize_with_mnemonic(
         &self, namespace: String, keys: KeyScheme, is_genesis: bool
     ) {
+        trace_span!("storage_helper.initialize", namespace = %namespace);
         let mut storage_owner = self.storage(namespace.clone());
         let mut storage_oper = self.storage(format!("{}-oper", namespace.clone()));
 
@@ -160,6 +203,11 @@ impl StorageHelper {
         storage_oper
             .import_private_key(EXECUTION_KEY, keys.child_5_executor.get_private_key())
             .unwrap();
+        import_vfn_network_key(
+            &mut storage_oper,
+            keys.child_6_vfn_network.get_private_key(),
+        )
+        .unwrap();
         storage_oper
             .set(SAFETY_DATA, SafetyData::new(0, 0, 0, None))
             .unwrap();
@@ -176,17 +224,35 @@ impl StorageHelper {
             .unwrap();
     }
 
+    //////// 0L ////////
+    /// Routes all storage returned by `storage` through a `FaultyStorage`, so flows can be tested
+    /// against storage failures. Commands executed by the helper open the storage themselves and
+    /// are not affected.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn with_faults(mut self, faults: Faults) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
     pub fn storage(&self, namespace: String) -> Storage {
-        let storage = OnDiskStorage::new(self.temppath.path().to_path_buf());
-        Storage::from(Namespaced::new(namespace, Box::new(Storage::from(storage))))
+        let storage = self.storage.storage(namespace);
+        #[cfg(any(test, feature = "testing"))]
+        if let Some(faults) = &self.faults {
+            return Storage::from(FaultyStorage::new(Box::new(storage), faults.clone()));
+        }
+        storage
     }
 
-    pub fn path(&self) -> &Path {
-        self.temppath.path()
+    //////// 0L ////////
+    /// The key store file, None if the storage isn't on disk
+    pub fn path(&self) -> Option<&Path> {
+        self.storage.path()
     }
 
-    pub fn path_string(&self) -> &str {
-        self.temppath.path().to_str().unwrap()
+    //////// 0L ////////
+    /// The storage as a backend argument of the commands executed by the helper
+    fn backend(&self) -> Result<String, Error> {
+        self.storage.backend()
     }
 
     //////// 0L ////////    
@@ -278,6 +344,8 @@ impl StorageHelper {
         storage
             .import_private_key(VALIDATOR_NETWORK_KEY, Ed25519PrivateKey::generate(&mut rng))
             .unwrap();
+        //////// 0L ////////
+        import_vfn_network_key(&mut storage, Ed25519PrivateKey::generate(&mut rng)).unwrap();
 
         // Initialize all other data in storage
         storage
@@ -313,17 +381,16 @@ impl StorageHelper {
     }
 
     pub fn create_waypoint(&self, chain_id: ChainId) -> Result<Waypoint, Error> {
+        trace_span!("storage_helper.create_waypoint", chain_id = %chain_id);
         let args = format!(
             "
                 diem-genesis-tool
                 create-waypoint
                 --chain-id {chain_id}
-                --shared-backend backend={backend};\
-                    path={path}
+                --shared-backend {backend}
             ",
             chain_id = chain_id,
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
         );
 
         let command = Command::from_iter(args.split_whitespace());
@@ -338,6 +405,7 @@ impl StorageHelper {
         genesis_path: &PathBuf,
         layout_path: &PathBuf,
     ) -> Result<Waypoint, Error> {
+        trace_span!("storage_helper.build_genesis", chain_id = %chain_id, layout = "local");
         let args = format!(
         "
             diem-genesis-tool
@@ -364,6 +432,7 @@ impl StorageHelper {
         remote: &str,
         genesis_path: &PathBuf,
     ) -> Result<Waypoint, Error> {
+        trace_span!("storage_helper.build_genesis", chain_id = %chain_id, layout = "remote");
         let args = format!(
         "
             diem-genesis-tool
@@ -381,19 +450,39 @@ impl StorageHelper {
         command.create_waypoint()
     }    
 
+    ///////// 0L  /////////
+    /// Builds genesis from the registrations of a remote registry, with the layout of the
+    /// registry unless one is given
+    pub fn build_genesis_from_registry(
+        &self,
+        chain_id: ChainId,
+        registry: &dyn RemoteRegistry,
+        work_dir: &Path,
+        namespace: &str,
+        genesis_path: &PathBuf,
+        layout_path: &Option<PathBuf>,
+    ) -> Result<Waypoint, Error> {
+        let remote = registry.shared_backend(work_dir, namespace)?;
+        match layout_path {
+            Some(layout_path) => {
+                self.build_genesis_with_layout(chain_id, &remote, genesis_path, layout_path)
+            }
+            None => self.build_genesis_from_github(chain_id, &remote, genesis_path),
+        }
+    }
+
     pub fn insert_waypoint(&self, validator_ns: &str, waypoint: Waypoint) -> Result<(), Error> {
+        trace_span!("storage_helper.insert_waypoint", namespace = validator_ns);
         let args = format!(
             "
                 diem-genesis-tool
                 insert-waypoint
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={validator_ns}
                 --waypoint {waypoint}
                 --set-genesis
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             validator_ns = validator_ns,
             waypoint = waypoint,
         );
@@ -403,18 +492,17 @@ impl StorageHelper {
     }
 
     pub fn genesis(&self, chain_id: ChainId, genesis_path: &Path) -> Result<Transaction, Error> {
+        trace_span!("storage_helper.genesis", chain_id = %chain_id);
         let args = format!(
             "
                 diem-genesis-tool
                 genesis
                 --chain-id {chain_id}
-                --shared-backend backend={backend};\
-                    path={path}
+                --shared-backend {backend}
                 --path {genesis_path}
             ",
             chain_id = chain_id,
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             genesis_path = genesis_path.to_str().expect("Unable to parse genesis_path"),
         );
 
@@ -429,6 +517,7 @@ impl StorageHelper {
         remote: &str,
         genesis_path: &PathBuf,
     ) -> Result<Transaction, Error> {
+        trace_span!("storage_helper.genesis", chain_id = %chain_id, remote = true);
         let args = format!(
             "
                 diem-genesis-tool
@@ -455,15 +544,12 @@ impl StorageHelper {
             "
                 diem-genesis-tool
                 diem-root-key
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={validator_ns}
-                --shared-backend backend={backend};\
-                    path={path};\
+                --shared-backend {backend};\
                     namespace={shared_ns}
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             validator_ns = validator_ns,
             shared_ns = shared_ns,
         );
@@ -481,15 +567,12 @@ impl StorageHelper {
             "
                 diem-genesis-tool
                 operator-key
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={validator_ns}
-                --shared-backend backend={backend};\
-                    path={path};\
+                --shared-backend {backend};\
                     namespace={shared_ns}
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             validator_ns = validator_ns,
             shared_ns = shared_ns,
         );
@@ -507,15 +590,12 @@ impl StorageHelper {
             "
                 diem-genesis-tool
                 owner-key
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={validator_ns}
-                --shared-backend backend={backend};\
-                    path={path};\
+                --shared-backend {backend};\
                     namespace={shared_ns}
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             validator_ns = validator_ns,
             shared_ns = shared_ns,
         );
@@ -524,19 +604,39 @@ impl StorageHelper {
         command.owner_key()
     }
 
+    //////// 0L ////////
+    /// Signs the registrations of `owner_name` with the owner key of `validator_ns`
+    pub fn sign_registration(&self, validator_ns: &str, owner_name: &str) -> Result<String, Error> {
+        let args = format!(
+            "
+                diem-genesis-tool
+                sign-registration
+                --owner-name {owner_name}
+                --validator-backend {backend};\
+                    namespace={validator_ns}
+                --shared-backend {backend}
+            ",
+            owner_name = owner_name,
+            backend = self.backend()?,
+            validator_ns = validator_ns,
+        );
+
+        let command = Command::from_iter(args.split_whitespace());
+        command.sign_registration()
+    }
+
     #[cfg(test)]
     pub fn set_layout(&self, path: &str) -> Result<crate::layout::Layout, Error> {
+        trace_span!("storage_helper.set_layout", path);
         let args = format!(
             "
                 diem-genesis-tool
                 set-layout
                 --path {path}
-                --shared-backend backend={backend};\
-                    path={storage_path}
+                --shared-backend {backend}
             ",
             path = path,
-            backend = DISK,
-            storage_path = self.path_string(),
+            backend = self.backend()?,
         );
 
         let command = Command::from_iter(args.split_whitespace());
@@ -549,13 +649,11 @@ impl StorageHelper {
                 diem-genesis-tool
                 set-operator
                 --operator-name {operator_name}
-                --shared-backend backend={backend};\
-                    path={path};\
+                --shared-backend {backend};\
                     namespace={shared_ns}
             ",
             operator_name = operator_name,
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             shared_ns = shared_ns,
         );
 
@@ -572,15 +670,12 @@ impl StorageHelper {
             "
                 diem-genesis-tool
                 treasury-compliance-key
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={validator_ns}
-                --shared-backend backend={backend};\
-                    path={path};\
+                --shared-backend {backend};\
                     namespace={shared_ns}
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             validator_ns = validator_ns,
             shared_ns = shared_ns,
         );
@@ -606,19 +701,16 @@ impl StorageHelper {
                 --validator-address {validator_address}
                 --fullnode-address {fullnode_address}
                 --chain-id {chain_id}
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={validator_ns}
-                --shared-backend backend={backend};\
-                    path={path};\
+                --shared-backend {backend};\
                     namespace={shared_ns}
             ",
             owner_name = owner_name,
             validator_address = validator_address,
             fullnode_address = fullnode_address,
             chain_id = chain_id.id(),
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             validator_ns = validator_ns,
             shared_ns = shared_ns,
         );
@@ -633,12 +725,10 @@ impl StorageHelper {
             "
                 diem-genesis-tool
                 verify
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={ns}
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             ns = namespace,
         );
 
@@ -647,17 +737,16 @@ impl StorageHelper {
     }
 
     pub fn verify_genesis(&self, namespace: &str, genesis_path: &Path) -> Result<String, Error> {
+        trace_span!("storage_helper.verify_genesis", namespace);
         let args = format!(
             "
                 diem-genesis-tool
                 verify
-                --validator-backend backend={backend};\
-                    path={path};\
+                --validator-backend {backend};\
                     namespace={ns}
                 --genesis-path {genesis_path}
             ",
-            backend = DISK,
-            path = self.path_string(),
+            backend = self.backend()?,
             ns = namespace,
             genesis_path = genesis_path.to_str().expect("Unable to parse genesis_path"),
         );
@@ -666,3 +755,38 @@ impl StorageHelper {
         command.verify()
     }
 }
+
+//////// 0L ////////
+/// Imports the network key of the VFN of a validator, with the peer id it derives, so that the
+/// private network of the validator and its VFN runs on keys of its own
+pub fn import_vfn_network_key(storage: &mut Storage, key: Ed25519PrivateKey) -> Result<(), Error> {
+    let network_key = x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes())
+        .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    let peer_id = account_address::from_identity_public_key(network_key.public_key());
+    storage
+        .import_private_key(VFN_NETWORK_KEY, key)
+        .map_err(|e| Error::StorageWriteError("validator", VFN_NETWORK_KEY, e.to_string()))?;
+    storage
+        .set(VFN_NETWORK_PEER_ID, peer_id)
+        .map_err(|e| Error::StorageWriteError("validator", VFN_NETWORK_PEER_ID, e.to_string()))
+}
+
+//////// 0L ////////
+/// The network key and peer id of the VFN of a validator. A key store from before the VFN had
+/// keys of its own gets a random key.
+pub fn vfn_network_identity(storage: &mut Storage) -> Result<(x25519::PublicKey, PeerId), Error> {
+    if storage.get_public_key(VFN_NETWORK_KEY).is_err() {
+        import_vfn_network_key(storage, Ed25519PrivateKey::generate(&mut rand::rngs::OsRng))?;
+    }
+    let key = storage
+        .get_public_key(VFN_NETWORK_KEY)
+        .map_err(|e| Error::StorageReadError("validator", VFN_NETWORK_KEY, e.to_string()))?
+        .public_key;
+    let key = x25519::PublicKey::from_ed25519_public_bytes(&key.to_bytes())
+        .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+    let peer_id = storage
+        .get::<PeerId>(VFN_NETWORK_PEER_ID)
+        .map_err(|e| Error::StorageReadError("validator", VFN_NETWORK_PEER_ID, e.to_string()))?
+        .value;
+    Ok((key, peer_id))
+}
@@ -0,0 +1,538 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Builds the configs of a complete local network for integration tests and devnets.
+//!
+//! ```ignore
+//! let swarm = SwarmBuilder::new().validators(4).vfns(2).public_fullnodes(1).build(&dir)?;
+//! ```
+
+use crate::{
+    config_builder::{operator_namespace, FullnodeBuilder, FullnodeType, ValidatorBuilder},
+    ol_node_files::{make_all_profiles_yaml, ListenAddresses},
+    rerun::line_diff,
+    seeds::Seeds,
+    swarm_config::BuildSwarm,
+};
+use anyhow::{ensure, Result};
+use diem_config::{
+    config::{NetworkConfig, NodeConfig, PeerRole},
+    doctor::listen_addresses,
+    generator::build_seed_for_network,
+    network_id::NetworkId,
+    redact::redact,
+};
+use diem_crypto::ed25519::Ed25519PrivateKey;
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::AccountAddress, chain_id::ChainId, transaction::Transaction,
+    waypoint::Waypoint, PeerId,
+};
+use diem_wallet::{Mnemonic, WalletLibrary};
+use ol_keys::scheme::KeyScheme;
+use ol_types::fixtures;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+};
+
+/// The configs of a swarm. All nodes share the genesis and waypoint of the validators.
+pub struct Swarm {
+    pub validators: Vec<NodeConfig>,
+    /// The validator full node `i` is attached to validator `i`
+    pub vfns: Vec<NodeConfig>,
+    pub public_fullnodes: Vec<NodeConfig>,
+    pub diem_root_key: Ed25519PrivateKey,
+    pub waypoint: Waypoint,
+}
+
+impl Swarm {
+    /// All node configs, validators first
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeConfig> {
+        self.validators
+            .iter()
+            .chain(self.vfns.iter())
+            .chain(self.public_fullnodes.iter())
+    }
+}
+
+pub struct SwarmBuilder {
+    num_validators: usize,
+    num_vfns: usize,
+    num_public_fullnodes: usize,
+    validator_template: NodeConfig,
+    vfn_template: NodeConfig,
+    public_fullnode_template: NodeConfig,
+}
+
+impl Default for SwarmBuilder {
+    fn default() -> Self {
+        Self {
+            num_validators: 1,
+            num_vfns: 0,
+            num_public_fullnodes: 0,
+            validator_template: NodeConfig::default_for_validator(),
+            vfn_template: NodeConfig::default_for_validator_full_node(),
+            public_fullnode_template: NodeConfig::default_for_public_full_node(),
+        }
+    }
+}
+
+impl SwarmBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn validators(mut self, num_validators: usize) -> Self {
+        self.num_validators = num_validators;
+        self
+    }
+
+    /// Validator full nodes, at most one per validator
+    pub fn vfns(mut self, num_vfns: usize) -> Self {
+        self.num_vfns = num_vfns;
+        self
+    }
+
+    pub fn public_fullnodes(mut self, num_public_fullnodes: usize) -> Self {
+        self.num_public_fullnodes = num_public_fullnodes;
+        self
+    }
+
+    pub fn validator_template(mut self, template: NodeConfig) -> Self {
+        self.validator_template = template;
+        self
+    }
+
+    pub fn vfn_template(mut self, template: NodeConfig) -> Self {
+        self.vfn_template = template;
+        self
+    }
+
+    pub fn public_fullnode_template(mut self, template: NodeConfig) -> Self {
+        self.public_fullnode_template = template;
+        self
+    }
+
+    /// Builds the configs, `swarm_path` holds the secure storage of the validators and must
+    /// outlive the swarm
+    pub fn build(&self, swarm_path: &Path) -> Result<Swarm> {
+        ensure!(
+            self.num_validators > 0,
+            "A swarm needs at least one validator"
+        );
+        ensure!(
+            self.num_vfns <= self.num_validators,
+            "Requested {} validator full nodes for {} validators",
+            self.num_vfns,
+            self.num_validators
+        );
+
+        let (mut validators, diem_root_key) = ValidatorBuilder::new(
+            self.num_validators,
+            self.validator_template.clone(),
+            swarm_path,
+        )
+        .build_swarm()?;
+        let waypoint = validators[0].base.waypoint.waypoint();
+
+        // Each validator full node swaps its public listen address with the vfn network of its
+        // validator and is seeded with it
+        let vfn_builder = FullnodeBuilder::new(
+            vec![],
+            PathBuf::new(),
+            self.vfn_template.clone(),
+            FullnodeType::ValidatorFullnode,
+        );
+        let vfns: Vec<_> = validators
+            .iter_mut()
+            .take(self.num_vfns)
+            .map(|validator| vfn_builder.attach_validator_full_node(validator))
+            .collect();
+
+        // Public full nodes connect to the public networks of all validator full nodes
+        let mut public_seeds = HashMap::new();
+        for vfn in &vfns {
+            if let Some(network) = public_network(vfn) {
+                public_seeds.extend(build_seed_for_network(network, PeerRole::Upstream));
+            }
+        }
+        let mut public_fullnodes = vec![];
+        for _ in 0..self.num_public_fullnodes {
+            let mut config = self.public_fullnode_template.clone();
+            config.randomize_ports();
+            FullnodeBuilder::insert_waypoint_and_genesis(&mut config, &validators[0]);
+            if let Some(network) = config
+                .full_node_networks
+                .iter_mut()
+                .find(|network| network.network_id == NetworkId::Public)
+            {
+                network.seeds = public_seeds.clone();
+            }
+            public_fullnodes.push(config);
+        }
+
+        let swarm = Swarm {
+            validators,
+            vfns,
+            public_fullnodes,
+            diem_root_key,
+            waypoint,
+        };
+        check_distinct_ports(&swarm)?;
+        Ok(swarm)
+    }
+}
+
+fn public_network(config: &NodeConfig) -> Option<&NetworkConfig> {
+    config
+        .full_node_networks
+        .iter()
+        .find(|network| network.network_id == NetworkId::Public)
+}
+
+/// Ports are allocated per node, so make sure no two nodes ended up with the same one
+fn check_distinct_ports(swarm: &Swarm) -> Result<()> {
+    let mut seen = HashMap::new();
+    for (index, config) in swarm.nodes().enumerate() {
+        for (name, address) in listen_addresses(config) {
+            if let Some((other, other_name)) = seen.insert(address.port(), (index, name.clone())) {
+                ensure!(
+                    other == index,
+                    "Port {} is used by {} of node {} and {} of node {}",
+                    address.port(),
+                    other_name,
+                    other,
+                    name,
+                    index
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The fixture personas, used as the first test identities
+const PERSONAS: &[&str] = &["alice", "bob", "carol", "dave"];
+
+/// The keys of test node `index`, with its peer id and account. Identities only depend on the
+/// index, so they are stable across runs and swarm sizes. The first identities are the fixture
+/// personas (alice, bob, ...), the following ones are derived from the index.
+///
+/// Nodes use their owner account as peer id on the validator network, so both are returned equal.
+pub fn test_identity(index: usize) -> (KeyScheme, PeerId, AccountAddress) {
+    let keys = match PERSONAS.get(index) {
+        Some(persona) => KeyScheme::new_from_mnemonic(fixtures::get_persona_mnem(persona)),
+        None => {
+            let mut entropy = [0u8; 32];
+            entropy[..8].copy_from_slice(&(index as u64).to_le_bytes());
+            let mnemonic = Mnemonic::mnemonic(&entropy).unwrap();
+            KeyScheme::new(&WalletLibrary::new_from_mnemonic(mnemonic))
+        }
+    };
+    let account = keys.derived_address();
+    (keys, account, account)
+}
+
+/// A small genesis with its waypoint and the key stores of its validators. The files live in a
+/// temporary directory that is removed when the fixture is dropped.
+pub struct GenesisFixture {
+    pub chain_id: ChainId,
+    pub genesis: Transaction,
+    pub waypoint: Waypoint,
+    /// Validator configs, with their key stores in `dir`
+    pub validators: Vec<NodeConfig>,
+    pub diem_root_key: Ed25519PrivateKey,
+    dir: TempPath,
+}
+
+impl GenesisFixture {
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The serialized genesis, as it would be distributed
+    pub fn genesis_blob(&self) -> Vec<u8> {
+        bcs::to_bytes(&self.genesis).unwrap()
+    }
+
+    /// The genesis blob written to disk, for APIs that read it from a file
+    pub fn genesis_path(&self) -> PathBuf {
+        self.dir.path().join(GENESIS_FILE)
+    }
+}
+
+const GENESIS_FILE: &str = "genesis.blob";
+
+/// Builds a genesis with `n_validators` test validators, without network access
+pub fn genesis_fixture(n_validators: usize, chain_id: ChainId) -> GenesisFixture {
+    let dir = TempPath::new();
+    dir.create_as_dir().unwrap();
+    let (validators, diem_root_key) = ValidatorBuilder::new(
+        n_validators,
+        NodeConfig::default_for_validator(),
+        dir.path(),
+    )
+    .chain_id(chain_id)
+    .build_swarm()
+    .unwrap();
+
+    let genesis = validators[0].execution.genesis.clone().unwrap();
+    let waypoint = validators[0].base.waypoint.waypoint();
+    fs::write(
+        dir.path().join(GENESIS_FILE),
+        bcs::to_bytes(&genesis).unwrap(),
+    )
+    .unwrap();
+
+    GenesisFixture {
+        chain_id,
+        genesis,
+        waypoint,
+        validators,
+        diem_root_key,
+        dir,
+    }
+}
+
+/// A participant of a simulated genesis ceremony, with the node files it generated
+pub struct CeremonyParticipant {
+    /// The namespace of the participant in its key store
+    pub namespace: String,
+    /// Holds `key_store.json`, `genesis.blob` and the node configs, as written by
+    /// `ol_node_files`
+    pub dir: PathBuf,
+    pub validator: NodeConfig,
+}
+
+pub struct CeremonyOutcome {
+    pub genesis_path: PathBuf,
+    pub waypoint: Waypoint,
+    pub participants: Vec<CeremonyParticipant>,
+}
+
+/// Runs a genesis ceremony of `n_participants` in one process: every participant uploads its
+/// keys, proofs and validator config to the shared backend, genesis is built from it, the
+/// waypoint is inserted into each participant's namespace, and each participant writes its node
+/// configs into `dir/<index>`. The shared backend is a local store rather than GitHub; it lives
+/// on disk as the management commands open it from its config.
+pub fn simulate_ceremony(
+    n_participants: usize,
+    chain_id: ChainId,
+    dir: &Path,
+) -> Result<CeremonyOutcome> {
+    let builder = ValidatorBuilder::new(n_participants, NodeConfig::default_for_validator(), dir)
+        .chain_id(chain_id);
+    let (validators, _) = builder.build_swarm()?;
+    let genesis = validators[0]
+        .execution
+        .genesis
+        .as_ref()
+        .ok_or_else(|| anyhow::format_err!("Genesis was not built"))?;
+    let waypoint = validators[0].base.waypoint.waypoint();
+
+    let genesis_path = dir.join(GENESIS_FILE);
+    fs::write(&genesis_path, bcs::to_bytes(genesis)?)?;
+    let seeds = Seeds::new(genesis_path.clone()).get_network_peers_info()?;
+
+    let mut participants = vec![];
+    for index in 0..n_participants {
+        let namespace = operator_namespace(index);
+        let participant_dir = dir.join(index.to_string());
+        fs::create_dir_all(&participant_dir)?;
+        fs::copy(
+            builder.storage_path(),
+            participant_dir.join("key_store.json"),
+        )?;
+        fs::copy(&genesis_path, participant_dir.join(GENESIS_FILE))?;
+
+        let validator = make_all_profiles_yaml(
+            participant_dir.clone(),
+            Ipv4Addr::LOCALHOST.into(),
+            None,
+            Some(seeds.clone()),
+            &namespace,
+            waypoint,
+            &ListenAddresses::default(),
+        )?;
+        participants.push(CeremonyParticipant {
+            namespace,
+            dir: participant_dir,
+            validator,
+        });
+    }
+
+    Ok(CeremonyOutcome {
+        genesis_path,
+        waypoint,
+        participants,
+    })
+}
+
+/// Set to rewrite the stored snapshots with the current output, as for the move baselines
+const UPDATE_BASELINE: &str = "UPDATE_BASELINE";
+/// Set by CI, where snapshots are never rewritten
+const CI: &str = "CI";
+
+/// Compares the sanitized yaml of `config` against the snapshot `tests/snapshots/<name>.yaml` of
+/// the calling crate, and panics with a line diff if they differ. Catches refactors of config
+/// generation that silently alter the output. Missing snapshots are written and fail the test, so
+/// they get reviewed before being committed; set `UPDATE_BASELINE=1` to accept changes. On CI
+/// `UPDATE_BASELINE` is ignored, so a stale snapshot always fails there.
+pub fn assert_config_snapshot<T: Serialize>(name: &str, config: &T) {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("Run by cargo");
+    let update = std::env::var_os(CI).is_none()
+        && std::env::var(UPDATE_BASELINE).map_or(false, |value| value == "1");
+    assert_snapshot_in(
+        &Path::new(&manifest_dir).join("tests/snapshots"),
+        name,
+        config,
+        update,
+    )
+}
+
+fn assert_snapshot_in<T: Serialize>(dir: &Path, name: &str, config: &T, update: bool) {
+    let mut value = serde_yaml::to_value(config).unwrap();
+    redact(&mut value);
+    let actual = serde_yaml::to_string(&value).unwrap();
+
+    let path = dir.join(name).with_extension("yaml");
+    if update || !path.exists() {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(&path, &actual).unwrap();
+        assert!(
+            update,
+            "Wrote new snapshot {:?}, review it and run again",
+            path
+        );
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap();
+    if expected != actual {
+        panic!(
+            "Config differs from snapshot {:?} (- snapshot, + actual), rerun with {}=1 to \
+             accept the change:\n{}",
+            path,
+            UPDATE_BASELINE,
+            line_diff(&expected, &actual)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::compute_genesis;
+    use diem_config::config::RoleType;
+
+    #[test]
+    fn test_build_swarm() {
+        let path = TempPath::new();
+        path.create_as_dir().unwrap();
+        let swarm = SwarmBuilder::new()
+            .validators(2)
+            .vfns(1)
+            .public_fullnodes(1)
+            .build(path.path())
+            .unwrap();
+
+        assert_eq!(swarm.validators.len(), 2);
+        assert_eq!(swarm.vfns.len(), 1);
+        assert_eq!(swarm.public_fullnodes.len(), 1);
+        for config in swarm.nodes() {
+            assert_eq!(config.base.waypoint.waypoint(), swarm.waypoint);
+            assert_eq!(
+                config.execution.genesis,
+                swarm.validators[0].execution.genesis
+            );
+        }
+
+        let public_network = public_network(&swarm.public_fullnodes[0]).unwrap();
+        assert_eq!(public_network.seeds.len(), 1);
+    }
+
+    #[test]
+    fn test_too_many_vfns() {
+        let path = TempPath::new();
+        path.create_as_dir().unwrap();
+        assert!(SwarmBuilder::new().vfns(2).build(path.path()).is_err());
+    }
+
+    #[test]
+    fn test_genesis_fixture() {
+        let fixture = genesis_fixture(2, ChainId::test());
+        assert_eq!(fixture.validators.len(), 2);
+
+        let db_path = TempPath::new();
+        let (_, waypoint) = compute_genesis(&fixture.genesis_path(), db_path.path()).unwrap();
+        assert_eq!(waypoint, fixture.waypoint);
+
+        let seeds = Seeds::new(fixture.genesis_path())
+            .get_network_peers_info()
+            .unwrap();
+        assert_eq!(seeds.len(), 2);
+    }
+
+    #[test]
+    fn test_identity_is_stable() {
+        let (_, peer_id, account) = test_identity(0);
+        assert_eq!(
+            account,
+            KeyScheme::new_from_mnemonic(fixtures::get_persona_mnem("alice")).derived_address()
+        );
+        assert_eq!(peer_id, account);
+
+        let (_, _, first) = test_identity(PERSONAS.len());
+        let (_, _, second) = test_identity(PERSONAS.len() + 1);
+        assert_ne!(first, second);
+        assert_eq!(first, test_identity(PERSONAS.len()).2);
+    }
+
+    #[test]
+    fn test_simulate_ceremony() {
+        let path = TempPath::new();
+        path.create_as_dir().unwrap();
+        let outcome = simulate_ceremony(2, ChainId::test(), path.path()).unwrap();
+
+        assert_eq!(outcome.participants.len(), 2);
+        for participant in &outcome.participants {
+            for file in &["validator.node.yaml", "vfn.node.yaml", "fullnode.node.yaml"] {
+                assert!(participant.dir.join(file).exists());
+            }
+            // The waypoint was distributed to the key store of the participant
+            assert_eq!(
+                participant.validator.base.waypoint.waypoint(),
+                outcome.waypoint
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_snapshot() {
+        let dir = TempPath::new();
+        let config = NodeConfig::default_for_validator();
+
+        // New snapshots are written and have to be reviewed
+        let result = std::panic::catch_unwind(|| {
+            assert_snapshot_in(dir.path(), "validator", &config, false)
+        });
+        assert!(result.is_err());
+        assert_snapshot_in(dir.path(), "validator", &config, false);
+
+        let mut changed = config.clone();
+        changed.base.role = RoleType::FullNode;
+        let result = std::panic::catch_unwind(|| {
+            assert_snapshot_in(dir.path(), "validator", &changed, false)
+        });
+        assert!(result.is_err());
+
+        // Accepting the change rewrites the snapshot
+        assert_snapshot_in(dir.path(), "validator", &changed, true);
+        assert_snapshot_in(dir.path(), "validator", &changed, false);
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! `topology` subcommands, for auditing the connectivity of a network.
+//!
+//! The graph is derived from a validator set, read from a genesis blob or from a running node:
+//! validators form a mesh on the validator network, each validator's VFN connects to it on the
+//! private VFN network, and public fullnodes connect to the VFNs on the public network. Public
+//! fullnodes are not known from the validator set, so they are a single node of the graph.
+
+use crate::validator_set::ValidatorSetSource;
+use diem_config::{config::PeerRole, network_id::NetworkId};
+use diem_management::error::Error;
+use diem_types::{on_chain_config::ValidatorSet, PeerId};
+use serde::Serialize;
+use std::{fmt::Write, fs, path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub enum TopologyCommand {
+    #[structopt(about = "Prints the peer graph of the validator set")]
+    Export(Export),
+    #[structopt(about = "Writes the Prometheus file_sd targets of the validator set")]
+    FileSd(crate::file_sd::FileSd),
+}
+
+impl TopologyCommand {
+    pub fn execute(self) -> Result<String, Error> {
+        match self {
+            TopologyCommand::Export(export) => export.execute(),
+            TopologyCommand::FileSd(file_sd) => file_sd.execute(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Dot,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "json" => Ok(Format::Json),
+            _ => Err(Error::UnexpectedError(format!(
+                "unknown topology format {}, expected dot or json",
+                s
+            ))),
+        }
+    }
+}
+
+/// Exports the validator/VFN/public peer graph
+#[derive(Debug, StructOpt)]
+pub struct Export {
+    #[structopt(flatten)]
+    source: ValidatorSetSource,
+    /// Graph format, dot or json
+    #[structopt(long, default_value = "dot")]
+    format: Format,
+    /// Write the graph to this file instead of printing it
+    #[structopt(long)]
+    output: Option<PathBuf>,
+}
+
+impl Export {
+    pub fn execute(self) -> Result<String, Error> {
+        let topology = Topology::from_validator_set(&self.source.validator_set()?);
+        let graph = match self.format {
+            Format::Dot => topology.to_dot(),
+            Format::Json => serde_json::to_string_pretty(&topology)
+                .map_err(|e| Error::UnexpectedError(e.to_string()))?,
+        };
+        match self.output {
+            Some(output) => {
+                fs::write(&output, graph)
+                    .map_err(|e| Error::IO(output.display().to_string(), e))?;
+                Ok(format!("Topology written to {}", output.display()))
+            }
+            None => Ok(graph),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Validator,
+    Vfn,
+    /// All the public fullnodes
+    Public,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub kind: NodeKind,
+    /// The validator account the node belongs to
+    pub account: Option<PeerId>,
+    pub addresses: Vec<String>,
+}
+
+/// A connection from a node to a peer, e.g., a VFN to its validator
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub network: NetworkId,
+    /// The role of `to` as seen by `from`
+    pub role: PeerRole,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Topology {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+const PUBLIC_NODE: &str = "public";
+
+impl Topology {
+    pub fn from_validator_set(validator_set: &ValidatorSet) -> Self {
+        let mut topology = Topology::default();
+        let validators: Vec<_> = validator_set.payload().iter().collect();
+
+        for (index, info) in validators.iter().enumerate() {
+            let id = node_id("validator", info.account_address());
+            topology.nodes.push(Node {
+                id: id.clone(),
+                kind: NodeKind::Validator,
+                account: Some(*info.account_address()),
+                // Validator addresses are encrypted on chain
+                addresses: vec![],
+            });
+            for peer in &validators[index + 1..] {
+                topology.edges.push(Edge {
+                    from: id.clone(),
+                    to: node_id("validator", peer.account_address()),
+                    network: NetworkId::Validator,
+                    role: PeerRole::Validator,
+                });
+            }
+
+            let addresses = info
+                .config()
+                .fullnode_network_addresses()
+                .unwrap_or_default();
+            if addresses.is_empty() {
+                continue;
+            }
+            let vfn = node_id("vfn", info.account_address());
+            topology.nodes.push(Node {
+                id: vfn.clone(),
+                kind: NodeKind::Vfn,
+                account: Some(*info.account_address()),
+                addresses: addresses.iter().map(|a| a.to_string()).collect(),
+            });
+            topology.edges.push(Edge {
+                from: vfn.clone(),
+                to: id,
+                network: NetworkId::vfn_network(),
+                role: PeerRole::Validator,
+            });
+            topology.edges.push(Edge {
+                from: PUBLIC_NODE.to_string(),
+                to: vfn,
+                network: NetworkId::Public,
+                role: PeerRole::ValidatorFullNode,
+            });
+        }
+
+        if topology.nodes.iter().any(|node| node.kind == NodeKind::Vfn) {
+            topology.nodes.push(Node {
+                id: PUBLIC_NODE.to_string(),
+                kind: NodeKind::Public,
+                account: None,
+                addresses: vec![],
+            });
+        }
+        topology
+    }
+
+    /// Graphviz DOT, the validator mesh is undirected
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph topology {\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Validator => "box",
+                NodeKind::Vfn => "ellipse",
+                NodeKind::Public => "doubleoctagon",
+            };
+            writeln!(
+                dot,
+                "  \"{}\" [shape={}, label=\"{}\"];",
+                node.id, shape, node.id
+            )
+            .unwrap();
+        }
+        for edge in &self.edges {
+            let dir = if edge.network == NetworkId::Validator {
+                ", dir=none"
+            } else {
+                ""
+            };
+            writeln!(
+                dot,
+                "  \"{}\" -> \"{}\" [label=\"{} ({:?})\"{}];",
+                edge.from, edge.to, edge.network, edge.role, dir
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn node_id(kind: &str, account: &PeerId) -> String {
+    format!("{}-{}", kind, account.short_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::genesis_fixture;
+    use diem_types::chain_id::ChainId;
+
+    #[test]
+    fn test_topology() {
+        let fixture = genesis_fixture(3, ChainId::test());
+        let source = ValidatorSetSource::from_genesis(fixture.genesis_path());
+        let topology = Topology::from_validator_set(&source.validator_set().unwrap());
+
+        let validators = topology
+            .nodes
+            .iter()
+            .filter(|node| node.kind == NodeKind::Validator)
+            .count();
+        assert_eq!(validators, 3);
+        let mesh = topology
+            .edges
+            .iter()
+            .filter(|edge| edge.network == NetworkId::Validator)
+            .count();
+        assert_eq!(mesh, 3);
+
+        for edge in &topology.edges {
+            assert!(topology.nodes.iter().any(|node| node.id == edge.from));
+            assert!(topology.nodes.iter().any(|node| node.id == edge.to));
+        }
+        let dot = topology.to_dot();
+        assert!(dot.starts_with("digraph topology {"));
+        assert_eq!(dot.matches(" -> ").count(), topology.edges.len());
+    }
+}
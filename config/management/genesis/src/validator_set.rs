@@ -0,0 +1,139 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Reads the validator set, either from a genesis blob or from a running node through JSON-RPC.
+
+use crate::verify::compute_genesis;
+use diem_client::BlockingClient;
+use diem_management::{error::Error, trace_span};
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::AccountAddress, account_config, account_state::AccountState,
+    account_state_blob::AccountStateBlob, on_chain_config::ValidatorSet,
+    validator_info::ValidatorInfo,
+};
+use std::{convert::TryFrom, path::PathBuf};
+use storage_interface::DbReaderWriter;
+use structopt::StructOpt;
+
+/// Where to read the validator set from
+#[derive(Debug, StructOpt)]
+pub struct ValidatorSetSource {
+    /// Path to a genesis blob
+    #[structopt(long, required_unless = "json_rpc", conflicts_with = "json_rpc")]
+    pub genesis_path: Option<PathBuf>,
+    /// JSON-RPC endpoint of a node, e.g. http://localhost:8080
+    #[structopt(long)]
+    pub json_rpc: Option<String>,
+}
+
+/// A validator of the set, with the name of its config
+pub struct Validator {
+    pub info: ValidatorInfo,
+    /// The `human_name` of the validator config, the operator's namespace
+    pub name: String,
+}
+
+impl ValidatorSetSource {
+    pub fn from_genesis(genesis_path: PathBuf) -> Self {
+        Self {
+            genesis_path: Some(genesis_path),
+            json_rpc: None,
+        }
+    }
+
+    pub fn from_json_rpc(json_rpc: String) -> Self {
+        Self {
+            genesis_path: None,
+            json_rpc: Some(json_rpc),
+        }
+    }
+
+    pub fn validator_set(&self) -> Result<ValidatorSet, Error> {
+        validator_set(&self.reader()?)
+    }
+
+    /// The validators of the set, with the names of their configs
+    pub fn validators(&self) -> Result<Vec<Validator>, Error> {
+        let reader = self.reader()?;
+        validator_set(&reader)?
+            .payload()
+            .iter()
+            .map(|info| {
+                let name = reader
+                    .account_state(*info.account_address())?
+                    .get_validator_config_resource()
+                    .ok()
+                    .flatten()
+                    .map(|resource| human_name(&resource.human_name))
+                    .unwrap_or_else(|| info.account_address().to_string());
+                Ok(Validator {
+                    info: info.clone(),
+                    name,
+                })
+            })
+            .collect()
+    }
+
+    fn reader(&self) -> Result<Reader, Error> {
+        match (&self.genesis_path, &self.json_rpc) {
+            (Some(genesis_path), _) => {
+                trace_span!("validator_set.compute_genesis", genesis_path = ?genesis_path);
+                let db_path = TempPath::new();
+                let (db_rw, _expected_waypoint) = compute_genesis(genesis_path, db_path.path())?;
+                Ok(Reader::Genesis(db_rw, db_path))
+            }
+            (None, Some(json_rpc)) => Ok(Reader::JsonRpc(BlockingClient::new(json_rpc.clone()))),
+            (None, None) => Err(Error::CommandArgumentError(
+                "either --genesis-path or --json-rpc is required".to_string(),
+            )),
+        }
+    }
+}
+
+enum Reader {
+    /// The genesis applied to a temporary db, which lives as long as the reader
+    Genesis(DbReaderWriter, TempPath),
+    JsonRpc(BlockingClient),
+}
+
+impl Reader {
+    fn account_state(&self, account: AccountAddress) -> Result<AccountState, Error> {
+        let blob = match self {
+            Reader::Genesis(db_rw, _) => db_rw
+                .reader
+                .get_latest_account_state(account)
+                .map_err(|e| Error::UnexpectedError(format!("{} account issue {}", account, e)))?,
+            Reader::JsonRpc(client) => {
+                let account_state = client
+                    .get_account_state_with_proof(account, None, None)
+                    .map_err(|e| Error::JsonRpcReadError("account-state", e.to_string()))?
+                    .into_inner();
+                account_state
+                    .blob
+                    .map(|blob| bcs::from_bytes::<Vec<u8>>(&blob).map(AccountStateBlob::from))
+                    .transpose()
+                    .map_err(|e| Error::JsonRpcReadError("account-state", e.to_string()))?
+            }
+        }
+        .ok_or_else(|| Error::UnexpectedError(format!("{} account does not exist", account)))?;
+
+        AccountState::try_from(&blob)
+            .map_err(|e| Error::UnexpectedError(format!("Failed to parse blob: {}", e)))
+    }
+}
+
+fn validator_set(reader: &Reader) -> Result<ValidatorSet, Error> {
+    reader
+        .account_state(account_config::validator_set_address())?
+        .get_validator_set()
+        .map_err(|e| Error::UnexpectedError(format!("ValidatorSet issue {}", e.to_string())))?
+        .ok_or_else(|| Error::UnexpectedError("ValidatorSet does not exist".into()))
+}
+
+fn human_name(name: &[u8]) -> String {
+    std::str::from_utf8(name)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|_| hex::encode(name))
+}
@@ -0,0 +1,324 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! `verify-genesis` rebuilds the genesis from the registrations of the genesis repo and compares
+//! it to a distributed genesis blob and waypoint. Rather than only a waypoint mismatch, it reports
+//! the validator entries that differ between the two validator sets.
+
+use crate::{
+    validator_set::{Validator, ValidatorSetSource},
+    waypoint::extract_waypoint_from_file,
+};
+use diem_management::{config::ConfigPath, error::Error, progress, secure_backend::SharedBackend};
+use diem_temppath::TempPath;
+use diem_types::{account_address::AccountAddress, chain_id::ChainId, waypoint::Waypoint};
+use std::{fmt, fs, path::Path, path::PathBuf};
+use structopt::StructOpt;
+
+/// Rebuilds the genesis from the shared storage and compares it to a genesis blob
+#[derive(Debug, StructOpt)]
+pub struct VerifyGenesis {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    #[structopt(long, required_unless("config"))]
+    chain_id: Option<ChainId>,
+    #[structopt(flatten)]
+    shared_backend: SharedBackend,
+    #[structopt(long)]
+    layout_path: Option<PathBuf>,
+    /// The genesis blob to verify
+    #[structopt(long, parse(from_os_str))]
+    genesis_path: PathBuf,
+    /// The waypoint published with the genesis blob
+    #[structopt(long)]
+    waypoint: Option<Waypoint>,
+    /// Refuse registrations that their owner did not sign, see sign-registration
+    #[structopt(long)]
+    require_signed_registrations: bool,
+}
+
+impl VerifyGenesis {
+    pub fn execute(self) -> Result<String, Error> {
+        let rebuilt_dir = TempPath::new();
+        rebuilt_dir
+            .create_as_dir()
+            .map_err(|e| Error::IO(rebuilt_dir.path().display().to_string(), e))?;
+        let rebuilt_path = rebuilt_dir.path().join("genesis.blob");
+        crate::genesis::Genesis {
+            config: self.config,
+            chain_id: self.chain_id,
+            backend: self.shared_backend,
+            path: Some(rebuilt_path.clone()),
+            layout_path: self.layout_path,
+            require_signed_registrations: self.require_signed_registrations,
+        }
+        .execute()?;
+
+        let comparison = compare_genesis(&rebuilt_path, &self.genesis_path, self.waypoint)?;
+        if comparison.is_match() {
+            Ok(comparison.to_string())
+        } else {
+            Err(Error::ConfigInvalid {
+                path: self.genesis_path.display().to_string(),
+                reason: comparison.to_string(),
+            })
+        }
+    }
+}
+
+/// A difference between the validator set of the rebuilt genesis and that of the blob
+#[derive(Debug, PartialEq)]
+pub enum Divergence {
+    /// A validator of the rebuilt genesis, missing from the blob
+    Missing {
+        name: String,
+        account: AccountAddress,
+    },
+    /// A validator of the blob, missing from the rebuilt genesis
+    Unexpected {
+        name: String,
+        account: AccountAddress,
+    },
+    /// A validator of both, with a differing entry
+    Entry {
+        name: String,
+        account: AccountAddress,
+        field: &'static str,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::Missing { name, account } => {
+                write!(f, "{} ({}) is missing from the blob", name, account)
+            }
+            Divergence::Unexpected { name, account } => write!(
+                f,
+                "{} ({}) is in the blob but not in the registrations",
+                name, account
+            ),
+            Divergence::Entry {
+                name,
+                account,
+                field,
+            } => write!(f, "{} ({}) has a different {}", name, account, field),
+        }
+    }
+}
+
+/// The result of comparing a rebuilt genesis to a genesis blob
+#[derive(Debug)]
+pub struct GenesisComparison {
+    /// Whether the blob is byte for byte the rebuilt genesis
+    pub identical: bool,
+    pub rebuilt_waypoint: Waypoint,
+    pub blob_waypoint: Waypoint,
+    pub expected_waypoint: Option<Waypoint>,
+    pub divergences: Vec<Divergence>,
+}
+
+impl GenesisComparison {
+    pub fn is_match(&self) -> bool {
+        self.identical
+            && self
+                .expected_waypoint
+                .map_or(true, |waypoint| waypoint == self.blob_waypoint)
+    }
+}
+
+impl fmt::Display for GenesisComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "rebuilt genesis waypoint: {}", self.rebuilt_waypoint)?;
+        writeln!(f, "genesis blob waypoint: {}", self.blob_waypoint)?;
+        if let Some(waypoint) = &self.expected_waypoint {
+            writeln!(
+                f,
+                "expected waypoint: {} - {}",
+                waypoint,
+                waypoint == &self.blob_waypoint
+            )?;
+        }
+        if self.identical {
+            return write!(f, "The genesis blob matches the registrations");
+        }
+        if self.divergences.is_empty() {
+            return write!(
+                f,
+                "The validator sets match, the genesis differs outside of the validator entries"
+            );
+        }
+        write!(f, "The validator sets diverge:")?;
+        for divergence in &self.divergences {
+            write!(f, "\n  {}", divergence)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the genesis rebuilt from the registrations to a genesis blob, and to the waypoint
+/// published with it
+pub fn compare_genesis(
+    rebuilt_path: &Path,
+    blob_path: &Path,
+    expected_waypoint: Option<Waypoint>,
+) -> Result<GenesisComparison, Error> {
+    let mut steps = progress::Progress::new("verify genesis", 2);
+    let read = |path: &Path| fs::read(path).map_err(|e| Error::IO(path.display().to_string(), e));
+    let identical = read(rebuilt_path)? == read(blob_path)?;
+
+    steps.step("computing waypoints");
+    let blob_waypoint = extract_waypoint_from_file(&blob_path.to_path_buf())?;
+    let rebuilt_waypoint = if identical {
+        blob_waypoint
+    } else {
+        extract_waypoint_from_file(&rebuilt_path.to_path_buf())?
+    };
+
+    steps.step("comparing validator sets");
+    let divergences = if identical {
+        vec![]
+    } else {
+        diff_validators(
+            &ValidatorSetSource::from_genesis(rebuilt_path.to_path_buf()).validators()?,
+            &ValidatorSetSource::from_genesis(blob_path.to_path_buf()).validators()?,
+        )
+    };
+    steps.done();
+
+    Ok(GenesisComparison {
+        identical,
+        rebuilt_waypoint,
+        blob_waypoint,
+        expected_waypoint,
+        divergences,
+    })
+}
+
+/// The validators of `rebuilt` that differ from those of `blob`, matched by account
+pub fn diff_validators(rebuilt: &[Validator], blob: &[Validator]) -> Vec<Divergence> {
+    let mut divergences = vec![];
+    for expected in rebuilt {
+        let account = *expected.info.account_address();
+        let actual = match find(blob, &account) {
+            Some(actual) => actual,
+            None => {
+                divergences.push(Divergence::Missing {
+                    name: expected.name.clone(),
+                    account,
+                });
+                continue;
+            }
+        };
+        let (expected_config, actual_config) = (expected.info.config(), actual.info.config());
+        let fields = [
+            ("name", expected.name == actual.name),
+            (
+                "consensus key",
+                expected_config.consensus_public_key == actual_config.consensus_public_key,
+            ),
+            (
+                "voting power",
+                expected.info.consensus_voting_power() == actual.info.consensus_voting_power(),
+            ),
+            (
+                "validator network address",
+                expected_config.validator_network_addresses
+                    == actual_config.validator_network_addresses,
+            ),
+            (
+                "fullnode network address",
+                expected_config.fullnode_network_addresses
+                    == actual_config.fullnode_network_addresses,
+            ),
+        ];
+        for (field, matches) in fields.iter() {
+            if !matches {
+                divergences.push(Divergence::Entry {
+                    name: expected.name.clone(),
+                    account,
+                    field: *field,
+                });
+            }
+        }
+    }
+    for actual in blob {
+        let account = *actual.info.account_address();
+        if find(rebuilt, &account).is_none() {
+            divergences.push(Divergence::Unexpected {
+                name: actual.name.clone(),
+                account,
+            });
+        }
+    }
+    divergences
+}
+
+fn find<'a>(validators: &'a [Validator], account: &AccountAddress) -> Option<&'a Validator> {
+    validators
+        .iter()
+        .find(|validator| validator.info.account_address() == account)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::genesis_fixture;
+    use diem_types::validator_info::ValidatorInfo;
+
+    #[test]
+    fn test_compare_genesis() {
+        let fixture = genesis_fixture(2, ChainId::test());
+        let genesis_path = fixture.genesis_path();
+
+        let comparison =
+            compare_genesis(&genesis_path, &genesis_path, Some(fixture.waypoint)).unwrap();
+        assert!(comparison.is_match());
+
+        let other = genesis_fixture(1, ChainId::test());
+        let comparison = compare_genesis(&other.genesis_path(), &genesis_path, None).unwrap();
+        assert!(!comparison.is_match());
+        assert_ne!(comparison.rebuilt_waypoint, comparison.blob_waypoint);
+        assert!(!comparison.divergences.is_empty());
+    }
+
+    #[test]
+    fn test_diff_validators() {
+        let fixture = genesis_fixture(2, ChainId::test());
+        let rebuilt = ValidatorSetSource::from_genesis(fixture.genesis_path())
+            .validators()
+            .unwrap();
+        let mut blob = ValidatorSetSource::from_genesis(fixture.genesis_path())
+            .validators()
+            .unwrap();
+        assert!(diff_validators(&rebuilt, &blob).is_empty());
+
+        // A validator with another fullnode address, and one missing
+        let tampered = &blob[0].info;
+        let mut config = tampered.config().clone();
+        config.fullnode_network_addresses = vec![];
+        blob[0].info = ValidatorInfo::new(
+            *tampered.account_address(),
+            tampered.consensus_voting_power(),
+            config,
+        );
+        let missing = blob.pop().unwrap();
+
+        let divergences = diff_validators(&rebuilt, &blob);
+        assert_eq!(
+            divergences,
+            vec![
+                Divergence::Entry {
+                    name: blob[0].name.clone(),
+                    account: *blob[0].info.account_address(),
+                    field: "fullnode network address",
+                },
+                Divergence::Missing {
+                    name: missing.name.clone(),
+                    account: *missing.info.account_address(),
+                },
+            ]
+        );
+    }
+}
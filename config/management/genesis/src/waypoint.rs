@@ -4,7 +4,7 @@
 use std::{fs::File, io::Read, path::PathBuf};
 
 use diem_config::config::RocksdbConfig;
-use diem_management::{config::ConfigPath, error::Error, secure_backend::SharedBackend};
+use diem_management::{config::ConfigPath, error::Error, progress, secure_backend::SharedBackend};
 use diem_temppath::TempPath;
 use diem_types::{chain_id::ChainId, transaction::Transaction, waypoint::Waypoint};
 use diem_vm::DiemVM;
@@ -28,6 +28,9 @@ pub struct CreateWaypoint {
     genesis_path: Option<std::path::PathBuf>,
     #[structopt(long)]
     layout_path: Option<std::path::PathBuf>,
+    /// Refuse registrations that their owner did not sign, see sign-registration
+    #[structopt(long)]
+    require_signed_registrations: bool,
 }
 
 impl CreateWaypoint {
@@ -38,17 +41,22 @@ impl CreateWaypoint {
             backend: self.shared_backend,
             path: self.genesis_path,       //////// 0L ////////
             layout_path: self.layout_path, //////// 0L ////////
+            require_signed_registrations: self.require_signed_registrations, //////// 0L ////////
         };
 
         let genesis = genesis_helper.execute()?;
 
+        let mut steps = progress::Progress::new("waypoint", 1);
+        steps.step("executing genesis in a temporary database");
         let path = TempPath::new();
         let diemdb = DiemDB::open(&path, false, None, RocksdbConfig::default())
             .map_err(|e| Error::UnexpectedError(e.to_string()))?;
         let db_rw = DbReaderWriter::new(diemdb);
 
-        db_bootstrapper::generate_waypoint::<DiemVM>(&db_rw, &genesis)
-            .map_err(|e| Error::UnexpectedError(e.to_string()))
+        let waypoint = db_bootstrapper::generate_waypoint::<DiemVM>(&db_rw, &genesis)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        steps.done();
+        Ok(waypoint)
     }
 }
 
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{validate_transaction::ValidateTransaction, TransactionContext};
-use diem_management::error::Error;
+use diem_management::{error::Error, progress};
 use std::{thread::sleep, time};
 use structopt::StructOpt;
 
@@ -43,6 +43,15 @@ impl AutoValidate {
         );
 
         // Loop until we get a successful result, or hit the timeout
+        progress::report(
+            progress::Verbosity::Normal,
+            &format!(
+                "waiting up to {}s for transaction {}:{} to execute",
+                self.validate_timeout,
+                transaction_context.address,
+                transaction_context.sequence_number
+            ),
+        );
         let mut time_slept = 0;
         while time_slept < self.validate_timeout {
             let validation_result = validate_transaction.execute()?;
@@ -50,6 +59,7 @@ impl AutoValidate {
                 // The transaction was executed, return the context.
                 return Ok(validation_result);
             }
+            progress::detail(&format!("not yet executed after {}s", time_slept));
 
             sleep(time::Duration::from_secs(self.sleep_interval));
             time_slept = time_slept
@@ -58,6 +68,7 @@ impl AutoValidate {
         }
 
         // Tried to find the execution result, but the transaction still hasn't been executed...
+        progress::warn("transaction was not executed before the validation timeout");
         Ok(transaction_context)
     }
 }
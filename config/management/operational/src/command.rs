@@ -7,7 +7,10 @@ use crate::{
 };
 use diem_config::config::Peer;
 use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
-use diem_management::{error::Error, execute_command};
+use diem_management::{
+    error::{Error, ErrorCategory},
+    execute_command,
+};
 use diem_types::{account_address::AccountAddress, waypoint::Waypoint, PeerId};
 use serde::Serialize;
 use std::collections::HashMap;
@@ -72,6 +75,19 @@ pub enum Command {
     ValidatorConfig(crate::validator_config::ValidatorConfig),
     #[structopt(about = "Displays the current validator set infos registered on the blockchain")]
     ValidatorSet(crate::validator_set::ValidatorSet),
+    //////// 0L ////////
+    #[structopt(
+        about = "Rotates the validator network key and updates the identities of the node config"
+    )]
+    RotateNetworkKey(crate::validator_config::RotateNetworkKey),
+    #[structopt(about = "Exports the safety rules state from the validator storage to a file")]
+    ExportSafetyRules(crate::safety_rules::ExportSafetyRules),
+    #[structopt(
+        about = "Imports the safety rules state from a file, unless the validator storage is ahead"
+    )]
+    ImportSafetyRules(crate::safety_rules::ImportSafetyRules),
+    #[structopt(about = "Shows which instance of a validator failover pair holds the lease")]
+    FailoverStatus(crate::safety_rules::FailoverStatus),
 }
 
 #[derive(Debug, PartialEq)]
@@ -103,6 +119,11 @@ pub enum CommandName {
     ValidateTransaction,
     ValidatorConfig,
     ValidatorSet,
+    //////// 0L ////////
+    RotateNetworkKey,
+    ExportSafetyRules,
+    ImportSafetyRules,
+    FailoverStatus,
 }
 
 impl From<&Command> for CommandName {
@@ -135,6 +156,11 @@ impl From<&Command> for CommandName {
             Command::ValidateTransaction(_) => CommandName::ValidateTransaction,
             Command::ValidatorConfig(_) => CommandName::ValidatorConfig,
             Command::ValidatorSet(_) => CommandName::ValidatorSet,
+            //////// 0L ////////
+            Command::RotateNetworkKey(_) => CommandName::RotateNetworkKey,
+            Command::ExportSafetyRules(_) => CommandName::ExportSafetyRules,
+            Command::ImportSafetyRules(_) => CommandName::ImportSafetyRules,
+            Command::FailoverStatus(_) => CommandName::FailoverStatus,
         }
     }
 }
@@ -169,6 +195,11 @@ impl std::fmt::Display for CommandName {
             CommandName::ValidateTransaction => "validate-transaction",
             CommandName::ValidatorConfig => "validator-config",
             CommandName::ValidatorSet => "validator-set",
+            //////// 0L ////////
+            CommandName::RotateNetworkKey => "rotate-network-key",
+            CommandName::ExportSafetyRules => "export-safety-rules",
+            CommandName::ImportSafetyRules => "import-safety-rules",
+            CommandName::FailoverStatus => "failover-status",
         };
         write!(f, "{}", name)
     }
@@ -216,6 +247,11 @@ impl Command {
             Command::ValidateTransaction(cmd) => Self::print_transaction_context(cmd.execute()),
             Command::ValidatorConfig(cmd) => Self::pretty_print(cmd.execute()),
             Command::ValidatorSet(cmd) => Self::pretty_print(cmd.execute()),
+            //////// 0L ////////
+            Command::RotateNetworkKey(cmd) => Self::pretty_print(cmd.execute()),
+            Command::ExportSafetyRules(cmd) => Self::pretty_print(cmd.execute()),
+            Command::ImportSafetyRules(cmd) => Self::pretty_print(cmd.execute()),
+            Command::FailoverStatus(cmd) => Self::pretty_print(cmd.execute()),
         }
     }
 
@@ -420,6 +456,35 @@ impl Command {
     pub fn validator_set(self) -> Result<Vec<DecryptedValidatorInfo>, Error> {
         execute_command!(self, Command::ValidatorSet, CommandName::ValidatorSet)
     }
+
+    //////// 0L ////////
+    pub fn rotate_network_key(self) -> Result<crate::validator_config::NetworkKeyRotation, Error> {
+        execute_command!(
+            self,
+            Command::RotateNetworkKey,
+            CommandName::RotateNetworkKey
+        )
+    }
+
+    pub fn export_safety_rules(self) -> Result<crate::safety_rules::SafetyRulesState, Error> {
+        execute_command!(
+            self,
+            Command::ExportSafetyRules,
+            CommandName::ExportSafetyRules
+        )
+    }
+
+    pub fn import_safety_rules(self) -> Result<crate::safety_rules::SafetyRulesState, Error> {
+        execute_command!(
+            self,
+            Command::ImportSafetyRules,
+            CommandName::ImportSafetyRules
+        )
+    }
+
+    pub fn failover_status(self) -> Result<crate::safety_rules::LeaseStatus, Error> {
+        execute_command!(self, Command::FailoverStatus, CommandName::FailoverStatus)
+    }
 }
 
 /// A result wrapper for displaying either a correct execution result or an error.
@@ -429,6 +494,28 @@ pub enum ResultWrapper<T> {
     Error(String),
 }
 
+//////// 0L ////////
+/// The JSON form of a failed command. `Error` holds the message as before, the stable `code` and
+/// `category` allow scripts to react to specific failures.
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ErrorWrapper {
+    pub error: String,
+    pub code: String,
+    pub category: ErrorCategory,
+}
+
+impl From<&Error> for ErrorWrapper {
+    fn from(error: &Error) -> Self {
+        let report = error.report();
+        ErrorWrapper {
+            error: report.message,
+            code: report.code,
+            category: report.category,
+        }
+    }
+}
+
 /// A struct wrapper for displaying unvalidated transaction contexts.
 #[derive(Serialize)]
 struct UnvalidatedTransactionContext<'a> {
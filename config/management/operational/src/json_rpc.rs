@@ -3,7 +3,7 @@
 
 use crate::TransactionContext;
 use diem_client::{views::VMStatusView, BlockingClient};
-use diem_management::error::Error;
+use diem_management::{error::Error, trace_span};
 use diem_types::{
     account_address::AccountAddress, account_config, account_config::AccountResource,
     account_state::AccountState, account_state_blob::AccountStateBlob,
@@ -28,6 +28,7 @@ impl JsonRpcClientWrapper {
         &self,
         transaction: SignedTransaction,
     ) -> Result<TransactionContext, Error> {
+        trace_span!("json_rpc.submit", sender = %transaction.sender());
         self.client
             .submit(&transaction)
             .map_err(|e| Error::JsonRpcWriteError("transaction", e.to_string()))?;
@@ -38,6 +39,7 @@ impl JsonRpcClientWrapper {
     }
 
     pub fn account_state(&self, account: AccountAddress) -> Result<AccountState, Error> {
+        trace_span!("json_rpc.account_state", account = %account);
         let account_state = self
             .client
             .get_account_state_with_proof(account, None, None)
@@ -120,6 +122,7 @@ impl JsonRpcClientWrapper {
         account: AccountAddress,
         sequence_number: u64,
     ) -> Result<Option<VMStatusView>, Error> {
+        trace_span!("json_rpc.transaction_status", account = %account, sequence_number);
         self.client
             .get_account_transaction(account, sequence_number, false)
             .map(|maybe_txn_status| maybe_txn_status.into_inner().map(|status| status.vm_status))
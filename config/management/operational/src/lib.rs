@@ -11,6 +11,7 @@ pub mod json_rpc;
 pub mod keys;
 mod owner;
 mod print;
+pub mod safety_rules;
 mod validate_transaction;
 mod validator_config;
 mod validator_set;
@@ -2,19 +2,34 @@
 // SPDX-License-Identifier: Apache-2.0
 
 #![forbid(unsafe_code)]
-use diem_operational_tool::command::{Command, ResultWrapper};
+use diem_management::progress::VerbosityArgs;
+use diem_operational_tool::command::{Command, ErrorWrapper};
 use std::process::exit;
 use structopt::StructOpt;
 
+//////// 0L ////////
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Tool used for Operators")]
+struct Args {
+    #[structopt(flatten)]
+    verbosity: VerbosityArgs,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
 fn main() {
-    let result = Command::from_args().execute();
+    let args = Args::from_args();
+    args.verbosity.init();
+    #[cfg(feature = "tracing")]
+    diem_management::init_tracing();
+    let result = args.command.execute();
 
     match result {
         Ok(val) => println!("{}", val),
         Err(err) => {
-            let result: ResultWrapper<()> = ResultWrapper::Error(err.to_string());
+            let result = ErrorWrapper::from(&err);
             println!("{}", serde_json::to_string_pretty(&result).unwrap());
-            exit(1);
+            exit(err.exit_code());
         }
     }
 }
@@ -6,7 +6,7 @@ use crate::{
     validator_set::{validator_set_full_node_addresses, validator_set_validator_addresses},
 };
 use diem_config::{
-    config::{RoleType, HANDSHAKE_VERSION},
+    config::{HandshakeEnforcement, PeerAccess, RoleType, HANDSHAKE_VERSION},
     network_id::{NetworkContext, NetworkId},
 };
 use diem_crypto::{x25519, x25519::PRIVATE_KEY_SIZE};
@@ -217,6 +217,7 @@ fn build_upgrade_context(
         RoleType::FullNode,
         network_id.clone(),
         peer_id,
+        chain_id,
     ));
 
     // Let's make sure some protocol can be connected.  In the future we may want to allow for specifics
@@ -238,6 +239,8 @@ fn build_upgrade_context(
         supported_protocols,
         chain_id,
         network_id,
+        PeerAccess::default(),
+        HandshakeEnforcement::Strict,
     ))
 }
 
@@ -0,0 +1,237 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Moves the safety rules state of a validator between secure backends, so that a validator can
+//! migrate hosts without signing a vote that conflicts with one it already cast. The source node
+//! must be stopped before the export, otherwise it may vote again after the state was copied.
+//!
+//! `failover-status` shows which instance of an active/standby pair holds the failover lease.
+
+use consensus_types::safety_data::{FailoverLease, SafetyData};
+use diem_global_constants::{FAILOVER_LEASE, SAFETY_DATA, WAYPOINT};
+use diem_management::{
+    config::ConfigPath,
+    error::Error,
+    secure_backend::{SecureBackend, ValidatorBackend},
+};
+use diem_types::waypoint::Waypoint;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use structopt::StructOpt;
+
+/// The safety rules state of a validator, as stored in its secure backend
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SafetyRulesState {
+    pub safety_data: SafetyData,
+    pub waypoint: Waypoint,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExportSafetyRules {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+    /// Location to write the safety rules state to
+    #[structopt(long)]
+    output_file: PathBuf,
+}
+
+impl ExportSafetyRules {
+    pub fn execute(self) -> Result<SafetyRulesState, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        let storage = config.validator_backend();
+
+        let state = SafetyRulesState {
+            safety_data: storage.value(SAFETY_DATA)?,
+            waypoint: storage.waypoint(WAYPOINT)?,
+        };
+        let file = self.output_file.to_str().unwrap().to_string();
+        let contents = serde_json::to_string_pretty(&state)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        fs::write(&self.output_file, contents).map_err(|e| Error::IO(file, e))?;
+        Ok(state)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ImportSafetyRules {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+    /// Location of the safety rules state written by export-safety-rules
+    #[structopt(long)]
+    input_file: PathBuf,
+}
+
+impl ImportSafetyRules {
+    pub fn execute(self) -> Result<SafetyRulesState, Error> {
+        let config = self
+            .config
+            .load()?
+            .override_validator_backend(&self.validator_backend.validator_backend)?;
+        let mut storage = config.validator_backend();
+
+        let file = self.input_file.to_str().unwrap().to_string();
+        let contents = fs::read_to_string(&self.input_file)
+            .map_err(|e| Error::UnableToReadFile(file.clone(), e.to_string()))?;
+        let state: SafetyRulesState = serde_json::from_str(&contents)
+            .map_err(|e| Error::UnableToParseFile(file, e.to_string()))?;
+
+        check_not_stale(
+            storage.optional_value(SAFETY_DATA)?.as_ref(),
+            storage.optional_value(WAYPOINT)?,
+            &state,
+        )?;
+        storage.set(SAFETY_DATA, state.safety_data.clone())?;
+        storage.set(WAYPOINT, state.waypoint)?;
+        Ok(state)
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct FailoverStatus {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    /// Backend holding the lease, when the lease is kept in the safety rules backend
+    #[structopt(long, required_unless_one(&["config", "lease_file"]))]
+    validator_backend: Option<SecureBackend>,
+    /// The lease file, when the lease is kept in a file
+    #[structopt(long)]
+    lease_file: Option<PathBuf>,
+}
+
+/// The holder of the failover lease
+#[derive(Debug, PartialEq, Serialize)]
+pub struct LeaseStatus {
+    /// The instance that may sign, if its lease hasn't expired
+    pub active: Option<String>,
+    pub lease: Option<FailoverLease>,
+}
+
+impl FailoverStatus {
+    pub fn execute(self) -> Result<LeaseStatus, Error> {
+        let lease: Option<FailoverLease> = match &self.lease_file {
+            Some(lease_file) => {
+                let file_name = lease_file.to_str().unwrap().to_string();
+                let read_error =
+                    |e: std::io::Error| Error::UnableToReadFile(file_name.clone(), e.to_string());
+                let mut file = File::open(lease_file).map_err(read_error)?;
+                // Locked by the instances while they update the lease
+                file.lock_shared().map_err(read_error)?;
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).map_err(read_error)?;
+                if contents.is_empty() {
+                    None
+                } else {
+                    Some(
+                        serde_json::from_str(&contents)
+                            .map_err(|e| Error::UnableToParseFile(file_name, e.to_string()))?,
+                    )
+                }
+            }
+            None => self
+                .config
+                .load()?
+                .override_validator_backend(&self.validator_backend)?
+                .validator_backend()
+                .optional_value(FAILOVER_LEASE)?,
+        };
+
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?
+            .as_millis() as u64;
+        Ok(LeaseStatus {
+            active: lease
+                .as_ref()
+                .filter(|lease| lease.expires_at_ms > now_ms)
+                .map(|lease| lease.holder.clone()),
+            lease,
+        })
+    }
+}
+
+/// Refuses to overwrite a backend that has seen more of the chain than the imported state: the
+/// node could then vote again in rounds it has already voted in.
+fn check_not_stale(
+    current: Option<&SafetyData>,
+    current_waypoint: Option<Waypoint>,
+    imported: &SafetyRulesState,
+) -> Result<(), Error> {
+    let imported_data = &imported.safety_data;
+    if let Some(current) = current {
+        if current.epoch > imported_data.epoch {
+            return Err(Error::StaleSafetyRules(format!(
+                "the backend is at epoch {}, the imported state at epoch {}",
+                current.epoch, imported_data.epoch
+            )));
+        }
+        if current.epoch == imported_data.epoch
+            && (current.last_voted_round > imported_data.last_voted_round
+                || current.preferred_round > imported_data.preferred_round)
+        {
+            return Err(Error::StaleSafetyRules(format!(
+                "the backend has {}, the imported state has {}",
+                current, imported_data
+            )));
+        }
+    }
+    if let Some(current_waypoint) = current_waypoint {
+        if current_waypoint.version() > imported.waypoint.version() {
+            return Err(Error::StaleSafetyRules(format!(
+                "the backend waypoint {} is ahead of the imported waypoint {}",
+                current_waypoint, imported.waypoint
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::HashValue;
+    use diem_types::{block_info::BlockInfo, ledger_info::LedgerInfo};
+
+    fn waypoint(version: u64) -> Waypoint {
+        let block_info =
+            BlockInfo::new(0, 0, HashValue::zero(), HashValue::zero(), version, 0, None);
+        Waypoint::new_any(&LedgerInfo::new(block_info, HashValue::zero()))
+    }
+
+    #[test]
+    fn test_check_not_stale() {
+        let imported = SafetyRulesState {
+            safety_data: SafetyData::new(2, 10, 8, None),
+            waypoint: waypoint(100),
+        };
+
+        // A fresh backend, or one behind the imported state
+        check_not_stale(None, None, &imported).unwrap();
+        let behind = SafetyData::new(1, 50, 50, None);
+        check_not_stale(Some(&behind), Some(waypoint(50)), &imported).unwrap();
+        check_not_stale(Some(&imported.safety_data), Some(waypoint(100)), &imported).unwrap();
+
+        // A backend that has seen more of the chain
+        let ahead = SafetyData::new(3, 0, 0, None);
+        check_not_stale(Some(&ahead), None, &imported).unwrap_err();
+        let voted = SafetyData::new(2, 11, 8, None);
+        check_not_stale(Some(&voted), None, &imported).unwrap_err();
+        let preferred = SafetyData::new(2, 10, 9, None);
+        check_not_stale(Some(&preferred), None, &imported).unwrap_err();
+        let error = check_not_stale(None, Some(waypoint(101)), &imported).unwrap_err();
+        assert_eq!(error.code(), "stale_safety_rules");
+    }
+}
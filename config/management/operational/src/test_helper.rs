@@ -5,6 +5,7 @@ use crate::{
     account_resource::SimplifiedAccountResource,
     command::{Command, CommandName},
     keys::{load_key, EncodingType, KeyType},
+    safety_rules::SafetyRulesState,
     validator_config::DecryptedValidatorConfig,
     validator_set::DecryptedValidatorInfo,
     TransactionContext,
@@ -293,6 +294,44 @@ impl OperationalTool {
         load_key(key_file.to_path_buf(), encoding)
     }
 
+    pub fn export_safety_rules(
+        &self,
+        output_file: &Path,
+        backend: &config::SecureBackend,
+    ) -> Result<SafetyRulesState, Error> {
+        let args = format!(
+            "
+                {command}
+                --output-file {output_file}
+                --validator-backend {backend_args}
+            ",
+            command = command(TOOL_NAME, CommandName::ExportSafetyRules),
+            output_file = output_file.to_str().unwrap(),
+            backend_args = backend_args(backend)?,
+        );
+        let command = Command::from_iter(args.split_whitespace());
+        command.export_safety_rules()
+    }
+
+    pub fn import_safety_rules(
+        &self,
+        input_file: &Path,
+        backend: &config::SecureBackend,
+    ) -> Result<SafetyRulesState, Error> {
+        let args = format!(
+            "
+                {command}
+                --input-file {input_file}
+                --validator-backend {backend_args}
+            ",
+            command = command(TOOL_NAME, CommandName::ImportSafetyRules),
+            input_file = input_file.to_str().unwrap(),
+            backend_args = backend_args(backend)?,
+        );
+        let command = Command::from_iter(args.split_whitespace());
+        command.import_safety_rules()
+    }
+
     pub fn insert_waypoint(
         &self,
         waypoint: Waypoint,
@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{auto_validate::AutoValidate, json_rpc::JsonRpcClientWrapper, TransactionContext};
-use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
+use diem_config::config::{Identity, NodeConfig, PersistableConfig}; //////// 0L ////////
+use diem_crypto::{ed25519::Ed25519PublicKey, x25519, ValidCryptoMaterial};
 use diem_global_constants::{
     CONSENSUS_KEY, FULLNODE_NETWORK_KEY, OPERATOR_ACCOUNT, OWNER_ACCOUNT, VALIDATOR_NETWORK_KEY,
 };
@@ -13,7 +14,12 @@ use diem_types::{
     network_address::{NetworkAddress, Protocol},
 };
 use serde::Serialize;
-use std::{convert::TryFrom, str::FromStr};
+use std::{
+    convert::TryFrom,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 use structopt::StructOpt;
 
 // TODO: Load all chain IDs from the host
@@ -234,6 +240,125 @@ impl RotateFullNodeNetworkKey {
     }
 }
 
+//////// 0L ////////
+/// Rotates the validator network key, then once the rotation is executed on chain, moves the
+/// identities of the node config that used the previous key to the new one. Nothing is rotated
+/// if the node config can't be read, and the config is left as is until the rotation executed.
+#[derive(Debug, StructOpt)]
+pub struct RotateNetworkKey {
+    #[structopt(flatten)]
+    rotate_key: RotateKey,
+    /// Node config of the validator
+    #[structopt(long, parse(from_os_str))]
+    node_config: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkKeyRotation {
+    pub transaction_context: TransactionContext,
+    pub network_key: x25519::PublicKey,
+    /// The networks of the node config whose identity key was replaced
+    pub updated_networks: usize,
+}
+
+impl RotateNetworkKey {
+    pub fn execute(self) -> Result<NetworkKeyRotation, Error> {
+        let path = self.node_config.clone();
+        let mut node_config = NodeConfig::load_config(&path).map_err(|e| Error::ConfigInvalid {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        if node_config.validator_network.is_none() {
+            return Err(Error::ConfigInvalid {
+                path: path.display().to_string(),
+                reason: "the config has no validator network".to_string(),
+            });
+        }
+        let storage = self
+            .rotate_key
+            .validator_config
+            .config()?
+            .validator_backend();
+
+        let (transaction_context, key) = self.rotate_key.execute(VALIDATOR_NETWORK_KEY)?;
+        match &transaction_context.execution_result {
+            Some(status) if status.is_executed() => (),
+            status => {
+                let status = status
+                    .as_ref()
+                    .map_or("not executed before the timeout".to_string(), |status| {
+                        status.to_string()
+                    });
+                return Err(Error::UnexpectedError(format!(
+                    "The rotation transaction {}:{} is {}, {} is unchanged. Run the command \
+                     again to resubmit the key in storage.",
+                    transaction_context.address,
+                    transaction_context.sequence_number,
+                    status,
+                    path.display()
+                )));
+            }
+        }
+
+        let updated_networks = replace_identity_key(
+            &mut node_config,
+            storage.x25519_private(VALIDATOR_NETWORK_KEY)?,
+        );
+        save_atomically(&node_config, &path)?;
+        Ok(NetworkKeyRotation {
+            transaction_context,
+            network_key: to_x25519(key)?,
+            updated_networks,
+        })
+    }
+}
+
+/// Replaces the validator network key held in the config, on every network using it, keeping
+/// their peer ids. Identities read from storage already use the rotated key. Returns the number
+/// of networks updated.
+pub fn replace_identity_key(config: &mut NodeConfig, key: x25519::PrivateKey) -> usize {
+    let previous_key = match config
+        .validator_network
+        .as_ref()
+        .map(|network| &network.identity)
+    {
+        Some(Identity::FromConfig(identity)) => identity.key.public_key(),
+        _ => return 0,
+    };
+    if previous_key == key.public_key() {
+        return 0;
+    }
+    let mut updated = 0;
+    for network in config
+        .validator_network
+        .iter_mut()
+        .chain(config.full_node_networks.iter_mut())
+    {
+        let peer_id = match &network.identity {
+            Identity::FromConfig(identity) if identity.key.public_key() == previous_key => {
+                identity.peer_id
+            }
+            _ => continue,
+        };
+        network.identity = Identity::from_config(
+            x25519::PrivateKey::try_from(key.to_bytes().as_ref())
+                .expect("a key converts from its own bytes"),
+            peer_id,
+        );
+        updated += 1;
+    }
+    updated
+}
+
+/// Writes the config next to `path` then renames it, so that a node never reads a partial config
+fn save_atomically(config: &NodeConfig, path: &Path) -> Result<(), Error> {
+    let tmp_path = path.with_extension("yaml.tmp");
+    config
+        .save_config(&tmp_path)
+        .map_err(|e| Error::UnexpectedError(format!("Unable to write config: {}", e)))?;
+    fs::rename(&tmp_path, path).map_err(|e| Error::IO(path.display().to_string(), e))
+}
+
 /// Returns only the IP/DNS + Port portion of the NetworkAddress
 pub fn strip_address(address: &NetworkAddress) -> NetworkAddress {
     let protocols = address
@@ -249,6 +374,7 @@ pub fn strip_address(address: &NetworkAddress) -> NetworkAddress {
                     | Protocol::Ip6(_)
                     | Protocol::Memory(_)
                     | Protocol::Tcp(_)
+                    | Protocol::Onion3(_) //////// 0L ////////
             )
         })
         .cloned()
@@ -368,3 +494,58 @@ pub fn validator_addresses(
             ))
         })
 }
+
+//////// 0L ////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::{config::NetworkConfig, network_id::NetworkId};
+    use diem_crypto::Uniform;
+    use diem_types::PeerId;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_replace_identity_key() {
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let previous_key = x25519::PrivateKey::generate(&mut rng);
+        let peer_id = PeerId::random();
+
+        let mut config = NodeConfig::default_for_validator();
+        let validator_network = config.validator_network.as_mut().unwrap();
+        validator_network.identity = Identity::from_config(
+            x25519::PrivateKey::try_from(previous_key.to_bytes().as_ref()).unwrap(),
+            peer_id,
+        );
+        let mut vfn_network = NetworkConfig::network_with_id(NetworkId::vfn_network());
+        vfn_network.identity = validator_network.identity.clone();
+        let mut public_network = NetworkConfig::network_with_id(NetworkId::Public);
+        public_network.identity =
+            Identity::from_config(x25519::PrivateKey::generate(&mut rng), PeerId::random());
+        config.full_node_networks = vec![vfn_network, public_network.clone()];
+
+        let key = x25519::PrivateKey::generate(&mut rng);
+        let key_bytes = key.to_bytes();
+        let public_key = key.public_key();
+        assert_eq!(replace_identity_key(&mut config, key), 2);
+
+        for network in config
+            .validator_network
+            .iter()
+            .chain(config.full_node_networks[..1].iter())
+        {
+            match &network.identity {
+                Identity::FromConfig(identity) => {
+                    assert_eq!(identity.key.public_key(), public_key);
+                    assert_eq!(identity.peer_id, peer_id);
+                }
+                _ => panic!("Expected an identity from config"),
+            }
+        }
+        // The public network has its own key
+        assert_eq!(config.full_node_networks[1], public_network);
+
+        // Running it again, e.g. after an unconfirmed rotation, changes nothing
+        let key = x25519::PrivateKey::try_from(key_bytes.as_ref()).unwrap();
+        assert_eq!(replace_identity_key(&mut config, key), 0);
+    }
+}
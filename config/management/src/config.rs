@@ -77,7 +77,10 @@ impl Default for Config {
 impl Config {
     pub fn load(path: &Path) -> Result<Config, Error> {
         let reader = std::fs::File::open(path).map_err(|e| Error::ConfigError(e.to_string()))?;
-        serde_yaml::from_reader(reader).map_err(|e| Error::ConfigError(e.to_string()))
+        serde_yaml::from_reader(reader).map_err(|e| Error::ConfigInvalid {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })
     }
 
     pub fn override_chain_id(mut self, chain_id: Option<ChainId>) -> Self {
@@ -128,6 +131,8 @@ impl Config {
             config::SecureBackend::InMemoryStorage => panic!("Unsupported namespace for InMemory"),
             config::SecureBackend::Vault(config) => config.namespace = Some(namespace),
             config::SecureBackend::OnDiskStorage(config) => config.namespace = Some(namespace),
+            config::SecureBackend::YubiHsm(config) => config.namespace = Some(namespace),
+            config::SecureBackend::Kms(config) => config.namespace = Some(namespace),
         };
         StorageWrapper {
             storage_name: "shared",
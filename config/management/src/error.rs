@@ -1,6 +1,8 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use diem_types::waypoint::Waypoint;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,6 +17,8 @@ pub enum Error {
     CommandArgumentError(String),
     #[error("Unable to load config: {0}")]
     ConfigError(String),
+    #[error("Invalid config '{path}': {reason}")]
+    ConfigInvalid { path: String, reason: String },
     #[error("Error accessing '{0}': {1}")]
     IO(String, #[source] std::io::Error),
     #[error("Error (de)serializing '{0}': {1}")]
@@ -25,6 +29,10 @@ pub enum Error {
     JsonRpcWriteError(&'static str, String),
     #[error("Unable to decode network address: {0}")]
     NetworkAddressDecodeError(String),
+    #[error("Authentication with the {0} storage was rejected, please check your token: {1}")]
+    RemoteBackendAuth(&'static str, String),
+    #[error("Refusing to import stale safety rules: {0}")]
+    StaleSafetyRules(String),
     #[error("{0} storage unavailable, please check your configuration: {1}")]
     StorageUnavailable(&'static str, String),
     #[error("Failed to read '{1}' from {0} storage: {2}")]
@@ -45,4 +53,179 @@ pub enum Error {
     UnexpectedCommand(String, String),
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
+    #[error("Waypoint mismatch, expected {expected} but found {actual}")]
+    WaypointMismatch { expected: Waypoint, actual: Waypoint },
+}
+
+//////// 0L ////////
+/// Coarse classification of errors, so that automation can branch on the failure type instead of
+/// string-matching messages. The category also determines the process exit code.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Invalid command line usage
+    Usage,
+    /// Missing, unreadable or invalid configuration
+    Config,
+    /// A secure storage backend could not be reached or operated on
+    Storage,
+    /// A remote backend rejected our credentials
+    Auth,
+    /// JSON-RPC communication with a node failed
+    Network,
+    /// Verification of on-chain or genesis data failed
+    Verification,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Stable process exit code for this category. 1 is reserved for internal errors so that
+    /// existing scripts checking for a non-zero exit keep working.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Internal => 1,
+            ErrorCategory::Usage => 2,
+            ErrorCategory::Config => 3,
+            ErrorCategory::Storage => 4,
+            ErrorCategory::Auth => 5,
+            ErrorCategory::Network => 6,
+            ErrorCategory::Verification => 7,
+        }
+    }
+}
+
+impl Error {
+    /// A stable identifier for this error. These must not be renamed once released.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::BackendInvalidKeyValue(_) => "backend_invalid_key_value",
+            Error::BackendMissingBackendKey => "backend_missing_backend_key",
+            Error::BackendParsingError(_) => "backend_parsing_error",
+            Error::CommandArgumentError(_) => "command_argument_error",
+            Error::ConfigError(_) => "config_error",
+            Error::ConfigInvalid { .. } => "config_invalid",
+            Error::IO(_, _) => "io_error",
+            Error::BCS(_, _) => "serialization_error",
+            Error::JsonRpcReadError(_, _) => "json_rpc_read_error",
+            Error::JsonRpcWriteError(_, _) => "json_rpc_write_error",
+            Error::NetworkAddressDecodeError(_) => "network_address_decode_error",
+            Error::RemoteBackendAuth(_, _) => "remote_backend_auth",
+            Error::StaleSafetyRules(_) => "stale_safety_rules",
+            Error::StorageUnavailable(_, _) => "storage_unavailable",
+            Error::StorageReadError(_, _, _) => "storage_read_error",
+            Error::StorageSigningError(_, _, _, _) => "storage_signing_error",
+            Error::StorageWriteError(_, _, _) => "storage_write_error",
+            Error::Timeout(_, _) => "timeout",
+            Error::UnableToParse(_, _) => "unable_to_parse",
+            Error::UnableToParseFile(_, _) => "unable_to_parse_file",
+            Error::UnableToReadFile(_, _) => "unable_to_read_file",
+            Error::UnexpectedCommand(_, _) => "unexpected_command",
+            Error::UnexpectedError(_) => "unexpected_error",
+            Error::WaypointMismatch { .. } => "waypoint_mismatch",
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::BackendInvalidKeyValue(_)
+            | Error::BackendMissingBackendKey
+            | Error::BackendParsingError(_)
+            | Error::CommandArgumentError(_)
+            | Error::UnexpectedCommand(_, _) => ErrorCategory::Usage,
+            Error::ConfigError(_)
+            | Error::ConfigInvalid { .. }
+            | Error::UnableToParse(_, _)
+            | Error::UnableToParseFile(_, _)
+            | Error::UnableToReadFile(_, _) => ErrorCategory::Config,
+            Error::StorageUnavailable(_, _)
+            | Error::StorageReadError(_, _, _)
+            | Error::StorageSigningError(_, _, _, _)
+            | Error::StorageWriteError(_, _, _) => ErrorCategory::Storage,
+            Error::RemoteBackendAuth(_, _) => ErrorCategory::Auth,
+            Error::JsonRpcReadError(_, _)
+            | Error::JsonRpcWriteError(_, _)
+            | Error::Timeout(_, _) => ErrorCategory::Network,
+            Error::NetworkAddressDecodeError(_)
+            | Error::StaleSafetyRules(_)
+            | Error::WaypointMismatch { .. } => ErrorCategory::Verification,
+            Error::IO(_, _) | Error::BCS(_, _) | Error::UnexpectedError(_) => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.category().exit_code()
+    }
+
+    pub fn report(&self) -> ErrorReport {
+        ErrorReport {
+            code: self.code().to_string(),
+            category: self.category(),
+            message: self.to_string(),
+        }
+    }
+}
+
+/// The machine readable form of an `Error`, used for JSON output
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ErrorReport {
+    pub code: String,
+    pub category: ErrorCategory,
+    pub message: String,
+}
+
+/// Maps a secure storage error into the management error taxonomy: authorization failures and
+/// unreachable backends are reported separately from plain missing or malformed values.
+pub fn from_storage_error(
+    storage_name: &'static str,
+    error: diem_secure_storage::Error,
+    otherwise: impl FnOnce(String) -> Error,
+) -> Error {
+    match error {
+        diem_secure_storage::Error::PermissionDenied => {
+            Error::RemoteBackendAuth(storage_name, error.to_string())
+        }
+        diem_secure_storage::Error::InternalError(message) => {
+            Error::StorageUnavailable(storage_name, message)
+        }
+        error => otherwise(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_errors_are_classified() {
+        let error = from_storage_error(
+            "shared",
+            diem_secure_storage::Error::PermissionDenied,
+            |e| Error::StorageReadError("shared", "owner_key", e),
+        );
+        assert_eq!(error.code(), "remote_backend_auth");
+        assert_eq!(error.exit_code(), 5);
+
+        let error = from_storage_error(
+            "validator",
+            diem_secure_storage::Error::KeyNotSet("owner_key".into()),
+            |e| Error::StorageReadError("validator", "owner_key", e),
+        );
+        assert_eq!(error.category(), ErrorCategory::Storage);
+        assert_eq!(error.code(), "storage_read_error");
+    }
+
+    #[test]
+    fn report_serialization() {
+        let error = Error::ConfigInvalid {
+            path: "/tmp/config.yaml".into(),
+            reason: "missing field `chain_id`".into(),
+        };
+        let report = serde_json::to_value(error.report()).unwrap();
+        assert_eq!(report["code"], "config_invalid");
+        assert_eq!(report["category"], "config");
+        assert_eq!(error.exit_code(), 3);
+    }
 }
@@ -5,6 +5,7 @@
 
 pub mod config;
 pub mod error;
+pub mod progress; //////// 0L ////////
 pub mod secure_backend;
 pub mod storage;
 pub mod transaction;
@@ -38,6 +39,39 @@ macro_rules! execute_command {
     };
 }
 
+//////// 0L ////////
+#[cfg(feature = "tracing")]
+pub use tracing;
+
+/// Enters a `tracing` span that lasts until the end of the enclosing block. Spans are only
+/// recorded with the `tracing` feature, otherwise this expands to nothing. Arguments are the same
+/// as `tracing::info_span!`, e.g. `trace_span!("storage.read", storage = name, key = key)`.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        let _span = $crate::tracing::info_span!($($arg)*).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_span {
+    ($($arg:tt)*) => {};
+}
+
+/// Prints the spans of `trace_span!` to stderr as they close, with their duration, at the `-vv`
+/// verbosity. The binaries built with the `tracing` feature install it once the verbosity is set.
+#[cfg(feature = "tracing-subscriber")]
+pub fn init_tracing() {
+    if progress::enabled(progress::Verbosity::Trace) {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .init();
+    }
+}
+
 use diem_crypto::ed25519::Ed25519PublicKey;
 use std::{convert::TryInto, fs, path::Path};
 
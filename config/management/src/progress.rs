@@ -0,0 +1,191 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verbosity handling and step-level progress output for the management tooling.
+//!
+//! Command output (the value returned by `execute`) is always printed to stdout by the binaries,
+//! so all progress is written to stderr. This keeps the output of a command usable in scripts
+//! while long running operations (e.g., building genesis) are no longer silent.
+
+use std::{
+    sync::atomic::{AtomicU8, Ordering},
+    time::Instant,
+};
+use structopt::StructOpt;
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    /// Only errors and the final command output
+    Quiet = 0,
+    /// Step-level progress of long operations
+    Normal = 1,
+    /// Details within each step, including timings
+    Verbose = 2,
+    /// Everything, including the values read from and written to storage
+    Trace = 3,
+}
+
+impl Verbosity {
+    fn from_u8(level: u8) -> Verbosity {
+        match level {
+            0 => Verbosity::Quiet,
+            1 => Verbosity::Normal,
+            2 => Verbosity::Verbose,
+            _ => Verbosity::Trace,
+        }
+    }
+}
+
+/// Global verbosity flags shared by the management binaries.
+#[derive(Clone, Copy, Debug, Default, StructOpt)]
+pub struct VerbosityArgs {
+    /// Increase the output verbosity (-v for details, -vv for tracing)
+    #[structopt(short, long, parse(from_occurrences), global = true)]
+    verbose: u8,
+    /// Only print errors and the command result
+    #[structopt(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+impl VerbosityArgs {
+    pub fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else {
+            Verbosity::from_u8(1 + self.verbose)
+        }
+    }
+
+    /// Sets the process wide verbosity from the parsed flags
+    pub fn init(&self) {
+        set_verbosity(self.verbosity());
+    }
+}
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    Verbosity::from_u8(VERBOSITY.load(Ordering::Relaxed))
+}
+
+pub fn enabled(level: Verbosity) -> bool {
+    level != Verbosity::Quiet && verbosity() >= level
+}
+
+/// Prints a message if the current verbosity is at least `level`
+pub fn report(level: Verbosity, message: &str) {
+    if enabled(level) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Reports a warning, these are only hidden by `--quiet`
+pub fn warn(message: &str) {
+    report(Verbosity::Normal, &format!("WARNING: {}", message));
+}
+
+/// Reports an additional detail, shown with `-v`
+pub fn detail(message: &str) {
+    report(Verbosity::Verbose, &format!("    {}", message));
+}
+
+/// Reports a trace message, shown with `-vv`
+pub fn trace(message: &str) {
+    report(Verbosity::Trace, &format!("    [trace] {}", message));
+}
+
+/// Tracks the numbered steps of a long running operation, e.g.:
+///
+/// ```text
+/// genesis build [1/3] reading layout
+/// genesis build [2/3] collecting operator registrations
+/// genesis build [3/3] encoding genesis transaction
+/// genesis build done in 2.3s
+/// ```
+pub struct Progress {
+    operation: &'static str,
+    total: usize,
+    current: usize,
+    started: Instant,
+    step_started: Instant,
+}
+
+impl Progress {
+    pub fn new(operation: &'static str, total: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            operation,
+            total,
+            current: 0,
+            started: now,
+            step_started: now,
+        }
+    }
+
+    /// Starts the next step, reporting the duration of the previous one in verbose mode
+    pub fn step(&mut self, description: &str) {
+        self.finish_step();
+        self.current += 1;
+        self.step_started = Instant::now();
+        report(
+            Verbosity::Normal,
+            &format!(
+                "{} [{}/{}] {}",
+                self.operation, self.current, self.total, description
+            ),
+        );
+    }
+
+    pub fn done(mut self) {
+        self.finish_step();
+        report(
+            Verbosity::Normal,
+            &format!(
+                "{} done in {:.1}s",
+                self.operation,
+                self.started.elapsed().as_secs_f32()
+            ),
+        );
+    }
+
+    fn finish_step(&mut self) {
+        if self.current > 0 {
+            detail(&format!(
+                "step {} took {:.1}s",
+                self.current,
+                self.step_started.elapsed().as_secs_f32()
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, StructOpt)]
+    struct TestCommandLine {
+        #[structopt(flatten)]
+        verbosity: VerbosityArgs,
+    }
+
+    fn parse(args: &str) -> Verbosity {
+        TestCommandLine::from_iter(args.split_whitespace())
+            .verbosity
+            .verbosity()
+    }
+
+    #[test]
+    fn verbosity_flags() {
+        assert_eq!(parse("cmd"), Verbosity::Normal);
+        assert_eq!(parse("cmd -v"), Verbosity::Verbose);
+        assert_eq!(parse("cmd -vv"), Verbosity::Trace);
+        assert_eq!(parse("cmd -vvvv"), Verbosity::Trace);
+        assert_eq!(parse("cmd --quiet"), Verbosity::Quiet);
+        TestCommandLine::from_iter_safe("cmd -q -v".split_whitespace()).unwrap_err();
+    }
+}
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Error;
-use diem_config::config::{self, GitHubConfig, OnDiskStorageConfig, Token, VaultConfig};
+use diem_config::config::{
+    self, GitHubConfig, OnDiskStorageConfig, Token, VaultConfig, YubiHsmConfig,
+};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
@@ -16,6 +18,8 @@ pub const DISK: &str = "disk";
 pub const GITHUB: &str = "github";
 pub const MEMORY: &str = "memory";
 pub const VAULT: &str = "vault";
+//////// 0L ////////
+pub const YUBIHSM: &str = "yubihsm";
 
 // Custom timeouts for vault backend operations when using the management tooling.
 const CONNECTION_TIMEOUT_MS: u64 = 10_000;
@@ -124,6 +128,34 @@ impl TryInto<config::SecureBackend> for SecureBackend {
                     response_timeout_ms: Some(RESPONSE_TIMEOUT_MS),
                 })
             }
+            //////// 0L ////////
+            YUBIHSM => {
+                let password = self
+                    .parameters
+                    .remove("password")
+                    .ok_or_else(|| Error::BackendParsingError("missing password".into()))?;
+                let path = self
+                    .parameters
+                    .remove("path")
+                    .ok_or_else(|| Error::BackendParsingError("missing path".into()))?;
+                let mut storage = OnDiskStorageConfig::default();
+                storage.set_data_dir(PathBuf::from(""));
+                storage.path = PathBuf::from(path);
+                let mut config = YubiHsmConfig::new(
+                    Token::FromDisk(PathBuf::from(password)),
+                    config::SecureBackend::OnDiskStorage(storage),
+                );
+                if let Some(addr) = self.parameters.remove("addr") {
+                    config.addr = addr;
+                }
+                if let Some(port) = self.parameters.remove("port") {
+                    config.port = port.parse().map_err(|_| {
+                        Error::BackendParsingError(format!("invalid port {}", port))
+                    })?;
+                }
+                config.namespace = self.parameters.remove("namespace");
+                config::SecureBackend::YubiHsm(config)
+            }
             _ => panic!("Invalid backend: {}", self.backend),
         };
 
@@ -159,6 +191,10 @@ pair: "k0=v0;k1=v1;...".  The current supported formats are:
         an optional namespace: "namespace=NAMESPACE"
     InMemory: "backend=memory"
     OnDisk: "backend=disk;path=LOCAL_PATH"
+    YubiHSM: "backend=yubihsm;password=PATH_TO_PASSWORD;path=LOCAL_PATH"
+        the values and network keys are kept in the on disk storage at path
+        an optional connector: "addr=ADDRESS;port=PORT"
+        an optional namespace: "namespace=NAMESPACE"
                 "#)
             )]
             pub $field_name: Option<SecureBackend>,
@@ -218,6 +254,21 @@ mod tests {
         assert!(storage(disk).is_err());
     }
 
+    #[test]
+    fn test_yubihsm() {
+        let yubihsm = "backend=yubihsm;password=/password;path=/key_store.json;port=12346";
+        match storage(yubihsm).unwrap() {
+            config::SecureBackend::YubiHsm(config) => {
+                assert_eq!(config.port, 12346);
+                assert_eq!(config.password, Token::FromDisk("/password".into()));
+            }
+            backend => panic!("Unexpected backend {:?}", backend),
+        }
+
+        let yubihsm = "backend=yubihsm;path=/key_store.json";
+        assert!(storage(yubihsm).is_err());
+    }
+
     #[test]
     fn test_github() {
         let path = diem_temppath::TempPath::new();
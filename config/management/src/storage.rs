@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::error::Error;
+use crate::{
+    error::{from_storage_error, Error},
+    trace_span,
+};
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
     x25519,
@@ -27,10 +30,25 @@ impl StorageWrapper {
     }
 
     pub fn value<T: DeserializeOwned>(&self, name: &'static str) -> Result<T, Error> {
+        trace_span!("storage.read", storage = self.storage_name, key = name);
         self.storage
             .get(name)
             .map(|v| v.value)
-            .map_err(|e| Error::StorageReadError(self.storage_name, name, e.to_string()))
+            .map_err(|e| self.read_error(name, e))
+    }
+
+    //////// 0L ////////
+    /// Like `value`, but a key that was never set is `None` rather than an error
+    pub fn optional_value<T: DeserializeOwned>(
+        &self,
+        name: &'static str,
+    ) -> Result<Option<T>, Error> {
+        trace_span!("storage.read", storage = self.storage_name, key = name);
+        match self.storage.get(name) {
+            Ok(response) => Ok(Some(response.value)),
+            Err(diem_secure_storage::Error::KeyNotSet(_)) => Ok(None),
+            Err(e) => Err(self.read_error(name, e)),
+        }
     }
 
     pub fn account_address(&self, name: &'static str) -> Result<AccountAddress, Error> {
@@ -64,6 +82,7 @@ impl StorageWrapper {
     }
 
     pub fn rotate_key(&mut self, name: &'static str) -> Result<Ed25519PublicKey, Error> {
+        trace_span!("storage.rotate_key", storage = self.storage_name, key = name);
         self.storage
             .rotate_key(name)
             .map_err(|e| Error::StorageWriteError(self.storage_name, name, e.to_string()))
@@ -74,10 +93,11 @@ impl StorageWrapper {
         &self,
         key_name: &'static str,
     ) -> Result<Ed25519PublicKey, Error> {
+        trace_span!("storage.read_public_key", storage = self.storage_name, key = key_name);
         Ok(self
             .storage
             .get_public_key(key_name)
-            .map_err(|e| Error::StorageReadError(self.storage_name, key_name, e.to_string()))?
+            .map_err(|e| self.read_error(key_name, e))?
             .public_key)
     }
 
@@ -88,21 +108,23 @@ impl StorageWrapper {
     ) -> Result<Ed25519PublicKey, Error> {
         self.storage
             .get_public_key_previous_version(key_name)
-            .map_err(|e| Error::StorageReadError(self.storage_name, key_name, e.to_string()))
+            .map_err(|e| self.read_error(key_name, e))
     }
 
     /// Retrieves public key from the stored private key
     pub fn ed25519_private(&self, key_name: &'static str) -> Result<Ed25519PrivateKey, Error> {
+        trace_span!("storage.export_private_key", storage = self.storage_name, key = key_name);
         self.storage
             .export_private_key(key_name)
-            .map_err(|e| Error::StorageReadError(self.storage_name, key_name, e.to_string()))
+            .map_err(|e| self.read_error(key_name, e))
     }
 
     pub fn x25519_private(&self, key_name: &'static str) -> Result<x25519::PrivateKey, Error> {
+        trace_span!("storage.export_private_key", storage = self.storage_name, key = key_name);
         let key = self
             .storage
             .export_private_key(key_name)
-            .map_err(|e| Error::StorageReadError(self.storage_name, key_name, e.to_string()))?;
+            .map_err(|e| self.read_error(key_name, e))?;
         x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes())
             .map_err(|e| Error::StorageReadError(self.storage_name, key_name, e.to_string()))
     }
@@ -117,9 +139,28 @@ impl StorageWrapper {
     }
 
     pub fn set<T: Serialize>(&mut self, name: &'static str, value: T) -> Result<(), Error> {
-        self.storage
-            .set(name, value)
-            .map_err(|e| Error::StorageWriteError(self.storage_name, name, e.to_string()))
+        trace_span!("storage.write", storage = self.storage_name, key = name);
+        let storage_name = self.storage_name;
+        self.storage.set(name, value).map_err(|e| {
+            from_storage_error(storage_name, e, |e| {
+                Error::StorageWriteError(storage_name, name, e)
+            })
+        })
+    }
+
+    /// Checks that the backend is reachable and that our credentials are accepted
+    pub fn available(&self) -> Result<(), Error> {
+        let storage_name = self.storage_name;
+        self.storage.available().map_err(|e| {
+            from_storage_error(storage_name, e, |e| Error::StorageUnavailable(storage_name, e))
+        })
+    }
+
+    fn read_error(&self, name: &'static str, error: diem_secure_storage::Error) -> Error {
+        let storage_name = self.storage_name;
+        from_storage_error(storage_name, error, |e| {
+            Error::StorageReadError(storage_name, name, e)
+        })
     }
 
     /// Sign a transaction
@@ -129,6 +170,7 @@ impl StorageWrapper {
         script_name: &'static str,
         raw_transaction: RawTransaction,
     ) -> Result<SignedTransaction, Error> {
+        trace_span!("storage.sign", storage = self.storage_name, key = key_name);
         let public_key = self.ed25519_public_from_private(key_name)?;
         let signature = self.storage.sign(key_name, &raw_transaction).map_err(|e| {
             Error::StorageSigningError(self.storage_name, script_name, key_name, e.to_string())
@@ -149,6 +191,7 @@ impl StorageWrapper {
         script_name: &'static str,
         raw_transaction: RawTransaction,
     ) -> Result<SignedTransaction, Error> {
+        trace_span!("storage.sign", storage = self.storage_name, key = key_name);
         let signature = self
             .storage
             .sign_using_version(key_name, key_version.clone(), &raw_transaction)
@@ -124,10 +124,17 @@ pub fn validate_address(
 ) -> Result<(), Error> {
     let mut has_addr = false;
     let mut has_port = false;
+    //////// 0L ////////
+    let mut is_onion = false;
     // Only allow DNS and IP addresses
     for protocol in network_address.as_slice().iter() {
         match protocol {
             Protocol::Ip4(_) => has_addr = true,
+            //////// 0L ////////
+            Protocol::Onion3(_) => {
+                has_addr = true;
+                is_onion = true;
+            }
             Protocol::Dns4(dns_name) => {
                 let dns_name = format!("{}", dns_name);
                 if Ipv4Addr::from_str(&dns_name).is_ok() {
@@ -161,6 +168,12 @@ pub fn validate_address(
         )));
     }
 
+    //////// 0L ////////
+    // Onion addresses only resolve through a Tor proxy
+    if is_onion {
+        return Ok(());
+    }
+
     // Ensure that the address resolves to IP addresses
     let addrs = network_address.to_socket_addrs().map_err(|err| {
         Error::CommandArgumentError(format!(
@@ -0,0 +1,73 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Golden configs for serialization compatibility tests.
+//!
+//! At every release the canonical serialization of the default configs is stored under
+//! `config/tests/compat/<version>/`. The compat test verifies that all stored goldens still
+//! deserialize, and that the current serialization matches the goldens of the newest release, so
+//! that changes to the config format are always deliberate.
+
+use crate::config::{Error, Identity, NodeConfig, PersistableConfig};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The version goldens generated by this build are stored under
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The configs stored as goldens, by file name. The defaults generate a random identity for
+/// networks without one, so those are stored unset to keep the goldens reproducible.
+pub fn golden_configs() -> Vec<(&'static str, NodeConfig)> {
+    vec![
+        ("validator.yaml", NodeConfig::default_for_validator()),
+        (
+            "validator_full_node.yaml",
+            NodeConfig::default_for_validator_full_node(),
+        ),
+        (
+            "public_full_node.yaml",
+            NodeConfig::default_for_public_full_node(),
+        ),
+    ]
+    .into_iter()
+    .map(|(name, mut config)| {
+        clear_generated_identities(&mut config);
+        (name, config)
+    })
+    .collect()
+}
+
+fn clear_generated_identities(config: &mut NodeConfig) {
+    let networks = config
+        .validator_network
+        .iter_mut()
+        .chain(config.full_node_networks.iter_mut());
+    for network in networks {
+        if let Identity::FromConfig(_) = network.identity {
+            network.identity = Identity::None;
+        }
+    }
+}
+
+/// Writes the goldens of the current version into `compat_dir/<version>/`, returning that
+/// directory
+pub fn write_goldens(compat_dir: &Path) -> Result<PathBuf, Error> {
+    let version_dir = compat_dir.join(CURRENT_VERSION);
+    fs::create_dir_all(&version_dir)
+        .map_err(|e| Error::IO(version_dir.display().to_string(), e))?;
+    for (name, config) in golden_configs() {
+        config.save_config(version_dir.join(name))?;
+    }
+    Ok(version_dir)
+}
+
+/// Orders release directories such as `5.2.0` by version rather than lexicographically
+pub fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
@@ -0,0 +1,43 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::{config::Token, utils};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// A local HTTP endpoint to inspect a running node: its effective config, the config fingerprint,
+/// the upstream selection and the connected peers. With a `grpc_address`, a gRPC service also
+/// controls the running node: disconnecting peers, adding seeds, setting the log level,
+/// triggering state sync and dumping the network contexts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminServiceConfig {
+    pub enabled: bool,
+    /// Loopback by default, as the service exposes node internals
+    pub address: SocketAddr,
+    /// The bearer token requests have to present. The service does not start without one.
+    pub token: Option<Token>,
+    /// Where the gRPC admin service listens, authenticated with the same token
+    pub grpc_address: Option<SocketAddr>,
+}
+
+impl Default for AdminServiceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:9103".parse().unwrap(),
+            token: None,
+            grpc_address: None,
+        }
+    }
+}
+
+impl AdminServiceConfig {
+    pub fn randomize_ports(&mut self) {
+        self.address.set_port(utils::get_available_port());
+        if let Some(grpc_address) = self.grpc_address.as_mut() {
+            grpc_address.set_port(utils::get_available_port());
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::{config::TlsConfig, utils};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+pub const DEFAULT_API_PORT: u16 = 8081;
+pub const DEFAULT_API_PAGE_SIZE_LIMIT: u16 = 1000;
+
+/// The REST API, reading accounts, transactions, events and the ledger info as plain JSON next
+/// to JSON-RPC. The OpenAPI spec of the API is served at `/openapi.yaml`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub address: SocketAddr,
+    /// The most items a page of transactions or events may hold
+    pub page_size_limit: u16,
+    /// Serves HTTPS instead of HTTP
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: format!("127.0.0.1:{}", DEFAULT_API_PORT).parse().unwrap(),
+            page_size_limit: DEFAULT_API_PAGE_SIZE_LIMIT,
+            tls: None,
+        }
+    }
+}
+
+impl ApiConfig {
+    pub fn randomize_ports(&mut self) {
+        self.address.set_port(utils::get_available_port());
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Backups streamed by the node itself while it runs, as `db-backup coordinator run` would from
+/// the backup service. Disabled without a target.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    pub target: Option<BackupTarget>,
+    /// A state snapshot is backed up every this many versions
+    pub state_snapshot_interval: usize,
+    /// Transactions are backed up in batches of this many, a divisor of the snapshot interval
+    pub transaction_batch_size: usize,
+    /// Only this many of the latest state snapshots are kept in the target, all by default.
+    /// Transactions and epoch endings are always kept.
+    pub state_snapshot_retention: Option<usize>,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            target: None,
+            state_snapshot_interval: 10_000_000,
+            transaction_batch_size: 100_000,
+            state_snapshot_retention: None,
+        }
+    }
+}
+
+/// Where the backups go
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum BackupTarget {
+    /// A directory of the host
+    LocalFs { path: PathBuf },
+    /// A bucket of S3, or of an S3 compatible store at `endpoint_url`, through the aws cli
+    S3 {
+        bucket: String,
+        #[serde(default)]
+        sub_dir: String,
+        #[serde(default)]
+        endpoint_url: Option<String>,
+    },
+    /// The commands of a command adapter config of the backup cli
+    CommandAdapter { config: PathBuf },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backup_target() {
+        let config: BackupConfig = serde_yaml::from_str(
+            "target:\n  type: s3\n  bucket: backups\n  endpoint_url: \"https://minio:9000\"\n\
+             state_snapshot_retention: 3\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.target,
+            Some(BackupTarget::S3 {
+                bucket: "backups".into(),
+                sub_dir: "".into(),
+                endpoint_url: Some("https://minio:9000".into()),
+            })
+        );
+        assert_eq!(config.state_snapshot_retention, Some(3));
+        assert_eq!(
+            config.transaction_batch_size,
+            BackupConfig::default().transaction_batch_size
+        );
+    }
+}
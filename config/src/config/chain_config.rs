@@ -0,0 +1,55 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::config::{
+    ConsensusConfig, ExecutionConfig, JsonRpcConfig, NetworkConfig, RoleType, StorageConfig,
+    UpstreamConfig, WaypointConfig,
+};
+use diem_types::chain_id::{self, ChainId};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A further chain served by the same process, with its own storage, networks, waypoint and
+/// genesis. The other sections are shared with the config it is declared in, see
+/// `NodeConfig::chain_configs`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    /// The chain set up by the genesis, checked when the storage is opened
+    #[serde(deserialize_with = "chain_id::deserialize_config_chain_id")]
+    pub chain_id: ChainId,
+    /// Must differ from the data dir of every other chain
+    pub data_dir: PathBuf,
+    pub role: RoleType,
+    pub waypoint: WaypointConfig,
+    #[serde(default)]
+    pub consensus: ConsensusConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub full_node_networks: Vec<NetworkConfig>,
+    #[serde(default)]
+    pub json_rpc: JsonRpcConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub upstream: UpstreamConfig,
+    #[serde(default)]
+    pub validator_network: Option<NetworkConfig>,
+}
+
+impl ChainConfig {
+    pub fn randomize_ports(&mut self) {
+        self.json_rpc.randomize_ports();
+        self.storage.randomize_ports();
+
+        if let Some(network) = self.validator_network.as_mut() {
+            network.listen_address = crate::utils::get_available_port_in_multiaddr(true);
+        }
+
+        for network in self.full_node_networks.iter_mut() {
+            network.listen_address = crate::utils::get_available_port_in_multiaddr(true);
+        }
+    }
+}
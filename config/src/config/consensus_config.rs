@@ -17,6 +17,7 @@ pub struct ConsensusConfig {
     // Timeout for consensus to pull transactions from mempool and get a response (in milliseconds)
     pub mempool_txn_pull_timeout_ms: u64,
     pub round_initial_timeout_ms: u64,
+    /// How the proposer of each round is elected, see `ConsensusProposerType`
     pub proposer_type: ConsensusProposerType,
     pub safety_rules: SafetyRulesConfig,
     // Only sync committed transactions but not vote for any pending blocks. This is useful when
@@ -35,10 +36,9 @@ impl Default for ConsensusConfig {
             mempool_txn_pull_timeout_ms: 1000,
             mempool_executed_txn_timeout_ms: 1000,
             round_initial_timeout_ms: 5000, //////// 0L ////////
-            proposer_type: ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
-                active_weights: 99,
-                inactive_weights: 1,
-            }),
+            proposer_type: ConsensusProposerType::LeaderReputation(
+                LeaderReputationConfig::default(),
+            ),
             safety_rules: SafetyRulesConfig::default(),
             sync_only: false,
             mempool_poll_count: 1,
@@ -52,24 +52,91 @@ impl ConsensusConfig {
     }
 }
 
+/// The proposer election of every validator of a network must be the same, or they disagree on
+/// the proposer of a round. Tagged by `type`, e.g.
+///
+/// ```yaml
+/// proposer_type:
+///   type: leader_reputation
+///   active_weights: 99
+///   inactive_weights: 1
+///   window_size: 100
+/// ```
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ConsensusProposerType {
-    // Choose the smallest PeerId as the proposer
+    /// Choose the smallest PeerId as the proposer
     FixedProposer,
-    // Round robin rotation of proposers
+    /// Round robin rotation of proposers, each proposing `contiguous_rounds` in a row
     RotatingProposer,
-    // Committed history based proposer election
+    /// Committed history based proposer election
     LeaderReputation(LeaderReputationConfig),
-    // Pre-specified proposers for each round,
-    // or default proposer if round proposer not
-    // specified
+    /// Pre-specified proposers for each round, or default proposer if round proposer not
+    /// specified, e.g. `type: round_proposer` followed by `3: "<account address>"`
     RoundProposer(HashMap<Round, AccountAddress>),
 }
 
+//////// 0L ////////
+pub const DEFAULT_LEADER_REPUTATION_EXCLUDE_ROUND: u64 = 4;
+
+/// Elects the proposers at random, weighted by whether they proposed or voted for the blocks
+/// committed lately
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct LeaderReputationConfig {
+    /// Weight of the validators that proposed or voted in the window
     pub active_weights: u64,
+    /// Weight of the others
     pub inactive_weights: u64,
+    //////// 0L ////////
+    /// Committed blocks in the window, the number of validators when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window_size: Option<usize>,
+    /// The window of round `r` ends at round `r - exclude_round`, which every validator committed
+    #[serde(default = "default_exclude_round")]
+    pub exclude_round: u64,
+}
+
+fn default_exclude_round() -> u64 {
+    DEFAULT_LEADER_REPUTATION_EXCLUDE_ROUND
+}
+
+impl Default for LeaderReputationConfig {
+    fn default() -> Self {
+        Self {
+            active_weights: 99,
+            inactive_weights: 1,
+            window_size: None,
+            exclude_round: DEFAULT_LEADER_REPUTATION_EXCLUDE_ROUND,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proposer_type() {
+        let config: ConsensusConfig = serde_yaml::from_str(
+            "proposer_type:\n  type: leader_reputation\n  active_weights: 9\n  inactive_weights: 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.proposer_type,
+            ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
+                active_weights: 9,
+                inactive_weights: 1,
+                window_size: None,
+                exclude_round: DEFAULT_LEADER_REPUTATION_EXCLUDE_ROUND,
+            })
+        );
+
+        let config: ConsensusConfig =
+            serde_yaml::from_str("proposer_type:\n  type: rotating_proposer\n").unwrap();
+        assert_eq!(
+            config.proposer_type,
+            ConsensusProposerType::RotatingProposer
+        );
+    }
 }
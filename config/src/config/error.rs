@@ -15,6 +15,9 @@ pub enum Error {
     Yaml(String, #[source] serde_yaml::Error),
     #[error("Config is missing expected value: {0}")]
     Missing(&'static str),
+    //////// 0L ////////
+    #[error("Invalid config:{}", crate::config::display_validation_errors(.0))]
+    Invalid(Vec<crate::config::ValidationError>),
 }
 
 pub fn invariant(cond: bool, msg: String) -> Result<(), Error> {
@@ -0,0 +1,42 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// An HTTP endpoint for load balancers and orchestrators: `/health` answers while the node runs,
+/// `/ready` once state sync, mempool, consensus and the upstream networks are ready, and until
+/// the node halts for an upgrade, see `UpgradeConfig`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthCheckConfig {
+    pub enabled: bool,
+    /// Every interface by default, probes come from outside the host
+    pub address: SocketAddr,
+    /// State sync is ready within this many versions of the highest version known from peers
+    pub max_version_lag: u64,
+    /// Consensus of a validator is ready within this many rounds of the last committed round
+    pub max_round_lag: u64,
+    /// The network is ready with at least this many peers connected on the upstream networks
+    pub min_upstream_peers: usize,
+}
+
+impl Default for HealthCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "0.0.0.0:9104".parse().unwrap(),
+            max_version_lag: 100,
+            max_round_lag: 10,
+            min_upstream_peers: 1,
+        }
+    }
+}
+
+impl HealthCheckConfig {
+    pub fn randomize_ports(&mut self) {
+        self.address.set_port(utils::get_available_port());
+    }
+}
@@ -1,9 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{
+    config::{TlsConfig, DEFAULT_TLS_RELOAD_INTERVAL_SECS},
+    utils,
+};
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+use std::{collections::BTreeMap, net::SocketAddr};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -14,6 +17,13 @@ pub struct JsonRpcConfig {
     pub content_length_limit: usize,
     pub tls_cert_path: Option<String>,
     pub tls_key_path: Option<String>,
+    //////// 0L ////////
+    /// See `TlsConfig::reload_interval_secs`
+    pub tls_reload_interval_secs: u64,
+    /// Serves `get_network_peers`, the connected peers of every network of the node
+    pub expose_network_peers: bool,
+    /// Throttles the clients by IP, so a public node survives scraping without a reverse proxy
+    pub rate_limit: RpcRateLimitConfig,
 }
 
 pub const DEFAULT_JSON_RPC_ADDRESS: &str = "127.0.0.1";
@@ -34,6 +44,9 @@ impl Default for JsonRpcConfig {
             content_length_limit: DEFAULT_CONTENT_LENGTH_LIMIT,
             tls_cert_path: None,
             tls_key_path: None,
+            tls_reload_interval_secs: DEFAULT_TLS_RELOAD_INTERVAL_SECS,
+            expose_network_peers: false,
+            rate_limit: RpcRateLimitConfig::default(),
         }
     }
 }
@@ -42,4 +55,62 @@ impl JsonRpcConfig {
     pub fn randomize_ports(&mut self) {
         self.address.set_port(utils::get_available_port());
     }
+
+    //////// 0L ////////
+    /// The HTTPS config, if both the certificate and key are set
+    pub fn tls(&self) -> Option<TlsConfig> {
+        match (&self.tls_cert_path, &self.tls_key_path) {
+            (Some(cert_path), Some(key_path)) => Some(TlsConfig {
+                cert_path: cert_path.into(),
+                key_path: key_path.into(),
+                reload_interval_secs: self.tls_reload_interval_secs,
+            }),
+            _ => None,
+        }
+    }
+}
+
+//////// 0L ////////
+/// Token buckets of method calls per client IP, each call of a batch taking a token. A request
+/// over the bucket of its IP is answered 429, one over `max_concurrent_requests` 503.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcRateLimitConfig {
+    pub enabled: bool,
+    /// The calls an IP may burst
+    pub ip_bucket_size: usize,
+    /// The calls refilled to an IP each second
+    pub ip_bucket_rate: usize,
+    /// Extra buckets per IP for some methods by name, e.g. to throttle `get_events` harder
+    pub method_buckets: BTreeMap<String, RpcBucketConfig>,
+    /// The percentage of its size a new bucket starts with
+    pub initial_bucket_fill_percentage: u8,
+    /// The requests served at once, whatever their IP
+    pub max_concurrent_requests: usize,
+}
+
+pub const DEFAULT_RPC_IP_BUCKET_SIZE: usize = 100;
+pub const DEFAULT_RPC_IP_BUCKET_RATE: usize = 20;
+pub const DEFAULT_RPC_MAX_CONCURRENT_REQUESTS: usize = 256;
+
+impl Default for RpcRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ip_bucket_size: DEFAULT_RPC_IP_BUCKET_SIZE,
+            ip_bucket_rate: DEFAULT_RPC_IP_BUCKET_RATE,
+            method_buckets: BTreeMap::new(),
+            initial_bucket_fill_percentage: 100,
+            max_concurrent_requests: DEFAULT_RPC_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcBucketConfig {
+    /// The calls an IP may burst
+    pub size: usize,
+    /// The calls refilled each second, at most `size`
+    pub rate: usize,
 }
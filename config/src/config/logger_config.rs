@@ -13,6 +13,20 @@ pub struct LoggerConfig {
     pub is_async: bool,
     // The default logging level for slog.
     pub level: Level,
+    //////// 0L ////////
+    // The format of the log lines.
+    pub format: LogFormat,
+}
+
+//////// 0L ////////
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human readable lines, messages of the network prefixed with its `NetworkContext`
+    Text,
+    /// One JSON object per line, with the fields of the `NetworkContext` (role, network_id,
+    /// peer_id, ...) at the top level of the entry
+    Json,
 }
 
 impl Default for LoggerConfig {
@@ -21,6 +35,7 @@ impl Default for LoggerConfig {
             chan_size: CHANNEL_SIZE,
             is_async: true,
             level: Level::Info,
+            format: LogFormat::Text,
         }
     }
 }
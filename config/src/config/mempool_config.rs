@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::network_id::NetworkId;
+use diem_types::account_address::AccountAddress;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -35,6 +38,13 @@ pub struct MempoolConfig {
     pub system_transaction_timeout_secs: u64,
     /// tick interval for system GC.
     pub system_transaction_gc_interval_ms: u64,
+    //////// 0L ////////
+    /// Broadcast settings of the upstream networks overriding the ones above, e.g. to keep the VFN
+    /// network broadcasting at full rate while the public network is lagging
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub upstream_policies: Vec<MempoolUpstreamPolicy>,
+    /// Rules the transactions must pass to enter the mempool, besides `capacity_per_user`
+    pub admission: MempoolAdmissionConfig,
 }
 
 impl Default for MempoolConfig {
@@ -52,6 +62,172 @@ impl Default for MempoolConfig {
             default_failovers: 3,
             system_transaction_timeout_secs: 1000, //////// 0L //////// transacitons should timeout under this time
             system_transaction_gc_interval_ms: 1000, /////// 0L //////// increase rate of GC
+            upstream_policies: vec![],
+            admission: MempoolAdmissionConfig::default(),
         }
     }
 }
+
+//////// 0L ////////
+impl MempoolConfig {
+    fn upstream_policy(&self, network_id: &NetworkId) -> Option<&MempoolUpstreamPolicy> {
+        self.upstream_policies
+            .iter()
+            .find(|policy| &policy.network_id == network_id)
+    }
+
+    /// Size of the batches broadcast to the peers of `network_id`
+    pub fn batch_size(&self, network_id: &NetworkId) -> usize {
+        self.upstream_policy(network_id)
+            .and_then(|policy| policy.batch_size)
+            .unwrap_or(self.shared_mempool_batch_size)
+    }
+
+    /// Broadcasts pending an ACK from a peer of `network_id` before broadcasting to it stops
+    pub fn max_broadcasts_per_peer(&self, network_id: &NetworkId) -> usize {
+        self.upstream_policy(network_id)
+            .and_then(|policy| policy.max_broadcasts_per_peer)
+            .unwrap_or(self.max_broadcasts_per_peer)
+    }
+
+    /// Interval of the broadcasts to a peer of `network_id` in backoff mode
+    pub fn backoff_interval_ms(&self, network_id: &NetworkId) -> u64 {
+        self.upstream_policy(network_id)
+            .and_then(|policy| policy.backoff_interval_ms)
+            .unwrap_or(self.shared_mempool_backoff_interval_ms)
+    }
+}
+
+/// Broadcast settings of the peers of an upstream network, the global ones when unset
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MempoolUpstreamPolicy {
+    pub network_id: NetworkId,
+    /// Overrides `shared_mempool_batch_size`
+    #[serde(default)]
+    pub batch_size: Option<usize>,
+    /// Overrides `max_broadcasts_per_peer`
+    #[serde(default)]
+    pub max_broadcasts_per_peer: Option<usize>,
+    /// Overrides `shared_mempool_backoff_interval_ms`
+    #[serde(default)]
+    pub backoff_interval_ms: Option<u64>,
+}
+
+/// Rules of the node on the transactions entering its mempool, from its clients or from the peers
+/// broadcasting them. A rejected transaction gets `MempoolStatusCode::Rejected`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MempoolAdmissionConfig {
+    /// Transactions paying less per gas unit are rejected
+    pub min_gas_unit_price: u64,
+    /// Only these senders are admitted when set, e.g. on a private deployment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender_allowlist: Option<BTreeSet<AccountAddress>>,
+    /// These senders are never admitted
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub sender_denylist: BTreeSet<AccountAddress>,
+    /// Rules of the transactions broadcast by the peers of a network, overriding the ones above,
+    /// e.g. to be stricter with the public network than with the VFN network
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub network_limits: Vec<MempoolNetworkLimits>,
+}
+
+impl MempoolAdmissionConfig {
+    /// The limits of the network, none for the transactions submitted by the clients of the node
+    fn network_limits(&self, network_id: Option<&NetworkId>) -> Option<&MempoolNetworkLimits> {
+        let network_id = network_id?;
+        self.network_limits
+            .iter()
+            .find(|limits| &limits.network_id == network_id)
+    }
+
+    /// Whether the sender may have transactions in the mempool
+    pub fn admits_sender(&self, sender: &AccountAddress) -> bool {
+        !self.sender_denylist.contains(sender)
+            && self
+                .sender_allowlist
+                .as_ref()
+                .map_or(true, |allowlist| allowlist.contains(sender))
+    }
+
+    /// The minimum gas unit price of the transactions received over `network_id`, none for the
+    /// ones submitted by clients
+    pub fn min_gas_unit_price(&self, network_id: Option<&NetworkId>) -> u64 {
+        self.network_limits(network_id)
+            .and_then(|limits| limits.min_gas_unit_price)
+            .unwrap_or(self.min_gas_unit_price)
+    }
+
+    /// The transactions a sender may have in the mempool to admit one received over `network_id`,
+    /// if stricter than `capacity_per_user`
+    pub fn capacity_per_user(&self, network_id: Option<&NetworkId>) -> Option<usize> {
+        self.network_limits(network_id)
+            .and_then(|limits| limits.capacity_per_user)
+    }
+}
+
+/// Admission rules of the transactions broadcast by the peers of a network, the global ones when
+/// unset
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MempoolNetworkLimits {
+    pub network_id: NetworkId,
+    /// Overrides `min_gas_unit_price`
+    #[serde(default)]
+    pub min_gas_unit_price: Option<u64>,
+    /// Caps the transactions of a sender in the mempool below `capacity_per_user`
+    #[serde(default)]
+    pub capacity_per_user: Option<usize>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_upstream_policies() {
+        let config: MempoolConfig = serde_yaml::from_str(
+            "upstream_policies:\n  - network_id:\n      private: vfn\n    batch_size: 500\n",
+        )
+        .unwrap();
+        let vfn = NetworkId::vfn_network();
+        assert_eq!(config.batch_size(&vfn), 500);
+        assert_eq!(
+            config.max_broadcasts_per_peer(&vfn),
+            config.max_broadcasts_per_peer
+        );
+        assert_eq!(
+            config.batch_size(&NetworkId::Public),
+            config.shared_mempool_batch_size
+        );
+    }
+
+    #[test]
+    fn test_admission() {
+        let allowed = AccountAddress::random();
+        let denied = AccountAddress::random();
+        let config: MempoolAdmissionConfig = serde_yaml::from_str(&format!(
+            "min_gas_unit_price: 1\nsender_denylist: [\"{}\"]\nnetwork_limits:\n  - network_id: public\n    min_gas_unit_price: 10\n    capacity_per_user: 1\n",
+            denied
+        ))
+        .unwrap();
+        assert!(config.admits_sender(&allowed));
+        assert!(!config.admits_sender(&denied));
+        assert_eq!(config.min_gas_unit_price(None), 1);
+        assert_eq!(config.min_gas_unit_price(Some(&NetworkId::Public)), 10);
+        assert_eq!(
+            config.min_gas_unit_price(Some(&NetworkId::vfn_network())),
+            1
+        );
+        assert_eq!(config.capacity_per_user(None), None);
+        assert_eq!(config.capacity_per_user(Some(&NetworkId::Public)), Some(1));
+
+        let config = MempoolAdmissionConfig {
+            sender_allowlist: Some(vec![allowed].into_iter().collect()),
+            ..MempoolAdmissionConfig::default()
+        };
+        assert!(config.admits_sender(&allowed));
+        assert!(!config.admits_sender(&denied));
+    }
+}
@@ -1,8 +1,25 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{
+    config::{TlsConfig, Token},
+    network_id::NetworkContext,
+};
+use diem_types::chain_id::ChainId;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
+
+//////// 0L ////////
+/// Port of the scrape endpoint in the generated node configs
+pub const DEFAULT_METRICS_PORT: u16 = 9101;
+/// User of the scrape endpoint in the generated node configs
+pub const DEFAULT_METRICS_USER: &str = "prometheus";
+/// File in the node's data dir holding the metrics password of the generated node configs
+pub const METRICS_PASSWORD_FILE: &str = "metrics_password";
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -10,6 +27,12 @@ pub struct MetricsConfig {
     pub collection_interval_ms: u64,
     pub dir: PathBuf,
     pub enabled: bool,
+    //////// 0L ////////
+    /// The local endpoint scraped by Prometheus
+    pub scrape: MetricsScrapeConfig,
+    /// Pushes metrics to a Prometheus push-gateway, for nodes that cannot be scraped (e.g., behind
+    /// a NAT)
+    pub push_gateway: Option<PushGatewayConfig>,
     #[serde(skip)]
     data_dir: PathBuf,
 }
@@ -21,6 +44,8 @@ impl Default for MetricsConfig {
             data_dir: PathBuf::from("/opt/diem/data"),
             enabled: false,
             dir: PathBuf::from("metrics"),
+            scrape: MetricsScrapeConfig::default(),
+            push_gateway: None,
         }
     }
 }
@@ -38,3 +63,167 @@ impl MetricsConfig {
         self.data_dir = data_dir;
     }
 }
+
+//////// 0L ////////
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsScrapeConfig {
+    /// Bind address of the scrape endpoint. If not set, the debug interface address and
+    /// `metrics_server_port` are used.
+    pub address: Option<SocketAddr>,
+    /// HTTP path the metrics are served on
+    pub path: String,
+    /// Credentials required to read the metrics, the health check stays open
+    pub basic_auth: Option<BasicAuthConfig>,
+    /// Serves HTTPS instead of HTTP, e.g. to scrape over the internet with `basic_auth`
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for MetricsScrapeConfig {
+    fn default() -> Self {
+        Self {
+            address: None,
+            path: "/metrics".to_string(),
+            basic_auth: None,
+            tls: None,
+        }
+    }
+}
+
+impl MetricsScrapeConfig {
+    /// The endpoint of the generated node configs, on every interface at the default port. As
+    /// other hosts reach it, it requires the `prometheus` user with the password stored in
+    /// `METRICS_PASSWORD_FILE` of `data_dir`, which the config generation creates.
+    pub fn node_default(data_dir: &Path) -> Self {
+        Self {
+            address: Some(SocketAddr::new(
+                Ipv4Addr::UNSPECIFIED.into(),
+                DEFAULT_METRICS_PORT,
+            )),
+            basic_auth: Some(BasicAuthConfig {
+                username: DEFAULT_METRICS_USER.to_string(),
+                password: Token::FromDisk(data_dir.join(METRICS_PASSWORD_FILE)),
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BasicAuthConfig {
+    pub username: String,
+    pub password: Token,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PushGatewayConfig {
+    /// Base URL of the push-gateway, e.g. http://pushgateway:9091. Only HTTP is supported.
+    pub url: String,
+    /// The `job` grouping key of the pushed metrics
+    #[serde(default = "PushGatewayConfig::default_job")]
+    pub job: String,
+    #[serde(default = "PushGatewayConfig::default_push_interval_ms")]
+    pub push_interval_ms: u64,
+    /// Additional grouping labels, these take precedence over the derived labels
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl PushGatewayConfig {
+    fn default_job() -> String {
+        "diem-node".to_string()
+    }
+
+    fn default_push_interval_ms() -> u64 {
+        15_000
+    }
+
+    /// Grouping labels for the pushed metrics. Nodes pushing to the same gateway must use distinct
+    /// groups, so the chain and, if known, the role and peer id of the node are always included.
+    pub fn grouping_labels(
+        &self,
+        chain_id: ChainId,
+        context: Option<&NetworkContext>,
+    ) -> BTreeMap<String, String> {
+        let mut labels = BTreeMap::new();
+        labels.insert("chain_id".to_string(), chain_id.to_string());
+        if let Some(context) = context {
+            labels.insert("role".to_string(), context.role().to_string());
+            labels.insert("network".to_string(), context.network_id().to_string());
+            labels.insert("peer_id".to_string(), context.peer_id().to_string());
+        }
+        labels.extend(self.labels.clone());
+        labels
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config::RoleType, network_id::NetworkId};
+    use diem_types::PeerId;
+
+    #[test]
+    fn test_grouping_labels() {
+        let mut config = PushGatewayConfig {
+            url: "http://localhost:9091".to_string(),
+            job: PushGatewayConfig::default_job(),
+            push_interval_ms: PushGatewayConfig::default_push_interval_ms(),
+            labels: BTreeMap::new(),
+        };
+        config
+            .labels
+            .insert("network".to_string(), "operator".to_string());
+        let context = NetworkContext::new(
+            RoleType::Validator,
+            NetworkId::Validator,
+            PeerId::random(),
+            ChainId::test(),
+        );
+
+        let labels = config.grouping_labels(ChainId::test(), Some(&context));
+        assert_eq!(labels["chain_id"], ChainId::test().to_string());
+        assert_eq!(labels["role"], "validator");
+        assert_eq!(labels["network"], "operator");
+        assert!(labels.contains_key("peer_id"));
+    }
+
+    #[test]
+    fn test_deserialize_without_metrics_sections() {
+        let config: MetricsConfig = serde_yaml::from_str("enabled: true").unwrap();
+        assert_eq!(config.scrape, MetricsScrapeConfig::default());
+        assert_eq!(config.push_gateway, None);
+    }
+
+    #[test]
+    fn test_deserialize_basic_auth() {
+        let config: MetricsScrapeConfig = serde_yaml::from_str(
+            "address: \"0.0.0.0:9101\"\nbasic_auth:\n  username: prometheus\n  password:\n    \
+             from_config: secret",
+        )
+        .unwrap();
+        assert_eq!(config.address, Some("0.0.0.0:9101".parse().unwrap()));
+        assert_eq!(config.path, "/metrics");
+        assert_eq!(
+            config.basic_auth,
+            Some(BasicAuthConfig {
+                username: "prometheus".to_string(),
+                password: Token::FromConfig("secret".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_node_default_requires_auth() {
+        let config = MetricsScrapeConfig::node_default(Path::new("/opt/diem/data"));
+        assert_eq!(
+            config.basic_auth,
+            Some(BasicAuthConfig {
+                username: "prometheus".to_string(),
+                password: Token::FromDisk(PathBuf::from("/opt/diem/data/metrics_password")),
+            })
+        );
+    }
+}
@@ -13,6 +13,14 @@ use std::{
 };
 use thiserror::Error;
 
+mod admin_service_config; //////// 0L ////////
+pub use admin_service_config::*;
+mod api_config; //////// 0L ////////
+pub use api_config::*;
+mod backup_config; //////// 0L ////////
+pub use backup_config::*;
+mod chain_config; //////// 0L ////////
+pub use chain_config::*;
 mod consensus_config;
 pub use consensus_config::*;
 mod debug_interface_config;
@@ -21,6 +29,8 @@ mod error;
 pub use error::*;
 mod execution_config;
 pub use execution_config::*;
+mod health_check_config; //////// 0L ////////
+pub use health_check_config::*;
 mod key_manager_config;
 pub use key_manager_config::*;
 mod logger_config;
@@ -31,22 +41,35 @@ mod mempool_config;
 pub use mempool_config::*;
 mod network_config;
 pub use network_config::*;
+mod peer_reputation_config; //////// 0L ////////
+pub use peer_reputation_config::*;
 mod json_rpc_config;
 pub use json_rpc_config::*;
 mod secure_backend_config;
 pub use secure_backend_config::*;
+mod shutdown_config; //////// 0L ////////
+pub use shutdown_config::*;
+mod telemetry_config; //////// 0L ////////
+pub use telemetry_config::*;
 mod state_sync_config;
 pub use state_sync_config::*;
 mod storage_config;
 pub use storage_config::*;
 mod safety_rules_config;
 pub use safety_rules_config::*;
+mod upgrade_config; //////// 0L ////////
+pub use upgrade_config::*;
 mod upstream_config;
 pub use upstream_config::*;
+mod validation; //////// 0L ////////
+pub use validation::*;
 mod test_config;
+mod tls_config; //////// 0L ////////
+pub use tls_config::*;
 use crate::network_id::NetworkId;
+use diem_crypto::HashValue;
 use diem_secure_storage::{KVStorage, Storage};
-use diem_types::waypoint::Waypoint;
+use diem_types::{chain_id::ChainId, waypoint::Waypoint};
 pub use test_config::*;
 
 /// Config pulls in configuration information from the config file.
@@ -56,8 +79,16 @@ pub use test_config::*;
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct NodeConfig {
+    #[serde(default)]
+    pub admin_service: AdminServiceConfig, //////// 0L ////////
+    #[serde(default)]
+    pub api: ApiConfig, //////// 0L ////////
+    #[serde(default)]
+    pub backup: BackupConfig, //////// 0L ////////
     #[serde(default)]
     pub base: BaseConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chains: Vec<ChainConfig>, //////// 0L ////////
     #[serde(default)]
     pub consensus: ConsensusConfig,
     #[serde(default)]
@@ -67,6 +98,8 @@ pub struct NodeConfig {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub full_node_networks: Vec<NetworkConfig>,
     #[serde(default)]
+    pub health_check: HealthCheckConfig, //////// 0L ////////
+    #[serde(default)]
     pub logger: LoggerConfig,
     #[serde(default)]
     pub metrics: MetricsConfig,
@@ -75,12 +108,20 @@ pub struct NodeConfig {
     #[serde(default)]
     pub json_rpc: JsonRpcConfig,
     #[serde(default)]
+    pub peer_reputation: PeerReputationConfig, //////// 0L ////////
+    #[serde(default)]
+    pub shutdown: ShutdownConfig, //////// 0L ////////
+    #[serde(default)]
     pub state_sync: StateSyncConfig,
     #[serde(default)]
     pub storage: StorageConfig,
     #[serde(default)]
+    pub telemetry: TelemetryConfig, //////// 0L ////////
+    #[serde(default)]
     pub test: Option<TestConfig>,
     #[serde(default)]
+    pub upgrade: UpgradeConfig, //////// 0L ////////
+    #[serde(default)]
     pub upstream: UpstreamConfig, //////// 0L ////////
     #[serde(default)]
     pub validator_network: Option<NetworkConfig>,
@@ -114,6 +155,19 @@ pub enum WaypointConfig {
     FromConfig(Waypoint),
     FromFile(PathBuf),
     FromStorage(SecureBackend),
+    //////// 0L ////////
+    /// The latest epoch change waypoint of the chain, fetched on start from the JSON-RPC
+    /// `endpoints` of fullnodes and taken once `min_agreement` of them return it. A node offline
+    /// for many epochs rejoins without an operator pasting a recent waypoint. The epoch changes
+    /// are verified from `genesis_waypoint`, which also bootstraps an empty db.
+    FromRpc {
+        endpoints: Vec<String>,
+        min_agreement: usize,
+        genesis_waypoint: Waypoint,
+        /// The fetched waypoint, set by the node on start
+        #[serde(skip)]
+        latest: Option<Waypoint>,
+    },
     None,
 }
 
@@ -145,6 +199,10 @@ impl WaypointConfig {
                     .value;
                 Some(waypoint)
             }
+            //////// 0L ////////
+            WaypointConfig::FromRpc { latest, .. } => {
+                Some(latest.expect("The from_rpc waypoint is fetched by the node on start"))
+            }
             WaypointConfig::None => None,
         };
         waypoint.expect("waypoint should be present")
@@ -159,6 +217,10 @@ impl WaypointConfig {
                     .expect("Unable to read waypoint")
                     .value
             }
+            //////// 0L ////////
+            WaypointConfig::FromRpc {
+                genesis_waypoint, ..
+            } => *genesis_waypoint,
             _ => self.waypoint(),
         }
     }
@@ -222,6 +284,7 @@ impl NodeConfig {
         self.consensus.set_data_dir(data_dir.clone());
         self.execution.set_data_dir(data_dir.clone());
         self.metrics.set_data_dir(data_dir.clone());
+        self.peer_reputation.set_data_dir(data_dir.clone()); //////// 0L ////////
         self.storage.set_data_dir(data_dir);
     }
 
@@ -230,9 +293,13 @@ impl NodeConfig {
     /// Paths used in the config are either absolute or relative to the config location
     pub fn load<P: AsRef<Path>>(input_path: P) -> Result<Self, Error> {
         let mut config = Self::load_config(&input_path)?;
+        config.validate()?; //////// 0L ////////
 
         let input_dir = RootPath::new(input_path);
         config.execution.load(&input_dir)?;
+        for chain in &mut config.chains {
+            chain.execution.load(&input_dir)?; //////// 0L ////////
+        }
 
         let mut config = config.validate_network_configs()?;
         config.set_data_dir(config.data_dir().to_path_buf());
@@ -241,7 +308,7 @@ impl NodeConfig {
 
     /// Checks `NetworkConfig` setups so that they exist on proper networks
     /// Additionally, handles any strange missing default cases
-    fn validate_network_configs(mut self) -> Result<NodeConfig, Error> {
+    pub(crate) fn validate_network_configs(mut self) -> Result<NodeConfig, Error> {
         if self.base.role.is_validator() {
             invariant(
                 self.validator_network.is_some(),
@@ -270,19 +337,133 @@ impl NodeConfig {
             )?;
             network_ids.insert(network_id.clone());
         }
+        self.upstream.validate()?; //////// 0L ////////
+        self.validate_chains() //////// 0L ////////
+    }
+
+    //////// 0L ////////
+    /// Validates the networks of every chain, and checks that the chains share neither a chain id,
+    /// a data dir nor an address with each other or with this config
+    fn validate_chains(mut self) -> Result<NodeConfig, Error> {
+        let mut chains = std::mem::take(&mut self.chains);
+        for chain in &mut chains {
+            // Validating the networks may generate their identity, which has to be kept
+            let config = self.chain_config(chain).validate_network_configs()?;
+            chain.validator_network = config.validator_network;
+            chain.full_node_networks = config.full_node_networks;
+        }
+        self.chains = chains;
+
+        let mut chain_ids = HashSet::new();
+        let mut data_dirs = HashSet::new();
+        let mut addresses = HashSet::new();
+        data_dirs.insert(self.data_dir().to_path_buf());
+        for config in std::iter::once(self.clone()).chain(
+            self.chain_configs()
+                .into_iter()
+                .map(|(chain_id, config)| {
+                    invariant(
+                        chain_ids.insert(chain_id),
+                        format!("Chain {} is configured twice", chain_id),
+                    )?;
+                    invariant(
+                        data_dirs.insert(config.data_dir().to_path_buf()),
+                        format!(
+                            "Chain {} shares the data dir {}",
+                            chain_id,
+                            config.data_dir().display()
+                        ),
+                    )?;
+                    Ok(config)
+                })
+                .collect::<Result<Vec<_>, Error>>()?,
+        ) {
+            let listen_addresses = config
+                .validator_network
+                .iter()
+                .chain(config.full_node_networks.iter())
+                .map(|network| network.listen_address.to_string());
+            for address in vec![
+                config.json_rpc.address.to_string(),
+                config.storage.address.to_string(),
+                config.storage.backup_service_address.to_string(),
+            ]
+            .into_iter()
+            .chain(listen_addresses)
+            {
+                invariant(
+                    addresses.insert(address.clone()),
+                    format!("Chains share the address {}", address),
+                )?;
+            }
+        }
         Ok(self)
     }
 
+    /// The config of each further chain: this config with the sections of the chain swapped in.
+    /// Process-wide services, like the admin service, are left to this config.
+    pub fn chain_configs(&self) -> Vec<(ChainId, NodeConfig)> {
+        self.chains
+            .iter()
+            .map(|chain| (chain.chain_id, self.chain_config(chain)))
+            .collect()
+    }
+
+    fn chain_config(&self, chain: &ChainConfig) -> NodeConfig {
+        let mut config = self.clone();
+        config.chains = vec![];
+        config.admin_service.enabled = false;
+        config.api.enabled = false;
+        config.health_check.enabled = false;
+        config.backup.target = None;
+        // The ledger versions of the other chains don't line up with the boundary
+        config.upgrade.halt_at_version = None;
+        config.base.role = chain.role;
+        config.base.waypoint = chain.waypoint.clone();
+        config.consensus = chain.consensus.clone();
+        config.execution = chain.execution.clone();
+        config.full_node_networks = chain.full_node_networks.clone();
+        config.json_rpc = chain.json_rpc.clone();
+        config.storage = chain.storage.clone();
+        config.upstream = chain.upstream.clone();
+        config.validator_network = chain.validator_network.clone();
+        config.set_data_dir(chain.data_dir.clone());
+        config
+    }
+
+    //////// 0L ////////
+    /// A digest of the serialized config, to tell whether two nodes run the same config without
+    /// exposing it
+    pub fn fingerprint(&self) -> Result<HashValue, Error> {
+        let serialized =
+            serde_yaml::to_vec(self).map_err(|e| Error::Yaml("fingerprint".to_string(), e))?;
+        Ok(HashValue::sha3_256_of(&serialized))
+    }
+
     pub fn save<P: AsRef<Path>>(&mut self, output_path: P) -> Result<(), Error> {
         let output_dir = RootPath::new(&output_path);
         self.execution.save(&output_dir)?;
+        //////// 0L ////////
+        for chain in &mut self.chains {
+            // Keep the genesis of each chain apart from the default one
+            if chain.execution.genesis.is_some()
+                && chain.execution.genesis_file_location.as_os_str().is_empty()
+            {
+                chain.execution.genesis_file_location =
+                    PathBuf::from(format!("{}.genesis.blob", chain.chain_id));
+            }
+            chain.execution.save(&output_dir)?;
+        }
         // This must be last as calling save on subconfigs may change their fields
         self.save_config(&output_path)?;
         Ok(())
     }
 
     pub fn randomize_ports(&mut self) {
+        self.admin_service.randomize_ports(); //////// 0L ////////
+        self.api.randomize_ports(); //////// 0L ////////
         self.debug_interface.randomize_ports();
+        self.health_check.randomize_ports(); //////// 0L ////////
         self.json_rpc.randomize_ports();
         self.storage.randomize_ports();
 
@@ -293,6 +474,10 @@ impl NodeConfig {
         for network in self.full_node_networks.iter_mut() {
             network.listen_address = crate::utils::get_available_port_in_multiaddr(true);
         }
+
+        for chain in self.chains.iter_mut() {
+            chain.randomize_ports(); //////// 0L ////////
+        }
     }
 
     pub fn random() -> Self {
@@ -465,4 +650,68 @@ mod test {
         SafetyRulesConfig::parse(&contents)
             .unwrap_or_else(|e| panic!("Error in safety_rules.yaml: {}", e));
     }
+
+    #[test]
+    fn verify_fingerprint() {
+        let config = NodeConfig::default_for_validator();
+        assert_eq!(
+            config.fingerprint().unwrap(),
+            config.clone().fingerprint().unwrap()
+        );
+
+        let mut changed = config.clone();
+        changed.admin_service.enabled = true;
+        assert_ne!(
+            config.fingerprint().unwrap(),
+            changed.fingerprint().unwrap()
+        );
+    }
+
+    #[test]
+    fn verify_chain_configs() {
+        let full_node = NodeConfig::default_for_public_full_node();
+        let mut chain = ChainConfig {
+            chain_id: ChainId::new(42),
+            data_dir: PathBuf::from("/opt/diem/data/42"),
+            role: RoleType::FullNode,
+            waypoint: WaypointConfig::None,
+            consensus: ConsensusConfig::default(),
+            execution: ExecutionConfig::default(),
+            full_node_networks: full_node.full_node_networks.clone(),
+            json_rpc: JsonRpcConfig::default(),
+            storage: StorageConfig::default(),
+            upstream: full_node.upstream,
+            validator_network: None,
+        };
+        chain.randomize_ports();
+
+        let mut config = NodeConfig::default_for_validator();
+        config.chains.push(chain.clone());
+        let config = config.validate_network_configs().unwrap();
+        let chains = config.chain_configs();
+        assert_eq!(chains.len(), 1);
+        let (chain_id, chain_config) = &chains[0];
+        assert_eq!(*chain_id, ChainId::new(42));
+        assert_eq!(chain_config.base.role, RoleType::FullNode);
+        assert_eq!(chain_config.data_dir(), chain.data_dir.as_path());
+        assert!(chain_config.validator_network.is_none());
+        assert!(chain_config.chains.is_empty());
+        assert_eq!(chain_config.mempool, config.mempool);
+
+        // Chains cannot share storage or ports
+        let mut shared_dir = config.clone();
+        shared_dir.chains[0].data_dir = config.data_dir().to_path_buf();
+        assert!(shared_dir.validate_network_configs().is_err());
+
+        let mut shared_port = config.clone();
+        shared_port.chains[0].json_rpc = config.json_rpc.clone();
+        assert!(shared_port.validate_network_configs().is_err());
+
+        let mut same_chain = config.clone();
+        let mut twin = chain;
+        twin.data_dir = PathBuf::from("/opt/diem/data/twin");
+        twin.randomize_ports();
+        same_chain.chains.push(twin);
+        assert!(same_chain.validate_network_configs().is_err());
+    }
 }
@@ -13,6 +13,7 @@ use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
 use diem_types::{
     network_address::NetworkAddress, transaction::authenticator::AuthenticationKey, PeerId,
 };
+use ipnet::IpNet;
 use rand::{
     rngs::{OsRng, StdRng},
     Rng, SeedableRng,
@@ -20,9 +21,13 @@ use rand::{
 use serde::{Deserialize, Serialize};
 use short_hex_str::AsShortHexStr;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryFrom,
+    fmt,
+    net::IpAddr,
+    str::FromStr,
     string::ToString,
+    time::Duration,
 };
 
 // TODO: We could possibly move these constants somewhere else, but since they are defaults for the
@@ -43,6 +48,11 @@ pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
 pub const CONNECTION_BACKOFF_BASE: u64 = 2;
 pub const IP_BYTE_BUCKET_RATE: usize = 102400 /* 100 KiB */;
 pub const IP_BYTE_BUCKET_SIZE: usize = IP_BYTE_BUCKET_RATE;
+//////// 0L ////////
+pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
+pub const MAX_CONCURRENT_INBOUND_RPCS: u32 = 100;
+pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
+pub const WRITE_QUEUE_SIZE: usize = 1024;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
@@ -95,6 +105,40 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     // Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    //////// 0L ////////
+    // Quality of service of this network relative to the other networks of the node
+    pub qos: Option<QosConfig>,
+    // Per role limits and eviction order of inbound peers, otherwise only unknown inbound peers
+    // are limited, by `max_inbound_connections`
+    pub eviction_policy: Option<PeerEvictionPolicy>,
+    // Serve as a rendezvous peer for nodes with `DiscoveryMethod::Relay`
+    pub rendezvous: Option<RendezvousConfig>,
+    // Only these peers may connect, by peer id or IP CIDR, any peer if empty. Dialed peers, e.g.
+    // seeds, are not restricted
+    pub allowed_peers: Vec<PeerMatcher>,
+    // These peers may never connect nor be dialed, by peer id or IP CIDR, even if allowed
+    pub denied_peers: Vec<PeerMatcher>,
+    // Dial peers through this proxy, rather than the `https_proxy` of the environment
+    pub outbound_proxy: Option<OutboundProxyConfig>,
+    // `host:port` of the Tor SOCKS port `/onion3/` peers are dialed through, e.g. 127.0.0.1:9050
+    pub tor_proxy: Option<String>,
+    // The transport connections are made over, with `quic` the port of addresses is a UDP port
+    pub transport: NetworkTransport,
+    // How the messages of the protocols share each connection
+    pub multiplex: MultiplexConfig,
+    // The address registered with discovery instead of `listen_address`, for a node behind NAT
+    pub advertised_address: Option<AdvertisedAddress>,
+    // A second address listened on, of the other ip version than `listen_address`, for a
+    // dual-stack node, e.g. `/ip6/::/tcp/6180` along with `/ip4/0.0.0.0/tcp/6180`
+    pub dual_stack_address: Option<NetworkAddress>,
+    // What the handshake does with a peer of another chain or network
+    pub handshake_enforcement: HandshakeEnforcement,
+    // Roles pinned to peers, e.g. a partner node as `PreferredUpstream`, taking precedence over
+    // the roles discovery assigns them
+    pub peer_roles: HashMap<PeerId, PeerRole>,
+    // Tells apart the public networks of a node running several, e.g. one open to anyone and one
+    // for partner peers, in logs, metrics and the admin service
+    pub instance: Option<String>,
 }
 
 impl Default for NetworkConfig {
@@ -128,6 +172,20 @@ impl NetworkConfig {
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            qos: None,                                           //////// 0L ////////
+            eviction_policy: None,                               //////// 0L ////////
+            rendezvous: None,                                    //////// 0L ////////
+            allowed_peers: vec![],                               //////// 0L ////////
+            denied_peers: vec![],                                //////// 0L ////////
+            outbound_proxy: None,                                //////// 0L ////////
+            tor_proxy: None,                                     //////// 0L ////////
+            transport: NetworkTransport::Tcp,                    //////// 0L ////////
+            multiplex: MultiplexConfig::default(),               //////// 0L ////////
+            advertised_address: None,                            //////// 0L ////////
+            dual_stack_address: None,                            //////// 0L ////////
+            handshake_enforcement: HandshakeEnforcement::Strict, //////// 0L ////////
+            peer_roles: HashMap::new(),                          //////// 0L ////////
+            instance: None,                                      //////// 0L ////////
         };
         config.prepare_identity();
         config
@@ -195,6 +253,62 @@ impl NetworkConfig {
         Ok(())
     }
 
+    //////// 0L ////////
+    /// Inbound rate limit, from the QoS config if it sets one
+    pub fn inbound_rate_limit(&self) -> Option<RateLimitConfig> {
+        self.qos
+            .as_ref()
+            .and_then(|qos| qos.inbound_rate_limit)
+            .or(self.inbound_rate_limit_config)
+    }
+
+    /// Outbound rate limit, from the QoS config if it sets one
+    pub fn outbound_rate_limit(&self) -> Option<RateLimitConfig> {
+        self.qos
+            .as_ref()
+            .and_then(|qos| qos.outbound_rate_limit)
+            .or(self.outbound_rate_limit_config)
+    }
+
+    /// Names the network and its instance, e.g. `Public/partners`
+    pub fn label(&self) -> String {
+        self.network_id.label(self.instance.as_deref())
+    }
+
+    /// The peers allowed to connect to this network
+    pub fn peer_access(&self) -> PeerAccess {
+        PeerAccess {
+            allowed: self.allowed_peers.clone(),
+            denied: self.denied_peers.clone(),
+        }
+    }
+
+    /// Inbound connection limit, capped by the QoS connection limit
+    pub fn inbound_connection_limit(&self) -> usize {
+        self.cap_connections(self.max_inbound_connections)
+    }
+
+    /// Outbound connection limit, capped by the QoS connection limit
+    pub fn outbound_connection_limit(&self) -> usize {
+        self.cap_connections(self.max_outbound_connections)
+    }
+
+    fn cap_connections(&self, limit: usize) -> usize {
+        let max = self
+            .qos
+            .as_ref()
+            .and_then(|qos| qos.max_concurrent_connections);
+        max.map_or(limit, |max| limit.min(max))
+    }
+
+    /// Priority of the network's traffic, if QoS is configured
+    pub fn priority(&self) -> Option<PriorityClass> {
+        self.qos.as_ref().map(|qos| {
+            qos.priority
+                .unwrap_or_else(|| PriorityClass::for_network(&self.network_id))
+        })
+    }
+
     pub fn peer_id(&self) -> PeerId {
         match &self.identity {
             Identity::FromConfig(config) => Some(config.peer_id),
@@ -285,9 +399,172 @@ impl NetworkConfig {
 #[serde(rename_all = "snake_case")]
 pub enum DiscoveryMethod {
     Onchain,
+    Dns(DnsDiscoveryConfig),     //////// 0L ////////
+    Relay(RelayDiscoveryConfig), //////// 0L ////////
     None,
 }
 
+//////// 0L ////////
+/// Seeds listed in the DNS records of `domain`, so that operators rotate seed hosts by editing
+/// records rather than shipping new configs. Each TXT record of the domain holds a network address,
+/// optionally prefixed with the peer id and `@`. Each SRV record of `_diem._tcp.<domain>` points to
+/// a host and port, with the noise key of the host in a TXT record of its own, as
+/// `ln-noise-ik=<hex>`. The records are looked up again once their TTL expires, within the
+/// refresh bounds.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DnsDiscoveryConfig {
+    pub domain: String,
+    /// Shortest time between lookups, however short the TTL of the records
+    pub min_refresh_interval_secs: u64,
+    /// Longest time between lookups, however long the TTL of the records
+    pub max_refresh_interval_secs: u64,
+}
+
+impl Default for DnsDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            domain: String::new(),
+            min_refresh_interval_secs: 60,
+            max_refresh_interval_secs: 3600,
+        }
+    }
+}
+
+impl DnsDiscoveryConfig {
+    /// Time until the next lookup, given the shortest TTL of the records found
+    pub fn refresh_interval(&self, ttl: Option<Duration>) -> Duration {
+        let min = Duration::from_secs(self.min_refresh_interval_secs);
+        let max = Duration::from_secs(self.max_refresh_interval_secs);
+        ttl.map_or(max, |ttl| ttl.min(max).max(min))
+    }
+}
+
+//////// 0L ////////
+/// Discovery through rendezvous peers, for a node behind NAT that cannot accept inbound
+/// connections. The node dials the rendezvous peers, registers the addresses it is dialable at, if
+/// any, and learns the dialable peers registered by others.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RelayDiscoveryConfig {
+    /// The rendezvous peers, dialed as seeds
+    pub rendezvous: PeerSet,
+    /// Addresses advertised through the rendezvous, e.g. a port forwarded by the router
+    pub advertised_addresses: Vec<NetworkAddress>,
+    /// Time between registrations, which also refresh the dialable peers
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for RelayDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            rendezvous: PeerSet::default(),
+            advertised_addresses: vec![],
+            refresh_interval_secs: 60,
+        }
+    }
+}
+
+/// Serves as a rendezvous peer of the network, see `RelayDiscoveryConfig`
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RendezvousConfig {
+    /// Registrations kept, the oldest are dropped beyond it
+    pub max_registrations: usize,
+    /// Registrations expire unless renewed within this time
+    pub registration_ttl_secs: u64,
+}
+
+impl Default for RendezvousConfig {
+    fn default() -> Self {
+        Self {
+            max_registrations: 1000,
+            registration_ttl_secs: 300,
+        }
+    }
+}
+
+/// The address a node behind NAT is dialable at, registered with discovery instead of its listen
+/// address. Either address holds a host and port only, the noise key and handshake of the node are
+/// appended to it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdvertisedAddress {
+    /// A fixed address, e.g. of a port forwarded by the router
+    Address(NetworkAddress),
+    /// The external ip of the node, on the port of its listen address, kept refreshed
+    Auto(AutoAddressConfig),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoAddressConfig {
+    pub method: ExternalAddressMethod,
+    /// Time between lookups of the external ip, and renewals of the port mapping
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for AutoAddressConfig {
+    fn default() -> Self {
+        Self {
+            method: ExternalAddressMethod::Stun(vec![
+                "stun.l.google.com:19302".to_string(),
+                "stun.cloudflare.com:3478".to_string(),
+            ]),
+            refresh_interval_secs: 300,
+        }
+    }
+}
+
+/// How the external ip is found
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalAddressMethod {
+    /// Asks the `host:port` of STUN servers, in order, the address they see the node at. The
+    /// router has to forward the listen port.
+    Stun(Vec<String>),
+    /// Asks the UPnP gateway of the local network for its external ip, and to forward the listen
+    /// port to the node
+    Upnp,
+}
+
+/// A proxy the outbound connections of a network go through, e.g. to reach the seed peers from a
+/// restricted network. `address` is the `host:port` of the proxy.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum OutboundProxyConfig {
+    /// A SOCKS5 proxy without authentication, resolving the dns names of peers itself
+    Socks5 { address: String },
+    /// An HTTP proxy supporting the CONNECT method
+    HttpConnect { address: String },
+}
+
+impl OutboundProxyConfig {
+    pub fn address(&self) -> &str {
+        match self {
+            OutboundProxyConfig::Socks5 { address }
+            | OutboundProxyConfig::HttpConnect { address } => address,
+        }
+    }
+}
+
+/// The transport of the connections of a network, under the noise handshake. Every peer of a
+/// network must use the same one.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkTransport {
+    Tcp,
+    /// Streams over QUIC, with TLS keys derived from the noise identity. Spares lossy links the
+    /// head-of-line blocking of TCP and takes fewer round trips to connect.
+    Quic,
+}
+
+impl Default for NetworkTransport {
+    fn default() -> Self {
+        NetworkTransport::Tcp
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum Identity {
@@ -352,6 +629,260 @@ impl Default for RateLimitConfig {
     }
 }
 
+//////// 0L ////////
+/// The limits of the RPCs and messages multiplexed over each connection of a network. Raise them
+/// when protocols with heavy traffic, e.g. state sync and mempool during catch-up, stall each other
+/// on the same connection. Connections are kept alive by the pings of `ping_interval_ms`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MultiplexConfig {
+    /// RPCs from the peer handled at once, more are refused
+    pub max_concurrent_inbound_rpcs: u32,
+    /// RPCs to the peer awaiting their response at once, more are refused
+    pub max_concurrent_outbound_rpcs: u32,
+    /// Time to handle an RPC from the peer before it's cut off
+    pub inbound_rpc_timeout_ms: u64,
+    /// Messages waiting to be written to the connection, before senders wait
+    pub write_queue_size: usize,
+}
+
+impl Default for MultiplexConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_inbound_rpcs: MAX_CONCURRENT_INBOUND_RPCS,
+            max_concurrent_outbound_rpcs: MAX_CONCURRENT_OUTBOUND_RPCS,
+            inbound_rpc_timeout_ms: INBOUND_RPC_TIMEOUT_MS,
+            write_queue_size: WRITE_QUEUE_SIZE,
+        }
+    }
+}
+
+/// Quality of service of a network. Lets a node running several networks, e.g., a validator with
+/// its VFN and public networks, favor the validator network's traffic.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QosConfig {
+    /// Overrides `inbound_rate_limit_config`
+    pub inbound_rate_limit: Option<RateLimitConfig>,
+    /// Overrides `outbound_rate_limit_config`
+    pub outbound_rate_limit: Option<RateLimitConfig>,
+    /// Caps both `max_inbound_connections` and `max_outbound_connections`
+    pub max_concurrent_connections: Option<usize>,
+    /// Defaults to the priority of the network id, see `PriorityClass::for_network`
+    pub priority: Option<PriorityClass>,
+}
+
+/// Share of the host given to a network's message processing. Each network runs on its own
+/// runtime, which gets a number of worker threads according to its priority.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityClass {
+    High,
+    Normal,
+    Low,
+}
+
+impl PriorityClass {
+    /// Validator traffic first, then private networks such as the VFN network, then public
+    pub fn for_network(network_id: &NetworkId) -> Self {
+        match network_id {
+            NetworkId::Validator => PriorityClass::High,
+            NetworkId::Private(_) => PriorityClass::Normal,
+            NetworkId::Public => PriorityClass::Low,
+        }
+    }
+
+    /// Worker threads of the network runtime on a host with `cpus` cores: all of them for high
+    /// priority, half for normal and a quarter for low, at least one
+    pub fn worker_threads(&self, cpus: usize) -> usize {
+        let threads = match self {
+            PriorityClass::High => cpus,
+            PriorityClass::Normal => cpus / 2,
+            PriorityClass::Low => cpus / 4,
+        };
+        threads.max(1)
+    }
+}
+
+/// Inbound peers of a network, by role: the maximum of each role, and the roles evicted to make
+/// room for a more trusted peer once `max_inbound_connections` is reached. Keeps public fullnodes
+/// from crowding out the VFN connections of a network under load.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerEvictionPolicy {
+    /// Maximum inbound peers of a role, roles not listed are only bound by the network limit
+    pub max_inbound_per_role: BTreeMap<PeerRole, usize>,
+    /// Roles evicted first. A peer only evicts peers of a role listed before its own, and roles
+    /// not listed are never evicted nor rejected for the network limit.
+    pub eviction_order: Vec<PeerRole>,
+}
+
+impl Default for PeerEvictionPolicy {
+    fn default() -> Self {
+        Self {
+            max_inbound_per_role: BTreeMap::new(),
+            eviction_order: vec![
+                PeerRole::Unknown,
+                PeerRole::Known,
+                PeerRole::Downstream,
+                PeerRole::ValidatorFullNode,
+            ],
+        }
+    }
+}
+
+/// What becomes of an inbound connection under a `PeerEvictionPolicy`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InboundAdmission<T> {
+    Accept,
+    /// Accepted in place of this inbound peer
+    Evict(T),
+    Reject,
+}
+
+impl PeerEvictionPolicy {
+    /// Admits an inbound peer of `role`, given the roles of the inbound peers already connected
+    /// and the inbound limit of the network
+    pub fn admit<T: Clone>(
+        &self,
+        role: PeerRole,
+        inbound: &[(T, PeerRole)],
+        limit: usize,
+    ) -> InboundAdmission<T> {
+        if let Some(max) = self.max_inbound_per_role.get(&role) {
+            if inbound.iter().filter(|(_, other)| *other == role).count() >= *max {
+                return InboundAdmission::Reject;
+            }
+        }
+        if inbound.len() < limit {
+            return InboundAdmission::Accept;
+        }
+
+        let rank = self.eviction_order.iter().position(|other| *other == role);
+        let evictable = &self.eviction_order[..rank.unwrap_or(self.eviction_order.len())];
+        let evicted = evictable.iter().find_map(|evicted_role| {
+            inbound
+                .iter()
+                .find(|(_, other)| other == evicted_role)
+                .map(|(peer, _)| peer.clone())
+        });
+        match (evicted, rank) {
+            (Some(peer), _) => InboundAdmission::Evict(peer),
+            (None, Some(_)) => InboundAdmission::Reject,
+            (None, None) => InboundAdmission::Accept,
+        }
+    }
+}
+
+/// A peer of an allow or deny list: its peer id, or a CIDR block, or a single IP, holding its
+/// address. Written as a string, e.g. `10.0.0.0/8` or the hex of a peer id.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PeerMatcher {
+    PeerId(PeerId),
+    Ip(IpNet),
+}
+
+impl PeerMatcher {
+    pub fn matches(&self, peer_id: Option<PeerId>, ip: Option<IpAddr>) -> bool {
+        match self {
+            PeerMatcher::PeerId(id) => peer_id == Some(*id),
+            PeerMatcher::Ip(net) => ip.map_or(false, |ip| net.contains(&ip)),
+        }
+    }
+}
+
+impl FromStr for PeerMatcher {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(net) = s.parse::<IpNet>() {
+            return Ok(PeerMatcher::Ip(net));
+        }
+        if let Ok(ip) = s.parse::<IpAddr>() {
+            return Ok(PeerMatcher::Ip(ip.into()));
+        }
+        PeerId::from_hex_literal(s)
+            .or_else(|_| PeerId::from_hex(s))
+            .map(PeerMatcher::PeerId)
+            .map_err(|_| {
+                Error::InvariantViolation(format!("Neither a peer id nor an IP CIDR: {}", s))
+            })
+    }
+}
+
+impl TryFrom<String> for PeerMatcher {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for PeerMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerMatcher::PeerId(peer_id) => write!(f, "{}", peer_id),
+            PeerMatcher::Ip(net) => write!(f, "{}", net),
+        }
+    }
+}
+
+impl From<PeerMatcher> for String {
+    fn from(matcher: PeerMatcher) -> Self {
+        matcher.to_string()
+    }
+}
+
+//////// 0L ////////
+/// What the handshake does with a peer that reports another chain id or network id
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandshakeEnforcement {
+    /// Refuses the peer
+    Strict,
+    /// Logs a warning and connects anyway, e.g. for a staging environment bridging two test chains
+    WarnOnly,
+}
+
+impl Default for HandshakeEnforcement {
+    fn default() -> Self {
+        HandshakeEnforcement::Strict
+    }
+}
+
+/// The allow and deny lists of a network, checked as connections are upgraded
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PeerAccess {
+    /// Every peer is allowed if empty
+    pub allowed: Vec<PeerMatcher>,
+    pub denied: Vec<PeerMatcher>,
+}
+
+impl PeerAccess {
+    /// Whether a connection from `ip` is refused before the peer authenticates
+    pub fn denies_ip(&self, ip: Option<IpAddr>) -> bool {
+        self.denied.iter().any(|matcher| matcher.matches(None, ip))
+    }
+
+    /// Whether the peer, at `ip` if known, is on the deny list
+    pub fn denies(&self, peer_id: PeerId, ip: Option<IpAddr>) -> bool {
+        self.denied
+            .iter()
+            .any(|matcher| matcher.matches(Some(peer_id), ip))
+    }
+
+    /// Whether the peer may connect, from `ip` if known
+    pub fn permits(&self, peer_id: PeerId, ip: Option<IpAddr>) -> bool {
+        !self.denies(peer_id, ip)
+            && (self.allowed.is_empty()
+                || self
+                    .allowed
+                    .iter()
+                    .any(|matcher| matcher.matches(Some(peer_id), ip)))
+    }
+}
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
@@ -385,8 +916,31 @@ impl Default for PeerRole {
     }
 }
 
+//////// 0L ////////
+impl std::str::FromStr for PeerRole {
+    type Err = Error;
+
+    /// Parses the name of a role, ignoring case, e.g. `validator_full_node` or `ValidatorFullNode`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.replace(|c| c == '_' || c == '-', "");
+        [
+            PeerRole::Validator,
+            PeerRole::PreferredUpstream,
+            PeerRole::Upstream,
+            PeerRole::ValidatorFullNode,
+            PeerRole::Downstream,
+            PeerRole::Known,
+            PeerRole::Unknown,
+        ]
+        .iter()
+        .copied()
+        .find(|role| format!("{:?}", role).eq_ignore_ascii_case(&name))
+        .ok_or_else(|| Error::InvariantViolation(format!("Unknown peer role: {}", s)))
+    }
+}
+
 /// Represents a single seed configuration for a seed peer
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)] //////// 0L ////////
 #[serde(default)]
 pub struct Peer {
     pub addresses: Vec<NetworkAddress>,
@@ -435,3 +989,181 @@ impl Peer {
         Peer::new(addresses, keys, role)
     }
 }
+
+//////// 0L ////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_qos_limits() {
+        let mut config = NetworkConfig::network_with_id(NetworkId::Public);
+        config.inbound_rate_limit_config = Some(RateLimitConfig::default());
+        assert_eq!(config.priority(), None);
+        assert_eq!(config.inbound_connection_limit(), MAX_INBOUND_CONNECTIONS);
+
+        let rate_limit = RateLimitConfig {
+            ip_byte_bucket_rate: 1024,
+            ..RateLimitConfig::default()
+        };
+        config.qos = Some(QosConfig {
+            inbound_rate_limit: Some(rate_limit),
+            max_concurrent_connections: Some(10),
+            ..QosConfig::default()
+        });
+        assert_eq!(config.inbound_rate_limit(), Some(rate_limit));
+        assert_eq!(config.outbound_rate_limit(), None);
+        assert_eq!(config.inbound_connection_limit(), 10);
+        assert_eq!(config.outbound_connection_limit(), 10);
+        assert_eq!(config.priority(), Some(PriorityClass::Low));
+
+        assert!(PriorityClass::for_network(&NetworkId::Validator) < PriorityClass::Low);
+        assert_eq!(PriorityClass::Low.worker_threads(2), 1);
+        assert_eq!(PriorityClass::High.worker_threads(8), 8);
+    }
+
+    #[test]
+    fn test_dns_discovery_config() {
+        let yaml = "discovery_method:\n  dns:\n    domain: seeds.example.com\n";
+        let config: NetworkConfig = serde_yaml::from_str(yaml).unwrap();
+        let dns = match &config.discovery_method {
+            DiscoveryMethod::Dns(dns) => dns,
+            method => panic!("Unexpected discovery method {:?}", method),
+        };
+        assert_eq!(dns.domain, "seeds.example.com");
+        assert_eq!(dns.min_refresh_interval_secs, 60);
+
+        assert_eq!(dns.refresh_interval(None), Duration::from_secs(3600));
+        assert_eq!(
+            dns.refresh_interval(Some(Duration::from_secs(5))),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            dns.refresh_interval(Some(Duration::from_secs(300))),
+            Duration::from_secs(300)
+        );
+        assert_eq!(
+            dns.refresh_interval(Some(Duration::from_secs(86_400))),
+            Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn test_relay_discovery_config() {
+        let yaml = "discovery_method:\n  relay:\n    advertised_addresses: []\nrendezvous: {}\n";
+        let config: NetworkConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.discovery_method,
+            DiscoveryMethod::Relay(RelayDiscoveryConfig::default())
+        );
+        assert_eq!(config.rendezvous, Some(RendezvousConfig::default()));
+    }
+
+    #[test]
+    fn test_advertised_address_config() {
+        let yaml = "advertised_address:\n  address: /ip4/1.2.3.4/tcp/6180\n";
+        let config: NetworkConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.advertised_address,
+            Some(AdvertisedAddress::Address(
+                "/ip4/1.2.3.4/tcp/6180".parse().unwrap()
+            ))
+        );
+
+        let yaml = "advertised_address:\n  auto:\n    method: upnp\n";
+        let config: NetworkConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.advertised_address,
+            Some(AdvertisedAddress::Auto(AutoAddressConfig {
+                method: ExternalAddressMethod::Upnp,
+                refresh_interval_secs: 300,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_eviction_policy() {
+        let mut policy = PeerEvictionPolicy::default();
+        policy
+            .max_inbound_per_role
+            .insert(PeerRole::ValidatorFullNode, 2);
+        let inbound = vec![
+            (1, PeerRole::ValidatorFullNode),
+            (2, PeerRole::Known),
+            (3, PeerRole::Unknown),
+        ];
+
+        assert_eq!(
+            policy.admit(PeerRole::Unknown, &inbound, 4),
+            InboundAdmission::Accept
+        );
+        // Once full, unknown peers go first, and only for more trusted peers
+        assert_eq!(
+            policy.admit(PeerRole::ValidatorFullNode, &inbound, 3),
+            InboundAdmission::Evict(3)
+        );
+        assert_eq!(
+            policy.admit(PeerRole::Known, &inbound[..2], 2),
+            InboundAdmission::Reject
+        );
+        assert_eq!(
+            policy.admit(PeerRole::ValidatorFullNode, &inbound[..2], 2),
+            InboundAdmission::Evict(2)
+        );
+        assert_eq!(
+            policy.admit(PeerRole::Unknown, &inbound, 3),
+            InboundAdmission::Reject
+        );
+        // Unlisted roles are never rejected for the network limit
+        assert_eq!(
+            policy.admit(PeerRole::Validator, &[(4, PeerRole::Validator)], 1),
+            InboundAdmission::Accept
+        );
+
+        let inbound = vec![
+            (1, PeerRole::ValidatorFullNode),
+            (2, PeerRole::ValidatorFullNode),
+        ];
+        assert_eq!(
+            policy.admit(PeerRole::ValidatorFullNode, &inbound, 10),
+            InboundAdmission::Reject
+        );
+
+        let yaml = "max_inbound_per_role:\n  Unknown: 50\n";
+        let policy: PeerEvictionPolicy = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(policy.max_inbound_per_role[&PeerRole::Unknown], 50);
+        assert_eq!(
+            policy.eviction_order,
+            PeerEvictionPolicy::default().eviction_order
+        );
+    }
+
+    #[test]
+    fn test_peer_access() {
+        let allowed = PeerId::random();
+        let denied = PeerId::random();
+        let yaml = format!(
+            "allowed_peers: ['{}', 10.0.0.0/8]\ndenied_peers: ['{}', 10.0.0.1]\n",
+            allowed, denied
+        );
+        let config: NetworkConfig = serde_yaml::from_str(&yaml).unwrap();
+        let access = config.peer_access();
+        let ip = |s: &str| Some(s.parse().unwrap());
+
+        assert!(access.permits(allowed, None));
+        assert!(access.permits(PeerId::random(), ip("10.1.2.3")));
+        assert!(!access.permits(PeerId::random(), ip("192.168.0.1")));
+        assert!(!access.permits(PeerId::random(), None));
+        // Denials win over allowances
+        assert!(!access.permits(denied, ip("10.1.2.3")));
+        assert!(!access.permits(allowed, ip("10.0.0.1")));
+        assert!(access.denies_ip(ip("10.0.0.1")));
+        assert!(!access.denies_ip(ip("10.0.0.2")));
+
+        assert!(PeerAccess::default().permits(denied, None));
+        assert!("not a peer".parse::<PeerMatcher>().is_err());
+        let yaml = serde_yaml::to_string(&config.denied_peers).unwrap();
+        let denied_peers: Vec<PeerMatcher> = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(denied_peers, config.denied_peers);
+    }
+}
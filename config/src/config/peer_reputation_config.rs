@@ -0,0 +1,43 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::config::{OnDiskStorageConfig, SecureBackend};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// How misbehaving peers are banned, and where their reputation is kept across restarts
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerReputationConfig {
+    pub backend: SecureBackend,
+    /// Misbehaviors of a peer within the violation window that get it banned
+    pub violations_per_ban: u32,
+    /// Misbehaviors older than this are forgotten
+    pub violation_window_secs: u64,
+    /// Length of the first ban of a peer, each next ban lasts twice the previous one
+    pub base_ban_secs: u64,
+    pub max_ban_secs: u64,
+}
+
+impl Default for PeerReputationConfig {
+    fn default() -> Self {
+        let mut storage = OnDiskStorageConfig::default();
+        storage.path = PathBuf::from("peer_reputation.json");
+        Self {
+            backend: SecureBackend::OnDiskStorage(storage),
+            violations_per_ban: 10,
+            violation_window_secs: 3_600,
+            base_ban_secs: 60,
+            max_ban_secs: 86_400,
+        }
+    }
+}
+
+impl PeerReputationConfig {
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        if let SecureBackend::OnDiskStorage(backend) = &mut self.backend {
+            backend.set_data_dir(data_dir);
+        }
+    }
+}
@@ -26,6 +26,8 @@ pub struct SafetyRulesConfig {
     // Read/Write/Connect networking operation timeout in milliseconds.
     pub network_timeout_ms: u64,
     pub enable_cached_safety_data: bool,
+    //////// 0L ////////
+    pub failover: Option<FailoverConfig>,
 }
 
 impl Default for SafetyRulesConfig {
@@ -40,6 +42,7 @@ impl Default for SafetyRulesConfig {
             // Default value of 30 seconds for a timeout
             network_timeout_ms: 30_000,
             enable_cached_safety_data: true,
+            failover: None,
         }
     }
 }
@@ -52,6 +55,56 @@ impl SafetyRulesConfig {
     }
 }
 
+//////// 0L ////////
+/// Active/standby failover between two validator instances sharing the safety rules `backend`:
+/// an instance only signs while it holds the lease, which it renews on every signature and which
+/// the standby takes over once it expires. The expiry is written by the clock of one host and read
+/// by that of the other, so the clocks of both hosts have to be kept in sync (e.g. with NTP) within
+/// `max_clock_skew_ms`.
+///
+/// ```yaml
+/// failover:
+///   instance_id: validator-a
+///   lease_duration_ms: 10000
+///   max_clock_skew_ms: 1000
+///   lease:
+///     type: secure_backend
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FailoverConfig {
+    /// Names this instance as the lease holder, distinct between the two instances
+    pub instance_id: String,
+    #[serde(default = "default_lease_duration_ms")]
+    pub lease_duration_ms: u64,
+    /// How far the clocks of the two hosts may be apart. The standby only takes over a lease that
+    /// expired at least this long ago, so a standby with its clock ahead by up to this much can't
+    /// take it while the active instance still signs.
+    #[serde(default = "default_max_clock_skew_ms")]
+    pub max_clock_skew_ms: u64,
+    pub lease: LeaseBackend,
+}
+
+fn default_lease_duration_ms() -> u64 {
+    10_000
+}
+
+fn default_max_clock_skew_ms() -> u64 {
+    1_000
+}
+
+/// Where the failover lease is kept
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum LeaseBackend {
+    /// A file updated under an exclusive file lock. Only safe for two instances on the same host:
+    /// file locks are not reliable on network filesystems such as NFS, where both instances may
+    /// hold the lock at once. A pair on two hosts uses `SecureBackend`.
+    File { path: PathBuf },
+    /// A key of the safety rules backend, which must be Vault with check-and-set enabled
+    SecureBackend,
+}
+
 /// Defines how safety rules should be executed
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -71,6 +124,15 @@ pub enum SafetyRulesService {
 #[serde(deny_unknown_fields)]
 pub struct RemoteService {
     pub server_address: NetworkAddress,
+    //////// 0L ////////
+    /// Secures the connection between the validator and the safety rules process, which can then
+    /// run on a separate host
+    #[serde(default)]
+    pub tls: Option<MutualTlsConfig>,
+    /// The validator checks that a connection idle for longer than this is still alive before
+    /// sending a request over it
+    #[serde(default)]
+    pub health_check_interval_ms: Option<u64>,
 }
 
 impl RemoteService {
@@ -83,6 +145,36 @@ impl RemoteService {
     }
 }
 
+//////// 0L ////////
+/// Mutually authenticated TLS between the validator and the safety rules process: both present a
+/// certificate signed by the CA. The same section is read by both ends, each with its own
+/// certificate and key.
+///
+/// ```yaml
+/// tls:
+///   cert_path: /opt/diem/tls/validator.pem
+///   key_path: /opt/diem/tls/validator_key.pem
+///   ca_cert_path: /opt/diem/tls/ca.pem
+///   server_name: safety-rules
+///   allowed_clients:
+///     - validator
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MutualTlsConfig {
+    pub cert_path: PathBuf,
+    /// PKCS8 or RSA private key of the certificate
+    pub key_path: PathBuf,
+    pub ca_cert_path: PathBuf,
+    /// DNS name the certificate of the safety rules process must be valid for, checked by the
+    /// validator
+    pub server_name: String,
+    /// DNS names of the validators the safety rules process serves, checked against their
+    /// certificates. Any certificate of the CA is served if empty.
+    #[serde(default)]
+    pub allowed_clients: Vec<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct SafetyRulesTestConfig {
     pub author: PeerId,
@@ -2,14 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::config::Error;
+use diem_global_constants::{FULLNODE_NETWORK_KEY, VALIDATOR_NETWORK_KEY, VFN_NETWORK_KEY};
 use diem_secure_storage::{
-    GitHubStorage, InMemoryStorage, Namespaced, OnDiskStorage, Storage, VaultStorage,
+    AwsCredentialSource, AwsKmsClient, GcpCredentialSource, GcpKmsClient, GitHubStorage,
+    InMemoryStorage, KmsClient, KmsStorage, Namespaced, OnDiskStorage, Storage, VaultStorage,
+    YubiHsmStorage,
 };
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::Read,
     path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
 };
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -19,6 +25,10 @@ pub enum SecureBackend {
     InMemoryStorage,
     Vault(VaultConfig),
     OnDiskStorage(OnDiskStorageConfig),
+    //////// 0L ////////
+    #[serde(rename = "yubihsm")]
+    YubiHsm(YubiHsmConfig),
+    Kms(KmsConfig),
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -73,6 +83,204 @@ impl VaultConfig {
     }
 }
 
+//////// 0L ////////
+/// A YubiHSM 2 signing with the keys it holds, they can be referenced as any other backend, e.g.
+/// by safety rules with `export_consensus_key` unset. The values, and the keys that must be
+/// exported such as the network keys, are kept in `storage`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct YubiHsmConfig {
+    /// Address of the yubihsm-connector
+    #[serde(default = "YubiHsmConfig::default_addr")]
+    pub addr: String,
+    #[serde(default = "YubiHsmConfig::default_port")]
+    pub port: u16,
+    #[serde(default = "YubiHsmConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Object id of the authentication key of the session
+    #[serde(default = "YubiHsmConfig::default_auth_key_id")]
+    pub auth_key_id: u16,
+    /// The password of the authentication key
+    pub password: Token,
+    /// The domain of the keys created on the device
+    #[serde(default = "YubiHsmConfig::default_domain")]
+    pub domain: u8,
+    /// Object ids from this one up are taken for new keys
+    #[serde(default = "YubiHsmConfig::default_first_key_id")]
+    pub first_key_id: u16,
+    /// Keys kept in `storage` rather than on the device, as they must be exported
+    #[serde(default = "YubiHsmConfig::default_exportable_keys")]
+    pub exportable_keys: Vec<String>,
+    /// Holds the values, the object ids of the device keys, and the exportable keys
+    pub storage: Box<SecureBackend>,
+    /// A namespace is an optional portion of the path to a key stored within YubiHsmConfig. For
+    /// example, a key, S, without a namespace would be available in S, with a namespace, N, it
+    /// would be in N/S.
+    pub namespace: Option<String>,
+}
+
+impl YubiHsmConfig {
+    /// A device at the default address, with the default authentication key and key ids
+    pub fn new(password: Token, storage: SecureBackend) -> Self {
+        Self {
+            addr: Self::default_addr(),
+            port: Self::default_port(),
+            timeout_ms: Self::default_timeout_ms(),
+            auth_key_id: Self::default_auth_key_id(),
+            password,
+            domain: Self::default_domain(),
+            first_key_id: Self::default_first_key_id(),
+            exportable_keys: Self::default_exportable_keys(),
+            storage: Box::new(storage),
+            namespace: None,
+        }
+    }
+
+    fn default_addr() -> String {
+        "127.0.0.1".into()
+    }
+
+    fn default_port() -> u16 {
+        12345
+    }
+
+    fn default_timeout_ms() -> u64 {
+        5_000
+    }
+
+    fn default_auth_key_id() -> u16 {
+        1
+    }
+
+    fn default_domain() -> u8 {
+        1
+    }
+
+    fn default_first_key_id() -> u16 {
+        0x100
+    }
+
+    /// Noise runs on the network keys in memory
+    fn default_exportable_keys() -> Vec<String> {
+        vec![
+            VALIDATOR_NETWORK_KEY.into(),
+            FULLNODE_NETWORK_KEY.into(),
+            VFN_NETWORK_KEY.into(), //////// 0L ////////
+        ]
+    }
+
+    pub fn is_exportable(&self, key: &str) -> bool {
+        self.exportable_keys
+            .iter()
+            .any(|exportable| exportable == key)
+    }
+}
+
+//////// 0L ////////
+/// A cloud KMS, AWS KMS or GCP Cloud KMS, signing with the Ed25519 keys it holds, so that the
+/// private keys never reach the host. The keys are created in the KMS by the operator, e.g.:
+///
+/// ```yaml
+/// type: kms
+/// provider:
+///   type: aws
+///   region: us-east-1
+///   credentials: instance_metadata
+/// keys:
+///   consensus: "alias/alice-consensus"
+///   owner: "alias/alice-owner"
+/// storage:
+///   type: on_disk_storage
+///   path: key_store.json
+/// ```
+///
+/// The values, and the keys not in `keys` such as the network keys Noise needs in memory, are
+/// kept in `storage`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KmsConfig {
+    pub provider: KmsProvider,
+    /// The KMS key of each key held by the KMS, by key name, e.g., `consensus`
+    pub keys: BTreeMap<String, String>,
+    #[serde(default = "KmsConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Holds the values and the keys not held by the KMS
+    pub storage: Box<SecureBackend>,
+    /// A namespace is an optional portion of the path to a key stored within KmsConfig. For
+    /// example, a key, S, without a namespace would be available in S, with a namespace, N, it
+    /// would be in N/S.
+    pub namespace: Option<String>,
+}
+
+impl KmsConfig {
+    fn default_timeout_ms() -> u64 {
+        5_000
+    }
+
+    pub fn holds(&self, key: &str) -> bool {
+        self.keys.contains_key(key)
+    }
+}
+
+//////// 0L ////////
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum KmsProvider {
+    /// Keys of spec `ECC_NIST_EDWARDS25519`, by key id, alias or ARN, which the credentials may
+    /// `kms:GetPublicKey` and `kms:Sign` with
+    Aws(AwsKmsConfig),
+    /// Key versions of algorithm `EC_SIGN_ED25519`, by resource name, which the service account
+    /// is a `roles/cloudkms.signerVerifier` of
+    Gcp(GcpKmsConfig),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AwsKmsConfig {
+    pub region: String,
+    #[serde(default)]
+    pub credentials: AwsCredentials,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AwsCredentials {
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and, for temporary credentials,
+    /// `AWS_SESSION_TOKEN`
+    Environment,
+    /// The IAM role of the EC2 instance
+    InstanceMetadata,
+}
+
+impl Default for AwsCredentials {
+    fn default() -> Self {
+        AwsCredentials::Environment
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GcpKmsConfig {
+    #[serde(default)]
+    pub credentials: GcpCredentials,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcpCredentials {
+    /// The service account of the GCE instance or GKE workload
+    Metadata,
+    /// An OAuth access token, e.g., from `gcloud auth print-access-token`. It is not refreshed,
+    /// for short-lived processes such as the management tools.
+    AccessToken(Token),
+}
+
+impl Default for GcpCredentials {
+    fn default() -> Self {
+        GcpCredentials::Metadata
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct OnDiskStorageConfig {
@@ -82,10 +290,91 @@ pub struct OnDiskStorageConfig {
     /// example, a key, S, without a namespace would be available in S, with a namespace, N, it
     /// would be in N/S.
     pub namespace: Option<String>,
-    #[serde(skip)]
+    //////// 0L ////////
+    /// Encrypts the file with a key derived from this passphrase. Plaintext files are migrated
+    /// with `diem-genesis-tool key-store`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<Passphrase>,
+    #[serde(skip, default = "default_data_dir")] //////// 0L ////////
     data_dir: PathBuf,
 }
 
+//////// 0L ////////
+/// Passphrases are never stored within this config, only where to find them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Passphrase {
+    /// The name of an environment variable
+    FromEnv(String),
+    /// This is an absolute path and not relative to data_dir
+    FromDisk(PathBuf),
+    /// A program and its arguments printing the passphrase, e.g. to read it from the OS keyring
+    /// with `secret-tool lookup ...` or `security find-generic-password -w ...`
+    FromCommand(Vec<String>),
+}
+
+impl Passphrase {
+    pub fn read_passphrase(&self) -> Result<String, Error> {
+        let passphrase = match self {
+            Passphrase::FromEnv(name) => std::env::var(name).map_err(|_| {
+                Error::InvariantViolation(format!("Passphrase variable {} is not set", name))
+            })?,
+            Passphrase::FromDisk(path) => read_file(path)?,
+            Passphrase::FromCommand(command) => {
+                let (program, args) = command
+                    .split_first()
+                    .ok_or(Error::Missing("passphrase command"))?;
+                let output = Command::new(program)
+                    .args(args)
+                    .output()
+                    .map_err(|e| Error::IO(program.clone(), e))?;
+                if !output.status.success() {
+                    return Err(Error::InvariantViolation(format!(
+                        "Passphrase command {} failed: {}",
+                        program,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                String::from_utf8(output.stdout).map_err(|_| {
+                    Error::InvariantViolation(format!(
+                        "Passphrase command {} printed no text",
+                        program
+                    ))
+                })?
+            }
+        };
+        let passphrase = passphrase.trim_end_matches(&['\r', '\n'][..]);
+        if passphrase.is_empty() {
+            return Err(Error::Missing("passphrase"));
+        }
+        Ok(passphrase.to_string())
+    }
+}
+
+/// Parses `env:NAME`, `file:PATH` or `cmd:PROGRAM ARGS...`, for command lines
+impl FromStr for Passphrase {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || {
+            Error::InvariantViolation(format!(
+                "Invalid passphrase source {}, expected env:NAME, file:PATH or cmd:COMMAND",
+                s
+            ))
+        };
+        let (kind, value) = s.split_at(s.find(':').ok_or_else(invalid)?);
+        let value = &value[1..];
+        match kind {
+            "env" => Ok(Passphrase::FromEnv(value.to_string())),
+            "file" => Ok(Passphrase::FromDisk(PathBuf::from(value))),
+            "cmd" => Ok(Passphrase::FromCommand(
+                value.split_whitespace().map(str::to_string).collect(),
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
 /// Tokens can either be directly within this config or stored somewhere on disk.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -121,11 +410,19 @@ impl Default for OnDiskStorageConfig {
         Self {
             namespace: None,
             path: PathBuf::from("secure_storage.json"),
-            data_dir: PathBuf::from("/opt/diem/data"),
+            passphrase: None, //////// 0L ////////
+            data_dir: default_data_dir(), //////// 0L ////////
         }
     }
 }
 
+//////// 0L ////////
+/// Deserialized configs start out in the default data dir, same as `OnDiskStorageConfig::default`,
+/// so that configs round trip until `set_data_dir` moves them.
+fn default_data_dir() -> PathBuf {
+    PathBuf::from("/opt/diem/data")
+}
+
 impl OnDiskStorageConfig {
     pub fn path(&self) -> PathBuf {
         if self.path.is_relative() {
@@ -171,7 +468,81 @@ impl From<&SecureBackend> for Storage {
             }
             SecureBackend::InMemoryStorage => Storage::from(InMemoryStorage::new()),
             SecureBackend::OnDiskStorage(config) => {
-                let storage = Storage::from(OnDiskStorage::new(config.path()));
+                //////// 0L ////////
+                let storage = Storage::from(match &config.passphrase {
+                    Some(passphrase) => OnDiskStorage::new_encrypted(
+                        config.path(),
+                        passphrase
+                            .read_passphrase()
+                            .expect("Unable to read passphrase"),
+                    ),
+                    None => OnDiskStorage::new(config.path()),
+                });
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            }
+            //////// 0L ////////
+            SecureBackend::YubiHsm(config) => {
+                let password = config.password.read_token().expect("Unable to read token");
+                let client = YubiHsmStorage::connect_http(
+                    config.addr.clone(),
+                    config.port,
+                    config.timeout_ms,
+                    config.auth_key_id,
+                    password.trim_end(),
+                )
+                .expect("Unable to create the YubiHSM client");
+                let storage = Storage::from(
+                    YubiHsmStorage::new(
+                        client,
+                        Storage::from(config.storage.as_ref()),
+                        config.domain,
+                        config.first_key_id,
+                        config.exportable_keys.clone(),
+                    )
+                    .expect("Invalid YubiHSM config"),
+                );
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            }
+            //////// 0L ////////
+            SecureBackend::Kms(config) => {
+                let client: Box<dyn KmsClient> = match &config.provider {
+                    KmsProvider::Aws(aws) => Box::new(AwsKmsClient::new(
+                        aws.region.clone(),
+                        match aws.credentials {
+                            AwsCredentials::Environment => AwsCredentialSource::Environment,
+                            AwsCredentials::InstanceMetadata => {
+                                AwsCredentialSource::InstanceMetadata
+                            }
+                        },
+                        config.timeout_ms,
+                    )),
+                    KmsProvider::Gcp(gcp) => Box::new(GcpKmsClient::new(
+                        match &gcp.credentials {
+                            GcpCredentials::Metadata => GcpCredentialSource::Metadata,
+                            GcpCredentials::AccessToken(token) => GcpCredentialSource::AccessToken(
+                                token
+                                    .read_token()
+                                    .expect("Unable to read token")
+                                    .trim()
+                                    .to_string(),
+                            ),
+                        },
+                        config.timeout_ms,
+                    )),
+                };
+                let storage = Storage::from(KmsStorage::new(
+                    client,
+                    Storage::from(config.storage.as_ref()),
+                    config.keys.clone().into_iter().collect(),
+                ));
                 if let Some(namespace) = &config.namespace {
                     Storage::from(Namespaced::new(namespace, Box::new(storage)))
                 } else {
@@ -309,4 +680,132 @@ vault:
         let config = Token::FromConfig("config_token".to_string());
         assert_eq!("config_token", config.read_token().unwrap());
     }
+
+    //////// 0L ////////
+    #[test]
+    fn test_passphrase_parsing() {
+        let text = r#"
+path: key_store.json
+namespace: ~
+passphrase:
+    from_command: ["secret-tool", "lookup", "service", "diem"]
+        "#;
+        let config: OnDiskStorageConfig = serde_yaml::from_str(text).unwrap();
+        let passphrase = Passphrase::FromCommand(
+            vec!["secret-tool", "lookup", "service", "diem"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        );
+        assert_eq!(config.passphrase, Some(passphrase.clone()));
+        assert_eq!(
+            "cmd:secret-tool lookup service diem"
+                .parse::<Passphrase>()
+                .unwrap(),
+            passphrase
+        );
+        assert_eq!(
+            "file:/passphrase".parse::<Passphrase>().unwrap(),
+            Passphrase::FromDisk(PathBuf::from("/passphrase"))
+        );
+        assert!("passphrase".parse::<Passphrase>().is_err());
+
+        // Plaintext stores serialize as before
+        let text = serde_yaml::to_string(&OnDiskStorageConfig::default()).unwrap();
+        assert!(!text.contains("passphrase"));
+    }
+
+    #[test]
+    fn test_yubihsm_parsing() {
+        let text = r#"
+type: yubihsm
+password:
+    from_disk: /opt/diem/yubihsm_password
+storage:
+    type: on_disk_storage
+    path: key_store.json
+    namespace: ~
+namespace: alice
+        "#;
+        let backend: SecureBackend = serde_yaml::from_str(text).unwrap();
+        let config = match &backend {
+            SecureBackend::YubiHsm(config) => config,
+            backend => panic!("Unexpected backend {:?}", backend),
+        };
+        assert_eq!(config.port, 12345);
+        assert!(config.is_exportable(VALIDATOR_NETWORK_KEY));
+        assert!(!config.is_exportable("consensus"));
+        let text = serde_yaml::to_string(&backend).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<SecureBackend>(&text).unwrap(),
+            backend
+        );
+    }
+
+    #[test]
+    fn test_kms_parsing() {
+        let text = r#"
+type: kms
+provider:
+    type: gcp
+    credentials:
+        access_token:
+            from_disk: /opt/diem/gcp_token
+keys:
+    consensus: projects/p/locations/global/keyRings/r/cryptoKeys/consensus/cryptoKeyVersions/1
+storage:
+    type: on_disk_storage
+    path: key_store.json
+    namespace: ~
+namespace: alice
+        "#;
+        let backend: SecureBackend = serde_yaml::from_str(text).unwrap();
+        let config = match &backend {
+            SecureBackend::Kms(config) => config,
+            backend => panic!("Unexpected backend {:?}", backend),
+        };
+        assert_eq!(config.timeout_ms, 5_000);
+        assert!(config.holds("consensus"));
+        assert!(!config.holds(VALIDATOR_NETWORK_KEY));
+        let text = serde_yaml::to_string(&backend).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<SecureBackend>(&text).unwrap(),
+            backend
+        );
+
+        let text = r#"
+type: kms
+provider:
+    type: aws
+    region: us-east-1
+keys: {}
+storage:
+    type: in_memory_storage
+namespace: ~
+        "#;
+        match serde_yaml::from_str(text).unwrap() {
+            SecureBackend::Kms(KmsConfig {
+                provider: KmsProvider::Aws(aws),
+                ..
+            }) => assert_eq!(aws.credentials, AwsCredentials::Environment),
+            backend => panic!("Unexpected backend {:?}", backend),
+        }
+    }
+
+    #[test]
+    fn test_passphrase_reading() {
+        let temppath = diem_temppath::TempPath::new();
+        temppath.create_as_file().unwrap();
+        let mut file = File::create(temppath.path()).unwrap();
+        file.write_all(b"disk passphrase\n").unwrap();
+
+        let disk = Passphrase::FromDisk(temppath.path().to_path_buf());
+        assert_eq!("disk passphrase", disk.read_passphrase().unwrap());
+
+        let command = Passphrase::FromCommand(vec!["echo".into(), "keyring passphrase".into()]);
+        assert_eq!("keyring passphrase", command.read_passphrase().unwrap());
+
+        let env = Passphrase::FromEnv("DIEM_TEST_UNSET_PASSPHRASE".into());
+        assert!(env.read_passphrase().is_err());
+    }
 }
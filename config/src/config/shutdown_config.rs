@@ -0,0 +1,28 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use serde::{Deserialize, Serialize};
+
+/// How the node stops on SIGTERM or SIGINT: it stops accepting inbound connections, waits for its
+/// mempool broadcasts to be acknowledged and its state sync chunks to be applied, then closes the
+/// storage
+///
+/// ```yaml
+/// shutdown:
+///   drain_period_ms: 60000
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    /// The longest the node waits for the in-flight work, before it closes the storage anyway
+    pub drain_period_ms: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            drain_period_ms: 30_000,
+        }
+    }
+}
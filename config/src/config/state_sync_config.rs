@@ -15,6 +15,10 @@ pub struct StateSyncConfig {
     pub long_poll_timeout_ms: u64,
     // valid maximum chunk limit for sanity check
     pub max_chunk_limit: u64,
+    //////// 0L ////////
+    // Chunks requested at once, each from a different upstream peer, ahead of the synced version.
+    // Chunks arriving ahead of the synced version wait to be applied in order. 1 syncs serially.
+    pub max_parallel_chunk_requests: u64,
     // valid maximum timeout limit for sanity check
     // This timeout applies to the process_request_for_target_and_highest
     // if the chunk cannot be applied now, then insert it in a subscription to appply. The subscription expires at max_timeout_ms
@@ -41,6 +45,7 @@ impl Default for StateSyncConfig {
             client_commit_timeout_ms: 5_000,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1_000,
+            max_parallel_chunk_requests: 4, //////// 0L ////////
             max_timeout_ms: 1_200_000,
             mempool_commit_timeout_ms: 5_000,
             multicast_timeout_ms: 30_000,
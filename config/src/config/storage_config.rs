@@ -1,11 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::utils;
+use crate::{config::Error, utils};
 use serde::{Deserialize, Serialize};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
+    str::FromStr,
 };
 
 /// Port selected RocksDB options for tuning underlying rocksdb instance of DiemDB.
@@ -42,6 +43,11 @@ pub struct StorageConfig {
     /// None disables pruning. The windows is in number of versions, consider system tps
     /// (transaction per second) when calculating proper window.
     pub prune_window: Option<u64>,
+    //////// 0L ////////
+    /// Versions of the transactions and their infos kept besides the latest, None keeps them all
+    pub ledger_prune_window: Option<u64>,
+    /// Versions of the events kept besides the latest, None keeps them all
+    pub event_prune_window: Option<u64>,
     #[serde(skip)]
     data_dir: PathBuf,
     /// Read, Write, Connect timeout for network operations in milliseconds
@@ -66,6 +72,8 @@ impl Default for StorageConfig {
             //////// 0L ////////
             // ~50GB state tree history (about 1 day at 100 tps)
             prune_window: Some(10_000_000), 
+            ledger_prune_window: None,
+            event_prune_window: None,
             data_dir: PathBuf::from("/opt/diem/data"),
             // Default read/write/connection timeout, in milliseconds
             timeout_ms: 30_000,
@@ -92,4 +100,107 @@ impl StorageConfig {
         self.backup_service_address
             .set_port(utils::get_available_port());
     }
+
+    //////// 0L ////////
+    pub fn prune_windows(&self) -> PruneWindows {
+        PruneWindows {
+            state_store: self.prune_window,
+            ledger: self.ledger_prune_window,
+            event_store: self.event_prune_window,
+        }
+    }
+
+    pub fn apply_prune_preset(&mut self, preset: PrunePreset) {
+        let windows = preset.windows();
+        self.prune_window = windows.state_store;
+        self.ledger_prune_window = windows.ledger;
+        self.event_prune_window = windows.event_store;
+    }
+}
+
+//////// 0L ////////
+/// The fewest versions a prune window may keep: a request reading a consistent view of the DB
+/// must not have its versions pruned under it
+pub const MIN_PRUNE_WINDOW: u64 = 1_000;
+
+/// How many versions of each part of the DB are kept besides the latest, None keeps them all.
+/// Proofs of the state and of the events go through the transaction infos, so the ledger is kept
+/// at least as long as both.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PruneWindows {
+    pub state_store: Option<u64>,
+    pub ledger: Option<u64>,
+    pub event_store: Option<u64>,
+}
+
+impl PruneWindows {
+    pub fn is_pruning(&self) -> bool {
+        self.state_store.is_some() || self.ledger.is_some() || self.event_store.is_some()
+    }
+}
+
+impl From<Option<u64>> for PruneWindows {
+    /// Prunes the state store only, as the prune window did before the ledger and events had theirs
+    fn from(state_store: Option<u64>) -> Self {
+        Self {
+            state_store,
+            ..Self::default()
+        }
+    }
+}
+
+/// Prune windows for a kind of node
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrunePreset {
+    /// Keeps the whole history, e.g. for a node serving backups or historical queries
+    Archive,
+    /// Keeps about a day of ledger for syncing peers, and a few hours of state and events, e.g.
+    /// for the fullnode of a validator
+    Minimal,
+}
+
+impl PrunePreset {
+    pub fn windows(self) -> PruneWindows {
+        match self {
+            PrunePreset::Archive => PruneWindows::default(),
+            PrunePreset::Minimal => PruneWindows {
+                state_store: Some(1_000_000),
+                ledger: Some(10_000_000),
+                event_store: Some(1_000_000),
+            },
+        }
+    }
+}
+
+impl FromStr for PrunePreset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "archive" => Ok(PrunePreset::Archive),
+            "minimal" => Ok(PrunePreset::Minimal),
+            _ => Err(Error::InvariantViolation(format!(
+                "Unknown prune preset {}, expected archive or minimal",
+                s
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prune_preset() {
+        let mut config = StorageConfig::default();
+        config.apply_prune_preset("minimal".parse().unwrap());
+        assert_eq!(config.prune_windows(), PrunePreset::Minimal.windows());
+        assert!(config.prune_windows().is_pruning());
+
+        config.apply_prune_preset("archive".parse().unwrap());
+        assert!(!config.prune_windows().is_pruning());
+        assert!("full".parse::<PrunePreset>().is_err());
+    }
 }
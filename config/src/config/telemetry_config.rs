@@ -0,0 +1,37 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use serde::{Deserialize, Serialize};
+
+/// Opt-in reports of the node health to a collector, for a community dashboard of the network:
+/// the version of the binary, the role, chain id and synced version of the node, and its connected
+/// peers per network. Neither keys, addresses nor peer ids are reported.
+///
+/// ```yaml
+/// telemetry:
+///   enabled: true
+///   collector_url: "https://telemetry.example.com/report"
+///   node_name: "alice-fullnode"
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    /// The reports are POSTed there as JSON
+    pub collector_url: String,
+    pub report_interval_ms: u64,
+    /// The name of the node on the dashboard, anonymous if None
+    pub node_name: Option<String>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            collector_url: String::new(),
+            report_interval_ms: 60_000,
+            node_name: None,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, time::Duration};
+
+pub const DEFAULT_TLS_RELOAD_INTERVAL_SECS: u64 = 3600;
+
+/// HTTPS of a server of the node, terminated by the node itself
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM certificate chain
+    pub cert_path: PathBuf,
+    /// PEM private key, PKCS8 or RSA
+    pub key_path: PathBuf,
+    /// Seconds between checks of the files for a renewed certificate, e.g. written by an ACME
+    /// client such as certbot. 0 never checks.
+    #[serde(default = "TlsConfig::default_reload_interval_secs")]
+    pub reload_interval_secs: u64,
+}
+
+impl TlsConfig {
+    fn default_reload_interval_secs() -> u64 {
+        DEFAULT_TLS_RELOAD_INTERVAL_SECS
+    }
+
+    pub fn reload_interval(&self) -> Option<Duration> {
+        match self.reload_interval_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+}
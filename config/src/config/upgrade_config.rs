@@ -0,0 +1,34 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use serde::{Deserialize, Serialize};
+
+/// How the node follows the on-chain `DiemVersion`, so that it never runs a chain its binary
+/// doesn't support, and can stop for an upgrade coordinated at a known version
+///
+/// ```yaml
+/// upgrade:
+///   halt_at_version: 12000000
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpgradeConfig {
+    /// Refuse to start on, and halt once the chain moves to, an on-chain version newer than the
+    /// binary supports
+    pub enforce_version: bool,
+    /// Halt once the ledger reaches this version, within a poll interval, and refuse to start
+    /// past it. Replace the binary and unset it to carry on.
+    pub halt_at_version: Option<u64>,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for UpgradeConfig {
+    fn default() -> Self {
+        Self {
+            enforce_version: true,
+            halt_at_version: None,
+            poll_interval_ms: 5_000,
+        }
+    }
+}
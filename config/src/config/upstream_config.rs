@@ -1,11 +1,14 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::network_id::{NetworkId, NodeNetworkId};
+use crate::{
+    config::Error,
+    network_id::{NetworkId, NodeNetworkId},
+};
 use diem_types::PeerId;
 use serde::{Deserialize, Serialize};
 use short_hex_str::AsShortHexStr;
-use std::fmt;
+use std::{collections::HashSet, fmt};
 
 //////// 0L ////////
 /// If a node considers a network 'upstream', the node will broadcast transactions (via mempool) to and
@@ -44,6 +47,85 @@ impl UpstreamConfig {
         // but their unspecified validator network is considered upstream by default
         std::cmp::max(1, self.networks.len())
     }
+
+    pub fn builder() -> UpstreamConfigBuilder {
+        UpstreamConfigBuilder::default()
+    }
+
+    /// Checks that the validator network is not listed, as it is always the primary upstream,
+    /// and that no network is listed twice
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut seen = HashSet::new();
+        for network in &self.networks {
+            if network.is_validator_network() {
+                return Err(Error::InvariantViolation(
+                    "upstream.networks must not list the validator network, it is always upstream"
+                        .into(),
+                ));
+            }
+            if !seen.insert(network) {
+                return Err(Error::InvariantViolation(format!(
+                    "upstream.networks lists {} more than once",
+                    network
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The config mempool and state sync can rely on: without the validator network and with
+    /// each network at its first, i.e., most preferred, position
+    pub fn normalized(&self) -> UpstreamConfig {
+        let mut seen = HashSet::new();
+        let networks = self
+            .networks
+            .iter()
+            .filter(|network| !network.is_validator_network() && seen.insert(*network))
+            .cloned()
+            .collect();
+        UpstreamConfig { networks }
+    }
+}
+
+/// Builds an `UpstreamConfig` from weighted networks: the higher the weight, the more preferred
+/// the network. Networks of equal weight keep the order they were added in.
+#[derive(Clone, Debug, Default)]
+pub struct UpstreamConfigBuilder {
+    networks: Vec<(NetworkId, u32)>,
+}
+
+impl UpstreamConfigBuilder {
+    /// The weight of networks added without one
+    pub const DEFAULT_WEIGHT: u32 = 1;
+
+    pub fn network(self, network: NetworkId) -> Self {
+        self.weighted_network(network, Self::DEFAULT_WEIGHT)
+    }
+
+    pub fn weighted_network(mut self, network: NetworkId, weight: u32) -> Self {
+        self.networks.push((network, weight));
+        self
+    }
+
+    /// The networks with their weights, most preferred first
+    pub fn preferences(&self) -> Vec<(NetworkId, u32)> {
+        let mut preferences = self.networks.clone();
+        // Stable, so ties keep their insertion order
+        preferences.sort_by(|(_, a), (_, b)| b.cmp(a));
+        preferences
+    }
+
+    pub fn build(self) -> Result<UpstreamConfig, Error> {
+        let config = UpstreamConfig {
+            networks: self
+                .preferences()
+                .into_iter()
+                .map(|(network, _)| network)
+                .collect(),
+        };
+        config.validate()?;
+        Ok(config)
+    }
 }
 //////// 0L end ////////
 
@@ -97,3 +179,52 @@ impl fmt::Display for PeerNetworkId {
         )
     }
 }
+
+//////// 0L ////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_upstream_builder() {
+        let config = UpstreamConfig::builder()
+            .network(NetworkId::Public)
+            .weighted_network(NetworkId::vfn_network(), 10)
+            .build()
+            .unwrap();
+        assert_eq!(
+            config.networks,
+            vec![NetworkId::vfn_network(), NetworkId::Public]
+        );
+        assert_eq!(config.get_upstream_preference(NetworkId::Public), Some(1));
+
+        assert!(UpstreamConfig::builder()
+            .network(NetworkId::Validator)
+            .build()
+            .is_err());
+        assert!(UpstreamConfig::builder()
+            .network(NetworkId::Public)
+            .weighted_network(NetworkId::Public, 2)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_upstream_normalized() {
+        let config = UpstreamConfig {
+            networks: vec![
+                NetworkId::Public,
+                NetworkId::Validator,
+                NetworkId::vfn_network(),
+                NetworkId::Public,
+            ],
+        };
+        assert!(config.validate().is_err());
+        let normalized = config.normalized();
+        assert_eq!(
+            normalized.networks,
+            vec![NetworkId::Public, NetworkId::vfn_network()]
+        );
+        normalized.validate().unwrap();
+    }
+}
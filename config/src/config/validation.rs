@@ -0,0 +1,1157 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Cross checks of the fields of a `NodeConfig`, run on load. Each field combination that would
+//! only fail deep in the startup of a subsystem, or silently misbehave, is reported with the YAML
+//! path of the field to fix.
+
+use crate::{
+    config::{
+        AdvertisedAddress, ConsensusProposerType, DiscoveryMethod, Error, ExternalAddressMethod,
+        HandshakeEnforcement, Identity, LeaseBackend, NetworkConfig, NetworkTransport, NodeConfig,
+        RpcBucketConfig, SafetyRulesService, SecureBackend, WaypointConfig, MIN_PRUNE_WINDOW,
+    },
+    network_id::NetworkId,
+};
+use diem_global_constants::CONSENSUS_KEY;
+use diem_types::network_address::NetworkAddress;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    net::IpAddr,
+};
+
+/// A field of the config to fix
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationError {
+    /// YAML path of the field, e.g. `full_node_networks[0].identity`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+pub(crate) fn display_validation_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("\n  {}", error))
+        .collect()
+}
+
+impl NodeConfig {
+    /// Checks the field combinations of the config, returning every error found
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut errors = vec![];
+        let mut error = |path: String, message: &str| {
+            errors.push(ValidationError {
+                path,
+                message: message.into(),
+            })
+        };
+
+        if let WaypointConfig::FromStorage(SecureBackend::InMemoryStorage) = self.base.waypoint {
+            error(
+                "base.waypoint.from_storage".into(),
+                "an in memory storage starts empty and holds no waypoint, use a persistent \
+                 backend or from_config",
+            );
+        }
+        if let WaypointConfig::FromRpc {
+            endpoints,
+            min_agreement,
+            ..
+        } = &self.base.waypoint
+        {
+            if *min_agreement == 0 || *min_agreement > endpoints.len() {
+                error(
+                    "base.waypoint.from_rpc.min_agreement".into(),
+                    "at least one and at most all of the endpoints must agree on the waypoint",
+                );
+            }
+        }
+        let safety_rules = &self.consensus.safety_rules;
+        if let SecureBackend::YubiHsm(_) = safety_rules.backend {
+            if safety_rules.export_consensus_key {
+                error(
+                    "consensus.safety_rules.export_consensus_key".into(),
+                    "a yubihsm backend signs with the consensus key on the device and cannot \
+                     export it",
+                );
+            }
+        }
+        if let SecureBackend::Kms(kms) = &safety_rules.backend {
+            if safety_rules.export_consensus_key && kms.holds(CONSENSUS_KEY) {
+                error(
+                    "consensus.safety_rules.export_consensus_key".into(),
+                    "a kms backend signs with the consensus key in the KMS and cannot export it",
+                );
+            }
+        }
+        if let Some(failover) = &safety_rules.failover {
+            if let SecureBackend::InMemoryStorage = safety_rules.backend {
+                error(
+                    "consensus.safety_rules.backend".into(),
+                    "the instances of a failover pair share the safety data, use a persistent \
+                     backend both can reach",
+                );
+            }
+            let cas = match &safety_rules.backend {
+                SecureBackend::Vault(vault) => vault.disable_cas != Some(true),
+                _ => false,
+            };
+            if let (LeaseBackend::SecureBackend, false) = (&failover.lease, cas) {
+                error(
+                    "consensus.safety_rules.failover.lease".into(),
+                    "only vault with check-and-set keeps both instances from taking the lease \
+                     at once, use a vault backend with cas enabled or a file lease",
+                );
+            }
+            if failover.instance_id.is_empty() {
+                error(
+                    "consensus.safety_rules.failover.instance_id".into(),
+                    "the lease names its holder, give each instance a distinct id",
+                );
+            }
+            if failover.lease_duration_ms == 0 {
+                error(
+                    "consensus.safety_rules.failover.lease_duration_ms".into(),
+                    "a lease that expires at once lets both instances sign",
+                );
+            }
+            // A file lease is only safe on one host, whose clock both instances read
+            if let (LeaseBackend::SecureBackend, 0) = (&failover.lease, failover.max_clock_skew_ms)
+            {
+                error(
+                    "consensus.safety_rules.failover.max_clock_skew_ms".into(),
+                    "the clocks of two hosts are never exactly in sync, a standby with its clock \
+                     ahead could take the lease while the active instance still signs",
+                );
+            }
+        }
+        if let SafetyRulesService::Process(service) = &safety_rules.service {
+            if let Some(tls) = &service.tls {
+                if tls.server_name.is_empty() {
+                    error(
+                        "consensus.safety_rules.service.tls.server_name".into(),
+                        "the validator only connects to a safety rules process whose certificate \
+                         is valid for this name",
+                    );
+                }
+            }
+            if service.health_check_interval_ms == Some(0) {
+                error(
+                    "consensus.safety_rules.service.health_check_interval_ms".into(),
+                    "the connection would be checked before every request, leave it unset to \
+                     disable health checks",
+                );
+            }
+        }
+        if let ConsensusProposerType::LeaderReputation(reputation) = &self.consensus.proposer_type {
+            if reputation.inactive_weights == 0 {
+                error(
+                    "consensus.proposer_type.inactive_weights".into(),
+                    "no validator could be elected before any block is committed",
+                );
+            }
+            if reputation.window_size == Some(0) {
+                error(
+                    "consensus.proposer_type.window_size".into(),
+                    "the reputation is computed over at least one committed block",
+                );
+            }
+        }
+        let storage = &self.storage;
+        for (field, window) in &[
+            ("prune_window", storage.prune_window),
+            ("ledger_prune_window", storage.ledger_prune_window),
+            ("event_prune_window", storage.event_prune_window),
+        ] {
+            if window.map_or(false, |window| window < MIN_PRUNE_WINDOW) {
+                error(
+                    format!("storage.{}", field),
+                    &format!(
+                        "keep at least {} versions, for requests reading a consistent view of \
+                         the DB",
+                        MIN_PRUNE_WINDOW
+                    ),
+                );
+            }
+        }
+        if let Some(ledger) = storage.ledger_prune_window {
+            if storage.prune_window.map_or(true, |state| state > ledger) {
+                error(
+                    "storage.ledger_prune_window".into(),
+                    "proofs of the state go through the transaction infos, keep the ledger at \
+                     least as long as the state",
+                );
+            }
+            if storage
+                .event_prune_window
+                .map_or(true, |events| events > ledger)
+            {
+                error(
+                    "storage.event_prune_window".into(),
+                    "proofs of the events go through the transaction infos, keep the ledger at \
+                     least as long as the events",
+                );
+            }
+        }
+        let backup = &self.backup;
+        if backup.target.is_some() {
+            if backup.transaction_batch_size == 0
+                || backup.state_snapshot_interval % backup.transaction_batch_size != 0
+            {
+                error(
+                    "backup.state_snapshot_interval".into(),
+                    "must be a multiple of the transaction_batch_size, for every snapshot to be \
+                     followed by transaction backups to replay",
+                );
+            }
+            if backup.state_snapshot_retention == Some(0) {
+                error(
+                    "backup.state_snapshot_retention".into(),
+                    "keep at least one state snapshot to restore from",
+                );
+            }
+        }
+        let peer_reputation = &self.peer_reputation;
+        if peer_reputation.violations_per_ban == 0 {
+            error(
+                "peer_reputation.violations_per_ban".into(),
+                "a peer is banned once it misbehaved this many times, at least once",
+            );
+        }
+        if peer_reputation.base_ban_secs > peer_reputation.max_ban_secs {
+            error(
+                "peer_reputation.base_ban_secs".into(),
+                "the first ban is longer than the longest",
+            );
+        }
+        if self.state_sync.max_parallel_chunk_requests == 0 {
+            error(
+                "state_sync.max_parallel_chunk_requests".into(),
+                "at least one chunk must be requested for the node to sync",
+            );
+        }
+        match (&self.json_rpc.tls_cert_path, &self.json_rpc.tls_key_path) {
+            (Some(_), None) => error(
+                "json_rpc.tls_key_path".into(),
+                "HTTPS needs the private key of the certificate",
+            ),
+            (None, Some(_)) => error(
+                "json_rpc.tls_cert_path".into(),
+                "HTTPS needs the certificate of the private key",
+            ),
+            _ => (),
+        }
+        if self.metrics.scrape.tls.is_some() && self.metrics.scrape.address.is_none() {
+            error(
+                "metrics.scrape.tls".into(),
+                "HTTPS is only served on the scrape address, set it",
+            );
+        }
+        if let Some(push_gateway) = &self.metrics.push_gateway {
+            if push_gateway.push_interval_ms == 0 {
+                error(
+                    "metrics.push_gateway.push_interval_ms".into(),
+                    "the metrics would be pushed in a busy loop",
+                );
+            }
+        }
+        let rate_limit = &self.json_rpc.rate_limit;
+        if rate_limit.enabled {
+            let buckets = std::iter::once((
+                "json_rpc.rate_limit.ip_bucket_rate".to_string(),
+                RpcBucketConfig {
+                    size: rate_limit.ip_bucket_size,
+                    rate: rate_limit.ip_bucket_rate,
+                },
+            ))
+            .chain(rate_limit.method_buckets.iter().map(|(method, bucket)| {
+                (
+                    format!("json_rpc.rate_limit.method_buckets.{}", method),
+                    *bucket,
+                )
+            }));
+            for (path, bucket) in buckets {
+                if bucket.rate == 0 || bucket.size < bucket.rate {
+                    error(
+                        path,
+                        "a bucket refills at least one call a second, and holds at least a \
+                         second of refill",
+                    );
+                }
+            }
+            if rate_limit.initial_bucket_fill_percentage > 100 {
+                error(
+                    "json_rpc.rate_limit.initial_bucket_fill_percentage".into(),
+                    "a bucket starts at most full",
+                );
+            }
+            if rate_limit.max_concurrent_requests == 0 {
+                error(
+                    "json_rpc.rate_limit.max_concurrent_requests".into(),
+                    "every request would be refused",
+                );
+            }
+        }
+        let mut policy_networks = HashSet::new();
+        for (index, policy) in self.mempool.upstream_policies.iter().enumerate() {
+            let field = |name: &str| format!("mempool.upstream_policies[{}].{}", index, name);
+            if !policy_networks.insert(&policy.network_id) {
+                error(
+                    field("network_id"),
+                    "the network already has a policy, merge the two",
+                );
+            }
+            if policy.batch_size == Some(0) {
+                error(
+                    field("batch_size"),
+                    "a broadcast carries at least one transaction",
+                );
+            }
+            if policy.max_broadcasts_per_peer == Some(0) {
+                error(
+                    field("max_broadcasts_per_peer"),
+                    "nothing would ever be broadcast to the network",
+                );
+            }
+        }
+        let admission = &self.mempool.admission;
+        if let Some(allowlist) = &admission.sender_allowlist {
+            if allowlist.is_empty() {
+                error(
+                    "mempool.admission.sender_allowlist".into(),
+                    "no transaction would be admitted, unset it to admit every sender",
+                );
+            }
+            if allowlist
+                .iter()
+                .any(|sender| admission.sender_denylist.contains(sender))
+            {
+                error(
+                    "mempool.admission.sender_denylist".into(),
+                    "a sender is both allowed and denied",
+                );
+            }
+        }
+        let mut limited_networks = HashSet::new();
+        for (index, limits) in admission.network_limits.iter().enumerate() {
+            let field =
+                |name: &str| format!("mempool.admission.network_limits[{}].{}", index, name);
+            if !limited_networks.insert(&limits.network_id) {
+                error(
+                    field("network_id"),
+                    "the network already has limits, merge the two",
+                );
+            }
+            if limits.capacity_per_user == Some(0) {
+                error(
+                    field("capacity_per_user"),
+                    "no transaction of the network would be admitted",
+                );
+            }
+        }
+        if self.upgrade.poll_interval_ms == 0 {
+            error(
+                "upgrade.poll_interval_ms".into(),
+                "the on-chain version would be read in a busy loop",
+            );
+        }
+        if self.telemetry.enabled && self.telemetry.collector_url.is_empty() {
+            error(
+                "telemetry.collector_url".into(),
+                "telemetry is enabled without a collector to report to",
+            );
+        }
+        if self.telemetry.enabled && self.telemetry.report_interval_ms == 0 {
+            error(
+                "telemetry.report_interval_ms".into(),
+                "the collector would be sent reports in a busy loop",
+            );
+        }
+
+        let networks = self
+            .validator_network
+            .iter()
+            .map(|network| ("validator_network".to_string(), network))
+            .chain(
+                self.full_node_networks
+                    .iter()
+                    .enumerate()
+                    .map(|(index, network)| (format!("full_node_networks[{}]", index), network)),
+            );
+        let mut network_ids = HashMap::new();
+        let mut listening: Vec<(String, Listener)> = vec![];
+        for (path, network) in networks {
+            let field = |name: &str| format!("{}.{}", path, name);
+            if network.mutual_authentication && network.identity == Identity::None {
+                error(
+                    field("identity"),
+                    "mutual_authentication is set without an identity, peers cannot trust a \
+                     random identity generated on each start",
+                );
+            }
+            if let Identity::FromStorage(identity) = &network.identity {
+                if let SecureBackend::YubiHsm(hsm) = &identity.backend {
+                    if !hsm.is_exportable(&identity.key_name) {
+                        error(
+                            field("identity.key_name"),
+                            "noise needs the network key in memory, list it in the \
+                             exportable_keys of the yubihsm backend",
+                        );
+                    }
+                }
+                if let SecureBackend::Kms(kms) = &identity.backend {
+                    if kms.holds(&identity.key_name) {
+                        error(
+                            field("identity.key_name"),
+                            "noise needs the network key in memory, keep it in the storage of \
+                             the kms backend rather than in its keys",
+                        );
+                    }
+                }
+            }
+            if path == "validator_network" && !network.mutual_authentication {
+                error(
+                    field("mutual_authentication"),
+                    "the validator network must authenticate its peers",
+                );
+            }
+            if path == "validator_network"
+                && network.handshake_enforcement == HandshakeEnforcement::WarnOnly
+            {
+                error(
+                    field("handshake_enforcement"),
+                    "the validator network must refuse peers of another chain",
+                );
+            }
+            if !self.base.role.is_validator()
+                && network.network_id == NetworkId::vfn_network()
+                && !has_seeds(network)
+            {
+                error(
+                    field("network_id"),
+                    "a vfn network connects the fullnode of a validator to it, and has no \
+                     validator in seeds; a public fullnode uses the public network",
+                );
+            }
+            if let DiscoveryMethod::Dns(dns) = &network.discovery_method {
+                if dns.domain.is_empty() {
+                    error(
+                        field("discovery_method.dns.domain"),
+                        "dns discovery needs the domain listing the seeds",
+                    );
+                }
+                if dns.min_refresh_interval_secs > dns.max_refresh_interval_secs {
+                    error(
+                        field("discovery_method.dns.min_refresh_interval_secs"),
+                        "the shortest refresh interval is longer than the longest",
+                    );
+                }
+            }
+            if let DiscoveryMethod::Relay(relay) = &network.discovery_method {
+                if relay.rendezvous.is_empty() {
+                    error(
+                        field("discovery_method.relay.rendezvous"),
+                        "relay discovery needs the rendezvous peers to register with",
+                    );
+                }
+            }
+            if let Some(proxy) = &network.outbound_proxy {
+                if !is_host_port(proxy.address()) {
+                    error(
+                        field("outbound_proxy.address"),
+                        "the proxy address is the host:port of the proxy",
+                    );
+                }
+            }
+            if let Some(tor_proxy) = &network.tor_proxy {
+                if !is_host_port(tor_proxy) {
+                    error(
+                        field("tor_proxy"),
+                        "the tor proxy is the host:port of the SOCKS port of tor",
+                    );
+                }
+            }
+            if network.transport == NetworkTransport::Quic
+                && (network.outbound_proxy.is_some() || network.tor_proxy.is_some())
+            {
+                error(
+                    field("transport"),
+                    "quic connections cannot go through the tcp proxies, outbound_proxy and \
+                     tor_proxy",
+                );
+            }
+            if let Some(advertised) = &network.advertised_address {
+                if !matches!(network.discovery_method, DiscoveryMethod::Relay(_)) {
+                    error(
+                        field("advertised_address"),
+                        "only relay discovery registers the address of the node, the other \
+                         methods never advertise it",
+                    );
+                }
+                match advertised {
+                    AdvertisedAddress::Address(address) => {
+                        if address.find_port().is_none() || address.find_noise_proto().is_some() {
+                            error(
+                                field("advertised_address.address"),
+                                "the advertised address is a host and port, e.g. \
+                                 /ip4/1.2.3.4/tcp/6180, the noise key of the node is appended",
+                            );
+                        }
+                    }
+                    AdvertisedAddress::Auto(auto) => {
+                        match &auto.method {
+                            ExternalAddressMethod::Stun(servers) => {
+                                if servers.is_empty() || !servers.iter().all(|s| is_host_port(s)) {
+                                    error(
+                                        field("advertised_address.auto.method.stun"),
+                                        "stun needs the host:port of the servers to ask",
+                                    );
+                                }
+                            }
+                            ExternalAddressMethod::Upnp => {
+                                let ipv6 = |address: &NetworkAddress| {
+                                    address.find_ip_addr().map_or(false, |ip| ip.is_ipv6())
+                                };
+                                if ipv6(&network.listen_address)
+                                    || network.dual_stack_address.iter().any(ipv6)
+                                {
+                                    error(
+                                        field("advertised_address.auto.method"),
+                                        "upnp only forwards ip4 ports, stun finds the ip6 address",
+                                    );
+                                }
+                            }
+                        }
+                        if auto.refresh_interval_secs == 0 {
+                            error(
+                                field("advertised_address.auto.refresh_interval_secs"),
+                                "the external ip would be looked up without pause",
+                            );
+                        }
+                    }
+                }
+            }
+            if network.multiplex.max_concurrent_inbound_rpcs == 0 {
+                error(
+                    field("multiplex.max_concurrent_inbound_rpcs"),
+                    "every rpc from peers would be refused",
+                );
+            }
+            if network.multiplex.max_concurrent_outbound_rpcs == 0 {
+                error(
+                    field("multiplex.max_concurrent_outbound_rpcs"),
+                    "every rpc to peers would be refused",
+                );
+            }
+            if network.multiplex.inbound_rpc_timeout_ms == 0 {
+                error(
+                    field("multiplex.inbound_rpc_timeout_ms"),
+                    "every rpc from peers would time out",
+                );
+            }
+            if network.multiplex.write_queue_size == 0 {
+                error(
+                    field("multiplex.write_queue_size"),
+                    "no message could be queued to be written to the connection",
+                );
+            }
+            if network.instance.is_some() && network.network_id != NetworkId::Public {
+                error(
+                    field("instance"),
+                    "only the public network may run several instances",
+                );
+            }
+            if let Some(other) = network_ids.insert(network.label(), path.clone()) {
+                if network.network_id == NetworkId::Public {
+                    error(
+                        field("instance"),
+                        &format!(
+                            "{} is also the network of {}, the public networks of a node are \
+                             told apart by their instance",
+                            network.label(),
+                            other
+                        ),
+                    );
+                } else {
+                    error(
+                        field("network_id"),
+                        &format!("{} is also the network of {}", network.network_id, other),
+                    );
+                }
+            }
+            if let Some(address) = &network.dual_stack_address {
+                let is_ipv6 =
+                    |address: &NetworkAddress| address.find_ip_addr().map(|ip| ip.is_ipv6());
+                if address.find_port().is_none()
+                    || is_ipv6(address).is_none()
+                    || is_ipv6(address) == is_ipv6(&network.listen_address)
+                {
+                    error(
+                        field("dual_stack_address"),
+                        "the dual stack address is an ip and port of the other ip version \
+                         than the listen address, e.g. /ip6/::/tcp/6180 along with \
+                         /ip4/0.0.0.0/tcp/6180",
+                    );
+                }
+            }
+            // The addresses of a network are compared to those of the networks before it
+            let mut addresses = vec![("listen_address", &network.listen_address)];
+            addresses.extend(
+                network
+                    .dual_stack_address
+                    .as_ref()
+                    .map(|address| ("dual_stack_address", address)),
+            );
+            for (name, address) in &addresses {
+                if let Some((other, _)) = listening
+                    .iter()
+                    .find(|(_, other)| Listener::new(network, address).conflicts(other))
+                {
+                    error(
+                        field(name),
+                        &format!(
+                            "{} binds the same port as the listen address of {}",
+                            address, other
+                        ),
+                    );
+                }
+            }
+            listening.extend(
+                addresses
+                    .into_iter()
+                    .map(|(name, address)| (field(name), Listener::new(network, address))),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Invalid(errors))
+        }
+    }
+}
+
+fn has_seeds(network: &NetworkConfig) -> bool {
+    !network.seeds.is_empty() || !network.seed_addrs.is_empty()
+}
+
+/// A listen address of a network
+struct Listener<'a> {
+    transport: NetworkTransport,
+    address: &'a NetworkAddress,
+    /// Set on the networks of a dual-stack node, whose IPv6 listener leaves IPv4 to the other
+    ipv6_only: bool,
+}
+
+impl<'a> Listener<'a> {
+    fn new(network: &NetworkConfig, address: &'a NetworkAddress) -> Self {
+        Self {
+            transport: network.transport,
+            address,
+            ipv6_only: network.dual_stack_address.is_some(),
+        }
+    }
+
+    /// Whether both would bind the same port on one host: the same transport and port, on the
+    /// same ip or with one of them listening on every interface
+    fn conflicts(&self, other: &Listener) -> bool {
+        match (self.address.find_port(), other.address.find_port()) {
+            (Some(port), Some(other_port)) => {
+                self.transport == other.transport
+                    && port == other_port
+                    && match (self.ip(), other.ip()) {
+                        (Some(ip), Some(other_ip)) => {
+                            ip == other_ip
+                                || (ip.is_ipv4() == other_ip.is_ipv4()
+                                    && (ip.is_unspecified() || other_ip.is_unspecified()))
+                                || self.binds_ipv4(ip)
+                                || other.binds_ipv4(other_ip)
+                        }
+                        // A dns name may resolve to the ip of the other
+                        _ => true,
+                    }
+            }
+            _ => self.address == other.address,
+        }
+    }
+
+    fn ip(&self) -> Option<IpAddr> {
+        self.address.find_ip_addr()
+    }
+
+    /// An IPv6 listener on every interface also accepts IPv4, unless IPv6 only
+    fn binds_ipv4(&self, ip: IpAddr) -> bool {
+        ip.is_ipv6() && ip.is_unspecified() && !self.ipv6_only
+    }
+}
+
+fn is_host_port(address: &str) -> bool {
+    address.rsplit_once(':').map_or(false, |(host, port)| {
+        !host.is_empty() && port.parse::<u16>().is_ok()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{
+        AutoAddressConfig, AwsCredentials, AwsKmsConfig, BackupTarget, FailoverConfig, KmsConfig,
+        KmsProvider, LeaderReputationConfig, LeaseBackend, MempoolUpstreamPolicy, MutualTlsConfig,
+        OutboundProxyConfig, PrunePreset, PushGatewayConfig, RemoteService, Token, VaultConfig,
+        YubiHsmConfig,
+    };
+    use diem_global_constants::{OWNER_ACCOUNT, VALIDATOR_NETWORK_KEY};
+    use diem_types::waypoint::Waypoint;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn test_validate() {
+        NodeConfig::default_for_validator().validate().unwrap();
+        NodeConfig::default_for_validator_full_node()
+            .validate()
+            .unwrap();
+
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.validate().unwrap();
+        config.base.waypoint = WaypointConfig::FromStorage(SecureBackend::InMemoryStorage);
+        let mut network = config.full_node_networks[0].clone();
+        network.network_id = NetworkId::vfn_network();
+        network.mutual_authentication = true;
+        network.identity = Identity::None;
+        config.full_node_networks.push(network);
+
+        let errors = match config.validate() {
+            Err(Error::Invalid(errors)) => errors,
+            result => panic!("Unexpected result {:?}", result),
+        };
+        let paths: Vec<_> = errors.iter().map(|error| error.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "base.waypoint.from_storage",
+                "full_node_networks[1].identity",
+                "full_node_networks[1].network_id",
+                "full_node_networks[1].listen_address",
+            ]
+        );
+
+        // Every interface overlaps localhost, a quic network binds udp ports
+        let mut config = NodeConfig::default_for_validator();
+        let mut network = config.full_node_networks[0].clone();
+        network.network_id = NetworkId::Public;
+        network.listen_address = "/ip4/127.0.0.1/tcp/6180".parse().unwrap();
+        config.full_node_networks.push(network.clone());
+        network.network_id = NetworkId::Private("backup".into());
+        network.transport = NetworkTransport::Quic;
+        config.full_node_networks.push(network.clone());
+        network.network_id = NetworkId::Private("other".into());
+        network.listen_address = "/ip4/10.0.0.1/tcp/6180".parse().unwrap();
+        network.transport = NetworkTransport::Tcp;
+        config.full_node_networks.push(network);
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "full_node_networks[1].listen_address",
+                    "full_node_networks[3].listen_address",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        //////// 0L ////////
+        // Public networks sharing a node are told apart by their instance
+        let mut config = NodeConfig::default_for_public_full_node();
+        let mut network = config.full_node_networks[0].clone();
+        network.listen_address = "/ip4/0.0.0.0/tcp/6182".parse().unwrap();
+        config.full_node_networks.push(network.clone());
+        network.listen_address = "/ip4/0.0.0.0/tcp/6183".parse().unwrap();
+        network.instance = Some("partners".into());
+        config.full_node_networks.push(network.clone());
+        network.listen_address = "/ip4/0.0.0.0/tcp/6184".parse().unwrap();
+        network.network_id = NetworkId::vfn_network();
+        config.full_node_networks.push(network);
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "full_node_networks[1].instance",
+                    "full_node_networks[3].network_id",
+                    "full_node_networks[3].instance",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // A dual-stack network listens on the port for both ip versions
+        let mut config = NodeConfig::default_for_validator();
+        config
+            .validator_network
+            .as_mut()
+            .unwrap()
+            .dual_stack_address = Some("/ip6/::/tcp/6180".parse().unwrap());
+        config.validate().unwrap();
+        config.full_node_networks[0].dual_stack_address =
+            Some("/ip6/::1/tcp/6180".parse().unwrap());
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["full_node_networks[0].dual_stack_address"]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        config.full_node_networks[0].dual_stack_address =
+            Some("/ip4/127.0.0.1/tcp/7181".parse().unwrap());
+        assert!(config.validate().is_err());
+
+        // Only the fullnode networks may connect to peers of another chain
+        let mut config = NodeConfig::default_for_validator();
+        config.full_node_networks[0].handshake_enforcement = HandshakeEnforcement::WarnOnly;
+        config.validate().unwrap();
+        config
+            .validator_network
+            .as_mut()
+            .unwrap()
+            .handshake_enforcement = HandshakeEnforcement::WarnOnly;
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["validator_network.handshake_enforcement"]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.base.waypoint = WaypointConfig::FromRpc {
+            endpoints: vec!["http://127.0.0.1:8080".into()],
+            min_agreement: 2,
+            genesis_waypoint: Waypoint::default(),
+            latest: None,
+        };
+        config.full_node_networks[0].discovery_method = DiscoveryMethod::Dns(Default::default());
+        config.full_node_networks[0].outbound_proxy = Some(OutboundProxyConfig::Socks5 {
+            address: "localhost".into(),
+        });
+        config.full_node_networks[0].tor_proxy = Some("127.0.0.1:9050".into());
+        config.full_node_networks[0].transport = NetworkTransport::Quic;
+        config.full_node_networks[0].advertised_address =
+            Some(AdvertisedAddress::Auto(AutoAddressConfig {
+                method: ExternalAddressMethod::Stun(vec!["stun.example.com".into()]),
+                refresh_interval_secs: 300,
+            }));
+        config.full_node_networks[0]
+            .multiplex
+            .max_concurrent_outbound_rpcs = 0;
+        config.full_node_networks[0]
+            .multiplex
+            .inbound_rpc_timeout_ms = 0;
+        config.full_node_networks[0].multiplex.write_queue_size = 0;
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "base.waypoint.from_rpc.min_agreement",
+                    "full_node_networks[0].discovery_method.dns.domain",
+                    "full_node_networks[0].outbound_proxy.address",
+                    "full_node_networks[0].transport",
+                    "full_node_networks[0].advertised_address",
+                    "full_node_networks[0].advertised_address.auto.method.stun",
+                    "full_node_networks[0].multiplex.max_concurrent_outbound_rpcs",
+                    "full_node_networks[0].multiplex.inbound_rpc_timeout_ms",
+                    "full_node_networks[0].multiplex.write_queue_size",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_validator();
+        config.storage.apply_prune_preset(PrunePreset::Minimal);
+        config.validate().unwrap();
+        config.storage.prune_window = Some(100);
+        config.storage.event_prune_window = None;
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["storage.prune_window", "storage.event_prune_window"]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_public_full_node();
+        let policy = MempoolUpstreamPolicy {
+            network_id: NetworkId::Public,
+            batch_size: Some(0),
+            max_broadcasts_per_peer: None,
+            backoff_interval_ms: Some(10_000),
+        };
+        config.mempool.upstream_policies = vec![policy.clone(), policy];
+        config.mempool.admission.sender_allowlist = Some(BTreeSet::new());
+        config.state_sync.max_parallel_chunk_requests = 0;
+        config.peer_reputation.base_ban_secs = 1_000_000;
+        config.json_rpc.tls_cert_path = Some("/opt/diem/tls/cert.pem".into());
+        config.metrics.push_gateway = Some(PushGatewayConfig {
+            url: "http://pushgateway:9091".into(),
+            job: "diem-node".into(),
+            push_interval_ms: 0,
+            labels: BTreeMap::new(),
+        });
+        config.json_rpc.rate_limit.enabled = true;
+        config
+            .json_rpc
+            .rate_limit
+            .method_buckets
+            .insert("get_events".into(), RpcBucketConfig { size: 1, rate: 10 });
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "peer_reputation.base_ban_secs",
+                    "state_sync.max_parallel_chunk_requests",
+                    "json_rpc.tls_key_path",
+                    "metrics.push_gateway.push_interval_ms",
+                    "json_rpc.rate_limit.method_buckets.get_events",
+                    "mempool.upstream_policies[0].batch_size",
+                    "mempool.upstream_policies[1].network_id",
+                    "mempool.upstream_policies[1].batch_size",
+                    "mempool.admission.sender_allowlist",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_validator();
+        config.backup.target = Some(BackupTarget::LocalFs {
+            path: "/opt/diem/backup".into(),
+        });
+        config.validate().unwrap();
+        config.backup.transaction_batch_size = 3_000_000;
+        config.backup.state_snapshot_retention = Some(0);
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "backup.state_snapshot_interval",
+                    "backup.state_snapshot_retention",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_validator();
+        let mut hsm = YubiHsmConfig::new(
+            Token::FromDisk("/opt/diem/yubihsm_password".into()),
+            SecureBackend::InMemoryStorage,
+        );
+        hsm.exportable_keys.clear();
+        let hsm = SecureBackend::YubiHsm(hsm);
+        config.consensus.safety_rules.backend = hsm.clone();
+        config.consensus.safety_rules.export_consensus_key = true;
+        config.consensus.proposer_type =
+            ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
+                inactive_weights: 0,
+                window_size: Some(0),
+                ..LeaderReputationConfig::default()
+            });
+        if let Some(network) = config.validator_network.as_mut() {
+            network.identity =
+                Identity::from_storage(VALIDATOR_NETWORK_KEY.into(), OWNER_ACCOUNT.into(), hsm);
+        }
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "consensus.safety_rules.export_consensus_key",
+                    "consensus.proposer_type.inactive_weights",
+                    "consensus.proposer_type.window_size",
+                    "validator_network.identity.key_name",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_validator();
+        let kms = SecureBackend::Kms(KmsConfig {
+            provider: KmsProvider::Aws(AwsKmsConfig {
+                region: "us-east-1".into(),
+                credentials: AwsCredentials::InstanceMetadata,
+            }),
+            keys: vec![
+                (CONSENSUS_KEY.to_string(), "alias/consensus".to_string()),
+                (
+                    VALIDATOR_NETWORK_KEY.to_string(),
+                    "alias/network".to_string(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            timeout_ms: 5_000,
+            storage: Box::new(SecureBackend::InMemoryStorage),
+            namespace: None,
+        });
+        config.consensus.safety_rules.backend = kms.clone();
+        config.consensus.safety_rules.export_consensus_key = true;
+        if let Some(network) = config.validator_network.as_mut() {
+            network.identity =
+                Identity::from_storage(VALIDATOR_NETWORK_KEY.into(), OWNER_ACCOUNT.into(), kms);
+        }
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "consensus.safety_rules.export_consensus_key",
+                    "validator_network.identity.key_name",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_validator();
+        config.consensus.safety_rules.backend = SecureBackend::InMemoryStorage;
+        config.consensus.safety_rules.failover = Some(FailoverConfig {
+            instance_id: String::new(),
+            lease_duration_ms: 0,
+            max_clock_skew_ms: 0,
+            lease: LeaseBackend::SecureBackend,
+        });
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "consensus.safety_rules.backend",
+                    "consensus.safety_rules.failover.lease",
+                    "consensus.safety_rules.failover.instance_id",
+                    "consensus.safety_rules.failover.lease_duration_ms",
+                    "consensus.safety_rules.failover.max_clock_skew_ms",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        // Only a check-and-set write keeps two instances from taking the lease at once
+        let mut vault = VaultConfig {
+            ca_certificate: None,
+            namespace: None,
+            renew_ttl_secs: None,
+            server: "http://127.0.0.1:8200".into(),
+            token: Token::FromConfig("token".into()),
+            disable_cas: None,
+            connection_timeout_ms: None,
+            response_timeout_ms: None,
+        };
+        let mut config = NodeConfig::default_for_validator();
+        config.consensus.safety_rules.backend = SecureBackend::Vault(vault.clone());
+        config.consensus.safety_rules.failover = Some(FailoverConfig {
+            instance_id: "a".into(),
+            lease_duration_ms: 1_000,
+            max_clock_skew_ms: 100,
+            lease: LeaseBackend::SecureBackend,
+        });
+        config.validate().unwrap();
+        vault.disable_cas = Some(true);
+        config.consensus.safety_rules.backend = SecureBackend::Vault(vault);
+        match config.validate() {
+            Err(Error::Invalid(errors)) => {
+                assert_eq!(errors[0].path, "consensus.safety_rules.failover.lease")
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_validator();
+        config.consensus.safety_rules.service = SafetyRulesService::Process(RemoteService {
+            server_address: "/ip4/10.0.0.2/tcp/6191".parse().unwrap(),
+            tls: Some(MutualTlsConfig {
+                cert_path: "/opt/diem/tls/validator.pem".into(),
+                key_path: "/opt/diem/tls/validator_key.pem".into(),
+                ca_cert_path: "/opt/diem/tls/ca.pem".into(),
+                server_name: "safety-rules".into(),
+                allowed_clients: vec![],
+            }),
+            health_check_interval_ms: Some(5_000),
+        });
+        config.validate().unwrap();
+        if let SafetyRulesService::Process(service) = &mut config.consensus.safety_rules.service {
+            service.tls.as_mut().unwrap().server_name.clear();
+            service.health_check_interval_ms = Some(0);
+        }
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "consensus.safety_rules.service.tls.server_name",
+                    "consensus.safety_rules.service.health_check_interval_ms",
+                ]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.upgrade.poll_interval_ms = 0;
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["upgrade.poll_interval_ms"]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.telemetry.enabled = true;
+        match config.validate() {
+            Err(Error::Invalid(errors)) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.path.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["telemetry.collector_url"]
+            ),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Conversion of node configs written for upstream Diem.
+//!
+//! Upstream Diem has diverged from this crate's config format: the VFN network is a `vfn` network
+//! id rather than a private network, networks list their `discovery_methods`, the storage prune
+//! windows moved into a pruner section, and backends are named in several ways. The conversion
+//! maps what has an equivalent here and reports every field it had to drop, so operators know
+//! what to review.
+
+use crate::{
+    config::{Error, NetworkConfig, NodeConfig},
+    network_id::NetworkId,
+};
+use serde_yaml::Value;
+
+/// The result of a conversion
+#[derive(Debug)]
+pub struct Conversion {
+    pub config: NodeConfig,
+    /// The fields that were translated, e.g., `full_node_networks[0].network_id: vfn -> private`
+    pub changes: Vec<String>,
+    /// The fields without an equivalent, they are dropped from the config
+    pub unmapped: Vec<String>,
+}
+
+/// Converts the yaml of an upstream Diem node config
+pub fn convert_from_diem(yaml: &str) -> Result<Conversion, Error> {
+    let mut value: Value =
+        serde_yaml::from_str(yaml).map_err(|e| Error::Yaml("diem config".to_string(), e))?;
+    let mut converter = Converter::default();
+
+    if let Some(network) = get_mut(&mut value, "validator_network") {
+        converter.network(network, "validator_network");
+    }
+    if let Some(Value::Sequence(networks)) = get_mut(&mut value, "full_node_networks") {
+        for (index, network) in networks.iter_mut().enumerate() {
+            converter.network(network, &format!("full_node_networks[{}]", index));
+        }
+    }
+    if let Some(Value::Sequence(networks)) =
+        get_mut(&mut value, "upstream").and_then(|upstream| get_mut(upstream, "networks"))
+    {
+        for (index, network_id) in networks.iter_mut().enumerate() {
+            converter.network_id(network_id, &format!("upstream.networks[{}]", index));
+        }
+    }
+    if let Some(storage) = get_mut(&mut value, "storage") {
+        converter.storage(storage);
+    }
+    converter.backends(&mut value, "");
+    converter.drop_unknown(&mut value, &template(), "");
+
+    let config = serde_yaml::from_value(value)
+        .map_err(|e| Error::Yaml("converted config".to_string(), e))?;
+    Ok(Conversion {
+        config,
+        changes: converter.changes,
+        unmapped: converter.unmapped,
+    })
+}
+
+#[derive(Default)]
+struct Converter {
+    changes: Vec<String>,
+    unmapped: Vec<String>,
+}
+
+impl Converter {
+    fn network(&mut self, network: &mut Value, path: &str) {
+        if let Some(network_id) = get_mut(network, "network_id") {
+            self.network_id(network_id, &format!("{}.network_id", path));
+        }
+
+        let methods = match network {
+            Value::Mapping(mapping) => mapping.remove(&"discovery_methods".into()),
+            _ => None,
+        };
+        if let Some(Value::Sequence(mut methods)) = methods {
+            if methods.is_empty() {
+                return;
+            }
+            let method = methods.remove(0);
+            self.change(
+                &format!("{}.discovery_methods", path),
+                format!("discovery_method: {}", display(&method)),
+            );
+            for (index, method) in methods.iter().enumerate() {
+                self.unmapped.push(format!(
+                    "{}.discovery_methods[{}]: {}, only one discovery method is supported",
+                    path,
+                    index + 1,
+                    display(method)
+                ));
+            }
+            insert(network, "discovery_method", method);
+        }
+    }
+
+    /// Upstream network ids are plain names, with `vfn` for the private VFN network
+    fn network_id(&mut self, network_id: &mut Value, path: &str) {
+        let converted = match &*network_id {
+            Value::String(name) => match name.to_lowercase().as_str() {
+                "validator" => Some(NetworkId::Validator),
+                "public" => Some(NetworkId::Public),
+                "vfn" => Some(NetworkId::vfn_network()),
+                _ => None,
+            },
+            Value::Mapping(mapping) if mapping.len() == 1 => {
+                mapping.iter().next().and_then(|(variant, name)| {
+                    match (variant.as_str(), name.as_str()) {
+                        (Some(variant), Some(name)) if variant.eq_ignore_ascii_case("private") => {
+                            Some(NetworkId::Private(name.to_string()))
+                        }
+                        _ => None,
+                    }
+                })
+            }
+            _ => None,
+        };
+        match converted {
+            Some(converted) => self.replace_network_id(network_id, converted, path),
+            None => {
+                self.unmapped
+                    .push(format!("{}: unknown network {}", path, display(network_id)))
+            }
+        }
+    }
+
+    fn replace_network_id(&mut self, network_id: &mut Value, converted: NetworkId, path: &str) {
+        let converted = serde_yaml::to_value(&converted).expect("NetworkId serializes");
+        if *network_id != converted {
+            self.change(
+                path,
+                format!("{} -> {}", display(network_id), display(&converted)),
+            );
+            *network_id = converted;
+        }
+    }
+
+    /// Upstream moved the prune windows into a pruner section
+    fn storage(&mut self, storage: &mut Value) {
+        let pruner = match storage {
+            Value::Mapping(mapping) => mapping.remove(&"storage_pruner_config".into()),
+            _ => None,
+        };
+        if let Some(Value::Mapping(pruner)) = pruner {
+            for (key, value) in pruner {
+                let field = format!("storage.storage_pruner_config.{}", display(&key));
+                let window = match key.as_str() {
+                    Some("state_store_prune_window") => "prune_window",
+                    Some("ledger_prune_window") => "ledger_prune_window",
+                    _ => {
+                        self.unmapped.push(field);
+                        continue;
+                    }
+                };
+                self.change(&field, format!("storage.{}", window));
+                insert(storage, window, value);
+            }
+        }
+    }
+
+    /// Normalizes the names of secure backends, e.g., `OnDiskStorage` or `disk`
+    fn backends(&mut self, value: &mut Value, path: &str) {
+        match value {
+            Value::Mapping(mapping) => {
+                if let Some(Value::String(backend)) = mapping.get_mut(&"type".into()) {
+                    if let Some(name) = backend_name(backend) {
+                        if backend.as_str() != name {
+                            self.change(
+                                &format!("{}.type", path),
+                                format!("{} -> {}", backend, name),
+                            );
+                            *backend = name.to_string();
+                        }
+                    }
+                }
+                for (key, value) in mapping.iter_mut() {
+                    self.backends(value, &join(path, &display(key)));
+                }
+            }
+            Value::Sequence(values) => {
+                for (index, value) in values.iter_mut().enumerate() {
+                    self.backends(value, &format!("{}[{}]", path, index));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Removes the fields the template has no equivalent of. Only structs are checked: mappings
+    /// that are empty in the template (e.g., peer sets) or tagged enums may hold any key.
+    fn drop_unknown(&mut self, value: &mut Value, template: &Value, path: &str) {
+        match (value, template) {
+            (Value::Mapping(mapping), Value::Mapping(template))
+                if !template.is_empty() && !template.contains_key(&"type".into()) =>
+            {
+                let unknown: Vec<Value> = mapping
+                    .iter()
+                    .map(|(key, _)| key.clone())
+                    .filter(|key| !template.contains_key(key))
+                    .collect();
+                for key in unknown {
+                    mapping.remove(&key);
+                    self.unmapped.push(join(path, &display(&key)));
+                }
+                for (key, value) in mapping.iter_mut() {
+                    if let Some(template) = template.get(key) {
+                        self.drop_unknown(value, template, &join(path, &display(key)));
+                    }
+                }
+            }
+            (Value::Sequence(values), Value::Sequence(template)) if !template.is_empty() => {
+                for (index, value) in values.iter_mut().enumerate() {
+                    self.drop_unknown(value, &template[0], &format!("{}[{}]", path, index));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn change(&mut self, path: &str, change: String) {
+        self.changes.push(format!("{}: {}", path, change));
+    }
+}
+
+/// The serialized config with every optional section present, as the reference of known fields
+fn template() -> Value {
+    let config = NodeConfig {
+        validator_network: Some(NetworkConfig::network_with_id(NetworkId::Validator)),
+        full_node_networks: vec![NetworkConfig::network_with_id(NetworkId::Public)],
+        ..NodeConfig::default()
+    };
+    serde_yaml::to_value(&config).expect("NodeConfig serializes")
+}
+
+fn backend_name(name: &str) -> Option<&'static str> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    match normalized.as_str() {
+        "ondiskstorage" | "ondisk" | "disk" => Some("on_disk_storage"),
+        "inmemorystorage" | "inmemory" | "memory" => Some("in_memory_storage"),
+        "vault" => Some("vault"),
+        "github" => Some("github"),
+        _ => None,
+    }
+}
+
+fn get_mut<'a>(value: &'a mut Value, key: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Mapping(mapping) => mapping.get_mut(&key.into()),
+        _ => None,
+    }
+}
+
+fn insert(value: &mut Value, key: &str, field: Value) {
+    if let Value::Mapping(mapping) = value {
+        mapping.insert(key.into(), field);
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_start_matches("---").trim().replace('\n', " "))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{DiscoveryMethod, SecureBackend};
+
+    const DIEM_CONFIG: &str = r#"
+base:
+  role: full_node
+  waypoint:
+    from_config: "0:683185844ef67e5c8eeaa158e635de2a4c574ce7bbb7f41f787d38db2d623ae2"
+execution:
+  backend:
+    type: OnDiskStorage
+    path: secure_storage.json
+full_node_networks:
+  - network_id: vfn
+    discovery_methods: [onchain]
+    listen_address: "/ip4/0.0.0.0/tcp/6181"
+  - network_id:
+      private: ops
+upstream:
+  networks: [vfn, public]
+storage:
+  storage_pruner_config:
+    state_store_prune_window: 1000
+    ledger_prune_window: 5000
+inspection_service:
+  port: 9101
+"#;
+
+    #[test]
+    fn test_convert_from_diem() {
+        let conversion = convert_from_diem(DIEM_CONFIG).unwrap();
+        let config = conversion.config;
+
+        assert_eq!(
+            config.full_node_networks[0].network_id,
+            NetworkId::vfn_network()
+        );
+        assert_eq!(
+            config.full_node_networks[0].discovery_method,
+            DiscoveryMethod::Onchain
+        );
+        assert_eq!(
+            config.full_node_networks[1].network_id,
+            NetworkId::Private("ops".to_string())
+        );
+        assert_eq!(
+            config.upstream.networks,
+            vec![NetworkId::vfn_network(), NetworkId::Public]
+        );
+        assert_eq!(config.storage.prune_window, Some(1000));
+        assert_eq!(config.storage.ledger_prune_window, Some(5000));
+        assert!(matches!(
+            config.execution.backend,
+            SecureBackend::OnDiskStorage(_)
+        ));
+
+        assert_eq!(conversion.unmapped, vec!["inspection_service".to_string()]);
+        assert!(conversion
+            .changes
+            .contains(&"execution.backend.type: OnDiskStorage -> on_disk_storage".to_string()));
+    }
+
+    #[test]
+    fn test_convert_own_config() {
+        let config = NodeConfig::default_for_validator();
+        let conversion = convert_from_diem(&serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert!(conversion.changes.is_empty());
+        assert!(conversion.unmapped.is_empty());
+        assert_eq!(conversion.config, config);
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Semantic differences of two node configs, e.g., the configs written by two releases.
+//!
+//! Both configs are compared as `NodeConfig`s, field by field: the order of the fields does not
+//! matter, and a field left out is the same as its default. Sequences are compared element by
+//! element. The values of secret fields are not shown, only that they changed.
+
+use crate::{
+    config::{Error, NodeConfig},
+    redact::{REDACTED, SECRET_FIELDS},
+    template::{display, join},
+};
+use serde_yaml::{Mapping, Value};
+use std::fmt;
+
+/// A field that differs, None on the side that does not have it
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub path: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => write!(f, "~ {}: {} -> {}", self.path, before, after),
+            (None, Some(after)) => write!(f, "+ {}: {}", self.path, after),
+            (Some(before), None) => write!(f, "- {}: {}", self.path, before),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// The fields of `after` that differ from `before`
+pub fn diff_configs(before: &NodeConfig, after: &NodeConfig) -> Result<Vec<Change>, Error> {
+    let to_value = |config: &NodeConfig| {
+        serde_yaml::to_value(config).map_err(|e| Error::Yaml("node config".to_string(), e))
+    };
+    let mut changes = vec![];
+    diff(
+        &to_value(before)?,
+        &to_value(after)?,
+        "",
+        false,
+        &mut changes,
+    );
+    Ok(changes)
+}
+
+fn diff(before: &Value, after: &Value, path: &str, secret: bool, changes: &mut Vec<Change>) {
+    if before == after {
+        return;
+    }
+    match (before, after) {
+        (Value::Mapping(before), Value::Mapping(after)) => {
+            for (key, value) in before {
+                let after = after.get(key).unwrap_or(&Value::Null);
+                diff_field(key, value, after, path, secret, changes);
+            }
+            for (key, value) in after {
+                if !before.contains_key(key) {
+                    diff_field(key, &Value::Null, value, path, secret, changes);
+                }
+            }
+        }
+        (Value::Sequence(before), Value::Sequence(after)) => {
+            for index in 0..before.len().max(after.len()) {
+                diff(
+                    before.get(index).unwrap_or(&Value::Null),
+                    after.get(index).unwrap_or(&Value::Null),
+                    &format!("{}[{}]", path, index),
+                    secret,
+                    changes,
+                );
+            }
+        }
+        // A section present on one side only is reported field by field
+        (Value::Null, Value::Mapping(_)) => diff(
+            &Value::Mapping(Mapping::new()),
+            after,
+            path,
+            secret,
+            changes,
+        ),
+        (Value::Mapping(_), Value::Null) => diff(
+            before,
+            &Value::Mapping(Mapping::new()),
+            path,
+            secret,
+            changes,
+        ),
+        (Value::Null, Value::Sequence(_)) => {
+            diff(&Value::Sequence(vec![]), after, path, secret, changes)
+        }
+        (Value::Sequence(_), Value::Null) => {
+            diff(before, &Value::Sequence(vec![]), path, secret, changes)
+        }
+        _ => {
+            let show = |value: &Value| match value {
+                Value::Null => None,
+                _ if secret => Some(REDACTED.to_string()),
+                value => Some(display(value)),
+            };
+            changes.push(Change {
+                path: path.to_string(),
+                before: show(before),
+                after: show(after),
+            });
+        }
+    }
+}
+
+fn diff_field(
+    key: &Value,
+    before: &Value,
+    after: &Value,
+    path: &str,
+    secret: bool,
+    changes: &mut Vec<Change>,
+) {
+    let secret = secret
+        || key
+            .as_str()
+            .map_or(false, |key| SECRET_FIELDS.contains(&key));
+    diff(before, after, &join(path, &display(key)), secret, changes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{NetworkConfig, PersistableConfig},
+        network_id::NetworkId,
+    };
+
+    #[test]
+    fn test_diff_configs() {
+        let before = NodeConfig::default();
+        let mut after = before.clone();
+        after.storage.prune_window = Some(50_000);
+        after.validator_network = Some(NetworkConfig::network_with_id(NetworkId::Validator));
+
+        let changes = diff_configs(&before, &after).unwrap();
+        assert!(changes.contains(&Change {
+            path: "storage.prune_window".into(),
+            before: Some("10000000".into()),
+            after: Some("50000".into()),
+        }));
+        // The added network is reported field by field
+        assert!(
+            changes
+                .iter()
+                .any(|change| change.path == "validator_network.network_id"
+                    && change.before.is_none())
+        );
+        assert!(diff_configs(&after, &after).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_ignores_order_and_defaults() {
+        let before =
+            NodeConfig::parse("storage:\n  prune_window: 100\n  timeout_ms: 30000\n").unwrap();
+        let after =
+            NodeConfig::parse("storage:\n  timeout_ms: 30000\n  prune_window: 100\n").unwrap();
+        assert!(diff_configs(&before, &after).unwrap().is_empty());
+        assert!(diff_configs(
+            &before,
+            &NodeConfig::parse("storage:\n  prune_window: 100\n").unwrap()
+        )
+        .unwrap()
+        .is_empty());
+    }
+}
@@ -0,0 +1,431 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Environment self-checks for a loaded `NodeConfig`.
+//!
+//! `NodeConfig::load` only verifies that a config is well formed. The checks in this module verify
+//! that the node can actually start with it on this host: listen ports are free, the data
+//! directory is writable and has space left, and the keys referenced by the config exist in the
+//! key store. Each check produces a pass/warn/fail line, failures and warnings carry a hint on how
+//! to resolve them.
+
+use crate::config::{
+    GcpCredentials, Identity, KmsProvider, NodeConfig, SecureBackend, WaypointConfig,
+};
+use diem_global_constants::{CONSENSUS_KEY, EXECUTION_KEY, WAYPOINT};
+use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
+use diem_types::{
+    network_address::{parse_ip_tcp, NetworkAddress},
+    waypoint::Waypoint,
+    PeerId,
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener},
+    path::Path,
+};
+
+/// Below this amount of free space on the data directory disk a warning is reported
+pub const DEFAULT_MIN_FREE_SPACE_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// How to fix a warning or failure
+    pub hint: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    pub fn pass(&mut self, name: &str, message: impl Into<String>) {
+        self.push(name, CheckStatus::Pass, message.into(), None);
+    }
+
+    pub fn warn(&mut self, name: &str, message: impl Into<String>, hint: impl Into<String>) {
+        self.push(name, CheckStatus::Warn, message.into(), Some(hint.into()));
+    }
+
+    pub fn fail(&mut self, name: &str, message: impl Into<String>, hint: impl Into<String>) {
+        self.push(name, CheckStatus::Fail, message.into(), Some(hint.into()));
+    }
+
+    fn push(&mut self, name: &str, status: CheckStatus, message: String, hint: Option<String>) {
+        self.checks.push(CheckResult {
+            name: name.to_string(),
+            status,
+            message,
+            hint,
+        });
+    }
+
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.count(CheckStatus::Fail) > 0
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "[{}] {}: {}", check.status, check.name, check.message)?;
+            if let Some(hint) = &check.hint {
+                writeln!(f, "       hint: {}", hint)?;
+            }
+        }
+        write!(
+            f,
+            "{} passed, {} warnings, {} failed",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail)
+        )
+    }
+}
+
+/// Runs all environment checks for the given config
+pub fn check_environment(config: &NodeConfig, min_free_space: u64) -> DoctorReport {
+    let mut report = DoctorReport::default();
+    check_data_dir(&mut report, config.data_dir(), min_free_space);
+    check_ports(&mut report, config);
+    check_key_store(&mut report, config);
+    report
+}
+
+fn check_data_dir(report: &mut DoctorReport, data_dir: &Path, min_free_space: u64) {
+    const NAME: &str = "data directory";
+    let probe = data_dir.join(".doctor_probe");
+    let writable = fs::create_dir_all(data_dir)
+        .and_then(|_| fs::write(&probe, b"probe"))
+        .and_then(|_| fs::remove_file(&probe));
+    if let Err(e) = writable {
+        report.fail(
+            NAME,
+            format!("{} is not writable: {}", data_dir.display(), e),
+            "check the ownership and permissions of base.data_dir, or point it to another directory",
+        );
+        return;
+    }
+    report.pass(NAME, format!("{} is writable", data_dir.display()));
+
+    const SPACE: &str = "disk space";
+    match fs2::available_space(data_dir) {
+        Ok(available) if available < min_free_space => report.warn(
+            SPACE,
+            format!(
+                "only {} MiB available for {}",
+                available / 1024 / 1024,
+                data_dir.display()
+            ),
+            format!(
+                "free up space or move base.data_dir to a disk with at least {} MiB available",
+                min_free_space / 1024 / 1024
+            ),
+        ),
+        Ok(available) => report.pass(SPACE, format!("{} MiB available", available / 1024 / 1024)),
+        Err(e) => report.warn(
+            SPACE,
+            format!("unable to determine the available space: {}", e),
+            "check the disk of base.data_dir manually",
+        ),
+    }
+}
+
+/// Returns every address the node will listen on, with the config field it comes from
+pub fn listen_addresses(config: &NodeConfig) -> Vec<(String, SocketAddr)> {
+    let mut addresses = Vec::new();
+    let mut network_address = |name: String, address: &NetworkAddress| {
+        if let Some(((ip, port), _)) = parse_ip_tcp(address.as_slice()) {
+            addresses.push((name, SocketAddr::new(ip, port)));
+        }
+    };
+    if let Some(network) = &config.validator_network {
+        network_address("validator_network".into(), &network.listen_address);
+    }
+    for network in &config.full_node_networks {
+        network_address(
+            format!("full_node_networks[{}]", network.network_id),
+            &network.listen_address,
+        );
+    }
+    addresses.push(("json_rpc".into(), config.json_rpc.address));
+    if config.admin_service.enabled {
+        addresses.push(("admin_service".into(), config.admin_service.address));
+        //////// 0L ////////
+        if let Some(grpc_address) = config.admin_service.grpc_address {
+            addresses.push(("admin_service.grpc_address".into(), grpc_address));
+        }
+    }
+    //////// 0L ////////
+    if config.api.enabled {
+        addresses.push(("api".into(), config.api.address));
+    }
+    if config.health_check.enabled {
+        addresses.push(("health_check".into(), config.health_check.address));
+    }
+    addresses.push(("storage".into(), config.storage.address));
+    addresses.push((
+        "storage.backup_service".into(),
+        config.storage.backup_service_address,
+    ));
+
+    let debug_ip = config
+        .debug_interface
+        .address
+        .parse::<IpAddr>()
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    let debug = &config.debug_interface;
+    for (name, port) in &[
+        (
+            "debug_interface.admission_control",
+            debug.admission_control_node_debug_port,
+        ),
+        ("debug_interface.metrics_server", debug.metrics_server_port),
+        (
+            "debug_interface.public_metrics_server",
+            debug.public_metrics_server_port,
+        ),
+    ] {
+        addresses.push((name.to_string(), SocketAddr::new(debug_ip, *port)));
+    }
+    if let Some(address) = config.metrics.scrape.address {
+        addresses.retain(|(name, _)| name != "debug_interface.metrics_server");
+        addresses.push(("metrics.scrape".into(), address));
+    }
+    addresses
+}
+
+fn check_ports(report: &mut DoctorReport, config: &NodeConfig) {
+    let mut by_port: HashMap<u16, String> = HashMap::new();
+    for (name, address) in listen_addresses(config) {
+        let check = format!("port {}", name);
+        if let Some(other) = by_port.insert(address.port(), name.clone()) {
+            report.fail(
+                &check,
+                format!("port {} is also used by {}", address.port(), other),
+                "give every listener a distinct port",
+            );
+            continue;
+        }
+        match TcpListener::bind(address) {
+            Ok(_) => report.pass(&check, format!("{} is bindable", address)),
+            Err(e) => report.fail(
+                &check,
+                format!("unable to bind {}: {}", address, e),
+                "stop the process using this port (is another node running?) or change the port",
+            ),
+        }
+    }
+}
+
+fn check_key_store(report: &mut DoctorReport, config: &NodeConfig) {
+    let networks = config
+        .validator_network
+        .iter()
+        .chain(config.full_node_networks.iter());
+    for network in networks {
+        if let Identity::FromStorage(identity) = &network.identity {
+            let name = format!("{} network identity", network.network_id);
+            if let Some(storage) = open_storage(report, &name, &identity.backend) {
+                check_key(report, &name, &storage, &identity.key_name);
+                check_value::<PeerId>(report, &name, &storage, &identity.peer_id_name);
+            }
+        }
+    }
+
+    if config.base.role.is_validator() {
+        let name = "consensus key";
+        let backend = &config.consensus.safety_rules.backend;
+        if let Some(storage) = open_storage(report, name, backend) {
+            check_key(report, name, &storage, CONSENSUS_KEY);
+        }
+        if config.execution.sign_vote_proposal {
+            let name = "execution key";
+            if let Some(storage) = open_storage(report, name, &config.execution.backend) {
+                check_key(report, name, &storage, EXECUTION_KEY);
+            }
+        }
+    }
+
+    if let WaypointConfig::FromStorage(backend) = &config.base.waypoint {
+        let name = "waypoint";
+        if let Some(storage) = open_storage(report, name, backend) {
+            check_value::<Waypoint>(report, name, &storage, WAYPOINT);
+        }
+    }
+}
+
+/// Opens a backend without side effects, `Storage::from` panics on a missing token and creates
+/// missing on disk stores.
+fn open_storage(report: &mut DoctorReport, name: &str, backend: &SecureBackend) -> Option<Storage> {
+    match backend {
+        SecureBackend::InMemoryStorage => {
+            report.warn(
+                name,
+                "uses in-memory storage, keys are lost on restart",
+                "use an on-disk or vault backend outside of tests",
+            );
+            return None;
+        }
+        SecureBackend::OnDiskStorage(config) if !config.path().exists() => {
+            report.fail(
+                name,
+                format!("key store {} does not exist", config.path().display()),
+                "run the genesis tool `init` command, or fix the backend path",
+            );
+            return None;
+        }
+        SecureBackend::GitHub(config) => {
+            if let Err(e) = config.token.read_token() {
+                report.fail(
+                    name,
+                    format!("unable to read the GitHub token: {}", e),
+                    "check the token path",
+                );
+                return None;
+            }
+        }
+        SecureBackend::Vault(config) => {
+            if let Err(e) = config.token.read_token() {
+                report.fail(
+                    name,
+                    format!("unable to read the Vault token: {}", e),
+                    "check the token path",
+                );
+                return None;
+            }
+        }
+        SecureBackend::YubiHsm(config) => {
+            if let Err(e) = config.password.read_token() {
+                report.fail(
+                    name,
+                    format!("unable to read the YubiHSM password: {}", e),
+                    "check the password path",
+                );
+                return None;
+            }
+        }
+        SecureBackend::Kms(config) => {
+            if let KmsProvider::Gcp(gcp) = &config.provider {
+                if let GcpCredentials::AccessToken(token) = &gcp.credentials {
+                    if let Err(e) = token.read_token() {
+                        report.fail(
+                            name,
+                            format!("unable to read the GCP access token: {}", e),
+                            "check the token path",
+                        );
+                        return None;
+                    }
+                }
+            }
+            // The store of the values and of the keys not held by the KMS
+            open_storage(report, name, &config.storage)?;
+        }
+        SecureBackend::OnDiskStorage(_) => (),
+    }
+
+    let storage = Storage::from(backend);
+    if let Err(e) = storage.available() {
+        report.fail(
+            name,
+            format!("key store is not available: {}", e),
+            "check that the backend is reachable and the credentials are valid",
+        );
+        return None;
+    }
+    Some(storage)
+}
+
+fn check_key(report: &mut DoctorReport, name: &str, storage: &Storage, key: &str) {
+    match storage.get_public_key(key) {
+        Ok(_) => report.pass(name, format!("key '{}' is present", key)),
+        Err(e) => report.fail(
+            name,
+            format!("key '{}' is missing: {}", key, e),
+            "re-run key initialization for this node, or point the config to the right namespace",
+        ),
+    }
+}
+
+fn check_value<T: serde::de::DeserializeOwned>(
+    report: &mut DoctorReport,
+    name: &str,
+    storage: &Storage,
+    key: &str,
+) {
+    match storage.get::<T>(key) {
+        Ok(_) => report.pass(name, format!("'{}' is present", key)),
+        Err(e) => report.fail(
+            name,
+            format!("'{}' is missing or invalid: {}", key, e),
+            "re-run key initialization for this node, or point the config to the right namespace",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_port_conflict() {
+        let mut config = NodeConfig::default_for_validator();
+        config.randomize_ports();
+        config
+            .json_rpc
+            .address
+            .set_port(config.storage.address.port());
+
+        let mut report = DoctorReport::default();
+        check_ports(&mut report, &config);
+        assert!(report
+            .checks
+            .iter()
+            .any(|c| c.status == CheckStatus::Fail && c.message.contains("also used by")));
+    }
+
+    #[test]
+    fn test_data_dir() {
+        let path = diem_temppath::TempPath::new();
+        path.create_as_dir().unwrap();
+
+        let mut report = DoctorReport::default();
+        check_data_dir(&mut report, path.path(), 0);
+        assert!(!report.has_failures());
+        assert_eq!(report.count(CheckStatus::Pass), 2);
+
+        let mut report = DoctorReport::default();
+        check_data_dir(&mut report, path.path(), u64::max_value());
+        assert_eq!(report.count(CheckStatus::Warn), 1);
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Documentation of the node config fields, e.g., `storage.prune_window`. The fields and their doc
+//! comments are read from the sources of the config structs, embedded in the binary, so that the
+//! documentation printed is the one of the release.
+
+use crate::{
+    config::{Error, NodeConfig},
+    template::{display, join},
+};
+use serde_yaml::Value;
+use std::{collections::HashMap, fmt, mem};
+
+/// The sources of the structs of `NodeConfig`
+const SOURCES: &[&str] = &[
+    include_str!("config/admin_service_config.rs"),
+    include_str!("config/api_config.rs"),
+    include_str!("config/backup_config.rs"),
+    include_str!("config/chain_config.rs"),
+    include_str!("config/consensus_config.rs"),
+    include_str!("config/debug_interface_config.rs"),
+    include_str!("config/execution_config.rs"),
+    include_str!("config/health_check_config.rs"),
+    include_str!("config/json_rpc_config.rs"),
+    include_str!("config/key_manager_config.rs"),
+    include_str!("config/logger_config.rs"),
+    include_str!("config/mempool_config.rs"),
+    include_str!("config/metrics_config.rs"),
+    include_str!("config/mod.rs"),
+    include_str!("config/network_config.rs"),
+    include_str!("config/peer_reputation_config.rs"),
+    include_str!("config/safety_rules_config.rs"),
+    include_str!("config/secure_backend_config.rs"),
+    include_str!("config/shutdown_config.rs"),
+    include_str!("config/state_sync_config.rs"),
+    include_str!("config/storage_config.rs"),
+    include_str!("config/telemetry_config.rs"),
+    include_str!("config/test_config.rs"),
+    include_str!("config/tls_config.rs"),
+    include_str!("config/upgrade_config.rs"),
+    include_str!("config/upstream_config.rs"),
+];
+
+const ROOT: &str = "NodeConfig";
+
+/// A serialized field of a config struct
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    /// The Rust type, e.g., `Option<u64>`
+    pub ty: String,
+    pub doc: String,
+    /// The fields of its type are serialized in place of it
+    flatten: bool,
+}
+
+/// The documentation of a field
+#[derive(Debug)]
+pub struct Explanation {
+    pub path: String,
+    /// The Rust type of the field, `NodeConfig` for the whole config
+    pub ty: String,
+    pub doc: String,
+    /// The value in the default config, None if it has none there, e.g. an empty list, or if the
+    /// field is a config struct
+    pub default: Option<String>,
+    /// The fields of its type, if a config struct
+    pub fields: Vec<Field>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "(node config)"
+        } else {
+            &self.path
+        };
+        writeln!(f, "{}: {}", path, self.ty)?;
+        for line in self.doc.lines() {
+            writeln!(f, "  {}", line)?;
+        }
+        if let Some(default) = &self.default {
+            writeln!(f, "  default: {}", default)?;
+        }
+        if !self.fields.is_empty() {
+            writeln!(f, "  fields:")?;
+            for field in &self.fields {
+                writeln!(f, "    {}: {}", field.name, field.ty)?;
+                for line in field.doc.lines() {
+                    writeln!(f, "      {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Explains the field at `path`, e.g., `full_node_networks[0].listen_address`, the whole config
+/// if empty
+pub fn explain(path: &str) -> Result<Explanation, Error> {
+    let structs = parse_structs(SOURCES);
+    let mut ty = ROOT.to_string();
+    let mut doc = String::new();
+    let mut resolved = String::new();
+    for key in keys(path) {
+        let name = struct_name(&ty);
+        let field = find_field(&structs, name, key).ok_or_else(|| {
+            let reason = if structs.contains_key(name) {
+                format!("{} has no field {}", name, key)
+            } else {
+                format!("{} is a {}, it has no fields", resolved, ty)
+            };
+            Error::InvariantViolation(format!("Unknown config field {}: {}", path, reason))
+        })?;
+        resolved = join(&resolved, key);
+        ty = field.ty;
+        doc = field.doc;
+    }
+    let fields = fields_of(&structs, struct_name(&ty));
+    // The value of a whole struct is better read field by field
+    let default = if fields.is_empty() {
+        value_at(&NodeConfig::default(), path)?
+    } else {
+        None
+    };
+    Ok(Explanation {
+        path: path.to_string(),
+        ty,
+        doc,
+        default,
+        fields,
+    })
+}
+
+/// The value at `path` in `config`, None if it has none there
+pub fn value_at(config: &NodeConfig, path: &str) -> Result<Option<String>, Error> {
+    let mut value =
+        serde_yaml::to_value(config).map_err(|e| Error::Yaml("node config".to_string(), e))?;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let mut parts = segment.split('[');
+        let key = parts.next().unwrap_or_default();
+        value = match value.get(key) {
+            Some(value) => value.clone(),
+            None => return Ok(None),
+        };
+        for index in parts {
+            let index = index.trim_end_matches(']').parse::<usize>().map_err(|_| {
+                Error::InvariantViolation(format!("Invalid index in config field {}", path))
+            })?;
+            value = match value.get(index) {
+                Some(value) => value.clone(),
+                None => return Ok(None),
+            };
+        }
+    }
+    Ok(match value {
+        Value::Null => None,
+        value => Some(display(&value)),
+    })
+}
+
+/// The field names of `path`, without the indices of sequences
+fn keys(path: &str) -> impl Iterator<Item = &str> {
+    path.split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.split('[').next().unwrap_or_default())
+}
+
+/// The config struct of a field type, e.g., `NetworkConfig` for `Option<Vec<NetworkConfig>>`
+fn struct_name(ty: &str) -> &str {
+    let mut ty = ty.trim();
+    while let Some(inner) = ["Option<", "Vec<", "Box<", "Arc<"]
+        .iter()
+        .find_map(|wrapper| ty.strip_prefix(wrapper))
+    {
+        ty = inner.strip_suffix('>').unwrap_or(inner).trim();
+    }
+    ty.rsplit("::").next().unwrap_or(ty)
+}
+
+fn find_field(structs: &HashMap<String, Vec<Field>>, name: &str, key: &str) -> Option<Field> {
+    structs.get(name)?.iter().find_map(|field| {
+        if field.flatten {
+            find_field(structs, struct_name(&field.ty), key)
+        } else if field.name == key {
+            Some(field.clone())
+        } else {
+            None
+        }
+    })
+}
+
+/// The serialized fields of a struct, with the flattened ones in place
+fn fields_of(structs: &HashMap<String, Vec<Field>>, name: &str) -> Vec<Field> {
+    structs
+        .get(name)
+        .map(|fields| {
+            fields
+                .iter()
+                .flat_map(|field| {
+                    if field.flatten {
+                        fields_of(structs, struct_name(&field.ty))
+                    } else {
+                        vec![field.clone()]
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The fields of the structs with named fields, by struct name. Fields skipped by serde are left
+/// out.
+fn parse_structs(sources: &[&str]) -> HashMap<String, Vec<Field>> {
+    let mut structs = HashMap::new();
+    for source in sources {
+        let mut current: Option<(String, Vec<Field>)> = None;
+        let mut doc = vec![];
+        let (mut skip, mut flatten) = (false, false);
+        for line in source.lines().map(str::trim) {
+            let (name, fields) = match &mut current {
+                Some(current) => current,
+                None => {
+                    if let Some(declaration) = line.strip_prefix("pub struct ") {
+                        if declaration.ends_with('{') {
+                            let name = declaration
+                                .split(|c: char| c == '<' || c == '{' || c.is_whitespace())
+                                .next()
+                                .unwrap_or_default();
+                            current = Some((name.to_string(), vec![]));
+                        }
+                    }
+                    continue;
+                }
+            };
+            if line == "}" {
+                structs.insert(name.clone(), mem::take(fields));
+                current = None;
+            } else if let Some(text) = line.strip_prefix("///") {
+                doc.push(text.trim().to_string());
+            } else if let Some(attributes) = line.strip_prefix("#[serde(") {
+                for attribute in attributes.trim_end_matches(")]").split(',') {
+                    skip |= attribute.trim() == "skip";
+                    flatten |= attribute.trim() == "flatten";
+                }
+            } else if let Some((field_name, ty)) = parse_field(line) {
+                if !skip {
+                    fields.push(Field {
+                        name: field_name.to_string(),
+                        ty: ty.to_string(),
+                        doc: doc.join("\n"),
+                        flatten,
+                    });
+                }
+                doc.clear();
+                skip = false;
+                flatten = false;
+            }
+        }
+    }
+    structs
+}
+
+/// The name and type of a field declaration, e.g., `pub prune_window: Option<u64>,`
+fn parse_field(line: &str) -> Option<(&str, &str)> {
+    let line = line.split("//").next().unwrap_or_default().trim();
+    let line = line
+        .strip_prefix("pub(crate) ")
+        .or_else(|| line.strip_prefix("pub "))
+        .unwrap_or(line);
+    let (name, ty) = line.split_once(':')?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name, ty.trim().trim_end_matches(',')))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_explain_field() {
+        let explanation = explain("storage.prune_window").unwrap();
+        assert_eq!(explanation.ty, "Option<u64>");
+        assert!(explanation.doc.starts_with("None disables pruning"));
+        assert_eq!(explanation.default, Some("10000000".to_string()));
+        assert!(explanation.fields.is_empty());
+
+        let explanation = explain("full_node_networks[0].max_outbound_connections").unwrap();
+        assert_eq!(explanation.default, None);
+    }
+
+    #[test]
+    fn test_explain_struct() {
+        let explanation = explain("storage").unwrap();
+        assert_eq!(explanation.ty, "StorageConfig");
+        let names: Vec<_> = explanation.fields.iter().map(|f| f.name.as_str()).collect();
+        assert!(names.contains(&"prune_window"));
+        assert!(names.contains(&"rocksdb_config"));
+        // Not serialized
+        assert!(!names.contains(&"data_dir"));
+
+        assert!(explain("storage.unknown").is_err());
+        assert!(explain("storage.prune_window.unknown").is_err());
+    }
+
+    #[test]
+    fn test_explain_default_config() {
+        // Every field of the default config is documented, i.e., the sources cover its structs
+        fn check(value: &Value, path: &str) {
+            if let Value::Mapping(mapping) = value {
+                for (key, value) in mapping {
+                    let path = join(path, key.as_str().unwrap());
+                    let explanation = explain(&path).unwrap();
+                    if !explanation.fields.is_empty() {
+                        check(value, &path);
+                    }
+                }
+            }
+        }
+        check(&serde_yaml::to_value(NodeConfig::default()).unwrap(), "");
+    }
+}
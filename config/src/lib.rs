@@ -3,8 +3,18 @@
 
 #![forbid(unsafe_code)]
 
+pub mod compat; //////// 0L ////////
 pub mod config;
+pub mod convert; //////// 0L ////////
+pub mod diff; //////// 0L ////////
+pub mod doctor; //////// 0L ////////
+pub mod explain; //////// 0L ////////
 pub mod generator;
 pub mod keys;
 pub mod network_id;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod proptest_types; //////// 0L ////////
+pub mod redact; //////// 0L ////////
+pub mod reload; //////// 0L ////////
+pub mod template; //////// 0L ////////
 pub mod utils;
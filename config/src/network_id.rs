@@ -1,10 +1,18 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 use crate::config::{PeerRole, RoleType};
-use diem_types::PeerId;
+use diem_types::{chain_id::ChainId, PeerId};
 use serde::{Deserialize, Serialize, Serializer};
 use short_hex_str::AsShortHexStr;
-use std::{cmp::Ordering, fmt, str::FromStr};
+use std::{
+    cmp::Ordering,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        Arc,
+    },
+};
 
 /// A grouping of common information between all networking code for logging.
 /// This should greatly reduce the groupings between these given everywhere, and will allow
@@ -16,6 +24,60 @@ pub struct NetworkContext {
     #[serde(serialize_with = "NetworkId::serialize_str")]
     network_id: NetworkId,
     peer_id: PeerId,
+    //////// 0L ////////
+    /// Tells apart the networks of different chains hosted by the same process
+    chain_id: ChainId,
+    /// Precomputed label of the chain, to keep allocations off the metrics hot path
+    #[serde(skip)]
+    chain_label: String,
+    #[serde(skip_serializing_if = "CurrentEpoch::is_unknown")]
+    epoch: CurrentEpoch,
+    /// Tells apart the public networks of a node, when it has several
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    /// Precomputed label of the network and its instance, see `NetworkId::label`
+    #[serde(skip)]
+    network_label: String,
+}
+
+/// The current epoch of the chain, when the network learns it from reconfigurations. Shared by the
+/// clones of a `NetworkContext`.
+#[derive(Clone)]
+struct CurrentEpoch(Arc<AtomicU64>);
+
+impl CurrentEpoch {
+    const UNKNOWN: u64 = u64::MAX;
+
+    fn get(&self) -> Option<u64> {
+        match self.0.load(AtomicOrdering::Relaxed) {
+            Self::UNKNOWN => None,
+            epoch => Some(epoch),
+        }
+    }
+
+    fn is_unknown(&self) -> bool {
+        self.get().is_none()
+    }
+}
+
+impl Default for CurrentEpoch {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU64::new(Self::UNKNOWN)))
+    }
+}
+
+impl PartialEq for CurrentEpoch {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for CurrentEpoch {}
+
+impl Serialize for CurrentEpoch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
 }
 
 impl fmt::Debug for NetworkContext {
@@ -28,23 +90,46 @@ impl fmt::Display for NetworkContext {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "[{},{},{}]",
+            "[{},{},{},{}",
             self.role,
-            self.network_id.as_str(),
+            self.network_label,
             self.peer_id.short_str(),
-        )
+            self.chain_label,
+        )?;
+        if let Some(epoch) = self.epoch() {
+            write!(f, ",{}", epoch)?;
+        }
+        write!(f, "]")
     }
 }
 
 impl NetworkContext {
-    pub fn new(role: RoleType, network_id: NetworkId, peer_id: PeerId) -> NetworkContext {
+    pub fn new(
+        role: RoleType,
+        network_id: NetworkId,
+        peer_id: PeerId,
+        chain_id: ChainId,
+    ) -> NetworkContext {
         NetworkContext {
             role,
-            network_id,
             peer_id,
+            chain_id,
+            chain_label: chain_id.to_string(),
+            epoch: CurrentEpoch::default(),
+            network_label: network_id.as_str().to_string(),
+            network_id,
+            instance: None,
         }
     }
 
+    //////// 0L ////////
+    /// The context of the `instance` of the network, for a node running several public networks
+    pub fn with_instance(mut self, instance: Option<String>) -> Self {
+        self.network_label = self.network_id.label(instance.as_deref());
+        self.instance = instance;
+        self
+    }
+
     pub fn role(&self) -> RoleType {
         self.role
     }
@@ -57,12 +142,42 @@ impl NetworkContext {
         self.peer_id
     }
 
+    //////// 0L ////////
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// The network and its instance as a metrics label, e.g. `Public/partners`
+    pub fn network_label(&self) -> &str {
+        &self.network_label
+    }
+
+    //////// 0L ////////
+    pub fn chain_id(&self) -> ChainId {
+        self.chain_id
+    }
+
+    /// The chain as a metrics label
+    pub fn chain_label(&self) -> &str {
+        &self.chain_label
+    }
+
+    /// The current epoch, if the network follows reconfigurations
+    pub fn epoch(&self) -> Option<u64> {
+        self.epoch.get()
+    }
+
+    pub fn set_epoch(&self, epoch: u64) {
+        self.epoch.0.store(epoch, AtomicOrdering::Relaxed);
+    }
+
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     pub fn mock_with_peer_id(peer_id: PeerId) -> std::sync::Arc<Self> {
         std::sync::Arc::new(Self::new(
             RoleType::Validator,
             NetworkId::Validator,
             peer_id,
+            ChainId::test(),
         ))
     }
 
@@ -72,13 +187,14 @@ impl NetworkContext {
             RoleType::Validator,
             NetworkId::Validator,
             PeerId::random(),
+            ChainId::test(),
         ))
     }
 }
 
 /// A representation of the network being used in communication.
-/// There should only be one of each NetworkId used for a single node (except for NetworkId::Public),
-/// and handshakes should verify that the NetworkId being used is the same during a handshake,
+/// There should only be one of each NetworkId used for a single node (except for NetworkId::Public,
+/// whose instances are told apart by `NetworkConfig::instance`), and handshakes should verify that the NetworkId being used is the same during a handshake,
 /// to effectively ensure communication is restricted to a network.  Network should be checked that
 /// it is not the `DEFAULT_NETWORK`
 #[derive(Clone, Deserialize, Eq, Hash, PartialEq, Serialize)]
@@ -249,6 +365,16 @@ impl NetworkId {
         }
     }
 
+    //////// 0L ////////
+    /// Names the `instance` of the network, e.g. `Public/partners`, or the network alone if it
+    /// has a single instance
+    pub fn label(&self, instance: Option<&str>) -> String {
+        match instance {
+            Some(instance) => format!("{}/{}", self.as_str(), instance),
+            None => self.as_str().to_string(),
+        }
+    }
+
     fn serialize_str<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -299,15 +425,85 @@ mod test {
         assert_eq!(id, decoded);
     }
 
+    //////// 0L ////////
+    #[test]
+    fn test_custom_private_network() {
+        let id = NetworkId::Private("relay".to_string());
+        assert!(!id.is_vfn_network());
+        assert_eq!(id, NetworkId::from_str(id.as_str()).unwrap());
+
+        let encoded = serde_yaml::to_string(&id).unwrap();
+        assert_eq!(id, serde_yaml::from_str::<NetworkId>(&encoded).unwrap());
+        let encoded = bcs::to_bytes(&id).unwrap();
+        assert_eq!(id, bcs::from_bytes::<NetworkId>(&encoded).unwrap());
+
+        // Side networks only trust peers they are configured with, whatever the node's role
+        for role in &[RoleType::Validator, RoleType::FullNode] {
+            assert_eq!(
+                id.upstream_roles(role),
+                &[PeerRole::PreferredUpstream, PeerRole::Upstream]
+            );
+            assert_eq!(
+                id.downstream_roles(role),
+                &[PeerRole::Downstream, PeerRole::Known]
+            );
+        }
+    }
+
     #[test]
     fn test_network_context_serialization() {
         let peer_id = PeerId::random();
-        let context = NetworkContext::new(RoleType::Validator, NetworkId::vfn_network(), peer_id);
+        let context = NetworkContext::new(
+            RoleType::Validator,
+            NetworkId::vfn_network(),
+            peer_id,
+            ChainId::test(),
+        );
         let expected = format!(
-            "---\nrole: {}\nnetwork_id: {}\npeer_id: {:x}\n",
+            "---\nrole: {}\nnetwork_id: {}\npeer_id: {:x}\nchain_id: {}\n",
             RoleType::Validator,
             VFN_NETWORK,
-            peer_id
+            peer_id,
+            ChainId::test().id(),
+        );
+        assert_eq!(expected, serde_yaml::to_string(&context).unwrap());
+
+        //////// 0L ////////
+        // The epoch is shared by the clones of the context, and only reported once known
+        let clone = context.clone();
+        context.set_epoch(7);
+        assert_eq!(clone.epoch(), Some(7));
+        assert_eq!(
+            format!("{}epoch: 7\n", expected),
+            serde_yaml::to_string(&clone).unwrap()
+        );
+        assert!(clone
+            .to_string()
+            .ends_with(&format!(",{},7]", ChainId::test())));
+    }
+
+    //////// 0L ////////
+    #[test]
+    fn test_network_instance() {
+        let peer_id = PeerId::random();
+        let context = NetworkContext::new(
+            RoleType::FullNode,
+            NetworkId::Public,
+            peer_id,
+            ChainId::test(),
+        );
+        assert_eq!(context.network_label(), "Public");
+
+        let context = context.with_instance(Some("partners".into()));
+        assert_eq!(context.instance(), Some("partners"));
+        assert_eq!(context.network_label(), "Public/partners");
+        let prefix = format!("[{},Public/partners,", RoleType::FullNode);
+        assert!(context.to_string().starts_with(&prefix));
+        let expected = format!(
+            "---\nrole: {}\nnetwork_id: Public\npeer_id: {:x}\nchain_id: {}\ninstance: partners\n",
+            RoleType::FullNode,
+            peer_id,
+            ChainId::test().id(),
         );
         assert_eq!(expected, serde_yaml::to_string(&context).unwrap());
     }
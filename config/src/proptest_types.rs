@@ -0,0 +1,181 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Proptest strategies for configs and the network identifiers in them. Configs are generated
+//! from the defaults so that they are valid, and then randomized in the fields that have custom
+//! (de)serialization or take part in validation.
+
+use crate::{
+    config::{
+        DiscoveryMethod, NetworkConfig, NodeConfig, PeerNetworkId, RateLimitConfig, UpstreamConfig,
+    },
+    network_id::{NetworkId, NodeNetworkId},
+};
+use diem_types::{network_address::arb_diemnet_addr, PeerId};
+use proptest::{collection::vec, prelude::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+pub fn arb_network_id() -> impl Strategy<Value = NetworkId> {
+    prop_oneof![Just(NetworkId::Validator), arb_full_node_network_id()]
+}
+
+/// Any network id that may be used in `full_node_networks`
+pub fn arb_full_node_network_id() -> impl Strategy<Value = NetworkId> {
+    prop_oneof![
+        Just(NetworkId::Public),
+        Just(NetworkId::vfn_network()),
+        "[a-z][a-z0-9_]{0,15}".prop_map(NetworkId::Private),
+    ]
+}
+
+pub fn arb_peer_network_id() -> impl Strategy<Value = PeerNetworkId> {
+    (arb_network_id(), any::<usize>(), any::<PeerId>()).prop_map(|(network_id, num_id, peer_id)| {
+        PeerNetworkId(NodeNetworkId::new(network_id, num_id), peer_id)
+    })
+}
+
+pub fn arb_upstream_config() -> impl Strategy<Value = UpstreamConfig> {
+    vec(arb_full_node_network_id(), 0..4).prop_map(|networks| UpstreamConfig { networks })
+}
+
+pub fn arb_rate_limit_config() -> impl Strategy<Value = RateLimitConfig> {
+    (any::<usize>(), any::<usize>(), 0..=100u8, any::<bool>()).prop_map(
+        |(ip_byte_bucket_rate, ip_byte_bucket_size, initial_bucket_fill_percentage, enabled)| {
+            RateLimitConfig {
+                ip_byte_bucket_rate,
+                ip_byte_bucket_size,
+                initial_bucket_fill_percentage,
+                enabled,
+            }
+        },
+    )
+}
+
+pub fn arb_network_config(network_id: NetworkId) -> impl Strategy<Value = NetworkConfig> {
+    (
+        any::<[u8; 32]>(),
+        any::<bool>(),
+        any::<bool>(),
+        proptest::collection::hash_map(any::<PeerId>(), vec(arb_diemnet_addr(), 1..3), 0..3),
+        proptest::option::of(arb_rate_limit_config()),
+        (any::<u64>(), any::<u64>(), any::<usize>()),
+    )
+        .prop_map(
+            move |(
+                seed,
+                mutual_authentication,
+                onchain_discovery,
+                seed_addrs,
+                inbound_rate_limit_config,
+                (ping_interval_ms, max_connection_delay_ms, max_outbound_connections),
+            )| {
+                let mut config = NetworkConfig::network_with_id(network_id.clone());
+                config.random(&mut StdRng::from_seed(seed));
+                config.mutual_authentication = mutual_authentication;
+                config.discovery_method = if onchain_discovery {
+                    DiscoveryMethod::Onchain
+                } else {
+                    DiscoveryMethod::None
+                };
+                config.seed_addrs = seed_addrs;
+                config.inbound_rate_limit_config = inbound_rate_limit_config;
+                config.ping_interval_ms = ping_interval_ms;
+                config.max_connection_delay_ms = max_connection_delay_ms;
+                config.max_outbound_connections = max_outbound_connections;
+                config
+            },
+        )
+}
+
+/// Validator and full node configs, with random networks and upstream preferences
+pub fn arb_node_config() -> impl Strategy<Value = NodeConfig> {
+    let full_node_networks = vec(arb_full_node_network_id(), 0..3).prop_flat_map(|network_ids| {
+        network_ids
+            .into_iter()
+            .map(arb_network_config)
+            .collect::<Vec<_>>()
+    });
+    (
+        any::<bool>(),
+        arb_network_config(NetworkId::Validator),
+        full_node_networks,
+        arb_upstream_config(),
+    )
+        .prop_map(
+            |(is_validator, validator_network, full_node_networks, upstream)| {
+                let mut config = if is_validator {
+                    NodeConfig::default_for_validator()
+                } else {
+                    NodeConfig::default_for_public_full_node()
+                };
+                config.validator_network = if is_validator {
+                    Some(validator_network)
+                } else {
+                    None
+                };
+                config.full_node_networks = full_node_networks;
+                config.upstream = upstream;
+                config
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{de::DeserializeOwned, Serialize};
+    use std::fmt::Debug;
+
+    fn assert_yaml_round_trip<T: Debug + DeserializeOwned + PartialEq + Serialize>(value: &T) {
+        let encoded = serde_yaml::to_string(value).unwrap();
+        let decoded: T = serde_yaml::from_str(&encoded).unwrap();
+        assert_eq!(value, &decoded);
+    }
+
+    fn assert_bcs_round_trip<T: Debug + DeserializeOwned + PartialEq + Serialize>(value: &T) {
+        let encoded = bcs::to_bytes(value).unwrap();
+        let decoded: T = bcs::from_bytes(&encoded).unwrap();
+        assert_eq!(value, &decoded);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn network_id_round_trip(network_id in arb_network_id()) {
+            assert_yaml_round_trip(&network_id);
+            assert_bcs_round_trip(&network_id);
+        }
+
+        #[test]
+        fn peer_network_id_round_trip(peer_network_id in arb_peer_network_id()) {
+            assert_yaml_round_trip(&peer_network_id);
+            assert_bcs_round_trip(&peer_network_id);
+        }
+
+        #[test]
+        fn upstream_config_round_trip(upstream in arb_upstream_config()) {
+            assert_yaml_round_trip(&upstream);
+            assert_bcs_round_trip(&upstream);
+        }
+
+        #[test]
+        fn network_config_round_trip(config in arb_full_node_network_id().prop_flat_map(arb_network_config)) {
+            assert_yaml_round_trip(&config);
+        }
+
+        /// Validation must give the same result before and after a round-trip, so a config that
+        /// passes validation when written out still passes when the node loads it
+        #[test]
+        fn node_config_round_trip(config in arb_node_config()) {
+            assert_yaml_round_trip(&config);
+
+            let encoded = serde_yaml::to_string(&config).unwrap();
+            let decoded: NodeConfig = serde_yaml::from_str(&encoded).unwrap();
+            let expected = config.validate_network_configs().map_err(|e| e.to_string());
+            let actual = decoded.validate_network_configs().map_err(|e| e.to_string());
+            prop_assert_eq!(expected, actual);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Redaction of key material and credentials from serialized configs, for output that leaves the
+//! node: snapshots, exports and diagnostics.
+
+use serde_yaml::Value;
+
+/// Fields holding key material or credentials, e.g., `key` of an identity from config, `token`
+/// of a vault backend, or `password` of the metrics basic auth and of a yubihsm backend
+pub const SECRET_FIELDS: &[&str] = &[
+    "key",
+    "consensus_key",
+    "execution_key",
+    "operator_key",
+    "owner_key",
+    "token",
+    "password",
+    // The `AccessToken` credentials of a GCP KMS backend
+    "access_token",
+];
+
+pub const REDACTED: &str = "<redacted>";
+
+/// Replaces the values of all secret fields, returning whether any was found. Unset fields, e.g.,
+/// `token: ~`, and values already redacted hold no secret and are left as they are.
+pub fn redact(value: &mut Value) -> bool {
+    match value {
+        Value::Mapping(mapping) => {
+            let mut redacted = false;
+            for (key, value) in mapping.iter_mut() {
+                match key.as_str() {
+                    Some(key) if SECRET_FIELDS.contains(&key) => {
+                        if !value.is_null() && value.as_str() != Some(REDACTED) {
+                            *value = Value::String(REDACTED.into());
+                            redacted = true;
+                        }
+                    }
+                    _ => redacted |= redact(value),
+                }
+            }
+            redacted
+        }
+        Value::Sequence(values) => {
+            let mut redacted = false;
+            for value in values.iter_mut() {
+                redacted |= redact(value);
+            }
+            redacted
+        }
+        _ => false,
+    }
+}
+
+/// Whether the value holds any secret field
+pub fn contains_secrets(value: &Value) -> bool {
+    redact(&mut value.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{
+        BasicAuthConfig, GcpCredentials, GcpKmsConfig, Identity, KmsConfig, KmsProvider,
+        NodeConfig, SecureBackend, TestConfig, Token, YubiHsmConfig,
+    };
+    use diem_crypto::{x25519, Uniform};
+    use diem_types::PeerId;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_redact_identity() {
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.full_node_networks[0].identity = Identity::None;
+        let value = serde_yaml::to_value(&config).unwrap();
+        assert!(!contains_secrets(&value));
+
+        let key = x25519::PrivateKey::generate(&mut StdRng::from_seed([0u8; 32]));
+        config.full_node_networks[0].identity = Identity::from_config(key, PeerId::random());
+        let mut value = serde_yaml::to_value(&config).unwrap();
+        assert!(redact(&mut value));
+        assert!(!contains_secrets(&value));
+        assert_eq!(
+            value["full_node_networks"][0]["identity"]["key"],
+            Value::String(REDACTED.into())
+        );
+    }
+    #[test]
+    fn test_redact_account_keys() {
+        let mut test_config = TestConfig::new_with_temp_dir(None);
+        test_config.random_account_key(&mut StdRng::from_seed([0u8; 32]));
+        let mut value = serde_yaml::to_value(&test_config).unwrap();
+        assert!(redact(&mut value));
+        // Redacting again finds nothing left
+        assert!(!redact(&mut value));
+
+        let redacted = Value::String(REDACTED.into());
+        assert_eq!(value["operator_key"], redacted);
+        assert_eq!(value["owner_key"], redacted);
+        // Public data is kept
+        assert_ne!(value["auth_key"], redacted);
+    }
+    #[test]
+    fn test_redact_metrics_password() {
+        let mut config = NodeConfig::default_for_public_full_node();
+        config.metrics.scrape.basic_auth = Some(BasicAuthConfig {
+            username: "prometheus".to_string(),
+            password: Token::FromConfig("secret".to_string()),
+        });
+        let mut value = serde_yaml::to_value(&config).unwrap();
+        assert!(redact(&mut value));
+        let basic_auth = &value["metrics"]["scrape"]["basic_auth"];
+        assert_eq!(basic_auth["password"], Value::String(REDACTED.into()));
+        assert_eq!(basic_auth["username"], Value::String("prometheus".into()));
+    }
+    #[test]
+    fn test_redact_yubihsm_password() {
+        let mut config = NodeConfig::default_for_validator();
+        config.consensus.safety_rules.backend = SecureBackend::YubiHsm(YubiHsmConfig::new(
+            Token::FromConfig("hsm-password".to_string()),
+            SecureBackend::InMemoryStorage,
+        ));
+        let mut value = serde_yaml::to_value(&config).unwrap();
+        assert!(redact(&mut value));
+        assert_eq!(
+            value["consensus"]["safety_rules"]["backend"]["password"],
+            Value::String(REDACTED.into())
+        );
+        assert!(!serde_yaml::to_string(&value)
+            .unwrap()
+            .contains("hsm-password"));
+    }
+    #[test]
+    fn test_redact_gcp_access_token() {
+        let mut config = NodeConfig::default_for_validator();
+        config.consensus.safety_rules.backend = SecureBackend::Kms(KmsConfig {
+            provider: KmsProvider::Gcp(GcpKmsConfig {
+                credentials: GcpCredentials::AccessToken(Token::FromConfig(
+                    "gcp-access-token".to_string(),
+                )),
+            }),
+            keys: BTreeMap::new(),
+            timeout_ms: 5_000,
+            storage: Box::new(SecureBackend::InMemoryStorage),
+            namespace: None,
+        });
+        let mut value = serde_yaml::to_value(&config).unwrap();
+        assert!(redact(&mut value));
+        assert_eq!(
+            value["consensus"]["safety_rules"]["backend"]["provider"]["credentials"]
+                ["access_token"],
+            Value::String(REDACTED.into())
+        );
+        assert!(!serde_yaml::to_string(&value)
+            .unwrap()
+            .contains("gcp-access-token"));
+    }
+}
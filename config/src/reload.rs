@@ -0,0 +1,177 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The sections of a `NodeConfig` a running node applies without a restart: the log level, the
+//! upstream networks, the seeds of each network and the prune window. A reloaded config that
+//! changes any other field is rejected whole, listing the fields that need a restart.
+
+use crate::config::{Error, NodeConfig, UpstreamConfig};
+use diem_logger::Level;
+use serde_yaml::Value;
+
+/// The reloadable changes of a config
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigReload {
+    pub log_level: Option<Level>,
+    pub upstream: Option<UpstreamConfig>,
+    /// The networks whose `seeds` or `seed_addrs` changed, by `NetworkConfig::label`
+    pub seeds: Vec<String>,
+    pub prune_window: Option<u64>,
+}
+
+impl ConfigReload {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The changes from `current` to `new`, if they are all reloadable
+pub fn reloadable_changes(current: &NodeConfig, new: &NodeConfig) -> Result<ConfigReload, Error> {
+    let mut fixed = changed_paths(&without_reloadable(current)?, &without_reloadable(new)?);
+    let prune_window = match (current.storage.prune_window, new.storage.prune_window) {
+        (Some(current), Some(new)) if current != new => Some(new),
+        (Some(_), None) | (None, Some(_)) => {
+            // The pruner only runs if the storage was opened with a prune window
+            fixed.push("storage.prune_window".into());
+            None
+        }
+        _ => None,
+    };
+    if !fixed.is_empty() {
+        return Err(Error::InvariantViolation(format!(
+            "These fields need a restart to change: {}",
+            fixed.join(", ")
+        )));
+    }
+
+    let networks = |config: &NodeConfig| {
+        config
+            .validator_network
+            .iter()
+            .chain(config.full_node_networks.iter())
+            .map(|network| {
+                (
+                    network.label(),
+                    network.seeds.clone(),
+                    network.seed_addrs.clone(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    // Only the seeds may differ, the networks are otherwise the same and in the same order
+    let seeds = networks(current)
+        .into_iter()
+        .zip(networks(new))
+        .filter(|(current, new)| current != new)
+        .map(|(_, (label, _, _))| label)
+        .collect();
+
+    Ok(ConfigReload {
+        log_level: Some(new.logger.level).filter(|level| *level != current.logger.level),
+        upstream: Some(new.upstream.clone()).filter(|upstream| *upstream != current.upstream),
+        seeds,
+        prune_window,
+    })
+}
+
+fn without_reloadable(config: &NodeConfig) -> Result<Value, Error> {
+    let mut value =
+        serde_yaml::to_value(config).map_err(|e| Error::Yaml("NodeConfig".into(), e))?;
+    remove(&mut value["logger"], "level");
+    remove(&mut value["storage"], "prune_window");
+    remove(&mut value, "upstream");
+    remove_seeds(&mut value["validator_network"]);
+    if let Value::Sequence(networks) = &mut value["full_node_networks"] {
+        networks.iter_mut().for_each(remove_seeds);
+    }
+    Ok(value)
+}
+
+fn remove_seeds(network: &mut Value) {
+    remove(network, "seeds");
+    remove(network, "seed_addrs");
+}
+
+fn remove(value: &mut Value, field: &str) {
+    if let Value::Mapping(mapping) = value {
+        mapping.remove(&Value::String(field.into()));
+    }
+}
+
+/// The dotted paths of the fields that differ, regardless of the order of mapping keys
+fn changed_paths(current: &Value, new: &Value) -> Vec<String> {
+    let mut paths = vec![];
+    collect_changed_paths(current, new, "", &mut paths);
+    paths
+}
+
+fn collect_changed_paths(current: &Value, new: &Value, path: &str, paths: &mut Vec<String>) {
+    let child = |key: &Value| {
+        let key = key
+            .as_str()
+            .map_or_else(|| format!("{:?}", key), Into::into);
+        if path.is_empty() {
+            key
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (current, new) {
+        (Value::Mapping(current), Value::Mapping(new)) => {
+            for (key, value) in current.iter() {
+                match new.get(key) {
+                    Some(other) => collect_changed_paths(value, other, &child(key), paths),
+                    None => paths.push(child(key)),
+                }
+            }
+            for key in new.iter().map(|(key, _)| key) {
+                if !current.contains_key(key) {
+                    paths.push(child(key));
+                }
+            }
+        }
+        (Value::Sequence(current), Value::Sequence(new)) if current.len() == new.len() => {
+            for (index, (value, other)) in current.iter().zip(new.iter()).enumerate() {
+                collect_changed_paths(value, other, &format!("{}[{}]", path, index), paths);
+            }
+        }
+        _ if current != new => paths.push(path.to_string()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Peer;
+    use diem_types::PeerId;
+
+    #[test]
+    fn test_reloadable_changes() {
+        let current = NodeConfig::default_for_public_full_node();
+        assert!(reloadable_changes(&current, &current).unwrap().is_empty());
+
+        let mut new = current.clone();
+        new.logger.level = Level::Debug;
+        new.storage.prune_window = Some(1_000);
+        new.full_node_networks[0]
+            .seeds
+            .insert(PeerId::random(), Peer::default());
+        let reload = reloadable_changes(&current, &new).unwrap();
+        assert_eq!(reload.log_level, Some(Level::Debug));
+        assert_eq!(reload.prune_window, Some(1_000));
+        assert_eq!(reload.seeds, vec!["Public".to_string()]);
+        assert_eq!(reload.upstream, None);
+
+        new.base.role = crate::config::RoleType::Validator;
+        new.full_node_networks[0].max_inbound_connections += 1;
+        let error = reloadable_changes(&current, &new).unwrap_err().to_string();
+        assert!(error.contains("base.role"));
+        assert!(error.contains("full_node_networks[0].max_inbound_connections"));
+
+        let mut new = current.clone();
+        new.storage.prune_window = None;
+        assert!(reloadable_changes(&current, &new).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Site-specific overrides of generated node configs.
+//!
+//! A template is a partial node config, deep merged over a generated one: mappings are merged key
+//! by key, sequences element by element, and any other template value replaces the generated one.
+//! Every replaced value is reported. A template value of another shape than the generated one,
+//! e.g. a mapping over a string, is a conflict and fails the merge.
+
+use crate::config::{Error, NodeConfig};
+use serde_yaml::Value;
+
+/// The result of merging a template
+#[derive(Debug)]
+pub struct Merge {
+    pub config: NodeConfig,
+    /// The generated values the template replaced, e.g., `storage.prune_window: 100000 -> 50000`
+    pub overrides: Vec<String>,
+}
+
+/// Merges `template` over `config`
+pub fn merge_template(config: &NodeConfig, template: &Value) -> Result<Merge, Error> {
+    let mut value =
+        serde_yaml::to_value(config).map_err(|e| Error::Yaml("node config".to_string(), e))?;
+    let mut overrides = vec![];
+    merge(&mut value, template, "", &mut overrides)?;
+    let config = serde_yaml::from_value(value)
+        .map_err(|e| Error::Yaml("templated config".to_string(), e))?;
+    Ok(Merge { config, overrides })
+}
+
+fn merge(
+    generated: &mut Value,
+    template: &Value,
+    path: &str,
+    overrides: &mut Vec<String>,
+) -> Result<(), Error> {
+    match (&mut *generated, template) {
+        (Value::Mapping(generated), Value::Mapping(template)) => {
+            for (key, value) in template {
+                let path = join(path, &display(key));
+                match generated.get_mut(key) {
+                    Some(existing) => merge(existing, value, &path, overrides)?,
+                    None => {
+                        generated.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (Value::Sequence(generated), Value::Sequence(template)) => {
+            for (index, value) in template.iter().enumerate() {
+                let path = format!("{}[{}]", path, index);
+                match generated.get_mut(index) {
+                    Some(existing) => merge(existing, value, &path, overrides)?,
+                    None => generated.push(value.clone()),
+                }
+            }
+        }
+        (existing, template) => {
+            if existing == template {
+                return Ok(());
+            }
+            // Optional sections are null when absent, and setting null removes them
+            if !existing.is_null()
+                && !template.is_null()
+                && (is_structured(existing) || is_structured(template))
+            {
+                return Err(Error::InvariantViolation(format!(
+                    "Template conflicts with the generated config at {}: {} over {}",
+                    path,
+                    display(template),
+                    display(existing)
+                )));
+            }
+            overrides.push(format!(
+                "{}: {} -> {}",
+                path,
+                display(existing),
+                display(template)
+            ));
+            *existing = template.clone();
+        }
+    }
+    Ok(())
+}
+
+fn is_structured(value: &Value) -> bool {
+    matches!(value, Value::Mapping(_) | Value::Sequence(_))
+}
+
+pub(crate) fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+pub(crate) fn display(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => serde_yaml::to_string(value)
+            .map(|yaml| yaml.trim_start_matches("---").trim().replace('\n', " "))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::NetworkConfig;
+    use crate::network_id::NetworkId;
+
+    #[test]
+    fn test_merge_template() {
+        let mut config = NodeConfig::default();
+        config.storage.prune_window = Some(100_000);
+        config.full_node_networks = vec![
+            NetworkConfig::network_with_id(NetworkId::vfn_network()),
+            NetworkConfig::network_with_id(NetworkId::Public),
+        ];
+        let template: Value = serde_yaml::from_str(
+            r#"
+storage:
+  prune_window: 50000
+logger:
+  level: DEBUG
+full_node_networks:
+  - {}
+  - listen_address: "/ip4/10.0.0.1/tcp/6182"
+"#,
+        )
+        .unwrap();
+
+        let merge = merge_template(&config, &template).unwrap();
+        assert_eq!(merge.config.storage.prune_window, Some(50_000));
+        assert_eq!(
+            merge.config.full_node_networks[1]
+                .listen_address
+                .to_string(),
+            "/ip4/10.0.0.1/tcp/6182"
+        );
+        // Untouched elements of a sequence are kept
+        assert_eq!(
+            merge.config.full_node_networks[0],
+            config.full_node_networks[0]
+        );
+        assert_eq!(merge.config.json_rpc, config.json_rpc);
+        assert_eq!(merge.overrides.len(), 3);
+        assert!(merge
+            .overrides
+            .contains(&"storage.prune_window: 100000 -> 50000".to_string()));
+
+        // Applying it again changes nothing
+        let again = merge_template(&merge.config, &template).unwrap();
+        assert!(again.overrides.is_empty());
+    }
+
+    #[test]
+    fn test_template_conflict() {
+        let template: Value = serde_yaml::from_str("storage: /opt/storage").unwrap();
+        let error = merge_template(&NodeConfig::default(), &template).unwrap_err();
+        assert!(error.to_string().contains("at storage"));
+    }
+}
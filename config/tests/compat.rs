@@ -0,0 +1,76 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Verifies the configs stored under `tests/compat/` against the current serialization. New
+//! goldens are added at release time with `diem-genesis-tool node-config generate-goldens`.
+
+use diem_config::{
+    compat::{golden_configs, parse_version},
+    config::{NodeConfig, PersistableConfig},
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+fn compat_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/compat")
+}
+
+/// All release directories, oldest first
+fn versions() -> Vec<(Vec<u64>, PathBuf)> {
+    let mut versions: Vec<_> = fs::read_dir(compat_dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            let version = parse_version(&name)
+                .unwrap_or_else(|| panic!("Unexpected compat directory {}", path.display()));
+            (version, path)
+        })
+        .collect();
+    versions.sort();
+    versions
+}
+
+/// The version is expected to change on every release, so it is not part of the comparison
+fn without_version(mut value: serde_yaml::Value) -> serde_yaml::Value {
+    if let Some(base) = value.get_mut("base").and_then(|b| b.as_mapping_mut()) {
+        base.remove(&serde_yaml::Value::from("config_version"));
+    }
+    value
+}
+
+#[test]
+fn test_deserialize_all_goldens() {
+    let versions = versions();
+    assert!(!versions.is_empty(), "No goldens in {:?}", compat_dir());
+    for (_, dir) in versions {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let contents = fs::read_to_string(&path).unwrap();
+            NodeConfig::parse(&contents)
+                .unwrap_or_else(|e| panic!("Unable to deserialize {}: {}", path.display(), e));
+        }
+    }
+}
+
+#[test]
+fn test_newest_goldens_match() {
+    let (_, newest) = versions().pop().unwrap();
+    for (name, config) in golden_configs() {
+        let path = newest.join(name);
+        let contents = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Missing golden {}: {}", path.display(), e));
+        let expected: serde_yaml::Value = serde_yaml::from_str(&contents).unwrap();
+        let actual = serde_yaml::to_value(&config).unwrap();
+        assert_eq!(
+            without_version(expected),
+            without_version(actual),
+            "The serialization of {} changed, if this is intended add new goldens",
+            name
+        );
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Wire compatibility of network identifiers between node versions.
+//!
+//! Network ids are exchanged between peers and stored in configs, so nodes running different
+//! releases must agree on their encoding. The `legacy` module freezes the encoding of the released
+//! types. Every value is encoded with one side and decoded with the other, in all formats in use.
+//! Changes to the encoding of the current types need a migration path that keeps these tests
+//! passing; the legacy types must not be changed.
+
+use diem_config::{
+    config::{PeerNetworkId, UpstreamConfig},
+    network_id::{NetworkId, NodeNetworkId},
+};
+use diem_types::PeerId;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+
+/// The encoding of the released network identifiers
+mod legacy {
+    use diem_types::PeerId;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    #[serde(rename = "NetworkId", rename_all = "snake_case")]
+    pub enum NetworkId {
+        Validator,
+        Public,
+        Private(String),
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    pub struct NodeNetworkId(pub NetworkId, pub usize);
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    pub struct PeerNetworkId(pub NodeNetworkId, pub PeerId);
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    pub struct UpstreamConfig {
+        pub networks: Vec<NetworkId>,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Bcs,
+    Json,
+    Yaml,
+}
+
+const FORMATS: &[Format] = &[Format::Bcs, Format::Json, Format::Yaml];
+
+fn encode<T: Serialize>(format: Format, value: &T) -> Vec<u8> {
+    match format {
+        Format::Bcs => bcs::to_bytes(value).unwrap(),
+        Format::Json => serde_json::to_vec(value).unwrap(),
+        Format::Yaml => serde_yaml::to_vec(value).unwrap(),
+    }
+}
+
+fn decode<T: DeserializeOwned>(format: Format, bytes: &[u8]) -> T {
+    match format {
+        Format::Bcs => bcs::from_bytes(bytes).unwrap(),
+        Format::Json => serde_json::from_slice(bytes).unwrap(),
+        Format::Yaml => serde_yaml::from_slice(bytes).unwrap(),
+    }
+}
+
+/// Encodes with one version and decodes with the other, in both directions and all formats
+fn assert_compatible<L, C>(legacy: &L, current: &C)
+where
+    L: Debug + DeserializeOwned + PartialEq + Serialize,
+    C: Debug + DeserializeOwned + PartialEq + Serialize,
+{
+    for format in FORMATS {
+        let decoded: C = decode(*format, &encode(*format, legacy));
+        assert_eq!(&decoded, current, "legacy -> current ({:?})", format);
+        let decoded: L = decode(*format, &encode(*format, current));
+        assert_eq!(&decoded, legacy, "current -> legacy ({:?})", format);
+    }
+}
+
+fn network_ids() -> Vec<(legacy::NetworkId, NetworkId)> {
+    vec![
+        (legacy::NetworkId::Validator, NetworkId::Validator),
+        (legacy::NetworkId::Public, NetworkId::Public),
+        (
+            legacy::NetworkId::Private("vfn".into()),
+            NetworkId::vfn_network(),
+        ),
+        (
+            legacy::NetworkId::Private("Validator".into()),
+            NetworkId::Private("Validator".into()),
+        ),
+        (
+            legacy::NetworkId::Private("".into()),
+            NetworkId::Private("".into()),
+        ),
+    ]
+}
+
+#[test]
+fn test_network_id_compatibility() {
+    for (legacy, current) in network_ids() {
+        assert_compatible(&legacy, &current);
+    }
+}
+
+#[test]
+fn test_peer_network_id_compatibility() {
+    let peer_id = PeerId::random();
+    for (num_id, (legacy, current)) in network_ids().into_iter().enumerate() {
+        assert_compatible(
+            &legacy::NodeNetworkId(legacy.clone(), num_id),
+            &NodeNetworkId::new(current.clone(), num_id),
+        );
+        assert_compatible(
+            &legacy::PeerNetworkId(legacy::NodeNetworkId(legacy, num_id), peer_id),
+            &PeerNetworkId(NodeNetworkId::new(current, num_id), peer_id),
+        );
+    }
+}
+
+#[test]
+fn test_upstream_config_compatibility() {
+    let (legacy, current): (Vec<_>, Vec<_>) = network_ids().into_iter().unzip();
+    assert_compatible(
+        &legacy::UpstreamConfig { networks: legacy },
+        &UpstreamConfig { networks: current },
+    );
+}
+
+/// The BCS encoding is exchanged between peers, so it is pinned down to the byte
+#[test]
+fn test_network_id_bcs_bytes() {
+    assert_eq!(bcs::to_bytes(&NetworkId::Validator).unwrap(), vec![0]);
+    assert_eq!(bcs::to_bytes(&NetworkId::Public).unwrap(), vec![1]);
+    assert_eq!(
+        bcs::to_bytes(&NetworkId::vfn_network()).unwrap(),
+        vec![2, 3, b'v', b'f', b'n']
+    );
+}
+
+/// Configs written by hand must keep parsing
+#[test]
+fn test_network_id_yaml() {
+    let networks: Vec<NetworkId> =
+        serde_yaml::from_str("[validator, public, {private: vfn}]").unwrap();
+    assert_eq!(
+        networks,
+        vec![
+            NetworkId::Validator,
+            NetworkId::Public,
+            NetworkId::vfn_network()
+        ]
+    );
+}
@@ -39,3 +39,21 @@ impl fmt::Display for SafetyData {
         )
     }
 }
+
+//////// 0L ////////
+/// The lease of the instance of a validator failover pair that may sign, see `FailoverConfig`
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FailoverLease {
+    /// The `instance_id` of the holder
+    pub holder: String,
+    /// Milliseconds since the Unix epoch after which the lease may be taken over
+    pub expires_at_ms: u64,
+}
+
+impl FailoverLease {
+    /// Whether `instance_id` may take the lease at `now_ms`: it holds it already or it expired at
+    /// least `margin_ms` ago, allowing for the clock of the holder being behind
+    pub fn available_to(&self, instance_id: &str, now_ms: u64, margin_ms: u64) -> bool {
+        self.holder == instance_id || self.expires_at_ms.saturating_add(margin_ms) <= now_ms
+    }
+}
@@ -41,6 +41,9 @@ pub enum Error {
     ValidatorNotInSet(String),
     #[error("Vote proposal missing expected signature")]
     VoteProposalSignatureNotFound,
+    //////// 0L ////////
+    #[error("Not the holder of the failover lease, {0} is")]
+    NotLeaseHolder(String),
 }
 
 impl From<bcs::Error> for Error {
@@ -0,0 +1,188 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The lease of an active/standby validator pair, see `FailoverConfig`. Both instances run
+//! consensus, but only the holder of the lease signs: it renews the lease with every signature, and
+//! the standby takes it over once the active instance stopped renewing it for a lease duration,
+//! and the clock skew margin on top of it.
+
+use crate::{counters, Error};
+use consensus_types::safety_data::FailoverLease;
+use diem_config::config::{FailoverConfig, LeaseBackend};
+use diem_global_constants::FAILOVER_LEASE;
+use diem_logger::prelude::*;
+use diem_secure_storage::{KVStorage, Storage};
+use fs2::FileExt;
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub(crate) struct Failover {
+    instance_id: String,
+    lease_duration_ms: u64,
+    max_clock_skew_ms: u64,
+    lease: LeaseBackend,
+    /// Whether this instance got the lease the last time it tried
+    held: bool,
+}
+
+impl Failover {
+    pub fn new(config: &FailoverConfig) -> Self {
+        Self {
+            instance_id: config.instance_id.clone(),
+            lease_duration_ms: config.lease_duration_ms,
+            max_clock_skew_ms: config.max_clock_skew_ms,
+            lease: config.lease.clone(),
+            held: false,
+        }
+    }
+
+    /// Takes or renews the lease at `now_ms`, returning whether it was last held by another
+    /// instance, which may have updated the safety data since
+    pub fn acquire(&mut self, storage: &mut Storage, now_ms: u64) -> Result<bool, Error> {
+        let (instance_id, margin_ms) = (&self.instance_id, self.max_clock_skew_ms);
+        let lease = FailoverLease {
+            holder: instance_id.clone(),
+            expires_at_ms: now_ms.saturating_add(self.lease_duration_ms),
+        };
+        let check = |current: Option<FailoverLease>| match current {
+            Some(current) if !current.available_to(instance_id, now_ms, margin_ms) => {
+                Err(Error::NotLeaseHolder(current.holder))
+            }
+            current => Ok(current.map_or(true, |current| &current.holder != instance_id)),
+        };
+        let result = match &self.lease {
+            LeaseBackend::File { path } => {
+                update_file(path, |current| Ok((check(current)?, lease)))
+            }
+            LeaseBackend::SecureBackend => {
+                let current = match storage.get::<FailoverLease>(FAILOVER_LEASE) {
+                    Ok(response) => Some(response.value),
+                    Err(diem_secure_storage::Error::KeyNotSet(_)) => None,
+                    Err(error) => return Err(error.into()),
+                };
+                // The write is a check-and-set against the version just read, which validation
+                // requires of the backend
+                check(current).and_then(|took_over| {
+                    storage.set(FAILOVER_LEASE, lease)?;
+                    Ok(took_over)
+                })
+            }
+        };
+
+        if result.is_ok() != self.held {
+            self.held = result.is_ok();
+            counters::set_state("failover_lease_held", self.held as i64);
+            match &result {
+                Ok(_) => info!("Took the failover lease as {}", self.instance_id),
+                Err(error) => warn!("Lost the failover lease: {}", error),
+            }
+        }
+        result
+    }
+}
+
+/// Milliseconds since the Unix epoch, the clock of the lease expiry
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the Unix epoch")
+        .as_millis() as u64
+}
+
+/// Replaces the lease in the file, holding an exclusive lock so the other instance can't
+/// interleave its own update
+fn update_file<R>(
+    path: &Path,
+    update: impl FnOnce(Option<FailoverLease>) -> Result<(R, FailoverLease), Error>,
+) -> Result<R, Error> {
+    let io_error =
+        |error: std::io::Error| Error::InternalError(format!("{}: {}", path.display(), error));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(path)
+        .map_err(io_error)?;
+    // Released when the file is closed
+    file.lock_exclusive().map_err(io_error)?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(io_error)?;
+    let current = if contents.is_empty() {
+        None
+    } else {
+        Some(
+            serde_json::from_str(&contents)
+                .map_err(|error| Error::SerializationError(error.to_string()))?,
+        )
+    };
+
+    let (result, lease) = update(current)?;
+    let contents =
+        serde_json::to_vec(&lease).map_err(|error| Error::SerializationError(error.to_string()))?;
+    file.set_len(0).map_err(io_error)?;
+    file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+    file.write_all(&contents).map_err(io_error)?;
+    file.sync_all().map_err(io_error)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_secure_storage::InMemoryStorage;
+    use diem_temppath::TempPath;
+
+    fn failover(instance_id: &str, lease: LeaseBackend) -> Failover {
+        Failover::new(&FailoverConfig {
+            instance_id: instance_id.into(),
+            lease_duration_ms: 1_000,
+            max_clock_skew_ms: 100,
+            lease,
+        })
+    }
+
+    fn test_lease(lease: LeaseBackend) {
+        let mut storage = Storage::from(InMemoryStorage::new());
+        let mut active = failover("a", lease.clone());
+        let mut standby = failover("b", lease);
+
+        assert_eq!(active.acquire(&mut storage, 0), Ok(true));
+        assert_eq!(
+            standby.acquire(&mut storage, 500),
+            Err(Error::NotLeaseHolder("a".into()))
+        );
+        // Renewing the lease doesn't count as a takeover
+        assert_eq!(active.acquire(&mut storage, 900), Ok(false));
+        standby.acquire(&mut storage, 1_500).unwrap_err();
+
+        // The active instance stopped renewing the lease, which expired within the skew margin
+        standby.acquire(&mut storage, 1_950).unwrap_err();
+        assert_eq!(standby.acquire(&mut storage, 2_000), Ok(true));
+        assert_eq!(
+            active.acquire(&mut storage, 2_100),
+            Err(Error::NotLeaseHolder("b".into()))
+        );
+        assert_eq!(active.acquire(&mut storage, 3_100), Ok(true));
+        // Nobody else held the lease since it expired
+        assert_eq!(active.acquire(&mut storage, 5_000), Ok(false));
+    }
+
+    #[test]
+    fn test_file_lease() {
+        let path = TempPath::new();
+        test_lease(LeaseBackend::File {
+            path: path.path().to_path_buf(),
+        });
+    }
+
+    #[test]
+    fn test_secure_backend_lease() {
+        test_lease(LeaseBackend::SecureBackend);
+    }
+}
@@ -240,6 +240,7 @@ pub fn arb_safety_rules_input() -> impl Strategy<Value = SafetyRulesInput> {
             .prop_map(|input| { SafetyRulesInput::ConstructAndSignVote(Box::new(input)) }),
         arb_block_data().prop_map(|input| { SafetyRulesInput::SignProposal(Box::new(input)) }),
         arb_timeout().prop_map(|input| { SafetyRulesInput::SignTimeout(Box::new(input)) }),
+        Just(SafetyRulesInput::Ping),
     ]
 }
 
@@ -7,6 +7,7 @@ mod configurable_validator_signer;
 mod consensus_state;
 mod counters;
 mod error;
+mod failover;
 mod local_client;
 mod logging;
 mod persistent_safety_storage;
@@ -3,10 +3,12 @@
 
 use crate::{
     counters,
+    failover::{self, Failover},
     logging::{self, LogEntry, LogEvent},
     Error,
 };
 use consensus_types::{common::Author, safety_data::SafetyData};
+use diem_config::config::FailoverConfig;
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
@@ -29,6 +31,8 @@ pub struct PersistentSafetyStorage {
     enable_cached_safety_data: bool,
     cached_safety_data: Option<SafetyData>,
     internal_store: Storage,
+    //////// 0L ////////
+    failover: Option<Failover>,
 }
 
 impl PersistentSafetyStorage {
@@ -56,6 +60,7 @@ impl PersistentSafetyStorage {
             enable_cached_safety_data,
             cached_safety_data: Some(safety_data),
             internal_store,
+            failover: None,
         }
     }
 
@@ -92,9 +97,29 @@ impl PersistentSafetyStorage {
             enable_cached_safety_data,
             cached_safety_data: None,
             internal_store,
+            failover: None,
         }
     }
 
+    //////// 0L ////////
+    /// Signs only while holding the failover lease, see `acquire_lease`
+    pub fn with_failover(mut self, config: &FailoverConfig) -> Self {
+        self.failover = Some(Failover::new(config));
+        self
+    }
+
+    /// Takes or renews the failover lease before signing, if failover is configured. The other
+    /// instance may have updated the safety data while it held the lease, so the cache is dropped
+    /// on takeover.
+    pub fn acquire_lease(&mut self) -> Result<(), Error> {
+        if let Some(failover) = &mut self.failover {
+            if failover.acquire(&mut self.internal_store, failover::now_ms())? {
+                self.cached_safety_data = None;
+            }
+        }
+        Ok(())
+    }
+
     pub fn author(&self) -> Result<Author, Error> {
         let _timer = counters::start_timer("get", OWNER_ACCOUNT);
         Ok(self.internal_store.get(OWNER_ACCOUNT).map(|v| v.value)?)
@@ -6,7 +6,9 @@ use crate::{
     remote_service::{self, RemoteService},
     safety_rules_manager,
 };
-use diem_config::config::{SafetyRulesConfig, SafetyRulesService};
+use diem_config::config::{
+    MutualTlsConfig, RemoteService as RemoteServiceConfig, SafetyRulesConfig, SafetyRulesService,
+};
 
 use std::net::SocketAddr;
 
@@ -33,6 +35,7 @@ impl Process {
                 verify_vote_proposal_signature,
                 export_consensus_key,
                 network_timeout: config.network_timeout_ms,
+                tls: service.tls.clone(),
             }),
         }
     }
@@ -45,6 +48,7 @@ impl Process {
             data.verify_vote_proposal_signature,
             data.export_consensus_key,
             data.network_timeout,
+            data.tls.as_ref(),
         );
     }
 }
@@ -56,18 +60,25 @@ struct ProcessData {
     export_consensus_key: bool,
     // Timeout in Seconds for network operations
     network_timeout: u64,
+    //////// 0L ////////
+    tls: Option<MutualTlsConfig>,
 }
 
 pub struct ProcessService {
     server_addr: SocketAddr,
     network_timeout_ms: u64,
+    //////// 0L ////////
+    tls: Option<MutualTlsConfig>,
+    health_check_interval_ms: Option<u64>,
 }
 
 impl ProcessService {
-    pub fn new(server_addr: SocketAddr, network_timeout: u64) -> Self {
+    pub fn new(config: &RemoteServiceConfig, network_timeout: u64) -> Self {
         Self {
-            server_addr,
+            server_addr: config.server_address(),
             network_timeout_ms: network_timeout,
+            tls: config.tls.clone(),
+            health_check_interval_ms: config.health_check_interval_ms,
         }
     }
 }
@@ -80,4 +91,12 @@ impl RemoteService for ProcessService {
     fn network_timeout_ms(&self) -> u64 {
         self.network_timeout_ms
     }
+
+    fn tls(&self) -> Option<&MutualTlsConfig> {
+        self.tls.as_ref()
+    }
+
+    fn health_check_interval_ms(&self) -> Option<u64> {
+        self.health_check_interval_ms
+    }
 }
@@ -2,22 +2,39 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    counters,
     persistent_safety_storage::PersistentSafetyStorage,
     serializer::{SafetyRulesInput, SerializerClient, SerializerService, TSerializerClient},
     Error, SafetyRules, TSafetyRules,
 };
+use diem_config::config::MutualTlsConfig;
 use diem_logger::warn;
-use diem_secure_net::{NetworkClient, NetworkServer};
-use std::net::SocketAddr;
+use diem_secure_net::{ClientTls, NetworkClient, NetworkServer, ServerTls};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 pub trait RemoteService {
     fn client(&self) -> SerializerClient {
-        let network_client = NetworkClient::new(
+        let mut network_client = NetworkClient::new(
             "safety-rules",
             self.server_address(),
             self.network_timeout_ms(),
         );
-        let service = Box::new(RemoteClient::new(network_client));
+        //////// 0L ////////
+        if let Some(tls) = self.tls() {
+            let tls = ClientTls::new(
+                &tls.cert_path,
+                &tls.key_path,
+                &tls.ca_cert_path,
+                &tls.server_name,
+            )
+            .expect("Invalid SafetyRules TLS config");
+            network_client = network_client.with_tls(tls);
+        }
+        let health_check_interval = self.health_check_interval_ms().map(Duration::from_millis);
+        let service = Box::new(RemoteClient::new(network_client, health_check_interval));
         SerializerClient::new_client(service)
     }
 
@@ -25,6 +42,17 @@ pub trait RemoteService {
 
     /// Network Timeout in milliseconds.
     fn network_timeout_ms(&self) -> u64;
+
+    //////// 0L ////////
+    /// Mutually authenticated TLS with the server, see `MutualTlsConfig`
+    fn tls(&self) -> Option<&MutualTlsConfig> {
+        None
+    }
+
+    /// Idle time in milliseconds after which the connection is checked before a request
+    fn health_check_interval_ms(&self) -> Option<u64> {
+        None
+    }
 }
 
 pub fn execute(
@@ -33,6 +61,7 @@ pub fn execute(
     verify_vote_proposal_signature: bool,
     export_consensus_key: bool,
     network_timeout_ms: u64,
+    tls: Option<&MutualTlsConfig>,
 ) {
     let mut safety_rules = SafetyRules::new(
         storage,
@@ -45,6 +74,17 @@ pub fn execute(
 
     let mut serializer_service = SerializerService::new(safety_rules);
     let mut network_server = NetworkServer::new("safety-rules", listen_addr, network_timeout_ms);
+    //////// 0L ////////
+    if let Some(tls) = tls {
+        let tls = ServerTls::new(
+            &tls.cert_path,
+            &tls.key_path,
+            &tls.ca_cert_path,
+            tls.allowed_clients.clone(),
+        )
+        .expect("Invalid SafetyRules TLS config");
+        network_server = network_server.with_tls(tls);
+    }
 
     loop {
         if let Err(e) = process_one_message(&mut network_server, &mut serializer_service) {
@@ -65,16 +105,41 @@ fn process_one_message(
 
 struct RemoteClient {
     network_client: NetworkClient,
+    //////// 0L ////////
+    health_check_interval: Option<Duration>,
+    /// When the service last answered
+    last_response: Option<Instant>,
 }
 
 impl RemoteClient {
-    pub fn new(network_client: NetworkClient) -> Self {
-        Self { network_client }
+    pub fn new(network_client: NetworkClient, health_check_interval: Option<Duration>) -> Self {
+        Self {
+            network_client,
+            health_check_interval,
+            last_response: None,
+        }
     }
 
     fn process_one_message(&mut self, input: &[u8]) -> Result<Vec<u8>, Error> {
         self.network_client.write(&input)?;
-        self.network_client.read().map_err(|e| e.into())
+        let response = self.network_client.read()?;
+        self.last_response = Some(Instant::now());
+        Ok(response)
+    }
+
+    //////// 0L ////////
+    /// Pings the service over a connection that has been idle for longer than the health check
+    /// interval, so that a stale connection is replaced before a request is sent over it
+    fn check_health(&mut self) -> Result<(), Error> {
+        let idle = match (self.health_check_interval, self.last_response) {
+            (Some(interval), Some(last_response)) => last_response.elapsed() >= interval,
+            _ => false,
+        };
+        if !idle {
+            return Ok(());
+        }
+        let response = self.process_one_message(&bcs::to_bytes(&SafetyRulesInput::Ping)?)?;
+        bcs::from_bytes::<Result<(), Error>>(&response)?
     }
 }
 
@@ -82,7 +147,11 @@ impl TSerializerClient for RemoteClient {
     fn request(&mut self, input: SafetyRulesInput) -> Result<Vec<u8>, Error> {
         let input_message = bcs::to_bytes(&input)?;
         loop {
-            match self.process_one_message(&input_message) {
+            let result = self
+                .check_health()
+                .and_then(|_| self.process_one_message(&input_message));
+            counters::set_state("remote_service_healthy", result.is_ok() as i64);
+            match result {
                 Err(err) => warn!("Failed to communicate with SafetyRules service: {}", err),
                 Ok(value) => return Ok(value),
             }
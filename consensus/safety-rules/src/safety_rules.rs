@@ -339,6 +339,8 @@ impl SafetyRules {
     ) -> Result<Vote, Error> {
         // Exit early if we cannot sign
         self.signer()?;
+        //////// 0L ////////
+        self.persistent_storage.acquire_lease()?;
 
         let vote_proposal = &maybe_signed_vote_proposal.vote_proposal;
         let execution_signature = maybe_signed_vote_proposal.signature.as_ref();
@@ -389,6 +391,8 @@ impl SafetyRules {
 
     fn guarded_sign_proposal(&mut self, block_data: BlockData) -> Result<Block, Error> {
         self.signer()?;
+        //////// 0L ////////
+        self.persistent_storage.acquire_lease()?;
         self.verify_author(block_data.author())?;
 
         let mut safety_data = self.persistent_storage.safety_data()?;
@@ -415,6 +419,8 @@ impl SafetyRules {
 
     fn guarded_sign_timeout(&mut self, timeout: &Timeout) -> Result<Ed25519Signature, Error> {
         self.signer()?;
+        //////// 0L ////////
+        self.persistent_storage.acquire_lease()?;
 
         let mut safety_data = self.persistent_storage.safety_data()?;
         self.verify_epoch(timeout.epoch(), &safety_data)?;
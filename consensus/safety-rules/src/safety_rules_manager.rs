@@ -10,10 +10,12 @@ use crate::{
     thread::ThreadService,
     SafetyRules, TSafetyRules,
 };
-use diem_config::config::{SafetyRulesConfig, SafetyRulesService};
+use diem_config::config::{
+    RemoteService as RemoteServiceConfig, SafetyRulesConfig, SafetyRulesService,
+};
 use diem_infallible::RwLock;
 use diem_secure_storage::{KVStorage, Storage};
-use std::{convert::TryInto, net::SocketAddr, sync::Arc};
+use std::{convert::TryInto, sync::Arc};
 
 pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
     let backend = &config.backend;
@@ -22,7 +24,7 @@ pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
         panic!("Storage is not available: {:?}", error);
     }
 
-    if let Some(test_config) = &config.test {
+    let storage = if let Some(test_config) = &config.test {
         let author = test_config.author;
         let consensus_private_key = test_config
             .consensus_key
@@ -46,6 +48,11 @@ pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
         )
     } else {
         PersistentSafetyStorage::new(internal_storage, config.enable_cached_safety_data)
+    };
+    //////// 0L ////////
+    match &config.failover {
+        Some(failover) => storage.with_failover(failover),
+        None => storage,
     }
 }
 
@@ -63,7 +70,7 @@ pub struct SafetyRulesManager {
 impl SafetyRulesManager {
     pub fn new(config: &SafetyRulesConfig) -> Self {
         if let SafetyRulesService::Process(conf) = &config.service {
-            return Self::new_process(conf.server_address(), config.network_timeout_ms);
+            return Self::new_process(conf, config.network_timeout_ms);
         }
 
         let storage = storage(config);
@@ -105,8 +112,8 @@ impl SafetyRulesManager {
         }
     }
 
-    pub fn new_process(server_addr: SocketAddr, timeout_ms: u64) -> Self {
-        let process_service = ProcessService::new(server_addr, timeout_ms);
+    pub fn new_process(config: &RemoteServiceConfig, timeout_ms: u64) -> Self {
+        let process_service = ProcessService::new(config, timeout_ms);
         Self {
             internal_safety_rules: SafetyRulesWrapper::Process(process_service),
         }
@@ -19,6 +19,9 @@ pub enum SafetyRulesInput {
     ConstructAndSignVote(Box<MaybeSignedVoteProposal>),
     SignProposal(Box<BlockData>),
     SignTimeout(Box<Timeout>),
+    //////// 0L ////////
+    /// Health check of the connection to a remote service
+    Ping,
 }
 
 pub struct SerializerService {
@@ -45,6 +48,7 @@ impl SerializerService {
             SafetyRulesInput::SignTimeout(timeout) => {
                 bcs::to_bytes(&self.internal.sign_timeout(&timeout))
             }
+            SafetyRulesInput::Ping => bcs::to_bytes(&Ok::<(), Error>(())),
         };
 
         Ok(output?)
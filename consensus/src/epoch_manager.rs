@@ -169,13 +169,19 @@ impl EpochManager {
                 ))
             }
             ConsensusProposerType::LeaderReputation(heuristic_config) => {
-                let backend = Box::new(DiemDBBackend::new(proposers.len(), self.storage.diem_db()));
+                let window_size = heuristic_config.window_size.unwrap_or(proposers.len());
+                let backend = Box::new(DiemDBBackend::new(window_size, self.storage.diem_db()));
                 let heuristic = Box::new(ActiveInactiveHeuristic::new(
                     self.author,
                     heuristic_config.active_weights,
                     heuristic_config.inactive_weights,
                 ));
-                Box::new(LeaderReputation::new(proposers, backend, heuristic))
+                Box::new(LeaderReputation::new(
+                    proposers,
+                    backend,
+                    heuristic,
+                    heuristic_config.exclude_round,
+                ))
             }
             ConsensusProposerType::RoundProposer(round_proposers) => {
                 // Hardcoded to the first proposer
@@ -0,0 +1,10 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+fn main() {
+    tonic_build::configure()
+        .build_client(false)
+        .compile(&["proto/admin.proto"], &["proto/"])
+        .unwrap();
+}
@@ -0,0 +1,429 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! gRPC admin service to control a running node, see `AdminServiceConfig::grpc_address` and
+//! `proto/admin.proto`. Calls must carry the token of the admin service as
+//! `authorization: Bearer <token>` metadata.
+
+mod proto {
+    tonic::include_proto!("admin");
+}
+
+use diem_config::{
+    config::{NodeConfig, Peer, PeerRole, PeerSet},
+    network_id::NetworkContext,
+};
+use diem_infallible::Mutex;
+use diem_logger::{prelude::*, Filter, Logger};
+use diem_types::{network_address::NetworkAddress, PeerId};
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    peer_manager::ConnectionRequestSender,
+};
+use proto::{
+    admin_server::{Admin, AdminServer},
+    AddSeedRequest, AddSeedResponse, DisconnectPeerRequest, DisconnectPeerResponse,
+    DumpNetworkContextRequest, DumpNetworkContextResponse, SetLogLevelRequest, SetLogLevelResponse,
+    TriggerStateSyncRequest, TriggerStateSyncResponse,
+};
+use state_sync::client::StateSyncClient;
+use std::{collections::HashMap, sync::Arc};
+use subtle::ConstantTimeEq;
+use tokio::runtime::{Builder, Runtime};
+use tonic::{transport::Server, Request, Response, Status};
+
+/// A network of the node, with the handles the service controls it through
+pub struct AdminNetwork {
+    pub context: Arc<NetworkContext>,
+    pub listen_addresses: Vec<NetworkAddress>,
+    pub connection_reqs: ConnectionRequestSender,
+    /// The connectivity manager, for networks with one
+    pub conn_mgr_reqs: Option<channel::Sender<ConnectivityRequest>>,
+}
+
+pub struct AdminGrpcService {
+    _runtime: Runtime,
+}
+
+struct AdminHandler {
+    networks: Vec<AdminNetwork>,
+    logger: Option<Arc<Logger>>,
+    state_sync: StateSyncClient,
+    /// The seeds added to each network, by label, sent again along with every seed added after
+    /// them
+    seeds: Mutex<HashMap<String, PeerSet>>,
+}
+
+impl AdminGrpcService {
+    /// Starts the service if the admin service is enabled with a gRPC address. Fails without a
+    /// token.
+    pub fn start(
+        node_config: &NodeConfig,
+        networks: Vec<AdminNetwork>,
+        logger: Option<Arc<Logger>>,
+        state_sync: StateSyncClient,
+    ) -> anyhow::Result<Option<Self>> {
+        let config = &node_config.admin_service;
+        let address = match config.grpc_address {
+            Some(address) if config.enabled => address,
+            _ => return Ok(None),
+        };
+        let token = match &config.token {
+            Some(token) => token.read_token()?,
+            None => anyhow::bail!("admin_service is enabled without a token"),
+        };
+        let expected = format!("Bearer {}", token);
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("admin-grpc")
+            .enable_all()
+            .build()?;
+
+        let handler = AdminHandler {
+            networks,
+            logger,
+            state_sync,
+            seeds: Mutex::new(HashMap::new()),
+        };
+        let service = AdminServer::with_interceptor(handler, move |request: Request<()>| {
+            authorize(&expected, request)
+        });
+        runtime.spawn(async move {
+            if let Err(e) = Server::builder().add_service(service).serve(address).await {
+                error!("Admin gRPC service stopped: {}", e);
+            }
+        });
+        info!("Admin gRPC service listening on {}", address);
+
+        Ok(Some(Self { _runtime: runtime }))
+    }
+}
+
+/// Lets through the calls carrying `expected` as authorization metadata, compared in constant
+/// time so the time to reject doesn't leak the token
+fn authorize(expected: &str, request: Request<()>) -> Result<Request<()>, Status> {
+    let authorized = request
+        .metadata()
+        .get("authorization")
+        .map_or(false, |value| {
+            value.as_bytes().ct_eq(expected.as_bytes()).into()
+        });
+    if authorized {
+        Ok(request)
+    } else {
+        Err(Status::unauthenticated("missing or wrong admin token"))
+    }
+}
+
+impl AdminHandler {
+    fn network(&self, name: &str) -> Result<&AdminNetwork, Status> {
+        self.networks
+            .iter()
+            .find(|network| network.context.network_label() == name)
+            .ok_or_else(|| Status::not_found(format!("the node has no {} network", name)))
+    }
+}
+
+fn parse_peer_id(peer_id: &str) -> Result<PeerId, Status> {
+    peer_id
+        .parse()
+        .map_err(|e| Status::invalid_argument(format!("invalid peer id {}: {}", peer_id, e)))
+}
+
+/// The seed of an add seed request, with the keys of its diemnet addresses
+fn parse_seed(request: &AddSeedRequest) -> Result<Peer, Status> {
+    let role = if request.role.is_empty() {
+        PeerRole::Upstream
+    } else {
+        request.role.parse().map_err(|e| {
+            Status::invalid_argument(format!("invalid role {}: {}", request.role, e))
+        })?
+    };
+    let addresses = request
+        .addresses
+        .iter()
+        .map(|address| match address.parse::<NetworkAddress>() {
+            Ok(address) if address.is_diemnet_addr() => Ok(address),
+            _ => Err(Status::invalid_argument(format!(
+                "{} is not a diemnet address, with the noise key of the peer",
+                address
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if addresses.is_empty() {
+        return Err(Status::invalid_argument("a seed needs an address"));
+    }
+    Ok(Peer::from_addrs(role, addresses))
+}
+
+#[tonic::async_trait]
+impl Admin for AdminHandler {
+    async fn disconnect_peer(
+        &self,
+        request: Request<DisconnectPeerRequest>,
+    ) -> Result<Response<DisconnectPeerResponse>, Status> {
+        let request = request.into_inner();
+        let network = self.network(&request.network)?;
+        let peer_id = parse_peer_id(&request.peer_id)?;
+        info!("Admin disconnecting {} from {}", peer_id, network.context);
+        network
+            .connection_reqs
+            .clone()
+            .disconnect_peer(peer_id)
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+        Ok(Response::new(DisconnectPeerResponse {}))
+    }
+
+    async fn add_seed(
+        &self,
+        request: Request<AddSeedRequest>,
+    ) -> Result<Response<AddSeedResponse>, Status> {
+        let request = request.into_inner();
+        let network = self.network(&request.network)?;
+        let peer_id = parse_peer_id(&request.peer_id)?;
+        let seed = parse_seed(&request)?;
+        let mut conn_mgr_reqs = network.conn_mgr_reqs.clone().ok_or_else(|| {
+            Status::failed_precondition(format!(
+                "{} has no connectivity manager to dial seeds",
+                network.context
+            ))
+        })?;
+
+        let mut seeds = self.seeds.lock();
+        let network_seeds = seeds
+            .entry(network.context.network_label().to_string())
+            .or_default();
+        network_seeds.insert(peer_id, seed);
+        conn_mgr_reqs
+            .try_send(ConnectivityRequest::UpdateDiscoveredPeers(
+                DiscoverySource::Admin,
+                network_seeds.clone(),
+            ))
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        info!("Admin added the seed {} to {}", peer_id, network.context);
+        Ok(Response::new(AddSeedResponse {}))
+    }
+
+    async fn set_log_level(
+        &self,
+        request: Request<SetLogLevelRequest>,
+    ) -> Result<Response<SetLogLevelResponse>, Status> {
+        let filter = request.into_inner().filter;
+        let logger = self
+            .logger
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("the node has no logger"))?;
+        info!(filter = filter, "Admin updating local logging filter");
+        logger.set_filter(Filter::builder().parse(&filter).build());
+        Ok(Response::new(SetLogLevelResponse {}))
+    }
+
+    async fn trigger_state_sync(
+        &self,
+        _request: Request<TriggerStateSyncRequest>,
+    ) -> Result<Response<TriggerStateSyncResponse>, Status> {
+        let state = self
+            .state_sync
+            .trigger_sync()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        Ok(Response::new(TriggerStateSyncResponse {
+            synced_version: state.synced_version(),
+            committed_version: state.committed_version(),
+            trusted_epoch: state.trusted_epoch(),
+        }))
+    }
+
+    async fn dump_network_context(
+        &self,
+        _request: Request<DumpNetworkContextRequest>,
+    ) -> Result<Response<DumpNetworkContextResponse>, Status> {
+        let networks = self
+            .networks
+            .iter()
+            .map(|network| proto::NetworkContext {
+                network: network.context.network_label().to_string(),
+                role: network.context.role().to_string(),
+                peer_id: network.context.peer_id().to_string(),
+                listen_addresses: network
+                    .listen_addresses
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+            })
+            .collect();
+        Ok(Response::new(DumpNetworkContextResponse { networks }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use channel::{diem_channel, message_queues::QueueStyle};
+    use diem_config::{config::RoleType, network_id::NetworkId};
+    use diem_types::chain_id::ChainId;
+    use futures::{channel::mpsc, StreamExt};
+    use network::peer_manager::ConnectionRequest;
+    use tonic::{metadata::MetadataValue, Code};
+
+    const SEED_ADDRESS: &str = "/ip4/1.2.3.4/tcp/6180/ln-noise-ik/080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120/ln-handshake/0";
+
+    /// A handler of a validator network, with the receivers of its connection and connectivity
+    /// requests
+    fn handler() -> (
+        AdminHandler,
+        diem_channel::Receiver<PeerId, ConnectionRequest>,
+        channel::Receiver<ConnectivityRequest>,
+    ) {
+        let (connection_reqs_tx, connection_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new_test(10);
+        let network = AdminNetwork {
+            context: Arc::new(NetworkContext::new(
+                RoleType::Validator,
+                NetworkId::Validator,
+                PeerId::ZERO,
+                ChainId::test(),
+            )),
+            listen_addresses: vec!["/ip4/0.0.0.0/tcp/6180".parse().unwrap()],
+            connection_reqs: ConnectionRequestSender::new(connection_reqs_tx),
+            conn_mgr_reqs: Some(conn_mgr_reqs_tx),
+        };
+        let (coordinator_sender, _) = mpsc::unbounded();
+        let handler = AdminHandler {
+            networks: vec![network],
+            logger: None,
+            state_sync: StateSyncClient::new(coordinator_sender, 1_000),
+            seeds: Mutex::new(HashMap::new()),
+        };
+        (handler, connection_reqs_rx, conn_mgr_reqs_rx)
+    }
+
+    fn add_seed_request(network: &str, peer_id: PeerId, address: &str) -> Request<AddSeedRequest> {
+        Request::new(AddSeedRequest {
+            network: network.into(),
+            peer_id: peer_id.to_string(),
+            addresses: vec![address.into()],
+            role: String::new(),
+        })
+    }
+
+    #[test]
+    fn test_authorize() {
+        let expected = "Bearer admin-token";
+        let request = |token: Option<&'static str>| {
+            let mut request = Request::new(());
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert("authorization", MetadataValue::from_static(token));
+            }
+            request
+        };
+
+        for token in &[None, Some("Bearer wrong-token"), Some("admin-token")] {
+            let status = authorize(expected, request(*token)).unwrap_err();
+            assert_eq!(status.code(), Code::Unauthenticated);
+        }
+        authorize(expected, request(Some(expected))).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_add_seed() {
+        let (handler, _, mut conn_mgr_reqs_rx) = handler();
+        let first = PeerId::random();
+        let second = PeerId::random();
+
+        // Verify the seeds added before are sent along with each new seed
+        handler
+            .add_seed(add_seed_request("Validator", first, SEED_ADDRESS))
+            .await
+            .unwrap();
+        handler
+            .add_seed(add_seed_request("Validator", second, SEED_ADDRESS))
+            .await
+            .unwrap();
+        conn_mgr_reqs_rx.next().await.unwrap();
+        match conn_mgr_reqs_rx.next().await.unwrap() {
+            ConnectivityRequest::UpdateDiscoveredPeers(DiscoverySource::Admin, seeds) => {
+                assert_eq!(seeds.len(), 2);
+                assert_eq!(seeds[&second].role, PeerRole::Upstream);
+            }
+            request => panic!("Unexpected request {:?}", request),
+        }
+
+        let status = handler
+            .add_seed(add_seed_request("Public", first, SEED_ADDRESS))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        // The seed must be dialable with its noise key
+        let status = handler
+            .add_seed(add_seed_request(
+                "Validator",
+                first,
+                "/ip4/1.2.3.4/tcp/6180",
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_peer() {
+        let (handler, mut connection_reqs_rx, _) = handler();
+        let peer_id = PeerId::random();
+        let request = Request::new(DisconnectPeerRequest {
+            network: "Validator".into(),
+            peer_id: peer_id.to_string(),
+        });
+        let (response, _) = futures::join!(handler.disconnect_peer(request), async {
+            match connection_reqs_rx.next().await.unwrap() {
+                ConnectionRequest::DisconnectPeer(disconnected, response_tx) => {
+                    assert_eq!(disconnected, peer_id);
+                    response_tx.send(Ok(())).unwrap();
+                }
+                request => panic!("Unexpected request {:?}", request),
+            }
+        });
+        response.unwrap();
+
+        let request = Request::new(DisconnectPeerRequest {
+            network: "Validator".into(),
+            peer_id: "not a peer id".into(),
+        });
+        let status = handler.disconnect_peer(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_set_log_level_without_logger() {
+        let (handler, _, _) = handler();
+        let request = Request::new(SetLogLevelRequest {
+            filter: "debug".into(),
+        });
+        let status = handler.set_log_level(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+    }
+
+    #[tokio::test]
+    async fn test_dump_network_context() {
+        let (handler, _, _) = handler();
+        let response = handler
+            .dump_network_context(Request::new(DumpNetworkContextRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            response.networks,
+            vec![proto::NetworkContext {
+                network: "Validator".into(),
+                role: "validator".into(),
+                peer_id: PeerId::ZERO.to_string(),
+                listen_addresses: vec!["/ip4/0.0.0.0/tcp/6180".into()],
+            }]
+        );
+    }
+}
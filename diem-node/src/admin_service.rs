@@ -0,0 +1,263 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Local admin endpoint to inspect a running node, see `AdminServiceConfig`. All requests must
+//! carry the configured token as `Authorization: Bearer <token>`.
+//!
+//! * `GET /config`: the effective config, with secrets redacted
+//! * `GET /fingerprint`: the fingerprint of the effective config
+//! * `GET /upstream`: the upstream networks by preference, their health and the one currently
+//!   selected, see `UpstreamSelector`
+//! * `GET /peers`: the number of connected peers per network
+//! * `GET /peer_metadata`: every peer known to the node, see `PeerMetadataStorage`
+
+use diem_config::{config::NodeConfig, network_id::NetworkContext, redact::redact};
+use diem_logger::prelude::*;
+use netcore::transport::ConnectionOrigin;
+use network::{
+    counters,
+    peer_metadata::{PeerMetadata, PeerMetadataStorage},
+    upstream_selector::UpstreamSelector,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, convert::Infallible, sync::Arc};
+use subtle::ConstantTimeEq;
+use tokio::runtime::{Builder, Runtime};
+use warp::{http::StatusCode, reject::Reject, Filter, Rejection, Reply};
+
+pub struct AdminService {
+    _runtime: Runtime,
+}
+
+#[derive(Debug, Serialize)]
+struct Fingerprint {
+    fingerprint: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct PeerSummary {
+    inbound: i64,
+    outbound: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct PeerEntry {
+    peer: String,
+    network: String,
+    #[serde(flatten)]
+    metadata: PeerMetadata,
+}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+impl AdminService {
+    /// Starts the service if enabled. Fails if it is enabled without a token.
+    pub fn start(
+        node_config: &NodeConfig,
+        networks: Vec<Arc<NetworkContext>>,
+        upstream: Arc<UpstreamSelector>,
+        peer_metadata: Arc<PeerMetadataStorage>,
+    ) -> anyhow::Result<Option<Self>> {
+        let config = &node_config.admin_service;
+        if !config.enabled {
+            return Ok(None);
+        }
+        let token = match &config.token {
+            Some(token) => token.read_token()?,
+            None => anyhow::bail!("admin_service is enabled without a token"),
+        };
+
+        let runtime = Builder::new_multi_thread()
+            .thread_name("admin")
+            .enable_all()
+            .build()?;
+
+        let routes = routes(token, node_config, networks, upstream, peer_metadata)?;
+
+        let address = config.address;
+        runtime
+            .handle()
+            .spawn(async move { warp::serve(routes).bind(address).await });
+        info!("Admin service listening on {}", address);
+
+        Ok(Some(Self { _runtime: runtime }))
+    }
+}
+
+fn routes(
+    token: String,
+    node_config: &NodeConfig,
+    networks: Vec<Arc<NetworkContext>>,
+    upstream: Arc<UpstreamSelector>,
+    peer_metadata: Arc<PeerMetadataStorage>,
+) -> anyhow::Result<impl Filter<Extract = impl Reply, Error = Infallible> + Clone> {
+    let mut effective_config = serde_yaml::to_value(node_config)?;
+    redact(&mut effective_config);
+    let fingerprint = Fingerprint {
+        fingerprint: node_config.fingerprint()?.to_hex(),
+    };
+
+    // GET /config
+    let config_route = warp::path("config")
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&effective_config));
+
+    // GET /fingerprint
+    let fingerprint_route = warp::path("fingerprint")
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&fingerprint));
+
+    // GET /upstream
+    let upstream_route = warp::path("upstream")
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&upstream.selection()));
+
+    // GET /peers
+    let peers_route = warp::path("peers")
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&peer_summaries(&networks)));
+
+    // GET /peer_metadata
+    let peer_metadata_route = warp::path("peer_metadata")
+        .and(warp::path::end())
+        .map(move || {
+            let mut peers: Vec<_> = peer_metadata
+                .peers()
+                .into_iter()
+                .map(|(peer, metadata)| PeerEntry {
+                    peer: peer.peer_id().to_string(),
+                    network: peer.raw_network_id().to_string(),
+                    metadata,
+                })
+                .collect();
+            peers.sort_by(|a, b| (&a.network, &a.peer).cmp(&(&b.network, &b.peer)));
+            warp::reply::json(&peers)
+        });
+
+    Ok(warp::get()
+        .and(authorized(token))
+        .and(
+            config_route
+                .or(fingerprint_route)
+                .or(upstream_route)
+                .or(peer_metadata_route)
+                .or(peers_route),
+        )
+        .recover(handle_rejection))
+}
+
+fn authorized(token: String) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let expected = Arc::new(format!("Bearer {}", token));
+    warp::header::optional::<String>("authorization")
+        .and_then(move |header: Option<String>| {
+            let expected = expected.clone();
+            async move {
+                // Compared in constant time, so the time to reject doesn't leak the token
+                let matches = header.map_or(false, |header| {
+                    header.as_bytes().ct_eq(expected.as_bytes()).into()
+                });
+                if matches {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Infallible> {
+    let status = if rejection.find::<Unauthorized>().is_some() {
+        StatusCode::UNAUTHORIZED
+    } else if rejection.is_not_found() {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    Ok(warp::reply::with_status(warp::reply(), status))
+}
+
+/// Connected peers per network, by network label, as counted by the network layer
+fn peer_summaries(networks: &[Arc<NetworkContext>]) -> BTreeMap<String, PeerSummary> {
+    let mut summaries = BTreeMap::new();
+    for context in networks {
+        let summary: &mut PeerSummary = summaries
+            .entry(context.network_label().to_string())
+            .or_default();
+        summary.inbound += counters::connections(context, ConnectionOrigin::Inbound).get();
+        summary.outbound += counters::connections(context, ConnectionOrigin::Outbound).get();
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::config::{Token, UpstreamConfig};
+    use diem_time_service::TimeService;
+
+    const TOKEN: &str = "admin-token";
+
+    fn test_routes(
+        node_config: &NodeConfig,
+    ) -> impl Filter<Extract = impl Reply, Error = Infallible> + Clone {
+        routes(
+            TOKEN.into(),
+            node_config,
+            vec![],
+            Arc::new(UpstreamSelector::new(&UpstreamConfig::default())),
+            Arc::new(PeerMetadataStorage::new(TimeService::real())),
+        )
+        .unwrap()
+    }
+
+    fn request(path: &str) -> warp::test::RequestBuilder {
+        warp::test::request()
+            .path(path)
+            .header("authorization", format!("Bearer {}", TOKEN))
+    }
+
+    #[tokio::test]
+    async fn test_authorization() {
+        let routes = test_routes(&NodeConfig::default());
+        let response = warp::test::request()
+            .path("/fingerprint")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        for header in &["Bearer wrong-token", "Bearer admin-token-", TOKEN] {
+            let response = warp::test::request()
+                .path("/fingerprint")
+                .header("authorization", *header)
+                .reply(&routes)
+                .await;
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let response = request("/fingerprint").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        // Only the exact paths are routed
+        let response = request("/fingerprint/more").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_config_redacted() {
+        let mut node_config = NodeConfig::default();
+        node_config.admin_service.token = Some(Token::FromConfig(TOKEN.into()));
+        let routes = test_routes(&node_config);
+
+        let response = request("/config").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let config: serde_yaml::Value = serde_yaml::from_slice(response.body()).unwrap();
+        assert_eq!(
+            config["admin_service"]["token"],
+            serde_yaml::Value::String(diem_config::redact::REDACTED.into())
+        );
+        assert!(!String::from_utf8_lossy(response.body()).contains(TOKEN));
+    }
+}
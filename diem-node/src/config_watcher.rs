@@ -0,0 +1,277 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Reloads the node config on SIGHUP, or once the config file changes, and applies its reloadable
+//! sections to the running node: log level, upstream networks, seeds and prune window, see
+//! `diem_config::reload`. A config changing any other field is rejected and the node keeps
+//! running with the config it has.
+
+use diem_config::{
+    config::NodeConfig,
+    reload::{reloadable_changes, ConfigReload},
+};
+use diem_logger::{prelude::*, Filter, Logger};
+use diemdb::DiemDB;
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    upstream_selector::UpstreamSelector,
+};
+use network_builder::builder::merge_seeds;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    runtime::{Builder, Runtime},
+    signal::unix::{signal, SignalKind},
+};
+
+/// How often the config file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The components of a chain the reloadable sections apply to
+pub struct ReloadHandles {
+    pub upstream: Arc<UpstreamSelector>,
+    pub diem_db: Arc<DiemDB>,
+    /// The connectivity managers of the networks, by `NetworkConfig::label`
+    pub connectivity: Vec<(String, channel::Sender<ConnectivityRequest>)>,
+}
+
+pub struct ConfigWatcher {
+    _runtime: Runtime,
+}
+
+impl ConfigWatcher {
+    /// Watches the config at `path`, `config` being the one the node started with
+    pub fn start(
+        path: PathBuf,
+        config: NodeConfig,
+        logger: Option<Arc<Logger>>,
+        handles: ReloadHandles,
+    ) -> anyhow::Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("config-watcher")
+            .enable_all()
+            .build()?;
+        let mut hangups = {
+            let _guard = runtime.enter();
+            signal(SignalKind::hangup())?
+        };
+
+        let mut watcher = Watcher {
+            modified: modified(&path),
+            path,
+            config,
+            logger,
+            handles,
+        };
+        runtime.spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = hangups.recv() => watcher.reload("SIGHUP"),
+                    _ = interval.tick() => {
+                        if watcher.file_changed() {
+                            watcher.reload("file change")
+                        }
+                    }
+                }
+            }
+        });
+        Ok(Self { _runtime: runtime })
+    }
+}
+
+struct Watcher {
+    path: PathBuf,
+    config: NodeConfig,
+    logger: Option<Arc<Logger>>,
+    handles: ReloadHandles,
+    modified: Option<SystemTime>,
+}
+
+impl Watcher {
+    fn file_changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        let changed = modified != self.modified;
+        self.modified = modified;
+        changed
+    }
+
+    fn reload(&mut self, trigger: &str) {
+        info!("Reloading {:?} on {}", self.path, trigger);
+        let config = match NodeConfig::load(&self.path) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(
+                    "Config reload failed, unable to load {:?}: {}",
+                    self.path, e
+                );
+                return;
+            }
+        };
+        self.update(config);
+    }
+
+    /// Applies the reloadable changes of `config`, which becomes the config of the node, or
+    /// rejects it whole
+    fn update(&mut self, config: NodeConfig) {
+        let reload = match reloadable_changes(&self.config, &config) {
+            Ok(reload) => reload,
+            Err(e) => {
+                error!("Config reload rejected, nothing applied: {}", e);
+                return;
+            }
+        };
+        if reload.is_empty() {
+            info!("Config reload found no change");
+            return;
+        }
+        match self.apply(&config, &reload) {
+            Ok(()) => {
+                info!("Config reloaded: {:?}", reload);
+                self.config = config;
+            }
+            Err(e) => error!("Config reload failed: {}", e),
+        }
+    }
+
+    fn apply(&mut self, config: &NodeConfig, reload: &ConfigReload) -> anyhow::Result<()> {
+        // Seeds are checked before anything is applied
+        let mut seeds = vec![];
+        for network in config
+            .validator_network
+            .iter()
+            .chain(config.full_node_networks.iter())
+            .filter(|network| reload.seeds.contains(&network.label()))
+        {
+            network.verify_seeds()?;
+            seeds.push((network.label(), merge_seeds(network)));
+        }
+
+        if let Some(level) = reload.log_level {
+            match &self.logger {
+                Some(logger) => {
+                    logger.set_filter(Filter::builder().filter_level(level.into()).build())
+                }
+                None => warn!("No logger to set the level of"),
+            }
+        }
+        if let Some(upstream) = &reload.upstream {
+            self.handles.upstream.set_config(upstream);
+        }
+        if let Some(prune_window) = reload.prune_window {
+            self.handles.diem_db.set_prune_window(prune_window)?;
+        }
+        for (label, seeds) in seeds {
+            match self
+                .handles
+                .connectivity
+                .iter_mut()
+                .find(|(network, _)| network == &label)
+            {
+                Some((_, sender)) => sender.try_send(
+                    ConnectivityRequest::UpdateDiscoveredPeers(DiscoverySource::Config, seeds),
+                )?,
+                None => warn!("{} has no connectivity manager to update seeds of", label),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::{
+        config::{Peer, RocksdbConfig},
+        network_id::NetworkId,
+    };
+    use diem_temppath::TempPath;
+    use diem_types::PeerId;
+    use futures::StreamExt;
+
+    fn watcher(db_path: &TempPath) -> (Watcher, channel::Receiver<ConnectivityRequest>) {
+        let config = NodeConfig::default_for_public_full_node();
+        let diem_db = DiemDB::open(
+            db_path.path(),
+            false, /* readonly */
+            config.storage.prune_window,
+            RocksdbConfig::default(),
+        )
+        .unwrap();
+        let (sender, receiver) = channel::new_test(1);
+        let handles = ReloadHandles {
+            upstream: Arc::new(UpstreamSelector::new(&config.upstream)),
+            diem_db: Arc::new(diem_db),
+            connectivity: vec![("Public".into(), sender)],
+        };
+        let watcher = Watcher {
+            path: PathBuf::new(),
+            config,
+            logger: None,
+            handles,
+            modified: None,
+        };
+        (watcher, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_update_reloadable() {
+        let db_path = TempPath::new();
+        let (mut watcher, mut receiver) = watcher(&db_path);
+        let vfn = NetworkId::Private("vfn".into());
+
+        let mut config = watcher.config.clone();
+        config.upstream.networks.insert(0, vfn.clone());
+        config.storage.prune_window = Some(1_000);
+        let seed = PeerId::random();
+        config.full_node_networks[0]
+            .seeds
+            .insert(seed, Peer::default());
+        watcher.update(config.clone());
+
+        assert_eq!(watcher.config, config);
+        assert_eq!(watcher.handles.upstream.selection().preference[0], vfn);
+        match receiver.next().await.unwrap() {
+            ConnectivityRequest::UpdateDiscoveredPeers(DiscoverySource::Config, seeds) => {
+                assert!(seeds.contains_key(&seed))
+            }
+            request => panic!("Unexpected request {:?}", request),
+        }
+    }
+
+    #[test]
+    fn test_update_rejects_fixed_fields() {
+        let db_path = TempPath::new();
+        let (mut watcher, _receiver) = watcher(&db_path);
+        let current = watcher.config.clone();
+
+        // Nothing is applied, not even the reloadable changes along with a fixed one
+        let mut config = current.clone();
+        config
+            .upstream
+            .networks
+            .insert(0, NetworkId::Private("vfn".into()));
+        config.mempool.capacity += 1;
+        watcher.update(config);
+
+        assert_eq!(watcher.config, current);
+        assert_eq!(
+            watcher.handles.upstream.selection().preference,
+            UpstreamSelector::new(&current.upstream)
+                .selection()
+                .preference
+        );
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Health endpoint for load balancers and orchestrators, see `HealthCheckConfig`.
+//!
+//! * `GET /health`: answers while the node runs
+//! * `GET /ready`: the readiness of each subsystem, with a 503 status unless all are ready
+//!
+//! State sync, mempool, consensus and the upgrade coordinator are read from their metrics, the
+//! network from the `PeerMetadataStorage`, so the endpoint holds no handle into the subsystems.
+
+use diem_config::{
+    config::{HealthCheckConfig, NodeConfig},
+    network_id::NetworkId,
+};
+use diem_logger::prelude::*;
+use diem_metrics::metric_value;
+use network::peer_metadata::PeerMetadataStorage;
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Arc};
+use tokio::runtime::{Builder, Runtime};
+use warp::{http::StatusCode, Filter};
+
+pub struct HealthCheckService {
+    _runtime: Runtime,
+}
+
+#[derive(Debug, Serialize)]
+struct Health {
+    status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    ready: bool,
+    detail: String,
+}
+
+impl Check {
+    fn new(ready: bool, detail: String) -> Self {
+        Self { ready, detail }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Readiness {
+    ready: bool,
+    checks: BTreeMap<&'static str, Check>,
+}
+
+struct ReadinessChecks {
+    config: HealthCheckConfig,
+    is_validator: bool,
+    mempool_capacity: usize,
+    /// The validator network of a validator, then the upstream networks
+    upstream: Vec<NetworkId>,
+    peer_metadata: Arc<PeerMetadataStorage>,
+}
+
+impl HealthCheckService {
+    /// Starts the service if enabled
+    pub fn start(
+        node_config: &NodeConfig,
+        peer_metadata: Arc<PeerMetadataStorage>,
+    ) -> anyhow::Result<Option<Self>> {
+        let config = node_config.health_check.clone();
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("health-check")
+            .enable_all()
+            .build()?;
+
+        let address = config.address;
+        let checks = Arc::new(ReadinessChecks {
+            config,
+            is_validator: node_config.base.role.is_validator(),
+            mempool_capacity: node_config.mempool.capacity,
+            upstream: node_config
+                .validator_network
+                .iter()
+                .map(|network| network.network_id.clone())
+                .chain(node_config.upstream.networks.iter().cloned())
+                .collect(),
+            peer_metadata,
+        });
+
+        // GET /health
+        let health_route = warp::path("health").map(|| warp::reply::json(&Health { status: "ok" }));
+
+        // GET /ready
+        let ready_route = warp::path("ready").map(move || {
+            let readiness = checks.readiness();
+            let status = if readiness.ready {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            warp::reply::with_status(warp::reply::json(&readiness), status)
+        });
+
+        let routes = warp::get().and(health_route.or(ready_route));
+        runtime
+            .handle()
+            .spawn(async move { warp::serve(routes).bind(address).await });
+        info!("Health check listening on {}", address);
+
+        Ok(Some(Self { _runtime: runtime }))
+    }
+}
+
+/// The metrics and peers readiness is decided from, read at once
+#[derive(Debug, Default)]
+struct NodeStatus {
+    committed_version: Option<u64>,
+    highest_version: Option<u64>,
+    mempool_size: usize,
+    current_round: u64,
+    committed_round: u64,
+    onchain_version: Option<u64>,
+    supported_version: Option<u64>,
+    halt_at_version: Option<u64>,
+    halting: bool,
+    upstream_peers: usize,
+}
+
+impl ReadinessChecks {
+    fn readiness(&self) -> Readiness {
+        self.evaluate(&self.status())
+    }
+
+    /// Reads the metrics of the subsystems, and counts the peers connected upstream
+    fn status(&self) -> NodeStatus {
+        let version = |kind: &str| {
+            metric_value("diem_state_sync_version", &[("type", kind)]).map(|value| value as u64)
+        };
+        let round = |name: &str| metric_value(name, &[]).map_or(0, |value| value as u64);
+        let upgrade = |field: &str| {
+            metric_value("diem_upgrade_state", &[("field", field)]).map(|value| value as u64)
+        };
+        NodeStatus {
+            committed_version: version("committed"),
+            highest_version: version("highest"),
+            mempool_size: metric_value("diem_core_mempool_index_size", &[("index", "system_ttl")])
+                .map_or(0, |value| value as usize),
+            current_round: round("diem_consensus_current_round"),
+            committed_round: round("diem_consensus_last_committed_round"),
+            onchain_version: upgrade("onchain_version"),
+            supported_version: upgrade("supported_version"),
+            halt_at_version: upgrade("halt_at_version").filter(|boundary| *boundary > 0),
+            halting: upgrade("halting") == Some(1),
+            upstream_peers: self
+                .peer_metadata
+                .peers()
+                .iter()
+                .filter(|(peer, metadata)| {
+                    metadata.is_connected() && self.upstream.contains(&peer.raw_network_id())
+                })
+                .count(),
+        }
+    }
+
+    fn evaluate(&self, status: &NodeStatus) -> Readiness {
+        let mut checks = BTreeMap::new();
+        checks.insert("state_sync", self.state_sync(status));
+        checks.insert("mempool", self.mempool(status));
+        if self.is_validator {
+            checks.insert("consensus", self.consensus(status));
+        }
+        checks.insert("network", self.network(status));
+        checks.insert("upgrade", self.upgrade(status));
+        Readiness {
+            ready: checks.values().all(|check| check.ready),
+            checks,
+        }
+    }
+
+    /// Synced within `max_version_lag` of the highest version known from peers
+    fn state_sync(&self, status: &NodeStatus) -> Check {
+        match (status.committed_version, status.highest_version) {
+            (Some(committed), Some(highest)) => {
+                let lag = highest.saturating_sub(committed);
+                Check::new(
+                    lag <= self.config.max_version_lag,
+                    format!(
+                        "version {}, {} behind the highest known version",
+                        committed, lag
+                    ),
+                )
+            }
+            _ => Check::new(false, "no version synced yet".into()),
+        }
+    }
+
+    /// Room left in mempool for new transactions
+    fn mempool(&self, status: &NodeStatus) -> Check {
+        Check::new(
+            status.mempool_size < self.mempool_capacity,
+            format!(
+                "{} of {} transactions",
+                status.mempool_size, self.mempool_capacity
+            ),
+        )
+    }
+
+    /// Committing within `max_round_lag` of the current round
+    fn consensus(&self, status: &NodeStatus) -> Check {
+        let lag = status.current_round.saturating_sub(status.committed_round);
+        Check::new(
+            lag <= self.config.max_round_lag,
+            format!(
+                "round {}, {} since the last commit",
+                status.current_round, lag
+            ),
+        )
+    }
+
+    /// Not halting for an upgrade, see `upgrade`
+    fn upgrade(&self, status: &NodeStatus) -> Check {
+        let mut detail = match (status.onchain_version, status.supported_version) {
+            (Some(onchain), Some(supported)) => format!(
+                "on-chain version {}, the binary supports up to {}",
+                onchain, supported
+            ),
+            _ => "on-chain version unknown".into(),
+        };
+        if let Some(boundary) = status.halt_at_version {
+            detail.push_str(&format!(", halting at version {}", boundary));
+        }
+        Check::new(!status.halting, detail)
+    }
+
+    /// At least `min_upstream_peers` connected on the upstream networks
+    fn network(&self, status: &NodeStatus) -> Check {
+        Check::new(
+            status.upstream_peers >= self.config.min_upstream_peers,
+            format!("{} upstream peers connected", status.upstream_peers),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_time_service::TimeService;
+
+    fn checks(is_validator: bool) -> ReadinessChecks {
+        ReadinessChecks {
+            config: HealthCheckConfig::default(),
+            is_validator,
+            mempool_capacity: 100,
+            upstream: vec![NetworkId::Validator],
+            peer_metadata: Arc::new(PeerMetadataStorage::new(TimeService::real())),
+        }
+    }
+
+    fn healthy() -> NodeStatus {
+        NodeStatus {
+            committed_version: Some(1_000),
+            highest_version: Some(1_050),
+            mempool_size: 10,
+            current_round: 20,
+            committed_round: 18,
+            onchain_version: Some(1),
+            supported_version: Some(1),
+            upstream_peers: 3,
+            ..NodeStatus::default()
+        }
+    }
+
+    /// The checks that aren't ready
+    fn not_ready(readiness: &Readiness) -> Vec<&'static str> {
+        readiness
+            .checks
+            .iter()
+            .filter(|(_, check)| !check.ready)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    #[test]
+    fn test_healthy() {
+        let readiness = checks(true).evaluate(&healthy());
+        assert!(readiness.ready);
+        assert_eq!(readiness.checks.len(), 5);
+        // Consensus is only checked on a validator
+        assert!(!checks(false)
+            .evaluate(&healthy())
+            .checks
+            .contains_key("consensus"));
+    }
+
+    #[test]
+    fn test_syncing() {
+        let checks = checks(false);
+        let status = NodeStatus {
+            highest_version: Some(2_000),
+            ..healthy()
+        };
+        let readiness = checks.evaluate(&status);
+        assert!(!readiness.ready);
+        assert_eq!(not_ready(&readiness), vec!["state_sync"]);
+        assert_eq!(
+            readiness.checks["state_sync"].detail,
+            "version 1000, 1000 behind the highest known version"
+        );
+
+        let status = NodeStatus {
+            committed_version: None,
+            ..healthy()
+        };
+        assert_eq!(not_ready(&checks.evaluate(&status)), vec!["state_sync"]);
+    }
+
+    #[test]
+    fn test_unhealthy() {
+        let status = NodeStatus {
+            mempool_size: 100,
+            current_round: 40,
+            halt_at_version: Some(5_000),
+            halting: true,
+            upstream_peers: 0,
+            ..healthy()
+        };
+        let readiness = checks(true).evaluate(&status);
+        assert!(!readiness.ready);
+        assert_eq!(
+            not_ready(&readiness),
+            vec!["consensus", "mempool", "network", "upgrade"]
+        );
+        assert_eq!(
+            readiness.checks["upgrade"].detail,
+            "on-chain version 1, the binary supports up to 1, halting at version 5000"
+        );
+    }
+}
@@ -1,18 +1,32 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+//////// 0L ////////
+mod admin_grpc;
+mod admin_service;
+mod config_watcher;
+mod health_check;
+mod rpc_waypoint;
+mod shutdown;
+mod telemetry;
+mod upgrade;
+
+use admin_grpc::{AdminGrpcService, AdminNetwork};
+use admin_service::AdminService;
+use backup_cli::coordinators::node::NodeBackupCoordinator;
 use backup_service::start_backup_service;
+use config_watcher::{ConfigWatcher, ReloadHandles};
 use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
 use debug_interface::node_debug_service::NodeDebugService;
 use diem_config::{
-    config::{NetworkConfig, NodeConfig, PersistableConfig},
-    network_id::NodeNetworkId,
+    config::{LogFormat, NetworkConfig, NodeConfig, PersistableConfig},
+    network_id::{NetworkContext, NodeNetworkId},
     utils::get_genesis_txn,
 };
 use diem_json_rpc::bootstrap_from_config as bootstrap_rpc;
 use diem_logger::{prelude::*, Logger};
 use diem_mempool::gen_mempool_reconfig_subscription;
-use diem_metrics::metric_server;
+use diem_metrics::{metric_server, push_gateway};
 use diem_time_service::TimeService;
 use diem_types::{
     account_config::diem_root_address, account_state::AccountState, chain_id::ChainId,
@@ -23,24 +37,30 @@ use diemdb::DiemDB;
 use executor::{db_bootstrapper::maybe_bootstrap, Executor};
 use executor_types::ChunkExecutor;
 use futures::{channel::mpsc::channel, executor::block_on};
+use health_check::HealthCheckService;
+use network::{
+    drain::Drain, peer_metadata::PeerMetadataStorage, peer_reputation::PeerReputationStore,
+    upstream_selector::UpstreamSelector,
+};
 use network_builder::builder::NetworkBuilder;
+use shutdown::ShutdownSignal;
 use state_sync::bootstrapper::StateSyncBootstrapper;
 use std::{
     boxed::Box,
     convert::TryFrom,
     net::ToSocketAddrs,
     path::PathBuf,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+    sync::Arc,
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use storage_interface::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
+use subscription_service::ReconfigBus;
+use telemetry::Telemetry;
 use tokio::runtime::{Builder, Runtime};
 use tokio_stream::wrappers::IntervalStream;
+use upgrade::UpgradeCoordinator;
 
 const AC_SMP_CHANNEL_BUFFER_SIZE: usize = 1_024;
 const INTRA_NODE_CHANNEL_BUFFER_SIZE: usize = 1;
@@ -52,11 +72,25 @@ pub struct DiemHandle {
     _state_sync_bootstrapper: StateSyncBootstrapper,
     _network_runtimes: Vec<Runtime>,
     _consensus_runtime: Option<Runtime>,
-    _debug: NodeDebugService,
+    _debug: Option<NodeDebugService>, //////// 0L ////////
+    _backup_coordinator: Option<NodeBackupCoordinator>, //////// 0L ////////
     _backup: Runtime,
+    _admin: Option<AdminService>,              //////// 0L ////////
+    _admin_grpc: Option<AdminGrpcService>,     //////// 0L ////////
+    _api: Option<Runtime>,                     //////// 0L ////////
+    _health_check: Option<HealthCheckService>, //////// 0L ////////
+    _telemetry: Option<Telemetry>,             //////// 0L ////////
+    _chains: Vec<DiemHandle>,                  //////// 0L ////////
+    reload_handles: Option<ReloadHandles>,     //////// 0L ////////
+    upgrade: UpgradeCoordinator,               //////// 0L ////////
+    drain: Arc<Drain>,                         //////// 0L ////////
+    diem_db: Arc<DiemDB>,                      //////// 0L ////////
 }
 
-pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
+/// Starts the node. With a `config_path`, the reloadable sections of the config are reloaded on
+/// SIGHUP or once the file changes, see `config_watcher`. The node drains before it stops on
+/// SIGTERM or SIGINT, see `shutdown`.
+pub fn start(config: &NodeConfig, config_path: Option<PathBuf>, log_file: Option<PathBuf>) {
     crash_handler::setup_panic_handler();
 
     let mut logger = diem_logger::Logger::new();
@@ -65,6 +99,10 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
         .is_async(config.logger.is_async)
         .level(config.logger.level)
         .read_env();
+    //////// 0L ////////
+    if config.logger.format == LogFormat::Json {
+        logger.custom_format(diem_logger::json_format);
+    }
     if let Some(log_file) = log_file {
         logger.printer(Box::new(FileWriter::new(log_file)));
     }
@@ -72,6 +110,9 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
 
     // Let's now log some important information, since the logger is set up
     info!(config = config, "Loaded DiemNode config");
+    //////// 0L ////////
+    let resolved = rpc_waypoint::resolve(config).expect("Unable to fetch the waypoint");
+    let config = resolved.as_ref().unwrap_or(config);
 
     if config.metrics.enabled {
         for network in &config.full_node_networks {
@@ -95,12 +136,27 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
         warn!("failpoints is set in config, but the binary doesn't compile with this feature");
     }
 
-    let _node_handle = setup_environment(&config, logger);
-    let term = Arc::new(AtomicBool::new(false));
+    let mut node_handle = setup_environment(&config, logger.clone());
+    //////// 0L ////////
+    let _config_watcher = config_path.map(|path| {
+        let handles = node_handle
+            .reload_handles
+            .take()
+            .expect("The node has reload handles");
+        ConfigWatcher::start(path, config.clone(), logger, handles)
+            .expect("Failed to start the config watcher")
+    });
+    let shutdown_signal = ShutdownSignal::start().expect("Failed to handle the shutdown signals");
 
-    while !term.load(Ordering::Acquire) {
-        std::thread::park();
+    while !shutdown_signal.requested() {
+        //////// 0L ////////
+        if node_handle.upgrade.halted() {
+            info!("The node halted for an upgrade, see the upgrade config");
+            break;
+        }
+        std::thread::park_timeout(Duration::from_secs(1));
     }
+    shutdown::shutdown(node_handle, &config.shutdown);
 }
 
 fn setup_metrics(peer_id: PeerId, config: &NodeConfig) {
@@ -165,7 +221,7 @@ pub fn load_test_environment(config_path: Option<PathBuf>, random_ports: bool) {
     println!("Diem is running, press ctrl-c to exit");
     println!();
 
-    start(&config, Some(log_file))
+    start(&config, None, Some(log_file))
 }
 
 // Fetch chain ID from on-chain resource
@@ -251,23 +307,70 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
 }
 
 pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> DiemHandle {
-    let debug_if = setup_debug_interface(&node_config, logger);
-
-    let metrics_port = node_config.debug_interface.metrics_server_port;
-    let metric_host = node_config.debug_interface.address.clone();
-    thread::spawn(move || metric_server::start_server(metric_host, metrics_port, false));
+    let debug_if = setup_debug_interface(&node_config, logger.clone());
+
+    //////// 0L ////////
+    let scrape = &node_config.metrics.scrape;
+    let basic_auth = scrape.basic_auth.as_ref().map(|auth| {
+        let password = auth
+            .password
+            .read_token()
+            .expect("[metrics] unable to read the basic auth password");
+        (auth.username.as_str(), password)
+    });
+    match scrape.address {
+        Some(address) => metric_server::start_server_with_path(
+            address,
+            &scrape.path,
+            basic_auth
+                .as_ref()
+                .map(|(username, password)| (*username, password.trim())),
+            scrape.tls.as_ref().map(|tls| {
+                diem_tls::server_config(&tls.cert_path, &tls.key_path, tls.reload_interval())
+                    .expect("[metrics] invalid TLS config")
+            }),
+        ),
+        None => {
+            let metrics_port = node_config.debug_interface.metrics_server_port;
+            let metric_host = node_config.debug_interface.address.clone();
+            thread::spawn(move || metric_server::start_server(metric_host, metrics_port, false));
+        }
+    }
     let public_metrics_port = node_config.debug_interface.public_metrics_server_port;
     let public_metric_host = node_config.debug_interface.address.clone();
     thread::spawn(move || {
         metric_server::start_server(public_metric_host, public_metrics_port, true)
     });
 
+    //////// 0L ////////
+    let mut handle = setup_chain(node_config, None, &debug_if, logger.clone());
+    handle._chains = node_config
+        .chain_configs()
+        .into_iter()
+        .map(|(chain_id, config)| {
+            info!("Starting chain {}", chain_id);
+            setup_chain(&config, Some(chain_id), &debug_if, logger.clone())
+        })
+        .collect();
+    handle._debug = Some(debug_if);
+    handle
+}
+
+//////// 0L ////////
+/// Starts the storage, networks and components of a chain. The debug interface and the metrics
+/// servers are shared by the chains of the process.
+fn setup_chain(
+    node_config: &NodeConfig,
+    expected_chain_id: Option<ChainId>,
+    debug_if: &NodeDebugService,
+    logger: Option<Arc<Logger>>,
+) -> DiemHandle {
     let mut instant = Instant::now();
     let (diem_db, db_rw) = DbReaderWriter::wrap(
-        DiemDB::open(
+        DiemDB::open_with_prune_windows(
             &node_config.storage.dir(),
             false, /* readonly */
-            node_config.storage.prune_window,
+            node_config.storage.prune_windows(),
             node_config.storage.rocksdb_config,
         )
         .expect("DB should open."),
@@ -286,6 +389,13 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     } else {
         info!("Genesis txn not provided, it's fine if you don't expect to apply it otherwise please double check config");
     }
+    //////// 0L ////////
+    let backup_coordinator = NodeBackupCoordinator::start(
+        &node_config.backup,
+        node_config.storage.backup_service_address,
+        node_config.storage.dir().join("backup_metadata_cache"),
+    )
+    .expect("Backup coordinator should start.");
 
     debug!(
         "Storage service started in {} ms",
@@ -299,20 +409,57 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         instant.elapsed().as_millis()
     );
     let chain_id = fetch_chain_id(&db_rw);
+    //////// 0L ////////
+    if let Some(expected_chain_id) = expected_chain_id {
+        assert_eq!(
+            chain_id,
+            expected_chain_id,
+            "[diem-node] the storage in {} holds another chain",
+            node_config.storage.dir().display()
+        );
+    }
+    // The on-chain config changes published by state sync, to any subsystem subscribing
+    let reconfig_bus = ReconfigBus::default();
+    let upgrade = UpgradeCoordinator::start(
+        &node_config.upgrade,
+        Arc::clone(&db_rw.reader),
+        &reconfig_bus,
+    )
+    .unwrap_or_else(|e| panic!("[diem-node] {}", e));
+    if let Some(push_gateway) = &node_config.metrics.push_gateway {
+        let context = node_config
+            .validator_network
+            .iter()
+            .chain(node_config.full_node_networks.iter())
+            .next()
+            .map(|network| {
+                NetworkContext::new(
+                    node_config.base.role,
+                    network.network_id.clone(),
+                    network.peer_id(),
+                    chain_id,
+                )
+            });
+        push_gateway::start_pusher(
+            push_gateway.url.clone(),
+            push_gateway.job.clone(),
+            push_gateway.grouping_labels(chain_id, context.as_ref()),
+            Duration::from_millis(push_gateway.push_interval_ms),
+        );
+    }
     let mut network_runtimes = vec![];
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
-    let mut reconfig_subscriptions = vec![];
 
     let (mempool_reconfig_subscription, mempool_reconfig_events) =
         gen_mempool_reconfig_subscription();
-    reconfig_subscriptions.push(mempool_reconfig_subscription);
+    reconfig_bus.add(mempool_reconfig_subscription);
     // consensus has to subscribe to ALL on-chain configs
     let (consensus_reconfig_subscription, consensus_reconfig_events) =
         gen_consensus_reconfig_subscription();
     if node_config.base.role.is_validator() {
-        reconfig_subscriptions.push(consensus_reconfig_subscription);
+        reconfig_bus.add(consensus_reconfig_subscription);
     }
 
     // Gather all network configs into a single vector.
@@ -322,6 +469,20 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     }
 
     let mut network_builders = Vec::new();
+    let mut network_priorities = Vec::new(); //////// 0L ////////
+    let mut connectivity = Vec::new(); //////// 0L ////////
+    let mut admin_handles = Vec::new(); //////// 0L ////////
+
+    //////// 0L ////////
+    // The peers of every network, for the components that track peers
+    let peer_metadata = Arc::new(PeerMetadataStorage::new(TimeService::real()));
+    // The misbehaving peers, banned from every network
+    let peer_reputation = Arc::new(PeerReputationStore::new(
+        &node_config.peer_reputation,
+        TimeService::real(),
+    ));
+    // The work the chain waits for when the node shuts down
+    let drain = Arc::new(Drain::new());
 
     // Instantiate every network and collect the requisite endpoints for state_sync, mempool, and consensus.
     for (idx, network_config) in network_configs.into_iter().enumerate() {
@@ -333,6 +494,24 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             TimeService::real(),
         );
         let network_id = network_config.network_id.clone();
+        //////// 0L ////////
+        network_priorities.push(network_config.priority());
+        network_builder.add_peer_metadata_storage(
+            peer_metadata.clone(),
+            NodeNetworkId::new(network_id.clone(), idx),
+        );
+        network_builder.add_peer_reputation_store(
+            peer_reputation.clone(),
+            NodeNetworkId::new(network_id.clone(), idx),
+        );
+        network_builder.add_drain(drain.clone());
+        if let Some(conn_mgr_reqs_tx) = network_builder.conn_mgr_reqs_tx() {
+            connectivity.push((network_config.label(), conn_mgr_reqs_tx));
+        }
+        admin_handles.push((
+            network_builder.connection_request_sender(),
+            network_builder.conn_mgr_reqs_tx(),
+        ));
 
         // Create the endpoints to connect the Network to State Sync.
         let (state_sync_sender, state_sync_events) =
@@ -367,17 +546,30 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             );
         }
 
-        reconfig_subscriptions.append(network_builder.reconfig_subscriptions());
+        for subscription in network_builder.reconfig_subscriptions().drain(..) {
+            reconfig_bus.add(subscription);
+        }
 
         network_builders.push(network_builder);
     }
 
     // Build the configured networks.
-    for network_builder in &mut network_builders {
+    for (network_builder, priority) in network_builders.iter_mut().zip(network_priorities) {
         let network_context = network_builder.network_context();
         debug!("Creating runtime for {}", network_context);
-        let runtime = Builder::new_multi_thread()
-            .thread_name(format!("network-{}", network_context.network_id()))
+        let mut runtime_builder = Builder::new_multi_thread();
+        //////// 0L ////////
+        // Networks with a QoS config share the cores by priority
+        if let Some(priority) = priority {
+            let worker_threads = priority.worker_threads(num_cpus::get());
+            debug!(
+                "{} runs with {:?} priority on {} worker threads",
+                network_context, priority, worker_threads
+            );
+            runtime_builder.worker_threads(worker_threads);
+        }
+        let runtime = runtime_builder
+            .thread_name(format!("network-{}", network_context.network_label()))
             .enable_all()
             .build()
             .expect("Failed to start runtime. Won't be able to start networking.");
@@ -395,6 +587,24 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         network_builder.start();
     }
 
+    //////// 0L ////////
+    // Upstream health, fed by mempool and queried through the admin service
+    let upstream = Arc::new(UpstreamSelector::new(&node_config.upstream));
+    let admin = AdminService::start(
+        node_config,
+        network_builders
+            .iter()
+            .map(|network_builder| network_builder.network_context())
+            .collect(),
+        upstream.clone(),
+        peer_metadata.clone(),
+    )
+    .expect("Failed to start the admin service");
+    let health_check = HealthCheckService::start(node_config, peer_metadata.clone())
+        .expect("Failed to start the health check");
+    let telemetry = Telemetry::start(node_config, chain_id, peer_metadata.clone())
+        .expect("Failed to start the telemetry");
+
     // TODO set up on-chain discovery network based on UpstreamConfig.fallback_network
     // and pass network handles to mempool/state sync
 
@@ -408,11 +618,48 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         chunk_executor,
         node_config,
         genesis_waypoint,
-        reconfig_subscriptions,
+        reconfig_bus,
+        peer_reputation.clone(),
+        drain.clone(),
     );
     let (mp_client_sender, mp_client_events) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
 
-    let rpc_runtime = bootstrap_rpc(&node_config, chain_id, diem_db.clone(), mp_client_sender);
+    //////// 0L ////////
+    let admin_networks = network_builders
+        .iter()
+        .zip(admin_handles)
+        .map(
+            |(network_builder, (connection_reqs, conn_mgr_reqs))| AdminNetwork {
+                context: network_builder.network_context(),
+                listen_addresses: network_builder.listen_addresses(),
+                connection_reqs,
+                conn_mgr_reqs,
+            },
+        )
+        .collect();
+    let admin_grpc = AdminGrpcService::start(
+        node_config,
+        admin_networks,
+        logger,
+        state_sync_bootstrapper.create_client(node_config.state_sync.client_commit_timeout_ms),
+    )
+    .expect("Failed to start the admin gRPC service");
+
+    let rpc_runtime = bootstrap_rpc(
+        &node_config,
+        chain_id,
+        diem_db.clone(),
+        mp_client_sender,
+        peer_metadata,
+    );
+    let api_runtime = diem_api::bootstrap_from_config(&node_config, chain_id, diem_db.clone()); //////// 0L ////////
+
+    //////// 0L ////////
+    let reload_handles = ReloadHandles {
+        upstream: upstream.clone(),
+        diem_db: diem_db.clone(),
+        connectivity,
+    };
 
     let mut consensus_runtime = None;
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
@@ -426,6 +673,9 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         consensus_requests,
         state_sync_requests,
         mempool_reconfig_events,
+        upstream,
+        peer_reputation,
+        drain.clone(),
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
 
@@ -453,7 +703,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_network_events,
             state_sync_client,
             consensus_to_mempool_sender,
-            diem_db,
+            diem_db.clone(),
             consensus_reconfig_events,
         ));
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
@@ -471,7 +721,18 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         _mempool: mempool,
         _state_sync_bootstrapper: state_sync_bootstrapper,
         _consensus_runtime: consensus_runtime,
-        _debug: debug_if,
+        _debug: None,
+        _backup_coordinator: backup_coordinator,
         _backup: backup_service,
+        _admin: admin,
+        _admin_grpc: admin_grpc,
+        _api: api_runtime,
+        _health_check: health_check,
+        _telemetry: telemetry,
+        _chains: vec![],
+        reload_handles: Some(reload_handles),
+        upgrade,
+        drain,
+        diem_db,
     }
 }
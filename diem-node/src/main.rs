@@ -3,7 +3,7 @@
 
 #![forbid(unsafe_code)]
 
-use diem_config::config::NodeConfig;
+use diem_config::{config::NodeConfig, doctor};
 use std::path::PathBuf;
 use structopt::StructOpt;
 
@@ -21,6 +21,9 @@ struct Args {
     test: bool,
     #[structopt(long, help = "Enabling random ports for testnet")]
     random_ports: bool,
+    //////// 0L ////////
+    #[structopt(long, help = "Check the config and host environment before starting")]
+    doctor: bool,
 }
 
 #[global_allocator]
@@ -33,8 +36,17 @@ fn main() {
         println!("Entering test mode, this should never be used in production!");
         diem_node::load_test_environment(args.config, args.random_ports);
     } else {
-        let config = NodeConfig::load(args.config.unwrap()).expect("Failed to load node config");
+        let config_path = args.config.unwrap();
+        let config = NodeConfig::load(&config_path).expect("Failed to load node config");
+        //////// 0L ////////
+        if args.doctor {
+            let report = doctor::check_environment(&config, doctor::DEFAULT_MIN_FREE_SPACE_BYTES);
+            println!("{}", report);
+            if report.has_failures() {
+                std::process::exit(1);
+            }
+        }
         println!("Using node config {:?}", &config);
-        diem_node::start(&config, None);
+        diem_node::start(&config, Some(config_path), None);
     };
 }
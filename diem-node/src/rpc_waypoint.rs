@@ -0,0 +1,173 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Fetching of a `WaypointConfig::FromRpc` waypoint. Each endpoint is asked for the epoch changes
+//! of the chain from genesis on, each verified with the signatures of the validators of the epoch
+//! before it, from the genesis waypoint on. An endpoint whose epoch changes don't verify is left
+//! out, and the waypoint of the latest epoch change returned by at least `min_agreement` of the
+//! others is used. Endpoints lagging behind still count for the epoch changes they have, but two
+//! endpoints returning different verified waypoints at the same version are a fork and stop the
+//! node.
+
+use anyhow::{bail, ensure, Result};
+use diem_client::BlockingClient;
+use diem_config::config::{NodeConfig, WaypointConfig};
+use diem_logger::prelude::*;
+use diem_types::{
+    epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
+    trusted_state::{TrustedState, TrustedStateChange},
+    waypoint::Waypoint,
+};
+use std::collections::BTreeMap;
+
+/// The config with the waypoint of its from_rpc config fetched, its genesis waypoint is kept
+pub fn resolve(config: &NodeConfig) -> Result<Option<NodeConfig>> {
+    let (endpoints, min_agreement, genesis_waypoint) = match &config.base.waypoint {
+        WaypointConfig::FromRpc {
+            endpoints,
+            min_agreement,
+            genesis_waypoint,
+            ..
+        } => (endpoints, *min_agreement, *genesis_waypoint),
+        _ => return Ok(None),
+    };
+    let waypoint = fetch_waypoint(endpoints, min_agreement, genesis_waypoint)?;
+    info!(
+        waypoint = %waypoint,
+        "Waypoint fetched from the JSON-RPC endpoints"
+    );
+    let mut config = config.clone();
+    if let WaypointConfig::FromRpc { latest, .. } = &mut config.base.waypoint {
+        *latest = Some(waypoint);
+    }
+    Ok(Some(config))
+}
+
+fn fetch_waypoint(
+    endpoints: &[String],
+    min_agreement: usize,
+    genesis_waypoint: Waypoint,
+) -> Result<Waypoint> {
+    let mut answers = vec![];
+    for endpoint in endpoints {
+        match epoch_waypoints(endpoint, genesis_waypoint) {
+            Ok(waypoints) => answers.push(waypoints),
+            Err(e) => warn!(
+                endpoint = endpoint,
+                error = e.to_string(),
+                "Unable to fetch the epoch changes"
+            ),
+        }
+    }
+    agreed_waypoint(&answers, min_agreement)
+}
+
+/// The waypoints of the epoch changes of the chain returned by `endpoint`, from the genesis one
+/// on. Fails if the epoch changes don't verify.
+fn epoch_waypoints(endpoint: &str, genesis_waypoint: Waypoint) -> Result<Vec<Waypoint>> {
+    let client = BlockingClient::new(endpoint);
+    let mut trusted_state = TrustedState::from(genesis_waypoint);
+    let mut waypoints = vec![genesis_waypoint];
+    loop {
+        let state_proof = client
+            .get_state_proof(trusted_state.version())?
+            .into_inner();
+        let latest: LedgerInfoWithSignatures =
+            bcs::from_bytes(state_proof.ledger_info_with_signatures.inner())?;
+        let proof: EpochChangeProof = bcs::from_bytes(state_proof.epoch_change_proof.inner())?;
+        let new_state = match trusted_state.verify_and_ratchet(&latest, &proof)? {
+            TrustedStateChange::Epoch { new_state, .. } => new_state,
+            TrustedStateChange::Version { .. } | TrustedStateChange::NoChange => break,
+        };
+        // The epoch changes before the last waypoint are stale, and left unverified
+        for ledger_info in &proof.ledger_info_with_sigs {
+            let waypoint = Waypoint::new_epoch_boundary(ledger_info.ledger_info())?;
+            if waypoint.version() > waypoints[waypoints.len() - 1].version() {
+                waypoints.push(waypoint);
+            }
+        }
+        trusted_state = new_state;
+        if !proof.more {
+            break;
+        }
+    }
+    Ok(waypoints)
+}
+
+/// The waypoint at the highest version returned by at least `min_agreement` endpoints
+fn agreed_waypoint(answers: &[Vec<Waypoint>], min_agreement: usize) -> Result<Waypoint> {
+    let mut by_version: BTreeMap<u64, Vec<(Waypoint, usize)>> = BTreeMap::new();
+    for waypoint in answers.iter().flatten() {
+        let counts = by_version.entry(waypoint.version()).or_default();
+        match counts.iter_mut().find(|(known, _)| known == waypoint) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((*waypoint, 1)),
+        }
+    }
+    for (version, counts) in by_version.iter().rev() {
+        ensure!(
+            counts.len() == 1,
+            "The endpoints disagree on the epoch change at version {}: {:?}",
+            version,
+            counts
+        );
+        let (waypoint, agreement) = counts[0];
+        if agreement >= min_agreement {
+            return Ok(waypoint);
+        }
+    }
+    bail!(
+        "No epoch change is returned by {} of the endpoints, {} answered",
+        min_agreement,
+        answers.len()
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_crypto::HashValue;
+
+    fn waypoint(version: u64, hash: u8) -> Waypoint {
+        format!("{}:{}", version, HashValue::new([hash; 32]).to_hex())
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_agreed_waypoint() {
+        let genesis = waypoint(0, 0);
+        let chain = vec![genesis, waypoint(10, 1), waypoint(20, 2)];
+        let answers = vec![chain.clone(), chain.clone(), chain];
+        assert_eq!(agreed_waypoint(&answers, 3).unwrap(), waypoint(20, 2));
+        assert_eq!(agreed_waypoint(&answers[..1], 1).unwrap(), waypoint(20, 2));
+        assert!(agreed_waypoint(&answers[..2], 3).is_err());
+    }
+
+    #[test]
+    fn test_agreed_waypoint_lagging() {
+        let genesis = waypoint(0, 0);
+        let answers = vec![
+            vec![genesis, waypoint(10, 1), waypoint(20, 2)],
+            vec![genesis, waypoint(10, 1)],
+            vec![genesis],
+        ];
+        // The latest epoch change returned by enough of the endpoints
+        assert_eq!(agreed_waypoint(&answers, 1).unwrap(), waypoint(20, 2));
+        assert_eq!(agreed_waypoint(&answers, 2).unwrap(), waypoint(10, 1));
+        assert_eq!(agreed_waypoint(&answers, 3).unwrap(), genesis);
+    }
+
+    #[test]
+    fn test_agreed_waypoint_fork() {
+        let genesis = waypoint(0, 0);
+        let answers = vec![
+            vec![genesis, waypoint(10, 1), waypoint(20, 2)],
+            vec![genesis, waypoint(10, 1), waypoint(20, 3)],
+            vec![genesis, waypoint(10, 1), waypoint(20, 2)],
+        ];
+        assert!(agreed_waypoint(&answers, 2).is_err());
+    }
+}
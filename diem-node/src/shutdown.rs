@@ -0,0 +1,163 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Stops the node gracefully on SIGTERM or SIGINT, see `ShutdownConfig`. Every chain drains first:
+//! its networks stop accepting inbound connections, and the node waits for its mempool broadcasts
+//! to be acknowledged and its state sync chunks in flight to be applied, for up to the drain
+//! period. Consensus, state sync and mempool are then stopped, and the storage flushed.
+//!
+//! A second signal exits right away, without waiting for the drain.
+
+use crate::DiemHandle;
+use diem_config::config::ShutdownConfig;
+use diem_logger::prelude::*;
+use network::drain::Drain;
+use std::{
+    mem, process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{
+    runtime::{Builder, Runtime},
+    signal::unix::{signal, SignalKind},
+};
+
+/// How often the work in flight is checked while draining
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+    _runtime: Runtime,
+}
+
+impl ShutdownSignal {
+    /// Handles SIGTERM and SIGINT from now on, unparking the current thread on the first one
+    pub fn start() -> anyhow::Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("shutdown")
+            .enable_all()
+            .build()?;
+        let (mut terminations, mut interrupts) = {
+            let _guard = runtime.enter();
+            (
+                signal(SignalKind::terminate())?,
+                signal(SignalKind::interrupt())?,
+            )
+        };
+
+        let requested = Arc::new(AtomicBool::new(false));
+        let signaled = requested.clone();
+        let node = thread::current();
+        runtime.spawn(async move {
+            loop {
+                let name = tokio::select! {
+                    _ = terminations.recv() => "SIGTERM",
+                    _ = interrupts.recv() => "SIGINT",
+                };
+                if signaled.swap(true, Ordering::AcqRel) {
+                    warn!("{} while shutting down, exiting without draining", name);
+                    process::exit(1);
+                }
+                info!("{}, shutting down", name);
+                node.unpark();
+            }
+        });
+        Ok(Self {
+            requested,
+            _runtime: runtime,
+        })
+    }
+
+    pub fn requested(&self) -> bool {
+        self.requested.load(Ordering::Acquire)
+    }
+}
+
+/// Drains the chains of the node for up to the drain period, then closes them
+pub fn shutdown(mut node_handle: DiemHandle, config: &ShutdownConfig) {
+    let mut handles = mem::take(&mut node_handle._chains);
+    handles.insert(0, node_handle);
+    let drains: Vec<_> = handles.iter().map(|handle| handle.drain.as_ref()).collect();
+    drain(&drains, Duration::from_millis(config.drain_period_ms));
+
+    for handle in handles {
+        // Consensus first, it commits through state sync and mempool
+        drop(handle._consensus_runtime);
+        drop(handle._state_sync_bootstrapper);
+        drop(handle._mempool);
+        match handle.diem_db.flush() {
+            Ok(()) => info!("Storage flushed"),
+            Err(e) => error!("Unable to flush the storage: {}", e),
+        }
+    }
+}
+
+/// Starts draining, then waits for the work in flight for up to `period`. Returns whether it was
+/// all done in time.
+fn drain(drains: &[&Drain], period: Duration) -> bool {
+    for drain in drains {
+        drain.start();
+    }
+
+    info!("Draining for up to {} ms", period.as_millis());
+    let deadline = Instant::now() + period;
+    loop {
+        let pending: Vec<_> = drains.iter().flat_map(|drain| drain.pending()).collect();
+        if pending.is_empty() {
+            info!("Drained");
+            return true;
+        }
+        if Instant::now() >= deadline {
+            warn!("The drain period elapsed, still in flight: {:?}", pending);
+            return false;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_drain() {
+        let chain = Arc::new(Drain::new());
+        let in_flight = Arc::new(AtomicUsize::new(3));
+        let counted = in_flight.clone();
+        let draining = chain.clone();
+        chain.register("mempool", move || {
+            // The work is only waited for once new work is refused
+            assert!(draining.is_draining());
+            let count = counted.load(Ordering::Acquire);
+            counted.store(count.saturating_sub(1), Ordering::Release);
+            count
+        });
+        let idle = Drain::new();
+        idle.register("state_sync", || 0);
+
+        let start = Instant::now();
+        assert!(drain(&[chain.as_ref(), &idle], Duration::from_secs(10)));
+        assert!(idle.is_draining());
+        assert_eq!(in_flight.load(Ordering::Acquire), 0);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_drain_timeout() {
+        let chain = Drain::new();
+        chain.register("mempool", || 1);
+
+        let start = Instant::now();
+        assert!(!drain(&[&chain], Duration::from_millis(300)));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(300));
+        assert!(elapsed < Duration::from_millis(300) + 10 * POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,200 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Opt-in reports of the node health to a collector, see `TelemetryConfig`.
+//!
+//! Like the health check, the synced version is read from the state sync metrics and the peers
+//! from the `PeerMetadataStorage`. A failed report is logged and the next one sent on schedule.
+
+use diem_config::config::{NodeConfig, TelemetryConfig};
+use diem_logger::prelude::*;
+use diem_metrics::metric_value;
+use diem_types::chain_id::ChainId;
+use network::peer_metadata::PeerMetadataStorage;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Collectors answering slower than this are skipped until the next report
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Telemetry {
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    node_name: Option<String>,
+    version: &'static str,
+    role: String,
+    chain_id: String,
+    synced_version: Option<u64>,
+    /// The highest version known from peers
+    highest_version: Option<u64>,
+    /// Connected peers by network
+    peers: BTreeMap<String, usize>,
+}
+
+struct Reporter {
+    config: TelemetryConfig,
+    role: String,
+    chain_id: ChainId,
+    peer_metadata: Arc<PeerMetadataStorage>,
+}
+
+impl Telemetry {
+    /// Starts reporting if enabled
+    pub fn start(
+        node_config: &NodeConfig,
+        chain_id: ChainId,
+        peer_metadata: Arc<PeerMetadataStorage>,
+    ) -> anyhow::Result<Option<Self>> {
+        let config = node_config.telemetry.clone();
+        if !config.enabled {
+            return Ok(None);
+        }
+        let client = reqwest::blocking::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        let reporter = Reporter {
+            config,
+            role: node_config.base.role.to_string(),
+            chain_id,
+            peer_metadata,
+        };
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let telemetry = Self {
+            stopped: stopped.clone(),
+        };
+        info!(
+            "Reporting telemetry to {} every {} ms",
+            reporter.config.collector_url, reporter.config.report_interval_ms
+        );
+        thread::Builder::new()
+            .name("telemetry".into())
+            .spawn(move || {
+                let interval = Duration::from_millis(reporter.config.report_interval_ms);
+                while !stopped.load(Ordering::Acquire) {
+                    let report = reporter.report();
+                    let url = &reporter.config.collector_url;
+                    match client.post(url).json(&report).send() {
+                        Ok(response) if !response.status().is_success() => {
+                            warn!(
+                                "Telemetry collector {} responded with {}",
+                                url,
+                                response.status()
+                            )
+                        }
+                        Ok(_) => (),
+                        Err(e) => warn!("Unable to report telemetry to {}: {}", url, e),
+                    }
+                    thread::sleep(interval);
+                }
+            })?;
+        Ok(Some(telemetry))
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+impl Reporter {
+    fn report(&self) -> Report {
+        let version = |kind: &str| {
+            metric_value("diem_state_sync_version", &[("type", kind)]).map(|value| value as u64)
+        };
+        let mut peers = BTreeMap::new();
+        for (peer, metadata) in self.peer_metadata.peers() {
+            if metadata.is_connected() {
+                *peers.entry(peer.raw_network_id().to_string()).or_insert(0) += 1;
+            }
+        }
+        Report {
+            node_name: self.config.node_name.clone(),
+            version: env!("CARGO_PKG_VERSION"),
+            role: self.role.clone(),
+            chain_id: self.chain_id.to_string(),
+            synced_version: version("committed"),
+            highest_version: version("highest"),
+            peers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::{
+        config::PeerRole,
+        network_id::{NetworkId, NodeNetworkId},
+    };
+    use diem_time_service::TimeService;
+    use diem_types::PeerId;
+    use netcore::transport::ConnectionOrigin;
+    use network::{
+        protocols::wire::handshake::v1::{MessagingProtocolVersion, SupportedProtocols},
+        transport::ConnectionMetadata,
+    };
+
+    fn peer_metadata() -> Arc<PeerMetadataStorage> {
+        Arc::new(PeerMetadataStorage::new(TimeService::real()))
+    }
+
+    #[test]
+    fn test_opt_in() {
+        let mut node_config = NodeConfig::default();
+        assert!(!node_config.telemetry.enabled);
+        let telemetry = Telemetry::start(&node_config, ChainId::test(), peer_metadata()).unwrap();
+        assert!(telemetry.is_none());
+
+        node_config.telemetry.enabled = true;
+        let telemetry = Telemetry::start(&node_config, ChainId::test(), peer_metadata()).unwrap();
+        assert!(telemetry.is_some());
+    }
+
+    #[test]
+    fn test_report_is_anonymous() {
+        let peer_metadata = peer_metadata();
+        let peer_id = PeerId::random();
+        let address = "/ip4/203.0.113.7/tcp/6180";
+        peer_metadata.insert_connection(
+            NodeNetworkId::new(NetworkId::Public, 0),
+            &ConnectionMetadata::new(
+                peer_id,
+                1.into(),
+                address.parse().unwrap(),
+                ConnectionOrigin::Outbound,
+                MessagingProtocolVersion::V1,
+                SupportedProtocols::default(),
+                PeerRole::Upstream,
+            ),
+        );
+        let reporter = Reporter {
+            config: TelemetryConfig::default(),
+            role: "full_node".into(),
+            chain_id: ChainId::test(),
+            peer_metadata,
+        };
+
+        let report = reporter.report();
+        assert_eq!(report.node_name, None);
+        assert_eq!(report.peers.get("Public"), Some(&1));
+        // Peers are only counted, neither their id nor their address leaves the node
+        let payload = serde_yaml::to_string(&report).unwrap();
+        assert!(!payload.contains(&peer_id.to_string()));
+        assert!(!payload.contains(&peer_id.short_str_lossless()));
+        assert!(!payload.contains("203.0.113.7"));
+    }
+}
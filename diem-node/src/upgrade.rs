@@ -0,0 +1,222 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Keeps the node in step with the on-chain `DiemVersion`, see `UpgradeConfig`. The node refuses
+//! to start on a chain that requires a newer version than the binary supports, or that is past
+//! the configured `halt_at_version`, and halts once the running chain gets there.
+//!
+//! The on-chain version is checked again in the block it changes, notified on the `ReconfigBus`,
+//! and the halt boundary every poll interval. The state is published as the `diem_upgrade_state`
+//! gauges, which the health check reads.
+
+use anyhow::{bail, format_err, Result};
+use channel::diem_channel::Receiver;
+use diem_config::config::UpgradeConfig;
+use diem_logger::prelude::*;
+use diem_metrics::{register_int_gauge_vec, IntGaugeVec};
+use diem_types::{
+    account_config::diem_root_address,
+    account_state::AccountState,
+    on_chain_config::{DiemVersion, OnChainConfig, OnChainConfigPayload, DIEM_MAX_KNOWN_VERSION},
+    transaction::Version,
+};
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use storage_interface::DbReader;
+use subscription_service::ReconfigBus;
+use tokio::runtime::{Builder, Runtime};
+
+static UPGRADE_STATE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_upgrade_state",
+        "On-chain version of the node and its upgrade boundary",
+        &["field"]
+    )
+    .unwrap()
+});
+
+pub struct UpgradeCoordinator {
+    halted: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl UpgradeCoordinator {
+    /// Checks that the binary can run the chain in `db`, then watches it
+    pub fn start(
+        config: &UpgradeConfig,
+        db: Arc<dyn DbReader>,
+        reconfig_bus: &ReconfigBus,
+    ) -> Result<Self> {
+        let (version, onchain) = read_state(db.as_ref())?;
+        publish(config, onchain.as_ref(), false);
+        if let Some(reason) = halt_reason(config, version, onchain.as_ref()) {
+            bail!("Refusing to start, {}", reason);
+        }
+        info!(
+            "On-chain version {}, this binary supports up to {}",
+            onchain.map_or_else(|| "unknown".into(), |onchain| onchain.major.to_string()),
+            DIEM_MAX_KNOWN_VERSION.major
+        );
+
+        let halted = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let coordinator = Self {
+            halted: halted.clone(),
+            stopped: stopped.clone(),
+        };
+        let config = config.clone();
+        let mut reconfig_events =
+            Some(reconfig_bus.subscribe("upgrade", vec![DiemVersion::CONFIG_ID], vec![]));
+        let runtime = Builder::new_current_thread().enable_time().build()?;
+        thread::Builder::new()
+            .name("upgrade".into())
+            .spawn(move || {
+                let interval = Duration::from_millis(config.poll_interval_ms);
+                while !stopped.load(Ordering::Acquire) {
+                    wait(&runtime, &mut reconfig_events, interval);
+                    let (version, onchain) = match read_state(db.as_ref()) {
+                        Ok(state) => state,
+                        Err(e) => {
+                            warn!("Unable to read the on-chain version: {}", e);
+                            continue;
+                        }
+                    };
+                    let reason = halt_reason(&config, version, onchain.as_ref());
+                    publish(&config, onchain.as_ref(), reason.is_some());
+                    if let Some(reason) = reason {
+                        error!("Halting the node, {}", reason);
+                        halted.store(true, Ordering::Release);
+                        return;
+                    }
+                }
+            })?;
+        Ok(coordinator)
+    }
+
+    /// Whether the node reached a point it must not run past
+    pub fn halted(&self) -> bool {
+        self.halted.load(Ordering::Acquire)
+    }
+}
+
+impl Drop for UpgradeCoordinator {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+/// Waits for the on-chain version to change, `interval` at most
+fn wait(
+    runtime: &Runtime,
+    reconfig_events: &mut Option<Receiver<(), OnChainConfigPayload>>,
+    interval: Duration,
+) {
+    match reconfig_events {
+        Some(events) => {
+            if let Ok(None) = runtime.block_on(tokio::time::timeout(interval, events.next())) {
+                // State sync stopped, only the interval is left
+                *reconfig_events = None;
+            }
+        }
+        None => thread::sleep(interval),
+    }
+}
+
+/// The latest ledger version and the on-chain version of the binary at it
+fn read_state(db: &dyn DbReader) -> Result<(Version, Option<DiemVersion>)> {
+    let version = db.get_latest_version()?;
+    let blob = db
+        .get_latest_account_state(diem_root_address())?
+        .ok_or_else(|| format_err!("missing Diem root address account state"))?;
+    let onchain = AccountState::try_from(&blob)?.get_diem_version()?;
+    Ok((version, onchain))
+}
+
+/// Why the node must not run at `version`, if it must not
+fn halt_reason(
+    config: &UpgradeConfig,
+    version: Version,
+    onchain: Option<&DiemVersion>,
+) -> Option<String> {
+    if let Some(onchain) = onchain {
+        if config.enforce_version && onchain > &DIEM_MAX_KNOWN_VERSION {
+            return Some(format!(
+                "the chain requires version {}, this binary supports up to {}",
+                onchain.major, DIEM_MAX_KNOWN_VERSION.major
+            ));
+        }
+    }
+    match config.halt_at_version {
+        Some(boundary) if version >= boundary => Some(format!(
+            "the ledger is at version {}, the halt boundary is {}",
+            version, boundary
+        )),
+        _ => None,
+    }
+}
+
+fn publish(config: &UpgradeConfig, onchain: Option<&DiemVersion>, halting: bool) {
+    let set = |field: &str, value: u64| UPGRADE_STATE.with_label_values(&[field]).set(value as i64);
+    if let Some(onchain) = onchain {
+        set("onchain_version", onchain.major);
+    }
+    set("supported_version", DIEM_MAX_KNOWN_VERSION.major);
+    set("halt_at_version", config.halt_at_version.unwrap_or(0));
+    set("halting", halting as u64);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn diem_version(major: u64) -> DiemVersion {
+        DiemVersion { major }
+    }
+
+    #[test]
+    fn test_halt_on_newer_version() {
+        let mut config = UpgradeConfig::default();
+        let supported = diem_version(DIEM_MAX_KNOWN_VERSION.major);
+        let newer = diem_version(DIEM_MAX_KNOWN_VERSION.major + 1);
+
+        assert_eq!(halt_reason(&config, 100, None), None);
+        assert_eq!(halt_reason(&config, 100, Some(&supported)), None);
+        assert_eq!(
+            halt_reason(&config, 100, Some(&newer)),
+            Some(format!(
+                "the chain requires version {}, this binary supports up to {}",
+                newer.major, supported.major
+            ))
+        );
+
+        // Running a newer chain is left to the operator
+        config.enforce_version = false;
+        assert_eq!(halt_reason(&config, 100, Some(&newer)), None);
+    }
+
+    #[test]
+    fn test_halt_at_version() {
+        let config = UpgradeConfig {
+            halt_at_version: Some(1_000),
+            ..UpgradeConfig::default()
+        };
+        let supported = diem_version(DIEM_MAX_KNOWN_VERSION.major);
+
+        assert_eq!(halt_reason(&config, 999, Some(&supported)), None);
+        assert_eq!(
+            halt_reason(&config, 1_000, Some(&supported)),
+            Some("the ledger is at version 1000, the halt boundary is 1000".into())
+        );
+        assert!(halt_reason(&config, 2_000, None).is_some());
+    }
+}
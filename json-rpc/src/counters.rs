@@ -55,6 +55,17 @@ pub static INVALID_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+//////// 0L ////////
+/// Cumulative number of requests throttled before any of their calls is served
+pub static THROTTLED_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_client_service_throttled_requests_count",
+        "Cumulative number of requests that JSON RPC client service throttles",
+        &["reason"] // rate_limited / busy
+    )
+    .unwrap()
+});
+
 /// Cumulative number of server internal errors.
 pub static INTERNAL_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
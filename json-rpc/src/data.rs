@@ -6,19 +6,26 @@ use crate::{
     views::{
         AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, EventWithProofView,
         MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView, TowerStateResourceView, OracleUpgradeStateView, WaypointView
+        TransactionsWithProofsView, TowerStateResourceView, OracleUpgradeStateView, WaypointView,
+        NetworkPeerView, SimulationView,
     },
 };
 use anyhow::{format_err, Result};
 // use diem_client::views::WaypointView;
 // use diem_client::views::TowerStateResourceView;
 use diem_crypto::HashValue;
-use diem_types::{account_address::AccountAddress, account_config::{diem_root_address, resources::dual_attestation::Limit, AccountResource}, account_state::AccountState, chain_id::ChainId, event::EventKey, ledger_info::LedgerInfoWithSignatures, waypoint::Waypoint};
+use diem_state_view::StateViewId;
+use diem_types::{account_address::AccountAddress, account_config::{diem_root_address, resources::dual_attestation::Limit, AccountResource}, account_state::AccountState, chain_id::ChainId, event::EventKey, ledger_info::LedgerInfoWithSignatures, transaction::{SignedTransaction, TransactionStatus}, waypoint::Waypoint};
+use diem_vm::DiemVM;
+use network::peer_metadata::PeerMetadataStorage;
+use scratchpad::SparseMerkleTree;
 use std::{
     cmp::min,
     convert::{TryFrom, TryInto},
+    sync::Arc,
+    time::Duration,
 };
-use storage_interface::{DbReader, Order};
+use storage_interface::{state_view::VerifiedStateView, DbReader, Order};
 
 pub fn get_account_state(
     db: &dyn DbReader,
@@ -322,4 +329,60 @@ pub fn get_waypoint(
     ledger_info: &LedgerInfoWithSignatures,
 ) -> Result<WaypointView, JsonRpcError> {
     Ok(Waypoint::new_any(ledger_info.ledger_info()).try_into()?)
-}
\ No newline at end of file
+}
+
+//////// 0L ////////
+/// Returns the peers connected to every network of the node, at unix time `now`
+pub fn get_network_peers(
+    peer_metadata: &PeerMetadataStorage,
+    now: Duration,
+) -> Result<Vec<NetworkPeerView>, JsonRpcError> {
+    let mut peers: Vec<_> = peer_metadata
+        .peers()
+        .into_iter()
+        .filter(|(_, metadata)| metadata.is_connected())
+        .map(|(peer, metadata)| NetworkPeerView {
+            network: peer.raw_network_id().to_string(),
+            peer_id: peer.peer_id(),
+            role: format!("{:?}", metadata.role),
+            direction: metadata.origin.to_string(),
+            version: metadata.messaging_protocol.to_string(),
+            uptime_secs: metadata.uptime(now).as_secs(),
+        })
+        .collect();
+    peers.sort_by(|a, b| (&a.network, a.peer_id).cmp(&(&b.network, b.peer_id)));
+    Ok(peers)
+}
+
+//////// 0L ////////
+/// Executes the transaction against the latest state without committing it, nor checking its
+/// signature. A transaction the VM discards is an error, as on submission.
+pub fn simulate_transaction(
+    db: Arc<dyn DbReader>,
+    txn: SignedTransaction,
+) -> Result<SimulationView, JsonRpcError> {
+    let (version, state_root) = db.get_latest_state_root()?;
+    let smt = SparseMerkleTree::new(state_root);
+    let state_view = VerifiedStateView::new(
+        StateViewId::Miscellaneous,
+        db,
+        Some(version),
+        state_root,
+        &smt,
+    );
+    let (vm_status, output) = DiemVM::simulate_signed_transaction(txn, &state_view);
+    let status = match output.status() {
+        TransactionStatus::Keep(status) => status,
+        _ => return Err(JsonRpcError::vm_status(vm_status.status_code())),
+    };
+    let events = output
+        .events()
+        .iter()
+        .map(|event| EventView::try_from((version + 1, event.clone())))
+        .collect::<Result<_>>()?;
+    Ok(SimulationView {
+        vm_status: status.into(),
+        gas_used: output.gas_used(),
+        events,
+    })
+}
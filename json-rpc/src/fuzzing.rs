@@ -120,6 +120,8 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         diem_types::chain_id::ChainId::test(),
         config::DEFAULT_BATCH_SIZE_LIMIT,
         config::DEFAULT_PAGE_SIZE_LIMIT,
+        None,
+        None,
     );
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -138,7 +140,7 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         }
     });
     let body = rt.block_on(async {
-        let reply = runtime::rpc_endpoint(json_request, service, None)
+        let reply = runtime::rpc_endpoint(json_request, service, None, None)
             .await
             .unwrap();
 
@@ -11,6 +11,7 @@
 //! Module organization:
 //! ├── methods.rs        # contains all available JSON RPC method handlers
 //! ├── runtime.rs        # implementation of JSON RPC protocol over HTTP
+//! ├── rate_limit.rs     # throttling of the clients by IP
 //! ├── tests.rs          # tests
 
 #[macro_use]
@@ -19,6 +20,7 @@ mod util;
 mod counters;
 pub mod data;
 mod methods;
+mod rate_limit;
 mod runtime;
 
 pub use diem_json_rpc_types::{errors, response, views};
@@ -31,4 +33,4 @@ pub mod fuzzing;
 #[cfg(any(test, feature = "fuzzing"))]
 pub(crate) mod tests;
 #[cfg(any(test, feature = "fuzzing"))]
-pub use tests::utils::test_bootstrap;
+pub use tests::utils::{mock_db, test_bootstrap, MockDiemDB};
@@ -5,10 +5,12 @@
 use crate::{
     data,
     errors::JsonRpcError,
+    rate_limit::{RpcRateLimiter, Throttled},
     views::{
         AccountStateWithProofView, AccountView, CurrencyInfoView, EventView, EventWithProofView,
         MetadataView, TowerStateResourceView, OracleUpgradeStateView, StateProofView,
-        TransactionListView, TransactionView, TransactionsWithProofsView, WaypointView
+        TransactionListView, TransactionView, TransactionsWithProofsView, WaypointView,
+        NetworkPeerView, SimulationView,
     },
 };
 use anyhow::Result;
@@ -16,10 +18,12 @@ use diem_config::config::RoleType;
 use diem_json_rpc_types::request::{
     GetAccountParams, GetAccountStateWithProofParams, GetAccountTransactionParams,
     GetAccountTransactionsParams, GetCurrenciesParams, GetEventsParams, GetEventsWithProofsParams,
-    GetMetadataParams, GetTowerStateParams, GetNetworkStatusParams, GetStateProofParams,
+    GetMetadataParams, GetTowerStateParams, GetNetworkPeersParams, GetNetworkStatusParams,
+    GetStateProofParams,
     GetTransactionsParams, GetTransactionsWithProofsParams, MethodRequest,
     SubmitParams,
 };
+use diem_json_rpc_types::Method;
 use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
     chain_id::ChainId,
@@ -28,9 +32,16 @@ use diem_types::{
 };
 use fail::fail_point;
 use futures::{channel::oneshot, SinkExt};
+use network::peer_metadata::PeerMetadataStorage;
 use serde_json::Value;
-use std::{borrow::Borrow, sync::Arc};
+use std::{
+    borrow::Borrow,
+    net::IpAddr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use storage_interface::DbReader;
+use tokio::sync::OwnedSemaphorePermit;
 
 #[derive(Clone)]
 pub(crate) struct JsonRpcService {
@@ -40,6 +51,10 @@ pub(crate) struct JsonRpcService {
     chain_id: ChainId,
     batch_size_limit: u16,
     page_size_limit: u16,
+    /// The peers served by `get_network_peers`, if exposed
+    peer_metadata: Option<Arc<PeerMetadataStorage>>, //////// 0L ////////
+    /// Throttles the clients by IP, if enabled
+    rate_limiter: Option<Arc<RpcRateLimiter>>, //////// 0L ////////
 }
 
 impl JsonRpcService {
@@ -50,6 +65,8 @@ impl JsonRpcService {
         chain_id: ChainId,
         batch_size_limit: u16,
         page_size_limit: u16,
+        peer_metadata: Option<Arc<PeerMetadataStorage>>,
+        rate_limiter: Option<Arc<RpcRateLimiter>>,
     ) -> Self {
         Self {
             db,
@@ -58,6 +75,8 @@ impl JsonRpcService {
             chain_id,
             batch_size_limit,
             page_size_limit,
+            peer_metadata,
+            rate_limiter,
         }
     }
 
@@ -90,8 +109,40 @@ impl JsonRpcService {
         self.chain_id
     }
 
+    //////// 0L ////////
+    /// Admits a request of `calls` method calls from the IP, see `RpcRateLimiter::admit`
+    pub fn admit_request(
+        &self,
+        ip: Option<IpAddr>,
+        calls: usize,
+    ) -> Result<Option<OwnedSemaphorePermit>, Throttled> {
+        self.rate_limiter
+            .as_ref()
+            .map(|rate_limiter| rate_limiter.admit(ip, calls))
+            .transpose()
+    }
+
+    pub fn check_method_rate(
+        &self,
+        ip: Option<IpAddr>,
+        method: Method,
+    ) -> Result<(), JsonRpcError> {
+        match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.check_method(ip, method),
+            None => Ok(()),
+        }
+    }
+
     pub fn validate_batch_size_limit(&self, size: usize) -> Result<(), JsonRpcError> {
-        self.validate_size_limit("batch size", self.batch_size_limit, size)
+        //////// 0L ////////
+        // a batch of more calls than the bucket of an IP holds is never admitted
+        let limit = match &self.rate_limiter {
+            Some(rate_limiter) => {
+                rate_limiter.max_calls().min(self.batch_size_limit as usize) as u16
+            }
+            None => self.batch_size_limit,
+        };
+        self.validate_size_limit("batch size", limit, size)
     }
 
     pub fn validate_page_size_limit(&self, size: usize) -> Result<(), JsonRpcError> {
@@ -193,6 +244,12 @@ impl<'a> Handler<'a> {
             MethodRequest::GetWaypointView() => {
                 serde_json::to_value(self.get_waypoint().await?)?
             }
+            MethodRequest::GetNetworkPeers(params) => {
+                serde_json::to_value(self.get_network_peers(params).await?)?
+            }
+            MethodRequest::SimulateTransaction(params) => {
+                serde_json::to_value(self.simulate_transaction(params).await?)?
+            }
         };
         Ok(response)
     }
@@ -395,4 +452,34 @@ impl<'a> Handler<'a> {
     ) -> Result<WaypointView, JsonRpcError> {
         data::get_waypoint(self.ledger_info)
     }
-}
\ No newline at end of file
+
+    /// Returns the peers connected to every network of the node. Not found unless the node
+    /// exposes them, see `JsonRpcConfig::expose_network_peers`.
+    async fn get_network_peers(
+        &self,
+        _params: GetNetworkPeersParams,
+    ) -> Result<Vec<NetworkPeerView>, JsonRpcError> {
+        let peer_metadata = self
+            .service
+            .peer_metadata
+            .as_ref()
+            .ok_or_else(JsonRpcError::method_not_found)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        data::get_network_peers(peer_metadata, now)
+    }
+
+    /// Executes a transaction against the latest state without submitting it, for clients to learn
+    /// its gas usage, status and events. The signature is not checked, only the public key.
+    async fn simulate_transaction(
+        &self,
+        params: SubmitParams,
+    ) -> Result<SimulationView, JsonRpcError> {
+        let db = self.service.db.clone();
+        // Execution can take a while, don't hold up the other requests
+        tokio::task::spawn_blocking(move || data::simulate_transaction(db, params.data))
+            .await
+            .map_err(|e| JsonRpcError::internal_error(e.to_string()))?
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Throttles the clients of a public node by IP, see `RpcRateLimitConfig`. An IPv6 client is
+//! throttled by its /64 network, which a single host usually holds whole. The buckets are checked
+//! by the validation of the node config.
+
+use diem_config::config::{RpcBucketConfig, RpcRateLimitConfig};
+use diem_infallible::Mutex;
+use diem_json_rpc_types::{errors::JsonRpcError, Method};
+use diem_logger::warn;
+use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv6Addr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How often the buckets of the clients gone idle are dropped
+const GARBAGE_COLLECTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Why a request is not served
+#[derive(Debug)]
+pub(crate) enum Throttled {
+    /// The IP made too many calls, it may retry at the instant
+    RateLimited(Option<Instant>),
+    /// The node serves `max_concurrent_requests` already
+    Busy,
+}
+
+pub(crate) struct RpcRateLimiter {
+    /// The calls of each IP, whatever their method
+    calls: TokenBucketRateLimiter<IpAddr>,
+    /// The size of the bucket of an IP, the most calls a request may make
+    max_calls: usize,
+    /// The calls of each IP to the methods with a bucket of their own
+    methods: HashMap<Method, TokenBucketRateLimiter<IpAddr>>,
+    /// The requests being served
+    requests: Arc<Semaphore>,
+    /// When the idle buckets were last dropped
+    last_garbage_collection: Mutex<Instant>,
+}
+
+impl RpcRateLimiter {
+    pub fn new(config: &RpcRateLimitConfig) -> Self {
+        let bucket = RpcBucketConfig {
+            size: config.ip_bucket_size,
+            rate: config.ip_bucket_rate,
+        };
+        let methods = config
+            .method_buckets
+            .iter()
+            .filter_map(|(name, bucket)| {
+                match serde_json::from_value(Value::String(name.clone())) {
+                    Ok(method) => Some((method, Self::limiter(config, name, *bucket))),
+                    Err(_) => {
+                        warn!("[json-rpc] no method {} to rate limit", name);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            calls: Self::limiter(config, "calls", bucket),
+            max_calls: config.ip_bucket_size,
+            methods,
+            requests: Arc::new(Semaphore::new(config.max_concurrent_requests)),
+            last_garbage_collection: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn limiter(
+        config: &RpcRateLimitConfig,
+        name: &str,
+        bucket: RpcBucketConfig,
+    ) -> TokenBucketRateLimiter<IpAddr> {
+        TokenBucketRateLimiter::new(
+            "json-rpc",
+            name.to_string(),
+            config.initial_bucket_fill_percentage,
+            bucket.size,
+            bucket.rate,
+            None,
+        )
+    }
+
+    /// The most calls of a request, a batch of more could never be admitted
+    pub fn max_calls(&self) -> usize {
+        self.max_calls
+    }
+
+    /// Admits a request of `calls` method calls, it is served as long as the permit is held
+    pub fn admit(
+        &self,
+        ip: Option<IpAddr>,
+        calls: usize,
+    ) -> Result<OwnedSemaphorePermit, Throttled> {
+        let permit = self
+            .requests
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| Throttled::Busy)?;
+        if let Some(ip) = ip {
+            self.garbage_collect();
+            self.calls
+                .bucket(client(ip))
+                .lock()
+                .acquire_all_tokens(calls)
+                .map_err(Throttled::RateLimited)?;
+        }
+        Ok(permit)
+    }
+
+    /// Drops the buckets that refilled, every `GARBAGE_COLLECTION_INTERVAL`, so that the clients
+    /// seen once don't hold memory
+    fn garbage_collect(&self) {
+        let mut last_garbage_collection = self.last_garbage_collection.lock();
+        if last_garbage_collection.elapsed() < GARBAGE_COLLECTION_INTERVAL {
+            return;
+        }
+        *last_garbage_collection = Instant::now();
+        self.calls.garbage_collect_full_buckets();
+        for limiter in self.methods.values() {
+            limiter.garbage_collect_full_buckets();
+        }
+    }
+
+    /// Takes a call from the bucket of the method for the IP, if the method has a bucket
+    pub fn check_method(&self, ip: Option<IpAddr>, method: Method) -> Result<(), JsonRpcError> {
+        match (ip, self.methods.get(&method)) {
+            (Some(ip), Some(limiter)) => limiter
+                .bucket(client(ip))
+                .lock()
+                .acquire_all_tokens(1)
+                .map_err(|_| JsonRpcError::rate_limited(Some(method))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The key of the buckets of an IP: the IPv4 address, or the /64 network of the IPv6 one
+fn client(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ip) => match ip.segments() {
+            [0, 0, 0, 0, 0, 0xffff, ..] => ip.to_ipv4().map_or(IpAddr::V6(ip), IpAddr::V4),
+            _ => IpAddr::V6(Ipv6Addr::from(u128::from(ip) & !u128::from(std::u64::MAX))),
+        },
+        ip => ip,
+    }
+}
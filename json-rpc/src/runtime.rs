@@ -5,28 +5,30 @@ use crate::{
     counters,
     errors::is_internal_error,
     methods::{Handler, JsonRpcService},
+    rate_limit::{RpcRateLimiter, Throttled},
     response::{JsonRpcResponse, X_DIEM_CHAIN_ID, X_DIEM_TIMESTAMP_USEC_ID, X_DIEM_VERSION_ID},
     util::{sdk_info_from_user_agent, SdkInfo},
 };
 use anyhow::{ensure, Result};
-use diem_config::config::{NodeConfig, RoleType};
-use diem_json_rpc_types::Method;
+use diem_config::config::{NodeConfig, RoleType, RpcRateLimitConfig, TlsConfig};
+use diem_json_rpc_types::{errors::JsonRpcError, Method};
 use diem_logger::{debug, Schema};
 use diem_mempool::MempoolClientSender;
 use diem_types::{chain_id::ChainId, ledger_info::LedgerInfoWithSignatures};
 use futures::future::{join_all, Either};
+use network::peer_metadata::PeerMetadataStorage;
 use rand::{rngs::OsRng, RngCore};
 use serde_json::Value;
 use std::{
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     ops::Sub,
     sync::Arc,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use storage_interface::DbReader;
 use tokio::runtime::{Builder, Runtime};
 use warp::{
-    http::header,
+    http::{header, StatusCode},
     reject::{self, Reject},
     Filter, Reply,
 };
@@ -99,12 +101,13 @@ pub fn bootstrap(
     batch_size_limit: u16,
     page_size_limit: u16,
     content_len_limit: usize,
-    tls_cert_path: &Option<String>,
-    tls_key_path: &Option<String>,
+    tls: Option<&TlsConfig>,
     diem_db: Arc<dyn DbReader>,
     mp_sender: MempoolClientSender,
     role: RoleType,
     chain_id: ChainId,
+    peer_metadata: Option<Arc<PeerMetadataStorage>>,
+    rate_limit: &RpcRateLimitConfig,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("json-rpc")
@@ -112,6 +115,12 @@ pub fn bootstrap(
         .build()
         .expect("[json-rpc] failed to create runtime");
 
+    let rate_limiter = if rate_limit.enabled {
+        Some(Arc::new(RpcRateLimiter::new(rate_limit)))
+    } else {
+        None
+    };
+
     let service = JsonRpcService::new(
         diem_db.clone(),
         mp_sender,
@@ -119,6 +128,8 @@ pub fn bootstrap(
         chain_id,
         batch_size_limit,
         page_size_limit,
+        peer_metadata,
+        rate_limiter,
     );
 
     let base_route = warp::any()
@@ -128,6 +139,7 @@ pub fn bootstrap(
         .and(warp::body::json())
         .and(warp::any().map(move || service.clone()))
         .and(warp::filters::header::optional::<String>("user-agent"))
+        .and(diem_tls::remote_addr())
         .and_then(rpc_endpoint)
         .with(warp::log::custom(|info| {
             debug!(HttpRequestLog {
@@ -179,15 +191,17 @@ pub fn bootstrap(
     // Note: we need to enter the runtime context first to actually bind, since
     //       tokio TcpListener can only be bound inside a tokio context.
     let _guard = runtime.enter();
-    let server = match tls_cert_path {
+    let server = match tls {
         None => Either::Left(warp::serve(full_route).bind(address)),
-        Some(cert_path) => Either::Right(
-            warp::serve(full_route)
-                .tls()
-                .cert_path(cert_path)
-                .key_path(tls_key_path.as_ref().unwrap())
-                .bind(address),
-        ),
+        //////// 0L ////////
+        // Terminated by diem-tls, which reloads renewed certificates
+        Some(tls) => {
+            let config =
+                diem_tls::server_config(&tls.cert_path, &tls.key_path, tls.reload_interval())
+                    .expect("[json-rpc] invalid TLS config");
+            let listener = diem_tls::bind(address).expect("[json-rpc] failed to bind");
+            Either::Right(diem_tls::serve_filter(listener, config, full_route))
+        }
     };
     runtime.handle().spawn(server);
     runtime
@@ -199,18 +213,20 @@ pub fn bootstrap_from_config(
     chain_id: ChainId,
     diem_db: Arc<dyn DbReader>,
     mp_sender: MempoolClientSender,
+    peer_metadata: Arc<PeerMetadataStorage>,
 ) -> Runtime {
     bootstrap(
         config.json_rpc.address,
         config.json_rpc.batch_size_limit,
         config.json_rpc.page_size_limit,
         config.json_rpc.content_length_limit,
-        &config.json_rpc.tls_cert_path,
-        &config.json_rpc.tls_key_path,
+        config.json_rpc.tls().as_ref(),
         diem_db,
         mp_sender,
         config.base.role,
         chain_id,
+        Some(peer_metadata).filter(|_| config.json_rpc.expose_network_peers),
+        &config.json_rpc.rate_limit,
     )
 }
 
@@ -250,6 +266,7 @@ pub(crate) async fn rpc_endpoint(
     data: Value,
     service: JsonRpcService,
     user_agent: Option<String>,
+    remote_addr: Option<SocketAddr>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     let label = match data {
         Value::Array(_) => LABEL_BATCH,
@@ -259,7 +276,13 @@ pub(crate) async fn rpc_endpoint(
     let timer = counters::RPC_REQUEST_LATENCY
         .with_label_values(&[label])
         .start_timer();
-    let ret = rpc_endpoint_without_metrics(data, service, user_agent.as_deref()).await;
+    let ret = rpc_endpoint_without_metrics(
+        data,
+        service,
+        user_agent.as_deref(),
+        remote_addr.map(|addr| addr.ip()),
+    )
+    .await;
     timer.stop_and_record();
     ret
 }
@@ -268,6 +291,7 @@ async fn rpc_endpoint_without_metrics(
     data: Value,
     service: JsonRpcService,
     user_agent: Option<&str>,
+    remote_ip: Option<IpAddr>,
 ) -> Result<warp::reply::Response, warp::Rejection> {
     // take snapshot of latest version of DB to be used across all requests, especially for batched requests
     let ledger_info = service
@@ -285,12 +309,47 @@ async fn rpc_endpoint_without_metrics(
     let latest_ledger_timestamp_usecs = ledger_info.ledger_info().timestamp_usecs();
     let sdk_info = sdk_info_from_user_agent(user_agent);
 
+    //////// 0L ////////
+    // the permit is held until the response is built
+    let calls = match &data {
+        Value::Array(requests) => requests.len(),
+        _ => 1,
+    };
+    // an oversized batch gets the batch size error below, it takes no calls from the bucket of
+    // the IP
+    let oversized = data.is_array() && service.validate_batch_size_limit(calls).is_err();
+    let admitted = if oversized {
+        Ok(None)
+    } else {
+        service.admit_request(remote_ip, calls)
+    };
+    let _permit = match admitted {
+        Ok(permit) => permit,
+        Err(throttled) => {
+            let mut response = JsonRpcResponse::new(
+                chain_id,
+                latest_ledger_version,
+                latest_ledger_timestamp_usecs,
+            );
+            let http_response = throttled_response(throttled, &mut response);
+            log_response!(&trace_id, &response, data.is_array());
+            return Ok(http_response);
+        }
+    };
+
     let resp = if let Value::Array(requests) = data {
         match service.validate_batch_size_limit(requests.len()) {
             Ok(_) => {
                 // batch API call
                 let futures = requests.into_iter().map(|req| {
-                    rpc_request_handler(req, &service, &ledger_info, LABEL_BATCH, sdk_info)
+                    rpc_request_handler(
+                        req,
+                        &service,
+                        &ledger_info,
+                        LABEL_BATCH,
+                        sdk_info,
+                        remote_ip,
+                    )
                 });
                 let responses = join_all(futures).await;
                 for resp in &responses {
@@ -313,7 +372,15 @@ async fn rpc_endpoint_without_metrics(
         }
     } else {
         // single API call
-        let resp = rpc_request_handler(data, &service, &ledger_info, LABEL_SINGLE, sdk_info).await;
+        let resp = rpc_request_handler(
+            data,
+            &service,
+            &ledger_info,
+            LABEL_SINGLE,
+            sdk_info,
+            remote_ip,
+        )
+        .await;
         log_response!(&trace_id, &resp, false);
 
         warp::reply::json(&resp)
@@ -344,6 +411,7 @@ async fn rpc_request_handler(
     ledger_info: &LedgerInfoWithSignatures,
     request_type_label: &str,
     sdk_info: SdkInfo,
+    remote_ip: Option<IpAddr>,
 ) -> JsonRpcResponse {
     let handler = Handler::new(&service, &ledger_info);
 
@@ -361,7 +429,12 @@ async fn rpc_request_handler(
                 .with_label_values(&[request_type_label, request.method_request.method().as_str()])
                 .start_timer();
             response.id = Some(serde_json::to_value(&request.id).unwrap());
-            match handler.handle(request.method_request).await {
+            let admitted = service.check_method_rate(remote_ip, request.method_request.method());
+            let result = match admitted {
+                Ok(()) => handler.handle(request.method_request).await,
+                Err(e) => Err(e),
+            };
+            match result {
                 Ok(ret) => response.result = Some(ret),
                 Err(e) => response.error = Some(e),
             }
@@ -403,6 +476,7 @@ fn bump_counters(
                 -32601 => "method_not_found",
                 -32602 => "invalid_params",
                 -32604 => "invalid_format",
+                -32013 => "rate_limited",
                 _ => "unexpected_code",
             };
             counters::INVALID_REQUESTS
@@ -433,6 +507,44 @@ fn bump_counters(
     }
 }
 
+//////// 0L ////////
+/// Answers a request throttled before any of its calls is served
+fn throttled_response(
+    throttled: Throttled,
+    response: &mut JsonRpcResponse,
+) -> warp::reply::Response {
+    let (reason, status, error, retry_at) = match throttled {
+        Throttled::RateLimited(retry_at) => (
+            "rate_limited",
+            StatusCode::TOO_MANY_REQUESTS,
+            JsonRpcError::rate_limited(None),
+            retry_at,
+        ),
+        Throttled::Busy => (
+            "busy",
+            StatusCode::SERVICE_UNAVAILABLE,
+            JsonRpcError::server_busy(),
+            None,
+        ),
+    };
+    counters::THROTTLED_REQUESTS
+        .with_label_values(&[reason])
+        .inc();
+    response.error = Some(error);
+
+    let mut http_response =
+        warp::reply::with_status(warp::reply::json(response), status).into_response();
+    if let Some(retry_at) = retry_at {
+        // whole seconds, rounded up
+        let wait = retry_at.saturating_duration_since(Instant::now());
+        let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        http_response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, header::HeaderValue::from(secs));
+    }
+    http_response
+}
+
 /// Warp rejection types
 #[derive(Debug)]
 struct DatabaseError;
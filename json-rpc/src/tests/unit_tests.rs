@@ -3,6 +3,7 @@
 
 use crate::{
     errors::ServerCode,
+    rate_limit::{RpcRateLimiter, Throttled},
     runtime::check_latest_ledger_info_timestamp,
     tests::utils::{
         create_database_client_and_runtime, create_db_and_runtime, mock_db, test_bootstrap,
@@ -12,9 +13,17 @@ use crate::{
     views::VMStatusView,
 };
 use diem_client::{views::TransactionDataView, BlockingClient, MethodRequest};
-use diem_config::{config::DEFAULT_CONTENT_LENGTH_LIMIT, utils};
+use diem_config::{
+    config::{
+        PeerRole, RoleType, RpcBucketConfig, RpcRateLimitConfig, DEFAULT_BATCH_SIZE_LIMIT,
+        DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+    },
+    network_id::{NetworkId, NodeNetworkId},
+    utils,
+};
 use diem_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
 use diem_metrics::get_all_metrics;
+use diem_time_service::TimeService;
 use diem_types::{
     account_address::AccountAddress,
     account_config::AccountResource,
@@ -28,8 +37,11 @@ use diem_types::{
     test_helpers::transaction_test_helpers::get_test_signed_txn,
     transaction::{Transaction, TransactionInfo, TransactionPayload},
     vm_status::StatusCode,
+    PeerId,
 };
 use futures::{channel::mpsc::channel, StreamExt};
+use netcore::transport::ConnectionOrigin;
+use network::{peer_metadata::PeerMetadataStorage, transport::ConnectionMetadata};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{
     cmp::{max, min},
@@ -1408,6 +1420,157 @@ fn test_get_network_status() {
     assert_eq!(connected_peers, 0);
 }
 
+//////// 0L ////////
+#[test]
+fn test_get_network_peers() {
+    let request = json!({"jsonrpc": "2.0", "method": "get_network_peers", "id": 1});
+    let client = reqwest::blocking::Client::new();
+
+    // Not exposed by default
+    let (_mock_db, _runtime, url, _) = create_db_and_runtime();
+    let resp: serde_json::Value = client
+        .post(&url)
+        .json(&request)
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    assert_eq!(resp["error"]["code"], json!(-32601));
+
+    let peer_metadata = Arc::new(PeerMetadataStorage::new(TimeService::real()));
+    let network = NodeNetworkId::new(NetworkId::vfn_network(), 0);
+    let connection = ConnectionMetadata::mock_with_role_and_origin(
+        PeerId::random(),
+        PeerRole::ValidatorFullNode,
+        ConnectionOrigin::Inbound,
+    );
+    peer_metadata.insert_connection(network, &connection);
+
+    let port = utils::get_available_port();
+    let (mp_sender, _mp_events) = channel(1);
+    let _runtime = crate::bootstrap(
+        format!("127.0.0.1:{}", port).parse().unwrap(),
+        DEFAULT_BATCH_SIZE_LIMIT,
+        DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_CONTENT_LENGTH_LIMIT,
+        None,
+        Arc::new(mock_db()),
+        mp_sender,
+        RoleType::Validator,
+        ChainId::test(),
+        Some(peer_metadata),
+        &RpcRateLimitConfig::default(),
+    );
+    let url = format!("http://127.0.0.1:{}", port);
+    let resp: serde_json::Value = client
+        .post(&url)
+        .json(&request)
+        .send()
+        .unwrap()
+        .json()
+        .unwrap();
+    let peers = resp["result"].as_array().unwrap();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0]["network"], json!("vfn"));
+    assert_eq!(peers[0]["peer_id"], json!(connection.remote_peer_id));
+    assert_eq!(peers[0]["role"], json!("ValidatorFullNode"));
+    assert_eq!(peers[0]["direction"], json!("inbound"));
+    assert_eq!(peers[0]["version"], json!("V1"));
+}
+
+//////// 0L ////////
+#[test]
+fn test_rate_limit() {
+    let mut rate_limit = RpcRateLimitConfig {
+        enabled: true,
+        ip_bucket_size: 5,
+        ip_bucket_rate: 1,
+        ..RpcRateLimitConfig::default()
+    };
+    rate_limit.method_buckets.insert(
+        "get_currencies".to_string(),
+        RpcBucketConfig { size: 1, rate: 1 },
+    );
+
+    let port = utils::get_available_port();
+    let (mp_sender, _mp_events) = channel(1);
+    let _runtime = crate::bootstrap(
+        format!("127.0.0.1:{}", port).parse().unwrap(),
+        DEFAULT_BATCH_SIZE_LIMIT,
+        DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_CONTENT_LENGTH_LIMIT,
+        None,
+        Arc::new(mock_db()),
+        mp_sender,
+        RoleType::Validator,
+        ChainId::test(),
+        None,
+        &rate_limit,
+    );
+    let url = format!("http://127.0.0.1:{}", port);
+    let client = reqwest::blocking::Client::new();
+
+    // The method bucket throttles the second call only
+    let request = json!({"jsonrpc": "2.0", "method": "get_currencies", "id": 1});
+    let resp = client.post(&url).json(&request).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp: serde_json::Value = resp.json().unwrap();
+    assert!(resp["error"].is_null());
+    let resp = client.post(&url).json(&request).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp: serde_json::Value = resp.json().unwrap();
+    assert_eq!(resp["error"]["code"], ServerCode::RateLimited as i16);
+
+    // 3 calls are left to the IP, a batch of 5 waits for the refill
+    let call = json!({"jsonrpc": "2.0", "method": "get_metadata", "id": 1});
+    let request = serde_json::Value::Array(vec![call.clone(); 5]);
+    let resp = client.post(&url).json(&request).send().unwrap();
+    assert_eq!(resp.status(), 429);
+    assert!(resp.headers().contains_key("retry-after"));
+    let resp: serde_json::Value = resp.json().unwrap();
+    assert_eq!(resp["error"]["code"], ServerCode::RateLimited as i16);
+
+    // A batch larger than the bucket could never pass, it is refused for its size
+    let request = serde_json::Value::Array(vec![call; 6]);
+    let resp = client.post(&url).json(&request).send().unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(!resp.headers().contains_key("retry-after"));
+    let resp: serde_json::Value = resp.json().unwrap();
+    assert_eq!(
+        resp["error"]["message"],
+        "Invalid Request: batch size = 6, exceed limit 5"
+    );
+
+    // The node serves one request at once
+    rate_limit.max_concurrent_requests = 1;
+    let rate_limiter = RpcRateLimiter::new(&rate_limit);
+    let permit = rate_limiter.admit(None, 1).unwrap();
+    assert!(matches!(rate_limiter.admit(None, 1), Err(Throttled::Busy)));
+    drop(permit);
+    rate_limiter.admit(None, 1).unwrap();
+
+    // The addresses of an IPv6 /64 network share a bucket
+    let rate_limiter = RpcRateLimiter::new(&rate_limit);
+    let ip = |ip: &str| Some(ip.parse().unwrap());
+    drop(rate_limiter.admit(ip("2001:db8:0:1::1"), 5).unwrap());
+    assert!(matches!(
+        rate_limiter.admit(ip("2001:db8:0:1:ffff::2"), 1),
+        Err(Throttled::RateLimited(_))
+    ));
+    drop(rate_limiter.admit(ip("2001:db8:0:2::1"), 1).unwrap());
+    drop(rate_limiter.admit(ip("::ffff:10.0.0.1"), 5).unwrap());
+    drop(rate_limiter.admit(ip("::ffff:10.0.0.2"), 5).unwrap());
+
+    // A bucket of no method throttles nothing
+    rate_limit.method_buckets.insert(
+        "get_nothing".to_string(),
+        RpcBucketConfig { size: 1, rate: 1 },
+    );
+    let rate_limiter = RpcRateLimiter::new(&rate_limit);
+    assert_eq!(rate_limiter.max_calls(), 5);
+    drop(rate_limiter.admit(ip("10.0.0.3"), 1).unwrap());
+}
+
 #[test]
 fn test_health_check() {
     let (_mock_db, _runtime, url, _) = create_db_and_runtime();
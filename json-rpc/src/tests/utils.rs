@@ -4,7 +4,8 @@
 use anyhow::{format_err, Error, Result};
 use diem_config::{
     config::{
-        RoleType, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+        RoleType, RpcRateLimitConfig, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT,
+        DEFAULT_PAGE_SIZE_LIMIT,
     },
     utils,
 };
@@ -70,12 +71,13 @@ pub fn test_bootstrap(
         DEFAULT_BATCH_SIZE_LIMIT,
         DEFAULT_PAGE_SIZE_LIMIT,
         DEFAULT_CONTENT_LENGTH_LIMIT,
-        &None,
-        &None,
+        None,
         diem_db,
         mp_sender,
         RoleType::Validator,
         ChainId::test(),
+        None,
+        &RpcRateLimitConfig::default(),
     )
 }
 
@@ -3,7 +3,10 @@
 
 use serde_json::json;
 
-use diem_crypto::hash::CryptoHash;
+use diem_crypto::{
+    ed25519::{Ed25519Signature, ED25519_SIGNATURE_LENGTH},
+    hash::CryptoHash,
+};
 use diem_transaction_builder::stdlib;
 use diem_types::{
     access_path::AccessPath,
@@ -15,10 +18,16 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config::DIEM_MAX_KNOWN_VERSION,
     proof::TransactionAccumulatorRangeProof,
-    transaction::{ChangeSet, Transaction, TransactionInfo, TransactionPayload, WriteSetPayload},
+    transaction::{
+        ChangeSet, SignedTransaction, Transaction, TransactionInfo, TransactionPayload,
+        WriteSetPayload,
+    },
     write_set::{WriteOp, WriteSet, WriteSetMut},
 };
-use std::{convert::TryInto, ops::Deref};
+use std::{
+    convert::{TryFrom, TryInto},
+    ops::Deref,
+};
 
 use diem_json_rpc_types::views::EventView;
 use diem_transaction_builder::stdlib::{
@@ -571,6 +580,55 @@ fn create_test_cases() -> Vec<Test> {
                 );
             },
         },
+        Test {
+            name: "simulate transaction without submitting it",
+            run: |env: &mut testing::Env| {
+                let sender = env.vasps[0].children[0].clone();
+                let sequence_number = env
+                    .get_account_sequence(sender.address.to_string())
+                    .unwrap();
+                let txn = env.transfer_coins_txn((0, 0), (1, 0), 100);
+                // The signature is not checked
+                let unsigned = SignedTransaction::new(
+                    txn.into_raw_transaction(),
+                    sender.public_key.clone(),
+                    Ed25519Signature::try_from(&[0u8; ED25519_SIGNATURE_LENGTH][..]).unwrap(),
+                );
+                let txn_hex = hex::encode(bcs::to_bytes(&unsigned).unwrap());
+
+                let result = env
+                    .send("simulate_transaction", json!([txn_hex]))
+                    .result
+                    .unwrap();
+                assert_eq!(result["vm_status"], json!({"type": "executed"}));
+                assert!(result["gas_used"].as_u64().unwrap() > 0);
+                let events = result["events"].as_array().unwrap();
+                assert_eq!(events.len(), 2);
+                assert_eq!(events[0]["data"]["type"], json!("sentpayment"));
+                assert_eq!(events[1]["data"]["type"], json!("receivedpayment"));
+                assert_eq!(
+                    env.get_account_sequence(sender.address.to_string())
+                        .unwrap(),
+                    sequence_number
+                );
+
+                // A transaction the VM would discard is an error, as on submission
+                let txn = env.transfer_coins_txn((0, 0), (1, 0), 100);
+                let wrong_key = SignedTransaction::new(
+                    txn.into_raw_transaction(),
+                    env.vasps[1].children[0].public_key.clone(),
+                    Ed25519Signature::try_from(&[0u8; ED25519_SIGNATURE_LENGTH][..]).unwrap(),
+                );
+                let resp = env.send(
+                    "simulate_transaction",
+                    json!([hex::encode(bcs::to_bytes(&wrong_key).unwrap())]),
+                );
+                assert_eq!(
+                    resp.error.expect("error").message,
+                    "Server error: VM Validation error: INVALID_AUTH_KEY".to_string(),
+                );
+            },
+        },
         Test {
             name: "re-submit transaction won't fail",
             run: |env: &mut testing::Env| {
@@ -51,6 +51,13 @@ pub enum ServerCode {
     MempoolInvalidUpdate = -32010,
     MempoolVmError = -32011,
     MempoolUnknownError = -32012,
+
+    //////// 0L ////////
+    // Throttling - see `RpcRateLimitConfig`
+    RateLimited = -32013,
+    ServerBusy = -32014,
+    // Mempool admission rules - see `MempoolAdmissionConfig`
+    MempoolRejected = -32015,
 }
 
 /// JSON RPC server error codes for invalid request
@@ -173,6 +180,30 @@ impl JsonRpcError {
         }
     }
 
+    //////// 0L ////////
+    pub fn rate_limited(method: Option<Method>) -> Self {
+        let message = match method {
+            Some(method) => format!(
+                "Too many calls of method '{}', retry later",
+                method.as_str()
+            ),
+            None => "Too many calls, retry later".to_string(),
+        };
+        Self {
+            code: ServerCode::RateLimited as i16,
+            message,
+            data: None,
+        }
+    }
+
+    pub fn server_busy() -> Self {
+        Self {
+            code: ServerCode::ServerBusy as i16,
+            message: "Server busy, retry later".to_string(),
+            data: None,
+        }
+    }
+
     pub fn internal_error(message: String) -> Self {
         Self {
             code: ServerCode::DefaultServerError as i16,
@@ -189,6 +220,7 @@ impl JsonRpcError {
             MempoolStatusCode::InvalidUpdate => ServerCode::MempoolInvalidUpdate,
             MempoolStatusCode::VmError => ServerCode::MempoolVmError,
             MempoolStatusCode::UnknownStatus => ServerCode::MempoolUnknownError,
+            MempoolStatusCode::Rejected => ServerCode::MempoolRejected,
             MempoolStatusCode::Accepted => {
                 return Err(anyhow::format_err!(
                     "[JSON RPC] cannot create mempool error for mempool accepted status"
@@ -302,6 +334,7 @@ mod tests {
             MempoolStatusCode::UnknownStatus,
             ServerCode::MempoolUnknownError,
         );
+        assert_map_code(MempoolStatusCode::Rejected, ServerCode::MempoolRejected);
     }
 
     #[test]
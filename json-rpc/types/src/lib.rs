@@ -49,6 +49,8 @@ pub enum Method {
     GetTowerStateView,
     GetOracleUpgradeStateView,
     GetWaypointView,
+    GetNetworkPeers,
+    SimulateTransaction,
 }
 
 impl Method {
@@ -73,6 +75,8 @@ impl Method {
             
             Method::GetOracleUpgradeStateView => "get_oracle_upgrade_state_view",
             Method::GetWaypointView => "get_waypoint_view", 
+            Method::GetNetworkPeers => "get_network_peers",
+            Method::SimulateTransaction => "simulate_transaction",
         }
     }
 }
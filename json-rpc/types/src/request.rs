@@ -85,6 +85,8 @@ pub enum MethodRequest {
     GetTowerStateView(GetTowerStateParams),
     GetOracleUpgradeStateView(),
     GetWaypointView(),
+    GetNetworkPeers(GetNetworkPeersParams),
+    SimulateTransaction(SubmitParams),
 }
 
 impl MethodRequest {
@@ -130,6 +132,14 @@ impl MethodRequest {
             Method::GetWaypointView => {
                 MethodRequest::GetWaypointView()
             }
+
+            Method::GetNetworkPeers => {
+                MethodRequest::GetNetworkPeers(serde_json::from_value(value)?)
+            }
+
+            Method::SimulateTransaction => {
+                MethodRequest::SimulateTransaction(serde_json::from_value(value)?)
+            }
             
 
 
@@ -157,6 +167,8 @@ impl MethodRequest {
             MethodRequest::GetTowerStateView(_) =>  Method::GetTowerStateView, 
             MethodRequest::GetOracleUpgradeStateView() =>  Method::GetOracleUpgradeStateView,
             MethodRequest::GetWaypointView() => Method::GetWaypointView,
+            MethodRequest::GetNetworkPeers(_) => Method::GetNetworkPeers,
+            MethodRequest::SimulateTransaction(_) => Method::SimulateTransaction,
           }
     }
 }
@@ -281,6 +293,21 @@ impl<'de> Deserialize<'de> for GetNetworkStatusParams {
     }
 }
 
+//////// 0L ////////
+#[derive(Clone, Debug, Serialize)]
+pub struct GetNetworkPeersParams;
+
+impl<'de> Deserialize<'de> for GetNetworkPeersParams {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_option(NoParamsVisitor("get_network_peers params"))
+            .map(|_| GetNetworkPeersParams)
+    }
+}
+
 /// A de::Visitor implementation for jsonrpc param structs without any parameters
 struct NoParamsVisitor(&'static str);
 impl<'de> de::Visitor<'de> for NoParamsVisitor {
@@ -722,6 +749,25 @@ mod test {
         serde_json::from_value::<JsonRpcRequest>(value).unwrap();
     }
 
+    #[test]
+    fn get_network_peers() {
+        let value = serde_json::json!([10]);
+        serde_json::from_value::<GetNetworkPeersParams>(value).unwrap_err();
+
+        let value = serde_json::json!([]);
+        serde_json::from_value::<GetNetworkPeersParams>(value).unwrap();
+
+        let value = serde_json::Value::Null;
+        serde_json::from_value::<GetNetworkPeersParams>(value).unwrap();
+
+        let value = serde_json::json! {{
+            "jsonrpc": "2.0",
+            "method": Method::GetNetworkPeers,
+            "id": 1,
+        }};
+        serde_json::from_value::<JsonRpcRequest>(value).unwrap();
+    }
+
     #[test]
     fn get_state_proof() {
         // Array with all params
@@ -1520,4 +1520,30 @@ impl TryFrom<Waypoint> for WaypointView {
             waypoint: w,
         })
     }
-}
\ No newline at end of file
+}
+//////// 0L ////////
+/// A peer the node is connected to
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct NetworkPeerView {
+    /// The network of the connection, e.g. `Validator`, `Public` or the name of a private network
+    pub network: String,
+    pub peer_id: AccountAddress,
+    /// Role of the peer from the point of view of the node, e.g. `Upstream`
+    pub role: String,
+    /// `inbound` or `outbound`
+    pub direction: String,
+    /// The messaging protocol version negotiated with the peer
+    pub version: String,
+    pub uptime_secs: u64,
+}
+
+//////// 0L ////////
+/// The outcome of a transaction executed against the latest state without being committed
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SimulationView {
+    pub vm_status: VMStatusView,
+    pub gas_used: u64,
+    /// The events the transaction would emit, their `transaction_version` is the version it
+    /// would have if committed next
+    pub events: Vec<EventView>,
+}
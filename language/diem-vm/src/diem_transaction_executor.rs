@@ -24,8 +24,9 @@ use diem_types::{
     block_metadata::BlockMetadata,
     on_chain_config::DIEM_VERSION_3,
     transaction::{
-        ChangeSet, Module, SignatureCheckedTransaction, Transaction, TransactionArgument,
-        TransactionOutput, TransactionPayload, TransactionStatus, WriteSetPayload,
+        ChangeSet, Module, SignatureCheckedTransaction, SignedTransaction, Transaction,
+        TransactionArgument, TransactionOutput, TransactionPayload, TransactionStatus,
+        WriteSetPayload,
     },
     vm_status::{KeptVMStatus, StatusCode, VMStatus},
     write_set::{WriteSet, WriteSetMut},
@@ -288,10 +289,11 @@ impl DiemVM {
         )
     }
 
+    /// Executes a user transaction, with its signature checked unless it is simulated
     fn execute_user_transaction<S: MoveStorage>(
         &self,
         storage: &S,
-        txn: &SignatureCheckedTransaction,
+        txn: &SignedTransaction, //////// 0L ////////
         log_context: &impl LogContext,
     ) -> (VMStatus, TransactionOutput) {
         macro_rules! unwrap_or_discard {
@@ -873,6 +875,19 @@ impl DiemVM {
         let vm = DiemVM::new(&state_view_cache);
         vm.execute_block_impl(transactions, &mut state_view_cache)
     }
+
+    //////// 0L ////////
+    /// Executes a user transaction against `state_view` without checking its signature, for
+    /// clients to learn its outcome before submitting it. The output is never committed.
+    pub fn simulate_signed_transaction(
+        txn: SignedTransaction,
+        state_view: &dyn StateView,
+    ) -> (VMStatus, TransactionOutput) {
+        let state_view_cache = StateViewCache::new(state_view);
+        let vm = DiemVM::new(&state_view_cache);
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+        vm.execute_user_transaction(&state_view_cache, &txn, &log_context)
+    }
 }
 
 /// Check the signature (if any) of a transaction. If the signature is OK, the result
@@ -11,10 +11,7 @@ use diem_types::{
     account_address::AccountAddress,
     account_config::{self, CurrencyInfoResource, RoleId},
     on_chain_config::{DiemVersion, VMConfig, VMPublishingOption, DIEM_VERSION_2, DIEM_VERSION_3},
-    transaction::{
-        GovernanceRole, SignatureCheckedTransaction, SignedTransaction, TransactionPayload,
-        VMValidatorResult,
-    },
+    transaction::{GovernanceRole, SignedTransaction, TransactionPayload, VMValidatorResult},
     vm_status::{StatusCode, VMStatus},
 };
 use move_core_types::{
@@ -120,10 +117,13 @@ fn get_account_role(sender: AccountAddress, remote_cache: &StateViewCache) -> Go
     GovernanceRole::NonGovernanceRole
 }
 
+//////// 0L ////////
+/// Validates a transaction whose signature the caller checked, or skipped the check of when
+/// simulating it.
 pub(crate) fn validate_signature_checked_transaction<S: MoveStorage>(
     vm: &DiemVMImpl,
     mut session: &mut Session<S>,
-    transaction: &SignatureCheckedTransaction,
+    transaction: &SignedTransaction,
     remote_cache: &S,
     allow_too_new: bool,
     log_context: &impl LogContext,
@@ -101,6 +101,19 @@ impl Mempool {
         }
     }
 
+    //////// 0L ////////
+    /// Number of transactions of the sender, besides the one with `sequence_number`, counting the
+    /// `pending` ones about to be added
+    pub(crate) fn count_other_txns(
+        &self,
+        sender: &AccountAddress,
+        sequence_number: u64,
+        pending: &HashSet<u64>,
+    ) -> usize {
+        self.transactions
+            .count_other_txns(sender, sequence_number, pending)
+    }
+
     /// Used to add a transaction to the Mempool.
     /// Performs basic validation: checks account's sequence number.
     pub(crate) fn add_txn(
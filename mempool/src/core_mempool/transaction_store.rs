@@ -21,7 +21,7 @@ use diem_types::{
     transaction::SignedTransaction,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ops::Bound,
     time::{Duration, SystemTime},
 };
@@ -85,6 +85,24 @@ impl TransactionStore {
         None
     }
 
+    //////// 0L ////////
+    /// Number of transactions of the account, besides the one with `sequence_number` that a new
+    /// one would replace, once those with the `pending` sequence numbers are inserted too
+    pub(crate) fn count_other_txns(
+        &self,
+        address: &AccountAddress,
+        sequence_number: u64,
+        pending: &HashSet<u64>,
+    ) -> usize {
+        let txns = self.transactions.get(address);
+        let is_stored = |seq: &u64| txns.map_or(false, |txns| txns.contains_key(seq));
+        let pending = pending
+            .iter()
+            .filter(|seq| **seq != sequence_number && !is_stored(seq))
+            .count();
+        txns.map_or(0, |txns| txns.len()) - is_stored(&sequence_number) as usize + pending
+    }
+
     /// Insert transaction into TransactionStore. Performs validation checks and updates indexes.
     pub(crate) fn insert(
         &mut self,
@@ -10,19 +10,23 @@ use crate::{
         types::{notify_subscribers, SharedMempool, SharedMempoolNotification},
     },
 };
-use diem_config::config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType};
+use diem_config::{
+    config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType},
+    network_id::NetworkId,
+};
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_types::transaction::SignedTransaction;
 use itertools::Itertools;
 use netcore::transport::ConnectionOrigin;
-use network::transport::ConnectionMetadata;
+use network::{transport::ConnectionMetadata, upstream_selector::UpstreamSelector};
 use serde::{Deserialize, Serialize};
 use short_hex_str::AsShortHexStr;
 use std::{
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet, HashMap},
     ops::Add,
+    sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
 use vm_validator::vm_validator::TransactionValidation;
@@ -60,6 +64,11 @@ pub(crate) struct PeerManager {
     mempool_config: MempoolConfig,
     peer_states: Mutex<PeerSyncStates>,
     prioritized_peers: Mutex<Vec<PeerNetworkId>>,
+    //////// 0L ////////
+    /// Health of the upstream peers, fed with broadcast ACKs and failures
+    upstream: Arc<UpstreamSelector>,
+    /// Demoted networks as of the last ordering of `prioritized_peers`
+    demoted_networks: Mutex<Vec<NetworkId>>,
 }
 
 /// Identifier for a broadcasted batch of txns.
@@ -102,7 +111,11 @@ impl BroadcastInfo {
 }
 
 impl PeerManager {
-    pub fn new(role: RoleType, mempool_config: MempoolConfig) -> Self {
+    pub fn new(
+        role: RoleType,
+        mempool_config: MempoolConfig,
+        upstream: Arc<UpstreamSelector>,
+    ) -> Self {
         // Primary network is always chosen at initialization.
         counters::upstream_network(PRIMARY_NETWORK_PREFERENCE);
         info!(LogSchema::new(LogEntry::UpstreamNetwork).network_level(PRIMARY_NETWORK_PREFERENCE));
@@ -111,6 +124,8 @@ impl PeerManager {
             mempool_config,
             peer_states: Mutex::new(PeerSyncStates::new()),
             prioritized_peers: Mutex::new(Vec::new()),
+            upstream,
+            demoted_networks: Mutex::new(Vec::new()),
         }
     }
 
@@ -119,6 +134,8 @@ impl PeerManager {
         let mut peer_states = self.peer_states.lock();
         let is_new_peer = !peer_states.contains_key(&peer);
         if self.is_upstream_peer(&peer, Some(&metadata)) {
+            //////// 0L ////////
+            self.upstream.add_peer(peer.clone());
             // If we have a new peer, let's insert new data, otherwise, let's just update the current state
             if is_new_peer {
                 counters::active_upstream_peers(&peer.raw_network_id()).inc();
@@ -143,6 +160,7 @@ impl PeerManager {
         error!("shared mempool disable peer {:?}", &peer);
         // Remove all state on the peer, and start over
         self.peer_states.lock().remove(&peer);
+        self.upstream.remove_peer(&peer); //////// 0L ////////
         counters::active_upstream_peers(&peer.raw_network_id()).dec();
 
         // Always update prioritized peers to be in line with peer states
@@ -159,6 +177,19 @@ impl PeerManager {
         }
     }
 
+    //////// 0L ////////
+    /// The broadcasts to the live peers not acknowledged yet, or pending a retry
+    pub fn unacknowledged_broadcasts(&self) -> usize {
+        self.peer_states
+            .lock()
+            .values()
+            .filter(|state| state.is_alive)
+            .map(|state| {
+                state.broadcast_info.sent_batches.len() + state.broadcast_info.retry_batches.len()
+            })
+            .sum()
+    }
+
     pub fn execute_broadcast<V>(
         &self,
         peer: PeerNetworkId,
@@ -174,6 +205,14 @@ impl PeerManager {
         // Start timer for tracking broadcast latency.
         let start_time = Instant::now();
 
+        //////// 0L ////////
+        // Reorder the peers once an upstream network is demoted or recovers
+        if !self.role.is_validator()
+            && *self.demoted_networks.lock() != self.upstream.demoted_networks()
+        {
+            self.update_prioritized_peers();
+        }
+
         let mut peer_states = self.peer_states.lock();
         let state = if let Some(state) = peer_states.get_mut(&peer) {
             state
@@ -208,6 +247,13 @@ impl PeerManager {
             return;
         }
 
+        //////// 0L ////////
+        // Each upstream network broadcasts by its own policy, so a lagging network doesn't hold
+        // back the others
+        let network_id = peer.raw_network_id();
+        let max_broadcasts_per_peer = self.mempool_config.max_broadcasts_per_peer(&network_id);
+        let batch_size = self.mempool_config.batch_size(&network_id);
+
         let batch_id: BatchId;
         let transactions: Vec<SignedTransaction>;
         let mut metric_label = None;
@@ -248,7 +294,7 @@ impl PeerManager {
                 // and wait until an ACK is received or a sent broadcast expires.
                 // This helps rate-limit egress network bandwidth and not overload a remote peer or this
                 // node's Diem network sender.
-                if pending_broadcasts >= self.mempool_config.max_broadcasts_per_peer {
+                if pending_broadcasts >= max_broadcasts_per_peer {
                   error!("will stop broadcasting shared mempool to peer: {:?}", &peer);
                     return;
                 }
@@ -258,6 +304,8 @@ impl PeerManager {
             let (new_batch_id, new_transactions) = match std::cmp::max(expired, retry) {
                 Some(id) => {
                     metric_label = if Some(id) == expired {
+                        // An unacknowledged broadcast counts against the peer's health
+                        self.upstream.record_failure(&peer); //////// 0L ////////
                         Some(counters::EXPIRED_BROADCAST_LABEL)
                     } else {
                         Some(counters::RETRY_BROADCAST_LABEL)
@@ -268,10 +316,8 @@ impl PeerManager {
                 }
                 None => {
                     // Fresh broadcast
-                    let (txns, new_timeline_id) = mempool.read_timeline(
-                        state.timeline_id,
-                        self.mempool_config.shared_mempool_batch_size,
-                    );
+                    let (txns, new_timeline_id) =
+                        mempool.read_timeline(state.timeline_id, batch_size);
                     (BatchId(state.timeline_id, new_timeline_id), txns)
                 }
             };
@@ -304,6 +350,7 @@ impl PeerManager {
                     .peer(&peer)
                     .error(&e.into())
             );
+            self.upstream.record_failure(&peer); //////// 0L ////////
             return;
         }
         // Update peer sync state with info from above broadcast.
@@ -325,7 +372,6 @@ impl PeerManager {
                 .backpressure(scheduled_backoff)
         );
         let peer_id = peer.peer_id().short_str();
-        let network_id = peer.raw_network_id();
         counters::SHARED_MEMPOOL_TRANSACTION_BROADCAST_SIZE
             .with_label_values(&[network_id.as_str(), peer_id.as_str()])
             .observe(num_txns as f64);
@@ -369,13 +415,22 @@ impl PeerManager {
         // Order peers by network and by type
         // Origin doesn't matter at this point, only inserted ones into peer_states are upstream
         // Validators will always have the full set
+        //////// 0L ////////
+        // Peers of demoted networks go last, so that broadcasts fail over to healthy upstreams
+        let demoted_networks = self.upstream.demoted_networks();
+        let is_demoted = |peer: &PeerNetworkId| demoted_networks.contains(&peer.raw_network_id());
         let mut prioritized_peers = self.prioritized_peers.lock();
         let peers: Vec<_> = peers
             .iter()
-            .sorted_by(|peer_a, peer_b| compare_prioritized_peers(peer_a, peer_b))
+            .sorted_by(|peer_a, peer_b| {
+                is_demoted(&peer_a.0)
+                    .cmp(&is_demoted(&peer_b.0))
+                    .then_with(|| compare_prioritized_peers(peer_a, peer_b))
+            })
             .map(|(peer, _)| peer.clone())
             .collect();
         let _ = std::mem::replace(&mut *prioritized_peers, peers);
+        *self.demoted_networks.lock() = demoted_networks;
     }
 
     /// Node receives ack from peer.
@@ -407,6 +462,7 @@ impl PeerManager {
             let rtt = timestamp
                 .duration_since(sent_timestamp)
                 .expect("failed to calculate mempool broadcast RTT");
+            self.upstream.record_success(&peer, rtt); //////// 0L ////////
 
             let network_id = peer.raw_network_id();
             let peer_id = peer.peer_id().short_str();
@@ -20,6 +20,9 @@ use futures::channel::{
     mpsc::{self, Receiver, UnboundedSender},
     oneshot,
 };
+use network::{
+    drain::Drain, peer_reputation::PeerReputationStore, upstream_selector::UpstreamSelector,
+};
 use std::{collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
 use tokio::runtime::{Builder, Handle, Runtime};
@@ -44,10 +47,22 @@ pub(crate) fn start_shared_mempool<V>(
     db: Arc<dyn DbReader>,
     validator: Arc<RwLock<V>>,
     subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
+    upstream: Arc<UpstreamSelector>,
+    peer_reputation: Arc<PeerReputationStore>,
+    drain: Arc<Drain>, //////// 0L ////////
 ) where
     V: TransactionValidation + 'static,
 {
-    let peer_manager = Arc::new(PeerManager::new(config.base.role, config.mempool.clone()));
+    let peer_manager = Arc::new(PeerManager::new(
+        config.base.role,
+        config.mempool.clone(),
+        upstream,
+    ));
+    //////// 0L ////////
+    let broadcasts = peer_manager.clone();
+    drain.register("mempool broadcasts", move || {
+        broadcasts.unacknowledged_broadcasts()
+    });
 
     let mut all_network_events = vec![];
     let mut network_senders = HashMap::new();
@@ -64,6 +79,7 @@ pub(crate) fn start_shared_mempool<V>(
         validator,
         peer_manager,
         subscribers,
+        peer_reputation,
     };
 
     executor.spawn(coordinator(
@@ -97,6 +113,13 @@ pub fn bootstrap(
     consensus_requests: Receiver<ConsensusRequest>,
     state_sync_requests: Receiver<CommitNotification>,
     mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
+    //////// 0L ////////
+    // Shared with the admin service, which reports the current upstream selection
+    upstream: Arc<UpstreamSelector>,
+    // Reports the peers broadcasting transactions that are all rejected
+    peer_reputation: Arc<PeerReputationStore>,
+    // Waits for the broadcasts to be acknowledged when the node shuts down
+    drain: Arc<Drain>,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("shared-mem")
@@ -117,6 +140,9 @@ pub fn bootstrap(
         db,
         vm_validator,
         vec![],
+        upstream,
+        peer_reputation,
+        drain,
     );
     runtime
 }
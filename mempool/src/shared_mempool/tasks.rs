@@ -16,7 +16,7 @@ use crate::{
     SubmissionStatus,
 };
 use anyhow::Result;
-use diem_config::config::PeerNetworkId;
+use diem_config::{config::PeerNetworkId, network_id::NetworkId};
 use diem_infallible::{Mutex, RwLock};
 use diem_logger::prelude::*;
 use diem_metrics::HistogramTimer;
@@ -27,11 +27,12 @@ use diem_types::{
     vm_status::DiscardedVMStatus,
 };
 use futures::{channel::oneshot, stream::FuturesUnordered};
+use network::peer_reputation::Misbehavior;
 use rayon::prelude::*;
 use short_hex_str::AsShortHexStr;
 use std::{
     cmp,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -57,7 +58,7 @@ pub(crate) fn execute_broadcast<V>(
     let schedule_backoff = peer_manager.is_backoff_mode(&peer);
 
     let interval_ms = if schedule_backoff {
-        smp.config.shared_mempool_backoff_interval_ms
+        smp.config.backoff_interval_ms(&peer.raw_network_id()) //////// 0L ////////
     } else {
         smp.config.shared_mempool_tick_interval_ms
     };
@@ -91,7 +92,7 @@ pub(crate) async fn process_client_transaction_submission<V>(
     let _timer =
         counters::process_txn_submit_latency_timer(counters::CLIENT_LABEL, counters::CLIENT_LABEL);
     let statuses =
-        process_incoming_transactions(&smp, vec![transaction], TimelineState::NotReady).await;
+        process_incoming_transactions(&smp, vec![transaction], TimelineState::NotReady, None).await;
     log_txn_process_results(&statuses, None);
 
     if let Some(status) = statuses.get(0) {
@@ -123,8 +124,23 @@ pub(crate) async fn process_transaction_broadcast<V>(
         peer.raw_network_id().as_str(),
         peer.peer_id().short_str().as_str(),
     );
-    let results = process_incoming_transactions(&smp, transactions.clone(), timeline_state).await;
+    let results = process_incoming_transactions(
+        &smp,
+        transactions.clone(),
+        timeline_state,
+        Some(&peer.raw_network_id()),
+    )
+    .await;
     log_txn_process_results(&results, Some(peer.clone()));
+    //////// 0L ////////
+    // A broadcast the VM rejects entirely is spam
+    if !results.is_empty()
+        && results
+            .iter()
+            .all(|(_, (status, _))| status.code == MempoolStatusCode::VmError)
+    {
+        smp.peer_reputation.report(&peer, Misbehavior::MempoolSpam);
+    }
 
     let ack_response = gen_ack_response(request_id, results, &peer);
     let network_sender = smp
@@ -200,17 +216,76 @@ fn is_txn_retryable(result: SubmissionStatus) -> bool {
     result.0.code == MempoolStatusCode::MempoolIsFull
 }
 
+//////// 0L ////////
+/// Splits the transactions passing the admission rules of the node from the statuses of the
+/// others, see `MempoolAdmissionConfig`. `network_id` is the network of the peer broadcasting
+/// them, none for a client submission.
+fn admit_transactions<V>(
+    smp: &SharedMempool<V>,
+    transactions: Vec<SignedTransaction>,
+    network_id: Option<&NetworkId>,
+) -> (Vec<SignedTransaction>, Vec<SubmissionStatusBundle>)
+where
+    V: TransactionValidation,
+{
+    let admission = &smp.config.admission;
+    let min_gas_unit_price = admission.min_gas_unit_price(network_id);
+    let capacity_per_user = admission.capacity_per_user(network_id);
+    let mempool = smp.mempool.lock();
+    // Sequence numbers admitted from this batch, by sender, to count against its capacity
+    let mut pending: HashMap<_, HashSet<_>> = HashMap::new();
+    let mut rejected = vec![];
+    let admitted = transactions
+        .into_iter()
+        .filter_map(|t| {
+            let status = if !admission.admits_sender(&t.sender()) {
+                MempoolStatus::new(MempoolStatusCode::Rejected)
+                    .with_message(format!("sender {} is not admitted", t.sender()))
+            } else if t.gas_unit_price() < min_gas_unit_price {
+                MempoolStatus::new(MempoolStatusCode::Rejected).with_message(format!(
+                    "gas unit price {} is below the minimum {}",
+                    t.gas_unit_price(),
+                    min_gas_unit_price,
+                ))
+            } else {
+                let batch = pending.entry(t.sender()).or_default();
+                match capacity_per_user {
+                    Some(capacity)
+                        if mempool.count_other_txns(&t.sender(), t.sequence_number(), batch)
+                            >= capacity =>
+                    {
+                        MempoolStatus::new(MempoolStatusCode::TooManyTransactions)
+                            .with_message(format!("capacity per user: {}", capacity))
+                    }
+                    _ => {
+                        batch.insert(t.sequence_number());
+                        return Some(t);
+                    }
+                }
+            };
+            rejected.push((t, (status, None)));
+            None
+        })
+        .collect();
+    (admitted, rejected)
+}
+
 /// Submits a list of SignedTransaction to the local mempool
 /// and returns a vector containing AdmissionControlStatus.
 pub(crate) async fn process_incoming_transactions<V>(
     smp: &SharedMempool<V>,
     transactions: Vec<SignedTransaction>,
     timeline_state: TimelineState,
+    network_id: Option<&NetworkId>,
 ) -> Vec<SubmissionStatusBundle>
 where
     V: TransactionValidation,
 {
-    let mut statuses = vec![];
+    //////// 0L ////////
+    let (transactions, mut statuses) = admit_transactions(smp, transactions, network_id);
+    if transactions.is_empty() {
+        return statuses;
+    }
 
     let start_storage_read = Instant::now();
     // Track latency: fetching seq number
@@ -26,6 +26,7 @@ use futures::{
     future::Future,
     task::{Context, Poll},
 };
+use network::peer_reputation::PeerReputationStore;
 use std::{collections::HashMap, fmt, pin::Pin, sync::Arc, task::Waker, time::Instant};
 use storage_interface::DbReader;
 use subscription_service::ReconfigSubscription;
@@ -45,6 +46,7 @@ where
     pub validator: Arc<RwLock<V>>,
     pub peer_manager: Arc<PeerManager>,
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
+    pub peer_reputation: Arc<PeerReputationStore>, //////// 0L ////////
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
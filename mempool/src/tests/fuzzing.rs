@@ -8,6 +8,7 @@ use crate::{
 use diem_config::config::NodeConfig;
 use diem_infallible::{Mutex, RwLock};
 use diem_types::transaction::SignedTransaction;
+use network::{peer_reputation::PeerReputationStore, upstream_selector::UpstreamSelector};
 use proptest::{
     arbitrary::any,
     prelude::*,
@@ -41,11 +42,16 @@ pub fn test_mempool_process_incoming_transactions_impl(
         network_senders: HashMap::new(),
         db: Arc::new(mock_db),
         validator: vm_validator,
-        peer_manager: Arc::new(PeerManager::new(config.base.role, config.mempool)),
+        peer_manager: Arc::new(PeerManager::new(
+            config.base.role,
+            config.mempool,
+            Arc::new(UpstreamSelector::new(&config.upstream)),
+        )),
         subscribers: vec![],
+        peer_reputation: Arc::new(PeerReputationStore::in_memory()),
     };
 
-    let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state);
+    let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state, None);
 }
 
 proptest! {
@@ -20,8 +20,11 @@ use diem_types::{
 };
 use futures::channel::{mpsc, oneshot};
 use network::{
+    drain::Drain,
     peer_manager::{conn_notifs_channel, ConnectionRequestSender, PeerManagerRequestSender},
+    peer_reputation::PeerReputationStore,
     protocols::network::{NewNetworkEvents, NewNetworkSender},
+    upstream_selector::UpstreamSelector,
 };
 use std::sync::Arc;
 use storage_interface::mock::MockDbReader;
@@ -90,6 +93,9 @@ impl MockSharedMempool {
             Arc::new(MockDbReader),
             Arc::new(RwLock::new(MockVMValidator)),
             vec![],
+            Arc::new(UpstreamSelector::new(&config.upstream)),
+            Arc::new(PeerReputationStore::in_memory()),
+            Arc::new(Drain::new()),
         );
 
         Self {
@@ -15,7 +15,9 @@ use diem_config::{
     network_id::{NetworkContext, NetworkId, NodeNetworkId},
 };
 use diem_infallible::{Mutex, MutexGuard, RwLock};
-use diem_types::{account_address::AccountAddress, transaction::GovernanceRole, PeerId};
+use diem_types::{
+    account_address::AccountAddress, chain_id::ChainId, transaction::GovernanceRole, PeerId,
+};
 use enum_dispatch::enum_dispatch;
 use futures::{
     channel::mpsc::{self, unbounded, UnboundedReceiver},
@@ -23,12 +25,15 @@ use futures::{
 };
 use netcore::transport::ConnectionOrigin;
 use network::{
+    drain::Drain,
     peer_manager::{
         conn_notifs_channel, ConnectionNotification, ConnectionRequestSender,
         PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
     },
+    peer_reputation::PeerReputationStore,
     protocols::network::{NetworkEvents, NewNetworkEvents, NewNetworkSender},
     transport::ConnectionMetadata,
+    upstream_selector::UpstreamSelector,
     DisconnectReason, ProtocolId,
 };
 use rand::rngs::StdRng;
@@ -114,7 +119,7 @@ pub trait NodeInfoTrait {
         let role = self.role();
         let network = self.network(is_primary);
         let peer_id = self.peer_id(is_primary);
-        NetworkContext::new(role, network, peer_id)
+        NetworkContext::new(role, network, peer_id, ChainId::test())
     }
 }
 
@@ -611,6 +616,9 @@ fn start_node_mempool(
         Arc::new(MockDbReader),
         Arc::new(RwLock::new(MockVMValidator)),
         vec![sender],
+        Arc::new(UpstreamSelector::new(&config.upstream)),
+        Arc::new(PeerReputationStore::in_memory()),
+        Arc::new(Drain::new()),
     );
 
     (mempool, runtime, subscriber)
@@ -2,15 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    core_mempool::{CoreMempool, TimelineState},
     mocks::MockSharedMempool,
+    shared_mempool::{peer_manager::PeerManager, tasks, types::SharedMempool},
     tests::common::{batch_add_signed_txn, TestTransaction},
     CommitNotification, CommittedTransaction, ConsensusRequest,
 };
+use diem_config::{
+    config::{MempoolNetworkLimits, NodeConfig},
+    network_id::NetworkId,
+};
+use diem_infallible::{Mutex, RwLock};
+use diem_types::mempool_status::MempoolStatusCode;
 use futures::{
     channel::{mpsc, oneshot},
     executor::block_on,
     sink::SinkExt,
 };
+use network::{peer_reputation::PeerReputationStore, upstream_selector::UpstreamSelector};
+use std::{collections::HashMap, sync::Arc};
+use storage_interface::mock::MockDbReader;
+use vm_validator::mocks::mock_vm_validator::MockVMValidator;
 
 #[test]
 fn test_consensus_events_rejected_txns() {
@@ -96,3 +108,93 @@ fn test_state_sync_events_committed_txns() {
     assert_eq!(timeline.len(), 1);
     assert_eq!(timeline.get(0).unwrap(), &kept_txn);
 }
+
+//////// 0L ////////
+#[test]
+fn test_admission() {
+    let mut config = NodeConfig::default();
+    config.mempool.capacity_per_user = 10;
+    let admission = &mut config.mempool.admission;
+    admission.min_gas_unit_price = 2;
+    admission
+        .sender_denylist
+        .insert(TestTransaction::get_address(1));
+    admission.network_limits = vec![MempoolNetworkLimits {
+        network_id: NetworkId::Public,
+        min_gas_unit_price: Some(1),
+        capacity_per_user: Some(1),
+    }];
+    let smp = SharedMempool {
+        mempool: Arc::new(Mutex::new(CoreMempool::new(&config))),
+        config: config.mempool.clone(),
+        network_senders: HashMap::new(),
+        db: Arc::new(MockDbReader),
+        validator: Arc::new(RwLock::new(MockVMValidator)),
+        peer_manager: Arc::new(PeerManager::new(
+            config.base.role,
+            config.mempool.clone(),
+            Arc::new(UpstreamSelector::new(&config.upstream)),
+        )),
+        subscribers: vec![],
+        peer_reputation: Arc::new(PeerReputationStore::in_memory()),
+    };
+    let submit = |txn: TestTransaction, network_id: Option<&NetworkId>| {
+        let statuses = block_on(tasks::process_incoming_transactions(
+            &smp,
+            vec![txn.make_signed_transaction()],
+            TimelineState::NotReady,
+            network_id,
+        ));
+        (statuses[0].1).0.code
+    };
+
+    // A client pays at least the global minimum
+    assert_eq!(
+        submit(TestTransaction::new(0, 0, 1), None),
+        MempoolStatusCode::Rejected
+    );
+    assert_eq!(
+        submit(TestTransaction::new(0, 0, 2), None),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(
+        submit(TestTransaction::new(1, 0, 5), None),
+        MempoolStatusCode::Rejected
+    );
+
+    // The public network has limits of its own
+    let public = Some(&NetworkId::Public);
+    assert_eq!(
+        submit(TestTransaction::new(2, 0, 1), public),
+        MempoolStatusCode::Accepted
+    );
+    assert_eq!(
+        submit(TestTransaction::new(0, 1, 2), public),
+        MempoolStatusCode::TooManyTransactions
+    );
+    // Re-broadcasting a transaction in the mempool is not one more
+    assert_eq!(
+        submit(TestTransaction::new(0, 0, 2), public),
+        MempoolStatusCode::Accepted
+    );
+
+    // Nor can a broadcast carry more transactions of a sender than its capacity
+    let statuses = block_on(tasks::process_incoming_transactions(
+        &smp,
+        (0..3)
+            .map(|seq| TestTransaction::new(3, seq, 1).make_signed_transaction())
+            .collect(),
+        TimelineState::NotReady,
+        public,
+    ));
+    let status = |seq| {
+        let (_, (status, _)) = statuses
+            .iter()
+            .find(|(txn, _)| txn.sequence_number() == seq)
+            .unwrap();
+        status.code
+    };
+    assert_eq!(status(0), MempoolStatusCode::Accepted);
+    assert_eq!(status(1), MempoolStatusCode::TooManyTransactions);
+    assert_eq!(status(2), MempoolStatusCode::TooManyTransactions);
+}
@@ -9,15 +9,19 @@
 //! authentication -- a network end-point running with remote authentication enabled will
 //! connect to or accept connections from an end-point running in authenticated mode as
 //! long as the latter is in its trusted peers set.
+use crate::{dns_discovery::DnsDiscovery, external_address::ExternalAddress}; //////// 0L ////////
 use channel::{self, message_queues::QueueStyle};
 use diem_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RateLimitConfig, RoleType,
-        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_REQS,
+        AdvertisedAddress, AutoAddressConfig, DiscoveryMethod, DnsDiscoveryConfig,
+        HandshakeEnforcement, MultiplexConfig, NetworkConfig, NetworkTransport,
+        OutboundProxyConfig, Peer, PeerAccess, PeerEvictionPolicy, PeerRole, PeerSet,
+        RateLimitConfig, RelayDiscoveryConfig, RendezvousConfig, RoleType, CONNECTION_BACKOFF_BASE,
+        CONNECTIVITY_CHECK_INTERVAL_MS, HANDSHAKE_VERSION, MAX_CONCURRENT_NETWORK_REQS,
         MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS,
         MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
     },
-    network_id::NetworkContext,
+    network_id::{NetworkContext, NodeNetworkId},
 };
 use diem_crypto::x25519::PublicKey;
 use diem_infallible::RwLock;
@@ -25,17 +29,21 @@ use diem_logger::prelude::*;
 use diem_metrics::IntCounterVec;
 use diem_network_address_encryption::Encryptor;
 use diem_time_service::TimeService;
-use diem_types::{chain_id::ChainId, network_address::NetworkAddress};
+use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
 use network::{
     connectivity_manager::{builder::ConnectivityManagerBuilder, ConnectivityRequest},
+    drain::Drain,
     logging::NetworkSchema,
     peer_manager::{
         builder::{AuthenticationMode, PeerManagerBuilder},
-        ConnectionRequestSender,
+        conn_notifs_channel, ConnectionRequestSender,
     },
+    peer_metadata::PeerMetadataStorage,
+    peer_reputation::PeerReputationStore,
     protocols::{
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{NewNetworkEvents, NewNetworkSender},
+        relay::{self, builder::RelayBuilder, SharedAddresses}, //////// 0L ////////
     },
     ProtocolId,
 };
@@ -65,9 +73,27 @@ pub struct NetworkBuilder {
     network_context: Arc<NetworkContext>,
 
     validator_set_listener_builder: Option<ValidatorSetChangeListenerBuilder>,
+    dns_discovery: Option<DnsDiscovery>, //////// 0L ////////
+    relay_builder: Option<RelayBuilder>, //////// 0L ////////
+    //////// 0L ////////
+    // The addresses registered with discovery, and how to find them when automatic
+    advertised_address: SharedAddresses,
+    external_address: Option<(AutoAddressConfig, NetworkTransport, PublicKey)>,
     connectivity_manager_builder: Option<ConnectivityManagerBuilder>,
     health_checker_builder: Option<HealthCheckerBuilder>,
     peer_manager_builder: PeerManagerBuilder,
+    //////// 0L ////////
+    peer_metadata_listener: Option<(
+        Arc<PeerMetadataStorage>,
+        NodeNetworkId,
+        conn_notifs_channel::Receiver,
+    )>,
+    peer_reputation_enforcer: Option<(
+        Arc<PeerReputationStore>,
+        NodeNetworkId,
+        conn_notifs_channel::Receiver,
+        ConnectionRequestSender,
+    )>,
 
     // (StateSync) ReconfigSubscriptions required by internal Network components.
     reconfig_subscriptions: Vec<ReconfigSubscription>,
@@ -90,6 +116,14 @@ impl NetworkBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        eviction_policy: Option<PeerEvictionPolicy>, //////// 0L ////////
+        peer_access: PeerAccess,                     //////// 0L ////////
+        handshake_enforcement: HandshakeEnforcement, //////// 0L ////////
+        outbound_proxy: Option<OutboundProxyConfig>, //////// 0L ////////
+        tor_proxy: Option<String>,                   //////// 0L ////////
+        transport: NetworkTransport,                 //////// 0L ////////
+        multiplex: MultiplexConfig,                  //////// 0L ////////
+        dual_stack_address: Option<NetworkAddress>,  //////// 0L ////////
     ) -> Self {
         // A network cannot exist without a PeerManager
         // TODO:  construct this in create and pass it to new() as a parameter. The complication is manual construction of NetworkBuilder in various tests.
@@ -107,6 +141,14 @@ impl NetworkBuilder {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            eviction_policy,
+            peer_access,
+            handshake_enforcement,
+            outbound_proxy,
+            tor_proxy,
+            transport,
+            multiplex,
+            dual_stack_address,
         );
 
         NetworkBuilder {
@@ -115,9 +157,16 @@ impl NetworkBuilder {
             time_service,
             network_context,
             validator_set_listener_builder: None,
+            dns_discovery: None, //////// 0L ////////
+            relay_builder: None, //////// 0L ////////
+            //////// 0L ////////
+            advertised_address: SharedAddresses::default(),
+            external_address: None,
             connectivity_manager_builder: None,
             health_checker_builder: None,
             peer_manager_builder,
+            peer_metadata_listener: None,
+            peer_reputation_enforcer: None,
             reconfig_subscriptions: vec![],
         }
     }
@@ -147,6 +196,14 @@ impl NetworkBuilder {
             MAX_INBOUND_CONNECTIONS,
             None,
             None,
+            None,
+            PeerAccess::default(),
+            HandshakeEnforcement::Strict,
+            None,
+            None,
+            NetworkTransport::Tcp,
+            MultiplexConfig::default(),
+            None,
         );
 
         builder.add_connectivity_manager(
@@ -158,6 +215,7 @@ impl NetworkBuilder {
             CONNECTIVITY_CHECK_INTERVAL_MS,
             NETWORK_CHANNEL_SIZE,
             mutual_authentication,
+            HashMap::new(),
         );
 
         builder
@@ -180,11 +238,10 @@ impl NetworkBuilder {
             AuthenticationMode::MaybeMutual(identity_key)
         };
 
-        let network_context = Arc::new(NetworkContext::new(
-            role,
-            config.network_id.clone(),
-            peer_id,
-        ));
+        let network_context = Arc::new(
+            NetworkContext::new(role, config.network_id.clone(), peer_id, chain_id)
+                .with_instance(config.instance.clone()), //////// 0L ////////
+        );
 
         let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
 
@@ -199,9 +256,18 @@ impl NetworkBuilder {
             config.enable_proxy_protocol,
             config.network_channel_size,
             config.max_concurrent_network_reqs,
-            config.max_inbound_connections,
-            config.inbound_rate_limit_config,
-            config.outbound_rate_limit_config,
+            //////// 0L ////////
+            config.inbound_connection_limit(),
+            config.inbound_rate_limit(),
+            config.outbound_rate_limit(),
+            config.eviction_policy.clone(),
+            config.peer_access(),
+            config.handshake_enforcement,
+            config.outbound_proxy.clone(),
+            config.tor_proxy.clone(),
+            config.transport,
+            config.multiplex,
+            config.dual_stack_address.clone(),
         );
 
         network_builder.add_connection_monitoring(
@@ -232,12 +298,13 @@ impl NetworkBuilder {
             network_builder.add_connectivity_manager(
                 seeds,
                 trusted_peers,
-                config.max_outbound_connections,
+                config.outbound_connection_limit(), //////// 0L ////////
                 config.connection_backoff_base,
                 config.max_connection_delay_ms,
                 config.connectivity_check_interval_ms,
                 config.network_channel_size,
                 config.mutual_authentication,
+                config.peer_roles.clone(), //////// 0L ////////
             );
         }
 
@@ -245,9 +312,27 @@ impl NetworkBuilder {
             DiscoveryMethod::Onchain => {
                 network_builder.add_validator_set_listener(pubkey, config.encryptor());
             }
+            //////// 0L ////////
+            DiscoveryMethod::Dns(dns) => {
+                network_builder.add_dns_discovery(dns.clone());
+            }
+            // Added below, along with the rendezvous serving registrations
+            DiscoveryMethod::Relay(_) => {}
             DiscoveryMethod::None => {}
         }
 
+        //////// 0L ////////
+        if let Some(advertised) = &config.advertised_address {
+            network_builder.add_advertised_address(advertised.clone(), config.transport, pubkey);
+        }
+        let relay_discovery = match &config.discovery_method {
+            DiscoveryMethod::Relay(relay) => Some(relay.clone()),
+            _ => None,
+        };
+        if config.rendezvous.is_some() || relay_discovery.is_some() {
+            network_builder.add_relay(config.rendezvous, relay_discovery);
+        }
+
         network_builder
     }
 
@@ -266,6 +351,7 @@ impl NetworkBuilder {
         assert_eq!(self.state, State::BUILT);
         self.state = State::STARTED;
 
+        let listen_addresses = self.listen_addresses(); //////// 0L ////////
         let executor = self.executor.as_mut().expect("Executor must exist");
         self.peer_manager_builder.start(executor);
         debug!(
@@ -296,6 +382,58 @@ impl NetworkBuilder {
                 "{} Started validator set listener", self.network_context
             );
         }
+
+        //////// 0L ////////
+        if let Some(dns_discovery) = self.dns_discovery.take() {
+            dns_discovery.start(executor);
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started DNS discovery", self.network_context
+            );
+        }
+
+        if let Some((config, transport, pubkey)) = self.external_address.take() {
+            ExternalAddress::new(
+                self.network_context.clone(),
+                config,
+                transport,
+                listen_addresses,
+                pubkey,
+                self.time_service.clone(),
+                self.advertised_address.clone(),
+            )
+            .start(executor);
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started external address lookup", self.network_context
+            );
+        }
+
+        if let Some(relay_builder) = self.relay_builder.as_mut() {
+            relay_builder.start(executor);
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started relay", self.network_context
+            );
+        }
+
+        if let Some((storage, network_id, notifs_rx)) = self.peer_metadata_listener.take() {
+            executor.spawn(async move { storage.listen(network_id, notifs_rx).await });
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started peer metadata listener", self.network_context
+            );
+        }
+
+        if let Some((store, network_id, notifs_rx, conn_reqs)) =
+            self.peer_reputation_enforcer.take()
+        {
+            executor.spawn(async move { store.enforce(network_id, notifs_rx, conn_reqs).await });
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started peer reputation enforcer", self.network_context
+            );
+        }
         self
     }
 
@@ -313,10 +451,29 @@ impl NetworkBuilder {
             .map(|conn_mgr_builder| conn_mgr_builder.conn_mgr_reqs_tx())
     }
 
+    //////// 0L ////////
+    /// Dials and disconnects peers of the network. Only available before the network is built.
+    pub fn connection_request_sender(&self) -> ConnectionRequestSender {
+        ConnectionRequestSender::new(self.peer_manager_builder.connection_reqs_tx())
+    }
+
     pub fn listen_address(&self) -> NetworkAddress {
         self.peer_manager_builder.listen_address()
     }
 
+    //////// 0L ////////
+    pub fn dual_stack_address(&self) -> Option<NetworkAddress> {
+        self.peer_manager_builder.dual_stack_address()
+    }
+
+    //////// 0L ////////
+    /// The listen address, followed by the dual stack address, if any
+    pub fn listen_addresses(&self) -> Vec<NetworkAddress> {
+        std::iter::once(self.listen_address())
+            .chain(self.dual_stack_address())
+            .collect()
+    }
+
     /// Add a [`ConnectivityManager`] to the network.
     ///
     /// [`ConnectivityManager`] is responsible for ensuring that we are connected
@@ -336,6 +493,7 @@ impl NetworkBuilder {
         connectivity_check_interval_ms: u64,
         channel_size: usize,
         mutual_authentication: bool,
+        role_overrides: HashMap<PeerId, PeerRole>, //////// 0L ////////
     ) -> &mut Self {
         let pm_conn_mgr_notifs_rx = self.peer_manager_builder.add_connection_event_listener();
         let outbound_connection_limit = if !self.network_context.network_id().is_validator_network()
@@ -358,10 +516,45 @@ impl NetworkBuilder {
             pm_conn_mgr_notifs_rx,
             outbound_connection_limit,
             mutual_authentication,
+            role_overrides,
         ));
         self
     }
 
+    //////// 0L ////////
+    /// Feeds the node wide [`PeerMetadataStorage`] with the connections of this network, as
+    /// `network_id`.
+    pub fn add_peer_metadata_storage(
+        &mut self,
+        storage: Arc<PeerMetadataStorage>,
+        network_id: NodeNetworkId,
+    ) -> &mut Self {
+        let notifs_rx = self.peer_manager_builder.add_connection_event_listener();
+        self.peer_metadata_listener = Some((storage, network_id, notifs_rx));
+        self
+    }
+
+    //////// 0L ////////
+    /// Disconnects the peers the node wide [`PeerReputationStore`] bans, as `network_id`.
+    pub fn add_peer_reputation_store(
+        &mut self,
+        store: Arc<PeerReputationStore>,
+        network_id: NodeNetworkId,
+    ) -> &mut Self {
+        let notifs_rx = self.peer_manager_builder.add_connection_event_listener();
+        let conn_reqs =
+            ConnectionRequestSender::new(self.peer_manager_builder.connection_reqs_tx());
+        self.peer_reputation_enforcer = Some((store, network_id, notifs_rx, conn_reqs));
+        self
+    }
+
+    //////// 0L ////////
+    /// Stops accepting inbound connections once the node wide [`Drain`] started.
+    pub fn add_drain(&mut self, drain: Arc<Drain>) -> &mut Self {
+        self.peer_manager_builder.add_drain(drain);
+        self
+    }
+
     fn add_validator_set_listener(&mut self, pubkey: PublicKey, encryptor: Encryptor) -> &mut Self {
         let conn_mgr_reqs_tx = self
             .conn_mgr_reqs_tx()
@@ -381,6 +574,74 @@ impl NetworkBuilder {
         self
     }
 
+    //////// 0L ////////
+    fn add_dns_discovery(&mut self, config: DnsDiscoveryConfig) -> &mut Self {
+        let conn_mgr_reqs_tx = self
+            .conn_mgr_reqs_tx()
+            .expect("ConnectivityManager must be installed for DNS discovery");
+        self.dns_discovery = Some(DnsDiscovery::new(
+            self.network_context.clone(),
+            config,
+            self.time_service.clone(),
+            conn_mgr_reqs_tx,
+        ));
+        self
+    }
+
+    //////// 0L ////////
+    /// Add a Relay to the network, registering with the rendezvous peers of `discovery` and
+    /// serving registrations as a rendezvous peer with `rendezvous`.
+    fn add_relay(
+        &mut self,
+        rendezvous: Option<RendezvousConfig>,
+        discovery: Option<RelayDiscoveryConfig>,
+    ) -> &mut Self {
+        let discovery = discovery.map(|config| {
+            let conn_mgr_reqs_tx = self
+                .conn_mgr_reqs_tx()
+                .expect("ConnectivityManager must be installed for relay discovery");
+            (config, conn_mgr_reqs_tx)
+        });
+        let (relay_network_tx, relay_network_rx) =
+            self.add_protocol_handler(relay::network_endpoint_config());
+
+        self.relay_builder = Some(RelayBuilder::new(
+            self.network_context(),
+            self.time_service.clone(),
+            rendezvous,
+            discovery,
+            relay_network_tx,
+            relay_network_rx,
+            self.advertised_address.clone(),
+        ));
+        debug!(
+            NetworkSchema::new(&self.network_context),
+            "{} Created relay", self.network_context
+        );
+        self
+    }
+
+    //////// 0L ////////
+    /// Set the address registered with discovery: a fixed one right away, an automatic one once
+    /// the network is started, on the port it listens to.
+    fn add_advertised_address(
+        &mut self,
+        advertised: AdvertisedAddress,
+        transport: NetworkTransport,
+        pubkey: PublicKey,
+    ) -> &mut Self {
+        match advertised {
+            AdvertisedAddress::Address(address) => {
+                *self.advertised_address.write() =
+                    vec![address.append_prod_protos(pubkey, HANDSHAKE_VERSION)];
+            }
+            AdvertisedAddress::Auto(config) => {
+                self.external_address = Some((config, transport, pubkey));
+            }
+        }
+        self
+    }
+
     /// Add a HealthChecker to the network.
     fn add_connection_monitoring(
         &mut self,
@@ -440,7 +701,8 @@ impl NetworkBuilder {
 }
 
 /// Retrieve and merge seeds so that they have all keys associated
-fn merge_seeds(config: &NetworkConfig) -> PeerSet {
+//////// 0L //////// public for config reloads
+pub fn merge_seeds(config: &NetworkConfig) -> PeerSet {
     config.verify_seeds().expect("Seeds must be well formed");
     let mut seeds = config.seeds.clone();
 
@@ -463,6 +725,14 @@ fn merge_seeds(config: &NetworkConfig) -> PeerSet {
                 .or_insert(peer);
         });
 
+    //////// 0L ////////
+    // Rendezvous peers are dialed as seeds
+    if let DiscoveryMethod::Relay(relay) = &config.discovery_method {
+        for (peer_id, peer) in &relay.rendezvous {
+            seeds.entry(*peer_id).or_insert_with(|| peer.clone());
+        }
+    }
+
     // Pull public keys out of addresses
     seeds.values_mut().for_each(
         |Peer {
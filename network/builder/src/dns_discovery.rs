@@ -0,0 +1,302 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Discovery of seed peers from the DNS records of a domain, see `DnsDiscoveryConfig`. Each lookup
+//! replaces the peers of the previous one in the connectivity manager, under their own
+//! `DiscoverySource`, so they add to the seeds of the config rather than replace them. A failed
+//! lookup keeps the peers found before.
+
+use diem_config::{
+    config::{DnsDiscoveryConfig, Peer, PeerRole, PeerSet, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
+use diem_crypto::{x25519, ValidCryptoMaterialStringExt};
+use diem_logger::prelude::*;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::{network_address::NetworkAddress, PeerId};
+use futures::sink::SinkExt;
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    logging::NetworkSchema,
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::runtime::Handle;
+use trust_dns_resolver::{
+    error::{ResolveError, ResolveErrorKind},
+    proto::rr::rdata::TXT,
+    TokioAsyncResolver,
+};
+
+/// The SRV records of the seeds are under this prefix of the domain
+const SRV_PREFIX: &str = "_diem._tcp";
+/// Prefix of the TXT record of an SRV target holding its noise key
+const NOISE_KEY_PREFIX: &str = "ln-noise-ik=";
+
+pub struct DnsDiscovery {
+    network_context: Arc<NetworkContext>,
+    config: DnsDiscoveryConfig,
+    time_service: TimeService,
+    conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+}
+
+impl DnsDiscovery {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        config: DnsDiscoveryConfig,
+        time_service: TimeService,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    ) -> Self {
+        Self {
+            network_context,
+            config,
+            time_service,
+            conn_mgr_reqs_tx,
+        }
+    }
+
+    pub fn start(self, executor: &Handle) {
+        executor.spawn(self.run());
+    }
+
+    async fn run(mut self) {
+        let resolver = match TokioAsyncResolver::tokio_from_system_conf() {
+            Ok(resolver) => resolver,
+            Err(e) => {
+                error!(
+                    NetworkSchema::new(&self.network_context),
+                    "{} DNS discovery disabled, unable to read the resolver config: {}",
+                    self.network_context,
+                    e
+                );
+                return;
+            }
+        };
+
+        let mut known: Option<PeerSet> = None;
+        loop {
+            let interval = match self.lookup(&resolver).await {
+                Ok((seeds, ttl)) => {
+                    if known.as_ref() != Some(&seeds) {
+                        info!(
+                            NetworkSchema::new(&self.network_context),
+                            "{} DNS discovery found {} seeds for {}",
+                            self.network_context,
+                            seeds.len(),
+                            self.config.domain
+                        );
+                        let request = ConnectivityRequest::UpdateDiscoveredPeers(
+                            DiscoverySource::Dns,
+                            seeds.clone(),
+                        );
+                        if let Err(e) = self.conn_mgr_reqs_tx.send(request).await {
+                            warn!(
+                                NetworkSchema::new(&self.network_context),
+                                "{} DNS discovery stopped, the connectivity manager is gone: {}",
+                                self.network_context,
+                                e
+                            );
+                            return;
+                        }
+                        known = Some(seeds);
+                    }
+                    self.config.refresh_interval(ttl)
+                }
+                Err(e) => {
+                    warn!(
+                        NetworkSchema::new(&self.network_context),
+                        "{} DNS lookup of {} failed, keeping the seeds found before: {}",
+                        self.network_context,
+                        self.config.domain,
+                        e
+                    );
+                    Duration::from_secs(self.config.min_refresh_interval_secs)
+                }
+            };
+            self.time_service.sleep(interval).await;
+        }
+    }
+
+    /// The seeds of the domain, and the shortest TTL of the records they were found in
+    async fn lookup(
+        &self,
+        resolver: &TokioAsyncResolver,
+    ) -> Result<(PeerSet, Option<Duration>), ResolveError> {
+        let now = Instant::now();
+        let mut ttl = None;
+        let mut addresses: HashMap<PeerId, Vec<NetworkAddress>> = HashMap::new();
+        let domain = self.config.domain.as_str();
+
+        if let Some(txt) = found(resolver.txt_lookup(domain).await)? {
+            shortest_ttl(&mut ttl, now, txt.valid_until());
+            for record in txt.iter().map(txt_string) {
+                match parse_txt_seed(&record) {
+                    Ok((peer_id, address)) => addresses.entry(peer_id).or_default().push(address),
+                    Err(e) => self.ignore_record("TXT", &record, &e),
+                }
+            }
+        }
+
+        let srv_name = format!("{}.{}", SRV_PREFIX, domain);
+        if let Some(srv) = found(resolver.srv_lookup(srv_name.as_str()).await)? {
+            shortest_ttl(&mut ttl, now, srv.as_lookup().valid_until());
+            for record in srv.iter() {
+                let target = record.target().to_utf8();
+                let key = match found(resolver.txt_lookup(target.as_str()).await)? {
+                    Some(txt) => {
+                        shortest_ttl(&mut ttl, now, txt.valid_until());
+                        txt.iter()
+                            .find_map(|record| parse_noise_key(&txt_string(record)))
+                    }
+                    None => None,
+                };
+                let seed = key
+                    .ok_or_else(|| format!("{} has no {} TXT record", target, NOISE_KEY_PREFIX))
+                    .and_then(|key| srv_seed_address(&target, record.port(), key));
+                match seed {
+                    Ok(address) => addresses
+                        .entry(peer_id_of(&address))
+                        .or_default()
+                        .push(address),
+                    Err(e) => self.ignore_record("SRV", &record.to_string(), &e),
+                }
+            }
+        }
+
+        let seeds = addresses
+            .into_iter()
+            .map(|(peer_id, addresses)| {
+                (
+                    peer_id,
+                    Peer::from_addrs(PeerRole::ValidatorFullNode, addresses),
+                )
+            })
+            .collect();
+        Ok((seeds, ttl))
+    }
+
+    fn ignore_record(&self, kind: &str, record: &str, reason: &str) {
+        warn!(
+            NetworkSchema::new(&self.network_context),
+            "{} Ignored {} record '{}' of {}: {}",
+            self.network_context,
+            kind,
+            record,
+            self.config.domain,
+            reason
+        );
+    }
+}
+
+/// A lookup without records is no error, the domain lists no seed of that kind
+fn found<T>(lookup: Result<T, ResolveError>) -> Result<Option<T>, ResolveError> {
+    match lookup {
+        Ok(records) => Ok(Some(records)),
+        Err(e) => match e.kind() {
+            ResolveErrorKind::NoRecordsFound { .. } => Ok(None),
+            _ => Err(e),
+        },
+    }
+}
+
+fn shortest_ttl(ttl: &mut Option<Duration>, now: Instant, valid_until: Instant) {
+    let remaining = valid_until.saturating_duration_since(now);
+    *ttl = Some(ttl.map_or(remaining, |ttl| ttl.min(remaining)));
+}
+
+/// The character strings of a TXT record, joined as one
+fn txt_string(record: &TXT) -> String {
+    record
+        .txt_data()
+        .iter()
+        .map(|data| String::from_utf8_lossy(data))
+        .collect()
+}
+
+/// The peer id of a seed without one: derived from its noise key
+fn peer_id_of(address: &NetworkAddress) -> PeerId {
+    let key = address
+        .find_noise_proto()
+        .expect("A diemnet address has a noise key");
+    PeerId::from_identity_public_key(key)
+}
+
+/// The seed of a TXT record: a network address with a noise key, optionally prefixed with the
+/// peer id and `@`
+pub fn parse_txt_seed(record: &str) -> Result<(PeerId, NetworkAddress), String> {
+    let (peer_id, address) = match record.split_once('@') {
+        Some((peer_id, address)) => (Some(peer_id.trim()), address),
+        None => (None, record),
+    };
+    let address: NetworkAddress = address
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid network address: {}", e))?;
+    if !address.is_diemnet_addr() {
+        return Err(format!("{} is not a diemnet address", address));
+    }
+    let peer_id = match peer_id {
+        Some(peer_id) => peer_id
+            .parse()
+            .map_err(|e| format!("invalid peer id {}: {}", peer_id, e))?,
+        None => peer_id_of(&address),
+    };
+    Ok((peer_id, address))
+}
+
+/// The noise key of an SRV target, from a TXT record `ln-noise-ik=<hex>`
+pub fn parse_noise_key(record: &str) -> Option<x25519::PublicKey> {
+    record
+        .strip_prefix(NOISE_KEY_PREFIX)
+        .and_then(|key| x25519::PublicKey::from_encoded_string(key.trim()).ok())
+}
+
+/// The address of an SRV target, reached at `port` with the noise key `key`
+pub fn srv_seed_address(
+    target: &str,
+    port: u16,
+    key: x25519::PublicKey,
+) -> Result<NetworkAddress, String> {
+    format!("/dns4/{}/tcp/{}", target.trim_end_matches('.'), port)
+        .parse::<NetworkAddress>()
+        .map(|address| address.append_prod_protos(key, HANDSHAKE_VERSION))
+        .map_err(|e| format!("invalid target {}: {}", target, e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_crypto::{test_utils::TEST_SEED, Uniform};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_parse_records() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let key = x25519::PrivateKey::generate(&mut rng).public_key();
+        let address = srv_seed_address("seed1.example.com.", 6179, key).unwrap();
+        assert_eq!(
+            address.to_string(),
+            format!(
+                "/dns4/seed1.example.com/tcp/6179/ln-noise-ik/{}/ln-handshake/0",
+                key
+            )
+        );
+
+        let (peer_id, parsed) = parse_txt_seed(&address.to_string()).unwrap();
+        assert_eq!(parsed, address);
+        assert_eq!(peer_id, PeerId::from_identity_public_key(key));
+        let other = PeerId::random();
+        let (peer_id, _) = parse_txt_seed(&format!("{}@{}", other, address)).unwrap();
+        assert_eq!(peer_id, other);
+        assert!(parse_txt_seed("/dns4/seed1.example.com/tcp/6179").is_err());
+        assert!(parse_txt_seed("v=spf1 -all").is_err());
+
+        let record = format!("{}{}", NOISE_KEY_PREFIX, key);
+        assert_eq!(parse_noise_key(&record), Some(key));
+        assert_eq!(parse_noise_key("v=spf1 -all"), None);
+    }
+}
@@ -144,6 +144,7 @@ pub fn setup_network() -> DummyNetwork {
         role,
         network_id.clone(),
         listener_peer_id,
+        chain_id,
     ));
     let mut network_builder = NetworkBuilder::new_for_test(
         chain_id,
@@ -169,7 +170,12 @@ pub fn setup_network() -> DummyNetwork {
     let authentication_mode = AuthenticationMode::Mutual(dialer_identity_private_key);
 
     // Set up the dialer network
-    let network_context = Arc::new(NetworkContext::new(role, network_id, dialer_peer_id));
+    let network_context = Arc::new(NetworkContext::new(
+        role,
+        network_id,
+        dialer_peer_id,
+        chain_id,
+    ));
 
     let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
 
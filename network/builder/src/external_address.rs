@@ -0,0 +1,524 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The external address of a node behind NAT, see `AdvertisedAddress::Auto`. The external ip is
+//! looked up every refresh interval, from STUN servers or from the UPnP gateway of the local
+//! network, which also forwards the listen port to the node. The address advertised is the
+//! external ip on the listen port, with the noise key of the node. A dual-stack network looks up
+//! its external ip of each version, advertising both. A failed lookup keeps the address found
+//! before.
+
+use diem_config::{
+    config::{AutoAddressConfig, ExternalAddressMethod, NetworkTransport, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
+use diem_crypto::x25519;
+use diem_logger::prelude::*;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::network_address::{NetworkAddress, Protocol};
+use network::{logging::NetworkSchema, protocols::relay::SharedAddresses};
+use rand::Rng;
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{lookup_host, TcpStream, UdpSocket},
+    runtime::Handle,
+};
+
+/// Time a STUN server, or the UPnP gateway, has to answer a lookup
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const STUN_MAGIC_COOKIE: u32 = 0x2112_a442;
+const STUN_BINDING_REQUEST: u16 = 0x0001;
+const STUN_BINDING_RESPONSE: u16 = 0x0101;
+const STUN_MAPPED_ADDRESS: u16 = 0x0001;
+const STUN_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+
+const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+const WAN_IP_CONNECTION: &str = "urn:schemas-upnp-org:service:WANIPConnection:1";
+
+pub struct ExternalAddress {
+    network_context: Arc<NetworkContext>,
+    config: AutoAddressConfig,
+    transport: NetworkTransport,
+    listen_addresses: Vec<NetworkAddress>,
+    key: x25519::PublicKey,
+    time_service: TimeService,
+    advertised: SharedAddresses,
+}
+
+impl ExternalAddress {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        config: AutoAddressConfig,
+        transport: NetworkTransport,
+        listen_addresses: Vec<NetworkAddress>,
+        key: x25519::PublicKey,
+        time_service: TimeService,
+        advertised: SharedAddresses,
+    ) -> Self {
+        Self {
+            network_context,
+            config,
+            transport,
+            listen_addresses,
+            key,
+            time_service,
+            advertised,
+        }
+    }
+
+    pub fn start(self, executor: &Handle) {
+        executor.spawn(self.run());
+    }
+
+    async fn run(self) {
+        // The port and ip version of each listener
+        let mut listeners = Vec::new();
+        for listen_address in &self.listen_addresses {
+            match listen_address.find_port() {
+                Some(port) => listeners.push((port, is_ipv6(listen_address))),
+                None => error!(
+                    NetworkSchema::new(&self.network_context),
+                    "{} No address advertised for {}, the listen address has no port",
+                    self.network_context,
+                    listen_address
+                ),
+            }
+        }
+        if listeners.is_empty() {
+            return;
+        }
+
+        let interval = Duration::from_secs(self.config.refresh_interval_secs);
+        let mut found: Vec<Option<NetworkAddress>> = vec![None; listeners.len()];
+        loop {
+            for (&(port, ipv6), found) in listeners.iter().zip(found.iter_mut()) {
+                match self.lookup(port, ipv6, interval).await {
+                    Ok(ip) => *found = Some(advertised_address(ip, port, self.key)),
+                    Err(e) => warn!(
+                        NetworkSchema::new(&self.network_context),
+                        "{} External ip{} address lookup failed, keeping the address found \
+                         before: {}",
+                        self.network_context,
+                        if ipv6 { 6 } else { 4 },
+                        e
+                    ),
+                }
+            }
+            self.advertise(found.iter().flatten().cloned().collect());
+            self.time_service.sleep(interval).await;
+        }
+    }
+
+    fn advertise(&self, addresses: Vec<NetworkAddress>) {
+        let mut advertised = self.advertised.write();
+        for address in addresses.iter().filter(|a| !advertised.contains(a)) {
+            info!(
+                NetworkSchema::new(&self.network_context),
+                "{} Advertising the external address {}", self.network_context, address
+            );
+        }
+        *advertised = addresses;
+    }
+
+    /// The external ip of the node, of the version given by `ipv6`
+    async fn lookup(&self, port: u16, ipv6: bool, interval: Duration) -> io::Result<IpAddr> {
+        match &self.config.method {
+            ExternalAddressMethod::Stun(servers) => {
+                let mut last_error = invalid_data("no stun server is configured");
+                for server in servers {
+                    match self
+                        .time_service
+                        .timeout(LOOKUP_TIMEOUT, stun_lookup(server, ipv6))
+                        .await
+                    {
+                        Ok(Ok(ip)) => return Ok(ip),
+                        Ok(Err(e)) => last_error = e,
+                        Err(_) => last_error = timed_out(server),
+                    }
+                }
+                Err(last_error)
+            }
+            ExternalAddressMethod::Upnp if ipv6 => {
+                Err(invalid_data("upnp only forwards ip4 ports"))
+            }
+            ExternalAddressMethod::Upnp => {
+                // The mapping outlives the refresh interval, so it never lapses before its renewal
+                let lease_secs = 2 * interval.as_secs();
+                self.time_service
+                    .timeout(
+                        LOOKUP_TIMEOUT,
+                        upnp_map_port(port, self.transport, lease_secs),
+                    )
+                    .await
+                    .map_err(|_| timed_out("the upnp gateway"))?
+            }
+        }
+    }
+}
+
+fn is_ipv6(address: &NetworkAddress) -> bool {
+    matches!(address.as_slice().first(), Some(Protocol::Ip6(_)))
+}
+
+/// The address advertised for the external `ip`, on the listen `port`
+pub fn advertised_address(ip: IpAddr, port: u16, key: x25519::PublicKey) -> NetworkAddress {
+    NetworkAddress::from(SocketAddr::new(ip, port)).append_prod_protos(key, HANDSHAKE_VERSION)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn timed_out(peer: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        format!("{} did not answer in time", peer),
+    )
+}
+
+/// The ip a STUN server sees the node at, from a binding request over ip6 if `ipv6`, or else ip4
+async fn stun_lookup(server: &str, ipv6: bool) -> io::Result<IpAddr> {
+    let server_address = lookup_host(server)
+        .await?
+        .find(|address| address.is_ipv6() == ipv6)
+        .ok_or_else(|| {
+            let version = if ipv6 { 6 } else { 4 };
+            invalid_data(format!("{} has no ip{} address", server, version))
+        })?;
+    let local: SocketAddr = if server_address.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local).await?;
+    let transaction_id: [u8; 12] = rand::thread_rng().gen();
+    socket
+        .send_to(&stun_request(&transaction_id), server_address)
+        .await?;
+
+    // Datagrams other than the response are ignored, until the lookup times out
+    let mut response = [0u8; 512];
+    loop {
+        let (len, _) = socket.recv_from(&mut response).await?;
+        if let Some(mapped) = parse_stun_response(&response[..len], &transaction_id) {
+            return Ok(mapped.ip());
+        }
+    }
+}
+
+/// A binding request without attributes
+fn stun_request(transaction_id: &[u8; 12]) -> [u8; 20] {
+    let mut request = [0u8; 20];
+    request[..2].copy_from_slice(&STUN_BINDING_REQUEST.to_be_bytes());
+    request[4..8].copy_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+    request[8..].copy_from_slice(transaction_id);
+    request
+}
+
+/// The address of the binding response to the request `transaction_id`, from its
+/// XOR-MAPPED-ADDRESS, or else its MAPPED-ADDRESS
+fn parse_stun_response(response: &[u8], transaction_id: &[u8; 12]) -> Option<SocketAddr> {
+    if response.len() < 20
+        || response[..2] != STUN_BINDING_RESPONSE.to_be_bytes()
+        || response[4..8] != STUN_MAGIC_COOKIE.to_be_bytes()
+        || response[8..20] != transaction_id[..]
+    {
+        return None;
+    }
+    let length = u16::from_be_bytes([response[2], response[3]]) as usize;
+    let mut attributes = response.get(20..20 + length)?;
+    let mut mapped = None;
+    while attributes.len() >= 4 {
+        let kind = u16::from_be_bytes([attributes[0], attributes[1]]);
+        let len = u16::from_be_bytes([attributes[2], attributes[3]]) as usize;
+        let value = attributes.get(4..4 + len)?;
+        match kind {
+            STUN_XOR_MAPPED_ADDRESS => return parse_stun_address(value, Some(transaction_id)),
+            STUN_MAPPED_ADDRESS => mapped = parse_stun_address(value, None),
+            _ => {}
+        }
+        // Attributes are padded to 4 bytes
+        let padded = 4 + (len + 3) / 4 * 4;
+        attributes = attributes.get(padded..).unwrap_or(&[]);
+    }
+    mapped
+}
+
+/// A MAPPED-ADDRESS: a reserved byte, the family, the port and the ip. In a XOR-MAPPED-ADDRESS,
+/// the port and ip are masked with the magic cookie and the transaction id.
+fn parse_stun_address(value: &[u8], xor: Option<&[u8; 12]>) -> Option<SocketAddr> {
+    let mut mask = [0u8; 16];
+    if let Some(transaction_id) = xor {
+        mask[..4].copy_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+        mask[4..].copy_from_slice(transaction_id);
+    }
+    let port = u16::from_be_bytes([value.get(2)? ^ mask[0], value.get(3)? ^ mask[1]]);
+    let ip: IpAddr = match value.get(1)? {
+        0x01 => {
+            let mut octets = [0u8; 4];
+            for (i, octet) in octets.iter_mut().enumerate() {
+                *octet = value.get(4 + i)? ^ mask[i];
+            }
+            Ipv4Addr::from(octets).into()
+        }
+        0x02 => {
+            let mut octets = [0u8; 16];
+            for (i, octet) in octets.iter_mut().enumerate() {
+                *octet = value.get(4 + i)? ^ mask[i];
+            }
+            Ipv6Addr::from(octets).into()
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Asks the UPnP gateway to forward `port` to the node for `lease_secs`, returning its external ip
+async fn upnp_map_port(
+    port: u16,
+    transport: NetworkTransport,
+    lease_secs: u64,
+) -> io::Result<IpAddr> {
+    let location = ssdp_search().await?;
+    let (gateway, path) = split_url(&location)
+        .ok_or_else(|| invalid_data(format!("unexpected gateway location {}", location)))?;
+    let gateway = if gateway.contains(':') {
+        gateway.to_string()
+    } else {
+        format!("{}:80", gateway)
+    };
+    let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, gateway);
+    let (_, description) = http_request(&gateway, &request).await?;
+    let control = control_url(&description)
+        .ok_or_else(|| invalid_data("the gateway has no WANIPConnection service"))?;
+    let control = split_url(control).map_or(control, |(_, path)| path);
+
+    // The ip of the connection to the gateway is the one of the node on the local network
+    let (local_ip, reply) = soap_request(&gateway, control, "GetExternalIPAddress", "").await?;
+    let external_ip = tag_value(&reply, "NewExternalIPAddress")
+        .and_then(|ip| ip.trim().parse().ok())
+        .ok_or_else(|| invalid_data("the gateway has no external ip"))?;
+    let protocol = match transport {
+        NetworkTransport::Tcp => "TCP",
+        NetworkTransport::Quic => "UDP",
+    };
+    let mapping = format!(
+        "<NewRemoteHost></NewRemoteHost><NewExternalPort>{port}</NewExternalPort>\
+         <NewProtocol>{protocol}</NewProtocol><NewInternalPort>{port}</NewInternalPort>\
+         <NewInternalClient>{client}</NewInternalClient><NewEnabled>1</NewEnabled>\
+         <NewPortMappingDescription>diem</NewPortMappingDescription>\
+         <NewLeaseDuration>{lease}</NewLeaseDuration>",
+        port = port,
+        protocol = protocol,
+        client = local_ip,
+        lease = lease_secs,
+    );
+    soap_request(&gateway, control, "AddPortMapping", &mapping).await?;
+    Ok(external_ip)
+}
+
+/// The location of the description of the first gateway answering the search
+async fn ssdp_search() -> io::Result<String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\n\r\n",
+        SSDP_ADDRESS, WAN_IP_CONNECTION
+    );
+    socket.send_to(request.as_bytes(), SSDP_ADDRESS).await?;
+
+    let mut response = [0u8; 2048];
+    loop {
+        let (len, _) = socket.recv_from(&mut response).await?;
+        if let Some(location) = ssdp_location(&String::from_utf8_lossy(&response[..len])) {
+            return Ok(location.to_string());
+        }
+    }
+}
+
+async fn soap_request(
+    gateway: &str,
+    path: &str,
+    action: &str,
+    arguments: &str,
+) -> io::Result<(IpAddr, String)> {
+    let body = format!(
+        "<?xml version=\"1.0\"?><s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+         s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\"><s:Body>\
+         <u:{action} xmlns:u=\"{service}\">{arguments}</u:{action}></s:Body></s:Envelope>",
+        action = action,
+        service = WAN_IP_CONNECTION,
+        arguments = arguments,
+    );
+    let request = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: text/xml; charset=\"utf-8\"\r\n\
+         SOAPAction: \"{}#{}\"\r\nContent-Length: {}\r\n\r\n{}",
+        path,
+        gateway,
+        WAN_IP_CONNECTION,
+        action,
+        body.len(),
+        body
+    );
+    http_request(gateway, &request).await
+}
+
+/// Sends an HTTP/1.0 request to `host`, returning the ip of the connection on the side of the
+/// node, and the body of a successful response
+async fn http_request(host: &str, request: &str) -> io::Result<(IpAddr, String)> {
+    let mut stream = TcpStream::connect(host).await?;
+    let local_ip = stream.local_addr()?.ip();
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| invalid_data(format!("{} sent an incomplete response", host)))?;
+    if head.split_whitespace().nth(1) != Some("200") {
+        return Err(invalid_data(format!(
+            "{} answered {}",
+            host,
+            head.lines().next().unwrap_or_default()
+        )));
+    }
+    Ok((local_ip, body.to_string()))
+}
+
+/// The LOCATION header of an SSDP response
+fn ssdp_location(response: &str) -> Option<&str> {
+    response.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("location") {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// The host and path of an `http://` url
+fn split_url(url: &str) -> Option<(&str, &str)> {
+    let url = url.strip_prefix("http://")?;
+    Some(match url.find('/') {
+        Some(index) => url.split_at(index),
+        None => (url, "/"),
+    })
+}
+
+/// The text of the first `tag` element of an XML document
+fn tag_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(&xml[start..end])
+}
+
+/// The control url of the WANIPConnection service of a gateway description
+fn control_url(description: &str) -> Option<&str> {
+    let service = format!("<serviceType>{}</serviceType>", WAN_IP_CONNECTION);
+    let start = description.find(&service)?;
+    tag_value(&description[start..], "controlURL").map(str::trim)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_crypto::{test_utils::TEST_SEED, Uniform};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_parse_stun_response() {
+        let transaction_id = [7u8; 12];
+        let request = stun_request(&transaction_id);
+        assert_eq!(request[..2], [0x00, 0x01]);
+        assert_eq!(request[8..], transaction_id[..]);
+
+        // XOR-MAPPED-ADDRESS of 1.2.3.4:6180, after an unknown attribute
+        let mut response = vec![0x01, 0x01, 0x00, 0x14];
+        response.extend_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+        response.extend_from_slice(&transaction_id);
+        response.extend_from_slice(&[0x80, 0x22, 0x00, 0x02, b'o', b'k', 0x00, 0x00]);
+        let cookie = STUN_MAGIC_COOKIE.to_be_bytes();
+        let port = 6180u16.to_be_bytes();
+        response.extend_from_slice(&[0x00, 0x20, 0x00, 0x08, 0x00, 0x01]);
+        response.extend_from_slice(&[port[0] ^ cookie[0], port[1] ^ cookie[1]]);
+        response.extend(
+            [1u8, 2, 3, 4]
+                .iter()
+                .zip(cookie.iter())
+                .map(|(octet, mask)| octet ^ mask),
+        );
+        assert_eq!(
+            parse_stun_response(&response, &transaction_id),
+            Some("1.2.3.4:6180".parse().unwrap())
+        );
+        // A response to another request is ignored
+        assert_eq!(parse_stun_response(&response, &[8u8; 12]), None);
+        assert_eq!(parse_stun_response(&response[..20], &transaction_id), None);
+    }
+
+    #[test]
+    fn test_parse_upnp() {
+        let ssdp = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=120\r\n\
+                    Location: http://192.168.1.1:5000/rootDesc.xml\r\nST: upnp\r\n\r\n";
+        let location = ssdp_location(ssdp).unwrap();
+        assert_eq!(location, "http://192.168.1.1:5000/rootDesc.xml");
+        assert_eq!(
+            split_url(location),
+            Some(("192.168.1.1:5000", "/rootDesc.xml"))
+        );
+        assert_eq!(split_url("http://192.168.1.1"), Some(("192.168.1.1", "/")));
+        assert_eq!(split_url("/ctl/IPConn"), None);
+
+        let description = "<service>\
+            <serviceType>urn:schemas-upnp-org:service:Layer3Forwarding:1</serviceType>\
+            <controlURL>/ctl/L3F</controlURL></service><service>\
+            <serviceType>urn:schemas-upnp-org:service:WANIPConnection:1</serviceType>\
+            <controlURL> /ctl/IPConn </controlURL></service>";
+        assert_eq!(control_url(description), Some("/ctl/IPConn"));
+        assert_eq!(control_url("<service></service>"), None);
+
+        let reply = "<u:GetExternalIPAddressResponse>\
+            <NewExternalIPAddress>203.0.113.7</NewExternalIPAddress>\
+            </u:GetExternalIPAddressResponse>";
+        assert_eq!(
+            tag_value(reply, "NewExternalIPAddress"),
+            Some("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn test_advertised_address() {
+        let mut rng = StdRng::from_seed(TEST_SEED);
+        let key = x25519::PrivateKey::generate(&mut rng).public_key();
+        let address = advertised_address("203.0.113.7".parse().unwrap(), 6179, key);
+        assert_eq!(
+            address.to_string(),
+            format!(
+                "/ip4/203.0.113.7/tcp/6179/ln-noise-ik/{}/ln-handshake/0",
+                key
+            )
+        );
+        assert!(address.is_diemnet_addr());
+        assert!(!is_ipv6(&address));
+
+        let address = advertised_address("2001:db8::7".parse().unwrap(), 6179, key);
+        assert_eq!(
+            address.to_string(),
+            format!(
+                "/ip6/2001:db8::7/tcp/6179/ln-noise-ik/{}/ln-handshake/0",
+                key
+            )
+        );
+        assert!(is_ipv6(&address));
+    }
+}
@@ -21,6 +21,7 @@ pub mod boxed;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 pub mod memory;
 pub mod proxy_protocol;
+pub mod quic; //////// 0L ////////
 pub mod tcp;
 
 /// Origin of how a Connection was established.
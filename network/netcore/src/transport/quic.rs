@@ -0,0 +1,385 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! QUIC Transport
+//!
+//! Each connection carries a single bidirectional stream, secured by the noise handshake above it
+//! like a TCP connection is. The TLS of QUIC only encrypts: its self-signed certificate, derived
+//! from the noise identity key, is not verified since the noise handshake authenticates peers.
+//!
+//! Addresses are the same as for TCP, `/ip4/<addr>/tcp/<port>` and the like, the port being the
+//! UDP port of the peer.
+use crate::transport::{tcp::resolve_with_filter, Transport};
+use diem_crypto::HashValue;
+use diem_types::{
+    network_address::{parse_dns_tcp, parse_ip_tcp, NetworkAddress},
+    PeerId,
+};
+use futures::{
+    future::Future,
+    io::{AsyncRead, AsyncWrite},
+    stream::{Stream, StreamExt},
+};
+use quinn::{
+    Certificate, CertificateChain, ClientConfig, ClientConfigBuilder, Connecting, Connection,
+    Endpoint, Incoming, NewConnection, PrivateKey, RecvStream, SendStream, ServerConfigBuilder,
+};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+/// The ALPN protocol of diem connections
+pub const ALPN_PROTOCOL: &[u8] = b"diem";
+/// The server name of the certificates, which is not verified
+const SERVER_NAME: &str = "diem";
+/// Separates the seed of the certificate key from other uses of the noise identity key
+const TLS_KEY_DOMAIN: &[u8] = b"DIEM_QUIC_TLS_KEY";
+/// The PKCS#8 v1 encoding of an ed25519 private key, before its 32 byte seed
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+
+/// Transport to build QUIC connections
+#[derive(Clone)]
+pub struct QuicTransport {
+    /// Seed of the ed25519 key of the TLS certificate
+    tls_seed: HashValue,
+    /// IPv6 listeners refuse IPv4 datagrams, see `TcpTransport::ipv6_only`
+    ipv6_only: bool,
+}
+
+impl QuicTransport {
+    /// A transport whose TLS certificate is derived from the bytes of the noise `identity_key`
+    pub fn new(identity_key: &[u8]) -> Self {
+        Self {
+            tls_seed: HashValue::sha3_256_of(&[TLS_KEY_DOMAIN, identity_key].concat()),
+            ipv6_only: false,
+        }
+    }
+
+    /// Sets whether IPv6 listeners refuse IPv4 datagrams, for a dual-stack node
+    pub fn with_ipv6_only(mut self, ipv6_only: bool) -> Self {
+        self.ipv6_only = ipv6_only;
+        self
+    }
+
+    /// The self-signed certificate of the listener, and its private key
+    fn certificate(&self) -> io::Result<(CertificateChain, PrivateKey)> {
+        let pkcs8 = [&ED25519_PKCS8_PREFIX[..], &self.tls_seed.to_vec()[..]].concat();
+        let mut params = rcgen::CertificateParams::new(vec![SERVER_NAME.to_string()]);
+        params.alg = &rcgen::PKCS_ED25519;
+        params.key_pair = Some(rcgen::KeyPair::from_der(&pkcs8).map_err(other_error)?);
+        let certificate = rcgen::Certificate::from_params(params).map_err(other_error)?;
+        let chain = Certificate::from_der(&certificate.serialize_der().map_err(other_error)?)
+            .map_err(other_error)?;
+        let key =
+            PrivateKey::from_der(&certificate.serialize_private_key_der()).map_err(other_error)?;
+        Ok((CertificateChain::from_certs(vec![chain]), key))
+    }
+}
+
+impl fmt::Debug for QuicTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("QuicTransport")
+    }
+}
+
+/// A UDP socket at the IPv6 `address`, refusing IPv4 datagrams
+fn bind_ipv6_only(address: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::ipv6(), Type::dgram(), Some(Protocol::udp()))?;
+    socket.set_only_v6(true)?;
+    socket.bind(&address.into())?;
+    Ok(socket.into_udp_socket())
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicSocket;
+    type Error = ::std::io::Error;
+    type Listener = QuicListenerStream;
+    type Inbound = Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send + 'static>>;
+    type Outbound = Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send + 'static>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_tcp(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let (chain, key) = self.certificate()?;
+        let mut server_config = ServerConfigBuilder::default();
+        server_config.protocols(&[ALPN_PROTOCOL]);
+        server_config.certificate(chain, key).map_err(other_error)?;
+        let mut builder = Endpoint::builder();
+        builder.listen(server_config.build());
+        let address = SocketAddr::new(ipaddr, port);
+        let (endpoint, incoming) = if ipaddr.is_ipv6() && self.ipv6_only {
+            builder.with_socket(bind_ipv6_only(address)?)
+        } else {
+            builder.bind(&address)
+        }
+        .map_err(other_error)?;
+        let listen_addr = NetworkAddress::from(endpoint.local_addr()?);
+
+        Ok((
+            QuicListenerStream {
+                _endpoint: endpoint,
+                incoming,
+            },
+            listen_addr,
+        ))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let protos = addr.as_slice();
+
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        parse_ip_tcp(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_tcp(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(Box::pin(resolve_and_connect(addr)))
+    }
+}
+
+/// Connects to the first address `addr` resolves to that accepts the connection
+async fn resolve_and_connect(addr: NetworkAddress) -> io::Result<QuicSocket> {
+    let protos = addr.as_slice();
+
+    let socketaddrs: Vec<_> = if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_tcp(protos) {
+        vec![SocketAddr::new(ipaddr, port)]
+    } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_tcp(protos) {
+        resolve_with_filter(ip_filter, dns_name.as_ref(), port)
+            .await?
+            .collect()
+    } else {
+        return Err(invalid_addr_error(&addr));
+    };
+
+    let mut last_err = None;
+    for socketaddr in socketaddrs {
+        match connect(socketaddr).await {
+            Ok(socket) => return Ok(socket),
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("could not resolve '{}' to any address", addr),
+        )
+    }))
+}
+
+/// Connects from an ephemeral UDP port, which closes with the connection
+async fn connect(socketaddr: SocketAddr) -> io::Result<QuicSocket> {
+    let bind_addr = if socketaddr.is_ipv4() {
+        SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))
+    } else {
+        SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0))
+    };
+    let mut builder = Endpoint::builder();
+    builder.default_client_config(client_config());
+    let (endpoint, _incoming) = builder.bind(&bind_addr).map_err(other_error)?;
+
+    let NewConnection { connection, .. } = endpoint
+        .connect(&socketaddr, SERVER_NAME)
+        .map_err(other_error)?
+        .await
+        .map_err(other_error)?;
+    let (send, recv) = connection.open_bi().await.map_err(other_error)?;
+    Ok(QuicSocket {
+        send,
+        recv,
+        _connection: connection,
+    })
+}
+
+/// Accepts the stream the dialer opens on `connecting`
+async fn accept(connecting: Connecting) -> io::Result<QuicSocket> {
+    let NewConnection {
+        connection,
+        mut bi_streams,
+        ..
+    } = connecting.await.map_err(other_error)?;
+    let (send, recv) = bi_streams
+        .next()
+        .await
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the connection closed before opening a stream",
+            )
+        })?
+        .map_err(other_error)?;
+    Ok(QuicSocket {
+        send,
+        recv,
+        _connection: connection,
+    })
+}
+
+/// Skips the verification of the certificate of listeners, as the noise handshake authenticates
+/// them.
+struct NoiseVerified;
+
+impl rustls::ServerCertVerifier for NoiseVerified {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn client_config() -> ClientConfig {
+    let mut builder = ClientConfigBuilder::default();
+    builder.protocols(&[ALPN_PROTOCOL]);
+    let mut config = builder.build();
+    Arc::get_mut(&mut config.crypto)
+        .expect("The TLS config of a new client config is not shared")
+        .dangerous()
+        .set_certificate_verifier(Arc::new(NoiseVerified));
+    config
+}
+
+fn other_error<E: fmt::Display>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error.to_string())
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct QuicListenerStream {
+    _endpoint: Endpoint,
+    incoming: Incoming,
+}
+
+impl Stream for QuicListenerStream {
+    type Item = io::Result<(
+        Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send + 'static>>,
+        NetworkAddress,
+    )>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.incoming).poll_next(context) {
+            Poll::Ready(Some(connecting)) => {
+                let dialer_addr = NetworkAddress::from(connecting.remote_address());
+                let inbound: <QuicTransport as Transport>::Inbound = Box::pin(accept(connecting));
+                Poll::Ready(Some(Ok((inbound, dialer_addr))))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The bidirectional stream of a QUIC connection, which closes as the socket drops
+#[derive(Debug)]
+pub struct QuicSocket {
+    send: SendStream,
+    recv: RecvStream,
+    /// Kept for the connection to live as long as the socket
+    _connection: Connection,
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncRead::poll_read(Pin::new(&mut self.recv), context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.send), context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.send), context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(Pin::new(&mut self.send), context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, TransportExt};
+    use futures::{
+        future::{join, FutureExt},
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), ::std::io::Error> {
+        let t = QuicTransport::new(&[7; 32]).and_then(|mut out, _addr, origin| async move {
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Earth");
+                    out.write_all(b"Air").await?;
+                    out.close().await?;
+                }
+                ConnectionOrigin::Outbound => {
+                    out.write_all(b"Earth").await?;
+                    let mut buf = [0; 3];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Air");
+                }
+            }
+            Ok(())
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())?;
+        let dial = t.dial(PeerId::random(), addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::new(&[7; 32]);
+
+        let result = t.listen_on("/memory/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let result = t.dial(PeerId::random(), "/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
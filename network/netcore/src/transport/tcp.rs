@@ -4,7 +4,9 @@
 //! TCP Transport
 use crate::transport::Transport;
 use diem_types::{
-    network_address::{parse_dns_tcp, parse_ip_tcp, parse_tcp, IpFilter, NetworkAddress},
+    network_address::{
+        parse_dns_tcp, parse_ip_tcp, parse_onion_tcp, parse_tcp, IpFilter, NetworkAddress,
+    },
     PeerId,
 };
 use futures::{
@@ -14,6 +16,7 @@ use futures::{
     stream::Stream,
 };
 use proxy::Proxy;
+use socket2::{Domain, Protocol, Socket, Type};
 use std::{
     convert::TryFrom,
     fmt::Debug,
@@ -36,6 +39,25 @@ pub struct TcpTransport {
     pub ttl: Option<u32>,
     /// `TCP_NODELAY` to set for opened sockets, or `None` to keep default.
     pub nodelay: Option<bool>,
+    //////// 0L ////////
+    /// Proxy to dial through, or `None` to use the `https_proxy` of the environment, if any.
+    pub proxy: Option<TcpProxy>,
+    /// `host:port` of the Tor SOCKS port onion addresses are dialed through, onion addresses
+    /// cannot be dialed without it.
+    pub tor_proxy: Option<String>,
+    /// IPv6 listeners refuse IPv4 connections, so that an IPv4 listener binds the same port
+    /// alongside them, for a dual-stack node.
+    pub ipv6_only: bool,
+}
+
+//////// 0L ////////
+/// A proxy outbound connections are dialed through, by the `host:port` of the proxy
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TcpProxy {
+    /// SOCKS5 without authentication, the proxy resolves dns names
+    Socks5(String),
+    /// HTTP CONNECT
+    HttpConnect(String),
 }
 
 impl TcpTransport {
@@ -52,6 +74,19 @@ impl TcpTransport {
     }
 }
 
+//////// 0L ////////
+/// A listener at the IPv6 `address`, refusing IPv4 connections
+fn bind_ipv6_only(address: SocketAddr) -> io::Result<::std::net::TcpListener> {
+    let socket = Socket::new(Domain::ipv6(), Type::stream(), Some(Protocol::tcp()))?;
+    socket.set_only_v6(true)?;
+    // As std does on unix, so that a restarted node binds the port of connections in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(128)?;
+    Ok(socket.into_tcp_listener())
+}
+
 impl Transport for TcpTransport {
     type Output = TcpSocket;
     type Error = ::std::io::Error;
@@ -69,7 +104,12 @@ impl Transport for TcpTransport {
             return Err(invalid_addr_error(&addr));
         }
 
-        let listener = ::std::net::TcpListener::bind((ipaddr, port))?;
+        //////// 0L ////////
+        let listener = if ipaddr.is_ipv6() && self.ipv6_only {
+            bind_ipv6_only(SocketAddr::new(ipaddr, port))?
+        } else {
+            ::std::net::TcpListener::bind((ipaddr, port))?
+        };
         listener.set_nonblocking(true)?;
         let listener = TcpListener::try_from(listener)?;
         let listen_addr = NetworkAddress::from(listener.local_addr()?);
@@ -92,8 +132,36 @@ impl Transport for TcpTransport {
         parse_ip_tcp(protos)
             .map(|_| ())
             .or_else(|| parse_dns_tcp(protos).map(|_| ()))
+            .or_else(|| parse_onion_tcp(protos).map(|_| ()))
             .ok_or_else(|| invalid_addr_error(&addr))?;
 
+        //////// 0L ////////
+        if parse_onion_tcp(protos).is_some() {
+            let tor_proxy = self.tor_proxy.clone().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("No Tor SOCKS port to dial the onion address '{}'", addr),
+                )
+            })?;
+            return Ok(TcpOutbound {
+                inner: Box::pin(connect_via_socks5(tor_proxy, addr)),
+                config: self.clone(),
+            });
+        }
+        if let Some(proxy) = &self.proxy {
+            let f: Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send + 'static>> =
+                match proxy.clone() {
+                    TcpProxy::Socks5(proxy_addr) => Box::pin(connect_via_socks5(proxy_addr, addr)),
+                    TcpProxy::HttpConnect(proxy_addr) => {
+                        Box::pin(connect_via_proxy(proxy_addr, addr))
+                    }
+                };
+            return Ok(TcpOutbound {
+                inner: f,
+                config: self.clone(),
+            });
+        }
+
         let proxy = Proxy::new();
 
         let proxy_addr = {
@@ -134,7 +202,7 @@ impl Transport for TcpTransport {
 }
 
 /// Try to lookup the dns name, then filter addrs according to the `IpFilter`.
-async fn resolve_with_filter(
+pub(crate) async fn resolve_with_filter(
     ip_filter: IpFilter,
     dns_name: &str,
     port: u16,
@@ -218,6 +286,65 @@ async fn connect_via_proxy(proxy_addr: String, addr: NetworkAddress) -> io::Resu
     }
 }
 
+//////// 0L ////////
+/// Connects to `addr` through a SOCKS5 proxy (RFC 1928), handing dns names to the proxy so that
+/// they resolve from its side.
+async fn connect_via_socks5(proxy_addr: String, addr: NetworkAddress) -> io::Result<TcpStream> {
+    use diem_types::network_address::Protocol::*;
+
+    let socks_error = |reason: String| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            format!("SOCKS5 proxy CONNECT failed: {}", reason),
+        )
+    };
+    let (target, port) = match addr.as_slice() {
+        [Ip4(ip), Tcp(port), ..] => ([&[1][..], &ip.octets()[..]].concat(), *port),
+        [Ip6(ip), Tcp(port), ..] => ([&[4][..], &ip.octets()[..]].concat(), *port),
+        [Dns(name), Tcp(port), ..] | [Dns4(name), Tcp(port), ..] | [Dns6(name), Tcp(port), ..] => {
+            let name = name.as_ref().as_bytes();
+            let len = u8::try_from(name.len()).map_err(|_| invalid_addr_error(&addr))?;
+            ([&[3, len][..], name].concat(), *port)
+        }
+        [Onion3(onion), Tcp(port), ..] => {
+            let host = onion.host();
+            let len = u8::try_from(host.len()).map_err(|_| invalid_addr_error(&addr))?;
+            ([&[3, len][..], host.as_bytes()].concat(), *port)
+        }
+        _ => return Err(invalid_addr_error(&addr)),
+    };
+
+    let mut stream = TcpStream::connect(proxy_addr).await?;
+    // Greeting, offering no authentication only
+    stream.write_all(&[5, 1, 0]).await?;
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    if reply != [5, 0] {
+        return Err(socks_error(format!(
+            "the proxy requires an unsupported authentication method {}",
+            reply[1]
+        )));
+    }
+
+    let request = [&[5, 1, 0][..], &target[..], &port.to_be_bytes()[..]].concat();
+    stream.write_all(&request).await?;
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[1] != 0 {
+        return Err(socks_error(format!("reply code {}", reply[1])));
+    }
+    // Skip the address the proxy bound, and its port
+    let bound_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => usize::from(stream.read_u8().await?),
+        atyp => return Err(socks_error(format!("unknown address type {}", atyp))),
+    };
+    let mut bound = vec![0; bound_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(stream)
+}
+
 fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
     io::Error::new(
         io::ErrorKind::InvalidInput,
@@ -363,6 +490,48 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_dial_via_socks5() -> Result<(), ::std::io::Error> {
+        // A SOCKS5 proxy accepting a single connection to peer.test:6180, then echoing
+        let proxy = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy_addr = proxy.local_addr()?.to_string();
+        let server = async move {
+            let (mut socket, _) = proxy.accept().await?;
+            let mut greeting = [0; 3];
+            tokio::io::AsyncReadExt::read_exact(&mut socket, &mut greeting).await?;
+            assert_eq!(greeting, [5, 1, 0]);
+            tokio::io::AsyncWriteExt::write_all(&mut socket, &[5, 0]).await?;
+            let mut request = [0; 16];
+            tokio::io::AsyncReadExt::read_exact(&mut socket, &mut request).await?;
+            assert_eq!(&request[..5], &[5, 1, 0, 3, 9]);
+            assert_eq!(&request[5..14], b"peer.test");
+            assert_eq!(u16::from_be_bytes([request[14], request[15]]), 6180);
+            let reply = [5, 0, 0, 1, 127, 0, 0, 1, 0, 0];
+            tokio::io::AsyncWriteExt::write_all(&mut socket, &reply).await?;
+            let mut buf = [0; 5];
+            tokio::io::AsyncReadExt::read_exact(&mut socket, &mut buf).await?;
+            tokio::io::AsyncWriteExt::write_all(&mut socket, &buf).await
+        };
+
+        let t = TcpTransport {
+            proxy: Some(TcpProxy::Socks5(proxy_addr)),
+            ..TcpTransport::default()
+        };
+        let client = async move {
+            let mut socket = t
+                .dial(PeerId::random(), "/dns/peer.test/tcp/6180".parse().unwrap())?
+                .await?;
+            socket.write_all(b"Earth").await?;
+            let mut buf = [0; 5];
+            socket.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Earth");
+            Ok::<_, io::Error>(())
+        };
+
+        let (server, client) = join(server, client).await;
+        server.and(client)
+    }
+
     #[test]
     fn unsupported_multiaddrs() {
         let t = TcpTransport::default();
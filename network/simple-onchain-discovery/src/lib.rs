@@ -53,7 +53,7 @@ pub static DISCOVERY_COUNTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_simple_onchain_discovery_counts",
         "Histogram of busy time of spent in event processing loop",
-        &["role_type", "network_id", "peer_id", "metric"]
+        &["chain_id", "role_type", "network_id", "peer_id", "metric"]
     )
     .unwrap()
 });
@@ -62,7 +62,7 @@ pub static NETWORK_KEY_MISMATCH: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_network_key_mismatch",
         "Gauge of whether the network key mismatches onchain state",
-        &["role_type", "network_id", "peer_id"]
+        &["chain_id", "role_type", "network_id", "peer_id"]
     )
     .unwrap()
 });
@@ -130,10 +130,22 @@ fn extract_validator_set_updates(
             };
             (peer_id, Peer::from_addrs(peer_role, addrs))
         })
+        //////// 0L ////////
+        // Not every validator runs a fullnode
+        .filter(|(_, peer)| is_validator || !peer.addresses.is_empty())
         .collect();
 
+    //////// 0L ////////
+    // Other networks, e.g. the public network, discover the fullnodes the validators registered.
+    // The connectivity manager merges them with the seeds of the config, so that public nodes
+    // find the fullnodes of validators that joined after genesis.
+    let source = if is_validator {
+        DiscoverySource::OnChainValidatorSet
+    } else {
+        DiscoverySource::OnChainFullNodes
+    };
     vec![ConnectivityRequest::UpdateDiscoveredPeers(
-        DiscoverySource::OnChainValidatorSet,
+        source,
         discovered_peers,
     )]
 }
@@ -177,6 +189,7 @@ impl ValidatorSetChangeListener {
 
         NETWORK_KEY_MISMATCH
             .with_label_values(&[
+                self.network_context.chain_label(),
                 self.network_context.role().as_str(),
                 self.network_context.network_id().as_str(),
                 self.network_context.peer_id().short_str().as_str(),
@@ -188,6 +201,7 @@ impl ValidatorSetChangeListener {
     /// the appropriate configuration changes and passes it to the ConnectionManager channel.
     async fn process_payload(&mut self, payload: OnChainConfigPayload) {
         let _process_timer = EVENT_PROCESSING_LOOP_BUSY_DURATION_S.start_timer();
+        self.network_context.set_epoch(payload.epoch()); //////// 0L ////////
 
         let node_set: ValidatorSet = payload
             .get()
@@ -259,14 +273,17 @@ impl ValidatorSetChangeListener {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use diem_config::config::HANDSHAKE_VERSION;
+    use diem_config::{
+        config::{RoleType, HANDSHAKE_VERSION},
+        network_id::NetworkId,
+    };
     use diem_crypto::{
         ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
         x25519::PrivateKey,
         PrivateKey as PK, Uniform,
     };
     use diem_types::{
-        network_address::NetworkAddress, on_chain_config::OnChainConfig,
+        chain_id::ChainId, network_address::NetworkAddress, on_chain_config::OnChainConfig,
         validator_config::ValidatorConfig, validator_info::ValidatorInfo, PeerId,
     };
     use futures::executor::block_on;
@@ -323,11 +340,53 @@ mod tests {
         check_network_key_mismatch_metric(1, &network_context);
     }
 
+    #[test]
+    fn public_network_discovers_fullnodes() {
+        let pubkey = test_pubkey([2u8; 32]);
+        let fullnode_address = NetworkAddress::mock().append_prod_protos(pubkey, HANDSHAKE_VERSION);
+        let validator = |addresses: Vec<NetworkAddress>| {
+            let consensus_pubkey = Ed25519PrivateKey::generate_for_testing().public_key();
+            ValidatorInfo::new(
+                PeerId::random(),
+                0,
+                ValidatorConfig::new(consensus_pubkey, vec![], bcs::to_bytes(&addresses).unwrap()),
+            )
+        };
+        let with_fullnode = validator(vec![fullnode_address.clone()]);
+        let peer_id = *with_fullnode.account_address();
+        let validator_set = ValidatorSet::new(vec![with_fullnode, validator(vec![])]);
+
+        let network_context = Arc::new(NetworkContext::new(
+            RoleType::FullNode,
+            NetworkId::Public,
+            PeerId::random(),
+            ChainId::test(),
+        ));
+        let updates = extract_validator_set_updates(
+            network_context,
+            &Encryptor::for_testing(),
+            validator_set,
+        );
+        match updates.as_slice() {
+            [ConnectivityRequest::UpdateDiscoveredPeers(source, peers)] => {
+                assert_eq!(*source, DiscoverySource::OnChainFullNodes);
+                // The validator without a fullnode is left out
+                assert_eq!(peers.len(), 1);
+                let peer = &peers[&peer_id];
+                assert_eq!(peer.role, PeerRole::ValidatorFullNode);
+                assert_eq!(peer.addresses, vec![fullnode_address]);
+                assert!(peer.keys.contains(&pubkey));
+            }
+            updates => panic!("Unexpected updates {:?}", updates),
+        }
+    }
+
     fn check_network_key_mismatch_metric(expected: i64, network_context: &NetworkContext) {
         assert_eq!(
             expected,
             NETWORK_KEY_MISMATCH
                 .get_metric_with_label_values(&[
+                    network_context.chain_label(),
                     network_context.role().as_str(),
                     network_context.network_id().as_str(),
                     network_context.peer_id().short_str().as_str()
@@ -6,10 +6,14 @@ use crate::{
     counters,
     peer_manager::{conn_notifs_channel, ConnectionRequestSender},
 };
-use diem_config::{config::PeerSet, network_id::NetworkContext};
+use diem_config::{
+    config::{PeerRole, PeerSet},
+    network_id::NetworkContext,
+};
 use diem_infallible::RwLock;
 use diem_time_service::TimeService;
-use std::{sync::Arc, time::Duration};
+use diem_types::PeerId;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::runtime::Handle;
 use tokio_retry::strategy::ExponentialBackoff;
 
@@ -34,6 +38,7 @@ impl ConnectivityManagerBuilder {
         connection_notifs_rx: conn_notifs_channel::Receiver,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        role_overrides: HashMap<PeerId, PeerRole>, //////// 0L ////////
     ) -> Self {
         let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new(
             channel_size,
@@ -55,6 +60,7 @@ impl ConnectivityManagerBuilder {
                 Duration::from_millis(max_connection_delay_ms),
                 outbound_connection_limit,
                 mutual_authentication,
+                role_overrides,
             )),
         }
     }
@@ -113,6 +113,9 @@ pub struct ConnectivityManager<TBackoff> {
     rng: SmallRng,
     /// Whether we are using mutual authentication or not
     mutual_authentication: bool,
+    //////// 0L ////////
+    /// Roles pinned by config, taking precedence over the roles of the discovery sources
+    role_overrides: HashMap<PeerId, PeerRole>,
 }
 
 /// Different sources for peer addresses, ordered by priority (Onchain=highest,
@@ -121,6 +124,10 @@ pub struct ConnectivityManager<TBackoff> {
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, NumVariants, Serialize)]
 pub enum DiscoverySource {
     OnChainValidatorSet,
+    OnChainFullNodes, //////// 0L ////////
+    Dns,              //////// 0L ////////
+    Relay,            //////// 0L ////////
+    Admin,            //////// 0L ////////
     Config,
 }
 
@@ -137,6 +144,10 @@ impl fmt::Display for DiscoverySource {
             "{}",
             match self {
                 DiscoverySource::OnChainValidatorSet => "OnChainValidatorSet",
+                DiscoverySource::OnChainFullNodes => "OnChainFullNodes", //////// 0L ////////
+                DiscoverySource::Dns => "Dns",                           //////// 0L ////////
+                DiscoverySource::Relay => "Relay",                       //////// 0L ////////
+                DiscoverySource::Admin => "Admin",                       //////// 0L ////////
                 DiscoverySource::Config => "Config",
             }
         )
@@ -261,6 +272,7 @@ where
         max_delay: Duration,
         outbound_connection_limit: Option<usize>,
         mutual_authentication: bool,
+        role_overrides: HashMap<PeerId, PeerRole>, //////// 0L ////////
     ) -> Self {
         assert!(
             eligible.read().is_empty(),
@@ -291,6 +303,7 @@ where
             outbound_connection_limit,
             rng: SmallRng::from_entropy(),
             mutual_authentication,
+            role_overrides,
         };
 
         // set the initial config addresses and pubkeys
@@ -366,9 +379,12 @@ where
             .filter_map(|(peer_id, metadata)| {
                 // If we're using server only auth, we need to not evict unknown peers
                 // TODO: We should prevent `Unknown` from discovery sources
+                //////// 0L ////////
+                // nor the peers whose role is pinned, which are no longer `Unknown` once connected
                 if !self.mutual_authentication
                     && metadata.origin == ConnectionOrigin::Inbound
-                    && metadata.role == PeerRole::Unknown
+                    && (metadata.role == PeerRole::Unknown
+                        || self.role_overrides.contains_key(peer_id))
                 {
                     None
                 } else {
@@ -666,12 +682,19 @@ where
             }
 
             // Create the new `DiscoveredPeer`, role is set when a `Peer` is first discovered
+            //////// 0L ////////
+            // unless the config pins the role of the peer
+            let role = self
+                .role_overrides
+                .get(&peer_id)
+                .copied()
+                .unwrap_or(discovered_peer.role);
             let peer = self
                 .discovered_peers
                 .0
                 .entry(peer_id)
                 .or_insert(DiscoveredPeer {
-                    role: discovered_peer.role,
+                    role,
                     addrs: Addresses::default(),
                     keys: PublicKeys::default(),
                 });
@@ -737,8 +760,14 @@ where
             "Connection notification"
         );
         match notif {
-            peer_manager::ConnectionNotification::NewPeer(metadata, _context) => {
+            peer_manager::ConnectionNotification::NewPeer(mut metadata, _context) => {
                 let peer_id = metadata.remote_peer_id;
+                //////// 0L ////////
+                // The handshake only knows the roles of discovered peers, the config pins the role
+                // of inbound and not yet discovered peers too
+                if let Some(role) = self.role_overrides.get(&peer_id) {
+                    metadata.role = *role;
+                }
                 counters::peer_connected(&self.network_context, &peer_id, 1);
                 self.connected.insert(peer_id, metadata);
 
@@ -83,6 +83,14 @@ struct TestHarness {
 
 impl TestHarness {
     fn new(seeds: PeerSet) -> (Self, ConnectivityManager<FixedInterval>) {
+        Self::new_with_role_overrides(seeds, HashMap::new())
+    }
+
+    //////// 0L ////////
+    fn new_with_role_overrides(
+        seeds: PeerSet,
+        role_overrides: HashMap<PeerId, PeerRole>,
+    ) -> (Self, ConnectivityManager<FixedInterval>) {
         let network_context = NetworkContext::mock();
         let time_service = TimeService::mock();
         let (connection_reqs_tx, connection_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
@@ -103,6 +111,7 @@ impl TestHarness {
             MAX_CONNECTION_DELAY,
             Some(MAX_TEST_CONNECTIONS),
             true, /* mutual_authentication */
+            role_overrides,
         );
         let mock = Self {
             trusted_peers,
@@ -805,3 +814,46 @@ fn basic_update_discovered_peers() {
     conn_mgr.handle_update_discovered_peers(DiscoverySource::Config, peers_empty.clone());
     assert_eq!(*trusted_peers.read(), peers_empty);
 }
+
+//////// 0L ////////
+#[test]
+fn role_overrides() {
+    let (peer_id_a, peer_a, _, _) = test_peer(0);
+    let (peer_id_b, peer_b, _, _) = test_peer(1);
+    let role_overrides = hashmap! {peer_id_a => PeerRole::PreferredUpstream};
+    let (mock, mut conn_mgr) = TestHarness::new_with_role_overrides(HashMap::new(), role_overrides);
+
+    let peers = hashmap! {peer_id_a => peer_a, peer_id_b => peer_b};
+    conn_mgr.handle_update_discovered_peers(DiscoverySource::OnChainValidatorSet, peers);
+    let trusted_peers = mock.trusted_peers.read();
+    assert_eq!(trusted_peers[&peer_id_a].role, PeerRole::PreferredUpstream);
+    assert_eq!(trusted_peers[&peer_id_b].role, PeerRole::Validator);
+}
+
+//////// 0L ////////
+#[test]
+fn role_overrides_on_connection() {
+    let (peer_id_a, _, _, _) = test_peer(0);
+    let (peer_id_b, _, _, _) = test_peer(1);
+    let role_overrides = hashmap! {peer_id_a => PeerRole::PreferredUpstream};
+    let (_mock, mut conn_mgr) =
+        TestHarness::new_with_role_overrides(HashMap::new(), role_overrides);
+
+    // neither peer was discovered, both dialed in
+    for peer_id in [peer_id_a, peer_id_b].iter() {
+        let metadata = ConnectionMetadata::mock_with_role_and_origin(
+            *peer_id,
+            PeerRole::Unknown,
+            ConnectionOrigin::Inbound,
+        );
+        conn_mgr.handle_control_notification(peer_manager::ConnectionNotification::NewPeer(
+            metadata,
+            NetworkContext::mock(),
+        ));
+    }
+    assert_eq!(
+        conn_mgr.connected[&peer_id_a].role,
+        PeerRole::PreferredUpstream
+    );
+    assert_eq!(conn_mgr.connected[&peer_id_b].role, PeerRole::Unknown);
+}
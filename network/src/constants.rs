@@ -13,6 +13,9 @@ pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
 /// Limit on concurrent Inbound RPC requests before backpressure is applied
 pub const MAX_CONCURRENT_INBOUND_RPCS: u32 = 100;
+//////// 0L ////////
+/// Limit on messages queued to be written to a connection before backpressure is applied
+pub const WRITE_QUEUE_SIZE: usize = 1024;
 
 // These are only used in tests
 // TODO: Fix this so the tests and the defaults in config are the same
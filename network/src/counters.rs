@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::protocols::wire::handshake::v1::ProtocolId;
-use diem_config::network_id::NetworkContext;
+use diem_config::{config::PeerRole, network_id::NetworkContext};
 use diem_metrics::{
     register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
     Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
@@ -28,15 +28,22 @@ pub static DIEM_CONNECTIONS: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_connections",
         "Number of current connections and their direction",
-        &["role_type", "network_id", "peer_id", "direction"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction"
+        ]
     )
     .unwrap()
 });
 
 pub fn connections(network_context: &NetworkContext, origin: ConnectionOrigin) -> IntGauge {
     DIEM_CONNECTIONS.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         origin.as_str(),
     ])
@@ -46,7 +53,13 @@ pub static DIEM_CONNECTIONS_REJECTED: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_connections_rejected",
         "Number of connections rejected per interface",
-        &["role_type", "network_id", "peer_id", "direction"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction"
+        ]
     )
     .unwrap()
 });
@@ -56,18 +69,110 @@ pub fn connections_rejected(
     origin: ConnectionOrigin,
 ) -> IntCounter {
     DIEM_CONNECTIONS_REJECTED.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         origin.as_str(),
     ])
 }
 
+//////// 0L ////////
+pub static DIEM_NETWORK_HANDSHAKE_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_handshake_failures",
+        "Number of connection upgrades failed per interface, by reason",
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction",
+            "reason"
+        ]
+    )
+    .unwrap()
+});
+
+//////// 0L ////////
+pub fn handshake_failures(
+    network_context: &NetworkContext,
+    origin: ConnectionOrigin,
+    reason: &str,
+) -> IntCounter {
+    DIEM_NETWORK_HANDSHAKE_FAILURES.with_label_values(&[
+        network_context.chain_label(),
+        network_context.role().as_str(),
+        network_context.network_label(),
+        network_context.peer_id().short_str().as_str(),
+        origin.as_str(),
+        reason,
+    ])
+}
+
+//////// 0L ////////
+pub static DIEM_NETWORK_HANDSHAKE_MISMATCHES_TOLERATED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_handshake_mismatches_tolerated",
+        "Number of connections accepted despite a chain or network mismatch, by mismatch",
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction",
+            "reason"
+        ]
+    )
+    .unwrap()
+});
+
+pub fn handshake_mismatches_tolerated(
+    network_context: &NetworkContext,
+    origin: ConnectionOrigin,
+    reason: &str,
+) -> IntCounter {
+    DIEM_NETWORK_HANDSHAKE_MISMATCHES_TOLERATED.with_label_values(&[
+        network_context.chain_label(),
+        network_context.role().as_str(),
+        network_context.network_label(),
+        network_context.peer_id().short_str().as_str(),
+        origin.as_str(),
+        reason,
+    ])
+}
+
+//////// 0L ////////
+pub static DIEM_CONNECTIONS_EVICTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_connections_evicted",
+        "Number of inbound connections evicted by the eviction policy, per role of the evicted peer",
+        &["chain_id", "role_type", "network_id", "peer_id", "peer_role"]
+    )
+    .unwrap()
+});
+
+pub fn connections_evicted(network_context: &NetworkContext, peer_role: PeerRole) -> IntCounter {
+    DIEM_CONNECTIONS_EVICTED.with_label_values(&[
+        network_context.chain_label(),
+        network_context.role().as_str(),
+        network_context.network_label(),
+        network_context.peer_id().short_str().as_str(),
+        format!("{:?}", peer_role).as_str(),
+    ])
+}
+
 pub static DIEM_NETWORK_PEER_CONNECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_network_peer_connected",
         "Indicates if we are connected to a particular peer",
-        &["role_type", "network_id", "peer_id", "remote_peer_id"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "remote_peer_id"
+        ]
     )
     .unwrap()
 });
@@ -76,8 +181,9 @@ pub fn peer_connected(network_context: &NetworkContext, remote_peer_id: &PeerId,
     if network_context.network_id().is_validator_network() {
         DIEM_NETWORK_PEER_CONNECTED
             .with_label_values(&[
+                network_context.chain_label(),
                 network_context.role().as_str(),
-                network_context.network_id().as_str(),
+                network_context.network_label(),
                 network_context.peer_id().short_str().as_str(),
                 remote_peer_id.short_str().as_str(),
             ])
@@ -94,8 +200,9 @@ pub fn inc_by_with_context(
 ) {
     counter
         .with_label_values(&[
+            network_context.chain_label(),
             network_context.role().as_str(),
-            network_context.network_id().as_str(),
+            network_context.network_label(),
             network_context.peer_id().short_str().as_str(),
             label,
         ])
@@ -106,7 +213,13 @@ pub static DIEM_NETWORK_PENDING_CONNECTION_UPGRADES: Lazy<IntGaugeVec> = Lazy::n
     register_int_gauge_vec!(
         "diem_network_pending_connection_upgrades",
         "Number of concurrent inbound or outbound connections we're currently negotiating",
-        &["role_type", "network_id", "peer_id", "direction"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction"
+        ]
     )
     .unwrap()
 });
@@ -116,8 +229,9 @@ pub fn pending_connection_upgrades(
     direction: ConnectionOrigin,
 ) -> IntGauge {
     DIEM_NETWORK_PENDING_CONNECTION_UPGRADES.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         direction.as_str(),
     ])
@@ -127,7 +241,14 @@ pub static DIEM_NETWORK_CONNECTION_UPGRADE_TIME: Lazy<HistogramVec> = Lazy::new(
     register_histogram_vec!(
         "diem_network_connection_upgrade_time_seconds",
         "Time to complete a new inbound or outbound connection upgrade",
-        &["role_type", "network_id", "peer_id", "direction", "state"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "direction",
+            "state"
+        ]
     )
     .unwrap()
 });
@@ -138,8 +259,9 @@ pub fn connection_upgrade_time(
     state: &'static str,
 ) -> Histogram {
     DIEM_NETWORK_CONNECTION_UPGRADE_TIME.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         direction.as_str(),
         state,
@@ -159,7 +281,14 @@ pub static DIEM_NETWORK_RPC_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_network_rpc_messages",
         "Number of RPC messages",
-        &["role_type", "network_id", "peer_id", "type", "state"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "type",
+            "state"
+        ]
     )
     .unwrap()
 });
@@ -170,8 +299,9 @@ pub fn rpc_messages(
     state_label: &'static str,
 ) -> IntCounter {
     DIEM_NETWORK_RPC_MESSAGES.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         type_label,
         state_label,
@@ -182,7 +312,14 @@ pub static DIEM_NETWORK_RPC_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_network_rpc_bytes",
         "Number of RPC bytes transferred",
-        &["role_type", "network_id", "peer_id", "type", "state"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "type",
+            "state"
+        ]
     )
     .unwrap()
 });
@@ -193,8 +330,9 @@ pub fn rpc_bytes(
     state_label: &'static str,
 ) -> IntCounter {
     DIEM_NETWORK_RPC_BYTES.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         type_label,
         state_label,
@@ -205,7 +343,7 @@ pub static INVALID_NETWORK_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_network_invalid_messages",
         "Number of invalid messages (RPC/direct_send)",
-        &["role_type", "network_id", "peer_id", "type"]
+        &["chain_id", "role_type", "network_id", "peer_id", "type"]
     )
     .unwrap()
 });
@@ -214,7 +352,13 @@ pub static PEER_SEND_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_network_peer_send_failures",
         "Number of messages failed to send to peer",
-        &["role_type", "network_id", "peer_id", "protocol_id"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "protocol_id"
+        ]
     )
     .unwrap()
 });
@@ -223,7 +367,13 @@ pub static DIEM_NETWORK_OUTBOUND_RPC_REQUEST_LATENCY: Lazy<HistogramVec> = Lazy:
     register_histogram_vec!(
         "diem_network_outbound_rpc_request_latency_seconds",
         "Outbound RPC request latency in seconds",
-        &["role_type", "network_id", "peer_id", "protocol_id"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "protocol_id"
+        ]
     )
     .unwrap()
 });
@@ -233,8 +383,9 @@ pub fn outbound_rpc_request_latency(
     protocol_id: ProtocolId,
 ) -> Histogram {
     DIEM_NETWORK_OUTBOUND_RPC_REQUEST_LATENCY.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         protocol_id.as_str(),
     ])
@@ -244,7 +395,13 @@ pub static DIEM_NETWORK_INBOUND_RPC_HANDLER_LATENCY: Lazy<HistogramVec> = Lazy::
     register_histogram_vec!(
         "diem_network_inbound_rpc_handler_latency_seconds",
         "Inbound RPC request application handler latency in seconds",
-        &["role_type", "network_id", "peer_id", "protocol_id"]
+        &[
+            "chain_id",
+            "role_type",
+            "network_id",
+            "peer_id",
+            "protocol_id"
+        ]
     )
     .unwrap()
 });
@@ -254,8 +411,9 @@ pub fn inbound_rpc_handler_latency(
     protocol_id: ProtocolId,
 ) -> Histogram {
     DIEM_NETWORK_INBOUND_RPC_HANDLER_LATENCY.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         protocol_id.as_str(),
     ])
@@ -265,7 +423,7 @@ pub static DIEM_NETWORK_DIRECT_SEND_MESSAGES: Lazy<IntCounterVec> = Lazy::new(||
     register_int_counter_vec!(
         "diem_network_direct_send_messages",
         "Number of direct send messages",
-        &["role_type", "network_id", "peer_id", "state"]
+        &["chain_id", "role_type", "network_id", "peer_id", "state"]
     )
     .unwrap()
 });
@@ -275,8 +433,9 @@ pub fn direct_send_messages(
     state_label: &'static str,
 ) -> IntCounter {
     DIEM_NETWORK_DIRECT_SEND_MESSAGES.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         state_label,
     ])
@@ -286,7 +445,7 @@ pub static DIEM_NETWORK_DIRECT_SEND_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_network_direct_send_bytes",
         "Number of direct send bytes transferred",
-        &["role_type", "network_id", "peer_id", "state"]
+        &["chain_id", "role_type", "network_id", "peer_id", "state"]
     )
     .unwrap()
 });
@@ -296,8 +455,9 @@ pub fn direct_send_bytes(
     state_label: &'static str,
 ) -> IntCounter {
     DIEM_NETWORK_DIRECT_SEND_BYTES.with_label_values(&[
+        network_context.chain_label(),
         network_context.role().as_str(),
-        network_context.network_id().as_str(),
+        network_context.network_label(),
         network_context.peer_id().short_str().as_str(),
         state_label,
     ])
@@ -334,6 +494,17 @@ pub static PENDING_HEALTH_CHECKER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::ne
     .unwrap()
 });
 
+//////// 0L ////////
+/// Counter of pending network events to Relay.
+pub static PENDING_RELAY_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_pending_relay_events",
+        "Number of pending relay events by state",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counter of pending network events to Discovery.
 pub static PENDING_DISCOVERY_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -0,0 +1,83 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The drain of a node shutting down, shared by the components of a chain.
+//!
+//! Once the drain started, the networks stop accepting inbound connections and state sync stops
+//! requesting chunks. The components register the work they still have in flight, which the node
+//! waits for before it closes the storage.
+
+use diem_infallible::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+type Pending = Box<dyn Fn() -> usize + Send + Sync>;
+
+pub struct Drain {
+    draining: AtomicBool,
+    pending: Mutex<Vec<(&'static str, Pending)>>,
+}
+
+impl Drain {
+    pub fn new() -> Self {
+        Self {
+            draining: AtomicBool::new(false),
+            pending: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn start(&self) {
+        self.draining.store(true, Ordering::Release);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Acquire)
+    }
+
+    /// Registers the work of a component, `pending` counting what it has left in flight
+    pub fn register(
+        &self,
+        name: &'static str,
+        pending: impl Fn() -> usize + Send + Sync + 'static,
+    ) {
+        self.pending.lock().push((name, Box::new(pending)));
+    }
+
+    /// The work still in flight, by component, empty once drained
+    pub fn pending(&self) -> Vec<(&'static str, usize)> {
+        self.pending
+            .lock()
+            .iter()
+            .map(|(name, pending)| (*name, pending()))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{atomic::AtomicUsize, Arc};
+
+    #[test]
+    fn test_drain() {
+        let drain = Drain::new();
+        let chunks = Arc::new(AtomicUsize::new(2));
+        let chunks_clone = chunks.clone();
+        drain.register("chunks", move || chunks_clone.load(Ordering::Relaxed));
+        drain.register("broadcasts", || 0);
+        assert!(!drain.is_draining());
+
+        drain.start();
+        assert!(drain.is_draining());
+        assert_eq!(drain.pending(), vec![("chunks", 2)]);
+        chunks.store(0, Ordering::Relaxed);
+        assert!(drain.pending().is_empty());
+    }
+}
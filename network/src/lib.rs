@@ -13,13 +13,17 @@
 pub mod connectivity_manager;
 pub mod constants;
 pub mod counters;
+pub mod drain; //////// 0L ////////
 pub mod error;
 pub mod logging;
 pub mod noise;
 pub mod peer;
 pub mod peer_manager;
+pub mod peer_metadata; //////// 0L ////////
+pub mod peer_reputation; //////// 0L ////////
 pub mod protocols;
 pub mod transport;
+pub mod upstream_selector; //////// 0L ////////
 
 #[cfg(feature = "fuzzing")]
 pub mod fuzzing;
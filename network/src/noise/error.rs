@@ -89,4 +89,19 @@ impl NoiseHandshakeError {
         use NoiseHandshakeError::*;
         matches!(self, ServerReplayDetected(_, _))
     }
+
+    //////// 0L ////////
+    /// The category of the failure, the `reason` label of the handshake failure counter
+    pub fn rejection_reason(&self) -> &'static str {
+        use NoiseHandshakeError::*;
+        match self {
+            ClientExpectingDifferentPubkey(_, _)
+            | UnauthenticatedClientPubkey(_, _)
+            | UnauthenticatedClient(_, _)
+            | ClientPeerIdMismatch(_, _, _, _) => "unknown_key",
+            MissingAntiReplayTimestamp(_) | ServerReplayDetected(_, _) => "replay",
+            SelfDialDetected => "self_dial",
+            _ => "noise",
+        }
+    }
 }
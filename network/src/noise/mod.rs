@@ -18,7 +18,7 @@
 //! use diem_crypto::{x25519, ed25519, Uniform, PrivateKey, test_utils::TEST_SEED};
 //! use diem_infallible::RwLock;
 //! use rand::{rngs::StdRng, SeedableRng};
-//! use diem_types::PeerId;
+//! use diem_types::{chain_id::ChainId, PeerId};
 //! use std::{collections::{HashSet, HashMap}, io, sync::Arc};
 //!
 //! fn example() -> io::Result<()> {
@@ -46,6 +46,7 @@
 //!     RoleType::Validator,
 //!     NetworkId::Validator,
 //!     client_peer_id,
+//!     ChainId::test(),
 //! ));
 //! let client = NoiseUpgrader::new(client_context, client_private, client_auth);
 //!
@@ -54,6 +55,7 @@
 //!     RoleType::Validator,
 //!     NetworkId::Validator,
 //!     server_peer_id,
+//!     ChainId::test(),
 //! ));
 //! let server = NoiseUpgrader::new(server_context, server_private, server_auth);
 //!
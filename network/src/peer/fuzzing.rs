@@ -109,6 +109,7 @@ pub fn fuzz(data: &[u8]) {
         constants::MAX_FRAME_SIZE,
         None,
         None,
+        constants::WRITE_QUEUE_SIZE,
     );
     executor.spawn(peer.start());
 
@@ -134,6 +134,9 @@ pub struct Peer<TSocket> {
     inbound_rate_limiter: Option<SharedBucket>,
     /// Optional outbound rate limiter
     outbound_rate_limiter: Option<SharedBucket>,
+    //////// 0L ////////
+    /// Messages queued to be written to the connection, before backpressure is applied
+    write_queue_size: usize,
 }
 
 impl<TSocket> Peer<TSocket>
@@ -154,6 +157,7 @@ where
         max_frame_size: usize,
         inbound_rate_limiter: Option<SharedBucket>,
         outbound_rate_limiter: Option<SharedBucket>,
+        write_queue_size: usize, //////// 0L ////////
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
@@ -186,6 +190,7 @@ where
             max_frame_size,
             inbound_rate_limiter,
             outbound_rate_limiter,
+            write_queue_size,
         }
     }
 
@@ -229,6 +234,7 @@ where
             self.connection_metadata.clone(),
             self.network_context.clone(),
             writer,
+            self.write_queue_size,
         );
 
         // Start main Peer event loop.
@@ -305,6 +311,7 @@ where
         connection_metadata: ConnectionMetadata,
         network_context: Arc<NetworkContext>,
         mut writer: NetworkMessageSink<impl AsyncWrite + Unpin + Send + 'static>,
+        write_queue_size: usize, //////// 0L ////////
     ) -> (
         channel::Sender<(
             NetworkMessage,
@@ -319,7 +326,7 @@ where
                 oneshot::Sender<Result<(), PeerManagerError>>,
             )>,
             _,
-        ) = channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
+        ) = channel::new(write_queue_size, &counters::PENDING_WIRE_MESSAGES);
         let (close_tx, close_rx) = oneshot::channel();
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
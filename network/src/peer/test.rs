@@ -4,7 +4,7 @@
 use crate::{
     constants::{
         INBOUND_RPC_TIMEOUT_MS, MAX_CONCURRENT_INBOUND_RPCS, MAX_CONCURRENT_OUTBOUND_RPCS,
-        MAX_FRAME_SIZE, NETWORK_CHANNEL_SIZE,
+        MAX_FRAME_SIZE, NETWORK_CHANNEL_SIZE, WRITE_QUEUE_SIZE,
     },
     peer::{DisconnectReason, Peer, PeerNotification, PeerRequest},
     peer_manager::TransportNotification,
@@ -90,6 +90,7 @@ fn build_test_peer(
         MAX_FRAME_SIZE,
         None,
         None,
+        WRITE_QUEUE_SIZE,
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
@@ -4,6 +4,7 @@
 use crate::{
     counters,
     counters::NETWORK_RATE_LIMIT_METRICS,
+    drain::Drain,
     noise::{stream::NoiseStream, HandshakeAuthMode},
     peer_manager::{
         conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
@@ -15,10 +16,13 @@ use crate::{
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerSet, RateLimitConfig, HANDSHAKE_VERSION},
+    config::{
+        HandshakeEnforcement, MultiplexConfig, NetworkTransport, OutboundProxyConfig, PeerAccess,
+        PeerEvictionPolicy, PeerSet, RateLimitConfig, HANDSHAKE_VERSION,
+    },
     network_id::NetworkContext,
 };
-use diem_crypto::x25519;
+use diem_crypto::{x25519, ValidCryptoMaterial};
 use diem_infallible::RwLock;
 use diem_logger::prelude::*;
 use diem_metrics::IntCounterVec;
@@ -28,7 +32,8 @@ use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 use netcore::transport::memory::MemoryTransport;
 use netcore::transport::{
-    tcp::{TcpSocket, TcpTransport},
+    quic::{QuicSocket, QuicTransport},
+    tcp::{TcpProxy, TcpSocket, TcpTransport},
     Transport,
 };
 use std::{clone::Clone, collections::HashMap, fmt::Debug, net::IpAddr, sync::Arc};
@@ -55,6 +60,11 @@ struct TransportContext {
     authentication_mode: AuthenticationMode,
     trusted_peers: Arc<RwLock<PeerSet>>,
     enable_proxy_protocol: bool,
+    peer_access: PeerAccess,                     //////// 0L ////////
+    handshake_enforcement: HandshakeEnforcement, //////// 0L ////////
+    outbound_proxy: Option<OutboundProxyConfig>, //////// 0L ////////
+    tor_proxy: Option<String>,                   //////// 0L ////////
+    transport: NetworkTransport,                 //////// 0L ////////
 }
 
 impl TransportContext {
@@ -65,6 +75,11 @@ impl TransportContext {
         authentication_mode: AuthenticationMode,
         trusted_peers: Arc<RwLock<PeerSet>>,
         enable_proxy_protocol: bool,
+        peer_access: PeerAccess,
+        handshake_enforcement: HandshakeEnforcement,
+        outbound_proxy: Option<OutboundProxyConfig>,
+        tor_proxy: Option<String>,
+        transport: NetworkTransport,
     ) -> Self {
         Self {
             chain_id,
@@ -73,6 +88,11 @@ impl TransportContext {
             authentication_mode,
             trusted_peers,
             enable_proxy_protocol,
+            peer_access,
+            handshake_enforcement,
+            outbound_proxy,
+            tor_proxy,
+            transport,
         }
     }
 
@@ -115,6 +135,9 @@ struct PeerManagerContext {
     inbound_connection_limit: usize,
     inbound_rate_limit_config: Option<RateLimitConfig>,
     outbound_rate_limit_config: Option<RateLimitConfig>,
+    eviction_policy: Option<PeerEvictionPolicy>, //////// 0L ////////
+    multiplex: MultiplexConfig,                  //////// 0L ////////
+    drain: Option<Arc<Drain>>,                   //////// 0L ////////
 }
 
 impl PeerManagerContext {
@@ -137,6 +160,8 @@ impl PeerManagerContext {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        eviction_policy: Option<PeerEvictionPolicy>,
+        multiplex: MultiplexConfig,
     ) -> Self {
         Self {
             pm_reqs_tx,
@@ -154,6 +179,9 @@ impl PeerManagerContext {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            eviction_policy,
+            multiplex,
+            drain: None,
         }
     }
 
@@ -177,11 +205,14 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<DiemNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<DiemNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+//////// 0L ////////
+type QuicPeerManager = PeerManager<DiemNetTransport<QuicTransport>, NoiseStream<QuicSocket>>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
     Tcp(TcpPeerManager),
+    Quic(QuicPeerManager), //////// 0L ////////
 }
 
 pub struct PeerManagerBuilder {
@@ -193,6 +224,9 @@ pub struct PeerManagerBuilder {
     peer_manager: Option<TransportPeerManager>,
     // ListenAddress will be updated when the PeerManager is built
     listen_address: NetworkAddress,
+    //////// 0L ////////
+    // Second address of a dual-stack node, also updated when the PeerManager is built
+    dual_stack_address: Option<NetworkAddress>,
 }
 
 impl PeerManagerBuilder {
@@ -211,6 +245,14 @@ impl PeerManagerBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        eviction_policy: Option<PeerEvictionPolicy>, //////// 0L ////////
+        peer_access: PeerAccess,                     //////// 0L ////////
+        handshake_enforcement: HandshakeEnforcement, //////// 0L ////////
+        outbound_proxy: Option<OutboundProxyConfig>, //////// 0L ////////
+        tor_proxy: Option<String>,                   //////// 0L ////////
+        transport: NetworkTransport,                 //////// 0L ////////
+        multiplex: MultiplexConfig,                  //////// 0L ////////
+        dual_stack_address: Option<NetworkAddress>,  //////// 0L ////////
     ) -> Self {
         // Setup channel to send requests to peer manager.
         let (pm_reqs_tx, pm_reqs_rx) = diem_channel::new(
@@ -232,6 +274,11 @@ impl PeerManagerBuilder {
                 authentication_mode,
                 trusted_peers.clone(),
                 enable_proxy_protocol,
+                peer_access,
+                handshake_enforcement,
+                outbound_proxy,
+                tor_proxy,
+                transport,
             )),
             peer_manager_context: Some(PeerManagerContext::new(
                 pm_reqs_tx,
@@ -247,9 +294,12 @@ impl PeerManagerBuilder {
                 inbound_connection_limit,
                 inbound_rate_limit_config,
                 outbound_rate_limit_config,
+                eviction_policy,
+                multiplex,
             )),
             peer_manager: None,
             listen_address,
+            dual_stack_address,
         }
     }
 
@@ -257,6 +307,11 @@ impl PeerManagerBuilder {
         self.listen_address.clone()
     }
 
+    //////// 0L ////////
+    pub fn dual_stack_address(&self) -> Option<NetworkAddress> {
+        self.dual_stack_address.clone()
+    }
+
     pub fn connection_reqs_tx(&self) -> diem_channel::Sender<PeerId, ConnectionRequest> {
         self.peer_manager_context
             .as_ref()
@@ -278,6 +333,18 @@ impl PeerManagerBuilder {
         let protos = transport_context.supported_protocols();
         let chain_id = transport_context.chain_id;
         let enable_proxy_protocol = transport_context.enable_proxy_protocol;
+        let peer_access = transport_context.peer_access;
+        let handshake_enforcement = transport_context.handshake_enforcement;
+        let proxy = transport_context.outbound_proxy.map(|proxy| match proxy {
+            OutboundProxyConfig::Socks5 { address } => TcpProxy::Socks5(address),
+            OutboundProxyConfig::HttpConnect { address } => TcpProxy::HttpConnect(address),
+        });
+        let tor_proxy = transport_context.tor_proxy;
+        let transport = transport_context.transport;
+        //////// 0L ////////
+        // A dual-stack node binds its ip6 listener to ip6 only, leaving the
+        // ip4 port of the same number to the ip4 listener
+        let ipv6_only = self.dual_stack_address.is_some();
 
         let (key, auth_mode) = match transport_context.authentication_mode {
             AuthenticationMode::MaybeMutual(key) => (
@@ -291,10 +358,34 @@ impl PeerManagerBuilder {
         };
 
         self.peer_manager = match self.listen_address.as_slice() {
+            //////// 0L ////////
+            [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] if transport == NetworkTransport::Quic => {
+                Some(TransportPeerManager::Quic(self.build_with_transport(
+                    DiemNetTransport::new(
+                        QuicTransport::new(&key.to_bytes()).with_ipv6_only(ipv6_only),
+                        self.network_context.clone(),
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                        peer_access,
+                        handshake_enforcement,
+                    ),
+                    executor,
+                )))
+            }
             [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => {
                 Some(TransportPeerManager::Tcp(self.build_with_transport(
                     DiemNetTransport::new(
-                        DIEM_TCP_TRANSPORT.clone(),
+                        TcpTransport {
+                            proxy,
+                            tor_proxy,
+                            ipv6_only,
+                            ..DIEM_TCP_TRANSPORT
+                        },
                         self.network_context.clone(),
                         self.time_service.clone(),
                         key,
@@ -303,6 +394,8 @@ impl PeerManagerBuilder {
                         chain_id,
                         protos,
                         enable_proxy_protocol,
+                        peer_access,
+                        handshake_enforcement,
                     ),
                     executor,
                 )))
@@ -319,6 +412,8 @@ impl PeerManagerBuilder {
                     chain_id,
                     protos,
                     enable_proxy_protocol,
+                    peer_access,
+                    handshake_enforcement,
                 ),
                 executor,
             ))),
@@ -376,10 +471,15 @@ impl PeerManagerBuilder {
             pm_context.inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            pm_context.eviction_policy,
+            pm_context.multiplex,
+            self.dual_stack_address.clone(),
+            pm_context.drain,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
         self.listen_address = peer_mgr.listen_addr().clone();
+        self.dual_stack_address = peer_mgr.dual_stack_addr().cloned();
 
         peer_mgr
     }
@@ -406,6 +506,7 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::Quic(pm) => self.start_peer_manager(pm, executor), //////// 0L ////////
         }
     }
 
@@ -416,6 +517,15 @@ impl PeerManagerBuilder {
             .add_connection_event_listener()
     }
 
+    //////// 0L ////////
+    /// Stops accepting inbound connections once `drain` started
+    pub fn add_drain(&mut self, drain: Arc<Drain>) {
+        self.peer_manager_context
+            .as_mut()
+            .expect("Cannot add a drain if PeerManager has already been built.")
+            .drain = Some(drain);
+    }
+
     /// Add a handler for given protocols using raw bytes.
     pub fn add_protocol_handler(
         &mut self,
@@ -11,8 +11,8 @@
 //!  notification about new/lost Peers to the rest of the network stack.
 //!  * An actor responsible for dialing and listening for new connections.
 use crate::{
-    constants,
     counters::{self, FAILED_LABEL, SUCCEEDED_LABEL},
+    drain::Drain,
     logging::*,
     peer::{DisconnectReason, Peer, PeerNotification, PeerRequest},
     protocols::{
@@ -36,7 +36,7 @@ use futures::{
     future::{BoxFuture, FutureExt},
     io::{AsyncRead, AsyncWrite, AsyncWriteExt},
     sink::SinkExt,
-    stream::{Fuse, FuturesUnordered, StreamExt},
+    stream::{self, BoxStream, Fuse, FuturesUnordered, Stream, StreamExt},
 };
 use netcore::transport::{ConnectionOrigin, Transport};
 use serde::Serialize;
@@ -58,7 +58,9 @@ mod error;
 mod tests;
 
 pub use self::error::PeerManagerError;
-use diem_config::config::{PeerRole, PeerSet};
+use diem_config::config::{
+    InboundAdmission, MultiplexConfig, PeerEvictionPolicy, PeerRole, PeerSet,
+};
 use diem_infallible::RwLock;
 
 /// Request received by PeerManager from upstream actors.
@@ -251,7 +253,10 @@ where
     time_service: TimeService,
     /// Address to listen on for incoming connections.
     listen_addr: NetworkAddress,
-    /// Connection Listener, listening on `listen_addr`
+    //////// 0L ////////
+    /// Second address listened on by a dual-stack node, of the other ip version
+    dual_stack_addr: Option<NetworkAddress>,
+    /// Connection Listener, listening on `listen_addr` and `dual_stack_addr`
     transport_handler: Option<TransportHandler<TTransport, TSocket>>,
     /// Map from PeerId to corresponding Peer object.
     active_peers: HashMap<
@@ -296,6 +301,11 @@ where
     inbound_rate_limiters: IpAddrTokenBucketLimiter,
     /// Keyed storage of all outbound rate limiters
     outbound_rate_limiters: IpAddrTokenBucketLimiter,
+    //////// 0L ////////
+    /// Per role limits and eviction order of inbound peers, in place of the unknown peer limit
+    eviction_policy: Option<PeerEvictionPolicy>,
+    /// Limits of the RPCs and messages multiplexed over each connection
+    multiplex: MultiplexConfig,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -325,6 +335,10 @@ where
         inbound_connection_limit: usize,
         inbound_rate_limiters: IpAddrTokenBucketLimiter,
         outbound_rate_limiters: IpAddrTokenBucketLimiter,
+        eviction_policy: Option<PeerEvictionPolicy>, //////// 0L ////////
+        multiplex: MultiplexConfig,                  //////// 0L ////////
+        dual_stack_addr: Option<NetworkAddress>,     //////// 0L ////////
+        drain: Option<Arc<Drain>>,                   //////// 0L ////////
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -336,13 +350,15 @@ where
         // rethink how we init the PeerManager so we don't have to do this funny thing.
         let transport_notifs_tx_clone = transport_notifs_tx.clone();
         let _guard = executor.enter();
-        let (transport_handler, listen_addr) = TransportHandler::new(
+        let (transport_handler, listen_addr, dual_stack_addr) = TransportHandler::new(
             network_context.clone(),
             time_service.clone(),
             transport,
             listen_addr,
+            dual_stack_addr,
             transport_reqs_rx,
             transport_notifs_tx_clone,
+            drain,
         );
 
         Self {
@@ -350,6 +366,7 @@ where
             executor,
             time_service,
             listen_addr,
+            dual_stack_addr,
             transport_handler: Some(transport_handler),
             active_peers: HashMap::new(),
             trusted_peers,
@@ -368,6 +385,8 @@ where
             inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            eviction_policy,
+            multiplex,
         }
     }
 
@@ -412,6 +431,12 @@ where
         &self.listen_addr
     }
 
+    //////// 0L ////////
+    /// Get the second [`NetworkAddress`] a dual-stack node is listening on, if any
+    pub fn dual_stack_addr(&self) -> Option<&NetworkAddress> {
+        self.dual_stack_addr.as_ref()
+    }
+
     /// Start listening on the set address and return a future which runs PeerManager
     pub async fn start(mut self) {
         // Start listening for connections.
@@ -419,6 +444,12 @@ where
             NetworkSchema::new(&self.network_context),
             "Start listening for incoming connections on {}", self.listen_addr
         );
+        if let Some(dual_stack_addr) = &self.dual_stack_addr {
+            info!(
+                NetworkSchema::new(&self.network_context),
+                "Also listening for incoming connections on {}", dual_stack_addr
+            );
+        }
         self.start_connection_listener();
         loop {
             ::futures::select! {
@@ -473,6 +504,18 @@ where
                             )
                         }
                     }
+                    //////// 0L ////////
+                    ConnectionOrigin::Inbound if self.eviction_policy.is_some() => {
+                        if !self.admit_inbound(&conn.metadata) {
+                            counters::connections_rejected(
+                                &self.network_context,
+                                conn.metadata.origin,
+                            )
+                            .inc();
+                            self.disconnect(conn);
+                            return;
+                        }
+                    }
                     ConnectionOrigin::Inbound => {
                         // Everything below here is meant for unknown peers only, role comes from
                         // Noise handshake and if it's not `Unknown` it is trusted
@@ -754,6 +797,61 @@ where
         }
     }
 
+    //////// 0L ////////
+    /// Applies the eviction policy to a new inbound connection, evicting a less trusted inbound
+    /// peer to make room for it if needed. Returns whether the connection is accepted.
+    fn admit_inbound(&mut self, conn_metadata: &ConnectionMetadata) -> bool {
+        // Existing connections must pass through tie breaking
+        if self
+            .active_peers
+            .contains_key(&conn_metadata.remote_peer_id)
+        {
+            return true;
+        }
+        let policy = match &self.eviction_policy {
+            Some(policy) => policy,
+            None => return true,
+        };
+        let inbound: Vec<_> = self
+            .active_peers
+            .iter()
+            .filter(|(_, (metadata, _))| metadata.origin == ConnectionOrigin::Inbound)
+            .map(|(peer_id, (metadata, _))| (*peer_id, metadata.role))
+            .collect();
+
+        match policy.admit(conn_metadata.role, &inbound, self.inbound_connection_limit) {
+            InboundAdmission::Accept => true,
+            InboundAdmission::Evict(peer_id) => {
+                if let Some((evicted_metadata, sender)) = self.active_peers.remove(&peer_id) {
+                    info!(
+                        NetworkSchema::new(&self.network_context)
+                            .connection_metadata_with_address(&evicted_metadata),
+                        "{} Connection {} evicted for inbound {:?} peer {}",
+                        self.network_context,
+                        evicted_metadata,
+                        conn_metadata.role,
+                        conn_metadata.remote_peer_id.short_str(),
+                    );
+                    counters::connections_evicted(&self.network_context, evicted_metadata.role)
+                        .inc();
+                    // This triggers a disconnect.
+                    drop(sender);
+                }
+                true
+            }
+            InboundAdmission::Reject => {
+                info!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata_with_address(conn_metadata),
+                    "{} Connection rejected by the eviction policy: {}",
+                    self.network_context,
+                    conn_metadata
+                );
+                false
+            }
+        }
+    }
+
     fn disconnect(&mut self, connection: Connection<TSocket>) {
         let network_context = self.network_context.clone();
         let time_service = self.time_service.clone();
@@ -860,12 +958,14 @@ where
             self.transport_notifs_tx.clone(),
             peer_reqs_rx,
             peer_notifs_tx,
-            Duration::from_millis(constants::INBOUND_RPC_TIMEOUT_MS),
-            constants::MAX_CONCURRENT_INBOUND_RPCS,
-            constants::MAX_CONCURRENT_OUTBOUND_RPCS,
+            //////// 0L ////////
+            Duration::from_millis(self.multiplex.inbound_rpc_timeout_ms),
+            self.multiplex.max_concurrent_inbound_rpcs,
+            self.multiplex.max_concurrent_outbound_rpcs,
             self.max_frame_size,
             Some(inbound_rate_limiter),
             Some(outbound_rate_limiter),
+            self.multiplex.write_queue_size,
         );
         self.executor.spawn(peer.start());
 
@@ -1011,9 +1111,14 @@ where
     time_service: TimeService,
     /// [`Transport`] that is used to establish connections
     transport: TTransport,
-    listener: Fuse<TTransport::Listener>,
+    //////// 0L ////////
+    /// The listener of the listen address, merged with that of the dual stack address, if any
+    listener: Fuse<BoxStream<'static, <TTransport::Listener as Stream>::Item>>,
     transport_reqs_rx: channel::Receiver<TransportRequest>,
     transport_notifs_tx: channel::Sender<TransportNotification<TSocket>>,
+    //////// 0L ////////
+    /// Inbound connections are dropped once the node is draining
+    drain: Option<Arc<Drain>>,
 }
 
 impl<TTransport, TSocket> TransportHandler<TTransport, TSocket>
@@ -1029,9 +1134,11 @@ where
         time_service: TimeService,
         transport: TTransport,
         listen_addr: NetworkAddress,
+        dual_stack_addr: Option<NetworkAddress>,
         transport_reqs_rx: channel::Receiver<TransportRequest>,
         transport_notifs_tx: channel::Sender<TransportNotification<TSocket>>,
-    ) -> (Self, NetworkAddress) {
+        drain: Option<Arc<Drain>>, //////// 0L ////////
+    ) -> (Self, NetworkAddress, Option<NetworkAddress>) {
         let (listener, listen_addr) = transport
             .listen_on(listen_addr)
             .expect("Transport listen on fails");
@@ -1042,6 +1149,26 @@ where
             network_context,
             listen_addr
         );
+        //////// 0L ////////
+        let (listener, dual_stack_addr) = match dual_stack_addr {
+            Some(addr) => {
+                let (dual_stack_listener, addr) = transport
+                    .listen_on(addr)
+                    .expect("Transport listen on fails");
+                debug!(
+                    NetworkSchema::new(&network_context),
+                    listen_address = addr,
+                    "{} also listening on '{}'",
+                    network_context,
+                    addr
+                );
+                (
+                    stream::select(listener, dual_stack_listener).boxed(),
+                    Some(addr),
+                )
+            }
+            None => (listener.boxed(), None),
+        };
         (
             Self {
                 network_context,
@@ -1050,8 +1177,10 @@ where
                 listener: listener.fuse(),
                 transport_reqs_rx,
                 transport_notifs_tx,
+                drain,
             },
             listen_addr,
+            dual_stack_addr,
         )
     }
 
@@ -1073,6 +1202,16 @@ where
                 },
                 incoming_connection = self.listener.select_next_some() => {
                     match incoming_connection {
+                        //////// 0L ////////
+                        Ok((_, addr)) if self.is_draining() => {
+                            debug!(
+                                NetworkSchema::new(&self.network_context)
+                                    .network_address(&addr),
+                                "{} Dropping incoming connection from {}, the node is shutting down",
+                                self.network_context,
+                                addr
+                            );
+                        }
                         Ok((upgrade, addr)) => {
                             debug!(
                                 NetworkSchema::new(&self.network_context)
@@ -1118,6 +1257,13 @@ where
         );
     }
 
+    //////// 0L ////////
+    fn is_draining(&self) -> bool {
+        self.drain
+            .as_ref()
+            .map_or(false, |drain| drain.is_draining())
+    }
+
     fn dial_peer(
         &self,
         dial_peer_request: TransportRequest,
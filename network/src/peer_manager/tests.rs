@@ -20,7 +20,7 @@ use anyhow::anyhow;
 use bytes::Bytes;
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerRole, MAX_INBOUND_CONNECTIONS},
+    config::{MultiplexConfig, PeerRole, MAX_INBOUND_CONNECTIONS},
     network_id::NetworkContext,
 };
 use diem_infallible::RwLock;
@@ -114,6 +114,10 @@ fn build_test_peer_manager(
         MAX_INBOUND_CONNECTIONS,
         TokenBucketRateLimiter::open("inbound"),
         TokenBucketRateLimiter::open("outbound"),
+        None,
+        MultiplexConfig::default(),
+        None,
+        None,
     );
 
     (
@@ -0,0 +1,284 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The node wide table of peers, keyed by `PeerNetworkId`.
+//!
+//! Every network feeds the storage from the connection notifications of its peer manager, so
+//! components no longer keep their own peer tables from `NewPeer` and `LostPeer` events. Peers
+//! stay in the table once disconnected, with the time they were last seen, and components may
+//! subscribe to the changes of the table.
+
+use crate::{
+    peer_manager::{conn_notifs_channel, ConnectionNotification},
+    protocols::wire::handshake::v1::{MessagingProtocolVersion, ProtocolId},
+    transport::{ConnectionId, ConnectionMetadata},
+};
+use diem_config::{
+    config::{PeerNetworkId, PeerRole},
+    network_id::NodeNetworkId,
+};
+use diem_infallible::{Mutex, RwLock};
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::network_address::NetworkAddress;
+use futures::{channel::mpsc, stream::StreamExt};
+use netcore::transport::ConnectionOrigin;
+use serde::Serialize;
+use std::{collections::HashMap, convert::TryInto, time::Duration};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum PeerState {
+    Connected,
+    Disconnected,
+}
+
+/// What is known of a peer, from its latest connection
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PeerMetadata {
+    /// The addresses the peer advertises, or else the address of its first connection
+    pub addresses: Vec<NetworkAddress>,
+    pub role: PeerRole,
+    /// The application protocols negotiated with the peer
+    pub protocols: Vec<ProtocolId>,
+    /// The messaging protocol version negotiated with the peer
+    pub messaging_protocol: MessagingProtocolVersion,
+    pub origin: ConnectionOrigin,
+    pub connection_id: ConnectionId,
+    pub state: PeerState,
+    /// Unix time the latest connection to the peer was established
+    pub connected_since: Duration,
+    /// Unix time the peer was last connected, or heard from
+    pub last_seen: Duration,
+}
+
+impl PeerMetadata {
+    fn new(connection: &ConnectionMetadata, now: Duration) -> Self {
+        Self {
+            addresses: vec![connection.addr.clone()],
+            role: connection.role,
+            protocols: connection
+                .application_protocols
+                .clone()
+                .try_into()
+                .unwrap_or_default(),
+            messaging_protocol: connection.messaging_protocol,
+            origin: connection.origin,
+            connection_id: connection.connection_id,
+            state: PeerState::Connected,
+            connected_since: now,
+            last_seen: now,
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state == PeerState::Connected
+    }
+
+    pub fn supports(&self, protocol: ProtocolId) -> bool {
+        self.protocols.contains(&protocol)
+    }
+
+    /// How long the peer has been connected, at unix time `now`
+    pub fn uptime(&self, now: Duration) -> Duration {
+        if self.is_connected() {
+            now.saturating_sub(self.connected_since)
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+}
+
+/// A change of the peer table, sent to the subscribers
+#[derive(Clone, Debug, PartialEq)]
+pub enum PeerMetadataEvent {
+    Connected(PeerNetworkId, PeerMetadata),
+    Disconnected(PeerNetworkId, PeerMetadata),
+    Updated(PeerNetworkId, PeerMetadata),
+}
+
+pub struct PeerMetadataStorage {
+    time_service: TimeService,
+    peers: RwLock<HashMap<PeerNetworkId, PeerMetadata>>,
+    subscribers: Mutex<Vec<mpsc::UnboundedSender<PeerMetadataEvent>>>,
+}
+
+impl PeerMetadataStorage {
+    pub fn new(time_service: TimeService) -> Self {
+        Self {
+            time_service,
+            peers: RwLock::new(HashMap::new()),
+            subscribers: Mutex::new(vec![]),
+        }
+    }
+
+    /// Changes of the peer table from now on. Subscribers are dropped once their receiver is.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<PeerMetadataEvent> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    pub fn get(&self, peer: &PeerNetworkId) -> Option<PeerMetadata> {
+        self.peers.read().get(peer).cloned()
+    }
+
+    /// Every peer known to the node, connected or not
+    pub fn peers(&self) -> HashMap<PeerNetworkId, PeerMetadata> {
+        self.peers.read().clone()
+    }
+
+    /// The connected peers of a network
+    pub fn connected_peers(&self, network: &NodeNetworkId) -> Vec<(PeerNetworkId, PeerMetadata)> {
+        self.filter(|peer, metadata| &peer.network_id() == network && metadata.is_connected())
+    }
+
+    /// The connected peers of every network supporting `protocol`
+    pub fn connected_peers_with_protocol(
+        &self,
+        protocol: ProtocolId,
+    ) -> Vec<(PeerNetworkId, PeerMetadata)> {
+        self.filter(|_, metadata| metadata.is_connected() && metadata.supports(protocol))
+    }
+
+    fn filter(
+        &self,
+        predicate: impl Fn(&PeerNetworkId, &PeerMetadata) -> bool,
+    ) -> Vec<(PeerNetworkId, PeerMetadata)> {
+        self.peers
+            .read()
+            .iter()
+            .filter(|(peer, metadata)| predicate(peer, metadata))
+            .map(|(peer, metadata)| (peer.clone(), metadata.clone()))
+            .collect()
+    }
+
+    pub fn insert_connection(&self, network: NodeNetworkId, connection: &ConnectionMetadata) {
+        let peer = PeerNetworkId(network, connection.remote_peer_id);
+        let mut metadata = PeerMetadata::new(connection, self.time_service.now_unix_time());
+        let mut peers = self.peers.write();
+        // Addresses advertised through discovery outlive the connection
+        if let Some(previous) = peers.get(&peer) {
+            metadata.addresses = previous.addresses.clone();
+        }
+        peers.insert(peer.clone(), metadata.clone());
+        drop(peers);
+        self.notify(PeerMetadataEvent::Connected(peer, metadata));
+    }
+
+    /// Marks the peer disconnected, unless it already reconnected with another connection
+    pub fn remove_connection(&self, peer: &PeerNetworkId, connection_id: ConnectionId) {
+        let metadata = {
+            let mut peers = self.peers.write();
+            match peers.get_mut(peer) {
+                Some(metadata) if metadata.connection_id == connection_id => {
+                    metadata.state = PeerState::Disconnected;
+                    metadata.last_seen = self.time_service.now_unix_time();
+                    metadata.clone()
+                }
+                _ => return,
+            }
+        };
+        self.notify(PeerMetadataEvent::Disconnected(peer.clone(), metadata));
+    }
+
+    /// Records the peer was heard from, e.g. a health check or a response
+    pub fn mark_seen(&self, peer: &PeerNetworkId) {
+        if let Some(metadata) = self.peers.write().get_mut(peer) {
+            metadata.last_seen = self.time_service.now_unix_time();
+        }
+    }
+
+    /// Sets the addresses the peer advertises, e.g. from on chain discovery
+    pub fn set_addresses(&self, peer: &PeerNetworkId, addresses: Vec<NetworkAddress>) {
+        let metadata = {
+            let mut peers = self.peers.write();
+            match peers.get_mut(peer) {
+                Some(metadata) if !addresses.is_empty() => {
+                    metadata.addresses = addresses;
+                    metadata.clone()
+                }
+                _ => return,
+            }
+        };
+        self.notify(PeerMetadataEvent::Updated(peer.clone(), metadata));
+    }
+
+    fn notify(&self, event: PeerMetadataEvent) {
+        self.subscribers
+            .lock()
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+
+    /// Updates the storage from the connection notifications of a network, until its peer
+    /// manager stops
+    pub async fn listen(&self, network: NodeNetworkId, mut notifs: conn_notifs_channel::Receiver) {
+        while let Some(notif) = notifs.next().await {
+            match notif {
+                ConnectionNotification::NewPeer(connection, _) => {
+                    self.insert_connection(network.clone(), &connection)
+                }
+                ConnectionNotification::LostPeer(connection, _, _) => self.remove_connection(
+                    &PeerNetworkId(network.clone(), connection.remote_peer_id),
+                    connection.connection_id,
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::network_id::NetworkId;
+    use diem_types::PeerId;
+
+    #[test]
+    fn test_peer_metadata_storage() {
+        let time_service = TimeService::mock();
+        let storage = PeerMetadataStorage::new(time_service.clone());
+        let mut events = storage.subscribe();
+        let network = NodeNetworkId::new(NetworkId::Public, 0);
+        let mut connection = ConnectionMetadata::mock_with_role_and_origin(
+            PeerId::random(),
+            PeerRole::Known,
+            ConnectionOrigin::Outbound,
+        );
+        let peer = PeerNetworkId(network.clone(), connection.remote_peer_id);
+
+        storage.insert_connection(network.clone(), &connection);
+        assert_eq!(storage.connected_peers(&network).len(), 1);
+        assert!(storage
+            .connected_peers(&NodeNetworkId::new(NetworkId::Public, 1))
+            .is_empty());
+        assert!(matches!(
+            events.try_next().unwrap(),
+            Some(PeerMetadataEvent::Connected(_, _))
+        ));
+
+        // A lost connection that was already replaced leaves the peer connected
+        let replaced = connection.connection_id;
+        connection.connection_id = ConnectionId::from(1);
+        storage.insert_connection(network.clone(), &connection);
+        storage.remove_connection(&peer, replaced);
+        assert!(storage.get(&peer).unwrap().is_connected());
+
+        time_service.into_mock().advance(Duration::from_secs(10));
+        let metadata = storage.get(&peer).unwrap();
+        assert_eq!(metadata.connected_since, Duration::from_secs(0));
+        assert_eq!(
+            metadata.uptime(Duration::from_secs(10)),
+            Duration::from_secs(10)
+        );
+        storage.remove_connection(&peer, connection.connection_id);
+        let metadata = storage.get(&peer).unwrap();
+        assert_eq!(
+            metadata.uptime(Duration::from_secs(10)),
+            Duration::from_secs(0)
+        );
+        assert_eq!(metadata.state, PeerState::Disconnected);
+        assert_eq!(metadata.role, PeerRole::Known);
+        assert_eq!(metadata.last_seen, Duration::from_secs(10));
+        assert!(storage.connected_peers(&network).is_empty());
+        assert_eq!(storage.peers().len(), 1);
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The reputation of peers, keyed by `PeerNetworkId`, kept in a secure storage backend so that it
+//! survives restarts.
+//!
+//! Components report the misbehaviors of peers: protocol violations, invalid state sync chunks,
+//! mempool spam. A peer misbehaving `violations_per_ban` times within the violation window is
+//! banned, each ban lasting twice the previous one up to `max_ban_secs`. Every network
+//! disconnects the banned peers as soon as they connect.
+
+use crate::peer_manager::{conn_notifs_channel, ConnectionNotification, ConnectionRequestSender};
+use diem_config::{
+    config::{PeerNetworkId, PeerReputationConfig, SecureBackend},
+    network_id::NodeNetworkId,
+};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use diem_secure_storage::{Error, KVStorage, Storage};
+use diem_time_service::{TimeService, TimeServiceTrait};
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The key of the reputations in the storage backend
+pub const PEER_REPUTATION: &str = "peer_reputation";
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Misbehavior {
+    /// A message breaking the protocol it was sent on
+    ProtocolViolation,
+    /// A state sync chunk that doesn't verify
+    InvalidChunk,
+    /// A mempool broadcast of transactions that are all rejected
+    MempoolSpam,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PeerReputation {
+    /// Misbehaviors within the violation window, since the last ban
+    pub violations: u32,
+    pub last_misbehavior: Option<Misbehavior>,
+    /// In seconds since the unix epoch
+    pub last_misbehavior_secs: u64,
+    /// Bans so far, each doubling the next
+    pub bans: u32,
+    /// In seconds since the unix epoch
+    pub banned_until_secs: u64,
+}
+
+pub struct PeerReputationStore {
+    config: PeerReputationConfig,
+    time_service: TimeService,
+    storage: Mutex<Storage>,
+    peers: Mutex<HashMap<PeerNetworkId, PeerReputation>>,
+}
+
+impl PeerReputationStore {
+    /// Opens the store of the backend of `config`, with the reputations it kept
+    pub fn new(config: &PeerReputationConfig, time_service: TimeService) -> Self {
+        let storage = Storage::from(&config.backend);
+        let peers = match storage.get::<Vec<(PeerNetworkId, PeerReputation)>>(PEER_REPUTATION) {
+            Ok(response) => response.value.into_iter().collect(),
+            Err(Error::KeyNotSet(_)) => HashMap::new(),
+            Err(e) => {
+                error!(
+                    "Unable to read the peer reputations, starting afresh: {}",
+                    e
+                );
+                HashMap::new()
+            }
+        };
+        Self {
+            config: config.clone(),
+            time_service,
+            storage: Mutex::new(storage),
+            peers: Mutex::new(peers),
+        }
+    }
+
+    /// A store forgetting the reputations on restart, e.g. for tests
+    pub fn in_memory() -> Self {
+        let config = PeerReputationConfig {
+            backend: SecureBackend::InMemoryStorage,
+            ..Default::default()
+        };
+        Self::new(&config, TimeService::real())
+    }
+
+    /// Records a misbehavior of `peer`, banning it if that's one too many. Returns whether the
+    /// peer got banned.
+    pub fn report(&self, peer: &PeerNetworkId, misbehavior: Misbehavior) -> bool {
+        let now = self.time_service.now_secs();
+        let mut peers = self.peers.lock();
+        let reputation = peers.entry(peer.clone()).or_default();
+        if now.saturating_sub(reputation.last_misbehavior_secs) > self.config.violation_window_secs
+        {
+            reputation.violations = 0;
+        }
+        reputation.violations += 1;
+        reputation.last_misbehavior = Some(misbehavior);
+        reputation.last_misbehavior_secs = now;
+
+        let banned = reputation.violations >= self.config.violations_per_ban;
+        if banned {
+            let ban_secs = self
+                .config
+                .base_ban_secs
+                .checked_shl(reputation.bans)
+                .unwrap_or(u64::MAX)
+                .min(self.config.max_ban_secs);
+            reputation.violations = 0;
+            reputation.bans += 1;
+            reputation.banned_until_secs = now.saturating_add(ban_secs);
+            warn!(
+                "Banned peer {} for {}s after its last {:?}",
+                peer, ban_secs, misbehavior
+            );
+        }
+        self.persist(&peers);
+        banned
+    }
+
+    pub fn is_banned(&self, peer: &PeerNetworkId) -> bool {
+        let now = self.time_service.now_secs();
+        self.peers
+            .lock()
+            .get(peer)
+            .map_or(false, |reputation| reputation.banned_until_secs > now)
+    }
+
+    /// Every peer that ever misbehaved
+    pub fn reputations(&self) -> HashMap<PeerNetworkId, PeerReputation> {
+        self.peers.lock().clone()
+    }
+
+    /// Forgets the reputation of `peer`, or of every peer, lifting their bans
+    pub fn clear(&self, peer: Option<&PeerNetworkId>) {
+        let mut peers = self.peers.lock();
+        match peer {
+            Some(peer) => {
+                peers.remove(peer);
+            }
+            None => peers.clear(),
+        }
+        self.persist(&peers);
+    }
+
+    fn persist(&self, peers: &HashMap<PeerNetworkId, PeerReputation>) {
+        let peers: Vec<_> = peers.iter().collect();
+        if let Err(e) = self.storage.lock().set(PEER_REPUTATION, peers) {
+            error!("Unable to persist the peer reputations: {}", e);
+        }
+    }
+
+    /// Disconnects the banned peers of a network as they connect, until its peer manager stops
+    pub async fn enforce(
+        &self,
+        network: NodeNetworkId,
+        mut notifs: conn_notifs_channel::Receiver,
+        mut conn_reqs: ConnectionRequestSender,
+    ) {
+        while let Some(notif) = notifs.next().await {
+            if let ConnectionNotification::NewPeer(connection, _) = notif {
+                let peer = PeerNetworkId(network.clone(), connection.remote_peer_id);
+                if self.is_banned(&peer) {
+                    info!("Disconnecting banned peer {}", peer);
+                    if let Err(e) = conn_reqs.disconnect_peer(connection.remote_peer_id).await {
+                        warn!("Unable to disconnect banned peer {}: {}", peer, e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::{config::OnDiskStorageConfig, network_id::NetworkId};
+    use diem_temppath::TempPath;
+    use diem_types::PeerId;
+
+    #[test]
+    fn test_peer_reputation_store() {
+        let path = TempPath::new();
+        let mut storage = OnDiskStorageConfig::default();
+        storage.path = path.path().to_path_buf();
+        let config = PeerReputationConfig {
+            backend: SecureBackend::OnDiskStorage(storage),
+            violations_per_ban: 2,
+            violation_window_secs: 100,
+            base_ban_secs: 10,
+            max_ban_secs: 15,
+        };
+        let time_service = TimeService::mock();
+        let store = PeerReputationStore::new(&config, time_service.clone());
+        let peer = PeerNetworkId(NodeNetworkId::new(NetworkId::Public, 0), PeerId::random());
+        let advance = |secs| time_service.clone().into_mock().advance_secs(secs);
+
+        // Misbehaviors out of the window are forgotten
+        assert!(!store.report(&peer, Misbehavior::InvalidChunk));
+        advance(101);
+        assert!(!store.report(&peer, Misbehavior::InvalidChunk));
+        assert!(store.report(&peer, Misbehavior::MempoolSpam));
+        assert!(store.is_banned(&peer));
+        advance(10);
+        assert!(!store.is_banned(&peer));
+
+        // The next ban is longer, up to the longest
+        store.report(&peer, Misbehavior::ProtocolViolation);
+        assert!(store.report(&peer, Misbehavior::ProtocolViolation));
+        advance(14);
+        assert!(store.is_banned(&peer));
+        assert_eq!(store.reputations()[&peer].bans, 2);
+
+        // The reputations survive a restart, until cleared
+        let store = PeerReputationStore::new(&config, time_service.clone());
+        assert!(store.is_banned(&peer));
+        store.clear(Some(&peer));
+        let store = PeerReputationStore::new(&config, time_service);
+        assert!(!store.is_banned(&peer));
+        assert!(store.reputations().is_empty());
+    }
+}
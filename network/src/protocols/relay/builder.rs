@@ -0,0 +1,50 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::{
+    connectivity_manager::ConnectivityRequest,
+    protocols::relay::{Relay, RelayNetworkEvents, RelayNetworkSender, SharedAddresses},
+};
+use diem_config::{
+    config::{RelayDiscoveryConfig, RendezvousConfig},
+    network_id::NetworkContext,
+};
+use diem_time_service::TimeService;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+pub struct RelayBuilder {
+    service: Option<Relay>,
+}
+
+impl RelayBuilder {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        time_service: TimeService,
+        rendezvous: Option<RendezvousConfig>,
+        discovery: Option<(RelayDiscoveryConfig, channel::Sender<ConnectivityRequest>)>,
+        network_tx: RelayNetworkSender,
+        network_rx: RelayNetworkEvents,
+        advertised: SharedAddresses,
+    ) -> Self {
+        let service = Relay::new(
+            network_context,
+            time_service,
+            network_tx,
+            network_rx,
+            rendezvous,
+            discovery,
+            advertised,
+        );
+        Self {
+            service: Some(service),
+        }
+    }
+
+    pub fn start(&mut self, executor: &Handle) {
+        if let Some(service) = self.service.take() {
+            executor.spawn(service.start());
+        }
+    }
+}
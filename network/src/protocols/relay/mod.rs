@@ -0,0 +1,541 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Protocol used by fullnodes behind a NAT to find each other through rendezvous peers
+//!
+//! A node with `DiscoveryMethod::Relay` dials its rendezvous peers as seeds, and registers with
+//! each connected one every refresh interval: it sends the addresses it is dialable at, if any,
+//! along with the `advertised_address` of its network, and receives the dialable peers registered by others, along with the address the rendezvous
+//! sees its connection from. The peers go to the connectivity manager under
+//! `DiscoverySource::Relay`, the observed address is logged for the operator to forward.
+//!
+//! A node with a `RendezvousConfig` keeps the registrations of its peers until they expire, and
+//! drops the oldest beyond `max_registrations`.
+use crate::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    constants::NETWORK_CHANNEL_SIZE,
+    counters,
+    logging::NetworkSchema,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::{
+        network::{Event, NetworkEvents, NetworkSender, NewNetworkSender},
+        rpc::error::RpcError,
+    },
+    ProtocolId,
+};
+use bytes::Bytes;
+use channel::message_queues::QueueStyle;
+use diem_config::{
+    config::{Peer, PeerRole, PeerSet, RelayDiscoveryConfig, RendezvousConfig},
+    network_id::NetworkContext,
+};
+use diem_infallible::RwLock;
+use diem_logger::prelude::*;
+use diem_metrics::IntCounterVec;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::{network_address::NetworkAddress, PeerId};
+use futures::{
+    channel::oneshot,
+    sink::SinkExt,
+    stream::{FuturesUnordered, StreamExt},
+};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+pub mod builder;
+
+/// Time a rendezvous peer has to answer a registration
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time between expiries of registrations, on a rendezvous peer without relay discovery
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The addresses the node is dialable at, from the `advertised_address` of its network, updated as
+/// the external address is found again. A dual-stack network advertises one address per ip version
+pub type SharedAddresses = Arc<RwLock<Vec<NetworkAddress>>>;
+
+/// The interface from Network to the Relay layer.
+pub type RelayNetworkEvents = NetworkEvents<RelayMsg>;
+
+/// The interface from the Relay to Networking layer, a thin wrapper around a
+/// `NetworkSender<RelayMsg>`.
+#[derive(Clone)]
+pub struct RelayNetworkSender {
+    inner: NetworkSender<RelayMsg>,
+}
+
+/// Configuration for the network endpoints to support the Relay.
+pub fn network_endpoint_config() -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![ProtocolId::RelayRpc],
+        vec![],
+        QueueStyle::LIFO,
+        NETWORK_CHANNEL_SIZE,
+        Some(&counters::PENDING_RELAY_NETWORK_EVENTS),
+    )
+}
+
+impl NewNetworkSender for RelayNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl RelayNetworkSender {
+    /// Send a Relay RPC request to the rendezvous peer `recipient`, returning its reply.
+    pub async fn send_rpc(
+        &mut self,
+        recipient: PeerId,
+        req_msg: RelayMsg,
+        timeout: Duration,
+    ) -> Result<RelayMsg, RpcError> {
+        let protocol = ProtocolId::RelayRpc;
+        self.inner
+            .send_rpc(recipient, protocol, req_msg, timeout)
+            .await
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum RelayMsg {
+    /// Registers the sender with a rendezvous peer, with the addresses it is dialable at, if any
+    Register(Vec<NetworkAddress>),
+    /// The reply to a registration
+    Peers {
+        /// The address of the connection of the registered peer, as seen by the rendezvous
+        observed: Option<NetworkAddress>,
+        /// The dialable peers registered by others
+        peers: BTreeMap<PeerId, Vec<NetworkAddress>>,
+    },
+}
+
+/// The registrations kept by a rendezvous peer
+pub struct Registrations {
+    config: RendezvousConfig,
+    /// The dialable addresses of each registered peer, and the time its registration expires
+    entries: HashMap<PeerId, (Vec<NetworkAddress>, Duration)>,
+}
+
+impl Registrations {
+    pub fn new(config: RendezvousConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drops the registrations expired at `now`
+    pub fn expire(&mut self, now: Duration) {
+        self.entries.retain(|_, (_, expires)| *expires > now);
+    }
+
+    /// Registers `peer_id` at `now`, returning the dialable peers registered by others. Only
+    /// diemnet addresses are kept, a peer cannot be dialed at any other.
+    pub fn register(
+        &mut self,
+        peer_id: PeerId,
+        addresses: Vec<NetworkAddress>,
+        now: Duration,
+    ) -> BTreeMap<PeerId, Vec<NetworkAddress>> {
+        self.expire(now);
+        let addresses = addresses
+            .into_iter()
+            .filter(NetworkAddress::is_diemnet_addr)
+            .collect();
+        let expires = now + Duration::from_secs(self.config.registration_ttl_secs);
+        self.entries.insert(peer_id, (addresses, expires));
+        while self.entries.len() > self.config.max_registrations {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, expires))| *expires)
+                .map(|(peer_id, _)| *peer_id);
+            match oldest {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        self.entries
+            .iter()
+            .filter(|(other, (addresses, _))| **other != peer_id && !addresses.is_empty())
+            .map(|(other, (addresses, _))| (*other, addresses.clone()))
+            .collect()
+    }
+}
+
+/// The actor registering with rendezvous peers, and serving registrations if a rendezvous peer
+pub struct Relay {
+    network_context: Arc<NetworkContext>,
+    time_service: TimeService,
+    network_tx: RelayNetworkSender,
+    network_rx: RelayNetworkEvents,
+    /// The registrations served, if a rendezvous peer
+    registrations: Option<Registrations>,
+    /// Relay discovery, and the channel to the connectivity manager the peers found go to
+    discovery: Option<(RelayDiscoveryConfig, channel::Sender<ConnectivityRequest>)>,
+    /// The address of the connection of each connected peer
+    connected: HashMap<PeerId, NetworkAddress>,
+    /// The dialable peers returned by each rendezvous peer
+    discovered: HashMap<PeerId, BTreeMap<PeerId, Vec<NetworkAddress>>>,
+    /// The peers last sent to the connectivity manager
+    known: Option<PeerSet>,
+    /// The last address a rendezvous peer observed us at
+    observed: Option<NetworkAddress>,
+    /// The advertised addresses of the network, registered along with the configured ones
+    advertised: SharedAddresses,
+}
+
+impl Relay {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        time_service: TimeService,
+        network_tx: RelayNetworkSender,
+        network_rx: RelayNetworkEvents,
+        rendezvous: Option<RendezvousConfig>,
+        discovery: Option<(RelayDiscoveryConfig, channel::Sender<ConnectivityRequest>)>,
+        advertised: SharedAddresses,
+    ) -> Self {
+        Self {
+            network_context,
+            time_service,
+            network_tx,
+            network_rx,
+            registrations: rendezvous.map(Registrations::new),
+            discovery,
+            connected: HashMap::new(),
+            discovered: HashMap::new(),
+            known: None,
+            observed: None,
+            advertised,
+        }
+    }
+
+    pub async fn start(mut self) {
+        let mut registrations = FuturesUnordered::new();
+        info!(
+            NetworkSchema::new(&self.network_context),
+            "{} Relay actor started", self.network_context
+        );
+
+        let interval = match &self.discovery {
+            Some((config, _)) => Duration::from_secs(config.refresh_interval_secs),
+            None => EXPIRY_INTERVAL,
+        };
+        let ticker = self.time_service.interval(interval);
+        tokio::pin!(ticker);
+
+        loop {
+            futures::select! {
+                maybe_event = self.network_rx.next() => {
+                    // Shutdown the Relay when this network instance shuts down
+                    let event = match maybe_event {
+                        Some(event) => event,
+                        None => break,
+                    };
+
+                    match event {
+                        Event::NewPeer(metadata) => {
+                            self.connected.insert(metadata.remote_peer_id, metadata.addr.clone());
+                        }
+                        Event::LostPeer(metadata) => {
+                            self.connected.remove(&metadata.remote_peer_id);
+                        }
+                        Event::RpcRequest(peer_id, RelayMsg::Register(addresses), res_tx) => {
+                            self.handle_register_request(peer_id, addresses, res_tx);
+                        }
+                        Event::RpcRequest(peer_id, msg, _) => {
+                            warn!(
+                                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                                "{} Unexpected relay RPC message from {}: {:?}",
+                                self.network_context,
+                                peer_id,
+                                msg
+                            );
+                        }
+                        Event::Message(peer_id, msg) => {
+                            warn!(
+                                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                                "{} Unexpected relay direct send from {}: {:?}",
+                                self.network_context,
+                                peer_id,
+                                msg
+                            );
+                        }
+                    }
+                }
+                _ = ticker.select_next_some() => {
+                    if let Some(registrations) = &mut self.registrations {
+                        registrations.expire(self.time_service.now_unix_time());
+                    }
+                    if let Some((config, _)) = &self.discovery {
+                        let addresses = registered_addresses(config, &self.advertised);
+                        for peer_id in config.rendezvous.keys() {
+                            if self.connected.contains_key(peer_id) {
+                                registrations.push(Self::register(
+                                    self.network_tx.clone(),
+                                    *peer_id,
+                                    addresses.clone(),
+                                ));
+                            }
+                        }
+                    }
+                }
+                res = registrations.select_next_some() => {
+                    let (peer_id, result) = res;
+                    if !self.handle_register_response(peer_id, result).await {
+                        break;
+                    }
+                }
+            }
+        }
+        warn!(
+            NetworkSchema::new(&self.network_context),
+            "{} Relay actor terminated", self.network_context
+        );
+    }
+
+    fn handle_register_request(
+        &mut self,
+        peer_id: PeerId,
+        addresses: Vec<NetworkAddress>,
+        res_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        let registrations = match &mut self.registrations {
+            Some(registrations) => registrations,
+            None => {
+                // Dropping the response channel fails the request of the peer
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    "{} Registration from {} ignored, not a rendezvous peer",
+                    self.network_context,
+                    peer_id.short_str()
+                );
+                return;
+            }
+        };
+        let peers = registrations.register(peer_id, addresses, self.time_service.now_unix_time());
+        let reply = RelayMsg::Peers {
+            observed: self.connected.get(&peer_id).cloned(),
+            peers,
+        };
+        match bcs::to_bytes(&reply) {
+            Ok(message) => {
+                let _ = res_tx.send(Ok(message.into()));
+            }
+            Err(e) => warn!(
+                NetworkSchema::new(&self.network_context),
+                error = ?e,
+                "{} Unable to serialize relay peers: {}", self.network_context, e
+            ),
+        }
+    }
+
+    /// Returns false once the connectivity manager is gone
+    async fn handle_register_response(
+        &mut self,
+        peer_id: PeerId,
+        result: Result<RelayMsg, RpcError>,
+    ) -> bool {
+        match result {
+            Ok(RelayMsg::Peers { observed, peers }) => {
+                if observed.is_some() && observed != self.observed {
+                    info!(
+                        NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                        "{} Rendezvous {} observes this node at {:?}",
+                        self.network_context,
+                        peer_id.short_str(),
+                        observed
+                    );
+                    self.observed = observed;
+                }
+                self.discovered.insert(peer_id, peers);
+            }
+            Ok(msg) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    "{} Unexpected relay reply from {}: {:?}", self.network_context, peer_id, msg
+                );
+                return true;
+            }
+            Err(e) => {
+                // The peers found before stay, they may still be dialable
+                warn!(
+                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                    error = ?e,
+                    "{} Registration with rendezvous {} failed: {}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    e
+                );
+                return true;
+            }
+        }
+
+        let mut addresses: HashMap<PeerId, Vec<NetworkAddress>> = HashMap::new();
+        for (peer_id, peer_addresses) in self.discovered.values().flatten() {
+            let known = addresses.entry(*peer_id).or_default();
+            for address in peer_addresses {
+                if !known.contains(address) {
+                    known.push(address.clone());
+                }
+            }
+        }
+        let peers: PeerSet = addresses
+            .into_iter()
+            .map(|(peer_id, addresses)| {
+                (
+                    peer_id,
+                    Peer::from_addrs(PeerRole::ValidatorFullNode, addresses),
+                )
+            })
+            .collect();
+        if self.known.as_ref() == Some(&peers) {
+            return true;
+        }
+
+        let conn_mgr_reqs_tx = match &mut self.discovery {
+            Some((_, conn_mgr_reqs_tx)) => conn_mgr_reqs_tx,
+            None => return true,
+        };
+        let request =
+            ConnectivityRequest::UpdateDiscoveredPeers(DiscoverySource::Relay, peers.clone());
+        if let Err(e) = conn_mgr_reqs_tx.send(request).await {
+            warn!(
+                NetworkSchema::new(&self.network_context),
+                "{} Relay discovery stopped, the connectivity manager is gone: {}",
+                self.network_context,
+                e
+            );
+            return false;
+        }
+        self.known = Some(peers);
+        true
+    }
+
+    async fn register(
+        mut network_tx: RelayNetworkSender,
+        peer_id: PeerId,
+        addresses: Vec<NetworkAddress>,
+    ) -> (PeerId, Result<RelayMsg, RpcError>) {
+        let result = network_tx
+            .send_rpc(peer_id, RelayMsg::Register(addresses), REGISTER_TIMEOUT)
+            .await;
+        (peer_id, result)
+    }
+}
+
+/// The addresses registered with the rendezvous peers: the configured ones, and the advertised
+/// addresses of the network once known
+fn registered_addresses(
+    config: &RelayDiscoveryConfig,
+    advertised: &SharedAddresses,
+) -> Vec<NetworkAddress> {
+    let mut addresses = config.advertised_addresses.clone();
+    for address in advertised.read().iter() {
+        if !addresses.contains(address) {
+            addresses.push(address.clone());
+        }
+    }
+    addresses
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_registrations() {
+        let mut registrations = Registrations::new(RendezvousConfig {
+            max_registrations: 2,
+            registration_ttl_secs: 10,
+        });
+        let dialable: NetworkAddress = "/ip4/1.2.3.4/tcp/6180/ln-noise-ik/080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120/ln-handshake/0".parse().unwrap();
+        let (a, b, c) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        assert!(registrations
+            .register(a, vec![dialable.clone()], Duration::from_secs(0))
+            .is_empty());
+        // Addresses that cannot be dialed are not kept
+        let peers = registrations.register(
+            b,
+            vec!["/ip4/1.2.3.4/tcp/6180".parse().unwrap()],
+            Duration::from_secs(1),
+        );
+        assert_eq!(
+            peers.into_iter().collect::<Vec<_>>(),
+            vec![(a, vec![dialable.clone()])]
+        );
+
+        // Beyond the limit the oldest registration is dropped
+        let peers = registrations.register(c, vec![], Duration::from_secs(2));
+        assert!(peers.is_empty());
+        assert_eq!(registrations.len(), 2);
+
+        // Registrations expire unless renewed
+        registrations.register(c, vec![dialable], Duration::from_secs(5));
+        registrations.expire(Duration::from_secs(11));
+        assert_eq!(registrations.len(), 1);
+        registrations.expire(Duration::from_secs(15));
+        assert!(registrations.is_empty());
+    }
+
+    #[test]
+    fn test_registered_addresses() {
+        let configured: NetworkAddress = "/ip4/1.2.3.4/tcp/6180/ln-noise-ik/080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120/ln-handshake/0".parse().unwrap();
+        let config = RelayDiscoveryConfig {
+            advertised_addresses: vec![configured.clone()],
+            ..RelayDiscoveryConfig::default()
+        };
+        let advertised = SharedAddresses::default();
+        assert_eq!(
+            registered_addresses(&config, &advertised),
+            vec![configured.clone()]
+        );
+
+        // The advertised address is added once found, and not twice
+        let external: NetworkAddress = "/ip4/203.0.113.7/tcp/6180/ln-noise-ik/080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120/ln-handshake/0".parse().unwrap();
+        *advertised.write() = vec![external.clone()];
+        assert_eq!(
+            registered_addresses(&config, &advertised),
+            vec![configured.clone(), external.clone()]
+        );
+        *advertised.write() = vec![configured.clone()];
+        assert_eq!(
+            registered_addresses(&config, &advertised),
+            vec![configured.clone()]
+        );
+
+        // Both addresses of a dual-stack network are registered
+        let external6: NetworkAddress = "/ip6/2001:db8::7/tcp/6180/ln-noise-ik/080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120/ln-handshake/0".parse().unwrap();
+        *advertised.write() = vec![external.clone(), external6.clone()];
+        assert_eq!(
+            registered_addresses(&config, &advertised),
+            vec![configured, external, external6]
+        );
+    }
+}
@@ -12,7 +12,7 @@
 //!
 //! [DiemNet Handshake v1 Specification]: https://github.com/diem/diem/blob/main/specifications/network/handshake-v1.md
 
-use diem_config::network_id::NetworkId;
+use diem_config::{config::HandshakeEnforcement, network_id::NetworkId};
 use diem_types::chain_id::ChainId;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, convert::TryInto, fmt, iter::Iterator};
@@ -39,6 +39,7 @@ pub enum ProtocolId {
     StateSyncDirectSend = 3,
     DiscoveryDirectSend = 4,
     HealthCheckerRpc = 5,
+    RelayRpc = 6, //////// 0L ////////
 }
 
 impl ProtocolId {
@@ -51,6 +52,7 @@ impl ProtocolId {
             StateSyncDirectSend => "StateSyncDirectSend",
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
+            RelayRpc => "RelayRpc", //////// 0L ////////
         }
     }
 
@@ -62,6 +64,7 @@ impl ProtocolId {
             ProtocolId::StateSyncDirectSend,
             ProtocolId::DiscoveryDirectSend,
             ProtocolId::HealthCheckerRpc,
+            ProtocolId::RelayRpc, //////// 0L ////////
         ]
     }
 }
@@ -169,6 +172,18 @@ pub enum HandshakeError {
     NoCommonProtocols,
 }
 
+//////// 0L ////////
+impl HandshakeError {
+    /// The category of the failure, the `reason` label of the handshake failure counter
+    pub fn rejection_reason(&self) -> &'static str {
+        match self {
+            HandshakeError::InvalidChainId(_, _) => "wrong_chain_id",
+            HandshakeError::InvalidNetworkId(_, _) => "wrong_network_id",
+            HandshakeError::NoCommonProtocols => "protocol_mismatch",
+        }
+    }
+}
+
 /// The HandshakeMsg contains a mapping from [`MessagingProtocolVersion`]
 /// suppported by the node to a bit-vector specifying application-level protocols
 /// supported over that version.
@@ -202,9 +217,43 @@ impl HandshakeMsg {
         &self,
         other: &HandshakeMsg,
     ) -> Result<(MessagingProtocolVersion, SupportedProtocols), HandshakeError> {
+        self.perform_handshake_with(other, HandshakeEnforcement::Strict)
+            .map(|(messaging_protocol, application_protocols, _)| {
+                (messaging_protocol, application_protocols)
+            })
+    }
+
+    //////// 0L ////////
+    /// Like `perform_handshake`, but with `HandshakeEnforcement::WarnOnly` a peer of another chain
+    /// or network is accepted, the mismatches it was accepted despite returned along with the
+    /// negotiated protocols
+    pub fn perform_handshake_with(
+        &self,
+        other: &HandshakeMsg,
+        enforcement: HandshakeEnforcement,
+    ) -> Result<
+        (
+            MessagingProtocolVersion,
+            SupportedProtocols,
+            Vec<HandshakeError>,
+        ),
+        HandshakeError,
+    > {
+        let mut mismatches = self.mismatches(other);
+        if enforcement == HandshakeEnforcement::Strict && !mismatches.is_empty() {
+            return Err(mismatches.remove(0));
+        }
+        let (messaging_protocol, application_protocols) = self.common_protocols(other)?;
+        Ok((messaging_protocol, application_protocols, mismatches))
+    }
+
+    /// The differences of chain and network between both HandshakeMsg
+    fn mismatches(&self, other: &HandshakeMsg) -> Vec<HandshakeError> {
+        let mut mismatches = vec![];
+
         // verify that both peers are on the same chain
         if self.chain_id != other.chain_id {
-            return Err(HandshakeError::InvalidChainId(
+            mismatches.push(HandshakeError::InvalidChainId(
                 other.chain_id,
                 self.chain_id,
             ));
@@ -212,12 +261,18 @@ impl HandshakeMsg {
 
         // verify that both peers are on the same type of network
         if self.network_id != other.network_id {
-            return Err(HandshakeError::InvalidNetworkId(
+            mismatches.push(HandshakeError::InvalidNetworkId(
                 other.network_id.clone(),
                 self.network_id.clone(),
             ));
         }
+        mismatches
+    }
 
+    fn common_protocols(
+        &self,
+        other: &HandshakeMsg,
+    ) -> Result<(MessagingProtocolVersion, SupportedProtocols), HandshakeError> {
         // first, find the highest MessagingProtocolVersion supported by both nodes.
         let mut inner = other.supported_protocols.iter().rev().peekable();
 
@@ -53,6 +53,34 @@ fn represents_same_network() {
     h1.perform_handshake(&h2).unwrap_err();
 }
 
+//////// 0L ////////
+#[test]
+fn warn_only_enforcement() {
+    let h1 = HandshakeMsg::new_for_testing();
+    let mut h2 = h1.clone();
+    h2.chain_id = ChainId::new(h1.chain_id.id() + 1);
+    h2.network_id = NetworkId::Public;
+
+    let error = h1
+        .perform_handshake_with(&h2, HandshakeEnforcement::Strict)
+        .unwrap_err();
+    assert_eq!(error.rejection_reason(), "wrong_chain_id");
+
+    // The peer is accepted, along with both of its mismatches
+    let (version, protocols, mismatches) = h1
+        .perform_handshake_with(&h2, HandshakeEnforcement::WarnOnly)
+        .unwrap();
+    assert_eq!(version, MessagingProtocolVersion::V1);
+    assert_eq!(protocols, h1.supported_protocols[&version]);
+    let reasons: Vec<_> = mismatches.iter().map(|e| e.rejection_reason()).collect();
+    assert_eq!(reasons, vec!["wrong_chain_id", "wrong_network_id"]);
+
+    // Protocols are always enforced
+    h2.supported_protocols.clear();
+    h1.perform_handshake_with(&h2, HandshakeEnforcement::WarnOnly)
+        .unwrap_err();
+}
+
 #[test]
 fn common_protocols() {
     let network_id = NetworkId::default();
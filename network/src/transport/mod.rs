@@ -2,15 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    counters,
     logging::NetworkSchema,
     noise::{stream::NoiseStream, AntiReplayTimestamps, HandshakeAuthMode, NoiseUpgrader},
     protocols::{
         identity::exchange_handshake,
-        wire::handshake::v1::{HandshakeMsg, MessagingProtocolVersion, SupportedProtocols},
+        wire::handshake::v1::{
+            HandshakeError, HandshakeMsg, MessagingProtocolVersion, SupportedProtocols,
+        },
     },
 };
 use diem_config::{
-    config::{PeerRole, HANDSHAKE_VERSION},
+    config::{HandshakeEnforcement, PeerAccess, PeerRole, HANDSHAKE_VERSION},
     network_id::{NetworkContext, NetworkId},
 };
 use diem_crypto::x25519;
@@ -18,7 +21,7 @@ use diem_logger::prelude::*;
 use diem_time_service::{timeout, TimeService, TimeServiceTrait};
 use diem_types::{
     chain_id::ChainId,
-    network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress},
+    network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, parse_onion_tcp, NetworkAddress},
     PeerId,
 };
 use futures::{
@@ -60,6 +63,11 @@ pub const DIEM_TCP_TRANSPORT: tcp::TcpTransport = tcp::TcpTransport {
     ttl: None,
     // Use TCP_NODELAY for diem tcp connections.
     nodelay: Some(true),
+    // Use the https_proxy of the environment, if any.
+    proxy: None,     //////// 0L ////////
+    tor_proxy: None, //////// 0L ////////
+    // Set for dual-stack networks only
+    ipv6_only: false, //////// 0L ////////
 };
 
 /// A trait alias for "socket-like" things.
@@ -203,6 +211,8 @@ pub struct UpgradeContext {
     supported_protocols: BTreeMap<MessagingProtocolVersion, SupportedProtocols>,
     chain_id: ChainId,
     network_id: NetworkId,
+    peer_access: PeerAccess,                     //////// 0L ////////
+    handshake_enforcement: HandshakeEnforcement, //////// 0L ////////
 }
 
 impl UpgradeContext {
@@ -212,6 +222,8 @@ impl UpgradeContext {
         supported_protocols: BTreeMap<MessagingProtocolVersion, SupportedProtocols>,
         chain_id: ChainId,
         network_id: NetworkId,
+        peer_access: PeerAccess,                     //////// 0L ////////
+        handshake_enforcement: HandshakeEnforcement, //////// 0L ////////
     ) -> Self {
         UpgradeContext {
             noise,
@@ -219,8 +231,86 @@ impl UpgradeContext {
             supported_protocols,
             chain_id,
             network_id,
+            peer_access,
+            handshake_enforcement,
         }
     }
+
+    //////// 0L ////////
+    /// Counts a failed upgrade, by the category of the failure
+    fn count_failure(&self, origin: ConnectionOrigin, reason: &str) {
+        counters::handshake_failures(&self.noise.network_context, origin, reason).inc();
+    }
+
+    /// Negotiates the protocols of the connection, warning of the chain and network mismatches the
+    /// handshake enforcement of the network lets through
+    fn negotiate(
+        &self,
+        origin: ConnectionOrigin,
+        handshake_msg: &HandshakeMsg,
+        remote_handshake: &HandshakeMsg,
+        remote_peer_id: PeerId,
+    ) -> Result<(MessagingProtocolVersion, SupportedProtocols), HandshakeError> {
+        let (messaging_protocol, application_protocols, mismatches) = handshake_msg
+            .perform_handshake_with(remote_handshake, self.handshake_enforcement)
+            .map_err(|err| {
+                self.count_failure(origin, err.rejection_reason());
+                err
+            })?;
+        for mismatch in mismatches {
+            counters::handshake_mismatches_tolerated(
+                &self.noise.network_context,
+                origin,
+                mismatch.rejection_reason(),
+            )
+            .inc();
+            sample!(
+                SampleRate::Duration(Duration::from_secs(15)),
+                warn!(
+                    NetworkSchema::new(&self.noise.network_context)
+                        .remote_peer(&remote_peer_id)
+                        .connection_origin(&origin),
+                    error = %mismatch,
+                    "Accepting peer {} despite the handshake mismatch: {}",
+                    remote_peer_id.short_str(),
+                    mismatch
+                )
+            );
+        }
+        Ok((messaging_protocol, application_protocols))
+    }
+
+    /// Refuses a peer the allow and deny lists of the network keep out. The allow list only locks
+    /// down inbound connections, a dialed peer is only refused if denied: seeds and peers dialed by
+    /// DNS name have no IP to match the list against.
+    fn check_access(
+        &self,
+        origin: ConnectionOrigin,
+        peer_id: Option<PeerId>,
+        addr: &NetworkAddress,
+    ) -> io::Result<()> {
+        let ip = addr.find_ip_addr();
+        let permitted = match (origin, peer_id) {
+            (ConnectionOrigin::Outbound, Some(peer_id)) => !self.peer_access.denies(peer_id, ip),
+            (_, Some(peer_id)) => self.peer_access.permits(peer_id, ip),
+            (_, None) => !self.peer_access.denies_ip(ip),
+        };
+        if permitted {
+            return Ok(());
+        }
+        self.count_failure(origin, "denied");
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "peer {} at {} is denied on this network",
+                peer_id.map_or_else(
+                    || "?".to_string(),
+                    |peer_id| peer_id.short_str().to_string()
+                ),
+                addr
+            ),
+        ))
+    }
 }
 
 /// If we have proxy protocol enabled, then prepend the un-proxied address to the error.
@@ -266,10 +356,14 @@ async fn upgrade_inbound<T: TSocket>(
     } else {
         addr
     };
+    //////// 0L ////////
+    ctxt.check_access(origin, None, &addr)?;
 
     // try authenticating via noise handshake
     let (mut socket, remote_peer_id, peer_role) =
         ctxt.noise.upgrade_inbound(socket).await.map_err(|err| {
+            //////// 0L ////////
+            ctxt.count_failure(origin, err.rejection_reason());
             if err.should_security_log() {
                 sample!(
                     SampleRate::Duration(Duration::from_secs(15)),
@@ -285,6 +379,7 @@ async fn upgrade_inbound<T: TSocket>(
             let err = io::Error::new(io::ErrorKind::Other, err);
             add_pp_addr(proxy_protocol_enabled, err, &addr)
         })?;
+    ctxt.check_access(origin, Some(remote_peer_id), &addr)?; //////// 0L ////////
     let remote_pubkey = socket.get_remote_static();
     let addr = addr.append_prod_protos(remote_pubkey, HANDSHAKE_VERSION);
 
@@ -296,11 +391,15 @@ async fn upgrade_inbound<T: TSocket>(
     };
     let remote_handshake = exchange_handshake(&handshake_msg, &mut socket)
         .await
-        .map_err(|err| add_pp_addr(proxy_protocol_enabled, err, &addr))?;
+        .map_err(|err| {
+            //////// 0L ////////
+            ctxt.count_failure(origin, "exchange");
+            add_pp_addr(proxy_protocol_enabled, err, &addr)
+        })?;
 
     // try to negotiate common diemnet version and supported application protocols
-    let (messaging_protocol, application_protocols) = handshake_msg
-        .perform_handshake(&remote_handshake)
+    let (messaging_protocol, application_protocols) = ctxt
+        .negotiate(origin, &handshake_msg, &remote_handshake, remote_peer_id) //////// 0L ////////
         .map_err(|err| {
             let err = format!(
                 "handshake negotiation with peer {} failed: {}",
@@ -339,6 +438,7 @@ pub async fn upgrade_outbound<T: TSocket>(
     remote_pubkey: x25519::PublicKey,
 ) -> io::Result<Connection<NoiseStream<T>>> {
     let origin = ConnectionOrigin::Outbound;
+    ctxt.check_access(origin, Some(remote_peer_id), &addr)?; //////// 0L ////////
     let socket = fut_socket.await?;
 
     // noise handshake
@@ -347,6 +447,8 @@ pub async fn upgrade_outbound<T: TSocket>(
         .upgrade_outbound(socket, remote_pubkey, AntiReplayTimestamps::now)
         .await
         .map_err(|err| {
+            //////// 0L ////////
+            ctxt.count_failure(origin, err.rejection_reason());
             if err.should_security_log() {
                 sample!(
                     SampleRate::Duration(Duration::from_secs(15)),
@@ -371,11 +473,17 @@ pub async fn upgrade_outbound<T: TSocket>(
         chain_id: ctxt.chain_id,
         network_id: ctxt.network_id.clone(),
     };
-    let remote_handshake = exchange_handshake(&handshake_msg, &mut socket).await?;
+    let remote_handshake = exchange_handshake(&handshake_msg, &mut socket)
+        .await
+        .map_err(|err| {
+            //////// 0L ////////
+            ctxt.count_failure(origin, "exchange");
+            err
+        })?;
 
     // try to negotiate common diemnet version and supported application protocols
-    let (messaging_protocol, application_protocols) = handshake_msg
-        .perform_handshake(&remote_handshake)
+    let (messaging_protocol, application_protocols) = ctxt
+        .negotiate(origin, &handshake_msg, &remote_handshake, remote_peer_id) //////// 0L ////////
         .map_err(|e| {
             let e = format!(
                 "handshake negotiation with peer {} failed: {}",
@@ -437,6 +545,8 @@ where
         chain_id: ChainId,
         application_protocols: SupportedProtocols,
         enable_proxy_protocol: bool,
+        peer_access: PeerAccess,                     //////// 0L ////////
+        handshake_enforcement: HandshakeEnforcement, //////// 0L ////////
     ) -> Self {
         // build supported protocols
         let mut supported_protocols = BTreeMap::new();
@@ -451,6 +561,8 @@ where
             supported_protocols,
             chain_id,
             network_id,
+            peer_access,
+            handshake_enforcement,
         );
 
         Self {
@@ -476,13 +588,14 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_onion_tcp(protos).map(|x| (&protos[..2], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, or onion3+tcp",
                         addr
                     ),
                 )
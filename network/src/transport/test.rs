@@ -7,7 +7,7 @@ use crate::{
 };
 use bytes::{Bytes, BytesMut};
 use diem_config::{
-    config::{Peer, PeerRole, PeerSet, HANDSHAKE_VERSION},
+    config::{HandshakeEnforcement, Peer, PeerAccess, PeerRole, PeerSet, HANDSHAKE_VERSION},
     network_id::NetworkContext,
 };
 use diem_crypto::{test_utils::TEST_SEED, traits::Uniform, x25519};
@@ -65,6 +65,35 @@ fn setup<TTransport>(
     Arc<RwLock<PeerSet>>,
     SupportedProtocols,
 )
+where
+    TTransport: Transport<Error = io::Error> + Clone,
+    TTransport::Output: TSocket,
+    TTransport::Outbound: Send + 'static,
+    TTransport::Inbound: Send + 'static,
+    TTransport::Listener: Send + 'static,
+{
+    setup_with_access(
+        base_transport,
+        auth,
+        PeerAccess::default(),
+        PeerAccess::default(),
+    )
+}
+
+//////// 0L ////////
+fn setup_with_access<TTransport>(
+    base_transport: TTransport,
+    auth: Auth,
+    listener_access: PeerAccess,
+    dialer_access: PeerAccess,
+) -> (
+    Runtime,
+    MockTimeService,
+    (PeerId, DiemNetTransport<TTransport>),
+    (PeerId, DiemNetTransport<TTransport>),
+    Arc<RwLock<PeerSet>>,
+    SupportedProtocols,
+)
 where
     TTransport: Transport<Error = io::Error> + Clone,
     TTransport::Output: TSocket,
@@ -157,6 +186,8 @@ where
         chain_id,
         supported_protocols.clone(),
         false, /* Disable proxy protocol */
+        listener_access,
+        HandshakeEnforcement::Strict,
     );
 
     let dialer_transport = DiemNetTransport::new(
@@ -169,6 +200,8 @@ where
         chain_id,
         supported_protocols.clone(),
         false, /* Disable proxy protocol */
+        dialer_access,
+        HandshakeEnforcement::Strict,
     );
 
     (
@@ -490,6 +523,107 @@ fn test_transport_maybe_mutual<TTransport>(
     rt.block_on(future::join(listener_task, dialer_task));
 }
 
+//////// 0L ////////
+/// Dials the listener over tcp on localhost, with the allow and deny lists of each side. Returns
+/// whether the listener accepted the connection, and whether the dial succeeded.
+fn connect_with_access(listener_access: PeerAccess, dialer_access: PeerAccess) -> (bool, bool) {
+    let (
+        rt,
+        _mock_time,
+        (listener_peer_id, listener_transport),
+        (_dialer_peer_id, dialer_transport),
+        _trusted_peers,
+        _supported_protocols,
+    ) = setup_with_access(
+        DIEM_TCP_TRANSPORT.clone(),
+        Auth::ServerOnly,
+        listener_access,
+        dialer_access,
+    );
+
+    let _guard = rt.enter();
+    let (mut inbounds, listener_addr) = listener_transport
+        .listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+        .unwrap();
+
+    let listener_task = async move {
+        match inbounds.next().await {
+            Some(Ok((inbound, _dialer_addr))) => inbound.await.is_ok(),
+            _ => false,
+        }
+    };
+    let dialer_task = async move {
+        match dialer_transport.dial(listener_peer_id, listener_addr) {
+            Ok(outbound) => outbound.await.is_ok(),
+            Err(_) => false,
+        }
+    };
+    let listener_task = rt.spawn(listener_task);
+    let dialed = rt.block_on(dialer_task);
+    // A dial refused before it connects never reaches the listener
+    if !dialed {
+        return (false, false);
+    }
+    (rt.block_on(listener_task).unwrap(), dialed)
+}
+
+fn access(allowed: &[&str], denied: &[&str]) -> PeerAccess {
+    PeerAccess {
+        allowed: allowed.iter().map(|m| m.parse().unwrap()).collect(),
+        denied: denied.iter().map(|m| m.parse().unwrap()).collect(),
+    }
+}
+
+#[test]
+fn test_tcp_transport_peer_access() {
+    // Both sides without lists connect
+    assert_eq!(
+        connect_with_access(PeerAccess::default(), PeerAccess::default()),
+        (true, true)
+    );
+    // The allow list of the dialer does not keep it from dialing peers outside of it, e.g. seeds
+    assert_eq!(
+        connect_with_access(PeerAccess::default(), access(&["10.0.0.0/8"], &[])),
+        (true, true)
+    );
+    // The deny list of the dialer refuses the dial
+    assert_eq!(
+        connect_with_access(PeerAccess::default(), access(&[], &["127.0.0.1"])),
+        (false, false)
+    );
+}
+
+#[test]
+fn test_tcp_transport_peer_access_inbound() {
+    // The allow list of the listener refuses the dialer from outside of it
+    let (rt, _, (listener_peer_id, listener_transport), (_, dialer_transport), _, _) =
+        setup_with_access(
+            DIEM_TCP_TRANSPORT.clone(),
+            Auth::ServerOnly,
+            access(&["10.0.0.0/8"], &[]),
+            PeerAccess::default(),
+        );
+
+    let _guard = rt.enter();
+    let (mut inbounds, listener_addr) = listener_transport
+        .listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+        .unwrap();
+    let listener_task = async move {
+        let (inbound, _dialer_addr) = inbounds.next().await.unwrap().unwrap();
+        inbound
+            .await
+            .expect_err("should fail because the dialer is not on the allow list");
+    };
+    let dialer_task = async move {
+        dialer_transport
+            .dial(listener_peer_id, listener_addr)
+            .unwrap()
+            .await
+            .expect_err("should fail because the listener refuses the connection");
+    };
+    rt.block_on(future::join(listener_task, dialer_task));
+}
+
 ////////////////////////////////////////
 // DiemNetTransport<MemoryTransport> //
 ////////////////////////////////////////
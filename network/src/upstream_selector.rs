@@ -0,0 +1,290 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Runtime selection of the upstream network, following the preference of `UpstreamConfig`.
+//!
+//! Components talking to upstream peers report the latency of their requests and the failures.
+//! A peer is healthy while its smoothed latency and error rate stay under the policy limits, and a
+//! network is demoted as soon as none of its connected peers is healthy, rather than only once it
+//! has no peers at all. The most preferred network that is not demoted is selected.
+
+use diem_config::{
+    config::{PeerNetworkId, UpstreamConfig},
+    network_id::NetworkId,
+};
+use diem_infallible::RwLock;
+use diem_logger::prelude::*;
+use serde::Serialize;
+use std::{collections::HashMap, time::Duration};
+
+/// When a peer is considered degraded
+#[derive(Clone, Copy, Debug)]
+pub struct FailoverPolicy {
+    /// Smoothed latency above which a peer is degraded
+    pub max_latency: Duration,
+    /// Smoothed error rate, between 0 and 1, above which a peer is degraded
+    pub max_error_rate: f64,
+    /// Weight of the latest sample in the smoothed values
+    pub smoothing: f64,
+    /// Samples needed before a peer can be considered degraded
+    pub min_samples: u64,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            max_latency: Duration::from_secs(2),
+            max_error_rate: 0.5,
+            smoothing: 0.2,
+            min_samples: 5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct PeerHealth {
+    /// Exponentially smoothed latency, in milliseconds
+    pub latency_ms: Option<f64>,
+    /// Exponentially smoothed share of failed requests
+    pub error_rate: f64,
+    pub samples: u64,
+}
+
+impl PeerHealth {
+    fn record(&mut self, policy: &FailoverPolicy, latency: Option<Duration>) {
+        let alpha = policy.smoothing;
+        let error = if latency.is_some() { 0.0 } else { 1.0 };
+        if self.samples == 0 {
+            self.error_rate = error;
+        } else {
+            self.error_rate = alpha * error + (1.0 - alpha) * self.error_rate;
+        }
+        if let Some(latency) = latency {
+            let latency_ms = latency.as_secs_f64() * 1000.0;
+            self.latency_ms = Some(match self.latency_ms {
+                Some(smoothed) => alpha * latency_ms + (1.0 - alpha) * smoothed,
+                None => latency_ms,
+            });
+        }
+        self.samples += 1;
+    }
+
+    pub fn is_healthy(&self, policy: &FailoverPolicy) -> bool {
+        if self.samples < policy.min_samples {
+            return true;
+        }
+        let max_latency_ms = policy.max_latency.as_secs_f64() * 1000.0;
+        self.error_rate <= policy.max_error_rate
+            && self
+                .latency_ms
+                .map_or(true, |latency_ms| latency_ms <= max_latency_ms)
+    }
+}
+
+/// The health of an upstream network, as reported by `UpstreamSelector::selection`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NetworkHealth {
+    pub network: NetworkId,
+    pub peers: usize,
+    pub healthy_peers: usize,
+    /// Whether the network has peers, none of them healthy
+    pub demoted: bool,
+}
+
+/// The current selection, for debugging
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct UpstreamSelection {
+    pub preference: Vec<NetworkId>,
+    pub selected: Option<NetworkId>,
+    pub networks: Vec<NetworkHealth>,
+}
+
+pub struct UpstreamSelector {
+    config: RwLock<UpstreamConfig>,
+    policy: FailoverPolicy,
+    peers: RwLock<HashMap<PeerNetworkId, PeerHealth>>,
+}
+
+impl UpstreamSelector {
+    pub fn new(config: &UpstreamConfig) -> Self {
+        Self::new_with_policy(config, FailoverPolicy::default())
+    }
+
+    pub fn new_with_policy(config: &UpstreamConfig, policy: FailoverPolicy) -> Self {
+        Self {
+            config: RwLock::new(config.normalized()),
+            policy,
+            peers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the network preference, e.g. on a config reload. Peer health is kept.
+    pub fn set_config(&self, config: &UpstreamConfig) {
+        *self.config.write() = config.normalized();
+        info!(
+            "Upstream networks changed to {:?}",
+            self.config.read().networks
+        );
+    }
+
+    /// Tracks a connected upstream peer, keeping its health if it reconnects
+    pub fn add_peer(&self, peer: PeerNetworkId) {
+        self.peers.write().entry(peer).or_default();
+    }
+
+    pub fn remove_peer(&self, peer: &PeerNetworkId) {
+        self.peers.write().remove(peer);
+    }
+
+    /// Reports a request to `peer` that completed after `latency`
+    pub fn record_success(&self, peer: &PeerNetworkId, latency: Duration) {
+        self.record(peer, Some(latency));
+    }
+
+    /// Reports a request to `peer` that failed or timed out
+    pub fn record_failure(&self, peer: &PeerNetworkId) {
+        self.record(peer, None);
+    }
+
+    fn record(&self, peer: &PeerNetworkId, latency: Option<Duration>) {
+        let selected = self.selected_network();
+        if let Some(health) = self.peers.write().get_mut(peer) {
+            health.record(&self.policy, latency);
+        } else {
+            return;
+        }
+        let now_selected = self.selected_network();
+        if now_selected != selected {
+            info!(
+                "Upstream network changed from {:?} to {:?}",
+                selected, now_selected
+            );
+        }
+    }
+
+    /// Whether the network has connected peers, none of them healthy
+    pub fn is_demoted(&self, network: &NetworkId) -> bool {
+        self.network_health()
+            .iter()
+            .any(|health| &health.network == network && health.demoted)
+    }
+
+    /// The networks with connected peers, none of them healthy
+    pub fn demoted_networks(&self) -> Vec<NetworkId> {
+        self.network_health()
+            .into_iter()
+            .filter(|health| health.demoted)
+            .map(|health| health.network)
+            .collect()
+    }
+
+    /// The most preferred network with a healthy peer. If every network with peers is demoted,
+    /// the most preferred one with peers, as a degraded upstream beats none.
+    pub fn selected_network(&self) -> Option<NetworkId> {
+        let networks = self.network_health();
+        networks
+            .iter()
+            .find(|health| health.healthy_peers > 0)
+            .or_else(|| networks.iter().find(|health| health.peers > 0))
+            .map(|health| health.network.clone())
+    }
+
+    pub fn selection(&self) -> UpstreamSelection {
+        UpstreamSelection {
+            preference: self.config.read().networks.clone(),
+            selected: self.selected_network(),
+            networks: self.network_health(),
+        }
+    }
+
+    pub fn peer_health(&self, peer: &PeerNetworkId) -> Option<PeerHealth> {
+        self.peers.read().get(peer).copied()
+    }
+
+    /// The health of the upstream networks, by preference
+    fn network_health(&self) -> Vec<NetworkHealth> {
+        let peers = self.peers.read();
+        let mut networks: Vec<NetworkHealth> = std::iter::once(NetworkId::Validator)
+            .chain(self.config.read().networks.iter().cloned())
+            .map(|network| NetworkHealth {
+                network,
+                peers: 0,
+                healthy_peers: 0,
+                demoted: false,
+            })
+            .collect();
+        for (peer, health) in peers.iter() {
+            if let Some(network) = networks
+                .iter_mut()
+                .find(|network| network.network == peer.raw_network_id())
+            {
+                network.peers += 1;
+                if health.is_healthy(&self.policy) {
+                    network.healthy_peers += 1;
+                }
+            }
+        }
+        for network in &mut networks {
+            network.demoted = network.peers > 0 && network.healthy_peers == 0;
+        }
+        networks
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diem_config::network_id::NodeNetworkId;
+    use diem_types::PeerId;
+
+    fn peer(network: NetworkId) -> PeerNetworkId {
+        PeerNetworkId(NodeNetworkId::new(network, 0), PeerId::random())
+    }
+
+    #[test]
+    fn test_failover_on_degraded_peers() {
+        let config = UpstreamConfig {
+            networks: vec![NetworkId::vfn_network(), NetworkId::Public],
+        };
+        let selector = UpstreamSelector::new(&config);
+        assert_eq!(selector.selected_network(), None);
+
+        let vfn = peer(NetworkId::vfn_network());
+        let public = peer(NetworkId::Public);
+        selector.add_peer(vfn.clone());
+        selector.add_peer(public.clone());
+        assert_eq!(selector.selected_network(), Some(NetworkId::vfn_network()));
+
+        // The VFN is still connected but failing, so the public network takes over
+        for _ in 0..FailoverPolicy::default().min_samples {
+            selector.record_failure(&vfn);
+            selector.record_success(&public, Duration::from_millis(50));
+        }
+        assert!(selector.is_demoted(&NetworkId::vfn_network()));
+        assert_eq!(selector.selected_network(), Some(NetworkId::Public));
+
+        // And it is promoted back once it recovers
+        for _ in 0..20 {
+            selector.record_success(&vfn, Duration::from_millis(50));
+        }
+        assert!(!selector.is_demoted(&NetworkId::vfn_network()));
+        let selection = selector.selection();
+        assert_eq!(selection.selected, Some(NetworkId::vfn_network()));
+        assert_eq!(selection.networks.len(), 3);
+    }
+
+    #[test]
+    fn test_degraded_upstream_beats_none() {
+        let selector = UpstreamSelector::new(&UpstreamConfig {
+            networks: vec![NetworkId::Public],
+        });
+        let public = peer(NetworkId::Public);
+        selector.add_peer(public.clone());
+        for _ in 0..FailoverPolicy::default().min_samples {
+            selector.record_success(&public, Duration::from_secs(10));
+        }
+        assert!(selector.is_demoted(&NetworkId::Public));
+        assert_eq!(selector.selected_network(), Some(NetworkId::Public));
+    }
+}
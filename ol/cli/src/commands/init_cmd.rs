@@ -12,7 +12,8 @@ use abscissa_core::{config, Command, FrameworkError, Options, Runnable};
 use anyhow::{anyhow, bail, Error};
 use dialoguer::Confirm;
 use diem_genesis_tool::{
-    init, key, ol_node_files,
+    init, key,
+    ol_node_files::{self, ListenAddresses},
     seeds::{SeedAddresses, Seeds},
 };
 use diem_json_rpc_client::AccountAddress;
@@ -185,9 +186,10 @@ impl Runnable for InitCmd {
 
             match ol_node_files::make_vfn_file(
                 output_dir,
-                val_ip_address,
+                val_ip_address.into(),
                 gen_wp.unwrap_or_default(),
                 &namespace,
+                &ListenAddresses::default(),
             ) {
                 Ok(_) => {}
                 Err(e) => {
@@ -212,7 +214,13 @@ impl Runnable for InitCmd {
                 None
             };
 
-            match ol_node_files::make_val_file(output_dir, seeds, None, &namespace) {
+            match ol_node_files::make_val_file(
+                output_dir,
+                seeds,
+                None,
+                &namespace,
+                &ListenAddresses::default(),
+            ) {
                 Ok(_) => {}
                 Err(e) => {
                     println!("Could not create file, exiting. Message: {:?}", e);
@@ -233,7 +241,12 @@ impl Runnable for InitCmd {
             // TODO: get seed addresses from file optionally
             // let seed = SeedAddresses::read_from_file(seed_peers_path);
 
-            match ol_node_files::make_fullnode_file(output_dir, seed, gen_wp.unwrap_or_default()) {
+            match ol_node_files::make_fullnode_file(
+                output_dir,
+                seed,
+                gen_wp.unwrap_or_default(),
+                &ListenAddresses::default(),
+            ) {
                 Ok(_) => {}
                 Err(e) => {
                     println!("Could not create file, exiting. Message: {:?}", e);
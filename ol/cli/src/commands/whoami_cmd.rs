@@ -2,7 +2,11 @@
 
 #![allow(clippy::never_loop)]
 
-use std::{net::Ipv4Addr, path::PathBuf, process::exit};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    process::exit,
+};
 
 use abscissa_core::{Command, Options, Runnable};
 use anyhow::{anyhow, Error};
@@ -65,6 +69,10 @@ impl Runnable for WhoamiCmd {
             scheme.child_4_consensus.get_public()
         );
         println!("key 5 Executor: {}\n", scheme.child_5_executor.get_public());
+        println!(
+            "key 6 VFN Network: {}\n",
+            scheme.child_6_vfn_network.get_public()
+        );
 
         println!("----- pub x25519 network keys -----\n");
 
@@ -86,6 +94,12 @@ impl Runnable for WhoamiCmd {
             &key.public_key().to_string()
         );
 
+        let vfn_net_priv = scheme.child_6_vfn_network.get_private_key().to_bytes();
+
+        let key = x25519::PrivateKey::from_ed25519_private_bytes(&vfn_net_priv)
+            .expect("Unable to convert key");
+        println!("vfn network key: {:?}\n", &key.public_key().to_string());
+
         println!("----- noise protocol addresses -----\n");
 
         println!("Validator (encrypted) address on VALIDATOR network\n");
@@ -109,10 +123,10 @@ fn display_id_in_file(yaml_path: &PathBuf) -> Result<(), Error> {
     })?;
 
     println!("We will use this machines external IP for display. Note that if you move this file the IP will display differently on another machine. ");
-    let ip = get_my_ip().unwrap_or_else(|_| {
+    let ip = IpAddr::from(get_my_ip().unwrap_or_else(|_| {
         println!("could not get external IP, using 0.0.0.0 for display");
-        "0.0.0.0".parse().unwrap()
-    });
+        Ipv4Addr::UNSPECIFIED
+    }));
 
     println!("\n ACTUAL NETWORK IDs IN {:?}\n", yaml_path.as_os_str());
     println!("----- noise protocol addresses -----\n");
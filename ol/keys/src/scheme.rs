@@ -21,6 +21,8 @@ pub struct KeyScheme {
     pub child_4_consensus: ExtendedPrivKey,
     /// Execution key
     pub child_5_executor: ExtendedPrivKey,
+    /// Network identity of the VFN on its private network with the validator
+    pub child_6_vfn_network: ExtendedPrivKey,
 }
 
 impl KeyScheme {
@@ -34,6 +36,7 @@ impl KeyScheme {
             child_3_fullnode_network: kf.private_child(ChildNumber::new(3)).unwrap(),
             child_4_consensus: kf.private_child(ChildNumber::new(4)).unwrap(),
             child_5_executor: kf.private_child(ChildNumber::new(5)).unwrap(),
+            child_6_vfn_network: kf.private_child(ChildNumber::new(6)).unwrap(),
         }
     }
     /// Get KeyScheme from a mnemonic string.
@@ -5,14 +5,14 @@
 use crate::application::app_config;
 use abscissa_core::{Command, Options, Runnable};
 use anyhow::{bail, Error};
-use diem_types::waypoint::Waypoint;
+use diem_types::{chain_id::ChainId, waypoint::Waypoint};
 use std::{fs::File, io::Write, path::PathBuf, process::exit};
 
 /// `files` subcommand
 #[derive(Command, Debug, Default, Options)]
 pub struct GenesisFilesCmd {
-    #[options(help = "id of the chain")]
-    chain_id: Option<u8>,
+    #[options(help = "chain to join: mainnet, rc, devnet, ... or the id of a custom chain")]
+    chain_id: Option<ChainId>,
     #[options(help = "github org of genesis repo")]
     github_org: Option<String>,
     #[options(help = "repo with with genesis transactions")]
@@ -7,7 +7,11 @@ use super::genesis_files_cmd;
 use crate::entrypoint;
 use crate::prelude::app_config;
 use abscissa_core::{status_info, status_ok, Command, Options, Runnable};
-use diem_genesis_tool::{ol_node_files, waypoint};
+use diem_genesis_tool::{
+    ol_node_files::{self, ListenAddresses},
+    registry::SharedBackend,
+    waypoint,
+};
 use diem_types::chain_id::NamedChain;
 use diem_types::transaction::SignedTransaction;
 use diem_types::waypoint::Waypoint;
@@ -164,15 +168,15 @@ impl Runnable for ForkCmd {
         // TODO: use node_config to get the seed peers and then write upstream_node vec in 0L.toml from that.
         ol_node_files::onboard_helper_all_files(
             home_dir.clone(),
-            self.chain_id.unwrap_or(NamedChain::MAINNET),
-            self.github_org.clone(),
-            self.repo.clone(),
+            self.chain_id.unwrap_or(NamedChain::MAINNET).into(),
+            SharedBackend::from_github(self.github_org.clone(), self.repo.clone()),
             &namespace,
             &prebuilt_genesis_path,
             &false,
             None,
             &None,
-            Some(val_ip_address),
+            Some(val_ip_address.into()),
+            &ListenAddresses::default(),
         )
         .unwrap();
 
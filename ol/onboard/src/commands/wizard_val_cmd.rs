@@ -5,7 +5,10 @@
 use super::genesis_files_cmd;
 use crate::prelude::app_config;
 use abscissa_core::{status_info, status_ok, Command, Options, Runnable};
-use diem_genesis_tool::ol_node_files;
+use diem_genesis_tool::{
+    ol_node_files::{self, ListenAddresses},
+    registry::SharedBackend,
+};
 use diem_types::chain_id::NamedChain;
 use diem_types::{transaction::SignedTransaction, waypoint::Waypoint};
 use diem_wallet::WalletLibrary;
@@ -389,15 +392,15 @@ fn get_genesis_and_make_node_files(
     // TODO: use node_config to get the seed peers and then write upstream_node vec in 0L.toml from that.
     match ol_node_files::onboard_helper_all_files(
         home_dir.clone(),
-        cmd.chain_id.unwrap_or(NamedChain::MAINNET),
-        cmd.github_org.clone(),
-        cmd.repo.clone(),
+        cmd.chain_id.unwrap_or(NamedChain::MAINNET).into(),
+        SharedBackend::from_github(cmd.github_org.clone(), cmd.repo.clone()),
         &namespace,
         &genesis_blob_path,
         &false,
         None,
         &None,
-        Some(val_ip_address),
+        Some(val_ip_address.into()),
+        &ListenAddresses::default(),
     ) {
         Ok(_) => {}
         Err(e) => {
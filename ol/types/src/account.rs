@@ -18,7 +18,13 @@ use anyhow::{self, bail};
 use hex::{decode, encode};
 use ol_keys::scheme::KeyScheme;
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
-use std::{fs::File, io::Write, net::Ipv4Addr, path::PathBuf, process::exit};
+use std::{
+    fs::File,
+    io::Write,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    process::exit,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 /// Configuration data necessary to initialize a validator.
@@ -95,8 +101,11 @@ impl ValConfigs {
             PublicKey::from_ed25519_public_bytes(&keys.child_2_val_network.get_public().to_bytes())
                 .unwrap();
 
-        let val_addr_for_val_net =
-            ValConfigs::make_unencrypted_addr(&val_ip_address, val_pubkey, NetworkId::Validator);
+        let val_addr_for_val_net = ValConfigs::make_unencrypted_addr(
+            &val_ip_address.into(),
+            val_pubkey,
+            NetworkId::Validator,
+        );
 
         let encrypted_addr = val_addr_for_val_net
             .clone()
@@ -112,7 +121,7 @@ impl ValConfigs {
 
         // For the private VFN Fullnode network the Validator uses this identity:
         let val_addr_for_vfn_net = ValConfigs::make_unencrypted_addr(
-            &val_ip_address,
+            &val_ip_address.into(),
             val_pubkey,
             NetworkId::Private("vfn".to_owned()),
         );
@@ -124,8 +133,11 @@ impl ValConfigs {
             &keys.child_3_fullnode_network.get_public().to_bytes(),
         )
         .unwrap();
-        let vfn_addr_obj =
-            ValConfigs::make_unencrypted_addr(&vfn_ip_address, vfn_pubkey, NetworkId::Public);
+        let vfn_addr_obj = ValConfigs::make_unencrypted_addr(
+            &vfn_ip_address.into(),
+            vfn_pubkey,
+            NetworkId::Public,
+        );
 
         Self {
             /// Proof zero of the onboarded miner
@@ -189,7 +201,7 @@ impl ValConfigs {
 
     /// format the fullnode address which the validator's VFN will use.
     pub fn make_unencrypted_addr(
-        ip_address: &Ipv4Addr,
+        ip_address: &IpAddr,
         fn_pubkey: PublicKey,
         net: NetworkId,
     ) -> NetworkAddress {
@@ -199,10 +211,9 @@ impl ValConfigs {
             NetworkId::Private(_) => DEFAULT_VFN_PORT,
         };
 
-        let fullnode_network_string = format!("/ip4/{}/tcp/{}", ip_address.to_string(), port);
-        let fn_addr_obj: NetworkAddress = fullnode_network_string
-            .parse()
-            .expect("could not parse fullnode network address");
+        //////// 0L ////////
+        // An ip6 address makes an /ip6 multiaddr
+        let fn_addr_obj = NetworkAddress::from(SocketAddr::new(*ip_address, port as u16));
         fn_addr_obj.append_prod_protos(fn_pubkey, 0)
     }
 
@@ -14,6 +14,14 @@
 //!
 //! Internally both the client and server leverage a NetworkStream that communications in blocks
 //! where a block is a length prefixed array of bytes.
+//!
+//! Both ends can optionally secure the stream with mutually authenticated TLS, see `ClientTls` and
+//! `ServerTls`.
+
+//////// 0L ////////
+mod tls;
+
+pub use tls::{ClientTls, ServerTls};
 
 use diem_logger::{info, trace, warn, Schema};
 use diem_secure_push_metrics::{register_int_counter_vec, IntCounterVec};
@@ -25,6 +33,7 @@ use std::{
     thread, time,
 };
 use thiserror::Error;
+use tls::Stream;
 
 #[derive(Schema)]
 struct SecureNetLogSchema<'a> {
@@ -144,6 +153,13 @@ pub enum Error {
     NoActiveStream,
     #[error("Remote stream cleanly closed")]
     RemoteStreamClosed,
+    //////// 0L ////////
+    #[error("Invalid TLS configuration: {0}")]
+    TlsConfig(String),
+    #[error("TLS handshake failed: {0}")]
+    TlsHandshake(String),
+    #[error("Unauthorized client: {0}")]
+    UnauthorizedClient(String),
 }
 
 pub struct NetworkClient {
@@ -152,6 +168,7 @@ pub struct NetworkClient {
     stream: Option<NetworkStream>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    tls: Option<ClientTls>,
 }
 
 impl NetworkClient {
@@ -161,9 +178,17 @@ impl NetworkClient {
             server,
             stream: None,
             timeout_ms,
+            tls: None,
         }
     }
 
+    //////// 0L ////////
+    /// Secures the connections to the server with mutually authenticated TLS
+    pub fn with_tls(mut self, tls: ClientTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
     fn increment_counter(&self, method: Method, result: MethodResult) {
         increment_counter(self.service, NetworkMode::Client, method, result)
     }
@@ -255,7 +280,27 @@ impl NetworkClient {
 
             let stream = stream?;
             stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, self.server, self.timeout_ms));
+            let stream = NetworkStream::new(stream, self.server, self.timeout_ms, |stream| {
+                match &self.tls {
+                    Some(tls) => tls.connect(stream),
+                    None => Ok(Stream::Tcp(stream)),
+                }
+            });
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    self.increment_counter(Method::Connect, MethodResult::Failure);
+                    warn!(SecureNetLogSchema::new(
+                        self.service,
+                        NetworkMode::Client,
+                        LogEvent::ConnectionFailed,
+                    )
+                    .error(&err)
+                    .remote_peer(&self.server));
+                    return Err(err);
+                }
+            };
+            self.stream = Some(stream);
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -275,6 +320,7 @@ pub struct NetworkServer {
     stream: Option<NetworkStream>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    tls: Option<ServerTls>,
 }
 
 impl NetworkServer {
@@ -285,9 +331,17 @@ impl NetworkServer {
             listener: Some(listener.unwrap()),
             stream: None,
             timeout_ms,
+            tls: None,
         }
     }
 
+    //////// 0L ////////
+    /// Only serves the clients that complete a mutually authenticated TLS handshake
+    pub fn with_tls(mut self, tls: ServerTls) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
     fn increment_counter(&self, method: Method, result: MethodResult) {
         increment_counter(self.service, NetworkMode::Server, method, result)
     }
@@ -388,6 +442,29 @@ impl NetworkServer {
                 }
             };
 
+            stream.set_nodelay(true)?;
+            // A client failing the handshake is dropped, the next call accepts another one
+            let stream = NetworkStream::new(stream, stream_addr, self.timeout_ms, |stream| {
+                match &self.tls {
+                    Some(tls) => tls.accept(stream),
+                    None => Ok(Stream::Tcp(stream)),
+                }
+            });
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    self.increment_counter(Method::Connect, MethodResult::Failure);
+                    warn!(SecureNetLogSchema::new(
+                        self.service,
+                        NetworkMode::Server,
+                        LogEvent::ConnectionFailed,
+                    )
+                    .error(&err)
+                    .remote_peer(&stream_addr));
+                    return Err(err);
+                }
+            };
+
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -395,9 +472,7 @@ impl NetworkServer {
                 LogEvent::ConnectionSuccessful,
             )
             .remote_peer(&stream_addr));
-
-            stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, stream_addr, self.timeout_ms));
+            self.stream = Some(stream);
         }
 
         self.stream.as_mut().ok_or(Error::NoActiveStream)
@@ -405,25 +480,31 @@ impl NetworkServer {
 }
 
 struct NetworkStream {
-    stream: TcpStream,
+    stream: Stream,
     remote: SocketAddr,
     buffer: Vec<u8>,
     temp_buffer: [u8; 1024],
 }
 
 impl NetworkStream {
-    pub fn new(stream: TcpStream, remote: SocketAddr, timeout_ms: u64) -> Self {
+    /// Sets the timeouts of the socket before `secure` performs any handshake on it
+    pub fn new(
+        stream: TcpStream,
+        remote: SocketAddr,
+        timeout_ms: u64,
+        secure: impl FnOnce(TcpStream) -> Result<Stream, Error>,
+    ) -> Result<Self, Error> {
         let timeout = Some(std::time::Duration::from_millis(timeout_ms));
         // These only fail if a duration of 0 is passed in.
         stream.set_read_timeout(timeout).unwrap();
         stream.set_write_timeout(timeout).unwrap();
 
-        Self {
-            stream,
+        Ok(Self {
+            stream: secure(stream)?,
             remote,
             buffer: Vec::new(),
             temp_buffer: [0; 1024],
-        }
+        })
     }
 
     /// Blocking read until able to successfully read an entire message
@@ -452,7 +533,7 @@ impl NetworkStream {
 
     /// Terminate the socket
     pub fn shutdown(&self) -> Result<(), Error> {
-        Ok(self.stream.shutdown(Shutdown::Both)?)
+        Ok(self.stream.tcp().shutdown(Shutdown::Both)?)
     }
 
     /// Blocking write until able to successfully send an entire message
@@ -466,6 +547,8 @@ impl NetworkStream {
         self.write_all(&data_len.to_le_bytes())?;
         trace!("Attempting to write data, {},  to the stream", data_len);
         self.write_all(data)?;
+        // TLS buffers the records until flushed
+        self.stream.flush()?;
         trace!(
             "Successfully wrote length, {}, and data to the stream",
             data_len
@@ -652,4 +735,79 @@ mod test {
         let result2 = server2.read().unwrap();
         assert_eq!(data2, result2);
     }
+
+    //////// 0L ////////
+    fn test_data(file: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/test_data")
+            .join(file)
+    }
+
+    fn tls_server(server_addr: SocketAddr, allowed_clients: Vec<String>) -> NetworkServer {
+        let tls = ServerTls::new(
+            &test_data("safety_rules.pem"),
+            &test_data("safety_rules_key.pem"),
+            &test_data("ca.pem"),
+            allowed_clients,
+        )
+        .unwrap();
+        NetworkServer::new("test", server_addr, TIMEOUT).with_tls(tls)
+    }
+
+    fn tls_client(server_addr: SocketAddr, name: &str) -> NetworkClient {
+        let tls = ClientTls::new(
+            &test_data(&format!("{}.pem", name)),
+            &test_data(&format!("{}_key.pem", name)),
+            &test_data("ca.pem"),
+            "safety-rules",
+        )
+        .unwrap();
+        NetworkClient::new("test", server_addr, TIMEOUT).with_tls(tls)
+    }
+
+    #[test]
+    fn test_tls_ping() {
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server = tls_server(server_addr, vec!["validator".into()]);
+        let client = thread::spawn(move || {
+            let mut client = tls_client(server_addr, "validator");
+            client.write(&[0, 1, 2, 3]).unwrap();
+            client.read().unwrap()
+        });
+
+        let result = server.read().unwrap();
+        assert_eq!(vec![0, 1, 2, 3], result);
+        server.write(&[4, 5, 6, 7]).unwrap();
+        assert_eq!(vec![4, 5, 6, 7], client.join().unwrap());
+    }
+
+    #[test]
+    fn test_tls_unauthorized_client() {
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server = tls_server(server_addr, vec!["validator".into()]);
+
+        // Signed by the CA, but not an allowed client
+        let intruder = thread::spawn(move || {
+            let mut client = tls_client(server_addr, "intruder");
+            client.write(&[0, 1, 2, 3]).and_then(|_| client.read())
+        });
+        match server.read() {
+            Err(Error::UnauthorizedClient(_)) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        intruder.join().unwrap().unwrap_err();
+
+        // A plaintext client fails the handshake
+        let plaintext = thread::spawn(move || {
+            let mut client = NetworkClient::new("test", server_addr, TIMEOUT);
+            client.write(&[0, 1, 2, 3]).and_then(|_| client.read())
+        });
+        match server.read() {
+            Err(Error::TlsHandshake(_)) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        plaintext.join().unwrap().unwrap_err();
+    }
 }
@@ -0,0 +1,216 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Mutually authenticated TLS for `NetworkClient` and `NetworkServer`: both sides present a
+//! certificate signed by a common CA, and the server only serves the clients whose certificate is
+//! valid for one of its allowed names.
+
+use crate::Error;
+use rustls::{
+    internal::pemfile, AllowAnyAuthenticatedClient, Certificate, ClientConfig, ClientSession,
+    PrivateKey, RootCertStore, ServerConfig, ServerSession, Session, StreamOwned,
+};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    net::TcpStream,
+    path::Path,
+    sync::Arc,
+};
+use webpki::{DNSNameRef, EndEntityCert};
+
+/// The TLS side of a `NetworkClient`
+#[derive(Clone)]
+pub struct ClientTls {
+    config: Arc<ClientConfig>,
+    /// The name the server certificate must be valid for
+    server_name: String,
+}
+
+impl ClientTls {
+    pub fn new(
+        cert_path: &Path,
+        key_path: &Path,
+        ca_cert_path: &Path,
+        server_name: &str,
+    ) -> Result<Self, Error> {
+        DNSNameRef::try_from_ascii_str(server_name)
+            .map_err(|_| Error::TlsConfig(format!("invalid server name {}", server_name)))?;
+        let mut config = ClientConfig::new();
+        config.root_store = read_roots(ca_cert_path)?;
+        config
+            .set_single_client_cert(read_certs(cert_path)?, read_private_key(key_path)?)
+            .map_err(|e| Error::TlsConfig(e.to_string()))?;
+        Ok(Self {
+            config: Arc::new(config),
+            server_name: server_name.into(),
+        })
+    }
+
+    pub(crate) fn connect(&self, stream: TcpStream) -> Result<Stream, Error> {
+        let name = DNSNameRef::try_from_ascii_str(&self.server_name)
+            .expect("The server name is checked on creation");
+        let mut stream = StreamOwned::new(ClientSession::new(&self.config, name), stream);
+        handshake(&mut stream.sess, &mut stream.sock)?;
+        Ok(Stream::Client(Box::new(stream)))
+    }
+}
+
+/// The TLS side of a `NetworkServer`
+#[derive(Clone)]
+pub struct ServerTls {
+    config: Arc<ServerConfig>,
+    /// The names of the clients allowed to connect, any client of the CA if empty
+    allowed_clients: Vec<String>,
+}
+
+impl ServerTls {
+    pub fn new(
+        cert_path: &Path,
+        key_path: &Path,
+        ca_cert_path: &Path,
+        allowed_clients: Vec<String>,
+    ) -> Result<Self, Error> {
+        for name in &allowed_clients {
+            DNSNameRef::try_from_ascii_str(name)
+                .map_err(|_| Error::TlsConfig(format!("invalid client name {}", name)))?;
+        }
+        let mut config =
+            ServerConfig::new(AllowAnyAuthenticatedClient::new(read_roots(ca_cert_path)?));
+        config
+            .set_single_cert(read_certs(cert_path)?, read_private_key(key_path)?)
+            .map_err(|e| Error::TlsConfig(e.to_string()))?;
+        Ok(Self {
+            config: Arc::new(config),
+            allowed_clients,
+        })
+    }
+
+    pub(crate) fn accept(&self, stream: TcpStream) -> Result<Stream, Error> {
+        let mut stream = StreamOwned::new(ServerSession::new(&self.config), stream);
+        handshake(&mut stream.sess, &mut stream.sock)?;
+
+        // The handshake verified the chain of the certificate, not who the client is
+        let cert = stream
+            .sess
+            .get_peer_certificates()
+            .and_then(|certs| certs.into_iter().next())
+            .ok_or_else(|| Error::UnauthorizedClient("no client certificate".into()))?;
+        if !self.allowed_clients.is_empty() && !self.is_allowed(&cert) {
+            return Err(Error::UnauthorizedClient(
+                "the client certificate is not valid for an allowed client name".into(),
+            ));
+        }
+        Ok(Stream::Server(Box::new(stream)))
+    }
+
+    fn is_allowed(&self, cert: &Certificate) -> bool {
+        let cert = match EndEntityCert::from(&cert.0) {
+            Ok(cert) => cert,
+            Err(_) => return false,
+        };
+        self.allowed_clients.iter().any(|name| {
+            DNSNameRef::try_from_ascii_str(name).map_or(false, |name| {
+                cert.verify_is_valid_for_dns_name(name).is_ok()
+            })
+        })
+    }
+}
+
+fn handshake(session: &mut impl Session, stream: &mut TcpStream) -> Result<(), Error> {
+    while session.is_handshaking() {
+        let (read, written) = session
+            .complete_io(stream)
+            .map_err(|e| Error::TlsHandshake(e.to_string()))?;
+        if read == 0 && written == 0 {
+            return Err(Error::RemoteStreamClosed);
+        }
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|e| Error::TlsConfig(format!("{}: {}", path.display(), e)))
+}
+
+fn read_certs(path: &Path) -> Result<Vec<Certificate>, Error> {
+    let pem = read(path)?;
+    let certs = pemfile::certs(&mut pem.as_slice())
+        .map_err(|_| Error::TlsConfig(format!("invalid certificate {}", path.display())))?;
+    if certs.is_empty() {
+        return Err(Error::TlsConfig(format!(
+            "no certificate in {}",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn read_roots(path: &Path) -> Result<RootCertStore, Error> {
+    let mut roots = RootCertStore::empty();
+    for cert in read_certs(path)? {
+        roots
+            .add(&cert)
+            .map_err(|e| Error::TlsConfig(format!("{}: {:?}", path.display(), e)))?;
+    }
+    Ok(roots)
+}
+
+/// Reads a PKCS8 or RSA private key
+fn read_private_key(path: &Path) -> Result<PrivateKey, Error> {
+    let pem = read(path)?;
+    let invalid = |_| Error::TlsConfig(format!("invalid private key {}", path.display()));
+    let mut keys = pemfile::pkcs8_private_keys(&mut pem.as_slice()).map_err(invalid)?;
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut pem.as_slice()).map_err(invalid)?;
+    }
+    keys.into_iter()
+        .next()
+        .ok_or_else(|| Error::TlsConfig(format!("no private key in {}", path.display())))
+}
+
+/// The connection of a `NetworkStream`
+pub(crate) enum Stream {
+    Tcp(TcpStream),
+    Client(Box<StreamOwned<ClientSession, TcpStream>>),
+    Server(Box<StreamOwned<ServerSession, TcpStream>>),
+}
+
+impl Stream {
+    pub fn tcp(&self) -> &TcpStream {
+        match self {
+            Stream::Tcp(stream) => stream,
+            Stream::Client(stream) => &stream.sock,
+            Stream::Server(stream) => &stream.sock,
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Client(stream) => stream.read(buf),
+            Stream::Server(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Client(stream) => stream.write(buf),
+            Stream::Server(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Client(stream) => stream.flush(),
+            Stream::Server(stream) => stream.flush(),
+        }
+    }
+}
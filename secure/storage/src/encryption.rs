@@ -0,0 +1,162 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Encryption of the file of an `OnDiskStorage` with a passphrase. The file is sealed with
+//! AES-256-GCM under a key derived from the passphrase and a random salt with
+//! PBKDF2-HMAC-SHA3-256, and a new nonce is drawn on every write. The parameters are stored in the
+//! clear next to the ciphertext, so they can be raised without breaking existing files.
+
+use crate::{from_base64, to_base64, Error};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    Aes256Gcm,
+};
+use diem_crypto::compat::Sha3_256;
+use diem_infallible::Mutex;
+use hmac::Hmac;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+const CIPHER: &str = "aes-256-gcm";
+const KDF: &str = "pbkdf2-hmac-sha3-256";
+const KDF_ITERATIONS: u32 = 100_000;
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct EncryptedFile {
+    cipher: String,
+    kdf: String,
+    iterations: u32,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    salt: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    nonce: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    ciphertext: Vec<u8>,
+}
+
+struct DerivedKey {
+    salt: Vec<u8>,
+    iterations: u32,
+    key: [u8; KEY_LEN],
+}
+
+pub(crate) struct Cipher {
+    passphrase: String,
+    /// The key derived last, as the derivation is slow on purpose and the storage reads and
+    /// writes its file on every operation. Writes keep the salt of the file read.
+    derived: Mutex<Option<DerivedKey>>,
+}
+
+impl Cipher {
+    pub fn new(passphrase: String) -> Self {
+        Self {
+            passphrase,
+            derived: Mutex::new(None),
+        }
+    }
+
+    /// Whether `contents` is an encrypted storage file rather than a plaintext one
+    pub fn is_encrypted(contents: &[u8]) -> bool {
+        serde_json::from_slice::<EncryptedFile>(contents).is_ok()
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut derived = self.derived.lock();
+        if derived.is_none() {
+            let mut salt = vec![0; SALT_LEN];
+            fill_random(&mut salt)?;
+            *derived = Some(self.derive_key(salt, KDF_ITERATIONS));
+        }
+        let derived = derived.as_ref().expect("A key is derived");
+
+        let mut nonce = vec![0; NONCE_LEN];
+        fill_random(&mut nonce)?;
+        let ciphertext = Aes256Gcm::new(GenericArray::from_slice(&derived.key))
+            .encrypt(GenericArray::from_slice(&nonce), plaintext)
+            .map_err(|_| Error::InternalError("Unable to encrypt the storage".into()))?;
+        let file = EncryptedFile {
+            cipher: CIPHER.into(),
+            kdf: KDF.into(),
+            iterations: derived.iterations,
+            salt: derived.salt.clone(),
+            nonce,
+            ciphertext,
+        };
+        Ok(serde_json::to_vec(&file)?)
+    }
+
+    pub fn decrypt(&self, contents: &[u8]) -> Result<Vec<u8>, Error> {
+        let file: EncryptedFile = serde_json::from_slice(contents)?;
+        if file.cipher != CIPHER || file.kdf != KDF {
+            return Err(Error::SerializationError(format!(
+                "Unsupported storage encryption {} with {}",
+                file.cipher, file.kdf
+            )));
+        }
+        if file.nonce.len() != NONCE_LEN {
+            return Err(Error::SerializationError("Invalid storage nonce".into()));
+        }
+
+        let mut derived = self.derived.lock();
+        let cached = derived.as_ref().map_or(false, |key| {
+            key.salt == file.salt && key.iterations == file.iterations
+        });
+        if !cached {
+            *derived = Some(self.derive_key(file.salt, file.iterations));
+        }
+        let derived = derived.as_ref().expect("A key is derived");
+        Aes256Gcm::new(GenericArray::from_slice(&derived.key))
+            .decrypt(
+                GenericArray::from_slice(&file.nonce),
+                file.ciphertext.as_ref(),
+            )
+            .map_err(|_| Error::PermissionDenied)
+    }
+
+    fn derive_key(&self, salt: Vec<u8>, iterations: u32) -> DerivedKey {
+        let mut key = [0; KEY_LEN];
+        pbkdf2::pbkdf2::<Hmac<Sha3_256>>(self.passphrase.as_bytes(), &salt, iterations, &mut key);
+        DerivedKey {
+            salt,
+            iterations,
+            key,
+        }
+    }
+}
+
+fn fill_random(bytes: &mut [u8]) -> Result<(), Error> {
+    OsRng
+        .try_fill_bytes(bytes)
+        .map_err(|e| Error::EntropyError(e.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let plaintext = br#"{"owner":{"last_update":1,"value":"secret"}}"#;
+        let cipher = Cipher::new("passphrase".into());
+        let contents = cipher.encrypt(plaintext).unwrap();
+        assert!(Cipher::is_encrypted(&contents));
+        assert!(!Cipher::is_encrypted(plaintext));
+        assert!(!String::from_utf8_lossy(&contents).contains("secret"));
+
+        assert_eq!(
+            Cipher::new("passphrase".into()).decrypt(&contents).unwrap(),
+            plaintext
+        );
+        assert_eq!(
+            Cipher::new("wrong passphrase".into()).decrypt(&contents),
+            Err(Error::PermissionDenied)
+        );
+        // Every write draws a new nonce
+        assert_ne!(cipher.encrypt(plaintext).unwrap(), contents);
+    }
+}
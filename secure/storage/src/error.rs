@@ -6,6 +6,7 @@ use std::io;
 use thiserror::Error;
 
 #[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(Clone))] //////// 0L ////////
 pub enum Error {
     #[error("Entropy error: {0}")]
     EntropyError(String),
@@ -63,6 +64,13 @@ impl From<diem_vault_client::Error> for Error {
     }
 }
 
+//////// 0L ////////
+impl From<yubihsm::client::Error> for Error {
+    fn from(error: yubihsm::client::Error) -> Self {
+        Self::InternalError(format!("YubiHSM: {}", error))
+    }
+}
+
 impl From<diem_github_client::Error> for Error {
     fn from(error: diem_github_client::Error) -> Self {
         match error {
@@ -0,0 +1,253 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::{CryptoStorage, Error, GetResponse, KVStorage, PublicKeyResponse};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use diem_infallible::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+/// The storage operations faults can be injected into. Versioned variants of an operation (e.g.,
+/// `sign_using_version`) share the operation of their unversioned counterpart.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Operation {
+    Available,
+    Get,
+    Set,
+    CreateKey,
+    ExportPrivateKey,
+    ImportPrivateKey,
+    GetPublicKey,
+    RotateKey,
+    Sign,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fault {
+    /// The operation fails with the error, without reaching the inner storage
+    Fail(Error),
+    /// The operation is delayed, then executed
+    Delay(Duration),
+    /// A get returns the value the key held before its latest set, as a lagging replica would.
+    /// Only applies to `Operation::Get`, other operations are executed normally.
+    Stale,
+}
+
+#[derive(Default)]
+struct Schedule {
+    once: HashMap<Operation, VecDeque<Fault>>,
+    always: HashMap<Operation, Fault>,
+}
+
+/// A handle to program the faults of a `FaultyStorage`. Handles are cheap to clone, so tests can
+/// keep one after moving the storage into the code under test.
+#[derive(Clone)]
+pub struct Faults {
+    schedule: Arc<Mutex<Schedule>>,
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Self {
+            schedule: Arc::new(Mutex::new(Schedule::default())),
+        }
+    }
+}
+
+impl Faults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Injects the fault into the next call of the operation. Faults injected into the same
+    /// operation are consumed in order.
+    pub fn inject(&self, operation: Operation, fault: Fault) {
+        self.schedule
+            .lock()
+            .once
+            .entry(operation)
+            .or_default()
+            .push_back(fault);
+    }
+
+    /// Injects the fault into every call of the operation that has no pending one-off fault, until
+    /// cleared
+    pub fn inject_always(&self, operation: Operation, fault: Fault) {
+        self.schedule.lock().always.insert(operation, fault);
+    }
+
+    /// Removes all faults, pending or persistent
+    pub fn clear(&self) {
+        let mut schedule = self.schedule.lock();
+        schedule.once.clear();
+        schedule.always.clear();
+    }
+
+    fn next(&self, operation: Operation) -> Option<Fault> {
+        let mut schedule = self.schedule.lock();
+        if let Some(fault) = schedule
+            .once
+            .get_mut(&operation)
+            .and_then(|faults| faults.pop_front())
+        {
+            return Some(fault);
+        }
+        schedule.always.get(&operation).cloned()
+    }
+
+    /// Applies the next fault of the operation, returning whether the result should be stale
+    fn apply(&self, operation: Operation) -> Result<bool, Error> {
+        match self.next(operation) {
+            Some(Fault::Fail(error)) => Err(error),
+            Some(Fault::Delay(duration)) => {
+                thread::sleep(duration);
+                Ok(false)
+            }
+            Some(Fault::Stale) => Ok(operation == Operation::Get),
+            None => Ok(false),
+        }
+    }
+}
+
+/// A testing wrapper around a storage that fails, delays or returns stale data on demand, to test
+/// flows that use secure storage against partial failures. Without injected faults, all operations
+/// are passed through to the inner storage.
+pub struct FaultyStorage<S> {
+    inner: S,
+    faults: Faults,
+    /// The value each key held before its latest set
+    previous: HashMap<String, GetResponse<serde_json::Value>>,
+}
+
+impl<S> FaultyStorage<S> {
+    pub fn new(inner: S, faults: Faults) -> Self {
+        Self {
+            inner,
+            faults,
+            previous: HashMap::new(),
+        }
+    }
+
+    pub fn faults(&self) -> &Faults {
+        &self.faults
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: KVStorage> KVStorage for FaultyStorage<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.faults.apply(Operation::Available)?;
+        self.inner.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        if !self.faults.apply(Operation::Get)? {
+            return self.inner.get(key);
+        }
+        let previous = self
+            .previous
+            .get(key)
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
+        let value = serde_json::from_value(previous.value.clone())?;
+        Ok(GetResponse::new(value, previous.last_update))
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.faults.apply(Operation::Set)?;
+        match self.inner.get::<serde_json::Value>(key) {
+            Ok(previous) => {
+                self.previous.insert(key.to_string(), previous);
+            }
+            Err(_) => {
+                self.previous.remove(key);
+            }
+        }
+        self.inner.set(key, value)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.previous.clear();
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for FaultyStorage<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.faults.apply(Operation::CreateKey)?;
+        self.inner.create_key(name)
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.faults.apply(Operation::ExportPrivateKey)?;
+        self.inner.export_private_key(name)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.faults.apply(Operation::ImportPrivateKey)?;
+        self.inner.import_private_key(name, key)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.faults.apply(Operation::ExportPrivateKey)?;
+        self.inner.export_private_key_for_version(name, version)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.faults.apply(Operation::GetPublicKey)?;
+        self.inner.get_public_key(name)
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.faults.apply(Operation::GetPublicKey)?;
+        self.inner.get_public_key_previous_version(name)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.faults.apply(Operation::RotateKey)?;
+        self.inner.rotate_key(name)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.faults.apply(Operation::Sign)?;
+        self.inner.sign(name, message)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.faults.apply(Operation::Sign)?;
+        self.inner.sign_using_version(name, version, message)
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! YubiHsmStorage keeps Ed25519 keys on a YubiHSM 2, reached through the yubihsm-connector, and
+//! signs with them on the device. The private keys never leave it: they are generated there, or
+//! imported once, and cannot be exported. Everything else, i.e. the waypoint, the safety data and
+//! the object ids of the keys, is kept in a second storage.
+//!
+//! Noise needs the x25519 network keys in memory for its handshakes, which the device cannot do,
+//! so the keys named in `exportable_keys` are kept in the second storage instead, and signing
+//! keys only are held by the device.
+
+use crate::{
+    namespaced::NAMESPACE_SEPARATOR, CryptoStorage, Error, GetResponse, KVStorage,
+    PublicKeyResponse, Storage,
+};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::convert::TryFrom;
+use yubihsm::{
+    asymmetric, authentication, object, Capability, Client, Connector, Credentials, Domain,
+    HttpConfig,
+};
+
+/// Suffix of the name under which the object ids of a key are kept in the second storage
+const KEY_IDS_SUFFIX: &str = "_yubihsm";
+/// The longest label of a YubiHSM object
+const LABEL_LEN: usize = 40;
+
+/// A version of a key held by the device
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct DeviceKey {
+    id: object::Id,
+    public_key: Ed25519PublicKey,
+}
+
+/// The versions of a key held by the device, at most two are retained as for the other storages
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct DeviceKeys {
+    current: DeviceKey,
+    previous: Option<DeviceKey>,
+}
+
+pub struct YubiHsmStorage {
+    client: Client,
+    storage: Box<Storage>,
+    domain: Domain,
+    /// Object ids from this one up are taken for new keys
+    first_key_id: object::Id,
+    exportable_keys: Vec<String>,
+}
+
+impl YubiHsmStorage {
+    /// `storage` holds the values and exportable keys, the device is only reached on first use
+    pub fn new(
+        client: Client,
+        storage: Storage,
+        domain: u8,
+        first_key_id: u16,
+        exportable_keys: Vec<String>,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            client,
+            storage: Box::new(storage),
+            domain: Domain::at(domain as usize)
+                .map_err(|e| Error::InternalError(format!("Invalid YubiHSM domain: {}", e)))?,
+            first_key_id,
+            exportable_keys,
+        })
+    }
+
+    /// A client of the yubihsm-connector at `addr`:`port`, authenticating with the password of
+    /// the authentication key `auth_key_id`
+    pub fn connect_http(
+        addr: String,
+        port: u16,
+        timeout_ms: u64,
+        auth_key_id: u16,
+        password: &str,
+    ) -> Result<Client, Error> {
+        let connector = Connector::http(&HttpConfig {
+            addr,
+            port,
+            timeout_ms,
+        });
+        let credentials = Credentials::new(
+            auth_key_id,
+            authentication::Key::derive_from_password(password.as_bytes()),
+        );
+        Ok(Client::create(connector, credentials)?)
+    }
+
+    fn is_exportable(&self, name: &str) -> bool {
+        let key = name
+            .rsplit_once(NAMESPACE_SEPARATOR)
+            .map(|(_, key)| key)
+            .unwrap_or(name);
+        self.exportable_keys
+            .iter()
+            .any(|exportable| exportable == key)
+    }
+
+    fn key_ids(&self, name: &str) -> Result<GetResponse<DeviceKeys>, Error> {
+        self.storage
+            .get(&format!("{}{}", name, KEY_IDS_SUFFIX))
+            .map_err(|e| match e {
+                Error::KeyNotSet(_) => Error::KeyNotSet(name.into()),
+                e => e,
+            })
+    }
+
+    fn set_key_ids(&mut self, name: &str, keys: DeviceKeys) -> Result<(), Error> {
+        self.storage
+            .set(&format!("{}{}", name, KEY_IDS_SUFFIX), keys)
+    }
+
+    fn version(&self, name: &str, version: &Ed25519PublicKey) -> Result<DeviceKey, Error> {
+        let keys = self.key_ids(name)?.value;
+        std::iter::once(keys.current)
+            .chain(keys.previous)
+            .find(|key| &key.public_key == version)
+            .ok_or_else(|| Error::KeyVersionNotFound(name.into(), version.to_string()))
+    }
+
+    /// The first object id free for a new key
+    fn free_key_id(&self) -> Result<object::Id, Error> {
+        (self.first_key_id..=object::Id::MAX)
+            .find(|id| {
+                self.client
+                    .get_object_info(*id, object::Type::AsymmetricKey)
+                    .is_err()
+            })
+            .ok_or_else(|| Error::InternalError("No object id left on the YubiHSM".into()))
+    }
+
+    /// Generates a key on the device, or puts `key` on it
+    fn put_key(&self, name: &str, key: Option<Ed25519PrivateKey>) -> Result<DeviceKey, Error> {
+        let id = self.free_key_id()?;
+        let label = object::Label::from(&name.chars().take(LABEL_LEN).collect::<String>()[..]);
+        match key {
+            Some(key) => self.client.put_asymmetric_key(
+                id,
+                label,
+                self.domain,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+                key.to_bytes().to_vec(),
+            )?,
+            None => self.client.generate_asymmetric_key(
+                id,
+                label,
+                self.domain,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )?,
+        };
+        let public_key = self.client.get_public_key(id)?;
+        Ok(DeviceKey {
+            id,
+            public_key: Ed25519PublicKey::try_from(public_key.as_ref())
+                .map_err(|e| Error::SerializationError(e.to_string()))?,
+        })
+    }
+
+    fn create_or_import(
+        &mut self,
+        name: &str,
+        key: Option<Ed25519PrivateKey>,
+    ) -> Result<Ed25519PublicKey, Error> {
+        match self.key_ids(name) {
+            Ok(_) => return Err(Error::KeyAlreadyExists(name.into())),
+            Err(Error::KeyNotSet(_)) => (/* Expected this for new keys! */),
+            Err(e) => return Err(e),
+        }
+        let current = self.put_key(name, key)?;
+        let public_key = current.public_key.clone();
+        self.set_key_ids(
+            name,
+            DeviceKeys {
+                current,
+                previous: None,
+            },
+        )?;
+        Ok(public_key)
+    }
+
+    fn sign_with(&self, key: &DeviceKey, message: &[u8]) -> Result<Ed25519Signature, Error> {
+        let signature = self.client.sign_ed25519(key.id, message)?;
+        Ed25519Signature::try_from(&signature.to_bytes()[..])
+            .map_err(|e| Error::SerializationError(e.to_string()))
+    }
+}
+
+pub(crate) fn signing_bytes<T: CryptoHash + Serialize>(message: &T) -> Result<Vec<u8>, Error> {
+    let mut bytes = <T::Hasher as diem_crypto::hash::CryptoHasher>::seed().to_vec();
+    bcs::serialize_into(&mut bytes, &message).map_err(|e| {
+        Error::InternalError(format!(
+            "Serialization of signable material should not fail, yet returned Error:{}",
+            e
+        ))
+    })?;
+    Ok(bytes)
+}
+
+impl KVStorage for YubiHsmStorage {
+    fn available(&self) -> Result<(), Error> {
+        self.client.device_info()?;
+        self.storage.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.storage.get(key)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.storage.set(key, value)
+    }
+
+    /// Only clears the second storage, the keys are left on the device
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.storage.reset_and_clear()
+    }
+}
+
+impl CryptoStorage for YubiHsmStorage {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        if self.is_exportable(name) {
+            return self.storage.create_key(name);
+        }
+        self.create_or_import(name, None)
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        if self.is_exportable(name) {
+            return self.storage.export_private_key(name);
+        }
+        Err(Error::PermissionDenied)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        if self.is_exportable(name) {
+            return self.storage.import_private_key(name, key);
+        }
+        self.create_or_import(name, Some(key)).map(|_| ())
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        if self.is_exportable(name) {
+            return self.storage.export_private_key_for_version(name, version);
+        }
+        Err(Error::PermissionDenied)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        if self.is_exportable(name) {
+            return self.storage.get_public_key(name);
+        }
+        let keys = self.key_ids(name)?;
+        Ok(PublicKeyResponse {
+            last_update: keys.last_update,
+            public_key: keys.value.current.public_key,
+        })
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        if self.is_exportable(name) {
+            return self.storage.get_public_key_previous_version(name);
+        }
+        self.key_ids(name)?
+            .value
+            .previous
+            .map(|key| key.public_key)
+            .ok_or_else(|| Error::KeyVersionNotFound(name.into(), "previous version".into()))
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        if self.is_exportable(name) {
+            return self.storage.rotate_key(name);
+        }
+        let keys = self.key_ids(name)?.value;
+        let current = self.put_key(name, None)?;
+        let public_key = current.public_key.clone();
+        self.set_key_ids(
+            name,
+            DeviceKeys {
+                current,
+                previous: Some(keys.current),
+            },
+        )?;
+        if let Some(dropped) = keys.previous {
+            self.client
+                .delete_object(dropped.id, object::Type::AsymmetricKey)?;
+        }
+        Ok(public_key)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        if self.is_exportable(name) {
+            return self.storage.sign(name, message);
+        }
+        let key = self.key_ids(name)?.value.current;
+        self.sign_with(&key, &signing_bytes(message)?)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        if self.is_exportable(name) {
+            return self.storage.sign_using_version(name, version, message);
+        }
+        let key = self.version(name, &version)?;
+        self.sign_with(&key, &signing_bytes(message)?)
+    }
+}
@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{CryptoKVStorage, Error, GetResponse, KVStorage};
+use diem_infallible::RwLock;
 use diem_time_service::{TimeService, TimeServiceTrait};
 use serde::{de::DeserializeOwned, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 /// InMemoryStorage represents a key value store that is purely in memory and intended for single
 /// threads (or must be wrapped by a Arc<RwLock<>>). This provides no permission checks and simply
@@ -12,9 +13,11 @@ use std::collections::HashMap;
 /// Internally, it retains all data, which means that it must make copies of all key material which
 /// violates the Diem code base. It violates it because the anticipation is that data stores would
 /// securely handle key material. This should not be used in production.
-#[derive(Default)]
+///
+/// Clones share their data, so that a store can be handed out under several namespaces.
+#[derive(Clone, Default)]
 pub struct InMemoryStorage {
-    data: HashMap<String, Vec<u8>>,
+    data: Arc<RwLock<HashMap<String, Vec<u8>>>>, //////// 0L ////////
     time_service: TimeService,
 }
 
@@ -27,7 +30,7 @@ impl InMemoryStorage {
 impl InMemoryStorage {
     pub fn new_with_time_service(time_service: TimeService) -> Self {
         Self {
-            data: HashMap::new(),
+            data: Arc::new(RwLock::new(HashMap::new())),
             time_service,
         }
     }
@@ -39,8 +42,8 @@ impl KVStorage for InMemoryStorage {
     }
 
     fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
-        let response = self
-            .data
+        let data = self.data.read();
+        let response = data
             .get(key)
             .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
 
@@ -49,7 +52,7 @@ impl KVStorage for InMemoryStorage {
 
     fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
         let now = self.time_service.now_secs();
-        self.data.insert(
+        self.data.write().insert(
             key.to_string(),
             serde_json::to_vec(&GetResponse::new(value, now))?,
         );
@@ -58,7 +61,7 @@ impl KVStorage for InMemoryStorage {
 
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
-        self.data.clear();
+        self.data.write().clear();
         Ok(())
     }
 }
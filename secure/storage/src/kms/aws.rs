@@ -0,0 +1,244 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! A client of AWS KMS over its JSON API, with requests signed by AWS Signature Version 4. The
+//! keys must be asymmetric signing keys of spec `ECC_NIST_EDWARDS25519`, referenced by key id,
+//! alias or ARN, and the credentials allowed `kms:GetPublicKey` and `kms:Sign` on them.
+
+use super::{ed25519_from_spki, ed25519_signature, field, send, KmsClient};
+use crate::Error;
+use chrono::{DateTime, Duration, Utc};
+use diem_crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use diem_infallible::Mutex;
+use hmac::{Hmac, Mac, NewMac};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::env;
+
+const SERVICE: &str = "kms";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+const SIGNING_ALGORITHM: &str = "ED25519_SHA_512";
+/// The instance metadata service of EC2, in its session oriented version (IMDSv2)
+const INSTANCE_METADATA: &str = "http://169.254.169.254/latest";
+const INSTANCE_METADATA_TTL_SECS: &str = "21600";
+/// Temporary credentials are renewed this long before they expire
+const RENEWAL_MARGIN_SECS: i64 = 300;
+
+/// Where the client takes its credentials from
+#[derive(Clone, Debug, PartialEq)]
+pub enum AwsCredentialSource {
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and, for temporary credentials,
+    /// `AWS_SESSION_TOKEN`
+    Environment,
+    /// The role of the EC2 instance, from the instance metadata service
+    InstanceMetadata,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    #[serde(rename = "Token")]
+    pub session_token: Option<String>,
+    /// None for long-term credentials
+    pub expiration: Option<String>,
+}
+
+impl Credentials {
+    fn renew_at(&self) -> Result<Option<DateTime<Utc>>, Error> {
+        match &self.expiration {
+            Some(expiration) => {
+                let expiration = DateTime::parse_from_rfc3339(expiration)?.with_timezone(&Utc);
+                Ok(Some(expiration - Duration::seconds(RENEWAL_MARGIN_SECS)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+pub struct AwsKmsClient {
+    region: String,
+    source: AwsCredentialSource,
+    timeout_ms: u64,
+    credentials: Mutex<Option<Credentials>>,
+}
+
+impl AwsKmsClient {
+    pub fn new(region: String, source: AwsCredentialSource, timeout_ms: u64) -> Self {
+        Self {
+            region,
+            source,
+            timeout_ms,
+            credentials: Mutex::new(None),
+        }
+    }
+
+    fn credentials(&self) -> Result<Credentials, Error> {
+        let mut cached = self.credentials.lock();
+        if let Some(credentials) = cached.as_ref() {
+            match credentials.renew_at()? {
+                Some(renew_at) if renew_at <= Utc::now() => (),
+                _ => return Ok(credentials.clone()),
+            }
+        }
+        let credentials = match self.source {
+            AwsCredentialSource::Environment => environment_credentials()?,
+            AwsCredentialSource::InstanceMetadata => self.instance_credentials()?,
+        };
+        *cached = Some(credentials.clone());
+        Ok(credentials)
+    }
+
+    fn instance_credentials(&self) -> Result<Credentials, Error> {
+        let token = send(
+            ureq::put(&format!("{}/api/token", INSTANCE_METADATA)).set(
+                "X-aws-ec2-metadata-token-ttl-seconds",
+                INSTANCE_METADATA_TTL_SECS,
+            ),
+            self.timeout_ms,
+            Some(""),
+        )?;
+        let get = |path: &str| {
+            send(
+                ureq::get(&format!("{}/meta-data/{}", INSTANCE_METADATA, path))
+                    .set("X-aws-ec2-metadata-token", &token),
+                self.timeout_ms,
+                None,
+            )
+        };
+        let roles = get("iam/security-credentials/")?;
+        let role = roles.lines().next().ok_or_else(|| {
+            Error::InternalError("The EC2 instance has no IAM role attached".into())
+        })?;
+        Ok(serde_json::from_str(&get(&format!(
+            "iam/security-credentials/{}",
+            role
+        ))?)?)
+    }
+
+    /// Calls the action `target` of the KMS API, e.g., `TrentService.Sign`
+    fn call(&self, target: &str, body: serde_json::Value) -> Result<serde_json::Value, Error> {
+        let credentials = self.credentials()?;
+        let host = format!("{}.{}.amazonaws.com", SERVICE, self.region);
+        let body = body.to_string();
+        let mut request = ureq::post(&format!("https://{}/", host));
+        for (name, value) in
+            signed_headers(&credentials, &self.region, &host, target, &body, Utc::now())
+        {
+            request.set(name, &value);
+        }
+        Ok(serde_json::from_str(&send(
+            &mut request,
+            self.timeout_ms,
+            Some(&body),
+        )?)?)
+    }
+}
+
+impl KmsClient for AwsKmsClient {
+    fn public_key(&self, key_id: &str) -> Result<Ed25519PublicKey, Error> {
+        let response = self.call("TrentService.GetPublicKey", json!({ "KeyId": key_id }))?;
+        ed25519_from_spki(&base64::decode(field(&response, "PublicKey")?)?)
+    }
+
+    fn sign(&self, key_id: &str, message: &[u8]) -> Result<Ed25519Signature, Error> {
+        let response = self.call(
+            "TrentService.Sign",
+            json!({
+                "KeyId": key_id,
+                "Message": base64::encode(message),
+                "MessageType": "RAW",
+                "SigningAlgorithm": SIGNING_ALGORITHM,
+            }),
+        )?;
+        ed25519_signature(&base64::decode(field(&response, "Signature")?)?)
+    }
+}
+
+fn environment_credentials() -> Result<Credentials, Error> {
+    let var = |name: &str| {
+        env::var(name).map_err(|_| Error::InternalError(format!("{} is not set", name)))
+    };
+    Ok(Credentials {
+        access_key_id: var("AWS_ACCESS_KEY_ID")?,
+        secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+        session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        expiration: None,
+    })
+}
+
+/// The headers of a POST of `body` to the root of `host`, signed with Signature Version 4. The
+/// host header is set by the HTTP client, it is signed but not returned.
+pub(crate) fn signed_headers(
+    credentials: &Credentials,
+    region: &str,
+    host: &str,
+    target: &str,
+    body: &str,
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, String)> {
+    let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    // In the order of their names, as canonical requests list them
+    let mut headers = vec![
+        ("content-type", CONTENT_TYPE.to_string()),
+        ("host", host.to_string()),
+        ("x-amz-date", timestamp.clone()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    headers.push(("x-amz-target", target.to_string()));
+
+    let names = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let canonical_request = format!(
+        "POST\n/\n\n{}\n{}\n{}",
+        canonical_headers,
+        names,
+        hex::encode(Sha256::digest(body.as_bytes()))
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, SERVICE);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(&credentials.secret_access_key, &date, region, SERVICE);
+    let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+    headers.retain(|(name, _)| *name != "host");
+    headers.push((
+        "authorization",
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, names, signature
+        ),
+    ));
+    headers
+}
+
+/// The key of the requests of a day to `service` in `region`
+pub(crate) fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC takes keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,116 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! A client of GCP Cloud KMS over its REST API. The keys must be asymmetric signing key versions
+//! of algorithm `EC_SIGN_ED25519`, referenced by resource name, i.e.
+//! `projects/P/locations/L/keyRings/R/cryptoKeys/K/cryptoKeyVersions/V`, and the service account
+//! granted `roles/cloudkms.signerVerifier` on them.
+
+use super::{ed25519_from_pem, ed25519_signature, field, send, KmsClient};
+use crate::Error;
+use chrono::{DateTime, Duration, Utc};
+use diem_crypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use diem_infallible::Mutex;
+use serde::Deserialize;
+use serde_json::json;
+
+const ENDPOINT: &str = "https://cloudkms.googleapis.com/v1";
+const METADATA_TOKEN: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+/// Access tokens are renewed this long before they expire
+const RENEWAL_MARGIN_SECS: i64 = 300;
+
+/// Where the client takes its access tokens from
+#[derive(Clone, Debug, PartialEq)]
+pub enum GcpCredentialSource {
+    /// The service account of the GCE instance or GKE workload, from the metadata server
+    Metadata,
+    /// An OAuth access token, e.g., from `gcloud auth print-access-token`, used until the process
+    /// restarts
+    AccessToken(String),
+}
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: i64,
+}
+
+struct AccessToken {
+    token: String,
+    renew_at: DateTime<Utc>,
+}
+
+pub struct GcpKmsClient {
+    source: GcpCredentialSource,
+    timeout_ms: u64,
+    token: Mutex<Option<AccessToken>>,
+}
+
+impl GcpKmsClient {
+    pub fn new(source: GcpCredentialSource, timeout_ms: u64) -> Self {
+        Self {
+            source,
+            timeout_ms,
+            token: Mutex::new(None),
+        }
+    }
+
+    fn token(&self) -> Result<String, Error> {
+        if let GcpCredentialSource::AccessToken(token) = &self.source {
+            return Ok(token.clone());
+        }
+        let mut cached = self.token.lock();
+        if let Some(token) = cached.as_ref() {
+            if Utc::now() < token.renew_at {
+                return Ok(token.token.clone());
+            }
+        }
+        let response: MetadataToken = serde_json::from_str(&send(
+            ureq::get(METADATA_TOKEN).set("Metadata-Flavor", "Google"),
+            self.timeout_ms,
+            None,
+        )?)?;
+        *cached = Some(AccessToken {
+            token: response.access_token.clone(),
+            renew_at: Utc::now() + Duration::seconds(response.expires_in - RENEWAL_MARGIN_SECS),
+        });
+        Ok(response.access_token)
+    }
+
+    fn call(
+        &self,
+        mut request: ureq::Request,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        request.set("Authorization", &format!("Bearer {}", self.token()?));
+        let body = body.map(|body| body.to_string());
+        if body.is_some() {
+            request.set("Content-Type", "application/json");
+        }
+        Ok(serde_json::from_str(&send(
+            &mut request,
+            self.timeout_ms,
+            body.as_deref(),
+        )?)?)
+    }
+}
+
+impl KmsClient for GcpKmsClient {
+    fn public_key(&self, key_id: &str) -> Result<Ed25519PublicKey, Error> {
+        let response = self.call(
+            ureq::get(&format!("{}/{}/publicKey", ENDPOINT, key_id)),
+            None,
+        )?;
+        ed25519_from_pem(field(&response, "pem")?)
+    }
+
+    fn sign(&self, key_id: &str, message: &[u8]) -> Result<Ed25519Signature, Error> {
+        let response = self.call(
+            ureq::post(&format!("{}/{}:asymmetricSign", ENDPOINT, key_id)),
+            Some(json!({ "data": base64::encode(message) })),
+        )?;
+        ed25519_signature(&base64::decode(field(&response, "signature")?)?)
+    }
+}
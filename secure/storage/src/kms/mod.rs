@@ -0,0 +1,271 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! KmsStorage signs with Ed25519 keys held by a cloud key management service, AWS KMS or GCP Cloud
+//! KMS. The keys are created in the KMS, under the IAM policies of the operator, and the private
+//! keys never leave it: they cannot be generated, imported, exported nor rotated through the
+//! storage. Their public keys are read from the KMS on first use and cached. Everything else, i.e.
+//! the waypoint, the safety data and the keys not held by the KMS, is kept in a second storage.
+//!
+//! As for YubiHsmStorage, Noise needs the x25519 network keys in memory for its handshakes, which
+//! no KMS offers, so network keys are left to the second storage.
+
+pub(crate) mod aws;
+mod gcp;
+
+pub use aws::{AwsCredentialSource, AwsKmsClient};
+pub use gcp::{GcpCredentialSource, GcpKmsClient};
+
+use crate::{
+    hsm::signing_bytes, namespaced::NAMESPACE_SEPARATOR, CryptoStorage, Error, GetResponse,
+    KVStorage, PublicKeyResponse, Storage,
+};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+    Signature,
+};
+use diem_infallible::RwLock;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, convert::TryFrom, time::Duration};
+
+/// The DER prefix of an Ed25519 SubjectPublicKeyInfo, the form the KMSes return public keys in
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// A KMS holding Ed25519 keys, by key id
+pub trait KmsClient: Send + Sync {
+    fn public_key(&self, key_id: &str) -> Result<Ed25519PublicKey, Error>;
+
+    /// Signs `message` as is, with pure Ed25519
+    fn sign(&self, key_id: &str, message: &[u8]) -> Result<Ed25519Signature, Error>;
+}
+
+pub struct KmsStorage {
+    client: Box<dyn KmsClient>,
+    storage: Box<Storage>,
+    /// The KMS key id of each key held by the KMS, by key name without namespace
+    keys: HashMap<String, String>,
+    /// The public keys read from the KMS, by key id
+    public_keys: RwLock<HashMap<String, Ed25519PublicKey>>,
+}
+
+impl KmsStorage {
+    /// `storage` holds the values and the keys not in `keys`, the KMS is only reached on first use
+    pub fn new(
+        client: Box<dyn KmsClient>,
+        storage: Storage,
+        keys: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            client,
+            storage: Box::new(storage),
+            keys,
+            public_keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The KMS key id of `name`, None if the key is kept in the second storage
+    fn key_id(&self, name: &str) -> Option<&str> {
+        let key = name
+            .rsplit_once(NAMESPACE_SEPARATOR)
+            .map(|(_, key)| key)
+            .unwrap_or(name);
+        self.keys.get(key).map(String::as_str)
+    }
+
+    fn public_key(&self, key_id: &str) -> Result<Ed25519PublicKey, Error> {
+        if let Some(public_key) = self.public_keys.read().get(key_id) {
+            return Ok(public_key.clone());
+        }
+        let public_key = self.client.public_key(key_id)?;
+        self.public_keys
+            .write()
+            .insert(key_id.into(), public_key.clone());
+        Ok(public_key)
+    }
+
+    fn sign_with(
+        &self,
+        name: &str,
+        key_id: &str,
+        message: &[u8],
+    ) -> Result<Ed25519Signature, Error> {
+        let public_key = self.public_key(key_id)?;
+        let signature = self.client.sign(key_id, message)?;
+        // A key id pointing at another key must not sign in the name of this one
+        signature
+            .verify_arbitrary_msg(message, &public_key)
+            .map_err(|e| {
+                Error::InternalError(format!("KMS signature of {} does not verify: {}", name, e))
+            })?;
+        Ok(signature)
+    }
+}
+
+impl KVStorage for KmsStorage {
+    /// Also reads the public keys of the KMS, which checks the credentials and the keys
+    fn available(&self) -> Result<(), Error> {
+        for key_id in self.keys.values() {
+            self.public_key(key_id)?;
+        }
+        self.storage.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.storage.get(key)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.storage.set(key, value)
+    }
+
+    /// Only clears the second storage, the keys are left in the KMS
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.storage.reset_and_clear()
+    }
+}
+
+impl CryptoStorage for KmsStorage {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        match self.key_id(name) {
+            Some(_) => Err(Error::PermissionDenied),
+            None => self.storage.create_key(name),
+        }
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        match self.key_id(name) {
+            Some(_) => Err(Error::PermissionDenied),
+            None => self.storage.export_private_key(name),
+        }
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        match self.key_id(name) {
+            Some(_) => Err(Error::PermissionDenied),
+            None => self.storage.import_private_key(name, key),
+        }
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        match self.key_id(name) {
+            Some(_) => Err(Error::PermissionDenied),
+            None => self.storage.export_private_key_for_version(name, version),
+        }
+    }
+
+    /// The public key of a KMS key has no last update, it is reported as 0
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        match self.key_id(name) {
+            Some(key_id) => Ok(PublicKeyResponse {
+                last_update: 0,
+                public_key: self.public_key(key_id)?,
+            }),
+            None => self.storage.get_public_key(name),
+        }
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        match self.key_id(name) {
+            Some(_) => Err(Error::KeyVersionNotFound(
+                name.into(),
+                "previous version".into(),
+            )),
+            None => self.storage.get_public_key_previous_version(name),
+        }
+    }
+
+    /// KMS keys are rotated in the KMS, by pointing the config at a new key
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        match self.key_id(name) {
+            Some(_) => Err(Error::PermissionDenied),
+            None => self.storage.rotate_key(name),
+        }
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        match self.key_id(name) {
+            Some(key_id) => self.sign_with(name, key_id, &signing_bytes(message)?),
+            None => self.storage.sign(name, message),
+        }
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let key_id = match self.key_id(name) {
+            Some(key_id) => key_id,
+            None => return self.storage.sign_using_version(name, version, message),
+        };
+        if self.public_key(key_id)? != version {
+            return Err(Error::KeyVersionNotFound(name.into(), version.to_string()));
+        }
+        self.sign_with(name, key_id, &signing_bytes(message)?)
+    }
+}
+
+/// The Ed25519 public key of a DER SubjectPublicKeyInfo
+pub(crate) fn ed25519_from_spki(der: &[u8]) -> Result<Ed25519PublicKey, Error> {
+    let key = der
+        .strip_prefix(&ED25519_SPKI_PREFIX[..])
+        .ok_or_else(|| Error::SerializationError("KMS key is not an Ed25519 key".into()))?;
+    Ed25519PublicKey::try_from(key).map_err(|e| Error::SerializationError(e.to_string()))
+}
+
+/// The Ed25519 public key of a PEM SubjectPublicKeyInfo
+pub(crate) fn ed25519_from_pem(pem: &str) -> Result<Ed25519PublicKey, Error> {
+    let der: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+        .collect();
+    ed25519_from_spki(&base64::decode(der)?)
+}
+
+pub(crate) fn ed25519_signature(bytes: &[u8]) -> Result<Ed25519Signature, Error> {
+    Ed25519Signature::try_from(bytes).map_err(|e| Error::SerializationError(e.to_string()))
+}
+
+/// The string field `name` of a KMS response
+fn field<'a>(response: &'a serde_json::Value, name: &str) -> Result<&'a str, Error> {
+    response[name]
+        .as_str()
+        .ok_or_else(|| Error::SerializationError(format!("KMS response has no {}", name)))
+}
+
+/// Sends `request`, with `body` if any, and returns the body of the response
+fn send(request: &mut ureq::Request, timeout_ms: u64, body: Option<&str>) -> Result<String, Error> {
+    request.timeout_connect(timeout_ms);
+    request.timeout(Duration::from_millis(timeout_ms));
+    let response = match body {
+        Some(body) => request.send_string(body),
+        None => request.call(),
+    };
+    if let Some(e) = response.synthetic_error() {
+        return Err(Error::InternalError(format!("KMS: {}", e)));
+    }
+    let status = response.status();
+    let body = response.into_string()?;
+    match status {
+        200..=299 => Ok(body),
+        401 | 403 => Err(Error::PermissionDenied),
+        // AWS reports missing permissions as a client error
+        400 if body.contains("AccessDeniedException") => Err(Error::PermissionDenied),
+        _ => Err(Error::InternalError(format!("KMS: {}: {}", status, body))),
+    }
+}
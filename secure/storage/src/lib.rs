@@ -5,9 +5,15 @@
 
 mod crypto_kv_storage;
 mod crypto_storage;
+mod encryption; //////// 0L ////////
 mod error;
+//////// 0L ////////
+#[cfg(any(test, feature = "testing"))]
+mod faulty;
 mod github;
+mod hsm; //////// 0L ////////
 mod in_memory;
+mod kms; //////// 0L ////////
 mod kv_storage;
 mod namespaced;
 mod on_disk;
@@ -20,7 +26,11 @@ pub use crate::{
     crypto_storage::{CryptoStorage, PublicKeyResponse},
     error::Error,
     github::GitHubStorage,
+    hsm::YubiHsmStorage, //////// 0L ////////
     in_memory::InMemoryStorage,
+    kms::{
+        AwsCredentialSource, AwsKmsClient, GcpCredentialSource, GcpKmsClient, KmsClient, KmsStorage,
+    }, //////// 0L ////////
     kv_storage::{GetResponse, KVStorage},
     namespaced::Namespaced,
     on_disk::OnDiskStorage,
@@ -29,6 +39,10 @@ pub use crate::{
     vault::VaultStorage,
 };
 
+//////// 0L ////////
+#[cfg(any(test, feature = "testing"))]
+pub use crate::faulty::{Fault, FaultyStorage, Faults, Operation};
+
 // Some common serializations for interacting with bytes these must be manually added to types via:
 // #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
 // some_value: Vec<u8>
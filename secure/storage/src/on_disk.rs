@@ -1,7 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CryptoKVStorage, Error, GetResponse, KVStorage};
+use crate::{encryption::Cipher, CryptoKVStorage, Error, GetResponse, KVStorage};
 use diem_temppath::TempPath;
 use diem_time_service::{TimeService, TimeServiceTrait};
 use serde::{de::DeserializeOwned, Serialize};
@@ -20,10 +20,14 @@ use std::{
 /// must make copies of all key material which violates the Diem code base. It violates it because
 /// the anticipation is that data stores would securely handle key material. This should not be used
 /// in production.
+//////// 0L ////////
+/// With a passphrase, the file is encrypted at rest, see `encryption`.
 pub struct OnDiskStorage {
     file_path: PathBuf,
     temp_path: TempPath,
     time_service: TimeService,
+    //////// 0L ////////
+    cipher: Option<Cipher>,
 }
 
 impl OnDiskStorage {
@@ -31,6 +35,22 @@ impl OnDiskStorage {
         Self::new_with_time_service(file_path, TimeService::real())
     }
 
+    //////// 0L ////////
+    /// A storage whose file is encrypted with `passphrase`
+    pub fn new_encrypted(file_path: PathBuf, passphrase: String) -> Self {
+        let mut storage = Self::new(file_path);
+        storage.cipher = Some(Cipher::new(passphrase));
+        storage
+    }
+
+    /// Rewrites the file of the storage encrypted with `passphrase`, or in plaintext without one.
+    /// The storage must be created with the passphrase the file is currently encrypted with.
+    pub fn migrate(&mut self, passphrase: Option<String>) -> Result<(), Error> {
+        let data = self.read()?;
+        self.cipher = passphrase.map(Cipher::new);
+        self.write(&data)
+    }
+
     fn new_with_time_service(file_path: PathBuf, time_service: TimeService) -> Self {
         if !file_path.exists() {
             File::create(&file_path).expect("Unable to create storage");
@@ -46,22 +66,45 @@ impl OnDiskStorage {
             file_path,
             temp_path: TempPath::new_with_temp_dir(file_dir),
             time_service,
+            cipher: None,
         }
     }
 
     fn read(&self) -> Result<HashMap<String, Value>, Error> {
         let mut file = File::open(&self.file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
         if contents.is_empty() {
             return Ok(HashMap::new());
         }
-        let data = serde_json::from_str(&contents)?;
+        //////// 0L ////////
+        let contents = match (&self.cipher, Cipher::is_encrypted(&contents)) {
+            (Some(cipher), true) => cipher.decrypt(&contents)?,
+            (None, false) => contents,
+            (Some(_), false) => {
+                return Err(Error::InternalError(format!(
+                    "{:?} is not encrypted, migrate it to an encrypted storage first",
+                    self.file_path
+                )))
+            }
+            (None, true) => {
+                return Err(Error::InternalError(format!(
+                    "{:?} is encrypted, a passphrase is needed to read it",
+                    self.file_path
+                )))
+            }
+        };
+        let data = serde_json::from_slice(&contents)?;
         Ok(data)
     }
 
     fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
         let contents = serde_json::to_vec(data)?;
+        //////// 0L ////////
+        let contents = match &self.cipher {
+            Some(cipher) => cipher.encrypt(&contents)?,
+            None => contents,
+        };
         let mut file = File::create(self.temp_path.path())?;
         file.write_all(&contents)?;
         fs::rename(&self.temp_path, &self.file_path)?;
@@ -1,8 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
+#[cfg(any(test, feature = "testing"))]
+use crate::FaultyStorage;
 use crate::{
-    CryptoStorage, Error, GetResponse, GitHubStorage, InMemoryStorage, KVStorage, Namespaced,
-    OnDiskStorage, PublicKeyResponse, VaultStorage,
+    CryptoStorage, Error, GetResponse, GitHubStorage, InMemoryStorage, KVStorage, KmsStorage,
+    Namespaced, OnDiskStorage, PublicKeyResponse, VaultStorage, YubiHsmStorage,
 };
 use diem_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
 use enum_dispatch::enum_dispatch;
@@ -21,6 +23,11 @@ pub enum Storage {
     InMemoryStorage(InMemoryStorage),
     NamespacedStorage(Namespaced<Box<Storage>>),
     OnDiskStorage(OnDiskStorage),
+    //////// 0L ////////
+    #[cfg(any(test, feature = "testing"))]
+    FaultyStorage(FaultyStorage<Box<Storage>>),
+    YubiHsmStorage(YubiHsmStorage),
+    KmsStorage(KmsStorage),
 }
 
 impl KVStorage for Box<Storage> {
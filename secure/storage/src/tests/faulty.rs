@@ -0,0 +1,74 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    tests::suite, CryptoStorage, Error, Fault, Faults, FaultyStorage, InMemoryStorage, KVStorage,
+    Operation, Storage,
+};
+use std::time::{Duration, Instant};
+
+const KEY: &str = "key";
+
+fn faulty_storage() -> (Storage, Faults) {
+    let faults = Faults::new();
+    let inner = Box::new(Storage::from(InMemoryStorage::new()));
+    (
+        Storage::from(FaultyStorage::new(inner, faults.clone())),
+        faults,
+    )
+}
+
+#[test]
+fn faulty_without_faults() {
+    let (mut storage, _) = faulty_storage();
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+#[test]
+fn faulty_fail() {
+    let (mut storage, faults) = faulty_storage();
+    faults.inject(Operation::Set, Fault::Fail(Error::PermissionDenied));
+    assert_eq!(storage.set(KEY, 1u64), Err(Error::PermissionDenied));
+    assert_eq!(
+        storage.get::<u64>(KEY).unwrap_err(),
+        Error::KeyNotSet(KEY.into())
+    );
+
+    // One-off faults are consumed
+    storage.set(KEY, 1u64).unwrap();
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 1);
+
+    let error = Error::InternalError("unavailable".into());
+    faults.inject_always(Operation::RotateKey, Fault::Fail(error.clone()));
+    storage.create_key(KEY).unwrap();
+    assert_eq!(storage.rotate_key(KEY).unwrap_err(), error);
+    assert_eq!(storage.rotate_key(KEY).unwrap_err(), error);
+    faults.clear();
+    storage.rotate_key(KEY).unwrap();
+}
+
+#[test]
+fn faulty_delay() {
+    let (storage, faults) = faulty_storage();
+    let delay = Duration::from_millis(50);
+    faults.inject(Operation::Available, Fault::Delay(delay));
+    let start = Instant::now();
+    storage.available().unwrap();
+    assert!(start.elapsed() >= delay);
+}
+
+#[test]
+fn faulty_stale() {
+    let (mut storage, faults) = faulty_storage();
+    faults.inject(Operation::Get, Fault::Stale);
+    storage.set(KEY, 1u64).unwrap();
+    assert_eq!(
+        storage.get::<u64>(KEY).unwrap_err(),
+        Error::KeyNotSet(KEY.into())
+    );
+
+    storage.set(KEY, 2u64).unwrap();
+    faults.inject(Operation::Get, Fault::Stale);
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 1);
+    assert_eq!(storage.get::<u64>(KEY).unwrap().value, 2);
+}
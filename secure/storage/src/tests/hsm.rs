@@ -0,0 +1,82 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::{
+    CryptoStorage, Error, InMemoryStorage, KVStorage, Namespaced, Storage, YubiHsmStorage,
+};
+use diem_crypto::{
+    ed25519::Ed25519PrivateKey, test_utils::TestDiemCrypto, PrivateKey, Signature, Uniform,
+};
+use yubihsm::{Client, Connector, Credentials};
+
+const CONSENSUS_KEY: &str = "consensus";
+const NETWORK_KEY: &str = "validator_network";
+
+fn storage() -> Storage {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    let storage = YubiHsmStorage::new(
+        client,
+        Storage::from(InMemoryStorage::new()),
+        1,
+        0x100,
+        vec![NETWORK_KEY.into()],
+    )
+    .unwrap();
+    Storage::from(Namespaced::new("alice", Box::new(Storage::from(storage))))
+}
+
+#[test]
+fn yubihsm_signs_on_device() {
+    let mut storage = storage();
+    storage.available().unwrap();
+    let key = Ed25519PrivateKey::generate_for_testing();
+    storage
+        .import_private_key(CONSENSUS_KEY, key.clone())
+        .unwrap();
+    let public_key = storage.get_public_key(CONSENSUS_KEY).unwrap().public_key;
+    assert_eq!(public_key, key.public_key());
+    assert_eq!(
+        storage.export_private_key(CONSENSUS_KEY),
+        Err(Error::PermissionDenied)
+    );
+    assert_eq!(
+        storage.import_private_key(CONSENSUS_KEY, key),
+        Err(Error::KeyAlreadyExists("alice/consensus".into()))
+    );
+
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = storage.sign(CONSENSUS_KEY, &message).unwrap();
+    signature.verify(&message, &public_key).unwrap();
+
+    // The previous version is kept on the device and still signs
+    let rotated = storage.rotate_key(CONSENSUS_KEY).unwrap();
+    assert_ne!(rotated, public_key);
+    assert_eq!(
+        storage
+            .get_public_key_previous_version(CONSENSUS_KEY)
+            .unwrap(),
+        public_key
+    );
+    storage
+        .sign(CONSENSUS_KEY, &message)
+        .unwrap()
+        .verify(&message, &rotated)
+        .unwrap();
+    storage
+        .sign_using_version(CONSENSUS_KEY, public_key.clone(), &message)
+        .unwrap()
+        .verify(&message, &public_key)
+        .unwrap();
+}
+
+#[test]
+fn yubihsm_keeps_exportable_keys() {
+    let mut storage = storage();
+    let public_key = storage.create_key(NETWORK_KEY).unwrap();
+    let key = storage.export_private_key(NETWORK_KEY).unwrap();
+    assert_eq!(key.public_key(), public_key);
+
+    storage.set("waypoint", 5).unwrap();
+    assert_eq!(storage.get::<u64>("waypoint").unwrap().value, 5);
+}
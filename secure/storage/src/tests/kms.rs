@@ -0,0 +1,173 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+use crate::{
+    kms::{aws::signing_key, ed25519_from_pem},
+    CryptoStorage, Error, InMemoryStorage, KVStorage, KmsClient, KmsStorage, Namespaced, Storage,
+};
+use diem_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    test_utils::TestDiemCrypto,
+    PrivateKey, Signature, SigningKey, Uniform,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+const CONSENSUS_KEY: &str = "consensus";
+const NETWORK_KEY: &str = "validator_network";
+const KEY_ID: &str = "alias/alice-consensus";
+
+/// A key other than the one of `generate_for_testing`, which always returns the same key
+fn other_key() -> Ed25519PrivateKey {
+    Ed25519PrivateKey::generate(&mut StdRng::from_seed([44u8; 32]))
+}
+
+/// A KMS holding a key, counting the reads of its public key
+struct TestKms {
+    key: Ed25519PrivateKey,
+    reads: Arc<AtomicUsize>,
+}
+
+impl KmsClient for TestKms {
+    fn public_key(&self, key_id: &str) -> Result<Ed25519PublicKey, Error> {
+        assert_eq!(key_id, KEY_ID);
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        Ok(self.key.public_key())
+    }
+
+    fn sign(&self, key_id: &str, message: &[u8]) -> Result<Ed25519Signature, Error> {
+        assert_eq!(key_id, KEY_ID);
+        Ok(self.key.sign_arbitrary_message(message))
+    }
+}
+
+fn storage(key: Ed25519PrivateKey) -> (Storage, Arc<AtomicUsize>) {
+    let reads = Arc::new(AtomicUsize::new(0));
+    let kms = TestKms {
+        key,
+        reads: reads.clone(),
+    };
+    let keys: HashMap<_, _> = vec![(CONSENSUS_KEY.to_string(), KEY_ID.to_string())]
+        .into_iter()
+        .collect();
+    let storage = KmsStorage::new(Box::new(kms), Storage::from(InMemoryStorage::new()), keys);
+    let storage = Storage::from(Namespaced::new("alice", Box::new(Storage::from(storage))));
+    (storage, reads)
+}
+
+#[test]
+fn kms_signs_with_cached_public_key() {
+    let key = Ed25519PrivateKey::generate_for_testing();
+    let (mut storage, reads) = storage(key.clone());
+    storage.available().unwrap();
+    let public_key = storage.get_public_key(CONSENSUS_KEY).unwrap().public_key;
+    assert_eq!(public_key, key.public_key());
+
+    let message = TestDiemCrypto("Hello, World".to_string());
+    let signature = storage.sign(CONSENSUS_KEY, &message).unwrap();
+    signature.verify(&message, &public_key).unwrap();
+    storage
+        .sign_using_version(CONSENSUS_KEY, public_key, &message)
+        .unwrap();
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+    let other = other_key().public_key();
+    assert!(matches!(
+        storage.sign_using_version(CONSENSUS_KEY, other, &message),
+        Err(Error::KeyVersionNotFound(_, _))
+    ));
+    assert_eq!(
+        storage.export_private_key(CONSENSUS_KEY),
+        Err(Error::PermissionDenied)
+    );
+    assert_eq!(
+        storage.import_private_key(CONSENSUS_KEY, key),
+        Err(Error::PermissionDenied)
+    );
+    assert_eq!(
+        storage.rotate_key(CONSENSUS_KEY),
+        Err(Error::PermissionDenied)
+    );
+}
+
+#[test]
+fn kms_keeps_other_keys() {
+    let (mut storage, reads) = storage(Ed25519PrivateKey::generate_for_testing());
+    let key = Ed25519PrivateKey::generate_for_testing();
+    storage
+        .import_private_key(NETWORK_KEY, key.clone())
+        .unwrap();
+    assert_eq!(storage.export_private_key(NETWORK_KEY).unwrap(), key);
+    storage.set("waypoint", 7u64).unwrap();
+    assert_eq!(storage.get::<u64>("waypoint").unwrap().value, 7);
+    assert_eq!(reads.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn kms_rejects_mismatched_signatures() {
+    struct WrongKms(TestKms);
+
+    impl KmsClient for WrongKms {
+        fn public_key(&self, key_id: &str) -> Result<Ed25519PublicKey, Error> {
+            self.0.public_key(key_id)
+        }
+
+        fn sign(&self, _key_id: &str, message: &[u8]) -> Result<Ed25519Signature, Error> {
+            Ok(other_key().sign_arbitrary_message(message))
+        }
+    }
+
+    let kms = WrongKms(TestKms {
+        key: Ed25519PrivateKey::generate_for_testing(),
+        reads: Arc::new(AtomicUsize::new(0)),
+    });
+    let keys = vec![(CONSENSUS_KEY.to_string(), KEY_ID.to_string())]
+        .into_iter()
+        .collect();
+    let storage = KmsStorage::new(Box::new(kms), Storage::from(InMemoryStorage::new()), keys);
+    let message = TestDiemCrypto("Hello, World".to_string());
+    assert!(matches!(
+        storage.sign(CONSENSUS_KEY, &message),
+        Err(Error::InternalError(_))
+    ));
+}
+
+#[test]
+fn kms_public_key_formats() {
+    // RFC 8410, section 10.1
+    let pem = "-----BEGIN PUBLIC KEY-----\n\
+               MCowBQYDK2VwAyEAGb9ECWmEzf6FQbrBZ9w7lshQhqowtrbLDFw4rXAxZuE=\n\
+               -----END PUBLIC KEY-----\n";
+    let key = ed25519_from_pem(pem).unwrap();
+    assert_eq!(
+        hex::encode(key.to_bytes()),
+        "19bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1"
+    );
+    // A P-256 key
+    let pem = "-----BEGIN PUBLIC KEY-----\n\
+               MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEVEmDY5WtVplJvwe8xnA93XnkqBxd\n\
+               -----END PUBLIC KEY-----\n";
+    assert!(ed25519_from_pem(pem).is_err());
+}
+
+#[test]
+fn aws_signing_key() {
+    // The example of the AWS documentation on deriving a signing key
+    let key = signing_key(
+        "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "20120215",
+        "us-east-1",
+        "iam",
+    );
+    assert_eq!(
+        hex::encode(key),
+        "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+    );
+}
@@ -1,8 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod faulty; //////// 0L ////////
 mod github;
+mod hsm; //////// 0L ////////
 mod in_memory;
+mod kms; //////// 0L ////////
 mod on_disk;
 mod suite;
 mod vault;
@@ -1,8 +1,9 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{tests::suite, OnDiskStorage, Storage};
+use crate::{tests::suite, Error, KVStorage, OnDiskStorage, Storage};
 use diem_temppath::TempPath;
+use std::fs;
 
 #[test]
 fn on_disk() {
@@ -10,3 +11,43 @@ fn on_disk() {
     let mut storage = Storage::from(OnDiskStorage::new(path_buf));
     suite::execute_all_storage_tests(&mut storage);
 }
+
+//////// 0L ////////
+#[test]
+fn on_disk_encrypted() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(OnDiskStorage::new_encrypted(path_buf, "passphrase".into()));
+    suite::execute_all_storage_tests(&mut storage);
+}
+
+#[test]
+fn on_disk_migrate() {
+    let path = TempPath::new();
+    let path_buf = path.path().to_path_buf();
+    let mut plaintext = OnDiskStorage::new(path_buf.clone());
+    plaintext.set("key", "secret value").unwrap();
+
+    plaintext.migrate(Some("passphrase".into())).unwrap();
+    assert!(!fs::read_to_string(&path_buf)
+        .unwrap()
+        .contains("secret value"));
+    assert!(OnDiskStorage::new(path_buf.clone())
+        .get::<String>("key")
+        .is_err());
+    let wrong = OnDiskStorage::new_encrypted(path_buf.clone(), "wrong".into());
+    assert_eq!(wrong.get::<String>("key"), Err(Error::PermissionDenied));
+
+    let mut encrypted = OnDiskStorage::new_encrypted(path_buf.clone(), "passphrase".into());
+    assert_eq!(
+        encrypted.get::<String>("key").unwrap().value,
+        "secret value"
+    );
+    encrypted.migrate(None).unwrap();
+    assert_eq!(
+        OnDiskStorage::new(path_buf)
+            .get::<String>("key")
+            .unwrap()
+            .value,
+        "secret value"
+    );
+}
@@ -410,6 +410,15 @@ fn test_vault_cas() {
     assert_eq!(with_cas.get::<u64>("test").unwrap().value, 5);
     with_cas.set("test", 6).unwrap();
     assert_eq!(with_cas.get::<u64>("test").unwrap().value, 6);
+
+    //////// 0L ////////
+    // Test that a key read as unset is only written while it stays unset
+    let mut other_cas = create_vault();
+    with_cas.get::<u64>("unset").unwrap_err();
+    other_cas.get::<u64>("unset").unwrap_err();
+    other_cas.set("unset", 1).unwrap();
+    with_cas.set("unset", 2).unwrap_err();
+    assert_eq!(with_cas.get::<u64>("unset").unwrap().value, 1);
 }
 
 fn test_vault_key_trimming() {
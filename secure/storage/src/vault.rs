@@ -156,7 +156,18 @@ impl KVStorage for VaultStorage {
     fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
         let secret = key;
         let key = self.unnamespaced(key);
-        let resp = self.client().read_secret(&secret, key)?;
+        //////// 0L ////////
+        // A key read as unset is written with version 0, which Vault only accepts while the key
+        // is still unset
+        let resp = match self.client().read_secret(&secret, key) {
+            Err(error @ diem_vault_client::Error::NotFound(_, _)) => {
+                if self.use_cas {
+                    self.secret_versions.write().insert(key.to_string(), 0);
+                }
+                return Err(error.into());
+            }
+            resp => resp?,
+        };
         let last_update = DateTime::parse_from_rfc3339(&resp.creation_time)?.timestamp() as u64;
         let value: T = serde_json::from_value(resp.value)?;
         self.secret_versions
@@ -10,9 +10,10 @@ use diem_config::{config::NodeConfig, network_id::NodeNetworkId};
 use diem_types::waypoint::Waypoint;
 use executor_types::ChunkExecutor;
 use futures::channel::mpsc;
+use network::{drain::Drain, peer_reputation::PeerReputationStore};
 use std::{boxed::Box, collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
-use subscription_service::ReconfigSubscription;
+use subscription_service::ReconfigBus;
 use tokio::runtime::{Builder, Runtime};
 
 /// Creates and bootstraps new state syncs and creates clients for
@@ -30,7 +31,9 @@ impl StateSyncBootstrapper {
         executor: Box<dyn ChunkExecutor>,
         node_config: &NodeConfig,
         waypoint: Waypoint,
-        reconfig_event_subscriptions: Vec<ReconfigSubscription>,
+        reconfig_bus: ReconfigBus,                 //////// 0L ////////
+        peer_reputation: Arc<PeerReputationStore>, //////// 0L ////////
+        drain: Arc<Drain>,                         //////// 0L ////////
     ) -> Self {
         let runtime = Builder::new_multi_thread()
             .thread_name("state-sync")
@@ -38,7 +41,7 @@ impl StateSyncBootstrapper {
             .build()
             .expect("[State Sync] Failed to create runtime!");
 
-        let executor_proxy = ExecutorProxy::new(storage, executor, reconfig_event_subscriptions);
+        let executor_proxy = ExecutorProxy::new(storage, executor, reconfig_bus);
         Self::bootstrap_with_executor_proxy(
             runtime,
             network,
@@ -46,6 +49,8 @@ impl StateSyncBootstrapper {
             node_config,
             waypoint,
             executor_proxy,
+            peer_reputation,
+            drain,
         )
     }
 
@@ -56,6 +61,8 @@ impl StateSyncBootstrapper {
         node_config: &NodeConfig,
         waypoint: Waypoint,
         executor_proxy: E,
+        peer_reputation: Arc<PeerReputationStore>, //////// 0L ////////
+        drain: Arc<Drain>,                         //////// 0L ////////
     ) -> Self {
         let (coordinator_sender, coordinator_receiver) = mpsc::unbounded();
         let initial_state = executor_proxy
@@ -74,6 +81,8 @@ impl StateSyncBootstrapper {
             waypoint,
             executor_proxy,
             initial_state,
+            peer_reputation,
+            drain,
         )
         .expect("[State Sync] Unable to create state sync coordinator!");
         runtime.spawn(coordinator.start(network));
@@ -34,6 +34,7 @@ pub enum CoordinatorMessage {
     CommitNotification(Box<CommitNotification>), // Notify state sync about committed transactions.
     GetSyncState(oneshot::Sender<SyncState>), // Return the local sync state.
     WaitForInitialization(oneshot::Sender<Result<(), Error>>), // Wait until state sync is initialized to the waypoint.
+    TriggerSync(oneshot::Sender<Result<SyncState, Error>>), //////// 0L //////// Request the next chunk now.
 }
 
 /// A client used for communicating with a StateSyncCoordinator.
@@ -151,4 +152,19 @@ impl StateSyncClient {
             cb_receiver.await?
         }
     }
+
+    //////// 0L ////////
+    /// Sends the next chunk request to peers right away, instead of waiting for the previous one to
+    /// time out. Returns the local state the sync continues from.
+    pub fn trigger_sync(&self) -> impl Future<Output = Result<SyncState, Error>> {
+        let mut sender = self.coordinator_sender.clone();
+        let (cb_sender, cb_receiver) = oneshot::channel();
+
+        async move {
+            sender
+                .send(CoordinatorMessage::TriggerSync(cb_sender))
+                .await?;
+            cb_receiver.await?
+        }
+    }
 }
@@ -32,10 +32,19 @@ use futures::{
     stream::select_all,
     StreamExt,
 };
-use network::{protocols::network::Event, transport::ConnectionMetadata};
+use network::{
+    drain::Drain,
+    peer_reputation::{Misbehavior, PeerReputationStore},
+    protocols::network::Event,
+    transport::ConnectionMetadata,
+};
 use std::{
     cmp,
     collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, SystemTime},
 };
 use tokio::time::{interval, timeout};
@@ -90,6 +99,13 @@ pub(crate) struct StateSyncCoordinator<T> {
     // peer will be notified about new chunk of transactions if it's available before expiry time
     subscriptions: HashMap<PeerNetworkId, PendingRequestInfo>,
     executor_proxy: T,
+    //////// 0L ////////
+    // Node wide record of the peers sending invalid requests and chunks
+    peer_reputation: Arc<PeerReputationStore>,
+    // No chunk is requested anymore once the node is draining
+    drain: Arc<Drain>,
+    // The chunk requests still pending, waited for by the drain
+    pending_chunks: Arc<AtomicUsize>,
 }
 
 impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
@@ -101,6 +117,8 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         waypoint: Waypoint,
         executor_proxy: T,
         initial_state: SyncState,
+        peer_reputation: Arc<PeerReputationStore>, //////// 0L ////////
+        drain: Arc<Drain>,                         //////// 0L ////////
     ) -> Result<Self, Error> {
         info!(LogSchema::event_log(LogEntry::Waypoint, LogEvent::Initialize).waypoint(waypoint));
 
@@ -121,7 +139,13 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             Duration::from_millis(retry_timeout_val),
             Duration::from_millis(node_config.state_sync.multicast_timeout_ms),
             network_senders,
+            node_config.upstream.normalized(), //////// 0L ////////
+            node_config.state_sync.max_parallel_chunk_requests,
         );
+        //////// 0L ////////
+        let pending_chunks = Arc::new(AtomicUsize::new(0));
+        let pending = pending_chunks.clone();
+        drain.register("state sync chunks", move || pending.load(Ordering::Acquire));
 
         Ok(Self {
             client_events,
@@ -136,6 +160,9 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             target_ledger_info: None,
             initialization_listener: None,
             executor_proxy,
+            peer_reputation,
+            drain,
+            pending_chunks,
         })
     }
 
@@ -201,6 +228,15 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                                 error!(LogSchema::new(LogEntry::Waypoint).error(&e));
                             }
                         }
+                        //////// 0L ////////
+                        CoordinatorMessage::TriggerSync(callback) => {
+                            debug!("TriggerSync");
+                            let result = self.trigger_sync();
+                            if let Err(e) = &result {
+                                error!(LogSchema::event_log(LogEntry::TriggerSync, LogEvent::Fail).error(e));
+                            }
+                            let _ = callback.send(result);
+                        }
                     };
                 },
                 (network_id, event) = network_events.select_next_some() => {
@@ -238,6 +274,13 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                     }
                 }
             }
+            //////// 0L ////////
+            // Updated once a message is fully processed, so a chunk being applied still counts
+            self.pending_chunks.store(
+                self.request_manager
+                    .pending_requests(self.local_state.synced_version()),
+                Ordering::Release,
+            );
         }
     }
 
@@ -325,7 +368,34 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                     .start_timer();
 
                 // Process chunk response
-                self.process_chunk_response(&peer, *response).await
+                let process_result = self.process_chunk_response(&peer, *response).await;
+                //////// 0L ////////
+                if process_result.is_ok() {
+                    self.apply_prefetched_chunks().await;
+                }
+                process_result
+            }
+        }
+    }
+
+    //////// 0L ////////
+    /// Applies, in order, the chunks received ahead that now follow the synced version
+    async fn apply_prefetched_chunks(&mut self) {
+        while let Some((peer, response, txns_per_sec)) = self.request_manager.take_prefetched_chunk(
+            self.local_state.synced_version(),
+            self.local_state.trusted_epoch(),
+        ) {
+            if let Err(error) = self
+                .apply_chunk_response(&peer, response, txns_per_sec)
+                .await
+            {
+                error!(LogSchema::event_log(
+                    LogEntry::ProcessChunkResponse,
+                    LogEvent::ApplyChunkFail
+                )
+                .peer(&peer)
+                .error(&error));
+                break;
             }
         }
     }
@@ -703,6 +773,8 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                 .inc();
 
             self.request_manager.process_invalid_chunk_request(&peer);
+            self.peer_reputation
+                .report(&peer, Misbehavior::ProtocolViolation); //////// 0L ////////
             return Err(error);
         }
 
@@ -1022,6 +1094,7 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         }
         .map_err(|error| {
             self.request_manager.process_invalid_chunk(&peer);
+            self.peer_reputation.report(peer, Misbehavior::InvalidChunk); //////// 0L ////////
             Error::ProcessInvalidChunk(error.to_string())
         })?;
 
@@ -1064,12 +1137,33 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         Ok(())
     }
 
+    //////// 0L ////////
+    /// Keeps a chunk received ahead until the node syncs up to it, or applies it
+    async fn process_chunk_response(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetChunkResponse,
+    ) -> Result<(), Error> {
+        if self.request_manager.buffer_prefetched_chunk(
+            peer,
+            &response,
+            self.local_state.synced_version(),
+        ) {
+            return Ok(());
+        }
+        let txns_per_sec = self.request_manager.measure_chunk(peer, &response);
+        self.apply_chunk_response(peer, response, txns_per_sec)
+            .await
+    }
+
     /// * Verifies, processes and stores the chunk in the given response.
     /// * Triggers post-commit actions based on new local state (after successfully processing a chunk).
-    async fn process_chunk_response(
+    /// * Credits the peer with `txns_per_sec`, measured on receipt, once the chunk is applied.
+    async fn apply_chunk_response(
         &mut self,
         peer: &PeerNetworkId,
         response: GetChunkResponse,
+        txns_per_sec: Option<f64>,
     ) -> Result<(), Error> {
         // Ensure consensus isn't running, otherwise we might get a race with storage writes.
         if self.is_consensus_executing() {
@@ -1094,6 +1188,11 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
                         counters::SUCCESS_LABEL,
                     ])
                     .inc();
+                //////// 0L ////////
+                if let Some(txns_per_sec) = txns_per_sec {
+                    self.request_manager
+                        .process_chunk_applied(peer, txns_per_sec);
+                }
             }
             Err(error) => {
                 error!(LogSchema::event_log(
@@ -1568,6 +1667,11 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             }
         }
 
+        //////// 0L ////////
+        if self.drain.is_draining() {
+            return Ok(());
+        }
+
         // If the coordinator didn't make progress by the expected time or did not
         // send a request for the current local synced version, issue a new request.
         let known_version = self.local_state.synced_version();
@@ -1576,13 +1680,7 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
             warn!(LogSchema::new(LogEntry::Timeout).version(known_version));
 
             let trusted_epoch = self.local_state.trusted_epoch();
-            let chunk_target = if !self.is_initialized() {
-                self.create_waypoint_chunk_target()
-            } else if self.sync_request.is_some() {
-                self.create_sync_request_chunk_target(known_version)?
-            } else {
-                self.create_highest_available_chunk_target(self.target_ledger_info.clone())
-            };
+            let chunk_target = self.progress_chunk_target(known_version)?;
             self.send_chunk_request_and_log_error(
                 known_version,
                 trusted_epoch,
@@ -1594,6 +1692,36 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         }
     }
 
+    //////// 0L ////////
+    /// The target of the next chunk request: the waypoint until initialized, then the target of
+    /// the sync request, if any, or else the highest ledger info available
+    fn progress_chunk_target(&self, known_version: u64) -> Result<TargetType, Error> {
+        Ok(if !self.is_initialized() {
+            self.create_waypoint_chunk_target()
+        } else if self.sync_request.is_some() {
+            self.create_sync_request_chunk_target(known_version)?
+        } else {
+            self.create_highest_available_chunk_target(self.target_ledger_info.clone())
+        })
+    }
+
+    //////// 0L ////////
+    /// Sends a chunk request right away, instead of once the previous request timed out. Returns
+    /// the local state the request starts from.
+    fn trigger_sync(&mut self) -> Result<SyncState, Error> {
+        self.sync_state_with_local_storage()?;
+        let known_version = self.local_state.synced_version();
+        let trusted_epoch = self.local_state.trusted_epoch();
+        let chunk_target = self.progress_chunk_target(known_version)?;
+        self.send_chunk_request_and_log_error(
+            known_version,
+            trusted_epoch,
+            chunk_target,
+            LogEntry::TriggerSync,
+        )?;
+        Ok(self.local_state.clone())
+    }
+
     /// Sends a chunk request with a given `known_version`, `known_epoch` and `chunk_target`.
     /// Immediately logs any errors returned by the operation using the given log entry.
     fn send_chunk_request_and_log_error(
@@ -1627,6 +1755,11 @@ impl<T: ExecutorProxyTrait> StateSyncCoordinator<T> {
         target: TargetType,
     ) -> Result<(), Error> {
         debug!("sending chunk request with");
+        //////// 0L ////////
+        if self.drain.is_draining() {
+            debug!("not sending chunk request, the node is shutting down");
+            return Ok(());
+        }
         if self.request_manager.no_available_peers() {
             debug!("no available peers");
             counters::STATE_SYNC_NO_AVAILABLE_PEERS.inc();
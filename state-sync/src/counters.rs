@@ -367,6 +367,15 @@ pub static SYNC_PEER_SCORE: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of chunks received ahead of the synced version, waiting to be applied
+pub static PREFETCHED_CHUNKS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_prefetched_chunks",
+        "Number of chunks received ahead of the synced version"
+    )
+    .unwrap()
+});
+
 pub static GET_SYNC_STATE: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
         "diem_state_sync_get_sync_state",
@@ -20,7 +20,7 @@ use executor_types::{ChunkExecutor, ExecutedTrees};
 use itertools::Itertools;
 use std::{collections::HashSet, convert::TryFrom, sync::Arc};
 use storage_interface::DbReader;
-use subscription_service::ReconfigSubscription;
+use subscription_service::ReconfigBus;
 
 /// Proxies interactions with execution and storage for state synchronization
 pub trait ExecutorProxyTrait: Send {
@@ -60,7 +60,7 @@ pub trait ExecutorProxyTrait: Send {
 pub(crate) struct ExecutorProxy {
     storage: Arc<dyn DbReader>,
     executor: Box<dyn ChunkExecutor>,
-    reconfig_subscriptions: Vec<ReconfigSubscription>,
+    reconfig_bus: ReconfigBus, //////// 0L ////////
     on_chain_configs: OnChainConfigPayload,
 }
 
@@ -68,19 +68,17 @@ impl ExecutorProxy {
     pub(crate) fn new(
         storage: Arc<dyn DbReader>,
         executor: Box<dyn ChunkExecutor>,
-        mut reconfig_subscriptions: Vec<ReconfigSubscription>,
+        reconfig_bus: ReconfigBus, //////// 0L ////////
     ) -> Self {
         let on_chain_configs = Self::fetch_all_configs(&*storage)
             .expect("[state sync] Failed initial read of on-chain configs");
-        for subscription in reconfig_subscriptions.iter_mut() {
-            subscription
-                .publish(on_chain_configs.clone())
-                .expect("[state sync] Failed to publish initial on-chain config");
-        }
+        reconfig_bus
+            .publish_all(on_chain_configs.clone())
+            .expect("[state sync] Failed to publish initial on-chain config");
         Self {
             storage,
             executor,
-            reconfig_subscriptions,
+            reconfig_bus,
             on_chain_configs,
         }
     }
@@ -270,32 +268,27 @@ impl ExecutorProxyTrait for ExecutorProxy {
             .map(|(id, _)| *id)
             .collect::<HashSet<_>>();
 
-        // notify subscribers
+        // notify subscribers: the bus publishes updates if *any* of the subscribed configs changed
+        // or any of the subscribed events were emitted
+        let results = self
+            .reconfig_bus
+            .publish(new_configs.clone(), &changed_configs, &event_keys);
         let mut publish_success = true;
-        for subscription in self.reconfig_subscriptions.iter_mut() {
-            // publish updates if *any* of the subscribed configs changed
-            // or any of the subscribed events were emitted
-            let subscribed_items = subscription.subscribed_items();
-            if !changed_configs.is_disjoint(&subscribed_items.configs)
-                || !event_keys.is_disjoint(&subscribed_items.events)
-            {
-                if let Err(e) = subscription.publish(new_configs.clone()) {
-                    publish_success = false;
-                    error!(
-                        LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
-                            .subscription_name(subscription.name.clone())
-                            .error(&Error::UnexpectedError(e.to_string())),
-                        "Failed to publish reconfig notification to subscription {}",
-                        subscription.name
-                    );
-                } else {
-                    info!(
-                        LogSchema::event_log(LogEntry::Reconfig, LogEvent::Success)
-                            .subscription_name(subscription.name.clone()),
-                        "Successfully published reconfig notification to subscription {}",
-                        subscription.name
-                    );
-                }
+        for (name, result) in results {
+            if let Err(e) = result {
+                publish_success = false;
+                error!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
+                        .subscription_name(name.clone())
+                        .error(&Error::UnexpectedError(e.to_string())),
+                    "Failed to publish reconfig notification to subscription {}", name
+                );
+            } else {
+                info!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::Success)
+                        .subscription_name(name.clone()),
+                    "Successfully published reconfig notification to subscription {}", name
+                );
             }
         }
 
@@ -638,7 +631,8 @@ mod tests {
         // Create executor proxy with given subscription
         let block_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![subscription]);
+        let executor_proxy =
+            ExecutorProxy::new(db, chunk_executor, ReconfigBus::new(vec![subscription]));
 
         // Verify initial reconfiguration notification is sent
         assert!(
@@ -108,6 +108,7 @@ pub enum LogEntry {
     Multicast,
     SubscriptionDeliveryFail,
     ProgressCheck,
+    TriggerSync, //////// 0L ////////
 }
 
 #[derive(Clone, Copy, Serialize)]
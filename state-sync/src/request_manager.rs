@@ -3,13 +3,14 @@
 
 use crate::{
     chunk_request::GetChunkRequest,
+    chunk_response::GetChunkResponse,
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSyncMessage, StateSyncSender},
 };
 use diem_config::{
-    config::{PeerNetworkId, PeerRole},
+    config::{PeerNetworkId, PeerRole, UpstreamConfig},
     network_id::{NetworkId, NodeNetworkId},
 };
 use diem_logger::prelude::*;
@@ -24,7 +25,7 @@ use std::{
     cmp::Ordering,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
-        BTreeMap, HashMap,
+        BTreeMap, HashMap, HashSet,
     },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -35,6 +36,10 @@ const MIN_SCORE: f64 = 1.0;
 const STARTING_SCORE: f64 = 50.0;
 const STARTING_SCORE_PREFERRED: f64 = 100.0;
 
+//////// 0L ////////
+/// Weight of the latest chunk in the measured throughput of a peer.
+const THROUGHPUT_SMOOTHING: f64 = 0.2;
+
 /// Basic metadata about the chunk request.
 #[derive(Clone, Debug)]
 pub struct ChunkRequestInfo {
@@ -44,6 +49,10 @@ pub struct ChunkRequestInfo {
     multicast_level: NetworkId,
     multicast_start_time: SystemTime,
     last_request_peers: Vec<PeerNetworkId>,
+    //////// 0L ////////
+    // The epoch of a request fetching a chunk ahead of the synced version, None for the request
+    // at the synced version
+    prefetch_epoch: Option<u64>,
 }
 
 impl ChunkRequestInfo {
@@ -56,10 +65,21 @@ impl ChunkRequestInfo {
             multicast_level,
             multicast_start_time: now,
             last_request_peers: peers,
+            prefetch_epoch: None,
         }
     }
 }
 
+//////// 0L ////////
+/// A chunk received ahead of the synced version, waiting for the node to sync up to it.
+struct PrefetchedChunk {
+    peer: PeerNetworkId,
+    epoch: u64,
+    response: GetChunkResponse,
+    // Measured on receipt, credited to the peer once the chunk is applied
+    txns_per_sec: Option<f64>,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum PeerScoreUpdateType {
     Success,
@@ -85,6 +105,15 @@ pub struct RequestManager {
     // NetworkId.
     multicast_network_level: NetworkId,
     network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+    //////// 0L ////////
+    // Ranks the networks of the peers chunks are fetched ahead from
+    upstream_config: UpstreamConfig,
+    // The number of chunks requested at once, counting the chunk at the synced version
+    max_parallel_requests: u64,
+    // Measured throughput of each peer, in transactions per second, smoothed over its chunks
+    peer_throughput: HashMap<PeerNetworkId, f64>,
+    // Chunks received ahead of the synced version, by the known version of their request
+    prefetched_chunks: BTreeMap<u64, PrefetchedChunk>,
 }
 
 impl RequestManager {
@@ -92,6 +121,8 @@ impl RequestManager {
         request_timeout: Duration,
         multicast_timeout: Duration,
         network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+        upstream_config: UpstreamConfig,
+        max_parallel_requests: u64,
     ) -> Self {
         let multicast_network_level = NetworkId::Validator;
         update_multicast_network_counter(multicast_network_level.clone());
@@ -103,6 +134,10 @@ impl RequestManager {
             multicast_timeout,
             multicast_network_level,
             network_senders,
+            upstream_config,
+            max_parallel_requests,
+            peer_throughput: HashMap::new(),
+            prefetched_chunks: BTreeMap::new(),
         }
     }
 
@@ -163,6 +198,7 @@ impl RequestManager {
                 .with_label_values(&[&peer.raw_network_id().to_string()])
                 .dec();
             self.peer_scores.remove(peer);            
+            self.peer_throughput.remove(peer); //////// 0L ////////
         } else {
             warn!(LogSchema::new(LogEntry::LostPeerNotKnown).peer(&peer));
         }
@@ -286,6 +322,16 @@ impl RequestManager {
     pub fn send_chunk_request(&mut self, req: GetChunkRequest) -> Result<(), Error> {
         let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_request(req.clone());
 
+        //////// 0L ////////
+        // The chunk may already have been fetched ahead, or still be on its way
+        if self.is_prefetched(req.known_version) {
+            debug!(
+                log.event(LogEvent::ChunkRequestInfo),
+                "Chunk was fetched ahead"
+            );
+            return Ok(());
+        }
+
         let peers = self.pick_peers();
         if peers.is_empty() {
             debug!("no statesync available peers");
@@ -302,7 +348,7 @@ impl RequestManager {
             .event(LogEvent::ChunkRequestInfo)
             .chunk_req_info(&req_info));
 
-        let msg = StateSyncMessage::GetChunkRequest(Box::new(req));
+        let msg = StateSyncMessage::GetChunkRequest(Box::new(req.clone()));
         let mut failed_peer_sends = vec![];
 
         for peer in peers {
@@ -326,6 +372,7 @@ impl RequestManager {
                 ])
                 .inc();
         }
+        self.send_prefetch_requests(&req); //////// 0L ////////
 
         if failed_peer_sends.is_empty() {
             Ok(())
@@ -337,6 +384,204 @@ impl RequestManager {
         }
     }
 
+    //////// 0L ////////
+    /// Requests the chunks following the one of `req`, each from a different peer, so that up to
+    /// `max_parallel_requests` chunks are in flight. Chunks are only fetched ahead of a target
+    /// version, and from the peers of upstream networks.
+    fn send_prefetch_requests(&mut self, req: &GetChunkRequest) {
+        let target_version = match req.target.version() {
+            Some(target_version) => target_version,
+            None => return,
+        };
+        let busy_peers: HashSet<_> = self
+            .requests
+            .range(req.known_version.saturating_add(1)..)
+            .filter(|(version, _)| self.is_prefetched(**version))
+            .flat_map(|(_, req_info)| req_info.last_request_peers.clone())
+            .collect();
+        let mut peers = self
+            .rank_prefetch_peers()
+            .into_iter()
+            .filter(|peer| !busy_peers.contains(peer));
+
+        for index in 1..self.max_parallel_requests {
+            let version = match req
+                .limit
+                .checked_mul(index)
+                .and_then(|offset| req.known_version.checked_add(offset))
+            {
+                Some(version) if version < target_version => version,
+                _ => break,
+            };
+            if self.is_prefetched(version) {
+                continue;
+            }
+            // A peer that didn't send its chunk in time is slow rather than faulty: the chunk
+            // may cross into an epoch it cannot prove.
+            if let Some(expired) = self.requests.remove(&version) {
+                for peer in expired.last_request_peers {
+                    self.update_throughput(&peer, 0.0);
+                }
+            }
+            let peer = match peers.next() {
+                Some(peer) => peer,
+                None => break,
+            };
+
+            let prefetch_req =
+                GetChunkRequest::new(version, req.current_epoch, req.limit, req.target.clone());
+            let curr_log = LogSchema::new(LogEntry::SendChunkRequest)
+                .chunk_request(prefetch_req.clone())
+                .peer(&peer);
+            let msg = StateSyncMessage::GetChunkRequest(Box::new(prefetch_req));
+            let result_label =
+                if let Err(e) = self.get_network_sender(&peer).send_to(peer.peer_id(), msg) {
+                    error!(curr_log.event(LogEvent::NetworkSendError).error(&e));
+                    counters::SEND_FAIL_LABEL
+                } else {
+                    debug!(curr_log.event(LogEvent::Success));
+                    let mut req_info = ChunkRequestInfo::new(
+                        version,
+                        vec![peer.clone()],
+                        self.multicast_network_level.clone(),
+                    );
+                    req_info.prefetch_epoch = Some(req.current_epoch);
+                    self.requests.insert(version, req_info);
+                    counters::SEND_SUCCESS_LABEL
+                };
+            counters::REQUESTS_SENT
+                .with_label_values(&[
+                    &peer.raw_network_id().to_string(),
+                    &peer.peer_id().to_string(),
+                    result_label,
+                ])
+                .inc();
+        }
+    }
+
+    /// Ranks the peers to fetch chunks ahead from: by the upstream preference of their network,
+    /// then by measured throughput. Peers not measured yet come first in their network, to get
+    /// measured. Peers of downstream networks are left out.
+    fn rank_prefetch_peers(&self) -> Vec<PeerNetworkId> {
+        let mut peers: Vec<_> = self
+            .peer_scores
+            .keys()
+            .filter_map(|peer| {
+                let preference = self
+                    .upstream_config
+                    .get_upstream_preference(peer.raw_network_id())?;
+                let throughput = self
+                    .peer_throughput
+                    .get(peer)
+                    .copied()
+                    .unwrap_or(f64::INFINITY);
+                Some((preference, throughput, peer.clone()))
+            })
+            .collect();
+        // Most preferred network first, then fastest peer first
+        peers.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+        });
+        peers.into_iter().map(|(_, _, peer)| peer).collect()
+    }
+
+    /// Whether the chunk at `version` was received ahead, or was requested ahead and hasn't
+    /// timed out yet
+    fn is_prefetched(&self, version: u64) -> bool {
+        self.prefetched_chunks.contains_key(&version)
+            || self.requests.get(&version).map_or(false, |req_info| {
+                req_info.prefetch_epoch.is_some()
+                    && !is_timeout(req_info.last_request_time, self.request_timeout)
+            })
+    }
+
+    fn update_throughput(&mut self, peer: &PeerNetworkId, txns_per_sec: f64) {
+        let throughput = self
+            .peer_throughput
+            .entry(peer.clone())
+            .or_insert(txns_per_sec);
+        *throughput += (txns_per_sec - *throughput) * THROUGHPUT_SMOOTHING;
+    }
+
+    /// Measures the throughput of `peer` from the chunk of `response`, if it was requested from
+    /// `peer`. The peer is only credited for it once the chunk is applied, see
+    /// `process_chunk_applied`.
+    pub fn measure_chunk(&self, peer: &PeerNetworkId, response: &GetChunkResponse) -> Option<f64> {
+        let txn_list_with_proof = &response.txn_list_with_proof;
+        let known_version = txn_list_with_proof
+            .first_transaction_version?
+            .saturating_sub(1);
+        let elapsed = match self.requests.get(&known_version) {
+            Some(req_info) if req_info.last_request_peers.contains(peer) => SystemTime::now()
+                .duration_since(req_info.last_request_time)
+                .unwrap_or_default(),
+            _ => return None,
+        };
+        Some(txn_list_with_proof.len() as f64 / elapsed.as_secs_f64().max(0.001))
+    }
+
+    /// Credits `peer` with the throughput measured for a chunk it sent, now that it was applied
+    pub fn process_chunk_applied(&mut self, peer: &PeerNetworkId, txns_per_sec: f64) {
+        self.update_throughput(peer, txns_per_sec);
+    }
+
+    /// Keeps the chunk of `response` if `peer` sent it ahead of `synced_version`, in response to
+    /// a request fetching it ahead, until the node syncs up to it. Returns whether it was kept.
+    pub fn buffer_prefetched_chunk(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: &GetChunkResponse,
+        synced_version: u64,
+    ) -> bool {
+        let known_version = match response.txn_list_with_proof.first_transaction_version {
+            Some(first_version) if first_version.saturating_sub(1) > synced_version => {
+                first_version - 1
+            }
+            _ => return false,
+        };
+        let epoch = match self.requests.get(&known_version) {
+            Some(req_info) if req_info.last_request_peers.contains(peer) => {
+                match req_info.prefetch_epoch {
+                    Some(epoch) => epoch,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+        let txns_per_sec = self.measure_chunk(peer, response);
+        self.prefetched_chunks.insert(
+            known_version,
+            PrefetchedChunk {
+                peer: peer.clone(),
+                epoch,
+                response: response.clone(),
+                txns_per_sec,
+            },
+        );
+        counters::PREFETCHED_CHUNKS.set(self.prefetched_chunks.len() as i64);
+        true
+    }
+
+    /// Takes the chunk received ahead that follows `synced_version`, with the throughput
+    /// measured on its receipt, if it was requested in `epoch`: the proofs of a chunk requested in
+    /// an earlier epoch don't verify anymore.
+    pub fn take_prefetched_chunk(
+        &mut self,
+        synced_version: u64,
+        epoch: u64,
+    ) -> Option<(PeerNetworkId, GetChunkResponse, Option<f64>)> {
+        let chunk = self.prefetched_chunks.remove(&synced_version)?;
+        counters::PREFETCHED_CHUNKS.set(self.prefetched_chunks.len() as i64);
+        if chunk.epoch == epoch {
+            Some((chunk.peer, chunk.response, chunk.txns_per_sec))
+        } else {
+            // Let the chunk be requested again right away
+            self.requests.remove(&synced_version);
+            None
+        }
+    }
+
     fn get_network_sender(&mut self, peer: &PeerNetworkId) -> StateSyncSender {
         self.network_senders
             .get_mut(&peer.network_id())
@@ -368,6 +613,7 @@ impl RequestManager {
             }
             prev_request.last_request_peers = peers;
             prev_request.last_request_time = now;
+            prev_request.prefetch_epoch = None; //////// 0L ////////
             prev_request.clone()
         } else {
             let chunk_request_info =
@@ -478,6 +724,23 @@ impl RequestManager {
         for v in versions_to_remove {
             self.requests.remove(&v);
         }
+
+        //////// 0L ////////
+        // Chunks received ahead that the node synced past are of no use anymore
+        self.prefetched_chunks = self.prefetched_chunks.split_off(&version);
+        counters::PREFETCHED_CHUNKS.set(self.prefetched_chunks.len() as i64);
+    }
+
+    //////// 0L ////////
+    /// The chunk requests from `version` on that may still get their chunk
+    pub fn pending_requests(&self, version: u64) -> usize {
+        self.requests
+            .range(version..)
+            .filter(|(version, req_info)| {
+                !self.prefetched_chunks.contains_key(*version)
+                    && !is_timeout(req_info.last_request_time, self.request_timeout)
+            })
+            .count()
     }
 
     /// Checks whether the request sent with known_version = `version` has timed out
@@ -606,6 +869,15 @@ fn update_multicast_network_counter(multicast_network_level: NetworkId) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunk_response::ResponseLedgerInfo;
+    use diem_crypto::HashValue;
+    use diem_types::{
+        block_info::BlockInfo,
+        ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+        proof::TransactionListProof,
+        transaction::TransactionListWithProof,
+        PeerId,
+    };
 
     const NUM_CHUNKS_TO_PROCESS: u64 = 50;
     const NUM_PICKS_TO_MAKE: u64 = 1000;
@@ -835,6 +1107,125 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rank_prefetch_peers() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 3);
+
+        // Add a public peer, of the least preferred upstream network
+        let public_peer = PeerNetworkId(NodeNetworkId::new(NetworkId::Public, 0), PeerId::random());
+        let connection_metadata = ConnectionMetadata::mock_with_role_and_origin(
+            public_peer.peer_id(),
+            PeerRole::Unknown,
+            ConnectionOrigin::Outbound,
+        );
+        request_manager
+            .enable_peer(public_peer.clone(), connection_metadata)
+            .unwrap();
+
+        // Measure all peers but validator 2
+        request_manager.update_throughput(&validators[0], 10.0);
+        request_manager.update_throughput(&validators[1], 100.0);
+        request_manager.update_throughput(&public_peer, 1000.0);
+
+        // Verify the validators come first, unmeasured then fastest, whatever the public peer does
+        assert_eq!(
+            request_manager.rank_prefetch_peers(),
+            vec![
+                validators[2].clone(),
+                validators[1].clone(),
+                validators[0].clone(),
+                public_peer,
+            ]
+        );
+
+        // Verify a timed out validator falls behind the others
+        request_manager.update_throughput(&validators[2], 0.0);
+        assert_eq!(request_manager.rank_prefetch_peers()[2], validators[2]);
+    }
+
+    #[test]
+    fn test_prefetched_chunks() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(10, 2);
+        let chunk = create_chunk_response(101);
+
+        // Chunks are kept from the peer they were fetched ahead from, only
+        add_prefetch_request(&mut request_manager, 100, &validators[0], 1);
+        assert!(!request_manager.buffer_prefetched_chunk(&validators[1], &chunk, 50));
+        assert!(!request_manager.buffer_prefetched_chunk(&validators[0], &chunk, 100));
+        assert!(request_manager.buffer_prefetched_chunk(&validators[0], &chunk, 50));
+        assert!(request_manager.is_prefetched(100));
+
+        // Verify the chunk is taken once the node synced up to it
+        assert!(request_manager.take_prefetched_chunk(50, 1).is_none());
+        let (peer, response, txns_per_sec) = request_manager.take_prefetched_chunk(100, 1).unwrap();
+        assert_eq!(peer, validators[0]);
+        assert_eq!(response, chunk);
+        // Verify the peer is only credited once the chunk is applied
+        assert!(txns_per_sec.is_some());
+        assert!(request_manager.peer_throughput.get(&peer).is_none());
+
+        // Verify a chunk requested in an earlier epoch is requested again
+        add_prefetch_request(&mut request_manager, 100, &validators[0], 1);
+        assert!(request_manager.buffer_prefetched_chunk(&validators[0], &chunk, 50));
+        assert!(request_manager.take_prefetched_chunk(100, 2).is_none());
+        assert!(!request_manager.is_prefetched(100));
+
+        // Verify chunks the node synced past are dropped
+        add_prefetch_request(&mut request_manager, 100, &validators[0], 1);
+        assert!(request_manager.buffer_prefetched_chunk(&validators[0], &chunk, 50));
+        request_manager.remove_requests(150);
+        assert!(request_manager.take_prefetched_chunk(100, 1).is_none());
+    }
+
+    #[test]
+    fn test_pending_requests() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(10, 1);
+        request_manager.add_request(100, validators.clone());
+        add_prefetch_request(&mut request_manager, 200, &validators[0], 1);
+        add_prefetch_request(&mut request_manager, 300, &validators[0], 1);
+        assert_eq!(request_manager.pending_requests(100), 3);
+
+        // Verify a chunk received ahead, and the requests the node synced past, aren't pending
+        let chunk = create_chunk_response(301);
+        assert!(request_manager.buffer_prefetched_chunk(&validators[0], &chunk, 100));
+        assert_eq!(request_manager.pending_requests(100), 2);
+        assert_eq!(request_manager.pending_requests(150), 1);
+
+        // Verify timed out requests aren't pending
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 1);
+        request_manager.add_request(100, validators);
+        assert_eq!(request_manager.pending_requests(100), 0);
+    }
+
+    /// Adds a request fetching the chunk at `version` ahead from `peer`, in `epoch`.
+    fn add_prefetch_request(
+        request_manager: &mut RequestManager,
+        version: u64,
+        peer: &PeerNetworkId,
+        epoch: u64,
+    ) {
+        let mut req_info = ChunkRequestInfo::new(version, vec![peer.clone()], NetworkId::Validator);
+        req_info.prefetch_epoch = Some(epoch);
+        request_manager.requests.insert(version, req_info);
+    }
+
+    /// Creates an empty chunk response starting at `first_version`.
+    fn create_chunk_response(first_version: u64) -> GetChunkResponse {
+        let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+        GetChunkResponse::new(
+            ResponseLedgerInfo::LedgerInfoForWaypoint {
+                waypoint_li: LedgerInfoWithSignatures::new(ledger_info, BTreeMap::new()),
+                end_of_epoch_li: None,
+            },
+            TransactionListWithProof::new(
+                vec![],
+                None,
+                Some(first_version),
+                TransactionListProof::new_empty(),
+            ),
+        )
+    }
+
     /// Verify that the specified validator is chosen most often (due to having a
     /// higher peer score internally).
     fn verify_validator_picked_most_often(
@@ -906,6 +1297,10 @@ mod tests {
             Duration::from_secs(request_timeout),
             Duration::from_secs(30),
             HashMap::new(),
+            UpstreamConfig {
+                networks: vec![NetworkId::vfn_network(), NetworkId::Public],
+            },
+            4,
         )
     }
 
@@ -96,11 +96,14 @@ pub(crate) mod test_utils {
     use executor_test_helpers::bootstrap_genesis;
     use futures::channel::mpsc;
     use network::{
+        drain::Drain,
         peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+        peer_reputation::PeerReputationStore,
         protocols::network::NewNetworkSender,
     };
     use std::collections::HashMap;
     use storage_interface::DbReaderWriter;
+    use subscription_service::ReconfigBus;
 
     #[cfg(test)]
     pub(crate) fn create_coordinator_with_config_and_waypoint(
@@ -143,7 +146,7 @@ pub(crate) mod test_utils {
 
         // Create executor proxy
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![]);
+        let executor_proxy = ExecutorProxy::new(db, chunk_executor, ReconfigBus::default());
 
         // Get initial state
         let initial_state = executor_proxy.get_local_storage_state().unwrap();
@@ -173,6 +176,8 @@ pub(crate) mod test_utils {
             waypoint,
             executor_proxy,
             initial_state,
+            Arc::new(PeerReputationStore::in_memory()),
+            Arc::new(Drain::new()),
         )
         .unwrap()
     }
@@ -43,11 +43,13 @@ use futures::{executor::block_on, future::FutureExt, StreamExt};
 use memsocket::MemoryListener;
 use netcore::transport::ConnectionOrigin;
 use network::{
+    drain::Drain,
     peer_manager::{
         builder::AuthenticationMode, conn_notifs_channel, ConnectionNotification,
         ConnectionRequestSender, PeerManagerNotification, PeerManagerRequest,
         PeerManagerRequestSender,
     },
+    peer_reputation::PeerReputationStore,
     protocols::{
         direct_send::Message,
         network::{NewNetworkEvents, NewNetworkSender},
@@ -303,6 +305,8 @@ impl StateSyncEnvironment {
             &config,
             waypoint,
             MockExecutorProxy::new(handler, storage_proxy.clone()),
+            Arc::new(PeerReputationStore::in_memory()),
+            Arc::new(Drain::new()),
         );
 
         peer.client = Some(bootstrapper.create_client(config.state_sync.client_commit_timeout_ms));
@@ -366,6 +370,7 @@ impl StateSyncEnvironment {
                 *role,
                 VALIDATOR_NETWORK.clone(),
                 peer.peer_id,
+                ChainId::default(),
             ));
 
             let seeds: HashMap<_, _> = self
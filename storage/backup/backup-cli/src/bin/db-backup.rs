@@ -12,7 +12,10 @@ use backup_cli::{
         state_snapshot::backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
-    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    coordinators::{
+        backup::{BackupCoordinator, BackupCoordinatorOpt},
+        snapshot::{EpochSnapshotExportController, EpochSnapshotExportOpt},
+    },
     metadata::{cache, cache::MetadataCacheOpt},
     storage::StorageOpt,
     utils::{
@@ -101,6 +104,17 @@ enum BackupType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    //////// 0L ////////
+    #[structopt(
+        about = "Backs up the epoch endings, state and transaction at the end of an epoch, under \
+        a manifest to import with `db-restore epoch-snapshot`."
+    )]
+    EpochSnapshot {
+        #[structopt(flatten)]
+        opt: EpochSnapshotExportOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
 }
 
 #[derive(StructOpt)]
@@ -193,6 +207,18 @@ async fn main_impl() -> Result<()> {
                         .run()
                         .await?;
                     }
+                    //////// 0L ////////
+                    BackupType::EpochSnapshot { opt, storage } => {
+                        let manifest_handle = EpochSnapshotExportController::new(
+                            opt,
+                            global_opt,
+                            client,
+                            storage.init_storage().await?,
+                        )
+                        .run()
+                        .await?;
+                        println!("{}", manifest_handle)
+                    }
                 }
             }
         },
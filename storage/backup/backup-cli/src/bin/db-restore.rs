@@ -8,7 +8,10 @@ use backup_cli::{
         state_snapshot::restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
         transaction::restore::{TransactionRestoreController, TransactionRestoreOpt},
     },
-    coordinators::restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+    coordinators::{
+        restore::{RestoreCoordinator, RestoreCoordinatorOpt},
+        snapshot::{EpochSnapshotImportController, EpochSnapshotImportOpt},
+    },
     storage::StorageOpt,
     utils::{GlobalRestoreOpt, GlobalRestoreOptions},
 };
@@ -52,6 +55,13 @@ enum RestoreType {
         #[structopt(subcommand)]
         storage: StorageOpt,
     },
+    //////// 0L ////////
+    EpochSnapshot {
+        #[structopt(flatten)]
+        opt: EpochSnapshotImportOpt,
+        #[structopt(subcommand)]
+        storage: StorageOpt,
+    },
 }
 
 #[tokio::main]
@@ -100,6 +110,12 @@ async fn main_impl() -> Result<()> {
                 .run()
                 .await?;
         }
+        //////// 0L ////////
+        RestoreType::EpochSnapshot { opt, storage } => {
+            EpochSnapshotImportController::new(opt, global_opt, storage.init_storage().await?)
+                .run()
+                .await?;
+        }
     }
 
     Ok(())
@@ -8,7 +8,7 @@ use crate::{
         transaction::backup::{TransactionBackupController, TransactionBackupOpt},
     },
     metadata,
-    metadata::cache::MetadataCacheOpt,
+    metadata::{cache::MetadataCacheOpt, Metadata},
     metrics::backup::{
         EPOCH_ENDING_EPOCH, HEARTBEAT_TS, STATE_SNAPSHOT_VERSION, TRANSACTION_VERSION,
     },
@@ -48,6 +48,11 @@ pub struct BackupCoordinatorOpt {
     pub transaction_batch_size: usize,
     #[structopt(flatten)]
     pub concurernt_downloads: ConcurrentDownloadsOpt,
+    //////// 0L ////////
+    /// Keeps only this many of the latest state snapshots in the backup storage, removing the
+    /// older ones once a new one is backed up. Transactions and epoch endings are always kept.
+    #[structopt(long)]
+    pub state_snapshot_retention: Option<usize>,
 }
 
 impl BackupCoordinatorOpt {
@@ -63,6 +68,10 @@ impl BackupCoordinatorOpt {
              that's not yet in a transaction backup, resulting in replaying all transactions \
              at restore time."
         );
+        ensure!(
+            self.state_snapshot_retention != Some(0),
+            "Keep at least one state snapshot to restore from."
+        );
         Ok(())
     }
 }
@@ -75,6 +84,7 @@ pub struct BackupCoordinator {
     state_snapshot_interval: usize,
     transaction_batch_size: usize,
     concurrent_downloads: usize,
+    state_snapshot_retention: Option<usize>,
 }
 
 impl BackupCoordinator {
@@ -93,6 +103,7 @@ impl BackupCoordinator {
             state_snapshot_interval: opt.state_snapshot_interval,
             transaction_batch_size: opt.transaction_batch_size,
             concurrent_downloads: opt.concurernt_downloads.get(),
+            state_snapshot_retention: opt.state_snapshot_retention,
         }
     }
     pub async fn run(&self) -> Result<()> {
@@ -248,9 +259,49 @@ impl BackupCoordinator {
         .run()
         .await?;
 
+        //////// 0L ////////
+        if let Some(retention) = self.state_snapshot_retention {
+            if let Err(e) = self.remove_expired_state_snapshots(retention).await {
+                warn!(
+                    "Failed removing expired state snapshots: {}. Will retry.",
+                    e
+                );
+            }
+        }
+
         Ok(Some(next_snapshot_version))
     }
 
+    //////// 0L ////////
+    async fn remove_expired_state_snapshots(&self, retention: usize) -> Result<()> {
+        let metadata_handles = self.storage.list_metadata_files().await?;
+        let expired = metadata::cache::sync_and_load(
+            &self.metadata_cache_opt,
+            Arc::clone(&self.storage),
+            self.concurrent_downloads,
+        )
+        .await?
+        .expired_state_snapshots(retention);
+
+        for snapshot in expired {
+            let name =
+                Metadata::new_state_snapshot_backup(snapshot.version, snapshot.manifest.clone())
+                    .name();
+            let metadata_handle = metadata_handles
+                .iter()
+                .find(|handle| handle.rsplit('/').next() == Some(name.as_str()))
+                .ok_or_else(|| anyhow!("No metadata file {} in the backup storage.", *name))?;
+            self.storage
+                .remove_backup(&snapshot.manifest, metadata_handle)
+                .await?;
+            info!(
+                "State snapshot at version {} removed, beyond the retention of {}.",
+                snapshot.version, retention,
+            );
+        }
+        Ok(())
+    }
+
     async fn backup_transactions(
         &self,
         mut last_transaction_version_in_backup: Option<Version>,
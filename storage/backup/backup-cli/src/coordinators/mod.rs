@@ -4,3 +4,6 @@
 pub mod backup;
 pub mod restore;
 pub mod verify;
+//////// 0L ////////
+pub mod node;
+pub mod snapshot;
@@ -0,0 +1,105 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! The backup coordinator of a node, configured by its `BackupConfig` instead of the command line
+//! of `db-backup coordinator run`. It backs up from the backup service of the node, on a thread of
+//! its own, until the node stops.
+
+use crate::{
+    coordinators::backup::{BackupCoordinator, BackupCoordinatorOpt},
+    metadata::cache::MetadataCacheOpt,
+    storage::init_storage_for_target,
+    utils::{backup_service_client::BackupServiceClient, ConcurrentDownloadsOpt, GlobalBackupOpt},
+};
+use anyhow::{anyhow, Result};
+use diem_config::config::BackupConfig;
+use diem_logger::prelude::*;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, thread::JoinHandle};
+use tokio::{runtime::Builder, sync::oneshot};
+
+pub struct NodeBackupCoordinator {
+    quit_sender: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl NodeBackupCoordinator {
+    /// Starts backing up to the target of `config`, if any, from the backup service at
+    /// `backup_service_address`. The metadata of the target is cached in `metadata_cache_dir`.
+    pub fn start(
+        config: &BackupConfig,
+        backup_service_address: SocketAddr,
+        metadata_cache_dir: PathBuf,
+    ) -> Result<Option<Self>> {
+        let target = match &config.target {
+            Some(target) => target.clone(),
+            None => return Ok(None),
+        };
+        let opt = BackupCoordinatorOpt {
+            metadata_cache_opt: MetadataCacheOpt::new(Some(metadata_cache_dir)),
+            state_snapshot_interval: config.state_snapshot_interval,
+            transaction_batch_size: config.transaction_batch_size,
+            concurernt_downloads: ConcurrentDownloadsOpt::default(),
+            state_snapshot_retention: config.state_snapshot_retention,
+        };
+        let client = Arc::new(BackupServiceClient::new(format!(
+            "http://{}",
+            backup_service_address
+        )));
+        let (quit_sender, quit_receiver) = oneshot::channel();
+
+        // The coordinator runs its backups as local futures, on a runtime of the thread.
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| anyhow!("Failed to create the backup runtime: {}", e))?;
+        let thread = std::thread::Builder::new()
+            .name("backup-coordinator".into())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    let storage = match init_storage_for_target(&target).await {
+                        Ok(storage) => storage,
+                        Err(e) => {
+                            error!("Backup coordinator failed to open its target: {}", e);
+                            return;
+                        }
+                    };
+                    let coordinator = BackupCoordinator::new(
+                        opt,
+                        GlobalBackupOpt {
+                            max_chunk_size: GlobalBackupOpt::DEFAULT_MAX_CHUNK_SIZE,
+                        },
+                        client,
+                        storage,
+                    );
+                    tokio::select! {
+                        result = coordinator.run() => {
+                            if let Err(e) = result {
+                                error!("Backup coordinator stopped: {}", e);
+                            }
+                        }
+                        _ = quit_receiver => info!("Backup coordinator quit."),
+                    }
+                })
+            })?;
+
+        Ok(Some(Self {
+            quit_sender: Some(quit_sender),
+            thread: Some(thread),
+        }))
+    }
+}
+
+impl Drop for NodeBackupCoordinator {
+    fn drop(&mut self) {
+        if let Some(quit_sender) = self.quit_sender.take() {
+            // The coordinator is gone already if it stopped on an error.
+            let _ = quit_sender.send(());
+        }
+        self.thread
+            .take()
+            .expect("Backup thread must exist.")
+            .join()
+            .expect("Backup thread should join peacefully.");
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Epoch snapshots, for bootstrapping a node without replaying the chain. The export backs up the
+//! epoch endings from genesis, the account state and the transaction at the version ending an
+//! epoch, and publishes them under a single manifest. The import restores them, refusing to unless
+//! one of the epoch changes is a trusted waypoint, from which the rest are verified by signatures.
+
+use crate::{
+    backup_types::{
+        epoch_ending::{
+            backup::{EpochEndingBackupController, EpochEndingBackupOpt},
+            manifest::EpochEndingBackup,
+            restore::EpochHistoryRestoreController,
+        },
+        state_snapshot::{
+            backup::{StateSnapshotBackupController, StateSnapshotBackupOpt},
+            restore::{StateSnapshotRestoreController, StateSnapshotRestoreOpt},
+        },
+        transaction::{
+            backup::{TransactionBackupController, TransactionBackupOpt},
+            restore::TransactionRestoreBatchController,
+        },
+    },
+    storage::{BackupStorage, FileHandle, ShellSafeName},
+    utils::{
+        backup_service_client::BackupServiceClient, storage_ext::BackupStorageExt, GlobalBackupOpt,
+        GlobalRestoreOptions,
+    },
+};
+use anyhow::{anyhow, ensure, Result};
+use diem_logger::prelude::*;
+use diem_types::{transaction::Version, waypoint::Waypoint};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
+use structopt::StructOpt;
+use tokio::io::AsyncWriteExt;
+
+/// The manifest of an epoch snapshot, pointing at the manifests of its parts
+#[derive(Deserialize, Serialize)]
+pub struct EpochSnapshot {
+    pub epoch: u64,
+    pub version: Version,
+    pub waypoint: Waypoint,
+    pub epoch_ending: FileHandle,
+    pub state_snapshot: FileHandle,
+    pub transaction: FileHandle,
+}
+
+#[derive(StructOpt)]
+pub struct EpochSnapshotExportOpt {
+    #[structopt(
+        long,
+        help = "Epoch at the end of which the snapshot is taken. Defaults to the latest ended epoch."
+    )]
+    pub epoch: Option<u64>,
+}
+
+pub struct EpochSnapshotExportController {
+    epoch: Option<u64>,
+    global_opt: GlobalBackupOpt,
+    client: Arc<BackupServiceClient>,
+    storage: Arc<dyn BackupStorage>,
+}
+
+impl EpochSnapshotExportController {
+    pub fn new(
+        opt: EpochSnapshotExportOpt,
+        global_opt: GlobalBackupOpt,
+        client: Arc<BackupServiceClient>,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            epoch: opt.epoch,
+            global_opt,
+            client,
+            storage,
+        }
+    }
+
+    pub async fn run(self) -> Result<FileHandle> {
+        info!("Epoch snapshot export started.");
+        let ret = self
+            .run_impl()
+            .await
+            .map_err(|e| anyhow!("Epoch snapshot export failed: {}", e))?;
+        info!("Epoch snapshot export succeeded. Manifest: {}", ret);
+        Ok(ret)
+    }
+
+    async fn run_impl(self) -> Result<FileHandle> {
+        let db_state = self
+            .client
+            .get_db_state()
+            .await?
+            .ok_or_else(|| anyhow!("DB not bootstrapped."))?;
+        let epoch = match self.epoch {
+            Some(epoch) => {
+                ensure!(
+                    epoch < db_state.epoch,
+                    "Epoch {} hasn't ended, the current epoch is {}.",
+                    epoch,
+                    db_state.epoch,
+                );
+                epoch
+            }
+            None => db_state
+                .epoch
+                .checked_sub(1)
+                .ok_or_else(|| anyhow!("No epoch has ended yet."))?,
+        };
+
+        let epoch_ending = EpochEndingBackupController::new(
+            EpochEndingBackupOpt {
+                start_epoch: 0,
+                end_epoch: epoch + 1,
+            },
+            self.global_opt.clone(),
+            Arc::clone(&self.client),
+            Arc::clone(&self.storage),
+        )
+        .run()
+        .await?;
+        let waypoint = *self
+            .storage
+            .load_json_file::<EpochEndingBackup>(&epoch_ending)
+            .await?
+            .waypoints
+            .last()
+            .expect("No waypoints.");
+        let version = waypoint.version();
+
+        let state_snapshot = StateSnapshotBackupController::new(
+            StateSnapshotBackupOpt { version },
+            self.global_opt.clone(),
+            Arc::clone(&self.client),
+            Arc::clone(&self.storage),
+        )
+        .run()
+        .await?;
+        let transaction = TransactionBackupController::new(
+            TransactionBackupOpt {
+                start_version: version,
+                num_transactions: 1,
+            },
+            self.global_opt.clone(),
+            Arc::clone(&self.client),
+            Arc::clone(&self.storage),
+        )
+        .run()
+        .await?;
+
+        let manifest = EpochSnapshot {
+            epoch,
+            version,
+            waypoint,
+            epoch_ending,
+            state_snapshot,
+            transaction,
+        };
+        let backup_handle = self
+            .storage
+            .create_backup_with_random_suffix(&format!("epoch_snapshot_{}-", epoch))
+            .await?;
+        let (manifest_handle, mut manifest_file) = self
+            .storage
+            .create_for_write(&backup_handle, Self::manifest_name())
+            .await?;
+        manifest_file
+            .write_all(&serde_json::to_vec(&manifest)?)
+            .await?;
+        manifest_file.shutdown().await?;
+
+        Ok(manifest_handle)
+    }
+
+    fn manifest_name() -> &'static ShellSafeName {
+        static NAME: Lazy<ShellSafeName> =
+            Lazy::new(|| ShellSafeName::from_str("epoch_snapshot.manifest").unwrap());
+        &NAME
+    }
+}
+
+#[derive(StructOpt)]
+pub struct EpochSnapshotImportOpt {
+    #[structopt(long = "snapshot-manifest", help = "Manifest of the epoch snapshot.")]
+    pub manifest_handle: FileHandle,
+}
+
+pub struct EpochSnapshotImportController {
+    manifest_handle: FileHandle,
+    global_opt: GlobalRestoreOptions,
+    storage: Arc<dyn BackupStorage>,
+}
+
+impl EpochSnapshotImportController {
+    pub fn new(
+        opt: EpochSnapshotImportOpt,
+        global_opt: GlobalRestoreOptions,
+        storage: Arc<dyn BackupStorage>,
+    ) -> Self {
+        Self {
+            manifest_handle: opt.manifest_handle,
+            global_opt,
+            storage,
+        }
+    }
+
+    pub async fn run(self) -> Result<()> {
+        info!(
+            "Epoch snapshot import started. Manifest: {}",
+            self.manifest_handle
+        );
+        self.run_impl()
+            .await
+            .map_err(|e| anyhow!("Epoch snapshot import failed: {}", e))?;
+        info!("Epoch snapshot import succeeded.");
+        Ok(())
+    }
+
+    async fn run_impl(self) -> Result<()> {
+        let snapshot: EpochSnapshot = self.storage.load_json_file(&self.manifest_handle).await?;
+        let epoch_ending: EpochEndingBackup =
+            self.storage.load_json_file(&snapshot.epoch_ending).await?;
+        ensure!(
+            epoch_ending.first_epoch == 0
+                && epoch_ending.last_epoch == snapshot.epoch
+                && epoch_ending.waypoints.last() == Some(&snapshot.waypoint),
+            "Epoch endings in the snapshot don't lead from genesis to its waypoint {}.",
+            snapshot.waypoint,
+        );
+        let trusted = &self.global_opt.trusted_waypoints;
+        ensure!(
+            epoch_ending
+                .waypoints
+                .iter()
+                .any(|wp| trusted.get(&wp.version()) == Some(wp)),
+            "No waypoint of the snapshot is trusted, pass the genesis waypoint or a later one \
+            with --trust-waypoint.",
+        );
+        ensure!(
+            snapshot.version <= self.global_opt.target_version,
+            "Snapshot at version {} is beyond the target version {}.",
+            snapshot.version,
+            self.global_opt.target_version,
+        );
+
+        let epoch_history = Arc::new(
+            EpochHistoryRestoreController::new(
+                vec![snapshot.epoch_ending],
+                self.global_opt.clone(),
+                Arc::clone(&self.storage),
+            )
+            .run()
+            .await?,
+        );
+        StateSnapshotRestoreController::new(
+            StateSnapshotRestoreOpt {
+                manifest_handle: snapshot.state_snapshot,
+                version: snapshot.version,
+            },
+            self.global_opt.clone(),
+            Arc::clone(&self.storage),
+            Some(Arc::clone(&epoch_history)),
+        )
+        .run()
+        .await?;
+        TransactionRestoreBatchController::new(
+            self.global_opt,
+            self.storage,
+            vec![snapshot.transaction],
+            Some(snapshot.version + 1),
+            Some(epoch_history),
+        )
+        .run()
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coordinators::snapshot::{
+            EpochSnapshotExportController, EpochSnapshotExportOpt, EpochSnapshotImportController,
+            EpochSnapshotImportOpt,
+        },
+        storage::{local_fs::LocalFs, BackupStorage},
+        utils::{
+            backup_service_client::BackupServiceClient,
+            test_utils::{start_local_backup_service, tmp_db_with_random_content},
+            ConcurrentDownloadsOpt, GlobalBackupOpt, GlobalRestoreOpt, RocksdbOpt,
+            TrustedWaypointOpt,
+        },
+    };
+    use diem_config::config::RocksdbConfig;
+    use diem_temppath::TempPath;
+    use diem_types::waypoint::Waypoint;
+    use diemdb::DiemDB;
+    use std::{convert::TryInto, sync::Arc};
+    use storage_interface::DbReader;
+    use tokio::time::Duration;
+
+    #[test]
+    fn end_to_end() {
+        let (_src_db_dir, src_db, blocks) = tmp_db_with_random_content();
+        let tgt_db_dir = TempPath::new();
+        tgt_db_dir.create_as_dir().unwrap();
+        let backup_dir = TempPath::new();
+        backup_dir.create_as_dir().unwrap();
+        let store: Arc<dyn BackupStorage> = Arc::new(LocalFs::new(backup_dir.path().to_path_buf()));
+
+        let epoch_endings = blocks
+            .iter()
+            .map(|(_, li)| li.ledger_info())
+            .filter(|li| li.ends_epoch())
+            .collect::<Vec<_>>();
+        let genesis = Waypoint::new_epoch_boundary(epoch_endings[0]).unwrap();
+        let version = epoch_endings.last().unwrap().version();
+
+        let (rt, port) = start_local_backup_service(src_db);
+        let client = Arc::new(BackupServiceClient::new(format!(
+            "http://localhost:{}",
+            port
+        )));
+
+        let manifest_handle = rt
+            .block_on(
+                EpochSnapshotExportController::new(
+                    EpochSnapshotExportOpt { epoch: None },
+                    GlobalBackupOpt {
+                        max_chunk_size: 1024,
+                    },
+                    client,
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+            .unwrap();
+
+        let import = |trust_waypoint| {
+            rt.block_on(
+                EpochSnapshotImportController::new(
+                    EpochSnapshotImportOpt {
+                        manifest_handle: manifest_handle.clone(),
+                    },
+                    GlobalRestoreOpt {
+                        dry_run: false,
+                        db_dir: Some(tgt_db_dir.path().to_path_buf()),
+                        target_version: None, // max
+                        trusted_waypoints: TrustedWaypointOpt { trust_waypoint },
+                        rocksdb_opt: RocksdbOpt::default(),
+                        concurernt_downloads: ConcurrentDownloadsOpt::default(),
+                    }
+                    .try_into()
+                    .unwrap(),
+                    Arc::clone(&store),
+                )
+                .run(),
+            )
+        };
+        // Without a trusted waypoint the snapshot is refused
+        assert!(import(vec![]).is_err());
+        import(vec![genesis]).unwrap();
+
+        let tgt_db = DiemDB::open(
+            &tgt_db_dir,
+            true, /* read_only */
+            None, /* pruner */
+            RocksdbConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            tgt_db.get_latest_tree_state().unwrap().num_transactions,
+            version + 1,
+        );
+
+        rt.shutdown_timeout(Duration::from_secs(1));
+    }
+}
@@ -47,6 +47,11 @@ impl MetadataCacheOpt {
     // in cache we save things other than the cached files.
     const SUB_DIR: &'static str = "cache";
 
+    //////// 0L ////////
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
     fn cache_dir(&self) -> PathBuf {
         self.dir
             .clone()
@@ -34,6 +34,18 @@ impl MetadataView {
         }
     }
 
+    //////// 0L ////////
+    /// The state snapshots older than the `retention` latest ones
+    pub fn expired_state_snapshots(&self, retention: usize) -> Vec<StateSnapshotBackupMeta> {
+        self.state_snapshot_backups
+            .iter()
+            .sorted()
+            .rev()
+            .skip(retention)
+            .cloned()
+            .collect()
+    }
+
     pub fn select_state_snapshot(
         &self,
         target_version: Version,
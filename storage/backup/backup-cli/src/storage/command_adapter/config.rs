@@ -33,6 +33,11 @@ impl EnvVar {
         Self::new("BACKUP_HANDLE".to_string(), value)
     }
 
+    //////// 0L ////////
+    pub fn metadata_file_handle(value: FileHandle) -> Self {
+        Self::new("METADATA_FILE_HANDLE".to_string(), value)
+    }
+
     pub fn new(key: String, value: String) -> Self {
         Self { key, value }
     }
@@ -67,6 +72,13 @@ pub struct Commands {
     /// Command line to list all existing metadata file handles.
     /// expected stdout to stream out lines of file handles.
     pub list_metadata_files: String,
+    //////// 0L ////////
+    /// Optional command line to remove a backup, for retention.
+    /// input env vars:
+    ///     $METADATA_FILE_HANDLE of the metadata file to remove first
+    ///     $FILE_HANDLE of a file of the backup to remove then
+    #[serde(default)]
+    pub remove_backup: Option<String>,
 }
 
 #[derive(Clone, Default, Deserialize)]
@@ -18,9 +18,9 @@ use crate::{
     },
     utils::error_notes::ErrorNotes,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
@@ -50,6 +50,26 @@ impl CommandAdapter {
         Ok(Self::new(config))
     }
 
+    //////// 0L ////////
+    /// The store of `s3.sample.toml`, under `sub_dir` of `bucket` in S3, or in the S3 compatible
+    /// store at `endpoint_url`
+    pub fn new_s3(bucket: &str, sub_dir: &str, endpoint_url: Option<&str>) -> Result<Self> {
+        let mut config = CommandAdapterConfig::load_from_str(include_str!("s3.sample.toml"))?;
+        config.env_vars = vec![
+            EnvVar::new("BUCKET".to_string(), bucket.to_string()),
+            EnvVar::new("SUB_DIR".to_string(), sub_dir.to_string()),
+            EnvVar::new(
+                "ENDPOINT_URL".to_string(),
+                endpoint_url.unwrap_or_default().to_string(),
+            ),
+        ];
+        Ok(Self::new(config))
+    }
+
+    pub async fn new_with_config_path(path: &Path) -> Result<Self> {
+        Ok(Self::new(CommandAdapterConfig::load_from_file(path).await?))
+    }
+
     fn cmd(&self, cmd_str: &str, env_vars: Vec<EnvVar>) -> Command {
         Command::new(cmd_str, env_vars, self.config.env_vars.clone())
     }
@@ -143,4 +163,28 @@ impl BackupStorage for CommandAdapter {
             .err_notes((file!(), line!(), &buf))?;
         Ok(buf.lines().map(str::to_string).collect())
     }
+
+    //////// 0L ////////
+    async fn remove_backup(
+        &self,
+        file_handle: &FileHandleRef,
+        metadata_handle: &FileHandleRef,
+    ) -> Result<()> {
+        let cmd = self
+            .config
+            .commands
+            .remove_backup
+            .as_ref()
+            .ok_or_else(|| anyhow!("No remove_backup command configured."))?;
+        self.cmd(
+            cmd,
+            vec![
+                EnvVar::metadata_file_handle(metadata_handle.to_string()),
+                EnvVar::file_handle(file_handle.to_string()),
+            ],
+        )
+        .spawn()?
+        .join()
+        .await
+    }
 }
@@ -194,3 +194,11 @@ fn test_pipe_fail() {
         "echo okay | (cat; true) | cat; exec 1>&-; cat | (cat; true) | cat > /dev/null",
     ));
 }
+
+//////// 0L ////////
+#[test]
+fn test_s3_sample() {
+    let store = CommandAdapter::new_s3("diem-backup", "e1", Some("https://minio:9000")).unwrap();
+    assert!(store.config.commands.remove_backup.is_some());
+    assert_eq!(store.config.env_vars.len(), 3);
+}
@@ -10,12 +10,12 @@ use crate::{
     storage::{BackupStorage, ShellSafeName, TextLine},
     utils::{error_notes::ErrorNotes, path_exists, PathToString},
 };
-use anyhow::Result;
+use anyhow::{anyhow, ensure, Result};
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use tokio::{
-    fs::{create_dir, create_dir_all, read_dir, OpenOptions},
+    fs::{create_dir, create_dir_all, read_dir, remove_dir_all, remove_file, OpenOptions},
     io::{AsyncRead, AsyncWrite, AsyncWriteExt},
 };
 
@@ -122,4 +122,27 @@ impl BackupStorage for LocalFs {
         }
         Ok(res)
     }
+
+    //////// 0L ////////
+    async fn remove_backup(
+        &self,
+        file_handle: &FileHandleRef,
+        metadata_handle: &FileHandleRef,
+    ) -> Result<()> {
+        let metadata = self.dir.join(metadata_handle);
+        remove_file(&metadata).await.err_notes(&metadata)?;
+        let backup = self
+            .dir
+            .join(file_handle)
+            .parent()
+            .ok_or_else(|| anyhow!("File handle outside of a backup: {}", file_handle))?
+            .to_path_buf();
+        ensure!(
+            backup != self.dir,
+            "File handle outside of a backup: {}",
+            file_handle
+        );
+        remove_dir_all(&backup).await.err_notes(&backup)?;
+        Ok(())
+    }
 }
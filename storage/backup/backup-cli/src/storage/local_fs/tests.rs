@@ -8,7 +8,7 @@ use crate::storage::test_util::{
 };
 use diem_temppath::TempPath;
 use proptest::prelude::*;
-use tokio::runtime::Runtime;
+use tokio::{io::AsyncWriteExt, runtime::Runtime};
 
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
@@ -37,3 +37,34 @@ proptest! {
         rt.block_on(test_save_and_list_metadata_files_impl(Box::new(store), input));
     }
 }
+
+//////// 0L ////////
+#[test]
+fn test_remove_backup() {
+    let tmpdir = TempPath::new();
+    tmpdir.create_as_dir().unwrap();
+    let store = LocalFs::new(tmpdir.path().to_path_buf());
+    let name = |s: &str| s.parse::<ShellSafeName>().unwrap();
+
+    let rt = Runtime::new().unwrap();
+    rt.block_on(async {
+        let backup_handle = store.create_backup(&name("backup")).await.unwrap();
+        let (file_handle, mut file) = store
+            .create_for_write(&backup_handle, &name("manifest"))
+            .await
+            .unwrap();
+        file.shutdown().await.unwrap();
+        store
+            .save_metadata_line(&name("backup.meta"), &TextLine::new("{}").unwrap())
+            .await
+            .unwrap();
+        let metadata_handles = store.list_metadata_files().await.unwrap();
+
+        store
+            .remove_backup(&file_handle, &metadata_handles[0])
+            .await
+            .unwrap();
+        assert!(store.list_metadata_files().await.unwrap().is_empty());
+        assert!(!tmpdir.path().join(&backup_handle).exists());
+    });
+}
@@ -13,8 +13,9 @@ use crate::storage::{
     command_adapter::{CommandAdapter, CommandAdapterOpt},
     local_fs::{LocalFs, LocalFsOpt},
 };
-use anyhow::{ensure, Result};
+use anyhow::{bail, ensure, Result};
 use async_trait::async_trait;
+use diem_config::config::BackupTarget;
 use once_cell::sync::Lazy;
 #[cfg(test)]
 use proptest::prelude::*;
@@ -167,6 +168,16 @@ pub trait BackupStorage: Send + Sync {
     ///   2. But the cache does expect the content stays the same for a file handle, so when
     /// reorganising metadata files, give them new unique names.
     async fn list_metadata_files(&self) -> Result<Vec<FileHandle>>;
+    //////// 0L ////////
+    /// Removes the metadata file `metadata_handle`, then the backup holding the file
+    /// `file_handle` that it describes. Stores that can't remove keep every backup.
+    async fn remove_backup(
+        &self,
+        _file_handle: &FileHandleRef,
+        _metadata_handle: &FileHandleRef,
+    ) -> Result<()> {
+        bail!("The backup store doesn't remove backups.")
+    }
 }
 
 #[derive(StructOpt)]
@@ -185,3 +196,23 @@ impl StorageOpt {
         })
     }
 }
+
+//////// 0L ////////
+/// The store of the backups of a node, see `BackupConfig`
+pub async fn init_storage_for_target(target: &BackupTarget) -> Result<Arc<dyn BackupStorage>> {
+    Ok(match target {
+        BackupTarget::LocalFs { path } => Arc::new(LocalFs::new(path.clone())),
+        BackupTarget::S3 {
+            bucket,
+            sub_dir,
+            endpoint_url,
+        } => Arc::new(CommandAdapter::new_s3(
+            bucket,
+            sub_dir,
+            endpoint_url.as_deref(),
+        )?),
+        BackupTarget::CommandAdapter { config } => {
+            Arc::new(CommandAdapter::new_with_config_path(config).await?)
+        }
+    })
+}
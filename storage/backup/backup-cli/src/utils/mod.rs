@@ -38,6 +38,12 @@ pub struct GlobalBackupOpt {
     pub max_chunk_size: usize,
 }
 
+//////// 0L ////////
+impl GlobalBackupOpt {
+    /// The default of `--max-chunk-size`
+    pub const DEFAULT_MAX_CHUNK_SIZE: usize = 134_217_728;
+}
+
 #[derive(Clone, StructOpt)]
 pub struct RocksdbOpt {
     // using a smaller value than a node since we don't care much about reading performance
@@ -55,8 +55,8 @@ use crate::{
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
-use anyhow::{ensure, Result};
-use diem_config::config::RocksdbConfig;
+use anyhow::{bail, ensure, Result};
+use diem_config::config::{PruneWindows, RocksdbConfig};
 use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
 use diem_logger::prelude::*;
 use diem_types::{
@@ -230,7 +230,7 @@ impl DiemDB {
         ]
     }
 
-    fn new_with_db(db: DB, prune_window: Option<u64>) -> Self {
+    fn new_with_db(db: DB, prune_windows: PruneWindows) -> Self {
         let db = Arc::new(db);
 
         DiemDB {
@@ -241,7 +241,11 @@ impl DiemDB {
             transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db))),
             system_store: SystemStore::new(Arc::clone(&db)),
             rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
-            pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
+            pruner: if prune_windows.is_pruning() {
+                Some(Pruner::new(Arc::clone(&db), prune_windows))
+            } else {
+                None
+            },
         }
     }
 
@@ -250,9 +254,21 @@ impl DiemDB {
         readonly: bool,
         prune_window: Option<u64>,
         rocksdb_config: RocksdbConfig,
+    ) -> Result<Self> {
+        Self::open_with_prune_windows(db_root_path, readonly, prune_window.into(), rocksdb_config)
+    }
+
+    //////// 0L ////////
+    /// Like `open`, pruning the state store, the ledger history and the events each on its own
+    /// window.
+    pub fn open_with_prune_windows<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        readonly: bool,
+        prune_windows: PruneWindows,
+        rocksdb_config: RocksdbConfig,
     ) -> Result<Self> {
         ensure!(
-            prune_window.is_none() || !readonly,
+            !prune_windows.is_pruning() || !readonly,
             "Do not set prune_window when opening readonly.",
         );
 
@@ -279,7 +295,7 @@ impl DiemDB {
             )?
         };
 
-        let ret = Self::new_with_db(db, prune_window);
+        let ret = Self::new_with_db(db, prune_windows);
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -307,7 +323,7 @@ impl DiemDB {
                 Self::column_families(),
                 &rocksdb_opts,
             )?,
-            None, // prune_window
+            PruneWindows::default(),
         ))
     }
 
@@ -588,6 +604,23 @@ impl DiemDB {
         Ok(())
     }
 
+    //////// 0L ////////
+    /// Changes the prune window of the state store of a running db. Fails if it was opened
+    /// without pruning the state store.
+    pub fn set_prune_window(&self, prune_window: u64) -> Result<()> {
+        match self.pruner.as_ref() {
+            Some(pruner) => pruner.set_historical_versions_to_keep(prune_window),
+            None => bail!("DiemDB was opened without a prune window"),
+        }
+    }
+
+    //////// 0L ////////
+    /// Flushes the memtables, so that the db reopens without replaying its write ahead log. Called
+    /// once nothing writes to the db anymore.
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush_all()
+    }
+
     fn wake_pruner(&self, latest_version: Version) {
         if let Some(pruner) = self.pruner.as_ref() {
             pruner.wake(latest_version)
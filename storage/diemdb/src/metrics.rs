@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_metrics::{
-    register_histogram_vec, register_int_counter, register_int_gauge, register_int_gauge_vec,
-    HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -63,6 +63,25 @@ pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION: Lazy<IntGauge> = La
     .unwrap()
 });
 
+//////// 0L ////////
+pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_storage_pruner_least_readable_version",
+        "Diem storage pruner least readable version per bucket",
+        &["bucket"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_PRUNER_RECLAIMED_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_storage_pruner_reclaimed_bytes",
+        "Diem storage bytes deleted by the pruner per bucket, before compression",
+        &["bucket"]
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
@@ -7,18 +7,30 @@
 use crate::{
     metrics::{
         DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION,
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION, DIEM_STORAGE_PRUNER_RECLAIMED_BYTES,
         DIEM_STORAGE_PRUNE_WINDOW,
     },
     schema::{
+        event::EventSchema, event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
         jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema,
+        transaction_info::TransactionInfoSchema,
     },
 };
-use anyhow::Result;
+use anyhow::{bail, Result};
+use diem_config::config::PruneWindows;
 use diem_infallible::Mutex;
 use diem_jellyfish_merkle::StaleNodeIndex;
 use diem_logger::prelude::*;
-use diem_types::transaction::Version;
-use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
+use diem_types::{
+    proof::position::Position,
+    transaction::{Transaction, Version},
+};
+use schemadb::{
+    schema::{KeyCodec, Schema, ValueCodec},
+    ReadOptions, SchemaBatch, SchemaIterator, DB,
+};
 use std::{
     iter::Peekable,
     sync::{
@@ -30,6 +42,59 @@ use std::{
     time::{Duration, Instant},
 };
 
+//////// 0L ////////
+/// A part of the DB pruned on its own window
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Bucket {
+    /// The state tree
+    StateStore,
+    /// The transactions and their infos
+    Ledger,
+    /// The events and their accumulators
+    EventStore,
+}
+
+impl Bucket {
+    const COUNT: usize = 3;
+    const ALL: [Bucket; Bucket::COUNT] = [Bucket::StateStore, Bucket::Ledger, Bucket::EventStore];
+
+    fn name(self) -> &'static str {
+        match self {
+            Bucket::StateStore => "state_store",
+            Bucket::Ledger => "ledger",
+            Bucket::EventStore => "event_store",
+        }
+    }
+
+    fn window(self, windows: &PruneWindows) -> Option<u64> {
+        match self {
+            Bucket::StateStore => windows.state_store,
+            Bucket::Ledger => windows.ledger,
+            Bucket::EventStore => windows.event_store,
+        }
+    }
+
+    fn prune(
+        self,
+        db: Arc<DB>,
+        least_readable_version: Version,
+        target_least_readable_version: Version,
+        max_versions: usize,
+    ) -> Result<Version> {
+        let prune = match self {
+            Bucket::StateStore => prune_state,
+            Bucket::Ledger => prune_ledger,
+            Bucket::EventStore => prune_events,
+        };
+        prune(
+            db,
+            least_readable_version,
+            target_least_readable_version,
+            max_versions,
+        )
+    }
+}
+
 /// The `Pruner` is meant to be part of a `DiemDB` instance and runs in the background to prune old
 /// data.
 ///
@@ -37,49 +102,94 @@ use std::{
 /// quits the worker thread eagerly without waiting for all pending work to be done.
 #[derive(Debug)]
 pub(crate) struct Pruner {
-    /// Other than the latest version, how many historical versions to keep being readable. For
-    /// example, this being 0 means keep only the latest version.
-    historical_versions_to_keep: u64,
+    /// Per bucket, other than the latest version, how many historical versions to keep being
+    /// readable, `None` if the bucket isn't pruned. For example, this being 0 means keep only the
+    /// latest version.
+    historical_versions_to_keep: [Option<AtomicU64>; Bucket::COUNT],
     /// The worker thread handle, created upon Pruner instance construction and joined upon its
     /// destruction. It only becomes `None` after joined in `drop()`.
     worker_thread: Option<JoinHandle<()>>,
     /// The sender side of the channel talking to the worker thread.
     command_sender: Mutex<Sender<Command>>,
-    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress. If it
-    /// sets this atomic value to `V`, all versions before `V` can no longer be accessed.
+    /// (For tests) A way for the worker thread to inform the `Pruner` the pruning progress of each
+    /// bucket. If it sets this atomic value to `V`, all versions before `V` can no longer be
+    /// accessed.
     #[allow(dead_code)]
-    worker_progress: Arc<AtomicU64>,
+    worker_progress: Arc<[AtomicU64; Bucket::COUNT]>,
 }
 
 impl Pruner {
     /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, historical_versions_to_keep: u64) -> Self {
+    pub fn new(db: Arc<DB>, windows: PruneWindows) -> Self {
         let (command_sender, command_receiver) = channel();
 
-        let worker_progress = Arc::new(AtomicU64::new(0));
+        let worker_progress = Arc::new(<[AtomicU64; Bucket::COUNT]>::default());
         let worker_progress_clone = Arc::clone(&worker_progress);
 
-        DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
+        if let Some(window) = windows.state_store {
+            DIEM_STORAGE_PRUNE_WINDOW.set(window as i64);
+        }
+        let buckets = Bucket::ALL
+            .iter()
+            .copied()
+            .filter(|bucket| bucket.window(&windows).is_some())
+            .collect();
         let worker_thread = std::thread::Builder::new()
             .name("diemdb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work())
+            .spawn(move || Worker::new(db, command_receiver, buckets, worker_progress_clone).work())
             .expect("Creating pruner thread should succeed.");
 
+        let window = |bucket: Bucket| bucket.window(&windows).map(AtomicU64::new);
         Self {
-            historical_versions_to_keep,
+            historical_versions_to_keep: [
+                window(Bucket::StateStore),
+                window(Bucket::Ledger),
+                window(Bucket::EventStore),
+            ],
             worker_thread: Some(worker_thread),
             command_sender: Mutex::new(command_sender),
             worker_progress,
         }
     }
 
+    //////// 0L ////////
+    /// Changes the window of the state store from the next wake on. Versions already pruned stay
+    /// pruned.
+    pub fn set_historical_versions_to_keep(&self, historical_versions_to_keep: u64) -> Result<()> {
+        match &self.historical_versions_to_keep[Bucket::StateStore as usize] {
+            Some(window) => {
+                window.store(historical_versions_to_keep, Ordering::Relaxed);
+                DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
+                Ok(())
+            }
+            None => bail!("DiemDB was opened without a prune window for the state store"),
+        }
+    }
+
+    /// The least readable version of each bucket pruned, once pruned up to `latest_version`
+    fn least_readable_versions(
+        &self,
+        latest_version: Version,
+    ) -> impl Iterator<Item = (Bucket, Version)> + '_ {
+        Bucket::ALL.iter().filter_map(move |bucket| {
+            let historical_versions_to_keep = self.historical_versions_to_keep[*bucket as usize]
+                .as_ref()?
+                .load(Ordering::Relaxed);
+            if latest_version > historical_versions_to_keep {
+                Some((*bucket, latest_version - historical_versions_to_keep))
+            } else {
+                None
+            }
+        })
+    }
+
     /// Sends pruning command to the worker thread when necessary.
     pub fn wake(&self, latest_version: Version) {
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
+        for (bucket, least_readable_version) in self.least_readable_versions(latest_version) {
             self.command_sender
                 .lock()
                 .send(Command::Prune {
+                    bucket,
                     least_readable_version,
                 })
                 .expect("Receiver should not destruct prematurely.");
@@ -92,19 +202,22 @@ impl Pruner {
     pub fn wake_and_wait(&self, latest_version: Version) -> Result<()> {
         self.wake(latest_version);
 
-        if latest_version > self.historical_versions_to_keep {
-            let least_readable_version = latest_version - self.historical_versions_to_keep;
+        for (bucket, least_readable_version) in self.least_readable_versions(latest_version) {
             // Assuming no big pruning chunks will be issued by a test.
             const TIMEOUT: Duration = Duration::from_secs(10);
             let end = Instant::now() + TIMEOUT;
 
-            while Instant::now() < end {
-                if self.worker_progress.load(Ordering::Relaxed) >= least_readable_version {
-                    return Ok(());
+            loop {
+                if self.worker_progress[bucket as usize].load(Ordering::Relaxed)
+                    >= least_readable_version
+                {
+                    break;
+                }
+                if Instant::now() >= end {
+                    anyhow::bail!("Timeout waiting for pruner worker.");
                 }
                 sleep(Duration::from_millis(1));
             }
-            anyhow::bail!("Timeout waiting for pruner worker.");
         }
         Ok(())
     }
@@ -126,22 +239,28 @@ impl Drop for Pruner {
 
 enum Command {
     Quit,
-    Prune { least_readable_version: Version },
+    Prune {
+        bucket: Bucket,
+        least_readable_version: Version,
+    },
 }
 
 struct Worker {
     db: Arc<DB>,
     command_receiver: Receiver<Command>,
-    target_least_readable_version: Version,
-    /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
-    /// smaller than `V` are no longer readable.
+    /// The buckets pruned, the others are kept whole.
+    buckets: Vec<Bucket>,
+    target_least_readable_versions: [Version; Bucket::COUNT],
+    /// Keeps a record of the pruning progress of each bucket. If this equals to version `V`, we
+    /// know versions smaller than `V` are no longer readable.
     /// This being an atomic value is to communicate the info with the Pruner thread (for tests).
-    least_readable_version: Arc<AtomicU64>,
+    least_readable_versions: Arc<[AtomicU64; Bucket::COUNT]>,
     /// Indicates if there's NOT any pending work to do currently, to hint
     /// `Self::receive_commands()` to `recv()` blocking-ly.
     blocking_recv: bool,
     index_min_nonpurged_version: Version,
     index_purged_at: Instant,
+    reported_at: Instant,
 }
 
 impl Worker {
@@ -150,16 +269,19 @@ impl Worker {
     fn new(
         db: Arc<DB>,
         command_receiver: Receiver<Command>,
-        least_readable_version: Arc<AtomicU64>,
+        buckets: Vec<Bucket>,
+        least_readable_versions: Arc<[AtomicU64; Bucket::COUNT]>,
     ) -> Self {
         Self {
             db,
             command_receiver,
-            least_readable_version,
-            target_least_readable_version: 0,
+            buckets,
+            least_readable_versions,
+            target_least_readable_versions: [0; Bucket::COUNT],
             blocking_recv: true,
             index_min_nonpurged_version: 0,
             index_purged_at: Instant::now(),
+            reported_at: Instant::now(),
         }
     }
 
@@ -169,85 +291,126 @@ impl Worker {
         while self.receive_commands() {
             // Process a reasonably small batch of work before trying to receive commands again,
             // in case `Command::Quit` is received (that's when we should quit.)
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
-            match prune_state(
-                Arc::clone(&self.db),
-                least_readable_version,
-                self.target_least_readable_version,
-                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
-            ) {
-                Ok(new_least_readable_version) => {
-                    self.record_progress(new_least_readable_version);
-
-                    // Make next recv() blocking if nothing left to do.
-                    self.blocking_recv = new_least_readable_version == least_readable_version // did nothing
-                        || new_least_readable_version == self.target_least_readable_version; // did all
-
-                    // Try to purge the log.
-                    if let Err(e) = self.maybe_purge_index() {
-                        warn!(
+            let mut done = true;
+            for bucket in self.buckets.clone() {
+                let least_readable_version = self.least_readable_version(bucket);
+                let target_least_readable_version =
+                    self.target_least_readable_versions[bucket as usize];
+                match bucket.prune(
+                    Arc::clone(&self.db),
+                    least_readable_version,
+                    target_least_readable_version,
+                    Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+                ) {
+                    Ok(new_least_readable_version) => {
+                        self.record_progress(bucket, new_least_readable_version);
+
+                        // Make next recv() blocking if nothing left to do, i.e. the bucket did
+                        // nothing or did all.
+                        done &= new_least_readable_version == least_readable_version
+                            || new_least_readable_version == target_least_readable_version;
+                    }
+                    Err(e) => {
+                        // On error, stop retrying vigorously by not counting the bucket as
+                        // pending work.
+                        error!(
+                            bucket = bucket.name(),
                             error = ?e,
-                            "Failed purging state node index, ignored.",
+                            "Error pruning.",
                         );
                     }
                 }
-                Err(e) => {
-                    error!(
+            }
+            self.blocking_recv = done;
+
+            if self.buckets.contains(&Bucket::StateStore) {
+                // Try to purge the log.
+                if let Err(e) = self.maybe_purge_index() {
+                    warn!(
                         error = ?e,
-                        "Error pruning stale state nodes.",
+                        "Failed purging state node index, ignored.",
                     );
-                    // On error, stop retrying vigorously by making next recv() blocking.
-                    self.blocking_recv = true;
                 }
             }
+            self.maybe_report();
         }
     }
 
-    /// Find out the first undeleted item in the stale node index.
+    /// Find out the first undeleted item of each bucket pruned.
     ///
     /// Seeking from the beginning (version 0) is potentially costly, we do it once upon worker
     /// thread start, record the progress and seek from that position afterwards.
     fn initialize(&mut self) {
-        loop {
-            match self.get_least_readable_version() {
-                Ok(least_readable_version) => {
-                    info!(
-                        least_readable_version = least_readable_version,
-                        "[state pruner worker] initialized."
-                    );
-                    self.target_least_readable_version = least_readable_version;
-                    self.record_progress(least_readable_version);
-                    return;
-                }
-                Err(e) => {
-                    error!(
-                        error = ?e,
-                        "[state pruner worker] Error on first seek. Retrying in 1 second.",
-                    );
-                    sleep(Duration::from_secs(1));
+        for bucket in self.buckets.clone() {
+            loop {
+                match self.get_least_readable_version(bucket) {
+                    Ok(least_readable_version) => {
+                        info!(
+                            bucket = bucket.name(),
+                            least_readable_version = least_readable_version,
+                            "[pruner worker] initialized."
+                        );
+                        self.target_least_readable_versions[bucket as usize] =
+                            least_readable_version;
+                        self.record_progress(bucket, least_readable_version);
+                        break;
+                    }
+                    Err(e) => {
+                        error!(
+                            bucket = bucket.name(),
+                            error = ?e,
+                            "[pruner worker] Error on first seek. Retrying in 1 second.",
+                        );
+                        sleep(Duration::from_secs(1));
+                    }
                 }
             }
         }
     }
 
-    fn get_least_readable_version(&self) -> Result<Version> {
-        let mut iter = self
-            .db
-            .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
-        iter.seek_to_first();
-        Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
-            index
-                .stale_since_version
-                .checked_sub(1)
-                .expect("Nothing is stale since version 0.")
-        }))
+    fn get_least_readable_version(&self, bucket: Bucket) -> Result<Version> {
+        let opts = ReadOptions::default;
+        match bucket {
+            Bucket::StateStore => {
+                let mut iter = self.db.iter::<StaleNodeIndexSchema>(opts())?;
+                iter.seek_to_first();
+                Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
+                    index
+                        .stale_since_version
+                        .checked_sub(1)
+                        .expect("Nothing is stale since version 0.")
+                }))
+            }
+            Bucket::Ledger => {
+                let mut iter = self.db.iter::<TransactionSchema>(opts())?;
+                iter.seek_to_first();
+                Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+            }
+            Bucket::EventStore => {
+                let mut iter = self.db.iter::<EventSchema>(opts())?;
+                iter.seek_to_first();
+                Ok(iter
+                    .next()
+                    .transpose()?
+                    .map_or(0, |((version, _), _)| version))
+            }
+        }
+    }
+
+    fn least_readable_version(&self, bucket: Bucket) -> Version {
+        self.least_readable_versions[bucket as usize].load(Ordering::Relaxed)
     }
 
     /// Log the progress.
-    fn record_progress(&mut self, least_readable_version: Version) {
-        self.least_readable_version
+    fn record_progress(&mut self, bucket: Bucket, least_readable_version: Version) {
+        self.least_readable_versions[bucket as usize]
             .store(least_readable_version, Ordering::Relaxed);
-        DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION
+            .with_label_values(&[bucket.name()])
+            .set(least_readable_version as i64);
+        if bucket == Bucket::StateStore {
+            DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
+        }
     }
 
     /// Tries to receive all pending commands, blocking waits for the next command if no work needs
@@ -276,10 +439,12 @@ impl Worker {
                 // On `Command::Quit` inform the outer loop to quit by returning `false`.
                 Command::Quit => return false,
                 Command::Prune {
+                    bucket,
                     least_readable_version,
                 } => {
-                    if least_readable_version > self.target_least_readable_version {
-                        self.target_least_readable_version = least_readable_version;
+                    let target = &mut self.target_least_readable_versions[bucket as usize];
+                    if least_readable_version > *target {
+                        *target = least_readable_version;
                         // Switch to non-blocking to allow some work to be done after the
                         // channel has drained.
                         self.blocking_recv = false;
@@ -303,7 +468,7 @@ impl Worker {
         // this imposes at most one minute of work in vain after restarting.)
         let now = Instant::now();
         if now - self.index_purged_at > MIN_INTERVAL {
-            let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
+            let least_readable_version = self.least_readable_version(Bucket::StateStore);
 
             if least_readable_version - self.index_min_nonpurged_version + 1 > MIN_VERSIONS {
                 let new_min_non_purged_version = least_readable_version + 1;
@@ -318,6 +483,28 @@ impl Worker {
 
         Ok(())
     }
+
+    //////// 0L ////////
+    /// Logs the progress of each bucket and the space it reclaimed since the start, every few
+    /// minutes.
+    fn maybe_report(&mut self) {
+        const INTERVAL: Duration = Duration::from_secs(600);
+
+        let now = Instant::now();
+        if now - self.reported_at > INTERVAL {
+            for bucket in &self.buckets {
+                info!(
+                    bucket = bucket.name(),
+                    least_readable_version = self.least_readable_version(*bucket),
+                    reclaimed_bytes = DIEM_STORAGE_PRUNER_RECLAIMED_BYTES
+                        .with_label_values(&[bucket.name()])
+                        .get(),
+                    "[pruner worker] progress."
+                );
+            }
+            self.reported_at = now;
+        }
+    }
 }
 
 struct StaleNodeIndicesByVersionIterator<'a> {
@@ -395,18 +582,129 @@ pub fn prune_state(
     if indices.is_empty() {
         Ok(least_readable_version)
     } else {
-        let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
-            .with_label_values(&["pruner_commit"])
-            .start_timer();
         let new_least_readable_version = indices.last().expect("Should exist.").stale_since_version;
         let mut batch = SchemaBatch::new();
-        indices
-            .into_iter()
-            .try_for_each(|index| batch.delete::<JellyfishMerkleNodeSchema>(&index.node_key))?;
-        db.write_schemas(batch)?;
+        let mut reclaimed_bytes = 0;
+        for index in indices {
+            if let Some(node) = db.get::<JellyfishMerkleNodeSchema>(&index.node_key)? {
+                reclaimed_bytes +=
+                    encoded_size::<JellyfishMerkleNodeSchema>(&index.node_key, &node)?;
+            }
+            batch.delete::<JellyfishMerkleNodeSchema>(&index.node_key)?;
+        }
+        commit(&db, batch, Bucket::StateStore, reclaimed_bytes)?;
         Ok(new_least_readable_version)
     }
 }
 
+//////// 0L ////////
+/// Prunes the transactions, their infos and their index by account before
+/// `target_least_readable_version`, at most `max_versions` of them, returning the new least
+/// readable version.
+pub fn prune_ledger(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+
+    let mut new_least_readable_version = target_least_readable_version;
+    let mut batch = SchemaBatch::new();
+    let mut reclaimed_bytes = 0;
+    for (pruned, item) in iter.enumerate() {
+        let (version, transaction) = item?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        if pruned == max_versions {
+            new_least_readable_version = version;
+            break;
+        }
+
+        if let Transaction::UserTransaction(txn) = &transaction {
+            let key = (txn.sender(), txn.sequence_number());
+            reclaimed_bytes += encoded_size::<TransactionByAccountSchema>(&key, &version)?;
+            batch.delete::<TransactionByAccountSchema>(&key)?;
+        }
+        reclaimed_bytes += encoded_size::<TransactionSchema>(&version, &transaction)?;
+        batch.delete::<TransactionSchema>(&version)?;
+        if let Some(info) = db.get::<TransactionInfoSchema>(&version)? {
+            reclaimed_bytes += encoded_size::<TransactionInfoSchema>(&version, &info)?;
+            batch.delete::<TransactionInfoSchema>(&version)?;
+        }
+    }
+    commit(&db, batch, Bucket::Ledger, reclaimed_bytes)?;
+    Ok(new_least_readable_version)
+}
+
+/// Prunes the events, their indices and accumulators before `target_least_readable_version`, at
+/// most `max_versions` versions of them, returning the new least readable version. The
+/// accumulators are range deleted and not counted in the reclaimed bytes.
+pub fn prune_events(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+
+    let mut new_least_readable_version = target_least_readable_version;
+    let mut batch = SchemaBatch::new();
+    let mut reclaimed_bytes = 0;
+    let mut versions = 0;
+    let mut last_version = None;
+    for item in iter {
+        let ((version, index), event) = item?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        if last_version != Some(version) {
+            if versions == max_versions {
+                new_least_readable_version = version;
+                break;
+            }
+            versions += 1;
+            last_version = Some(version);
+        }
+
+        let by_key = (*event.key(), event.sequence_number());
+        reclaimed_bytes += encoded_size::<EventByKeySchema>(&by_key, &(version, index))?;
+        batch.delete::<EventByKeySchema>(&by_key)?;
+        let by_version = (*event.key(), version, event.sequence_number());
+        reclaimed_bytes += encoded_size::<EventByVersionSchema>(&by_version, &index)?;
+        batch.delete::<EventByVersionSchema>(&by_version)?;
+        reclaimed_bytes += encoded_size::<EventSchema>(&(version, index), &event)?;
+        batch.delete::<EventSchema>(&(version, index))?;
+    }
+    commit(&db, batch, Bucket::EventStore, reclaimed_bytes)?;
+    if new_least_readable_version > least_readable_version {
+        let first_position = Position::from_inorder_index(0);
+        db.range_delete::<EventAccumulatorSchema, (Version, Position)>(
+            &(least_readable_version, first_position),
+            &(new_least_readable_version, first_position), // end is exclusive
+        )?;
+    }
+    Ok(new_least_readable_version)
+}
+
+/// The size of a record as written to the DB, before compression
+fn encoded_size<S: Schema>(key: &S::Key, value: &S::Value) -> Result<u64> {
+    Ok((key.encode_key()?.len() + value.encode_value()?.len()) as u64)
+}
+
+fn commit(db: &DB, batch: SchemaBatch, bucket: Bucket, reclaimed_bytes: u64) -> Result<()> {
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["pruner_commit"])
+        .start_timer();
+    db.write_schemas(batch)?;
+    DIEM_STORAGE_PRUNER_RECLAIMED_BYTES
+        .with_label_values(&[bucket.name()])
+        .inc_by(reclaimed_bytes);
+    Ok(())
+}
+
 #[cfg(test)]
 mod test;
@@ -49,7 +49,10 @@ fn test_pruner() {
     let tmp_dir = TempPath::new();
     let db = DiemDB::new_for_test(&tmp_dir).db;
     let state_store = &StateStore::new(Arc::clone(&db));
-    let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        Some(0).into(), /* historical_versions_to_keep */
+    );
 
     let _root0 = put_account_state_set(
         &db,
@@ -135,15 +138,18 @@ fn test_worker_quit_eagerly() {
         let worker = Worker::new(
             Arc::clone(&db),
             command_receiver,
-            Arc::new(AtomicU64::new(0)), /* progress */
+            vec![Bucket::StateStore],
+            Arc::new(Default::default()), /* progress */
         );
         command_sender
             .send(Command::Prune {
+                bucket: Bucket::StateStore,
                 least_readable_version: 1,
             })
             .unwrap();
         command_sender
             .send(Command::Prune {
+                bucket: Bucket::StateStore,
                 least_readable_version: 2,
             })
             .unwrap();
@@ -432,8 +432,8 @@ impl DB {
         })
     }
 
-    /// Flushes all memtable data. This is only used for testing `get_approximate_sizes_cf` in unit
-    /// tests.
+    /// Flushes all memtable data. Used for testing `get_approximate_sizes_cf` in unit tests, and
+    /// by DiemDB on shutdown.
     pub fn flush_all(&self) -> Result<()> {
         for cf_name in &self.column_families {
             let cf_handle = self.get_cf_handle(cf_name)?;
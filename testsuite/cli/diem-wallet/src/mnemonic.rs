@@ -120,6 +120,22 @@ impl Mnemonic {
         Ok(Mnemonic(mnemonic))
     }
 
+    //////// 0L ////////
+    /// The entropy the mnemonic encodes, without its checksum.
+    pub fn entropy(&self) -> Vec<u8> {
+        let mut bit_writer = U11BitWriter::new(self.0.len());
+        for word in &self.0 {
+            // Words come from the list, so the search always succeeds.
+            let idx = WORDS.binary_search(word).unwrap_or_else(|_| unreachable!());
+            bit_writer.write_u11(idx as u16);
+        }
+        bit_writer.write_buffer();
+        let mut bytes = bit_writer.bytes;
+        // The checksum is in the last byte.
+        bytes.pop();
+        bytes
+    }
+
     /// Write mnemonic to output_file_path.
     pub fn write(&self, output_file_path: &Path) -> Result<()> {
         if output_file_path.exists() && !output_file_path.is_file() {
@@ -525,6 +541,15 @@ fn test_bips39_vectors() {
     }
 }
 
+//////// 0L ////////
+#[test]
+fn test_entropy_of_mnemonic() {
+    for t in test_vectors_bip39().iter() {
+        let mnemonic = Mnemonic::from(t.mnemonic).unwrap();
+        assert_eq!(hex::encode(mnemonic.entropy()), t.seed);
+    }
+}
+
 #[test]
 fn test_failed_checksum() {
     // CORRECT MNEMONIC: "abandon abandon abandon abandon abandon abandon abandon abandon abandon
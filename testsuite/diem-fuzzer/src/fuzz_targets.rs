@@ -7,6 +7,7 @@ use once_cell::sync::Lazy;
 use std::{collections::BTreeMap, env};
 
 // List fuzz target modules here.
+mod config; //////// 0L ////////
 mod consensus;
 mod executor;
 mod json_rpc_service;
@@ -24,6 +25,12 @@ mod vm;
 static ALL_TARGETS: Lazy<BTreeMap<&'static str, Box<dyn FuzzTargetImpl>>> = Lazy::new(|| {
     // List fuzz targets here in this format:
     let targets: Vec<Box<dyn FuzzTargetImpl>> = vec![
+        //////// 0L ////////
+        // Config
+        Box::new(config::ConfigNetworkIdDeserialize::default()),
+        Box::new(config::ConfigPeerNetworkIdDeserialize::default()),
+        Box::new(config::ConfigUpstreamConfigDeserialize::default()),
+        Box::new(config::ConfigNodeConfigDeserialize::default()),
         // Consensus
         Box::new(consensus::ConsensusProposal::default()),
         // Executor
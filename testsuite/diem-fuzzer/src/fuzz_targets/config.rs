@@ -0,0 +1,123 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//////// 0L ////////
+//! Configs are read from operator-supplied files and network identifiers are received from peers,
+//! so deserialization must fail with an error rather than panic on arbitrary input.
+//!
+//! The first byte of the input selects the format (even: yaml, odd: BCS), the remaining bytes are
+//! the serialized value.
+
+use crate::FuzzTargetImpl;
+use diem_config::{
+    config::{NodeConfig, PeerNetworkId, UpstreamConfig},
+    network_id::NetworkId,
+    proptest_types::{arb_network_id, arb_node_config, arb_peer_network_id, arb_upstream_config},
+};
+use diem_proptest_helpers::ValueGenerator;
+use proptest::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+const YAML: u8 = 0;
+const BCS: u8 = 1;
+
+/// Serializes a generated value in a randomly chosen format
+fn generate_value<S>(gen: &mut ValueGenerator, strategy: S, bcs_supported: bool) -> Vec<u8>
+where
+    S: Strategy,
+    S::Value: Serialize,
+{
+    let value = gen.generate(strategy);
+    let format = if bcs_supported {
+        gen.generate(prop_oneof![Just(YAML), Just(BCS)])
+    } else {
+        YAML
+    };
+    let mut data = vec![format];
+    if format == BCS {
+        data.extend(bcs::to_bytes(&value).unwrap());
+    } else {
+        data.extend(serde_yaml::to_vec(&value).unwrap());
+    }
+    data
+}
+
+fn deserialize<T: DeserializeOwned>(data: &[u8]) {
+    if let Some((format, data)) = data.split_first() {
+        if format % 2 == BCS {
+            let _ = bcs::from_bytes::<T>(data);
+        } else {
+            let _ = serde_yaml::from_slice::<T>(data);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConfigNetworkIdDeserialize;
+
+impl FuzzTargetImpl for ConfigNetworkIdDeserialize {
+    fn description(&self) -> &'static str {
+        "Config: NetworkId deserialization from yaml or BCS"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_value(gen, arb_network_id(), true))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        deserialize::<NetworkId>(data);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConfigPeerNetworkIdDeserialize;
+
+impl FuzzTargetImpl for ConfigPeerNetworkIdDeserialize {
+    fn description(&self) -> &'static str {
+        "Config: PeerNetworkId deserialization from yaml or BCS"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_value(gen, arb_peer_network_id(), true))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        deserialize::<PeerNetworkId>(data);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ConfigUpstreamConfigDeserialize;
+
+impl FuzzTargetImpl for ConfigUpstreamConfigDeserialize {
+    fn description(&self) -> &'static str {
+        "Config: UpstreamConfig deserialization from yaml or BCS"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_value(gen, arb_upstream_config(), true))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        deserialize::<UpstreamConfig>(data);
+    }
+}
+
+/// `NodeConfig` skips empty fields during serialization, which BCS does not support, so the corpus
+/// is yaml only. BCS input is still fuzzed, as it must not panic either.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigNodeConfigDeserialize;
+
+impl FuzzTargetImpl for ConfigNodeConfigDeserialize {
+    fn description(&self) -> &'static str {
+        "Config: NodeConfig deserialization from yaml or BCS"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(generate_value(gen, arb_node_config(), false))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        deserialize::<NodeConfig>(data);
+    }
+}
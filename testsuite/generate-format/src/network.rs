@@ -37,6 +37,12 @@ pub fn get_registry() -> Result<Registry> {
         &mut samples,
         &address::DnsName::from_str("example.com").unwrap(),
     )?;
+    //////// 0L ////////
+    tracer.trace_value(
+        &mut samples,
+        &address::OnionAddr::from_str("vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd")
+            .unwrap(),
+    )?;
     tracer.trace_value(&mut samples, &address::NetworkAddress::mock())?;
 
     // 2. Trace the main entry point(s) + every enum separately.
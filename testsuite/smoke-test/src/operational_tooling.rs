@@ -23,7 +23,8 @@ use diem_crypto::{
 };
 use diem_global_constants::{
     CONSENSUS_KEY, FULLNODE_NETWORK_KEY, GENESIS_WAYPOINT, OPERATOR_ACCOUNT, OPERATOR_KEY,
-    OWNER_ACCOUNT, OWNER_KEY, VALIDATOR_NETWORK_ADDRESS_KEYS, VALIDATOR_NETWORK_KEY, WAYPOINT,
+    OWNER_ACCOUNT, OWNER_KEY, SAFETY_DATA, VALIDATOR_NETWORK_ADDRESS_KEYS, VALIDATOR_NETWORK_KEY,
+    WAYPOINT,
 };
 use diem_key_manager::diem_interface::DiemInterface;
 use diem_management::storage::to_x25519;
@@ -232,6 +233,43 @@ fn test_move_explain_operational_tooling() {
     }
 }
 
+#[test]
+fn test_export_import_safety_rules() {
+    let (env, op_tool, backend, _) = launch_swarm_with_op_tool_and_backend(1, 0);
+
+    // Export the safety rules state of the validator
+    let (_, node_config_path) = load_node_config(&env.validator_swarm, 0);
+    let state_file = node_config_path.with_file_name("SAFETY_RULES_STATE");
+    let exported = op_tool.export_safety_rules(&state_file, &backend).unwrap();
+
+    // Import it into an empty backend, as on a new host
+    let target_path = TempPath::new();
+    let mut target_backend = backend.clone();
+    if let SecureBackend::OnDiskStorage(config) = &mut target_backend {
+        config.path = target_path.path().to_path_buf();
+    }
+    let imported = op_tool
+        .import_safety_rules(&state_file, &target_backend)
+        .unwrap();
+    assert_eq!(exported, imported);
+    assert_eq!(
+        exported,
+        op_tool
+            .export_safety_rules(&state_file, &target_backend)
+            .unwrap()
+    );
+
+    // The target backend has voted since, so the exported state is stale
+    let mut target_storage: Storage = (&target_backend).try_into().unwrap();
+    let mut voted = exported.safety_data;
+    voted.last_voted_round += 1;
+    target_storage.set(SAFETY_DATA, voted).unwrap();
+    let error = op_tool
+        .import_safety_rules(&state_file, &target_backend)
+        .unwrap_err();
+    assert_eq!(error.code(), "stale_safety_rules");
+}
+
 #[test]
 fn test_extract_private_key() {
     let (env, op_tool, backend, storage) = launch_swarm_with_op_tool_and_backend(1, 0);
@@ -8,6 +8,9 @@ use std::{convert::TryFrom, fmt, str::FromStr};
 /// Its main purpose is to improve human readability of reserved chain IDs in config files and CLI
 /// When signing transactions for such chains, the numerical chain ID should still be used
 /// (e.g. MAINNET has numeric chain ID 1, TESTNET has chain ID 2, etc)
+//////// 0L ////////
+/// Names are matched regardless of case, `rc` names the release candidate chain PREMAINNET. Any
+/// other chain is custom, and is given by its numeric ID where a `ChainId` is taken.
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, Serialize)] ///////// 0L ////////
 pub enum NamedChain {
@@ -26,19 +29,14 @@ pub enum NamedChain {
 
 impl NamedChain {
     pub fn str_to_named(s: &str) -> Result<Self> { //////// 0L ////////
-      let n = match s {
+      let n = match s.to_ascii_uppercase().as_str() {
           "MAINNET" => NamedChain::MAINNET,
           "TESTNET" => NamedChain::TESTNET,
           "DEVNET" => NamedChain::DEVNET,
           "TESTING" => NamedChain::TESTING,
-          "PREMAINNET" => NamedChain::PREMAINNET,
+          "PREMAINNET" | "RC" => NamedChain::PREMAINNET,
           "EXPERIMENTAL" => NamedChain::EXPERIMENTAL, //////// 0L ////////
-          "Mainnet" => NamedChain::MAINNET, // Backwards compatibility.
-          "Testnet" => NamedChain::TESTNET,
-          "Devnet" => NamedChain::DEVNET,
-          "Testing" => NamedChain::TESTING,
-          "Premainet" => NamedChain::PREMAINNET,
-          "Experimental" => NamedChain::EXPERIMENTAL, //////// 0L ////////
+          "PREMAINET" => NamedChain::PREMAINNET, // Backwards compatibility.
           "1" => NamedChain::MAINNET,
           "2" => NamedChain::TESTNET,
           "3" => NamedChain::DEVNET,
@@ -84,6 +82,13 @@ impl<'de> Deserialize<'de> for NamedChain { //////// 0L ////////
     }
 }
 
+//////// 0L ////////
+impl From<NamedChain> for ChainId {
+    fn from(chain: NamedChain) -> Self {
+        ChainId::new(chain.id())
+    }
+}
+
 impl FromStr for NamedChain { //////// 0L ////////
     type Err = Error;
 
@@ -211,5 +216,20 @@ mod test {
         assert!(ChainId::from_str("255255").is_err());
         assert_eq!(ChainId::from_str("TESTING").unwrap(), ChainId::test());
         assert_eq!(ChainId::from_str("255").unwrap(), ChainId::new(255));
+        //////// 0L ////////
+        assert_eq!(ChainId::from_str("mainnet").unwrap(), NamedChain::MAINNET.into());
+        assert_eq!(ChainId::from_str("Devnet").unwrap(), NamedChain::DEVNET.into());
+        assert_eq!(ChainId::from_str("rc").unwrap(), NamedChain::PREMAINNET.into());
+        assert_eq!(ChainId::from_str("42").unwrap().to_string(), "42");
+        assert!(ChainId::from_str("mainet").is_err());
+    }
+
+    //////// 0L ////////
+    #[test]
+    fn test_named_chain_serde() {
+        let chain: NamedChain = serde_json::from_str("\"mainnet\"").unwrap();
+        assert_eq!(chain.id(), 1);
+        assert_eq!(serde_json::to_string(&chain).unwrap(), "\"MAINNET\"");
+        assert!(serde_json::from_str::<NamedChain>("\"custom\"").is_err());
     }
 }
@@ -53,6 +53,9 @@ pub enum MempoolStatusCode {
     // transaction didn't pass vm_validation
     VmError = 5,
     UnknownStatus = 6,
+    //////// 0L ////////
+    // Refused by the admission rules of the node, e.g. a gas unit price below its minimum
+    Rejected = 7,
 }
 
 impl TryFrom<u64> for MempoolStatusCode {
@@ -67,6 +70,7 @@ impl TryFrom<u64> for MempoolStatusCode {
             4 => Ok(MempoolStatusCode::InvalidUpdate),
             5 => Ok(MempoolStatusCode::VmError),
             6 => Ok(MempoolStatusCode::UnknownStatus),
+            7 => Ok(MempoolStatusCode::Rejected),
             _ => Err("invalid StatusCode"),
         }
     }
@@ -28,6 +28,9 @@ use thiserror::Error;
 pub mod encrypted;
 
 const MAX_DNS_NAME_SIZE: usize = 255;
+//////// 0L ////////
+/// Length of a v3 onion service address, without the `.onion` suffix
+const ONION3_ADDR_LEN: usize = 56;
 
 /// ## Overview
 ///
@@ -128,6 +131,9 @@ pub enum Protocol {
     // probably need to move network wire into its own crate to avoid circular
     // dependency b/w network and types.
     Handshake(u8),
+    //////// 0L ////////
+    // A Tor v3 onion service, dialed through a Tor SOCKS port and followed by its `/tcp/<port>`
+    Onion3(OnionAddr),
 }
 
 /// A minimally parsed DNS name. We don't really do any checking other than
@@ -151,6 +157,12 @@ pub enum Protocol {
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
 pub struct DnsName(String);
 
+//////// 0L ////////
+/// The address of a Tor v3 onion service: 56 lower case base32 characters, written without its
+/// `.onion` suffix, which is accepted when parsing.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+pub struct OnionAddr(String);
+
 /// Possible errors when parsing a human-readable [`NetworkAddress`].
 #[derive(Error, Debug)]
 pub enum ParseError {
@@ -184,6 +196,10 @@ pub enum ParseError {
     #[error("dns name is too long: len: {0} bytes, max len: 255 bytes")]
     DnsNameTooLong(usize),
 
+    //////// 0L ////////
+    #[error("invalid onion v3 address: '{0}'")]
+    InvalidOnionAddr(String),
+
     #[error("error decrypting network address")]
     DecryptError,
 
@@ -276,6 +292,7 @@ impl NetworkAddress {
     /// `"/dns4/<domain>/tcp/<port>"` or
     /// `"/dns6/<domain>/tcp/<port>"` or
     /// `"/dns/<domain>/tcp/<port>"` or
+    /// `"/onion3/<onion-addr>/tcp/<port>"` or
     /// cfg!(test) `"/memory/<port>"`
     ///
     /// followed by transport upgrade handshake protocols:
@@ -305,6 +322,15 @@ impl NetworkAddress {
         })
     }
 
+    //////// 0L ////////
+    /// Retrieves the port of the transport protocol from the network address
+    pub fn find_port(&self) -> Option<u16> {
+        self.0.iter().find_map(|proto| match proto {
+            Protocol::Tcp(port) => Some(*port),
+            _ => None,
+        })
+    }
+
     /// A temporary, hacky function to parse out the first `/ln-noise-ik/<pubkey>` from
     /// a `NetworkAddress`. We can remove this soon, when we move to the interim
     /// "monolithic" transport model.
@@ -495,6 +521,8 @@ pub fn arb_diemnet_addr() -> impl Strategy<Value = NetworkAddress> {
             .prop_map(|(name, port)| vec![Protocol::Dns4(name), Protocol::Tcp(port)]),
         any::<(DnsName, u16)>()
             .prop_map(|(name, port)| vec![Protocol::Dns6(name), Protocol::Tcp(port)]),
+        any::<(OnionAddr, u16)>()
+            .prop_map(|(addr, port)| vec![Protocol::Onion3(addr), Protocol::Tcp(port)]),
     ];
     let arb_diemnet_protos = any::<(x25519::PublicKey, u8)>()
         .prop_map(|(pubkey, hs)| vec![Protocol::NoiseIK(pubkey), Protocol::Handshake(hs)]);
@@ -530,6 +558,7 @@ impl fmt::Display for Protocol {
                     .expect("ValidCryptoMaterialStringExt::to_encoded_string is infallible")
             ),
             Handshake(version) => write!(f, "/ln-handshake/{}", version),
+            Onion3(addr) => write!(f, "/onion3/{}", addr), //////// 0L ////////
         }
     }
 }
@@ -560,6 +589,7 @@ impl Protocol {
                 args.next().ok_or(ParseError::UnexpectedEnd)?,
             )?),
             "ln-handshake" => Protocol::Handshake(parse_one(args)?),
+            "onion3" => Protocol::Onion3(parse_one(args)?), //////// 0L ////////
             unknown => return Err(ParseError::UnknownProtocolType(unknown.to_string())),
         };
         Ok(protocol)
@@ -666,6 +696,69 @@ impl Arbitrary for DnsName {
     }
 }
 
+///////////////
+// OnionAddr //
+///////////////
+
+//////// 0L ////////
+impl OnionAddr {
+    fn validate(s: &str) -> Result<(), ParseError> {
+        let valid = s.len() == ONION3_ADDR_LEN
+            && s.bytes()
+                .all(|c| c.is_ascii_lowercase() || (b'2'..=b'7').contains(&c));
+        if valid {
+            Ok(())
+        } else {
+            Err(ParseError::InvalidOnionAddr(s.to_string()))
+        }
+    }
+
+    /// The host name of the onion service, e.g. for a SOCKS proxy to resolve
+    pub fn host(&self) -> String {
+        format!("{}.onion", self.0)
+    }
+}
+
+impl FromStr for OnionAddr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_suffix(".onion").unwrap_or(s);
+        OnionAddr::validate(s).map(|_| OnionAddr(s.to_owned()))
+    }
+}
+
+impl fmt::Display for OnionAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'de> Deserialize<'de> for OnionAddr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "OnionAddr")]
+        struct DeserializeWrapper(String);
+
+        let wrapper = DeserializeWrapper::deserialize(deserializer)?;
+        OnionAddr::validate(&wrapper.0).map_err(de::Error::custom)?;
+        Ok(OnionAddr(wrapper.0))
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+impl Arbitrary for OnionAddr {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        "[a-z2-7]{56}".prop_map(OnionAddr).boxed()
+    }
+}
+
 /////////////
 // Parsing //
 /////////////
@@ -748,6 +841,24 @@ pub fn parse_tcp(protos: &[Protocol]) -> Option<((String, u16), &[Protocol])> {
         [Dns(name), Tcp(port)] => Some(((name.to_string(), *port), suffix)),
         [Dns4(name), Tcp(port)] => Some(((name.to_string(), *port), suffix)),
         [Dns6(name), Tcp(port)] => Some(((name.to_string(), *port), suffix)),
+        [Onion3(addr), Tcp(port)] => Some(((addr.host(), *port), suffix)), //////// 0L ////////
+        _ => None,
+    }
+}
+
+//////// 0L ////////
+/// parse the `&[Protocol]` into the `"/onion3/<onion-addr>/tcp/<port>"` prefix and
+/// unparsed `&[Protocol]` suffix.
+pub fn parse_onion_tcp(protos: &[Protocol]) -> Option<((&OnionAddr, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 2 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(2);
+    match prefix {
+        [Onion3(addr), Tcp(port)] => Some(((addr, *port), suffix)),
         _ => None,
     }
 }
@@ -778,11 +889,13 @@ fn parse_diemnet_protos(protos: &[Protocol]) -> Option<&[Protocol]> {
     // ---
     // parse_ip_tcp
     // <or> parse_dns_tcp
+    // <or> parse_onion_tcp
     // <or> cfg!(test) parse_memory
 
     let transport_suffix = parse_ip_tcp(protos)
         .map(|x| x.1)
         .or_else(|| parse_dns_tcp(protos).map(|x| x.1))
+        .or_else(|| parse_onion_tcp(protos).map(|x| x.1))
         .or_else(|| {
             if cfg!(test) {
                 parse_memory(protos).map(|x| x.1)
@@ -995,6 +1108,29 @@ mod test {
         assert_eq!(None, parse_dns_tcp(addr.as_slice()));
     }
 
+    //////// 0L ////////
+    #[test]
+    fn test_parse_onion_tcp() {
+        let host = "vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd";
+        let onion_addr = OnionAddr::from_str(host).unwrap();
+        let addr = NetworkAddress::from_str(&format!("/onion3/{}.onion/tcp/6179", host)).unwrap();
+        assert_eq!(addr.to_string(), format!("/onion3/{}/tcp/6179", host));
+        let expected_suffix: &[Protocol] = &[];
+        assert_eq!(
+            parse_onion_tcp(addr.as_slice()).unwrap(),
+            ((&onion_addr, 6179), expected_suffix)
+        );
+        assert_eq!(
+            parse_tcp(addr.as_slice()).unwrap(),
+            ((format!("{}.onion", host), 6179), expected_suffix)
+        );
+        assert_eq!(None, parse_dns_tcp(addr.as_slice()));
+
+        // Not base32, or a v2 address
+        assert!(OnionAddr::from_str(&host.replace('v', "1")).is_err());
+        assert!(OnionAddr::from_str("expyuzz4wqqyqhjn.onion").is_err());
+    }
+
     #[test]
     fn test_parse_noise_ik() {
         let pubkey_str = "080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120";